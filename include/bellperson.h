@@ -0,0 +1,68 @@
+/*
+ * C bindings of bellperson, available with the `ffi` feature. Groth16 proofs over BLS12-381 of
+ * circom circuits.
+ *
+ * Proofs are 192 bytes, public inputs 32 byte little endian scalars. Functions returning a
+ * status return BELLPERSON_OK or a negative error code, verification functions return 1 for
+ * valid and 0 for invalid proofs. Handles must be freed with the matching `_free` function.
+ */
+
+#ifndef BELLPERSON_H
+#define BELLPERSON_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define BELLPERSON_PROOF_LEN 192
+#define BELLPERSON_INPUT_LEN 32
+
+#define BELLPERSON_OK 0
+#define BELLPERSON_ERR_INVALID_DATA -1
+#define BELLPERSON_ERR_SYNTHESIS -2
+#define BELLPERSON_ERR_PANIC -3
+
+typedef struct BellpersonParams BellpersonParams;
+typedef struct BellpersonVerifyingKey BellpersonVerifyingKey;
+typedef struct BellpersonR1cs BellpersonR1cs;
+
+/* Returns NULL if the parameters can't be read. */
+BellpersonParams *bellperson_params_read(const uint8_t *bytes, size_t len);
+void bellperson_params_free(BellpersonParams *params);
+BellpersonVerifyingKey *bellperson_params_verifying_key(const BellpersonParams *params);
+
+/* Returns NULL if the verifying key can't be read. */
+BellpersonVerifyingKey *bellperson_verifying_key_read(const uint8_t *bytes, size_t len);
+void bellperson_verifying_key_free(BellpersonVerifyingKey *vk);
+
+/* Reads a circuit in the .r1cs format, returns NULL if it can't be read. */
+BellpersonR1cs *bellperson_r1cs_read(const uint8_t *bytes, size_t len);
+void bellperson_r1cs_free(BellpersonR1cs *r1cs);
+
+/* Proves a circuit with a witness in the .wtns format, writes BELLPERSON_PROOF_LEN bytes. */
+int32_t bellperson_create_random_proof(const BellpersonParams *params,
+                                       const BellpersonR1cs *r1cs,
+                                       const uint8_t *wtns,
+                                       size_t wtns_len,
+                                       uint8_t *proof_out);
+
+int32_t bellperson_verify_proof(const BellpersonVerifyingKey *vk,
+                                const uint8_t *proof,
+                                const uint8_t *inputs,
+                                size_t num_inputs);
+
+/* The proofs and their inputs are concatenated, every proof has `num_inputs` inputs. */
+int32_t bellperson_verify_proofs_batch(const BellpersonVerifyingKey *vk,
+                                       const uint8_t *proofs,
+                                       size_t num_proofs,
+                                       const uint8_t *inputs,
+                                       size_t num_inputs);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BELLPERSON_H */
@@ -0,0 +1,3 @@
+//! Circuits that are defined outside of Rust, e.g. by other circuit compilers.
+
+pub mod r1cs;
@@ -0,0 +1,982 @@
+//! Circuits compiled by [circom], read from its binary `.r1cs` format, with witnesses read from
+//! the `.wtns` format of its witness generators.
+//!
+//! The wires of a circom circuit are the constant one, followed by the public outputs, the
+//! public inputs and all private wires. The public outputs and inputs become the inputs of the
+//! bellperson circuit in that order, all other wires auxiliary variables.
+//!
+//! [circom]: https://docs.circom.io/
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::sync::Arc;
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use ff::{Field, PrimeField};
+
+#[cfg(feature = "groth16")]
+use crate::groth16::{ProvingAssignment, QueryDensities};
+use crate::util_cs::r1cs_writer_cs::R1csWriterCS;
+#[cfg(feature = "groth16")]
+use crate::util_cs::witness_cs::WitnessCS;
+use crate::{Circuit, ConstraintSystem, LinearCombination, SynthesisError, Variable};
+
+const R1CS_MAGIC: [u8; 4] = *b"r1cs";
+const WTNS_MAGIC: [u8; 4] = *b"wtns";
+
+const R1CS_HEADER: u32 = 1;
+const R1CS_CONSTRAINTS: u32 = 2;
+const R1CS_WIRE_TO_LABEL: u32 = 3;
+const R1CS_CUSTOM_GATES_LIST: u32 = 4;
+const R1CS_CUSTOM_GATES_USES: u32 = 5;
+const WTNS_HEADER: u32 = 1;
+const WTNS_VALUES: u32 = 2;
+
+/// The largest size of field elements in bytes that is supported.
+const MAX_N8: usize = 64;
+
+/// The terms of a linear combination, pairs of a wire and its coefficient.
+pub type Terms<Scalar> = Vec<(usize, Scalar)>;
+
+/// A constraint `a * b = c`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Constraint<Scalar> {
+    pub a: Terms<Scalar>,
+    pub b: Terms<Scalar>,
+    pub c: Terms<Scalar>,
+}
+
+/// The constraint system of a circom circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct R1cs<Scalar> {
+    /// The number of wires, including the constant one.
+    pub num_wires: usize,
+    /// The number of public wires, the public outputs and the public inputs.
+    pub num_public: usize,
+    pub constraints: Vec<Constraint<Scalar>>,
+}
+
+/// The wires of a circuit that [`R1cs::optimize`] kept, it maps witnesses of the circuit to
+/// witnesses of the optimized circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WireMap {
+    num_wires: usize,
+    /// The wire of the original circuit for every wire of the optimized one, in ascending order.
+    kept: Vec<usize>,
+}
+
+impl WireMap {
+    pub fn kept_wires(&self) -> &[usize] {
+        &self.kept
+    }
+
+    /// Maps a witness of the original circuit to a witness of the optimized circuit.
+    pub fn map_witness<Scalar: Copy>(
+        &self,
+        witness: &[Scalar],
+    ) -> Result<Vec<Scalar>, SynthesisError> {
+        if witness.len() != self.num_wires {
+            return Err(SynthesisError::IncompatibleLengthVector(format!(
+                "witness of {} values, circuit with {} wires",
+                witness.len(),
+                self.num_wires
+            )));
+        }
+        Ok(self.kept.iter().map(|&wire| witness[wire]).collect())
+    }
+}
+
+impl<Scalar: PrimeField> R1cs<Scalar> {
+    /// Reads a circuit in the `.r1cs` format. Its prime must be the modulus of `Scalar`.
+    ///
+    /// Terms with a zero coefficient are dropped, so that they don't count towards the densities
+    /// of the A and B queries of the prover.
+    pub fn read<R: Read + Seek>(reader: R) -> io::Result<Self> {
+        let mut reader = BufReader::new(reader);
+        let sections = read_sections(&mut reader, R1CS_MAGIC, &[1])?;
+        if sections.iter().any(|section| {
+            section.kind == R1CS_CUSTOM_GATES_LIST || section.kind == R1CS_CUSTOM_GATES_USES
+        }) {
+            return Err(invalid_data("custom gates are not supported"));
+        }
+
+        seek_section(&mut reader, &sections, R1CS_HEADER)?;
+        let n8 = read_prime::<Scalar, _>(&mut reader)?;
+        let num_wires = reader.read_u32::<LittleEndian>()? as usize;
+        let num_pub_out = reader.read_u32::<LittleEndian>()? as usize;
+        let num_pub_in = reader.read_u32::<LittleEndian>()? as usize;
+        let _num_prv_in = reader.read_u32::<LittleEndian>()?;
+        let _num_labels = reader.read_u64::<LittleEndian>()?;
+        let num_constraints = reader.read_u32::<LittleEndian>()? as usize;
+        let num_public = num_pub_out + num_pub_in;
+        if num_public >= num_wires {
+            return Err(invalid_data("more public wires than wires"));
+        }
+
+        // Every term takes the bytes of its wire and its coefficient, the number of terms is only
+        // trusted as far as the section can hold them.
+        let size = seek_section(&mut reader, &sections, R1CS_CONSTRAINTS)?;
+        let max_terms = usize::try_from(size / (4 + n8 as u64)).unwrap_or(usize::MAX);
+        let read_terms = |reader: &mut BufReader<R>| -> io::Result<Terms<Scalar>> {
+            let num_terms = reader.read_u32::<LittleEndian>()? as usize;
+            let mut terms = Vec::with_capacity(num_terms.min(max_terms));
+            for _ in 0..num_terms {
+                let wire = reader.read_u32::<LittleEndian>()? as usize;
+                if wire >= num_wires {
+                    return Err(invalid_data(format!("wire {} out of range", wire)));
+                }
+                let coeff = read_scalar::<Scalar, _>(&mut *reader, n8)?;
+                if !bool::from(coeff.is_zero()) {
+                    terms.push((wire, coeff));
+                }
+            }
+            Ok(terms)
+        };
+        let constraints = (0..num_constraints)
+            .map(|_| {
+                Ok(Constraint {
+                    a: read_terms(&mut reader)?,
+                    b: read_terms(&mut reader)?,
+                    c: read_terms(&mut reader)?,
+                })
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(R1cs {
+            num_wires,
+            num_public,
+            constraints,
+        })
+    }
+
+    /// Captures the constraints of a bellperson circuit, without computing its witness. The
+    /// inputs become the public wires, see [`R1csWriterCS`].
+    pub fn from_circuit<C: Circuit<Scalar>>(circuit: C) -> Result<Self, SynthesisError> {
+        let mut cs = R1csWriterCS::new();
+        circuit.synthesize(&mut cs)?;
+        Ok(cs.r1cs())
+    }
+
+    /// Optimizes the circuit after synthesis, before generating parameters for it.
+    ///
+    /// - The terms of every linear combination are normalized: the coefficients of a wire are
+    ///   summed up, zero coefficients dropped and the terms sorted by wire.
+    /// - Constraints whose linear combinations only consist of the constant one are folded,
+    ///   they are dropped if they hold. Those that don't are kept, so that proving fails.
+    /// - Duplicate constraints are dropped, `b * a = c` is a duplicate of `a * b = c`.
+    /// - Private wires that no constraint references are dropped, the remaining wires keep
+    ///   their order. Public wires are always kept.
+    ///
+    /// Witnesses of the circuit have to be mapped to the optimized circuit with the returned
+    /// [`WireMap`], before proving with parameters of the optimized circuit.
+    pub fn optimize(&self) -> (Self, WireMap) {
+        let mut seen = HashSet::new();
+        let mut constraints = Vec::with_capacity(self.constraints.len());
+        for constraint in &self.constraints {
+            let a = normalize(&constraint.a);
+            let b = normalize(&constraint.b);
+            let c = normalize(&constraint.c);
+            if let (Some(a), Some(b), Some(c)) = (constant(&a), constant(&b), constant(&c)) {
+                if a * b == c {
+                    continue;
+                }
+            }
+
+            let (a_key, b_key) = (terms_key(&a), terms_key(&b));
+            let mut key = if a_key <= b_key {
+                [a_key, b_key].concat()
+            } else {
+                [b_key, a_key].concat()
+            };
+            key.extend(terms_key(&c));
+            if seen.insert(key) {
+                constraints.push(Constraint { a, b, c });
+            }
+        }
+
+        let mut used = vec![false; self.num_wires];
+        used[..=self.num_public]
+            .iter_mut()
+            .for_each(|used| *used = true);
+        for constraint in &constraints {
+            for (wire, _) in constraint
+                .a
+                .iter()
+                .chain(&constraint.b)
+                .chain(&constraint.c)
+            {
+                used[*wire] = true;
+            }
+        }
+        let kept = (0..self.num_wires)
+            .filter(|&wire| used[wire])
+            .collect::<Vec<_>>();
+        let mut renumbered = vec![0; self.num_wires];
+        for (new, &old) in kept.iter().enumerate() {
+            renumbered[old] = new;
+        }
+        for constraint in &mut constraints {
+            for (wire, _) in constraint
+                .a
+                .iter_mut()
+                .chain(&mut constraint.b)
+                .chain(&mut constraint.c)
+            {
+                *wire = renumbered[*wire];
+            }
+        }
+
+        let r1cs = R1cs {
+            num_wires: kept.len(),
+            num_public: self.num_public,
+            constraints,
+        };
+        let map = WireMap {
+            num_wires: self.num_wires,
+            kept,
+        };
+        (r1cs, map)
+    }
+
+    /// Writes the circuit in the `.r1cs` format, with the public wires as public inputs. Every
+    /// wire is its own label.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let n8 = (Scalar::NUM_BITS as usize + 63) / 64 * 8;
+
+        // The modulus is one more than the largest field element.
+        let mut prime = to_le_bytes(&-Scalar::ONE, n8);
+        for byte in prime.iter_mut() {
+            let (sum, carry) = byte.overflowing_add(1);
+            *byte = sum;
+            if !carry {
+                break;
+            }
+        }
+
+        let mut header = Vec::new();
+        header.write_u32::<LittleEndian>(n8 as u32)?;
+        header.extend_from_slice(&prime);
+        header.write_u32::<LittleEndian>(self.num_wires as u32)?;
+        header.write_u32::<LittleEndian>(0)?;
+        header.write_u32::<LittleEndian>(self.num_public as u32)?;
+        header.write_u32::<LittleEndian>(0)?;
+        header.write_u64::<LittleEndian>(self.num_wires as u64)?;
+        header.write_u32::<LittleEndian>(self.constraints.len() as u32)?;
+
+        let mut constraints = Vec::new();
+        for constraint in &self.constraints {
+            for terms in [&constraint.a, &constraint.b, &constraint.c] {
+                constraints.write_u32::<LittleEndian>(terms.len() as u32)?;
+                for (wire, coeff) in terms {
+                    constraints.write_u32::<LittleEndian>(*wire as u32)?;
+                    constraints.extend_from_slice(&to_le_bytes(coeff, n8));
+                }
+            }
+        }
+
+        let mut labels = Vec::with_capacity(self.num_wires * 8);
+        for wire in 0..self.num_wires {
+            labels.write_u64::<LittleEndian>(wire as u64)?;
+        }
+
+        writer.write_all(&R1CS_MAGIC)?;
+        writer.write_u32::<LittleEndian>(1)?;
+        writer.write_u32::<LittleEndian>(3)?;
+        for (kind, content) in [
+            (R1CS_HEADER, header),
+            (R1CS_CONSTRAINTS, constraints),
+            (R1CS_WIRE_TO_LABEL, labels),
+        ] {
+            writer.write_u32::<LittleEndian>(kind)?;
+            writer.write_u64::<LittleEndian>(content.len() as u64)?;
+            writer.write_all(&content)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "groth16")]
+impl<Scalar: PrimeField> R1cs<Scalar> {
+    /// Assembles the assignment of the prover from the values of all wires, e.g. read with
+    /// [`read_witness`], without synthesizing the circuit. The public wires become the input
+    /// assignment, all other wires except the constant one the auxiliary assignment.
+    ///
+    /// The assignment can be proven with
+    /// [`create_proof_from_assignments`](crate::groth16::create_proof_from_assignments), it
+    /// tracks the query densities, so the parameters don't need to contain them.
+    pub fn proving_assignment(
+        &self,
+        witness: &[Scalar],
+    ) -> Result<ProvingAssignment<Scalar>, SynthesisError> {
+        if witness.len() != self.num_wires {
+            return Err(SynthesisError::IncompatibleLengthVector(format!(
+                "witness of {} values, circuit with {} wires",
+                witness.len(),
+                self.num_wires
+            )));
+        }
+
+        let eval = |terms: &Terms<Scalar>| -> Scalar {
+            terms
+                .iter()
+                .map(|(wire, coeff)| witness[*wire] * coeff)
+                .sum()
+        };
+        let a = self.constraints.iter().map(|c| eval(&c.a)).collect();
+        let b = self.constraints.iter().map(|c| eval(&c.b)).collect();
+        let c = self.constraints.iter().map(|c| eval(&c.c)).collect();
+
+        let num_inputs = self.num_public + 1;
+        ProvingAssignment::from_assignments(
+            a,
+            b,
+            c,
+            &witness[1..num_inputs],
+            &witness[num_inputs..],
+            Some(&self.densities()),
+        )
+    }
+
+    /// Synthesizes only the witness of an instance of a circuit, whose constraints were captured
+    /// with [`R1cs::from_circuit`], and assembles the assignment of the prover from it like
+    /// [`R1cs::proving_assignment`] does.
+    ///
+    /// The circuit is synthesized with a [`WitnessCS`], which ignores the constraints, so no
+    /// linear combinations are built, and the densities come from the constraints. The assignment can be proven
+    /// with [`create_proof_batch_from_witnesses`](crate::groth16::create_proof_batch_from_witnesses)
+    /// in one go.
+    pub fn synthesize_witness<C: Circuit<Scalar>>(
+        &self,
+        circuit: C,
+    ) -> Result<ProvingAssignment<Scalar>, SynthesisError> {
+        let mut cs = WitnessCS::new();
+        circuit.synthesize(&mut cs)?;
+        if cs.input_assignment.len() != self.num_public + 1 {
+            return Err(SynthesisError::IncompatibleLengthVector(format!(
+                "{} inputs, circuit with {} public wires",
+                cs.input_assignment.len() - 1,
+                self.num_public
+            )));
+        }
+
+        let mut witness = cs.input_assignment;
+        witness.extend(cs.aux_assignment);
+        self.proving_assignment(&witness)
+    }
+
+    /// The densities of the A and B queries of the prover for this circuit.
+    pub fn densities(&self) -> QueryDensities {
+        let mut a = vec![false; self.num_wires];
+        let mut b = vec![false; self.num_wires];
+        for constraint in &self.constraints {
+            mark_dense(&mut a, &constraint.a);
+            mark_dense(&mut b, &constraint.b);
+        }
+
+        let num_inputs = self.num_public + 1;
+        QueryDensities::new(
+            num_inputs,
+            self.num_wires - num_inputs,
+            a[num_inputs..].iter().copied(),
+            b[..num_inputs].iter().copied(),
+            b[num_inputs..].iter().copied(),
+        )
+    }
+}
+
+/// Marks the wires of a linear combination as dense. The coefficients of repeated wires are
+/// summed up first, like in a [`LinearCombination`], a wire whose coefficients cancel out isn't
+/// dense.
+#[cfg(feature = "groth16")]
+fn mark_dense<Scalar: PrimeField>(dense: &mut [bool], terms: &Terms<Scalar>) {
+    let mut terms = terms.clone();
+    terms.sort_by_key(|(wire, _)| *wire);
+    let mut i = 0;
+    while i < terms.len() {
+        let wire = terms[i].0;
+        let mut coeff = Scalar::ZERO;
+        while i < terms.len() && terms[i].0 == wire {
+            coeff += terms[i].1;
+            i += 1;
+        }
+        if !bool::from(coeff.is_zero()) {
+            dense[wire] = true;
+        }
+    }
+}
+
+/// Reads the values of all wires of a circuit in the `.wtns` format. Its prime must be the
+/// modulus of `Scalar`.
+pub fn read_witness<Scalar, R>(reader: R) -> io::Result<Vec<Scalar>>
+where
+    Scalar: PrimeField,
+    R: Read + Seek,
+{
+    let mut reader = BufReader::new(reader);
+    let sections = read_sections(&mut reader, WTNS_MAGIC, &[1, 2])?;
+
+    seek_section(&mut reader, &sections, WTNS_HEADER)?;
+    let n8 = read_prime::<Scalar, _>(&mut reader)?;
+    let num_values = reader.read_u32::<LittleEndian>()? as usize;
+
+    seek_section(&mut reader, &sections, WTNS_VALUES)?;
+    (0..num_values)
+        .map(|_| read_scalar(&mut reader, n8))
+        .collect()
+}
+
+/// A circom circuit, with the values of its wires if it's used for proving.
+#[derive(Clone, Debug)]
+pub struct R1csCircuit<Scalar> {
+    r1cs: Arc<R1cs<Scalar>>,
+    witness: Option<Vec<Scalar>>,
+}
+
+impl<Scalar: PrimeField> R1csCircuit<Scalar> {
+    /// Creates the circuit. The witness contains the values of all wires, starting with the
+    /// constant one, it is only needed for proving.
+    pub fn new(r1cs: Arc<R1cs<Scalar>>, witness: Option<Vec<Scalar>>) -> Self {
+        R1csCircuit { r1cs, witness }
+    }
+
+    /// The values of the public wires, the public inputs of a proof of this circuit.
+    pub fn public_inputs(&self) -> Option<&[Scalar]> {
+        self.witness
+            .as_ref()
+            .filter(|witness| witness.len() == self.r1cs.num_wires)
+            .map(|witness| &witness[1..=self.r1cs.num_public])
+    }
+}
+
+impl<Scalar: PrimeField> Circuit<Scalar> for R1csCircuit<Scalar> {
+    fn synthesize<CS: ConstraintSystem<Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let r1cs = &self.r1cs;
+        if let Some(witness) = &self.witness {
+            if witness.len() != r1cs.num_wires {
+                return Err(SynthesisError::IncompatibleLengthVector(format!(
+                    "witness of {} values, circuit with {} wires",
+                    witness.len(),
+                    r1cs.num_wires
+                )));
+            }
+        }
+        let value = |wire: usize| {
+            self.witness
+                .as_ref()
+                .map(|witness| witness[wire])
+                .ok_or(SynthesisError::AssignmentMissing)
+        };
+
+        let mut variables: Vec<Variable> = Vec::with_capacity(r1cs.num_wires);
+        variables.push(CS::one());
+        for wire in 1..r1cs.num_wires {
+            let variable = if wire <= r1cs.num_public {
+                cs.alloc_input(|| format!("public wire {}", wire), || value(wire))?
+            } else {
+                cs.alloc(|| format!("wire {}", wire), || value(wire))?
+            };
+            variables.push(variable);
+        }
+
+        let lc = |mut lc: LinearCombination<Scalar>, terms: &Terms<Scalar>| {
+            for (wire, coeff) in terms {
+                lc = lc + (*coeff, variables[*wire]);
+            }
+            lc
+        };
+        for (i, constraint) in r1cs.constraints.iter().enumerate() {
+            cs.enforce(
+                || format!("constraint {}", i),
+                |l| lc(l, &constraint.a),
+                |l| lc(l, &constraint.b),
+                |l| lc(l, &constraint.c),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Sums up the coefficients of every wire, drops zero coefficients and sorts the terms by wire.
+fn normalize<Scalar: PrimeField>(terms: &Terms<Scalar>) -> Terms<Scalar> {
+    let mut sums = BTreeMap::new();
+    for (wire, coeff) in terms {
+        *sums.entry(*wire).or_insert(Scalar::ZERO) += coeff;
+    }
+    sums.into_iter()
+        .filter(|(_, coeff)| !bool::from(coeff.is_zero()))
+        .collect()
+}
+
+/// The value of normalized terms that only consist of the constant one, if they do.
+fn constant<Scalar: PrimeField>(terms: &Terms<Scalar>) -> Option<Scalar> {
+    match terms.as_slice() {
+        [] => Some(Scalar::ZERO),
+        [(0, coeff)] => Some(*coeff),
+        _ => None,
+    }
+}
+
+/// The bytes identifying normalized terms.
+fn terms_key<Scalar: PrimeField>(terms: &Terms<Scalar>) -> Vec<u8> {
+    let mut key = (terms.len() as u64).to_le_bytes().to_vec();
+    for (wire, coeff) in terms {
+        key.extend_from_slice(&(*wire as u64).to_le_bytes());
+        key.extend_from_slice(coeff.to_repr().as_ref());
+    }
+    key
+}
+
+pub(crate) struct Section {
+    kind: u32,
+    offset: u64,
+    size: u64,
+}
+
+/// Reads the magic bytes, the version and the table of sections of a file. Every section must
+/// be within the file.
+pub(crate) fn read_sections<R: Read + Seek>(
+    reader: &mut R,
+    magic: [u8; 4],
+    versions: &[u32],
+) -> io::Result<Vec<Section>> {
+    let mut file_magic = [0; 4];
+    reader.read_exact(&mut file_magic)?;
+    if file_magic != magic {
+        return Err(invalid_data("invalid magic bytes"));
+    }
+    let version = reader.read_u32::<LittleEndian>()?;
+    if !versions.contains(&version) {
+        return Err(invalid_data(format!("unsupported version {}", version)));
+    }
+
+    let num_sections = reader.read_u32::<LittleEndian>()?;
+    let start = reader.stream_position()?;
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(start))?;
+
+    // The number of sections isn't trusted to allocate, the table ends with the file anyway.
+    let mut sections = Vec::new();
+    for _ in 0..num_sections {
+        let kind = reader.read_u32::<LittleEndian>()?;
+        let size = reader.read_u64::<LittleEndian>()?;
+        let offset = reader.stream_position()?;
+        let end = offset
+            .checked_add(size)
+            .filter(|&end| end <= file_len)
+            .ok_or_else(|| invalid_data(format!("section {} exceeds the file", kind)))?;
+        sections.push(Section { kind, offset, size });
+        reader.seek(SeekFrom::Start(end))?;
+    }
+    Ok(sections)
+}
+
+/// Seeks to the start of a section and returns its size.
+pub(crate) fn seek_section<R: Seek>(
+    reader: &mut R,
+    sections: &[Section],
+    kind: u32,
+) -> io::Result<u64> {
+    let section = sections
+        .iter()
+        .find(|section| section.kind == kind)
+        .ok_or_else(|| invalid_data(format!("section {} is missing", kind)))?;
+    reader.seek(SeekFrom::Start(section.offset))?;
+    Ok(section.size)
+}
+
+/// Reads the size of field elements in bytes and the prime of a header, which must be the
+/// modulus of `Scalar`.
+pub(crate) fn read_prime<Scalar: PrimeField, R: Read>(reader: &mut R) -> io::Result<usize> {
+    let n8 = reader.read_u32::<LittleEndian>()? as usize;
+    if n8 == 0 || n8 % 8 != 0 || n8 > MAX_N8 {
+        return Err(invalid_data(format!("unsupported field size {}", n8)));
+    }
+    let mut prime = vec![0; n8];
+    reader.read_exact(&mut prime)?;
+
+    // The only number of `NUM_BITS` bits that is zero in the field is its modulus.
+    let num_bits = match prime.iter().rposition(|&byte| byte != 0) {
+        Some(i) => i * 8 + (8 - prime[i].leading_zeros() as usize),
+        None => 0,
+    };
+    if num_bits != Scalar::NUM_BITS as usize
+        || !bool::from(from_le_bytes::<Scalar>(&prime).is_zero())
+    {
+        return Err(invalid_data("prime doesn't match the scalar field"));
+    }
+    Ok(n8)
+}
+
+fn read_scalar<Scalar: PrimeField, R: Read>(reader: &mut R, n8: usize) -> io::Result<Scalar> {
+    let mut bytes = [0; MAX_N8];
+    let bytes = bytes
+        .get_mut(..n8)
+        .ok_or_else(|| invalid_data(format!("unsupported field size {}", n8)))?;
+    reader.read_exact(bytes)?;
+    Ok(from_le_bytes(bytes))
+}
+
+/// Converts a little endian number, whose length is a multiple of 8 bytes, into a field
+/// element.
+fn from_le_bytes<Scalar: PrimeField>(bytes: &[u8]) -> Scalar {
+    let shift = Scalar::from(1u64 << 32).square();
+    bytes.chunks(8).rev().fold(Scalar::ZERO, |acc, limb| {
+        acc * shift + Scalar::from(u64::from_le_bytes(limb.try_into().unwrap()))
+    })
+}
+
+/// Converts a field element into a little endian number of `n8` bytes.
+fn to_le_bytes<Scalar: PrimeField>(scalar: &Scalar, n8: usize) -> Vec<u8> {
+    let mut bytes = scalar.to_repr().as_ref().to_vec();
+    // The endianness of the representation is up to the field, the one of one tells it.
+    if Scalar::ONE.to_repr().as_ref()[0] != 1 {
+        bytes.reverse();
+    }
+    bytes.resize(n8, 0);
+    bytes
+}
+
+fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use blstrs::Scalar as Fr;
+    use byteorder::WriteBytesExt;
+
+    use crate::gadgets::test::TestConstraintSystem;
+
+    /// The scalar field modulus of BLS12-381, little endian.
+    fn prime() -> Vec<u8> {
+        let mut prime =
+            hex!("73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001").to_vec();
+        prime.reverse();
+        prime
+    }
+
+    fn write_section(file: &mut Vec<u8>, kind: u32, content: &[u8]) {
+        file.write_u32::<LittleEndian>(kind).unwrap();
+        file.write_u64::<LittleEndian>(content.len() as u64)
+            .unwrap();
+        file.extend_from_slice(content);
+    }
+
+    fn write_terms(out: &mut Vec<u8>, terms: &[(u32, u64)]) {
+        out.write_u32::<LittleEndian>(terms.len() as u32).unwrap();
+        for &(wire, coeff) in terms {
+            out.write_u32::<LittleEndian>(wire).unwrap();
+            let mut bytes = [0; 32];
+            bytes[..8].copy_from_slice(&coeff.to_le_bytes());
+            out.extend_from_slice(&bytes);
+        }
+    }
+
+    /// `out = x * y`, `out` is a public output, `x` a public and `y` a private input. The
+    /// second constraint `2 * y = y + y` has a zero term in A.
+    fn product_r1cs(prime: &[u8]) -> Vec<u8> {
+        let mut header = Vec::new();
+        header.write_u32::<LittleEndian>(32).unwrap();
+        header.extend_from_slice(prime);
+        for n in [4, 1, 1, 1] {
+            header.write_u32::<LittleEndian>(n).unwrap();
+        }
+        header.write_u64::<LittleEndian>(4).unwrap();
+        header.write_u32::<LittleEndian>(2).unwrap();
+
+        let mut constraints = Vec::new();
+        write_terms(&mut constraints, &[(2, 1)]);
+        write_terms(&mut constraints, &[(3, 1)]);
+        write_terms(&mut constraints, &[(1, 1)]);
+        write_terms(&mut constraints, &[(0, 2), (1, 0)]);
+        write_terms(&mut constraints, &[(3, 1)]);
+        write_terms(&mut constraints, &[(3, 1), (3, 1)]);
+
+        let mut file = b"r1cs".to_vec();
+        file.write_u32::<LittleEndian>(1).unwrap();
+        file.write_u32::<LittleEndian>(3).unwrap();
+        // Sections don't need to be in order.
+        write_section(&mut file, R1CS_CONSTRAINTS, &constraints);
+        write_section(&mut file, R1CS_HEADER, &header);
+        write_section(&mut file, 3, &[0; 32]);
+        file
+    }
+
+    fn witness_wtns(values: &[u64]) -> Vec<u8> {
+        let mut header = Vec::new();
+        header.write_u32::<LittleEndian>(32).unwrap();
+        header.extend_from_slice(&prime());
+        header
+            .write_u32::<LittleEndian>(values.len() as u32)
+            .unwrap();
+
+        let mut content = Vec::new();
+        for &value in values {
+            let mut bytes = [0; 32];
+            bytes[..8].copy_from_slice(&value.to_le_bytes());
+            content.extend_from_slice(&bytes);
+        }
+
+        let mut file = b"wtns".to_vec();
+        file.write_u32::<LittleEndian>(2).unwrap();
+        file.write_u32::<LittleEndian>(2).unwrap();
+        write_section(&mut file, WTNS_HEADER, &header);
+        write_section(&mut file, WTNS_VALUES, &content);
+        file
+    }
+
+    #[test]
+    fn test_read_r1cs() {
+        let r1cs = R1cs::<Fr>::read(Cursor::new(product_r1cs(&prime()))).unwrap();
+        assert_eq!(r1cs.num_wires, 4);
+        assert_eq!(r1cs.num_public, 2);
+        assert_eq!(
+            r1cs.constraints,
+            vec![
+                Constraint {
+                    a: vec![(2, Fr::ONE)],
+                    b: vec![(3, Fr::ONE)],
+                    c: vec![(1, Fr::ONE)],
+                },
+                Constraint {
+                    a: vec![(0, Fr::from(2))],
+                    b: vec![(3, Fr::ONE)],
+                    c: vec![(3, Fr::ONE), (3, Fr::ONE)],
+                },
+            ]
+        );
+
+        let mut written = Vec::new();
+        r1cs.write(&mut written).unwrap();
+        assert_eq!(written[28..60], prime()[..]);
+        assert_eq!(R1cs::<Fr>::read(Cursor::new(written)).unwrap(), r1cs);
+
+        // Another prime.
+        let mut prime = prime();
+        prime[0] += 2;
+        assert!(R1cs::<Fr>::read(Cursor::new(product_r1cs(&prime))).is_err());
+        // A number of the wrong size.
+        let mut prime = vec![0; 32];
+        prime[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(R1cs::<Fr>::read(Cursor::new(product_r1cs(&prime))).is_err());
+    }
+
+    #[test]
+    fn test_read_r1cs_hostile() {
+        let file = |num_sections: u32, sections: &[(u32, u64, &[u8])]| {
+            let mut file = b"r1cs".to_vec();
+            file.write_u32::<LittleEndian>(1).unwrap();
+            file.write_u32::<LittleEndian>(num_sections).unwrap();
+            for (kind, size, content) in sections {
+                file.write_u32::<LittleEndian>(*kind).unwrap();
+                file.write_u64::<LittleEndian>(*size).unwrap();
+                file.extend_from_slice(content);
+            }
+            file
+        };
+
+        // None of the sizes in these files may be allocated before it's checked against the file.
+        let hostile = [
+            // A huge number of sections in a truncated file.
+            file(u32::MAX, &[(R1CS_HEADER, 4, &[0; 4][..])]),
+            // Sections beyond the end of the file.
+            file(1, &[(R1CS_HEADER, u64::MAX, &[0; 4][..])]),
+            file(1, &[(R1CS_HEADER, 1 << 40, &[0; 4][..])]),
+            // A huge field size.
+            file(1, &[(R1CS_HEADER, 4, &u32::MAX.to_le_bytes()[..])]),
+        ];
+        for file in hostile {
+            assert!(R1cs::<Fr>::read(Cursor::new(file)).is_err());
+        }
+
+        // A huge number of terms in the first linear combination, the constraints section comes
+        // right after the table of the sections.
+        let mut file = product_r1cs(&prime());
+        file[24..28].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(R1cs::<Fr>::read(Cursor::new(file)).is_err());
+    }
+
+    #[test]
+    fn test_r1cs_circuit() {
+        let r1cs = Arc::new(R1cs::<Fr>::read(Cursor::new(product_r1cs(&prime()))).unwrap());
+
+        let witness = read_witness::<Fr, _>(Cursor::new(witness_wtns(&[1, 6, 2, 3]))).unwrap();
+        assert_eq!(
+            witness,
+            vec![Fr::ONE, Fr::from(6), Fr::from(2), Fr::from(3)]
+        );
+        let circuit = R1csCircuit::new(r1cs.clone(), Some(witness));
+        assert_eq!(
+            circuit.public_inputs(),
+            Some(&[Fr::from(6), Fr::from(2)][..])
+        );
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        circuit.synthesize(&mut cs).unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_inputs(), 3);
+        assert_eq!(cs.num_constraints(), 2);
+
+        let witness = vec![Fr::ONE, Fr::from(7), Fr::from(2), Fr::from(3)];
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        R1csCircuit::new(r1cs.clone(), Some(witness))
+            .synthesize(&mut cs)
+            .unwrap();
+        assert_eq!(cs.which_is_unsatisfied(), Some("constraint 0"));
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        assert!(R1csCircuit::new(r1cs, Some(vec![Fr::ONE]))
+            .synthesize(&mut cs)
+            .is_err());
+    }
+
+    #[test]
+    fn test_optimize() {
+        let one = Fr::ONE;
+        // Wire 1 is the public output, 2 a boolean, 3 unused and 4 a factor of the output.
+        let r1cs = R1cs {
+            num_wires: 5,
+            num_public: 1,
+            constraints: vec![
+                Constraint {
+                    a: vec![(2, one)],
+                    b: vec![(0, one), (2, -one)],
+                    c: vec![],
+                },
+                // The same constraint, with split and reordered terms.
+                Constraint {
+                    a: vec![(2, Fr::from(2)), (2, -one)],
+                    b: vec![(2, -one), (0, one)],
+                    c: vec![(3, Fr::ZERO)],
+                },
+                // The same constraint with A and B swapped.
+                Constraint {
+                    a: vec![(0, one), (2, -one)],
+                    b: vec![(2, one)],
+                    c: vec![],
+                },
+                // 2 * 3 = 6
+                Constraint {
+                    a: vec![(0, Fr::from(2))],
+                    b: vec![(0, Fr::from(3))],
+                    c: vec![(0, Fr::from(6))],
+                },
+                Constraint {
+                    a: vec![(2, one)],
+                    b: vec![(4, one)],
+                    c: vec![(1, one)],
+                },
+                // 0 * 0 = 0
+                Constraint {
+                    a: vec![(4, Fr::ZERO)],
+                    b: vec![],
+                    c: vec![],
+                },
+            ],
+        };
+
+        let (optimized, map) = r1cs.optimize();
+        assert_eq!(map.kept_wires(), &[0, 1, 2, 4]);
+        assert_eq!(
+            optimized,
+            R1cs {
+                num_wires: 4,
+                num_public: 1,
+                constraints: vec![
+                    Constraint {
+                        a: vec![(2, one)],
+                        b: vec![(0, one), (2, -one)],
+                        c: vec![],
+                    },
+                    Constraint {
+                        a: vec![(2, one)],
+                        b: vec![(3, one)],
+                        c: vec![(1, one)],
+                    },
+                ],
+            }
+        );
+        assert_eq!(optimized.optimize().0, optimized);
+
+        let witness = [1, 7, 1, 42, 7].map(Fr::from);
+        let mapped = map.map_witness(&witness).unwrap();
+        assert_eq!(mapped, [1, 7, 1, 7].map(Fr::from));
+        assert!(map.map_witness(&mapped).is_err());
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        R1csCircuit::new(Arc::new(optimized), Some(mapped))
+            .synthesize(&mut cs)
+            .unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 2);
+
+        // Constant constraints that don't hold are kept.
+        let r1cs = R1cs {
+            num_wires: 1,
+            num_public: 0,
+            constraints: vec![Constraint {
+                a: vec![(0, one)],
+                b: vec![(0, one)],
+                c: vec![(0, Fr::from(2))],
+            }],
+        };
+        assert_eq!(r1cs.optimize().0, r1cs);
+    }
+
+    #[cfg(feature = "groth16")]
+    #[test]
+    fn test_proving_assignment() {
+        use blstrs::Bls12;
+        use rand_core::SeedableRng;
+        use rand_xorshift::XorShiftRng;
+
+        use crate::groth16::{
+            create_proof_from_assignments, generate_random_parameters, prepare_verifying_key,
+            verify_proof,
+        };
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let mut r1cs = R1cs::<Fr>::read(Cursor::new(product_r1cs(&prime()))).unwrap();
+        // A wire whose coefficients cancel out doesn't count towards the densities.
+        r1cs.constraints[1].b.push((2, Fr::ONE));
+        r1cs.constraints[1].b.push((2, -Fr::ONE));
+        let r1cs = Arc::new(r1cs);
+        let witness = read_witness::<Fr, _>(Cursor::new(witness_wtns(&[1, 6, 2, 3]))).unwrap();
+
+        let assignment = r1cs.proving_assignment(&witness).unwrap();
+        let circuit = R1csCircuit::new(r1cs.clone(), Some(witness.clone()));
+        assert_eq!(
+            assignment,
+            ProvingAssignment::synthesize(circuit.clone()).unwrap()
+        );
+        assert!(r1cs.proving_assignment(&witness[1..]).is_err());
+
+        let params = generate_random_parameters::<Bls12, _, _>(
+            R1csCircuit::new(r1cs.clone(), None),
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(&r1cs.densities(), params.densities.as_deref().unwrap());
+        let pvk = prepare_verifying_key(&params.vk);
+        let proof = create_proof_from_assignments(
+            assignment,
+            &params,
+            Fr::random(&mut rng),
+            Fr::random(&mut rng),
+        )
+        .unwrap();
+        assert!(verify_proof(&pvk, &proof, circuit.public_inputs().unwrap()).unwrap());
+    }
+}
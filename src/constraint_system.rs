@@ -1,9 +1,12 @@
+use std::fmt;
 use std::io;
 use std::marker::PhantomData;
+use std::panic::Location;
 
 use ec_gpu_gen::EcError;
 use ff::PrimeField;
 
+use crate::multicore::prelude::*;
 use crate::{gpu, Index, LinearCombination, Variable};
 
 /// Computations are expressed in terms of arithmetic circuits, in particular
@@ -57,6 +60,58 @@ pub enum SynthesisError {
     IncompatibleLengthVector(String),
     #[error("invalid pairing")]
     InvalidPairing,
+    /// During an MPC ceremony, a contribution to the parameters didn't verify
+    #[error("invalid contribution: {0}")]
+    InvalidContribution(String),
+    /// During proof generation, the query densities were neither provided by the parameters
+    /// nor tracked in the assignment
+    #[error("query densities are missing")]
+    MissingDensities,
+    /// During proof generation, the circuit didn't match the fingerprint of the parameters, see
+    /// [`circuit_fingerprint`](crate::groth16::circuit_fingerprint)
+    #[error("the circuit doesn't match the fingerprint of the parameters")]
+    FingerprintMismatch,
+    /// During proof generation, the fingerprint of the parameters was to be checked, but the
+    /// proofs are of precomputed assignments, whose constraints aren't synthesized
+    #[error("precomputed assignments can't be checked against the fingerprint of the parameters")]
+    UncheckedFingerprint,
+    /// During synthesis, an error occurred at the given path, the namespaces and the annotation
+    /// of the variable joined by `/`. Only constraint systems that track namespaces attach it,
+    /// see [`ProverConfig::namespace_paths`](crate::groth16::ProverConfig::namespace_paths).
+    #[error("{path}: {source}")]
+    Located {
+        path: String,
+        source: Box<SynthesisError>,
+    },
+}
+
+impl SynthesisError {
+    /// Attaches the path where synthesis failed, unless the error already carries one.
+    pub fn at_path<P: Into<String>>(self, path: P) -> Self {
+        match self {
+            SynthesisError::Located { .. } => self,
+            _ => SynthesisError::Located {
+                path: path.into(),
+                source: Box::new(self),
+            },
+        }
+    }
+
+    /// The path where synthesis failed, see [`SynthesisError::Located`].
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            SynthesisError::Located { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// The error without the path where it occurred.
+    pub fn unlocated(&self) -> &SynthesisError {
+        match self {
+            SynthesisError::Located { source, .. } => source,
+            _ => self,
+        }
+    }
 }
 
 // Don't create a separate "EC GPU error", but convert it into a `GpuError` first.
@@ -96,6 +151,10 @@ pub trait ConstraintSystem<Scalar: PrimeField>: Sized + Send {
 
     /// Allocate a public variable in the constraint system. The provided function is used to
     /// determine the assignment of the variable.
+    ///
+    /// Inputs and private variables are numbered separately, so inputs can be allocated at any
+    /// point of the synthesis, also after or between private variables. The public inputs of a
+    /// proof are the inputs in the order they were allocated.
     fn alloc_input<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
     where
         F: FnOnce() -> Result<Scalar, SynthesisError>,
@@ -112,6 +171,26 @@ pub trait ConstraintSystem<Scalar: PrimeField>: Sized + Send {
         LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
         LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>;
 
+    /// Enforce that `A` * `B` = `C` like [`ConstraintSystem::enforce`], with metadata that
+    /// describes the constraint, e.g. the section of the specification it implements. Constraint
+    /// systems that don't report on constraints ignore the metadata, which is the default.
+    fn enforce_with_metadata<A, AR, LA, LB, LC>(
+        &mut self,
+        annotation: A,
+        _metadata: ConstraintMetadata,
+        a: LA,
+        b: LB,
+        c: LC,
+    ) where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    {
+        self.enforce(annotation, a, b, c)
+    }
+
     /// Create a new (sub)namespace and enter into it. Not intended
     /// for downstream use; use `namespace` instead.
     fn push_namespace<NR, N>(&mut self, name_fn: N)
@@ -157,6 +236,15 @@ pub trait ConstraintSystem<Scalar: PrimeField>: Sized + Send {
         );
     }
 
+    /// Creates an empty constraint system for synthesizing an independent sub-circuit, which is
+    /// then merged into the receiver with `extend`, see [`synthesize_parallel`]. Its first input
+    /// must be the "one" variable, which `extend` skips. The default is `new`, implementations
+    /// whose `new` doesn't allocate the "one" variable, or that carry settings over to the
+    /// sub-circuit, override it.
+    fn new_shard(&self) -> Self {
+        Self::new()
+    }
+
     /// Determines if the current `ConstraintSystem` instance is a witness generator.
     /// ConstraintSystems that are witness generators need not assemble the actual constraints. Rather, they exist only
     /// to efficiently create a witness.
@@ -246,6 +334,234 @@ pub trait ConstraintSystem<Scalar: PrimeField>: Sized + Send {
     }
 }
 
+/// How much a constraint matters for the soundness of a circuit, see [`ConstraintMetadata`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        })
+    }
+}
+
+/// Structured information about a constraint, attached with
+/// [`ConstraintSystem::enforce_with_metadata`] and reported by
+/// [`TestConstraintSystem`](crate::util_cs::test_cs::TestConstraintSystem).
+///
+/// ```
+/// use bellperson::{ConstraintMetadata, Severity};
+///
+/// let metadata = ConstraintMetadata::new()
+///     .with_tag("spec:4.2")
+///     .with_severity(Severity::Critical);
+/// assert_eq!(metadata.tags, ["spec:4.2"]);
+/// assert!(metadata.location.unwrap().file().ends_with(".rs"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConstraintMetadata {
+    /// Free-form tags, e.g. the section of the specification the constraint implements.
+    pub tags: Vec<String>,
+    pub severity: Option<Severity>,
+    /// Where in the source the constraint is enforced.
+    pub location: Option<&'static Location<'static>>,
+}
+
+impl ConstraintMetadata {
+    /// Metadata located at the caller, without tags or severity.
+    #[track_caller]
+    pub fn new() -> Self {
+        ConstraintMetadata {
+            tags: vec![],
+            severity: None,
+            location: Some(Location::caller()),
+        }
+    }
+
+    pub fn with_tag<T: Into<String>>(mut self, tag: T) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = Some(severity);
+        self
+    }
+}
+
+impl fmt::Display for ConstraintMetadata {
+    /// Formats the metadata as e.g. `severity: high, tags: spec:4.2, at src/gadget.rs:12:9`,
+    /// omitting what is missing.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = vec![];
+        if let Some(severity) = self.severity {
+            parts.push(format!("severity: {}", severity));
+        }
+        if !self.tags.is_empty() {
+            parts.push(format!("tags: {}", self.tags.join(", ")));
+        }
+        if let Some(location) = self.location {
+            parts.push(format!("at {}", location));
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
+
+/// A table of rows of field elements, which lookups prove membership in, see
+/// [`LookupConstraintSystem`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LookupTable<Scalar> {
+    name: String,
+    width: usize,
+    rows: Vec<Vec<Scalar>>,
+}
+
+impl<Scalar: PrimeField> LookupTable<Scalar> {
+    /// Creates a table from its rows, which must all have the same, non-zero width.
+    pub fn new<N: Into<String>>(name: N, rows: Vec<Vec<Scalar>>) -> Result<Self, SynthesisError> {
+        let width = rows.first().map_or(0, Vec::len);
+        if width == 0 {
+            return Err(SynthesisError::IncompatibleLengthVector(
+                "lookup table without columns".to_string(),
+            ));
+        }
+        if let Some(row) = rows.iter().find(|row| row.len() != width) {
+            return Err(SynthesisError::IncompatibleLengthVector(format!(
+                "lookup table row of width {}, expected {}",
+                row.len(),
+                width
+            )));
+        }
+
+        Ok(Self {
+            name: name.into(),
+            width,
+            rows,
+        })
+    }
+
+    /// The name of the table, which backends use to identify it.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The number of columns of every row.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn rows(&self) -> &[Vec<Scalar>] {
+        &self.rows
+    }
+}
+
+/// An extension of [`ConstraintSystem`] with lookup arguments, constraints that a tuple of linear
+/// combinations equals a row of a [`LookupTable`].
+///
+/// Backends with native lookup arguments implement the methods. The provided methods lower
+/// lookups to R1CS constraints, which is what the constraint systems of this crate use, so
+/// gadgets can be written against this trait regardless of the backend.
+pub trait LookupConstraintSystem<Scalar: PrimeField>: ConstraintSystem<Scalar> {
+    /// Whether lookups are native arguments of the backend rather than lowered to R1CS, in which
+    /// case gadgets may prefer lookups to equivalent R1CS constraints, see
+    /// [`range`](crate::gadgets::range).
+    fn has_native_lookups(&self) -> bool {
+        false
+    }
+
+    /// Registers a table before the first lookup into it. Backends with native lookup arguments
+    /// commit to the table here, the lowering to R1CS needs no registration.
+    fn register_table(&mut self, _table: &LookupTable<Scalar>) {}
+
+    /// Enforces that `row` equals a row of `table`, the index of which is part of the witness and
+    /// computed by `index`, like the assignments of [`ConstraintSystem::alloc`].
+    ///
+    /// The lowering to R1CS allocates a boolean selector per row of the table, constrains exactly
+    /// one of them to be set and every entry of `row` to be the sum of the column weighted by the
+    /// selectors. This costs one variable per row and `rows + 1 + width` constraints, so it suits
+    /// small tables like S-boxes and range checks of a few bits.
+    fn enforce_lookup<A, AR, F>(
+        &mut self,
+        annotation: A,
+        table: &LookupTable<Scalar>,
+        row: &[LinearCombination<Scalar>],
+        index: F,
+    ) -> Result<(), SynthesisError>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        F: FnOnce() -> Result<usize, SynthesisError>,
+    {
+        if row.len() != table.width() {
+            return Err(SynthesisError::IncompatibleLengthVector(format!(
+                "lookup of width {} into table {} of width {}",
+                row.len(),
+                table.name(),
+                table.width()
+            )));
+        }
+        let index = match index() {
+            Ok(index) if index < table.rows().len() => Some(index),
+            Ok(_) => return Err(SynthesisError::Unsatisfiable),
+            Err(SynthesisError::AssignmentMissing) => None,
+            Err(e) => return Err(e),
+        };
+
+        let mut cs = self.namespace(annotation);
+        let mut selectors = Vec::with_capacity(table.rows().len());
+        for i in 0..table.rows().len() {
+            let selector = cs.alloc(
+                || format!("selector {}", i),
+                || {
+                    let index = index.ok_or(SynthesisError::AssignmentMissing)?;
+                    Ok(if index == i {
+                        Scalar::ONE
+                    } else {
+                        Scalar::ZERO
+                    })
+                },
+            )?;
+            cs.enforce(
+                || format!("selector {} is boolean", i),
+                |lc| lc + selector,
+                |lc| lc + Self::one() - selector,
+                |lc| lc,
+            );
+            selectors.push(selector);
+        }
+        cs.enforce(
+            || "one selector is set",
+            |lc| selectors.iter().fold(lc, |lc, selector| lc + *selector),
+            |lc| lc + Self::one(),
+            |lc| lc + Self::one(),
+        );
+        for (j, entry) in row.iter().enumerate() {
+            cs.enforce(
+                || format!("column {}", j),
+                |lc| {
+                    selectors
+                        .iter()
+                        .zip(table.rows())
+                        .fold(lc, |lc, (selector, row)| lc + (row[j], *selector))
+                },
+                |lc| lc + Self::one(),
+                |lc| lc + entry,
+            );
+        }
+
+        Ok(())
+    }
+}
+
 /// This is a "namespaced" constraint system which borrows a constraint system (pushing
 /// a namespace context) and, when dropped, pops out of the namespace context.
 pub struct Namespace<'a, Scalar: PrimeField, CS: ConstraintSystem<Scalar>>(
@@ -291,6 +607,23 @@ impl<'cs, Scalar: PrimeField, CS: ConstraintSystem<Scalar>> ConstraintSystem<Sca
         self.0.enforce(annotation, a, b, c)
     }
 
+    fn enforce_with_metadata<A, AR, LA, LB, LC>(
+        &mut self,
+        annotation: A,
+        metadata: ConstraintMetadata,
+        a: LA,
+        b: LB,
+        c: LC,
+    ) where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    {
+        self.0.enforce_with_metadata(annotation, metadata, a, b, c)
+    }
+
     // Downstream users who use `namespace` will never interact with these
     // functions and they will never be invoked because the namespace is
     // never a root constraint system.
@@ -341,6 +674,33 @@ impl<'a, Scalar: PrimeField, CS: ConstraintSystem<Scalar>> Drop for Namespace<'a
     }
 }
 
+impl<'cs, Scalar: PrimeField, CS: LookupConstraintSystem<Scalar>> LookupConstraintSystem<Scalar>
+    for Namespace<'cs, Scalar, CS>
+{
+    fn has_native_lookups(&self) -> bool {
+        self.0.has_native_lookups()
+    }
+
+    fn register_table(&mut self, table: &LookupTable<Scalar>) {
+        self.0.register_table(table)
+    }
+
+    fn enforce_lookup<A, AR, F>(
+        &mut self,
+        annotation: A,
+        table: &LookupTable<Scalar>,
+        row: &[LinearCombination<Scalar>],
+        index: F,
+    ) -> Result<(), SynthesisError>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        F: FnOnce() -> Result<usize, SynthesisError>,
+    {
+        self.0.enforce_lookup(annotation, table, row, index)
+    }
+}
+
 /// Convenience implementation of ConstraintSystem<Scalar> for mutable references to
 /// constraint systems.
 impl<'cs, Scalar: PrimeField, CS: ConstraintSystem<Scalar>> ConstraintSystem<Scalar>
@@ -381,6 +741,23 @@ impl<'cs, Scalar: PrimeField, CS: ConstraintSystem<Scalar>> ConstraintSystem<Sca
         (**self).enforce(annotation, a, b, c)
     }
 
+    fn enforce_with_metadata<A, AR, LA, LB, LC>(
+        &mut self,
+        annotation: A,
+        metadata: ConstraintMetadata,
+        a: LA,
+        b: LB,
+        c: LC,
+    ) where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    {
+        (**self).enforce_with_metadata(annotation, metadata, a, b, c)
+    }
+
     fn push_namespace<NR, N>(&mut self, name_fn: N)
     where
         NR: Into<String>,
@@ -445,3 +822,73 @@ impl<'cs, Scalar: PrimeField, CS: ConstraintSystem<Scalar>> ConstraintSystem<Sca
         (**self).aux_slice()
     }
 }
+
+impl<'cs, Scalar: PrimeField, CS: LookupConstraintSystem<Scalar>> LookupConstraintSystem<Scalar>
+    for &'cs mut CS
+{
+    fn has_native_lookups(&self) -> bool {
+        (**self).has_native_lookups()
+    }
+
+    fn register_table(&mut self, table: &LookupTable<Scalar>) {
+        (**self).register_table(table)
+    }
+
+    fn enforce_lookup<A, AR, F>(
+        &mut self,
+        annotation: A,
+        table: &LookupTable<Scalar>,
+        row: &[LinearCombination<Scalar>],
+        index: F,
+    ) -> Result<(), SynthesisError>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        F: FnOnce() -> Result<usize, SynthesisError>,
+    {
+        (**self).enforce_lookup(annotation, table, row, index)
+    }
+}
+
+/// Synthesizes independent sub-circuits into `cs`, in parallel if its root constraint system is
+/// extensible, see [`ConstraintSystem::is_extensible`].
+///
+/// The sub-circuits must not use any variables of `cs` or of each other, except for the "one"
+/// variable. Each is synthesized into its own shard, see [`ConstraintSystem::new_shard`], on the
+/// thread pool, and the shards are merged with [`ConstraintSystem::extend`] in the order of
+/// `circuits`. The variables and constraints end up in the same order as with sequential
+/// synthesis, which is what happens for constraint systems that aren't extensible, like the one
+/// used for generating parameters.
+pub fn synthesize_parallel<Scalar, CS, C>(
+    cs: &mut CS,
+    circuits: Vec<C>,
+) -> Result<(), SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+    C: Circuit<Scalar> + Send,
+{
+    if !CS::Root::is_extensible() {
+        for (i, circuit) in circuits.into_iter().enumerate() {
+            circuit.synthesize(&mut cs.namespace(|| format!("sub-circuit {}", i)))?;
+        }
+        return Ok(());
+    }
+
+    let root = cs.get_root();
+    let shards = circuits
+        .into_iter()
+        .map(|circuit| (circuit, root.new_shard()))
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|(circuit, mut shard)| -> Result<_, SynthesisError> {
+            circuit.synthesize(&mut shard)?;
+            Ok(shard)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for shard in &shards {
+        root.extend(shard);
+    }
+
+    Ok(())
+}
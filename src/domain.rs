@@ -16,7 +16,7 @@ use ec_gpu_gen::fft::FftKernel;
 use ff::{Field, PrimeField};
 
 use super::SynthesisError;
-use crate::gpu;
+use crate::{gpu, wipe};
 use ec_gpu_gen::fft_cpu;
 use ec_gpu_gen::threadpool::Worker;
 
@@ -41,30 +41,19 @@ impl<F: PrimeField + gpu::GpuName> AsMut<[F]> for EvaluationDomain<F> {
     }
 }
 
+impl<F: PrimeField + gpu::GpuName> Drop for EvaluationDomain<F> {
+    fn drop(&mut self) {
+        wipe::wipe_scalars(&mut self.coeffs);
+    }
+}
+
 impl<F: PrimeField + gpu::GpuName> EvaluationDomain<F> {
-    pub fn into_coeffs(self) -> Vec<F> {
-        self.coeffs
+    pub fn into_coeffs(mut self) -> Vec<F> {
+        std::mem::take(&mut self.coeffs)
     }
 
     pub fn from_coeffs(mut coeffs: Vec<F>) -> Result<Self, SynthesisError> {
-        // Compute the size of our evaluation domain
-        let mut m = 1;
-        let mut exp = 0;
-        while m < coeffs.len() {
-            m *= 2;
-            exp += 1;
-
-            // The pairing-friendly curve may not be able to support
-            // large enough (radix2) evaluation domains.
-            if exp >= F::S {
-                return Err(SynthesisError::PolynomialDegreeTooLarge);
-            }
-        }
-        // Compute omega, the 2^exp primitive root of unity
-        let mut omega = F::ROOT_OF_UNITY;
-        for _ in exp..F::S {
-            omega = omega.square();
-        }
+        let (m, exp, omega) = domain_size::<F>(coeffs.len())?;
 
         // Extend the coeffs vector with zeroes if necessary
         coeffs.resize(m, F::ZERO);
@@ -79,6 +68,50 @@ impl<F: PrimeField + gpu::GpuName> EvaluationDomain<F> {
         })
     }
 
+    /// Grows the domain to at least `new_size` elements, padding the coefficients with zeros in
+    /// place. The size is rounded up to a power of two and the roots of unity are regenerated.
+    ///
+    /// The contents must be coefficients, e.g. before calling [`Self::fft`]. Panics if `new_size`
+    /// is smaller than the current size.
+    pub fn extend_with_zeros(&mut self, new_size: usize) -> Result<(), SynthesisError> {
+        assert!(new_size >= self.coeffs.len(), "domains can only grow");
+
+        let (m, exp, omega) = domain_size::<F>(new_size)?;
+        self.coeffs.resize(m, F::ZERO);
+        self.exp = exp;
+        self.omega = omega;
+        self.omegainv = omega.invert().unwrap();
+        self.minv = F::from(m as u64).invert().unwrap();
+
+        Ok(())
+    }
+
+    /// Grows a domain holding evaluations to at least `new_size` points, see
+    /// [`Self::extend_with_zeros`].
+    ///
+    /// The evaluations are interpolated, the polynomial is extended and evaluated over the larger
+    /// domain. Panics if `new_size` is smaller than the current size.
+    pub fn resize_to(
+        &mut self,
+        new_size: usize,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFftKernel<F>>,
+    ) -> Result<(), SynthesisError> {
+        assert!(new_size >= self.coeffs.len(), "domains can only grow");
+        if new_size == self.coeffs.len() {
+            return Ok(());
+        }
+
+        // Check the size before interpolating, so that the evaluations are left untouched on
+        // errors.
+        domain_size::<F>(new_size)?;
+        self.ifft(worker, kern)?;
+        self.extend_with_zeros(new_size)?;
+        self.fft(worker, kern)?;
+
+        Ok(())
+    }
+
     pub fn fft(
         &mut self,
         worker: &Worker,
@@ -197,6 +230,40 @@ impl<F: PrimeField + gpu::GpuName> EvaluationDomain<F> {
         Ok(())
     }
 
+    /// Computes the coefficients of `(a * b - c) / z` from the evaluations `a`, `b` and `c`, the
+    /// same as [`Self::ifft_many`], [`Self::coset_fft_many`], [`Self::mul_assign`],
+    /// [`Self::sub_assign`], [`Self::divide_by_z_on_coset`] and [`Self::icoset_fft`] do, but
+    /// serially on the calling thread and always on the CPU. The result is left in `a`.
+    pub(crate) fn quotient_serial(a: &mut Self, b: &mut Self, c: &mut Self) {
+        fn distribute_powers<F: Field>(coeffs: &mut [F], g: F) {
+            let mut u = F::ONE;
+            for v in coeffs {
+                *v *= u;
+                u *= g;
+            }
+        }
+
+        for domain in [&mut *a, &mut *b, &mut *c] {
+            fft_cpu::serial_fft::<F>(&mut domain.coeffs, &domain.omegainv, domain.exp);
+            let minv = domain.minv;
+            domain.coeffs.iter_mut().for_each(|v| *v *= minv);
+            distribute_powers(&mut domain.coeffs, F::MULTIPLICATIVE_GENERATOR);
+            fft_cpu::serial_fft::<F>(&mut domain.coeffs, &domain.omega, domain.exp);
+        }
+
+        assert_eq!(a.coeffs.len(), b.coeffs.len());
+        assert_eq!(a.coeffs.len(), c.coeffs.len());
+        let i = a.z(&F::MULTIPLICATIVE_GENERATOR).invert().unwrap();
+        for ((a, b), c) in a.coeffs.iter_mut().zip(&b.coeffs).zip(&c.coeffs) {
+            *a = (*a * b - c) * i;
+        }
+
+        fft_cpu::serial_fft::<F>(&mut a.coeffs, &a.omegainv, a.exp);
+        let minv = a.minv;
+        a.coeffs.iter_mut().for_each(|v| *v *= minv);
+        distribute_powers(&mut a.coeffs, a.geninv);
+    }
+
     /// This evaluates t(tau) for this domain, which is
     /// tau^m - 1 for these radix-2 domains.
     pub fn z(&self, tau: &F) -> F {
@@ -260,6 +327,31 @@ impl<F: PrimeField + gpu::GpuName> EvaluationDomain<F> {
     }
 }
 
+/// Computes the size `m = 2^exp` of the smallest domain with at least `len` elements, returns
+/// it together with `exp` and `omega`, the `2^exp` primitive root of unity.
+pub(crate) fn domain_size<F: PrimeField>(len: usize) -> Result<(usize, u32, F), SynthesisError> {
+    // Compute the size of our evaluation domain
+    let mut m = 1;
+    let mut exp = 0;
+    while m < len {
+        m *= 2;
+        exp += 1;
+
+        // The pairing-friendly curve may not be able to support
+        // large enough (radix2) evaluation domains.
+        if exp >= F::S {
+            return Err(SynthesisError::PolynomialDegreeTooLarge);
+        }
+    }
+    // Compute omega, the 2^exp primitive root of unity
+    let mut omega = F::ROOT_OF_UNITY;
+    for _ in exp..F::S {
+        omega = omega.square();
+    }
+
+    Ok((m, exp, omega))
+}
+
 fn best_fft<F: PrimeField + gpu::GpuName>(
     #[allow(unused_variables)] kern: &mut Option<gpu::LockedFftKernel<F>>,
     worker: &Worker,
@@ -269,14 +361,35 @@ fn best_fft<F: PrimeField + gpu::GpuName>(
 ) {
     #[cfg(any(feature = "cuda", feature = "opencl"))]
     if let Some(ref mut kern) = kern {
-        if kern
+        // Keep the inputs around in case the GPU result is cross-checked.
+        let mut inputs =
+            gpu::should_check().then(|| coeffs.iter().map(|a| a.to_vec()).collect::<Vec<_>>());
+        let gpu_ok = kern
             .with(|k: &mut FftKernel<F>| gpu_fft(k, coeffs, omegas, log_ns))
-            .is_ok()
-        {
+            .is_ok();
+        if let Some(expected) = inputs.as_mut() {
+            if gpu_ok {
+                {
+                    let mut expected = expected.iter_mut().map(|a| &mut a[..]).collect::<Vec<_>>();
+                    cpu_fft(worker, &mut expected, omegas, log_ns);
+                }
+                if !gpu::check_fft(coeffs, expected) {
+                    for (a, expected) in coeffs.iter_mut().zip(expected.iter()) {
+                        a.copy_from_slice(expected);
+                    }
+                }
+            }
+            expected.iter_mut().for_each(|a| wipe::wipe_scalars(a));
+        }
+        if gpu_ok {
             return;
         }
     }
 
+    cpu_fft(worker, coeffs, omegas, log_ns);
+}
+
+fn cpu_fft<F: PrimeField>(worker: &Worker, coeffs: &mut [&mut [F]], omegas: &[F], log_ns: &[u32]) {
     let log_cpus = worker.log_num_threads();
     for ((a, omega), log_n) in coeffs.iter_mut().zip(omegas.iter()).zip(log_ns.iter()) {
         if *log_n <= log_cpus {
@@ -384,4 +497,39 @@ mod tests {
 
         test_comp::<<Bls12 as Engine>::Fr, _>(rng);
     }
+
+    #[test]
+    fn resize_domain() {
+        use blstrs::Scalar as Fr;
+
+        let worker = Worker::new();
+        let rng = &mut rand::thread_rng();
+
+        let coeffs: Vec<Fr> = (0..5).map(|_| Fr::random(&mut *rng)).collect();
+        let evaluate = |x: Fr| coeffs.iter().rev().fold(Fr::ZERO, |acc, c| acc * x + c);
+
+        // Extending the coefficients gives the evaluations over the larger domain.
+        let mut domain = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+        assert_eq!(domain.as_ref().len(), 8);
+        domain.extend_with_zeros(9).unwrap();
+        assert_eq!(domain.as_ref().len(), 16);
+        let omega = domain.omega;
+        domain.fft(&worker, &mut None).unwrap();
+        for (i, eval) in domain.as_ref().iter().enumerate() {
+            assert_eq!(*eval, evaluate(omega.pow_vartime(&[i as u64])));
+        }
+
+        // Resizing the evaluations gives the same result.
+        let mut resized = EvaluationDomain::from_coeffs(coeffs.clone()).unwrap();
+        resized.fft(&worker, &mut None).unwrap();
+        resized.resize_to(16, &worker, &mut None).unwrap();
+        assert_eq!(resized.as_ref(), domain.as_ref());
+        resized.ifft(&worker, &mut None).unwrap();
+        assert_eq!(&resized.as_ref()[..5], &coeffs[..]);
+        assert!(resized.as_ref()[5..]
+            .iter()
+            .all(|c| bool::from(c.is_zero())));
+
+        assert!(domain.extend_with_zeros(1 << Fr::S).is_err());
+    }
 }
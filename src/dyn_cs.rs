@@ -0,0 +1,360 @@
+use ff::PrimeField;
+
+use crate::{
+    ConstraintMetadata, ConstraintSystem, LinearCombination, LookupConstraintSystem,
+    SynthesisError, Variable,
+};
+
+/// An object-safe version of [`ConstraintSystem`], with the closures boxed and the linear
+/// combinations built by the caller. Every constraint system implements it, it is the backend
+/// of [`DynConstraintSystem`], which is what gadgets should use.
+///
+/// The methods have their own names, so that they don't shadow the ones of
+/// [`ConstraintSystem`] where both traits are in scope.
+pub trait ErasedConstraintSystem<Scalar: PrimeField>: Send {
+    fn alloc_boxed(
+        &mut self,
+        annotation: Box<dyn FnOnce() -> String + '_>,
+        f: Box<dyn FnOnce() -> Result<Scalar, SynthesisError> + '_>,
+    ) -> Result<Variable, SynthesisError>;
+
+    fn alloc_input_boxed(
+        &mut self,
+        annotation: Box<dyn FnOnce() -> String + '_>,
+        f: Box<dyn FnOnce() -> Result<Scalar, SynthesisError> + '_>,
+    ) -> Result<Variable, SynthesisError>;
+
+    fn enforce_boxed(
+        &mut self,
+        annotation: Box<dyn FnOnce() -> String + '_>,
+        a: LinearCombination<Scalar>,
+        b: LinearCombination<Scalar>,
+        c: LinearCombination<Scalar>,
+    );
+
+    fn enforce_with_metadata_boxed(
+        &mut self,
+        annotation: Box<dyn FnOnce() -> String + '_>,
+        metadata: ConstraintMetadata,
+        a: LinearCombination<Scalar>,
+        b: LinearCombination<Scalar>,
+        c: LinearCombination<Scalar>,
+    );
+
+    /// Enters a namespace of the root constraint system.
+    fn push_namespace_boxed(&mut self, name: Box<dyn FnOnce() -> String + '_>);
+
+    /// Leaves a namespace of the root constraint system.
+    fn pop_namespace_boxed(&mut self);
+
+    fn is_witness_generator_boxed(&self) -> bool;
+
+    fn extend_inputs_boxed(&mut self, new_inputs: &[Scalar]);
+
+    fn extend_aux_boxed(&mut self, new_aux: &[Scalar]);
+
+    fn allocate_empty_boxed(
+        &mut self,
+        aux_n: usize,
+        inputs_n: usize,
+    ) -> (&mut [Scalar], &mut [Scalar]);
+
+    fn inputs_slice_boxed(&self) -> &[Scalar];
+
+    fn aux_slice_boxed(&self) -> &[Scalar];
+}
+
+impl<Scalar: PrimeField, CS: ConstraintSystem<Scalar>> ErasedConstraintSystem<Scalar> for CS {
+    fn alloc_boxed(
+        &mut self,
+        annotation: Box<dyn FnOnce() -> String + '_>,
+        f: Box<dyn FnOnce() -> Result<Scalar, SynthesisError> + '_>,
+    ) -> Result<Variable, SynthesisError> {
+        self.alloc(annotation, f)
+    }
+
+    fn alloc_input_boxed(
+        &mut self,
+        annotation: Box<dyn FnOnce() -> String + '_>,
+        f: Box<dyn FnOnce() -> Result<Scalar, SynthesisError> + '_>,
+    ) -> Result<Variable, SynthesisError> {
+        self.alloc_input(annotation, f)
+    }
+
+    fn enforce_boxed(
+        &mut self,
+        annotation: Box<dyn FnOnce() -> String + '_>,
+        a: LinearCombination<Scalar>,
+        b: LinearCombination<Scalar>,
+        c: LinearCombination<Scalar>,
+    ) {
+        self.enforce(annotation, |_| a, |_| b, |_| c)
+    }
+
+    fn enforce_with_metadata_boxed(
+        &mut self,
+        annotation: Box<dyn FnOnce() -> String + '_>,
+        metadata: ConstraintMetadata,
+        a: LinearCombination<Scalar>,
+        b: LinearCombination<Scalar>,
+        c: LinearCombination<Scalar>,
+    ) {
+        self.enforce_with_metadata(annotation, metadata, |_| a, |_| b, |_| c)
+    }
+
+    // A wrapped namespace can't push or pop namespaces itself, only its root can.
+
+    fn push_namespace_boxed(&mut self, name: Box<dyn FnOnce() -> String + '_>) {
+        self.get_root().push_namespace(name)
+    }
+
+    fn pop_namespace_boxed(&mut self) {
+        self.get_root().pop_namespace()
+    }
+
+    fn is_witness_generator_boxed(&self) -> bool {
+        self.is_witness_generator()
+    }
+
+    fn extend_inputs_boxed(&mut self, new_inputs: &[Scalar]) {
+        self.extend_inputs(new_inputs)
+    }
+
+    fn extend_aux_boxed(&mut self, new_aux: &[Scalar]) {
+        self.extend_aux(new_aux)
+    }
+
+    fn allocate_empty_boxed(
+        &mut self,
+        aux_n: usize,
+        inputs_n: usize,
+    ) -> (&mut [Scalar], &mut [Scalar]) {
+        self.allocate_empty(aux_n, inputs_n)
+    }
+
+    fn inputs_slice_boxed(&self) -> &[Scalar] {
+        self.inputs_slice()
+    }
+
+    fn aux_slice_boxed(&self) -> &[Scalar] {
+        self.aux_slice()
+    }
+}
+
+/// A [`ConstraintSystem`] that forwards to any other one through dynamic dispatch.
+///
+/// Gadgets that are generic over the constraint system are compiled once per backend they are
+/// used with, e.g. `TestConstraintSystem`, `ProvingAssignment` and `MetricCS`. Gadgets that take a
+/// `DynConstraintSystem` instead are compiled once, at the cost of a virtual call and, for
+/// closures that capture something, an allocation per variable and constraint:
+///
+/// ```
+/// use bellperson::{ConstraintSystem, DynConstraintSystem, SynthesisError, Variable};
+/// use bellperson::util_cs::test_cs::TestConstraintSystem;
+/// use blstrs::Scalar as Fr;
+///
+/// fn square(
+///     cs: &mut DynConstraintSystem<'_, Fr>,
+///     x: Variable,
+///     value: Option<Fr>,
+/// ) -> Result<Variable, SynthesisError> {
+///     let square = value.map(|value| value * value);
+///     let y = cs.alloc(|| "y", || square.ok_or(SynthesisError::AssignmentMissing))?;
+///     cs.enforce(|| "x * x = y", |lc| lc + x, |lc| lc + x, |lc| lc + y);
+///     Ok(y)
+/// }
+///
+/// let mut cs = TestConstraintSystem::<Fr>::new();
+/// let x = cs.alloc(|| "x", || Ok(Fr::from(3u64))).unwrap();
+/// let mut ns = cs.namespace(|| "square");
+/// square(&mut DynConstraintSystem::new(&mut ns), x, Some(Fr::from(3u64))).unwrap();
+/// drop(ns);
+/// assert!(cs.is_satisfied());
+/// ```
+///
+/// Lookups are lowered to R1CS, see [`LookupConstraintSystem::enforce_lookup`], even if the
+/// wrapped constraint system supports them natively.
+pub struct DynConstraintSystem<'a, Scalar: PrimeField> {
+    cs: &'a mut dyn ErasedConstraintSystem<Scalar>,
+}
+
+impl<'a, Scalar: PrimeField> DynConstraintSystem<'a, Scalar> {
+    pub fn new<CS: ConstraintSystem<Scalar>>(cs: &'a mut CS) -> Self {
+        DynConstraintSystem { cs }
+    }
+
+    /// Wraps a constraint system that is already behind dynamic dispatch.
+    pub fn from_dyn(cs: &'a mut dyn ErasedConstraintSystem<Scalar>) -> Self {
+        DynConstraintSystem { cs }
+    }
+}
+
+impl<'a, Scalar: PrimeField> ConstraintSystem<Scalar> for DynConstraintSystem<'a, Scalar> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs
+            .alloc_boxed(Box::new(|| annotation().into()), Box::new(f))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs
+            .alloc_input_boxed(Box::new(|| annotation().into()), Box::new(f))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, annotation: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    {
+        self.cs.enforce_boxed(
+            Box::new(|| annotation().into()),
+            a(LinearCombination::zero()),
+            b(LinearCombination::zero()),
+            c(LinearCombination::zero()),
+        )
+    }
+
+    fn enforce_with_metadata<A, AR, LA, LB, LC>(
+        &mut self,
+        annotation: A,
+        metadata: ConstraintMetadata,
+        a: LA,
+        b: LB,
+        c: LC,
+    ) where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    {
+        self.cs.enforce_with_metadata_boxed(
+            Box::new(|| annotation().into()),
+            metadata,
+            a(LinearCombination::zero()),
+            b(LinearCombination::zero()),
+            c(LinearCombination::zero()),
+        )
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.push_namespace_boxed(Box::new(|| name_fn().into()))
+    }
+
+    fn pop_namespace(&mut self) {
+        self.cs.pop_namespace_boxed()
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+
+    fn is_witness_generator(&self) -> bool {
+        self.cs.is_witness_generator_boxed()
+    }
+
+    fn extend_inputs(&mut self, new_inputs: &[Scalar]) {
+        self.cs.extend_inputs_boxed(new_inputs)
+    }
+
+    fn extend_aux(&mut self, new_aux: &[Scalar]) {
+        self.cs.extend_aux_boxed(new_aux)
+    }
+
+    fn allocate_empty(&mut self, aux_n: usize, inputs_n: usize) -> (&mut [Scalar], &mut [Scalar]) {
+        self.cs.allocate_empty_boxed(aux_n, inputs_n)
+    }
+
+    fn inputs_slice(&self) -> &[Scalar] {
+        self.cs.inputs_slice_boxed()
+    }
+
+    fn aux_slice(&self) -> &[Scalar] {
+        self.cs.aux_slice_boxed()
+    }
+}
+
+impl<'a, Scalar: PrimeField> LookupConstraintSystem<Scalar> for DynConstraintSystem<'a, Scalar> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::witness_cs::WitnessCS;
+
+    /// Computes `x^3` in two namespaces, compiled once for every backend.
+    fn cube(
+        cs: &mut DynConstraintSystem<'_, Fr>,
+        x: Variable,
+        value: Option<Fr>,
+    ) -> Result<Variable, SynthesisError> {
+        let mut y = x;
+        let mut y_value = value;
+        for i in 0..2 {
+            let mut cs = cs.namespace(|| format!("round {}", i));
+            let product = y_value.zip(value).map(|(y, x)| y * x);
+            let z = cs.alloc(|| "z", || product.ok_or(SynthesisError::AssignmentMissing))?;
+            cs.enforce(|| "y * x = z", |lc| lc + y, |lc| lc + x, |lc| lc + z);
+            y = z;
+            y_value = product;
+        }
+        Ok(y)
+    }
+
+    #[test]
+    fn test_dyn_constraint_system() {
+        let x = Fr::from(3u64);
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let var = cs.alloc_input(|| "x", || Ok(x)).unwrap();
+        let out = cube(
+            &mut DynConstraintSystem::new(&mut cs.namespace(|| "cube")),
+            var,
+            Some(x),
+        )
+        .unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 2);
+        assert_eq!(cs.get("cube/round 1/z"), Fr::from(27u64));
+        assert_eq!(out, Variable::new_unchecked(crate::Index::Aux(1)));
+
+        // The same gadget into a witness generator.
+        let mut cs = WitnessCS::<Fr>::new();
+        let var = cs.alloc_input(|| "x", || Ok(x)).unwrap();
+        let erased: &mut dyn ErasedConstraintSystem<Fr> = &mut cs;
+        cube(
+            &mut DynConstraintSystem::from_dyn(&mut *erased),
+            var,
+            Some(x),
+        )
+        .unwrap();
+        assert!(erased.is_witness_generator_boxed());
+        assert_eq!(erased.aux_slice_boxed(), [Fr::from(9u64), Fr::from(27u64)]);
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let var = cs.alloc_input(|| "x", || Ok(x)).unwrap();
+        let err = cube(&mut DynConstraintSystem::new(&mut cs), var, None).unwrap_err();
+        assert!(matches!(err, SynthesisError::AssignmentMissing));
+    }
+}
@@ -0,0 +1,457 @@
+//! C bindings for proving and verifying Groth16 proofs over BLS12-381, see
+//! `include/bellperson.h`.
+//!
+//! Circuits are given as circom `.r1cs` files with `.wtns` witnesses, see
+//! [`circuits::r1cs`](crate::circuits::r1cs). Parameters, prepared verifying keys and circuits
+//! are opaque handles that are created from byte buffers and must be freed with the matching
+//! `_free` function. Proofs are in the compressed 192 byte encoding of [`Proof::write`],
+//! public inputs are 32 byte little endian scalars.
+//!
+//! The functions are exported from a `cdylib` or `staticlib` that depends on this crate with
+//! the `ffi` feature, e.g. with `pub use bellperson::ffi::*;`. Functions returning a status
+//! return [`BELLPERSON_OK`] or a negative error code, verification functions return `1` for
+//! valid and `0` for invalid proofs.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+use std::sync::Arc;
+
+use blstrs::{Bls12, Scalar as Fr};
+use ff::PrimeField;
+use rand_core::OsRng;
+
+use crate::circuits::r1cs::{read_witness, R1cs, R1csCircuit};
+use crate::groth16::{self, Parameters, PreparedVerifyingKey, Proof, VerifyingKey};
+
+/// The size of a compressed proof.
+pub const BELLPERSON_PROOF_LEN: usize = 192;
+/// The size of a public input.
+pub const BELLPERSON_INPUT_LEN: usize = 32;
+
+/// The call succeeded.
+pub const BELLPERSON_OK: i32 = 0;
+/// A buffer couldn't be decoded, e.g. a point isn't on the curve or a scalar isn't canonical.
+pub const BELLPERSON_ERR_INVALID_DATA: i32 = -1;
+/// Proving or verifying failed, e.g. because the witness doesn't match the circuit.
+pub const BELLPERSON_ERR_SYNTHESIS: i32 = -2;
+/// The call panicked.
+pub const BELLPERSON_ERR_PANIC: i32 = -3;
+
+/// Groth16 parameters, used for proving.
+pub struct BellpersonParams(Parameters<Bls12>);
+
+/// A prepared verifying key, used for verifying.
+pub struct BellpersonVerifyingKey(PreparedVerifyingKey<Bls12>);
+
+/// A circuit in the `.r1cs` format of circom.
+pub struct BellpersonR1cs(Arc<R1cs<Fr>>);
+
+/// Reads parameters in the format of [`Parameters::write`], their points are checked. Returns
+/// null if they can't be read.
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bellperson_params_read(
+    bytes: *const u8,
+    len: usize,
+) -> *mut BellpersonParams {
+    let bytes = buffer(bytes, len);
+    catch(|| Parameters::<Bls12>::read(bytes, true).ok())
+        .flatten()
+        .map_or(std::ptr::null_mut(), |params| {
+            Box::into_raw(Box::new(BellpersonParams(params)))
+        })
+}
+
+/// Frees parameters returned by [`bellperson_params_read`].
+///
+/// # Safety
+///
+/// `params` must be null or a handle that wasn't freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn bellperson_params_free(params: *mut BellpersonParams) {
+    if !params.is_null() {
+        drop(Box::from_raw(params));
+    }
+}
+
+/// Returns the prepared verifying key of parameters.
+///
+/// # Safety
+///
+/// `params` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn bellperson_params_verifying_key(
+    params: *const BellpersonParams,
+) -> *mut BellpersonVerifyingKey {
+    let pvk = groth16::prepare_verifying_key(&(*params).0.vk);
+    Box::into_raw(Box::new(BellpersonVerifyingKey(pvk)))
+}
+
+/// Reads a verifying key in the format of [`VerifyingKey::write`] and prepares it. Returns
+/// null if it can't be read.
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bellperson_verifying_key_read(
+    bytes: *const u8,
+    len: usize,
+) -> *mut BellpersonVerifyingKey {
+    let bytes = buffer(bytes, len);
+    catch(|| VerifyingKey::<Bls12>::read(bytes).ok())
+        .flatten()
+        .map_or(std::ptr::null_mut(), |vk| {
+            let pvk = groth16::prepare_verifying_key(&vk);
+            Box::into_raw(Box::new(BellpersonVerifyingKey(pvk)))
+        })
+}
+
+/// Frees a verifying key.
+///
+/// # Safety
+///
+/// `vk` must be null or a handle that wasn't freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn bellperson_verifying_key_free(vk: *mut BellpersonVerifyingKey) {
+    if !vk.is_null() {
+        drop(Box::from_raw(vk));
+    }
+}
+
+/// Reads a circuit in the `.r1cs` format. Returns null if it can't be read.
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bellperson_r1cs_read(bytes: *const u8, len: usize) -> *mut BellpersonR1cs {
+    let bytes = buffer(bytes, len);
+    catch(|| R1cs::read(std::io::Cursor::new(bytes)).ok())
+        .flatten()
+        .map_or(std::ptr::null_mut(), |r1cs| {
+            Box::into_raw(Box::new(BellpersonR1cs(Arc::new(r1cs))))
+        })
+}
+
+/// Frees a circuit returned by [`bellperson_r1cs_read`].
+///
+/// # Safety
+///
+/// `r1cs` must be null or a handle that wasn't freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn bellperson_r1cs_free(r1cs: *mut BellpersonR1cs) {
+    if !r1cs.is_null() {
+        drop(Box::from_raw(r1cs));
+    }
+}
+
+/// Proves a circuit with a witness in the `.wtns` format and writes the proof to `proof_out`.
+///
+/// # Safety
+///
+/// `params` and `r1cs` must be valid handles, `wtns` must point to `wtns_len` readable bytes and
+/// `proof_out` to [`BELLPERSON_PROOF_LEN`] writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bellperson_create_random_proof(
+    params: *const BellpersonParams,
+    r1cs: *const BellpersonR1cs,
+    wtns: *const u8,
+    wtns_len: usize,
+    proof_out: *mut u8,
+) -> i32 {
+    let params = &(*params).0;
+    let r1cs = &(*r1cs).0;
+    let wtns = buffer(wtns, wtns_len);
+    let proof_out = slice::from_raw_parts_mut(proof_out, BELLPERSON_PROOF_LEN);
+
+    catch(|| {
+        let witness = match read_witness(std::io::Cursor::new(wtns)) {
+            Ok(witness) => witness,
+            Err(_) => return BELLPERSON_ERR_INVALID_DATA,
+        };
+        let circuit = R1csCircuit::new(r1cs.clone(), Some(witness));
+        match groth16::create_random_proof(circuit, params, &mut OsRng) {
+            Ok(proof) => {
+                proof
+                    .write(&mut proof_out[..])
+                    .expect("buffer has the size of a proof");
+                BELLPERSON_OK
+            }
+            Err(_) => BELLPERSON_ERR_SYNTHESIS,
+        }
+    })
+    .unwrap_or(BELLPERSON_ERR_PANIC)
+}
+
+/// Verifies a proof of `num_inputs` public inputs.
+///
+/// # Safety
+///
+/// `vk` must be a valid handle, `proof` must point to [`BELLPERSON_PROOF_LEN`] and `inputs` to
+/// `num_inputs` times [`BELLPERSON_INPUT_LEN`] readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bellperson_verify_proof(
+    vk: *const BellpersonVerifyingKey,
+    proof: *const u8,
+    inputs: *const u8,
+    num_inputs: usize,
+) -> i32 {
+    bellperson_verify_proofs_batch(vk, proof, 1, inputs, num_inputs)
+}
+
+/// Verifies `num_proofs` proofs at once, the proofs and their public inputs are concatenated.
+/// Every proof has `num_inputs` public inputs. Returns `1` only if all proofs are valid, there
+/// must be at least one.
+///
+/// # Safety
+///
+/// `vk` must be a valid handle, `proofs` must point to `num_proofs` times
+/// [`BELLPERSON_PROOF_LEN`] and `inputs` to `num_proofs * num_inputs` times
+/// [`BELLPERSON_INPUT_LEN`] readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bellperson_verify_proofs_batch(
+    vk: *const BellpersonVerifyingKey,
+    proofs: *const u8,
+    num_proofs: usize,
+    inputs: *const u8,
+    num_inputs: usize,
+) -> i32 {
+    let pvk = &(*vk).0;
+    if num_proofs == 0 {
+        return BELLPERSON_ERR_INVALID_DATA;
+    }
+    let proofs_len = num_proofs.checked_mul(BELLPERSON_PROOF_LEN);
+    let inputs_len = num_proofs
+        .checked_mul(num_inputs)
+        .and_then(|n| n.checked_mul(BELLPERSON_INPUT_LEN));
+    let (proofs, inputs) = match (proofs_len, inputs_len) {
+        (Some(proofs_len), Some(inputs_len)) => {
+            (buffer(proofs, proofs_len), buffer(inputs, inputs_len))
+        }
+        _ => return BELLPERSON_ERR_INVALID_DATA,
+    };
+
+    catch(|| {
+        let proofs = match proofs
+            .chunks(BELLPERSON_PROOF_LEN)
+            .map(Proof::<Bls12>::read)
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(proofs) => proofs,
+            Err(_) => return BELLPERSON_ERR_INVALID_DATA,
+        };
+        let inputs = match read_inputs(inputs) {
+            Some(inputs) => inputs,
+            None => return BELLPERSON_ERR_INVALID_DATA,
+        };
+
+        let result = if num_proofs == 1 {
+            groth16::verify_proof(pvk, &proofs[0], &inputs)
+        } else {
+            let proofs: Vec<_> = proofs.iter().collect();
+            let inputs: Vec<_> = inputs
+                .chunks(num_inputs.max(1))
+                .map(<[Fr]>::to_vec)
+                .chain(std::iter::repeat(Vec::new()))
+                .take(num_proofs)
+                .collect();
+            groth16::verify_proofs_batch(pvk, &mut OsRng, &proofs, &inputs)
+        };
+        match result {
+            Ok(valid) => valid as i32,
+            Err(_) => BELLPERSON_ERR_SYNTHESIS,
+        }
+    })
+    .unwrap_or(BELLPERSON_ERR_PANIC)
+}
+
+/// Reads little endian scalars, `None` if one isn't canonical.
+fn read_inputs(bytes: &[u8]) -> Option<Vec<Fr>> {
+    bytes
+        .chunks(BELLPERSON_INPUT_LEN)
+        .map(|chunk| {
+            let mut repr = <Fr as PrimeField>::Repr::default();
+            repr.as_mut().copy_from_slice(chunk);
+            Option::from(Fr::from_repr(repr))
+        })
+        .collect()
+}
+
+/// Turns a pointer and a length into a slice, a null pointer is only valid for an empty
+/// buffer.
+unsafe fn buffer<'a>(bytes: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(bytes, len)
+    }
+}
+
+/// Runs `f`, panics must not unwind into the caller. Nothing that outlives the call is modified
+/// before it can panic, so nothing is left in an inconsistent state.
+fn catch<T, F: FnOnce() -> T>(f: F) -> Option<T> {
+    panic::catch_unwind(AssertUnwindSafe(f)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use crate::groth16::generate_random_parameters;
+    use crate::util_cs::r1cs_writer_cs::R1csWriterCS;
+    use crate::{Circuit, ConstraintSystem, SynthesisError};
+
+    /// `x * x = y` with `y` public.
+    struct Square {
+        x: Option<Fr>,
+    }
+
+    impl Circuit<Fr> for Square {
+        fn synthesize<CS: ConstraintSystem<Fr>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            let y = cs.alloc_input(
+                || "y",
+                || {
+                    self.x
+                        .map(|x| x * x)
+                        .ok_or(SynthesisError::AssignmentMissing)
+                },
+            )?;
+            let x = cs.alloc(|| "x", || self.x.ok_or(SynthesisError::AssignmentMissing))?;
+            cs.enforce(|| "x * x = y", |lc| lc + x, |lc| lc + x, |lc| lc + y);
+
+            Ok(())
+        }
+    }
+
+    /// A `.wtns` file with the given values, little endian numbers below 2^64.
+    fn wtns(values: &[u64]) -> Vec<u8> {
+        let mut prime = (-Fr::from(1)).to_repr().as_ref().to_vec();
+        prime[0] += 1;
+
+        let mut file = b"wtns".to_vec();
+        file.extend_from_slice(&2u32.to_le_bytes());
+        file.extend_from_slice(&2u32.to_le_bytes());
+        file.extend_from_slice(&1u32.to_le_bytes());
+        file.extend_from_slice(&40u64.to_le_bytes());
+        file.extend_from_slice(&32u32.to_le_bytes());
+        file.extend_from_slice(&prime);
+        file.extend_from_slice(&(values.len() as u32).to_le_bytes());
+        file.extend_from_slice(&2u32.to_le_bytes());
+        file.extend_from_slice(&(values.len() as u64 * 32).to_le_bytes());
+        for value in values {
+            let mut bytes = [0; 32];
+            bytes[..8].copy_from_slice(&value.to_le_bytes());
+            file.extend_from_slice(&bytes);
+        }
+        file
+    }
+
+    #[test]
+    fn test_ffi() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let params =
+            generate_random_parameters::<Bls12, _, _>(Square { x: None }, &mut rng).unwrap();
+        let mut params_bytes = Vec::new();
+        params.write(&mut params_bytes).unwrap();
+        let mut cs = R1csWriterCS::new();
+        Square { x: None }.synthesize(&mut cs).unwrap();
+        let mut r1cs_bytes = Vec::new();
+        cs.write_r1cs(&mut r1cs_bytes).unwrap();
+
+        unsafe {
+            let params = bellperson_params_read(params_bytes.as_ptr(), params_bytes.len());
+            let r1cs = bellperson_r1cs_read(r1cs_bytes.as_ptr(), r1cs_bytes.len());
+            assert!(!params.is_null() && !r1cs.is_null());
+            assert!(bellperson_params_read(params_bytes.as_ptr(), 10).is_null());
+            let vk = bellperson_params_verifying_key(params);
+
+            let mut proofs = [0; 2 * BELLPERSON_PROOF_LEN];
+            for (proof, x) in proofs.chunks_mut(BELLPERSON_PROOF_LEN).zip([3, 4]) {
+                let wtns = wtns(&[1, x * x, x]);
+                let status = bellperson_create_random_proof(
+                    params,
+                    r1cs,
+                    wtns.as_ptr(),
+                    wtns.len(),
+                    proof.as_mut_ptr(),
+                );
+                assert_eq!(status, BELLPERSON_OK);
+            }
+            // A witness that doesn't fit the circuit.
+            let wtns = wtns(&[1, 9]);
+            let status = bellperson_create_random_proof(
+                params,
+                r1cs,
+                wtns.as_ptr(),
+                wtns.len(),
+                proofs.as_mut_ptr(),
+            );
+            assert_eq!(status, BELLPERSON_ERR_SYNTHESIS);
+
+            let mut inputs = [0; 2 * BELLPERSON_INPUT_LEN];
+            inputs[0] = 9;
+            inputs[BELLPERSON_INPUT_LEN] = 16;
+            assert_eq!(
+                bellperson_verify_proof(vk, proofs.as_ptr(), inputs.as_ptr(), 1),
+                1
+            );
+            assert_eq!(
+                bellperson_verify_proofs_batch(vk, proofs.as_ptr(), 2, inputs.as_ptr(), 1),
+                1
+            );
+            inputs[0] = 10;
+            assert_eq!(
+                bellperson_verify_proofs_batch(vk, proofs.as_ptr(), 2, inputs.as_ptr(), 1),
+                0
+            );
+            inputs[0] = 0xff;
+            inputs[31] = 0xff;
+            assert_eq!(
+                bellperson_verify_proof(vk, proofs.as_ptr(), inputs.as_ptr(), 1),
+                BELLPERSON_ERR_INVALID_DATA
+            );
+            // Lengths that don't fit in a usize.
+            assert_eq!(
+                bellperson_verify_proofs_batch(vk, proofs.as_ptr(), usize::MAX, inputs.as_ptr(), 1),
+                BELLPERSON_ERR_INVALID_DATA
+            );
+            assert_eq!(
+                bellperson_verify_proofs_batch(vk, proofs.as_ptr(), 2, inputs.as_ptr(), usize::MAX),
+                BELLPERSON_ERR_INVALID_DATA
+            );
+            assert_eq!(
+                bellperson_verify_proofs_batch(vk, proofs.as_ptr(), 0, inputs.as_ptr(), 1),
+                BELLPERSON_ERR_INVALID_DATA
+            );
+
+            // Truncated files with a huge number of sections must not be allocated for.
+            let mut truncated = r1cs_bytes[..12].to_vec();
+            truncated[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert!(bellperson_r1cs_read(truncated.as_ptr(), truncated.len()).is_null());
+            let mut truncated = wtns(&[1, 9, 3])[..12].to_vec();
+            truncated[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+            let status = bellperson_create_random_proof(
+                params,
+                r1cs,
+                truncated.as_ptr(),
+                truncated.len(),
+                proofs.as_mut_ptr(),
+            );
+            assert_eq!(status, BELLPERSON_ERR_INVALID_DATA);
+
+            bellperson_verifying_key_free(vk);
+            bellperson_r1cs_free(r1cs);
+            bellperson_params_free(params);
+        }
+    }
+}
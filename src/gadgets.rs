@@ -2,14 +2,31 @@
 
 pub mod test;
 
+pub mod bignat;
 pub mod blake2s;
 pub mod boolean;
+pub mod cmp;
+pub mod compose;
+pub mod der;
+pub mod ecdsa;
+pub mod eddsa;
+pub mod edwards;
+pub mod hkdf;
+pub mod hmac;
 pub mod lookup;
+pub mod merkle;
+pub mod mimc;
 pub mod multieq;
 pub mod multipack;
 pub mod num;
+pub mod pedersen_hash;
+pub mod range;
+pub mod select;
 pub mod sha256;
 pub mod uint32;
+pub mod uint64;
+pub mod uint8;
+pub mod weierstrass;
 
 use crate::SynthesisError;
 
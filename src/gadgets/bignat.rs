@@ -0,0 +1,1088 @@
+//! Arithmetic on big natural numbers, e.g. to emulate the base field of secp256k1 or an RSA group
+//! inside a circuit.
+//!
+//! A [`BigNat`] is a sequence of limbs of `limb_width` bits, least significant limb first. Sums
+//! and products are computed limb by limb without carrying, so their limbs grow beyond
+//! `limb_width` bits, and [`BigNat::reduce`] brings them back by reducing modulo a constant.
+//! Numbers with different limbs are compared by propagating the carries between their limbs, see
+//! [`BigNat::enforce_equal`].
+//!
+//! Every limb of an allocated number is range checked, which costs `limb_width + 1` constraints.
+//! A product costs a constraint per limb of the result unless either factor is a constant, and an
+//! equality costs a range check of a carry per limb.
+//!
+//! The limbs must not wrap around the modulus of the scalar field, the operations panic if they
+//! could. This bounds the number of limbs: a reduced product has limbs of twice the limb width,
+//! plus the bits of the number of limbs, plus a few bits of carries.
+
+use std::cmp::Ordering;
+
+use ff::{PrimeField, PrimeFieldBits};
+
+use crate::{ConstraintSystem, LinearCombination, SynthesisError, Variable};
+
+use super::boolean::Boolean;
+use super::cmp;
+use super::num::AllocatedNum;
+
+/// A big natural number in limbs of `limb_width` bits, see the [module documentation](self).
+#[derive(Clone)]
+pub struct BigNat<Scalar: PrimeField> {
+    limbs: Vec<Limb<Scalar>>,
+    limb_width: usize,
+    // Every limb is less than 2^word_bits.
+    word_bits: usize,
+    is_constant: bool,
+}
+
+#[derive(Clone)]
+struct Limb<Scalar: PrimeField> {
+    // The limb is lc + constant.
+    lc: LinearCombination<Scalar>,
+    constant: Scalar,
+    value: Option<Nat>,
+}
+
+impl<Scalar: PrimeFieldBits> BigNat<Scalar> {
+    /// A constant `value`, given in 64-bit words, least significant word first.
+    ///
+    /// Panics if the value doesn't fit into `n_limbs` limbs.
+    pub fn constant(value: &[u64], limb_width: usize, n_limbs: usize) -> Self {
+        let value = Nat::new(value.to_vec());
+        assert!(
+            value.bits() <= limb_width * n_limbs,
+            "constant of {} bits in {} limbs of {} bits",
+            value.bits(),
+            n_limbs,
+            limb_width
+        );
+
+        let limbs = split(&value, limb_width, n_limbs)
+            .into_iter()
+            .map(|limb| Limb {
+                lc: LinearCombination::zero(),
+                constant: limb.to_scalar(),
+                value: Some(limb),
+            })
+            .collect();
+
+        BigNat {
+            limbs,
+            limb_width,
+            word_bits: limb_width,
+            is_constant: true,
+        }
+    }
+
+    /// Allocates `value`, given in 64-bit words, least significant word first, in `n_limbs`
+    /// range checked limbs.
+    ///
+    /// Returns [`SynthesisError::Unsatisfiable`] if the value doesn't fit into the limbs.
+    pub fn alloc<CS>(
+        cs: CS,
+        value: Option<&[u64]>,
+        limb_width: usize,
+        n_limbs: usize,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let value = value.map(|value| Nat::new(value.to_vec()));
+        Self::alloc_nat(cs, value.as_ref(), limb_width, n_limbs)
+    }
+
+    pub(crate) fn alloc_nat<CS>(
+        mut cs: CS,
+        value: Option<&Nat>,
+        limb_width: usize,
+        n_limbs: usize,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        assert!(limb_width > 0 && n_limbs > 0, "empty limbs");
+        if value.map_or(false, |value| value.bits() > limb_width * n_limbs) {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let values = match value {
+            Some(value) => split(value, limb_width, n_limbs)
+                .into_iter()
+                .map(Some)
+                .collect(),
+            None => vec![None; n_limbs],
+        };
+        let limbs = values
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let num = AllocatedNum::alloc(cs.namespace(|| format!("limb {}", i)), || {
+                    value
+                        .as_ref()
+                        .map(Nat::to_scalar)
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })?;
+                cmp::enforce_num_bits(
+                    cs.namespace(|| format!("limb {} range", i)),
+                    &num,
+                    limb_width,
+                )?;
+
+                Ok(Limb {
+                    lc: LinearCombination::zero() + num.get_variable(),
+                    constant: Scalar::ZERO,
+                    value,
+                })
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+        Ok(BigNat {
+            limbs,
+            limb_width,
+            word_bits: limb_width,
+            is_constant: false,
+        })
+    }
+
+    /// The value in 64-bit words, least significant word first, without leading zero words.
+    pub fn get_value(&self) -> Option<Vec<u64>> {
+        self.value().map(|value| value.0)
+    }
+
+    pub fn limb_width(&self) -> usize {
+        self.limb_width
+    }
+
+    pub fn n_limbs(&self) -> usize {
+        self.limbs.len()
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.is_constant
+    }
+
+    /// Adds the numbers limb by limb, which costs no constraints.
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(&self, other: &Self) -> Self {
+        self.assert_limb_width(other);
+
+        let n_limbs = self.limbs.len().max(other.limbs.len());
+        let limbs = (0..n_limbs)
+            .map(|i| match (self.limbs.get(i), other.limbs.get(i)) {
+                (Some(a), Some(b)) => a.add(b),
+                (Some(limb), None) | (None, Some(limb)) => limb.clone(),
+                (None, None) => unreachable!(),
+            })
+            .collect();
+        let word_bits = self.word_bits.max(other.word_bits) + 1;
+        check_word_bits::<Scalar>(word_bits);
+
+        BigNat {
+            limbs,
+            limb_width: self.limb_width,
+            word_bits,
+            is_constant: self.is_constant && other.is_constant,
+        }
+    }
+
+    /// Multiplies the numbers without reducing the product, which costs a constraint per limb of
+    /// the product, or none if either factor is a constant.
+    pub fn mul<CS>(&self, mut cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.assert_limb_width(other);
+
+        let n_limbs = self.limbs.len() + other.limbs.len() - 1;
+        let n_terms = self.limbs.len().min(other.limbs.len());
+        let word_bits = self.word_bits
+            + other.word_bits
+            + (usize::BITS - (n_terms - 1).leading_zeros()) as usize;
+        check_word_bits::<Scalar>(word_bits);
+
+        let values = (0..n_limbs)
+            .map(|k| {
+                self.limbs
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, a)| Some((a, other.limbs.get(k.checked_sub(i)?)?)))
+                    .try_fold(Nat::default(), |sum, (a, b)| {
+                        Some(sum.add(&a.value.as_ref()?.mul(b.value.as_ref()?)))
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        if self.is_constant || other.is_constant {
+            let (constant, num) = if other.is_constant {
+                (other, self)
+            } else {
+                (self, other)
+            };
+            let limbs = values
+                .into_iter()
+                .enumerate()
+                .map(|(k, value)| {
+                    let mut limb = Limb {
+                        lc: LinearCombination::zero(),
+                        constant: Scalar::ZERO,
+                        value,
+                    };
+                    for (j, c) in constant.limbs.iter().enumerate() {
+                        if let Some(a) = k.checked_sub(j).and_then(|i| num.limbs.get(i)) {
+                            limb.lc = limb.lc + (c.constant, &a.lc);
+                            limb.constant += c.constant * a.constant;
+                        }
+                    }
+                    limb
+                })
+                .collect();
+
+            return Ok(BigNat {
+                limbs,
+                limb_width: self.limb_width,
+                word_bits,
+                is_constant: self.is_constant && other.is_constant,
+            });
+        }
+
+        let limbs = values
+            .into_iter()
+            .enumerate()
+            .map(|(k, value)| {
+                let num =
+                    AllocatedNum::alloc(cs.namespace(|| format!("product limb {}", k)), || {
+                        value
+                            .as_ref()
+                            .map(Nat::to_scalar)
+                            .ok_or(SynthesisError::AssignmentMissing)
+                    })?;
+
+                Ok(Limb {
+                    lc: LinearCombination::zero() + num.get_variable(),
+                    constant: Scalar::ZERO,
+                    value,
+                })
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+        let product = BigNat {
+            limbs,
+            limb_width: self.limb_width,
+            word_bits,
+            is_constant: false,
+        };
+
+        // The limbs are the coefficients of the product of two polynomials, which is determined
+        // by its values at as many points as it has coefficients. The coefficients don't wrap
+        // around the modulus, so this proves the product over the integers.
+        for k in 0..n_limbs {
+            let point = Scalar::from(k as u64);
+            cs.enforce(
+                || format!("product at {}", k),
+                |_| self.eval(point, CS::one()),
+                |_| other.eval(point, CS::one()),
+                |_| product.eval(point, CS::one()),
+            );
+        }
+
+        Ok(product)
+    }
+
+    /// Reduces the number modulo a constant `modulus`, given in 64-bit words, least significant
+    /// word first, by allocating a quotient and a remainder and enforcing
+    /// `self = quotient * modulus + remainder`.
+    ///
+    /// The remainder is range checked to the limbs of the modulus, but not proven to be less than
+    /// the modulus. It is congruent to the number, and [`BigNat::enforce_equal_mod`] compares such
+    /// numbers.
+    ///
+    /// Panics if the modulus is zero.
+    pub fn reduce<CS>(&self, mut cs: CS, modulus: &[u64]) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let (modulus, modulus_value) = self.constant_modulus(modulus);
+        let (quotient, remainder) = match self.value() {
+            Some(value) => {
+                let (quotient, remainder) = value.divrem(&modulus_value);
+                (Some(quotient), Some(remainder))
+            }
+            None => (None, None),
+        };
+
+        let quotient = Self::alloc_nat(
+            cs.namespace(|| "quotient"),
+            quotient.as_ref(),
+            self.limb_width,
+            self.quotient_limbs(&modulus_value),
+        )?;
+        let remainder = Self::alloc_nat(
+            cs.namespace(|| "remainder"),
+            remainder.as_ref(),
+            self.limb_width,
+            modulus.limbs.len(),
+        )?;
+        let product = quotient.mul(cs.namespace(|| "product"), &modulus)?;
+        self.enforce_equal(cs.namespace(|| "reduction"), &product.add(&remainder))?;
+
+        Ok(remainder)
+    }
+
+    /// Computes `self + other` modulo a constant, see [`BigNat::reduce`].
+    pub fn add_mod<CS>(&self, cs: CS, other: &Self, modulus: &[u64]) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.add(other).reduce(cs, modulus)
+    }
+
+    /// Computes `self * other` modulo a constant, see [`BigNat::reduce`].
+    pub fn mul_mod<CS>(
+        &self,
+        mut cs: CS,
+        other: &Self,
+        modulus: &[u64],
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let product = self.mul(cs.namespace(|| "product"), other)?;
+        product.reduce(cs.namespace(|| "reduce"), modulus)
+    }
+
+    /// Computes the inverse of the number modulo a constant `modulus`, given in 64-bit words,
+    /// least significant word first, by allocating it in the limbs of the modulus and enforcing
+    /// that its product with the number is congruent to one.
+    ///
+    /// Returns [`SynthesisError::Unsatisfiable`] if the number isn't invertible. Panics if the
+    /// modulus is zero.
+    pub fn inverse_mod<CS>(&self, mut cs: CS, modulus: &[u64]) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let (modulus_nat, modulus_value) = self.constant_modulus(modulus);
+        let inverse = match self.value() {
+            Some(value) => Some(
+                value
+                    .inverse_mod(&modulus_value)
+                    .ok_or(SynthesisError::Unsatisfiable)?,
+            ),
+            None => None,
+        };
+
+        let inverse = Self::alloc_nat(
+            cs.namespace(|| "inverse"),
+            inverse.as_ref(),
+            self.limb_width,
+            modulus_nat.limbs.len(),
+        )?;
+        let product = self.mul(cs.namespace(|| "product"), &inverse)?;
+        let one = Self::constant(&[1], self.limb_width, 1);
+        product.enforce_equal_mod(cs.namespace(|| "congruence"), &one, modulus)?;
+
+        Ok(inverse)
+    }
+
+    /// Returns `a` if `condition` is set and `b` otherwise, which costs a constraint per limb.
+    pub fn select<CS>(
+        mut cs: CS,
+        condition: &Boolean,
+        a: &Self,
+        b: &Self,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        a.assert_limb_width(b);
+
+        let n_limbs = a.limbs.len().max(b.limbs.len());
+        let limbs = (0..n_limbs)
+            .map(|i| {
+                let value = match condition.get_value() {
+                    Some(true) => a.limb_value(i),
+                    Some(false) => b.limb_value(i),
+                    None => None,
+                };
+                let num = AllocatedNum::alloc(cs.namespace(|| format!("limb {}", i)), || {
+                    value
+                        .as_ref()
+                        .map(Nat::to_scalar)
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })?;
+
+                // Constrain: (a - b) * condition = selected - b
+                cs.enforce(
+                    || format!("limb {} selection", i),
+                    |_| a.limb_lc(i, CS::one()) - &b.limb_lc(i, CS::one()),
+                    |_| condition.lc(CS::one(), Scalar::ONE),
+                    |lc| lc + num.get_variable() - &b.limb_lc(i, CS::one()),
+                );
+
+                Ok(Limb {
+                    lc: LinearCombination::zero() + num.get_variable(),
+                    constant: Scalar::ZERO,
+                    value,
+                })
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+        Ok(BigNat {
+            limbs,
+            limb_width: a.limb_width,
+            word_bits: a.word_bits.max(b.word_bits),
+            is_constant: false,
+        })
+    }
+
+    /// Decomposes the number into `limb_width` bits per limb, least significant bit first, which
+    /// costs `limb_width + 1` constraints per limb.
+    ///
+    /// The constraints are only satisfied if every limb fits into `limb_width` bits, like the
+    /// limbs of allocated and reduced numbers.
+    pub fn to_bits<CS>(&self, mut cs: CS) -> Result<Vec<Boolean>, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let mut bits = Vec::with_capacity(self.limb_width * self.limbs.len());
+        for (i, limb) in self.limbs.iter().enumerate() {
+            let limb_bits = cmp::decompose(
+                cs.namespace(|| format!("limb {}", i)),
+                limb.value.as_ref().map(Nat::to_scalar),
+                limb.lc(CS::one()),
+                self.limb_width,
+            )?;
+            bits.extend(limb_bits.into_iter().map(Boolean::from));
+        }
+
+        Ok(bits)
+    }
+
+    /// Enforces that the numbers are equal as integers, although their limbs may differ.
+    ///
+    /// The difference of the limbs is carried from limb to limb,
+    /// `a_i - b_i + c_(i - 1) = c_i * 2^limb_width`, with range checked carries and a final
+    /// carry of zero.
+    pub fn enforce_equal<CS>(&self, mut cs: CS, other: &Self) -> Result<(), SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.assert_limb_width(other);
+
+        let n_limbs = self.limbs.len().max(other.limbs.len());
+        let word_bits = self.word_bits.max(other.word_bits);
+        check_word_bits::<Scalar>(word_bits + 1);
+        // |a_i - b_i + c_(i - 1)| < 2^(word_bits + 1), so |c_i| < 2^carry_bits. The carries are
+        // allocated plus 2^carry_bits, which is less than 2^(carry_bits + 1).
+        let carry_bits = word_bits + 1 - self.limb_width;
+        let offset = Nat::one().shl(carry_bits).to_scalar::<Scalar>();
+        let shift = Scalar::from(2).pow_vartime([self.limb_width as u64]);
+
+        // The carry is a sign, set if negative, and a magnitude.
+        let mut carry = Some((false, Nat::default()));
+        let mut carry_lc = LinearCombination::zero();
+        for i in 0..n_limbs {
+            let difference = self.limb_lc(i, CS::one()) - &other.limb_lc(i, CS::one()) + &carry_lc;
+            if i == n_limbs - 1 {
+                cs.enforce(
+                    || "final carry",
+                    |_| difference,
+                    |lc| lc + CS::one(),
+                    |lc| lc,
+                );
+                break;
+            }
+
+            carry = match (carry, self.limb_value(i), other.limb_value(i)) {
+                (Some((negative, carry)), Some(a), Some(b)) => {
+                    let (plus, minus) = if negative {
+                        (a, b.add(&carry))
+                    } else {
+                        (a.add(&carry), b)
+                    };
+                    Some(if plus >= minus {
+                        (false, plus.sub(&minus).shr(self.limb_width))
+                    } else {
+                        (true, minus.sub(&plus).shr(self.limb_width))
+                    })
+                }
+                _ => None,
+            };
+            let shifted = AllocatedNum::alloc(cs.namespace(|| format!("carry {}", i)), || {
+                let (negative, carry) = carry.as_ref().ok_or(SynthesisError::AssignmentMissing)?;
+                let carry = carry.to_scalar::<Scalar>();
+                Ok(if *negative {
+                    offset - carry
+                } else {
+                    offset + carry
+                })
+            })?;
+            cmp::enforce_num_bits(
+                cs.namespace(|| format!("carry {} range", i)),
+                &shifted,
+                carry_bits + 1,
+            )?;
+
+            carry_lc = LinearCombination::zero() + shifted.get_variable() - (offset, CS::one());
+            cs.enforce(
+                || format!("carry {} constraint", i),
+                |_| difference,
+                |lc| lc + CS::one(),
+                |lc| lc + (shift, &carry_lc),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Enforces that the numbers are congruent modulo a constant `modulus`, given in 64-bit
+    /// words, least significant word first, by allocating quotients such that
+    /// `self + q_a * modulus = other + q_b * modulus`.
+    ///
+    /// Panics if the modulus is zero.
+    pub fn enforce_equal_mod<CS>(
+        &self,
+        mut cs: CS,
+        other: &Self,
+        modulus: &[u64],
+    ) -> Result<(), SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.assert_limb_width(other);
+
+        let (modulus, modulus_value) = self.constant_modulus(modulus);
+        let (quotient_a, quotient_b) = match (self.value(), other.value()) {
+            (Some(a), Some(b)) if a >= b => (
+                Some(Nat::default()),
+                Some(a.sub(&b).divrem(&modulus_value).0),
+            ),
+            (Some(a), Some(b)) => (
+                Some(b.sub(&a).divrem(&modulus_value).0),
+                Some(Nat::default()),
+            ),
+            _ => (None, None),
+        };
+
+        let n_limbs = self
+            .quotient_limbs(&modulus_value)
+            .max(other.quotient_limbs(&modulus_value));
+        let quotient_a = Self::alloc_nat(
+            cs.namespace(|| "quotient a"),
+            quotient_a.as_ref(),
+            self.limb_width,
+            n_limbs,
+        )?;
+        let quotient_b = Self::alloc_nat(
+            cs.namespace(|| "quotient b"),
+            quotient_b.as_ref(),
+            self.limb_width,
+            n_limbs,
+        )?;
+        let a = self.add(&quotient_a.mul(cs.namespace(|| "product a"), &modulus)?);
+        let b = other.add(&quotient_b.mul(cs.namespace(|| "product b"), &modulus)?);
+
+        a.enforce_equal(cs.namespace(|| "congruence"), &b)
+    }
+
+    /// Enforces that the number is less than a constant `bound`, given in 64-bit words, least
+    /// significant word first, by allocating `bound - 1 - self` in range checked limbs.
+    ///
+    /// Returns [`SynthesisError::Unsatisfiable`] if the number isn't less. Panics if the bound is
+    /// zero.
+    pub fn enforce_less_than<CS>(&self, mut cs: CS, bound: &[u64]) -> Result<(), SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let bound = Nat::new(bound.to_vec());
+        assert!(!bound.is_zero(), "bound of zero");
+        let max = bound.sub(&Nat::one());
+        let gap = match self.value() {
+            Some(value) if value < bound => Some(max.sub(&value)),
+            Some(_) => return Err(SynthesisError::Unsatisfiable),
+            None => None,
+        };
+
+        let n_limbs = ((max.bits() + self.limb_width - 1) / self.limb_width).max(1);
+        let gap = Self::alloc_nat(
+            cs.namespace(|| "gap"),
+            gap.as_ref(),
+            self.limb_width,
+            n_limbs,
+        )?;
+        let max = Self::constant(&max.0, self.limb_width, n_limbs);
+
+        self.add(&gap).enforce_equal(cs.namespace(|| "sum"), &max)
+    }
+
+    fn value(&self) -> Option<Nat> {
+        self.limbs
+            .iter()
+            .rev()
+            .try_fold(Nat::default(), |value, limb| {
+                Some(value.shl(self.limb_width).add(limb.value.as_ref()?))
+            })
+    }
+
+    fn limb_value(&self, i: usize) -> Option<Nat> {
+        match self.limbs.get(i) {
+            Some(limb) => limb.value.clone(),
+            None => Some(Nat::default()),
+        }
+    }
+
+    fn limb_lc(&self, i: usize, one: Variable) -> LinearCombination<Scalar> {
+        match self.limbs.get(i) {
+            Some(limb) => limb.lc(one),
+            None => LinearCombination::zero(),
+        }
+    }
+
+    /// The linear combination of the limbs as coefficients of a polynomial at `point`.
+    fn eval(&self, point: Scalar, one: Variable) -> LinearCombination<Scalar> {
+        let mut lc = LinearCombination::zero();
+        let mut coeff = Scalar::ONE;
+        for limb in &self.limbs {
+            lc = lc + (coeff, &limb.lc(one));
+            coeff *= point;
+        }
+        lc
+    }
+
+    /// The number is less than `2^max_bits()`.
+    fn max_bits(&self) -> usize {
+        let low_bits = self.limb_width * (self.limbs.len() - 1);
+        if self.word_bits == self.limb_width {
+            low_bits + self.limb_width
+        } else {
+            low_bits + self.word_bits + 1
+        }
+    }
+
+    /// The number of limbs of the quotient of the number by `modulus`.
+    fn quotient_limbs(&self, modulus: &Nat) -> usize {
+        let bits = self.max_bits().saturating_sub(modulus.bits() - 1);
+        ((bits + self.limb_width - 1) / self.limb_width).max(1)
+    }
+
+    fn constant_modulus(&self, modulus: &[u64]) -> (Self, Nat) {
+        let value = Nat::new(modulus.to_vec());
+        assert!(!value.is_zero(), "modulus of zero");
+        let n_limbs = (value.bits() + self.limb_width - 1) / self.limb_width;
+
+        (Self::constant(modulus, self.limb_width, n_limbs), value)
+    }
+
+    fn assert_limb_width(&self, other: &Self) {
+        assert_eq!(
+            self.limb_width, other.limb_width,
+            "numbers with limbs of different widths"
+        );
+    }
+}
+
+impl<Scalar: PrimeField> Limb<Scalar> {
+    fn add(&self, other: &Self) -> Self {
+        Limb {
+            lc: self.lc.clone() + &other.lc,
+            constant: self.constant + other.constant,
+            value: self
+                .value
+                .as_ref()
+                .zip(other.value.as_ref())
+                .map(|(a, b)| a.add(b)),
+        }
+    }
+
+    fn lc(&self, one: Variable) -> LinearCombination<Scalar> {
+        if bool::from(self.constant.is_zero()) {
+            self.lc.clone()
+        } else {
+            self.lc.clone() + (self.constant, one)
+        }
+    }
+}
+
+fn check_word_bits<Scalar: PrimeField>(word_bits: usize) {
+    assert!(
+        word_bits < Scalar::CAPACITY as usize,
+        "limbs of {} bits overflow the field",
+        word_bits
+    );
+}
+
+/// The `n_limbs` limbs of `limb_width` bits of `value`, truncating it if it doesn't fit.
+fn split(value: &Nat, limb_width: usize, n_limbs: usize) -> Vec<Nat> {
+    (0..n_limbs)
+        .map(|i| value.shr(limb_width * i).low_bits(limb_width))
+        .collect()
+}
+
+/// A natural number in 64-bit words, least significant word first, without leading zero words.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Nat(pub(crate) Vec<u64>);
+
+impl Nat {
+    pub(crate) fn new(mut words: Vec<u64>) -> Self {
+        while words.last() == Some(&0) {
+            words.pop();
+        }
+        Nat(words)
+    }
+
+    pub(crate) fn one() -> Self {
+        Nat(vec![1])
+    }
+
+    pub(crate) fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn bits(&self) -> usize {
+        self.0
+            .last()
+            .map_or(0, |top| 64 * self.0.len() - top.leading_zeros() as usize)
+    }
+
+    pub(crate) fn bit(&self, i: usize) -> bool {
+        (self.word(i / 64) >> (i % 64)) & 1 == 1
+    }
+
+    fn word(&self, i: usize) -> u64 {
+        self.0.get(i).copied().unwrap_or(0)
+    }
+
+    pub(crate) fn add(&self, other: &Self) -> Self {
+        let len = self.0.len().max(other.0.len());
+        let mut words = Vec::with_capacity(len + 1);
+        let mut carry = 0u128;
+        for i in 0..len {
+            let sum = self.word(i) as u128 + other.word(i) as u128 + carry;
+            words.push(sum as u64);
+            carry = sum >> 64;
+        }
+        words.push(carry as u64);
+        Nat::new(words)
+    }
+
+    /// Panics if `other` is larger.
+    pub(crate) fn sub(&self, other: &Self) -> Self {
+        assert!(self >= other, "negative difference");
+
+        let mut words = Vec::with_capacity(self.0.len());
+        let mut borrow = false;
+        for (i, word) in self.0.iter().enumerate() {
+            let (difference, borrow_a) = word.overflowing_sub(other.word(i));
+            let (difference, borrow_b) = difference.overflowing_sub(borrow as u64);
+            words.push(difference);
+            borrow = borrow_a || borrow_b;
+        }
+        Nat::new(words)
+    }
+
+    pub(crate) fn mul(&self, other: &Self) -> Self {
+        let mut words = vec![0u64; self.0.len() + other.0.len()];
+        for (i, a) in self.0.iter().enumerate() {
+            let mut carry = 0u128;
+            for (j, b) in other.0.iter().enumerate() {
+                let product = *a as u128 * *b as u128 + words[i + j] as u128 + carry;
+                words[i + j] = product as u64;
+                carry = product >> 64;
+            }
+            words[i + other.0.len()] = carry as u64;
+        }
+        Nat::new(words)
+    }
+
+    fn shl(&self, n: usize) -> Self {
+        if self.is_zero() {
+            return Nat::default();
+        }
+
+        let (words, bits) = (n / 64, n % 64);
+        let mut shifted = vec![0u64; words];
+        let mut carry = 0;
+        for word in &self.0 {
+            shifted.push((word << bits) | carry);
+            carry = if bits == 0 { 0 } else { word >> (64 - bits) };
+        }
+        shifted.push(carry);
+        Nat::new(shifted)
+    }
+
+    fn shr(&self, n: usize) -> Self {
+        let (words, bits) = (n / 64, n % 64);
+        let shifted = (words..self.0.len())
+            .map(|i| {
+                let high = if bits == 0 {
+                    0
+                } else {
+                    self.word(i + 1) << (64 - bits)
+                };
+                (self.0[i] >> bits) | high
+            })
+            .collect();
+        Nat::new(shifted)
+    }
+
+    /// The `n` least significant bits.
+    fn low_bits(&self, n: usize) -> Self {
+        let mut words = self
+            .0
+            .iter()
+            .take((n + 63) / 64)
+            .copied()
+            .collect::<Vec<_>>();
+        if n % 64 != 0 {
+            if let Some(top) = words.get_mut(n / 64) {
+                *top &= (1 << (n % 64)) - 1;
+            }
+        }
+        Nat::new(words)
+    }
+
+    /// Long division, panics if `divisor` is zero.
+    pub(crate) fn divrem(&self, divisor: &Self) -> (Self, Self) {
+        assert!(!divisor.is_zero(), "division by zero");
+
+        let mut quotient = vec![0u64; self.0.len()];
+        let mut remainder = Nat::default();
+        for i in (0..self.bits()).rev() {
+            remainder = remainder.shl(1);
+            if self.bit(i) {
+                remainder = remainder.add(&Nat::one());
+            }
+            if remainder >= *divisor {
+                remainder = remainder.sub(divisor);
+                quotient[i / 64] |= 1 << (i % 64);
+            }
+        }
+        (Nat::new(quotient), remainder)
+    }
+
+    /// The inverse modulo `modulus` by the extended Euclidean algorithm, if there is one. Panics
+    /// if `modulus` is zero.
+    pub(crate) fn inverse_mod(&self, modulus: &Self) -> Option<Self> {
+        // Invariant: r_i = t_i * self modulo the modulus.
+        let (mut r0, mut r1) = (modulus.clone(), self.divrem(modulus).1);
+        let (mut t0, mut t1) = (Nat::default(), Nat::one());
+        while !r1.is_zero() {
+            let (quotient, remainder) = r0.divrem(&r1);
+            let t = t0
+                .add(modulus)
+                .sub(&quotient.mul(&t1).divrem(modulus).1)
+                .divrem(modulus)
+                .1;
+            r0 = std::mem::replace(&mut r1, remainder);
+            t0 = std::mem::replace(&mut t1, t);
+        }
+
+        if r0 == Nat::one() {
+            Some(t0.divrem(modulus).1)
+        } else {
+            None
+        }
+    }
+
+    fn to_scalar<Scalar: PrimeField>(&self) -> Scalar {
+        let base = Scalar::from(u64::MAX) + Scalar::ONE;
+        self.0.iter().rev().fold(Scalar::ZERO, |value, word| {
+            value * base + Scalar::from(*word)
+        })
+    }
+}
+
+impl Ord for Nat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .len()
+            .cmp(&other.0.len())
+            .then_with(|| self.0.iter().rev().cmp(other.0.iter().rev()))
+    }
+}
+
+impl PartialOrd for Nat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+    use ff::Field;
+    use rand_core::{RngCore, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    use crate::gadgets::boolean::AllocatedBit;
+    use crate::gadgets::test::*;
+
+    // 2^256 - 2^32 - 977, the modulus of the base field of secp256k1.
+    const SECP256K1_P: [u64; 4] = [0xffff_fffe_ffff_fc2f, u64::MAX, u64::MAX, u64::MAX];
+
+    #[test]
+    fn test_bignat_small_modulus() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for _ in 0..20 {
+            let modulus = (rng.next_u64() >> (rng.next_u32() % 63)).max(2);
+            let (a, b) = (rng.next_u64(), rng.next_u64());
+            let product = a as u128 * b as u128;
+            let alloc = |cs: &mut TestConstraintSystem<Fr>| {
+                let a_nat = BigNat::alloc(cs.namespace(|| "a"), Some(&[a][..]), 16, 4).unwrap();
+                let b_nat = BigNat::alloc(cs.namespace(|| "b"), Some(&[b][..]), 16, 4).unwrap();
+                (a_nat, b_nat)
+            };
+
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let (a_nat, b_nat) = alloc(&mut cs);
+            assert_eq!(cs.num_constraints(), 2 * 4 * 17);
+
+            let sum = a_nat
+                .add_mod(cs.namespace(|| "add"), &b_nat, &[modulus])
+                .unwrap();
+            let reduced = a_nat
+                .mul_mod(cs.namespace(|| "mul"), &b_nat, &[modulus])
+                .unwrap();
+            assert!(cs.is_satisfied());
+            let sum_value = (a as u128 + b as u128) % modulus as u128;
+            assert_eq!(sum.get_value(), Some(Nat::new(vec![sum_value as u64]).0));
+            let reduced_value = product % modulus as u128;
+            assert_eq!(
+                reduced.get_value(),
+                Some(Nat::new(vec![reduced_value as u64]).0)
+            );
+
+            // The product is congruent to its remainder plus the modulus, but not plus one.
+            for (delta, congruent) in [(modulus as u128, true), (1, false)] {
+                let mut cs = TestConstraintSystem::<Fr>::new();
+                let (a_nat, b_nat) = alloc(&mut cs);
+                let unreduced = a_nat.mul(cs.namespace(|| "product"), &b_nat).unwrap();
+                let other = reduced_value + delta;
+                let other = BigNat::constant(&[other as u64, (other >> 64) as u64], 16, 5);
+                unreduced
+                    .enforce_equal_mod(cs.namespace(|| "congruence"), &other, &[modulus])
+                    .unwrap();
+                assert_eq!(cs.is_satisfied(), congruent);
+            }
+
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let (a_nat, b_nat) = alloc(&mut cs);
+            let unreduced = a_nat.mul(cs.namespace(|| "product"), &b_nat).unwrap();
+            let other = BigNat::constant(&[product as u64, (product >> 64) as u64], 16, 8);
+            unreduced
+                .enforce_equal(cs.namespace(|| "equal"), &other)
+                .unwrap();
+            assert!(cs.is_satisfied());
+            assert_eq!(unreduced.get_value(), other.get_value());
+        }
+
+        // A value doesn't fit into too few limbs.
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        assert!(matches!(
+            BigNat::alloc(cs.namespace(|| "a"), Some(&[1 << 16][..]), 8, 2),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+
+    #[test]
+    fn test_bignat_secp256k1() {
+        let mut cs = TestConstraintSystem::<Fr>::new();
+
+        // (-1)^2 = 1
+        let mut minus_one = SECP256K1_P;
+        minus_one[0] -= 1;
+        let a = BigNat::alloc(cs.namespace(|| "a"), Some(&minus_one[..]), 64, 4).unwrap();
+        let square = a
+            .mul_mod(cs.namespace(|| "square"), &a, &SECP256K1_P)
+            .unwrap();
+        assert_eq!(square.get_value(), Some(vec![1]));
+
+        // 2^255 * 2 = 2^256 = 2^32 + 977
+        let b = BigNat::alloc(cs.namespace(|| "b"), Some(&[0, 0, 0, 1 << 63][..]), 64, 4).unwrap();
+        let two = BigNat::constant(&[2], 64, 1);
+        let double = b
+            .mul_mod(cs.namespace(|| "double"), &two, &SECP256K1_P)
+            .unwrap();
+        assert_eq!(double.get_value(), Some(vec![(1 << 32) + 977]));
+        double
+            .enforce_equal(
+                cs.namespace(|| "equal"),
+                &BigNat::constant(&[(1 << 32) + 977], 64, 1),
+            )
+            .unwrap();
+        b.add(&b)
+            .enforce_equal_mod(cs.namespace(|| "congruence"), &double, &SECP256K1_P)
+            .unwrap();
+        assert!(cs.is_satisfied());
+
+        // A remainder that fits into its limbs but isn't congruent is rejected.
+        cs.set("square/reduce/remainder/limb 0/num", Fr::from(2));
+        cs.set(
+            "square/reduce/remainder/limb 0 range/bit 0/boolean",
+            Fr::ZERO,
+        );
+        cs.set(
+            "square/reduce/remainder/limb 0 range/bit 1/boolean",
+            Fr::ONE,
+        );
+        assert!(cs
+            .which_is_unsatisfied()
+            .unwrap()
+            .starts_with("square/reduce/reduction/"));
+    }
+
+    #[test]
+    fn test_bignat_inverse_select_bits() {
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let modulus = [1_000_003];
+        let a = BigNat::alloc(cs.namespace(|| "a"), Some(&[12345][..]), 8, 3).unwrap();
+        let b = BigNat::alloc(cs.namespace(|| "b"), Some(&[999_999][..]), 8, 3).unwrap();
+
+        let inverse = a.inverse_mod(cs.namespace(|| "inverse"), &modulus).unwrap();
+        let inverse_value = inverse.get_value().unwrap()[0];
+        assert_eq!(12345 * inverse_value % modulus[0], 1);
+
+        for condition in [false, true] {
+            let bit = Boolean::from(
+                AllocatedBit::alloc(
+                    cs.namespace(|| format!("condition {}", condition)),
+                    Some(condition),
+                )
+                .unwrap(),
+            );
+            let selected = BigNat::select(
+                cs.namespace(|| format!("select {}", condition)),
+                &bit,
+                &a,
+                &b,
+            )
+            .unwrap();
+            let expected = if condition { 12345 } else { 999_999 };
+            assert_eq!(selected.get_value(), Some(vec![expected]));
+        }
+
+        let bits = a.to_bits(cs.namespace(|| "bits")).unwrap();
+        assert_eq!(bits.len(), 24);
+        for (i, bit) in bits.iter().enumerate() {
+            assert_eq!(bit.get_value(), Some((12345 >> i) & 1 == 1));
+        }
+
+        a.enforce_less_than(cs.namespace(|| "less than"), &[12346])
+            .unwrap();
+        assert!(cs.is_satisfied());
+
+        assert!(matches!(
+            a.enforce_less_than(cs.namespace(|| "not less than"), &[12345]),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+        let zero = BigNat::alloc(cs.namespace(|| "zero"), Some(&[][..]), 8, 3).unwrap();
+        assert!(matches!(
+            zero.inverse_mod(cs.namespace(|| "no inverse"), &modulus),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+}
@@ -4,6 +4,7 @@ use ff::{PrimeField, PrimeFieldBits};
 
 use crate::{ConstraintSystem, LinearCombination, SynthesisError, Variable};
 
+use super::num::is_zero_lc;
 use super::Assignment;
 
 /// Represents a variable in the constraint system which is guaranteed
@@ -23,6 +24,12 @@ impl AllocatedBit {
         self.variable
     }
 
+    /// Wraps a variable which is already constrained to be either zero
+    /// or one by other constraints, without adding a boolean constraint.
+    pub(crate) fn from_constrained(variable: Variable, value: Option<bool>) -> Self {
+        AllocatedBit { variable, value }
+    }
+
     /// Allocate a variable in the constraint system which can only be a
     /// boolean value. Further, constrain that the boolean is false
     /// unless the condition is false.
@@ -513,6 +520,164 @@ impl Boolean {
         }
     }
 
+    /// Perform AND over any number of operands, which is true if there
+    /// are none.
+    ///
+    /// More than two non-constant operands cost 2 constraints: they are all
+    /// true iff their count minus their sum is zero, which can't wrap around
+    /// the modulus.
+    pub fn and_many<Scalar, CS>(cs: CS, operands: &[Self]) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        if operands
+            .iter()
+            .any(|b| matches!(b, Boolean::Constant(false)))
+        {
+            return Ok(Boolean::Constant(false));
+        }
+        let operands = operands
+            .iter()
+            .filter(|b| !b.is_constant())
+            .collect::<Vec<_>>();
+
+        match operands[..] {
+            [] => Ok(Boolean::Constant(true)),
+            [a] => Ok(a.clone()),
+            [a, b] => Boolean::and(cs, a, b),
+            _ => {
+                let value = operands.iter().try_fold(Scalar::ZERO, |acc, b| {
+                    b.get_value()
+                        .map(|b| if b { acc } else { acc + Scalar::ONE })
+                });
+                let lc = operands.iter().fold(LinearCombination::zero(), |lc, b| {
+                    lc + (Scalar::ONE, CS::one()) - &b.lc(CS::one(), Scalar::ONE)
+                });
+                is_zero_lc(cs, value, lc)
+            }
+        }
+    }
+
+    /// Perform OR over any number of operands, which is false if there
+    /// are none.
+    ///
+    /// More than two non-constant operands cost 2 constraints: they are all
+    /// false iff their sum is zero.
+    pub fn or_many<Scalar, CS>(cs: CS, operands: &[Self]) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        if operands
+            .iter()
+            .any(|b| matches!(b, Boolean::Constant(true)))
+        {
+            return Ok(Boolean::Constant(true));
+        }
+        let operands = operands
+            .iter()
+            .filter(|b| !b.is_constant())
+            .collect::<Vec<_>>();
+
+        match operands[..] {
+            [] => Ok(Boolean::Constant(false)),
+            [a] => Ok(a.clone()),
+            // a OR b = NOT((NOT a) AND (NOT b))
+            [a, b] => Ok(Boolean::and(cs, &a.not(), &b.not())?.not()),
+            _ => {
+                let value = operands.iter().try_fold(Scalar::ZERO, |acc, b| {
+                    b.get_value()
+                        .map(|b| if b { acc + Scalar::ONE } else { acc })
+                });
+                let lc = operands.iter().fold(LinearCombination::zero(), |lc, b| {
+                    lc + &b.lc(CS::one(), Scalar::ONE)
+                });
+                Ok(is_zero_lc(cs, value, lc)?.not())
+            }
+        }
+    }
+
+    /// Perform XOR over any number of operands, which is false if there
+    /// are none.
+    ///
+    /// `n` non-constant operands cost the `n - 1` constraints of a balanced
+    /// tree of XORs, or `ceil(log2(n + 1)) + 1` constraints to decompose
+    /// their sum into bits, the least significant of which is the result,
+    /// whichever is less.
+    pub fn xor_many<Scalar, CS>(mut cs: CS, operands: &[Self]) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let parity = operands
+            .iter()
+            .filter(|b| matches!(b, Boolean::Constant(true)))
+            .count()
+            % 2
+            == 1;
+        let operands = operands
+            .iter()
+            .filter(|b| !b.is_constant())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let num_sum_bits = (usize::BITS - operands.len().leading_zeros()) as usize;
+        let result = if operands.len() <= num_sum_bits + 2 {
+            let mut level = 0;
+            let mut operands = operands;
+            while operands.len() > 1 {
+                let mut cs = cs.namespace(|| format!("level {}", level));
+                operands = operands
+                    .chunks(2)
+                    .enumerate()
+                    .map(|(i, pair)| match pair {
+                        [a, b] => Boolean::xor(cs.namespace(|| format!("xor {}", i)), a, b),
+                        [a] => Ok(a.clone()),
+                        _ => unreachable!(),
+                    })
+                    .collect::<Result<_, _>>()?;
+                level += 1;
+            }
+            operands.pop().unwrap_or(Boolean::Constant(false))
+        } else {
+            let sum = operands
+                .iter()
+                .try_fold(0usize, |acc, b| b.get_value().map(|b| acc + b as usize));
+            let bits = (0..num_sum_bits)
+                .map(|i| {
+                    AllocatedBit::alloc(
+                        cs.namespace(|| format!("sum bit {}", i)),
+                        sum.map(|sum| (sum >> i) & 1 == 1),
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // The sum is less than 2^num_sum_bits, which is far below the
+            // modulus, so its bits are unique.
+            let mut packed = LinearCombination::zero();
+            let mut coeff = Scalar::ONE;
+            for bit in &bits {
+                packed = packed + (coeff, bit.get_variable());
+                coeff = coeff.double();
+            }
+            cs.enforce(
+                || "sum constraint",
+                |_| {
+                    operands.iter().fold(LinearCombination::zero(), |lc, b| {
+                        lc + &b.lc(CS::one(), Scalar::ONE)
+                    })
+                },
+                |lc| lc + CS::one(),
+                |_| packed,
+            );
+
+            Boolean::Is(bits[0].clone())
+        };
+
+        Ok(if parity { result.not() } else { result })
+    }
+
     /// Computes (a and b) xor ((not a) and c)
     pub fn sha256_ch<'a, Scalar, CS>(
         mut cs: CS,
@@ -1525,6 +1690,115 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_boolean_reductions() {
+        use rand_core::{RngCore, SeedableRng};
+        use rand_xorshift::XorShiftRng;
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        type Gadget = fn(&mut TestConstraintSystem<Fr>, &[Boolean]) -> Boolean;
+        type Native = fn(&[bool]) -> bool;
+        let ops: [(Gadget, Native); 3] = [
+            (
+                |cs, operands| Boolean::and_many(cs, operands).unwrap(),
+                |values| values.iter().all(|v| *v),
+            ),
+            (
+                |cs, operands| Boolean::or_many(cs, operands).unwrap(),
+                |values| values.iter().any(|v| *v),
+            ),
+            (
+                |cs, operands| Boolean::xor_many(cs, operands).unwrap(),
+                |values| values.iter().filter(|v| **v).count() % 2 == 1,
+            ),
+        ];
+
+        for num_operands in 0..20 {
+            for _ in 0..20 {
+                for (op_index, (gadget, native)) in ops.iter().enumerate() {
+                    let mut cs = TestConstraintSystem::<Fr>::new();
+
+                    // Mostly allocated operands, and all true ones for AND.
+                    let operands = (0..num_operands)
+                        .map(|i| {
+                            let value = op_index == 0 || rng.next_u32() % 2 == 0;
+                            match rng.next_u32() % 8 {
+                                0 => Boolean::constant(value),
+                                1 => Boolean::from(
+                                    AllocatedBit::alloc(
+                                        cs.namespace(|| format!("operand {}", i)),
+                                        Some(!value),
+                                    )
+                                    .unwrap(),
+                                )
+                                .not(),
+                                _ => Boolean::from(
+                                    AllocatedBit::alloc(
+                                        cs.namespace(|| format!("operand {}", i)),
+                                        Some(value),
+                                    )
+                                    .unwrap(),
+                                ),
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    let values = operands
+                        .iter()
+                        .map(|b| b.get_value().unwrap())
+                        .collect::<Vec<_>>();
+                    let num_allocated = operands.iter().filter(|b| !b.is_constant()).count();
+
+                    let before = cs.num_constraints();
+                    let result = gadget(&mut cs, &operands);
+                    let cost = cs.num_constraints() - before;
+
+                    assert!(cs.is_satisfied());
+                    assert_eq!(result.get_value(), Some(native(&values)));
+                    assert!(cost <= num_allocated.saturating_sub(1).max(2));
+                }
+            }
+        }
+
+        // A result that contradicts the operands isn't satisfiable.
+        {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let operands = (0..4)
+                .map(|i| {
+                    Boolean::from(
+                        AllocatedBit::alloc(cs.namespace(|| format!("operand {}", i)), Some(true))
+                            .unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let result = Boolean::and_many(cs.namespace(|| "and"), &operands).unwrap();
+            assert_eq!(result.get_value(), Some(true));
+            assert!(cs.is_satisfied());
+            cs.set("and/is zero", Fr::ZERO);
+            assert!(!cs.is_satisfied());
+        }
+        {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let operands = (0..8)
+                .map(|i| {
+                    Boolean::from(
+                        AllocatedBit::alloc(cs.namespace(|| format!("operand {}", i)), Some(true))
+                            .unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let result = Boolean::xor_many(cs.namespace(|| "xor"), &operands).unwrap();
+            assert_eq!(result.get_value(), Some(false));
+            assert_eq!(cs.num_constraints(), 8 + 5);
+            assert!(cs.is_satisfied());
+            cs.set("xor/sum bit 0/boolean", Fr::ONE);
+            assert!(!cs.is_satisfied());
+        }
+    }
+
     #[allow(clippy::identity_op)]
     #[test]
     fn test_u64_into_boolean_vec_le() {
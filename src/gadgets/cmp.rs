@@ -0,0 +1,275 @@
+//! Gadgets comparing numbers in the scalar field as integers.
+//!
+//! The comparisons decompose a difference of the operands into bits, which only proves the
+//! ordering if the operands are known to be less than `2^num_bits`, e.g. because they are
+//! checked with [`enforce_num_bits`] or are composed of that many bits. `num_bits` must be less
+//! than the capacity of the field, so that the differences don't wrap around the modulus.
+//!
+//! Enforcing an ordering costs `num_bits + 1` constraints, computing it as a [`Boolean`] costs
+//! `num_bits + 2`.
+
+use ff::{PrimeField, PrimeFieldBits};
+
+use crate::{ConstraintSystem, LinearCombination, SynthesisError};
+
+use super::boolean::{AllocatedBit, Boolean};
+use super::num::AllocatedNum;
+
+/// Enforces that `a < 2^num_bits`.
+pub fn enforce_num_bits<Scalar, CS>(
+    cs: CS,
+    a: &AllocatedNum<Scalar>,
+    num_bits: usize,
+) -> Result<(), SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: ConstraintSystem<Scalar>,
+{
+    decompose(
+        cs,
+        a.get_value(),
+        LinearCombination::zero() + a.get_variable(),
+        num_bits,
+    )?;
+    Ok(())
+}
+
+/// Enforces that `a < b`, given that both are less than `2^num_bits`.
+pub fn enforce_less_than<Scalar, CS>(
+    cs: CS,
+    a: &AllocatedNum<Scalar>,
+    b: &AllocatedNum<Scalar>,
+    num_bits: usize,
+) -> Result<(), SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: ConstraintSystem<Scalar>,
+{
+    // a < b iff b - a - 1 is in [0, 2^num_bits).
+    let value = a
+        .get_value()
+        .zip(b.get_value())
+        .map(|(a, b)| b - a - Scalar::ONE);
+    let lc = LinearCombination::zero() + b.get_variable() - a.get_variable() - CS::one();
+    decompose(cs, value, lc, num_bits)?;
+    Ok(())
+}
+
+/// Enforces that `a <= b`, given that both are less than `2^num_bits`.
+pub fn enforce_less_or_equal<Scalar, CS>(
+    cs: CS,
+    a: &AllocatedNum<Scalar>,
+    b: &AllocatedNum<Scalar>,
+    num_bits: usize,
+) -> Result<(), SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: ConstraintSystem<Scalar>,
+{
+    // a <= b iff b - a is in [0, 2^num_bits).
+    let value = a.get_value().zip(b.get_value()).map(|(a, b)| b - a);
+    let lc = LinearCombination::zero() + b.get_variable() - a.get_variable();
+    decompose(cs, value, lc, num_bits)?;
+    Ok(())
+}
+
+/// Enforces that `a < bound`, given that `a` is less than `2^num_bits`. Costs no constraints if
+/// the bound is larger than `2^num_bits`.
+pub fn enforce_less_than_constant<Scalar, CS>(
+    cs: CS,
+    a: &AllocatedNum<Scalar>,
+    bound: Scalar,
+    num_bits: usize,
+) -> Result<(), SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: ConstraintSystem<Scalar>,
+{
+    assert_num_bits::<Scalar>(num_bits);
+    if bool::from(bound.is_zero()) {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    let max = bound - Scalar::ONE;
+    if max.to_le_bits().iter().skip(num_bits).any(|bit| *bit) {
+        return Ok(());
+    }
+
+    // a < bound iff bound - 1 - a is in [0, 2^num_bits).
+    let value = a.get_value().map(|a| max - a);
+    let lc = LinearCombination::zero() + (max, CS::one()) - a.get_variable();
+    decompose(cs, value, lc, num_bits)?;
+    Ok(())
+}
+
+/// Computes whether `a < b`, given that both are less than `2^num_bits`.
+pub fn less_than<Scalar, CS>(
+    cs: CS,
+    a: &AllocatedNum<Scalar>,
+    b: &AllocatedNum<Scalar>,
+    num_bits: usize,
+) -> Result<Boolean, SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: ConstraintSystem<Scalar>,
+{
+    // 2^num_bits + a - b is in [1, 2^(num_bits + 1)), its top bit is set iff a >= b.
+    let offset = Scalar::from(2).pow_vartime([num_bits as u64]);
+    let value = a
+        .get_value()
+        .zip(b.get_value())
+        .map(|(a, b)| offset + a - b);
+    let lc = LinearCombination::zero() + (offset, CS::one()) + a.get_variable() - b.get_variable();
+    let bits = decompose(cs, value, lc, num_bits + 1)?;
+
+    Ok(Boolean::from(bits[num_bits].clone()).not())
+}
+
+/// Computes whether `a <= b`, given that both are less than `2^num_bits`.
+pub fn less_or_equal<Scalar, CS>(
+    cs: CS,
+    a: &AllocatedNum<Scalar>,
+    b: &AllocatedNum<Scalar>,
+    num_bits: usize,
+) -> Result<Boolean, SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: ConstraintSystem<Scalar>,
+{
+    Ok(less_than(cs, b, a, num_bits)?.not())
+}
+
+fn assert_num_bits<Scalar: PrimeField>(num_bits: usize) {
+    assert!(
+        num_bits < Scalar::CAPACITY as usize,
+        "comparison of {}-bit numbers",
+        num_bits
+    );
+}
+
+/// Allocates the `num_bits` least significant bits of `value` and enforces that they compose
+/// `lc`, which proves that `lc` is in `[0, 2^num_bits)`.
+pub(crate) fn decompose<Scalar, CS>(
+    mut cs: CS,
+    value: Option<Scalar>,
+    lc: LinearCombination<Scalar>,
+    num_bits: usize,
+) -> Result<Vec<AllocatedBit>, SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: ConstraintSystem<Scalar>,
+{
+    assert_num_bits::<Scalar>(num_bits);
+
+    let values = match value {
+        Some(value) => value
+            .to_le_bits()
+            .into_iter()
+            .take(num_bits)
+            .map(Some)
+            .collect(),
+        None => vec![None; num_bits],
+    };
+    let bits = values
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| AllocatedBit::alloc(cs.namespace(|| format!("bit {}", i)), value))
+        .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+    let mut packed = LinearCombination::zero();
+    let mut coeff = Scalar::ONE;
+    for bit in &bits {
+        packed = packed + (coeff, bit.get_variable());
+        coeff = coeff.double();
+    }
+    cs.enforce(
+        || "packing constraint",
+        |_| packed,
+        |lc| lc + CS::one(),
+        |_| lc,
+    );
+
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+    use ff::Field;
+    use rand_core::{RngCore, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    use crate::gadgets::test::*;
+
+    #[test]
+    fn test_cmp() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let mut pairs = vec![(0, 0), (0, 1), (1, 0), (0xffff, 0xffff), (0xfffe, 0xffff)];
+        pairs.extend((0..20).map(|_| (rng.next_u32() as u16, rng.next_u32() as u16)));
+        for (a, b) in pairs {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let a_num =
+                AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Fr::from(a as u64))).unwrap();
+            let b_num =
+                AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(Fr::from(b as u64))).unwrap();
+
+            let lt = less_than(cs.namespace(|| "lt"), &a_num, &b_num, 16).unwrap();
+            let le = less_or_equal(cs.namespace(|| "le"), &a_num, &b_num, 16).unwrap();
+            assert_eq!(lt.get_value(), Some(a < b));
+            assert_eq!(le.get_value(), Some(a <= b));
+            assert!(cs.is_satisfied());
+            assert_eq!(cs.num_constraints(), 2 * 18);
+
+            enforce_less_than(cs.namespace(|| "enforce lt"), &a_num, &b_num, 16).unwrap();
+            assert_eq!(cs.is_satisfied(), a < b);
+            assert_eq!(cs.num_constraints(), 2 * 18 + 17);
+
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let a_num =
+                AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Fr::from(a as u64))).unwrap();
+            let b_num =
+                AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(Fr::from(b as u64))).unwrap();
+            enforce_less_or_equal(cs.namespace(|| "enforce le"), &a_num, &b_num, 16).unwrap();
+            assert_eq!(cs.is_satisfied(), a <= b);
+
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let a_num =
+                AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Fr::from(a as u64))).unwrap();
+            let bound = Fr::from(b as u64);
+            match enforce_less_than_constant(cs.namespace(|| "bound"), &a_num, bound, 16) {
+                Ok(()) => assert_eq!(cs.is_satisfied(), a < b),
+                // Nothing is less than zero.
+                Err(SynthesisError::Unsatisfiable) => assert_eq!(b, 0),
+                Err(e) => panic!("unexpected error: {}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_cmp_bounds() {
+        // A number that isn't less than 2^num_bits doesn't fit.
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let a = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Fr::from(256))).unwrap();
+        enforce_num_bits(cs.namespace(|| "8 bits"), &a, 8).unwrap();
+        assert!(!cs.is_satisfied());
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let a = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(-Fr::ONE)).unwrap();
+        enforce_num_bits(cs.namespace(|| "8 bits"), &a, 8).unwrap();
+        assert!(!cs.is_satisfied());
+
+        // Bounds beyond 2^num_bits hold for every number of that many bits.
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let a = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Fr::from(255))).unwrap();
+        enforce_less_than_constant(cs.namespace(|| "bound"), &a, Fr::from(257), 8).unwrap();
+        assert_eq!(cs.num_constraints(), 0);
+        enforce_less_than_constant(cs.namespace(|| "tight bound"), &a, Fr::from(256), 8).unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 9);
+    }
+}
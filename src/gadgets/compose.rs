@@ -0,0 +1,244 @@
+//! Combinators for building circuits out of smaller sub-circuits.
+//!
+//! Sub-circuits implement [`WiredCircuit`]: they receive the wires they are
+//! connected to and return the wires they declare for later sub-circuits.
+//! [`Sequence`] and [`Replicate`] are sub-circuits themselves, so they can be
+//! nested, and together with [`Expose`] they also implement [`Circuit`].
+
+use ff::PrimeField;
+
+use super::num::AllocatedNum;
+use crate::{Circuit, ConstraintSystem, SynthesisError};
+
+/// A sub-circuit that is connected to other sub-circuits through wires.
+pub trait WiredCircuit<Scalar: PrimeField> {
+    /// Synthesize the sub-circuit on top of the `inputs` wires, returning the
+    /// wires it declares as its outputs.
+    fn synthesize_wired<CS: ConstraintSystem<Scalar>>(
+        self,
+        cs: &mut CS,
+        inputs: &[AllocatedNum<Scalar>],
+    ) -> Result<Vec<AllocatedNum<Scalar>>, SynthesisError>;
+}
+
+/// Runs `first` and then `second` on the outputs of `first`.
+///
+/// The sub-circuits are synthesized in the namespaces `first` and `second`,
+/// the outputs are those of `second`.
+#[derive(Clone, Debug)]
+pub struct Sequence<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> Sequence<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Sequence { first, second }
+    }
+}
+
+impl<Scalar, A, B> WiredCircuit<Scalar> for Sequence<A, B>
+where
+    Scalar: PrimeField,
+    A: WiredCircuit<Scalar>,
+    B: WiredCircuit<Scalar>,
+{
+    fn synthesize_wired<CS: ConstraintSystem<Scalar>>(
+        self,
+        cs: &mut CS,
+        inputs: &[AllocatedNum<Scalar>],
+    ) -> Result<Vec<AllocatedNum<Scalar>>, SynthesisError> {
+        let shared = self
+            .first
+            .synthesize_wired(&mut cs.namespace(|| "first"), inputs)?;
+        self.second
+            .synthesize_wired(&mut cs.namespace(|| "second"), &shared)
+    }
+}
+
+impl<Scalar, A, B> Circuit<Scalar> for Sequence<A, B>
+where
+    Scalar: PrimeField,
+    A: WiredCircuit<Scalar>,
+    B: WiredCircuit<Scalar>,
+{
+    fn synthesize<CS: ConstraintSystem<Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        self.synthesize_wired(cs, &[])?;
+        Ok(())
+    }
+}
+
+/// Runs one instance of a sub-circuit per equally sized chunk of the inputs.
+///
+/// Instance `i` is synthesized in the namespace `instance {i}` on the `i`-th
+/// chunk, the outputs are the concatenated outputs of all instances.
+#[derive(Clone, Debug)]
+pub struct Replicate<C> {
+    instances: Vec<C>,
+}
+
+impl<C> Replicate<C> {
+    /// Usually the instances only differ in their witness.
+    pub fn new(instances: Vec<C>) -> Self {
+        Replicate { instances }
+    }
+}
+
+impl<Scalar, C> WiredCircuit<Scalar> for Replicate<C>
+where
+    Scalar: PrimeField,
+    C: WiredCircuit<Scalar>,
+{
+    fn synthesize_wired<CS: ConstraintSystem<Scalar>>(
+        self,
+        cs: &mut CS,
+        inputs: &[AllocatedNum<Scalar>],
+    ) -> Result<Vec<AllocatedNum<Scalar>>, SynthesisError> {
+        if self.instances.is_empty() {
+            assert!(inputs.is_empty(), "inputs without instances");
+            return Ok(vec![]);
+        }
+        assert_eq!(
+            inputs.len() % self.instances.len(),
+            0,
+            "inputs can't be split evenly among the instances"
+        );
+        let chunk_size = inputs.len() / self.instances.len();
+
+        let mut outputs = vec![];
+        for (i, instance) in self.instances.into_iter().enumerate() {
+            let chunk = &inputs[i * chunk_size..(i + 1) * chunk_size];
+            outputs.extend(
+                instance
+                    .synthesize_wired(&mut cs.namespace(|| format!("instance {}", i)), chunk)?,
+            );
+        }
+
+        Ok(outputs)
+    }
+}
+
+impl<Scalar, C> Circuit<Scalar> for Replicate<C>
+where
+    Scalar: PrimeField,
+    C: WiredCircuit<Scalar>,
+{
+    fn synthesize<CS: ConstraintSystem<Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        self.synthesize_wired(cs, &[])?;
+        Ok(())
+    }
+}
+
+/// Makes the selected outputs of a sub-circuit public inputs.
+///
+/// The outputs are inputized in the order of `outputs`, in the namespaces
+/// `output {i}` where `i` is the index of the output.
+#[derive(Clone, Debug)]
+pub struct Expose<C> {
+    circuit: C,
+    outputs: Vec<usize>,
+}
+
+impl<C> Expose<C> {
+    pub fn new(circuit: C, outputs: Vec<usize>) -> Self {
+        Expose { circuit, outputs }
+    }
+}
+
+impl<Scalar, C> WiredCircuit<Scalar> for Expose<C>
+where
+    Scalar: PrimeField,
+    C: WiredCircuit<Scalar>,
+{
+    fn synthesize_wired<CS: ConstraintSystem<Scalar>>(
+        self,
+        cs: &mut CS,
+        inputs: &[AllocatedNum<Scalar>],
+    ) -> Result<Vec<AllocatedNum<Scalar>>, SynthesisError> {
+        let outputs = self.circuit.synthesize_wired(cs, inputs)?;
+        for &i in &self.outputs {
+            outputs[i].inputize(cs.namespace(|| format!("output {}", i)))?;
+        }
+
+        Ok(outputs)
+    }
+}
+
+impl<Scalar, C> Circuit<Scalar> for Expose<C>
+where
+    Scalar: PrimeField,
+    C: WiredCircuit<Scalar>,
+{
+    fn synthesize<CS: ConstraintSystem<Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        self.synthesize_wired(cs, &[])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use blstrs::Scalar as Fr;
+    use ff::Field;
+
+    use super::{Expose, Replicate, Sequence, WiredCircuit};
+    use crate::gadgets::num::AllocatedNum;
+    use crate::gadgets::test::*;
+    use crate::{Circuit, ConstraintSystem, SynthesisError};
+
+    /// Declares its witness as outputs.
+    struct Witness(Vec<Fr>);
+
+    impl WiredCircuit<Fr> for Witness {
+        fn synthesize_wired<CS: ConstraintSystem<Fr>>(
+            self,
+            cs: &mut CS,
+            _inputs: &[AllocatedNum<Fr>],
+        ) -> Result<Vec<AllocatedNum<Fr>>, SynthesisError> {
+            self.0
+                .iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    AllocatedNum::alloc(cs.namespace(|| format!("{}", i)), || Ok(*value))
+                })
+                .collect()
+        }
+    }
+
+    /// Outputs the product of its two inputs.
+    struct Mul;
+
+    impl WiredCircuit<Fr> for Mul {
+        fn synthesize_wired<CS: ConstraintSystem<Fr>>(
+            self,
+            cs: &mut CS,
+            inputs: &[AllocatedNum<Fr>],
+        ) -> Result<Vec<AllocatedNum<Fr>>, SynthesisError> {
+            Ok(vec![inputs[0].mul(cs.namespace(|| "mul"), &inputs[1])?])
+        }
+    }
+
+    #[test]
+    fn test_compose() {
+        let values = (1..=6).map(Fr::from).collect::<Vec<_>>();
+        let circuit = Expose::new(
+            Sequence::new(Witness(values), Replicate::new(vec![Mul, Mul, Mul])),
+            vec![2, 0],
+        );
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        circuit.synthesize(&mut cs).unwrap();
+
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 5);
+        assert_eq!(cs.num_inputs(), 3);
+        assert_eq!(cs.get_input(1, "output 2/input variable"), Fr::from(30));
+        assert_eq!(cs.get_input(2, "output 0/input variable"), Fr::from(2));
+
+        // The instances share the wires of the witness.
+        cs.set("first/3/num", Fr::ZERO);
+        assert_eq!(
+            cs.which_is_unsatisfied(),
+            Some("second/instance 1/mul/multiplication constraint")
+        );
+    }
+}
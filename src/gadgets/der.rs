@@ -0,0 +1,578 @@
+//! Gadgets for parsing fixed-schema [DER] structures, such as X.509
+//! certificates, over bytes.
+//!
+//! The schema of a structure, i.e. the tags and lengths of the elements on
+//! the way to the fields of interest, is fixed when the circuit is built.
+//! Parsing a witness then amounts to enforcing that its headers match the
+//! schema, after which the fields are at known offsets. Only structures that
+//! share the schema, e.g. certificates of the same issuer with keys of the
+//! same size, can be parsed by the same circuit.
+//!
+//! [DER]: https://www.itu.int/rec/T-REC-X.690
+
+use std::ops::Range;
+
+use ff::PrimeField;
+
+use super::boolean::Boolean;
+use super::uint8::UInt8;
+use crate::{ConstraintSystem, SynthesisError};
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_SEQUENCE: u8 = 0x30;
+/// The explicit `[0]` tag of the version of a certificate.
+const TAG_VERSION: u8 = 0xa0;
+
+/// The DER encoded object identifier `rsaEncryption`.
+const OID_RSA_ENCRYPTION: &[u8] = &[
+    0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01,
+];
+/// The DER encoded object identifier `id-ecPublicKey`.
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+
+/// The header of a DER element, its tag and the length of its contents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DerHeader {
+    pub tag: u8,
+    pub len: usize,
+}
+
+impl DerHeader {
+    /// Parses the header at the start of `der`, returns it together with its
+    /// encoded length.
+    ///
+    /// Returns `None` if the header is not valid DER, uses a multi-byte tag,
+    /// or if the contents don't fit into `der`.
+    pub fn parse(der: &[u8]) -> Option<(Self, usize)> {
+        let (&tag, rest) = der.split_first()?;
+        if tag & 0x1f == 0x1f {
+            return None;
+        }
+
+        let (&first, rest) = rest.split_first()?;
+        let (len, len_len) = if first < 0x80 {
+            (first as usize, 0)
+        } else {
+            let len_len = (first & 0x7f) as usize;
+            if len_len == 0 || len_len > std::mem::size_of::<usize>() || rest.len() < len_len {
+                return None;
+            }
+            let len = rest[..len_len]
+                .iter()
+                .fold(0, |len, &byte| (len << 8) | byte as usize);
+            // DER requires the shortest encoding of the length.
+            if rest[0] == 0 || len < 0x80 {
+                return None;
+            }
+            (len, len_len)
+        };
+
+        let header_len = 2 + len_len;
+        if der.len() - header_len < len {
+            return None;
+        }
+
+        Some((DerHeader { tag, len }, header_len))
+    }
+
+    /// Encodes the header.
+    pub fn to_der(&self) -> Vec<u8> {
+        let mut der = vec![self.tag];
+        if self.len < 0x80 {
+            der.push(self.len as u8);
+        } else {
+            let len = self.len.to_be_bytes();
+            let skip = len.iter().take_while(|&&byte| byte == 0).count();
+            der.push(0x80 | (len.len() - skip) as u8);
+            der.extend_from_slice(&len[skip..]);
+        }
+        der
+    }
+}
+
+/// Enforces that `bytes` start with a DER element with the given `header`.
+/// Returns the contents of the element and the bytes following it.
+///
+/// # Panics
+///
+/// Panics if `bytes` is too short to hold the element.
+pub fn der_element<'a, Scalar, CS>(
+    cs: CS,
+    bytes: &'a [UInt8],
+    header: &DerHeader,
+) -> Result<(&'a [UInt8], &'a [UInt8]), SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    let encoded = header.to_der();
+    assert!(bytes.len() >= encoded.len() + header.len);
+
+    enforce_constant_bytes(cs, &bytes[..encoded.len()], &encoded)?;
+    Ok(bytes[encoded.len()..].split_at(header.len))
+}
+
+/// Enforces that `bytes` are equal to the constant `expected`.
+fn enforce_constant_bytes<Scalar, CS>(
+    mut cs: CS,
+    bytes: &[UInt8],
+    expected: &[u8],
+) -> Result<(), SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    assert_eq!(bytes.len(), expected.len());
+
+    for (i, (byte, &expected)) in bytes.iter().zip(expected).enumerate() {
+        let mut cs = cs.namespace(|| format!("byte {}", i));
+        let bits = byte.clone().into_bits_be();
+        for (j, bit) in bits.iter().enumerate() {
+            let expected = Boolean::constant((expected >> (7 - j)) & 1 == 1);
+            Boolean::enforce_equal(cs.namespace(|| format!("bit {}", j)), bit, &expected)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Walks over a template structure and records the bytes every structure
+/// with the same schema has in common with it.
+struct Walker<'a> {
+    der: &'a [u8],
+    offset: usize,
+    /// The offsets and values of the constant bytes.
+    constants: Vec<(usize, Vec<u8>)>,
+}
+
+impl<'a> Walker<'a> {
+    /// Enters the element at the current offset, returns the range of its
+    /// contents.
+    fn enter(&mut self, tag: Option<u8>) -> Option<Range<usize>> {
+        let (header, header_len) = DerHeader::parse(&self.der[self.offset..])?;
+        if tag.map_or(false, |tag| tag != header.tag) {
+            return None;
+        }
+        self.constant(header_len)?;
+        Some(self.offset..self.offset + header.len)
+    }
+
+    /// Skips the element at the current offset, only its header is constant.
+    fn skip(&mut self) -> Option<Range<usize>> {
+        let start = self.offset;
+        let contents = self.enter(None)?;
+        self.offset = contents.end;
+        Some(start..contents.end)
+    }
+
+    /// Marks the whole element at the current offset as constant, returns its
+    /// range.
+    fn constant_element(&mut self, tag: u8) -> Option<Range<usize>> {
+        let (header, header_len) = DerHeader::parse(&self.der[self.offset..])?;
+        if header.tag != tag {
+            return None;
+        }
+        let start = self.offset;
+        self.constant(header_len + header.len)?;
+        Some(start..self.offset)
+    }
+
+    /// Marks the next `len` bytes as constant.
+    fn constant(&mut self, len: usize) -> Option<()> {
+        let bytes = self.der.get(self.offset..self.offset + len)?;
+        self.constants.push((self.offset, bytes.to_vec()));
+        self.offset += len;
+        Some(())
+    }
+
+    /// Skips the next `len` bytes, which may differ between structures.
+    fn take(&mut self, len: usize) -> Option<Range<usize>> {
+        if self.der.len() - self.offset < len {
+            return None;
+        }
+        self.offset += len;
+        Some(self.offset - len..self.offset)
+    }
+
+    /// Checks that the current offset is at the end of the element with the
+    /// given contents.
+    fn end(&self, contents: &Range<usize>) -> Option<()> {
+        (self.offset == contents.end).then_some(())
+    }
+}
+
+/// The layout of the public key of a certificate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PublicKeyLayout {
+    Rsa {
+        modulus: Range<usize>,
+        exponent: Range<usize>,
+    },
+    Ec {
+        x: Range<usize>,
+        y: Range<usize>,
+    },
+}
+
+/// The schema of X.509 certificates, derived from a template certificate.
+///
+/// Certificates that match the template in the headers of the fields of the
+/// to-be-signed certificate, in the public key and signature algorithms and
+/// in the lengths of the public key and the signature share the layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CertificateLayout {
+    len: usize,
+    constants: Vec<(usize, Vec<u8>)>,
+    tbs: Range<usize>,
+    signature_algorithm: Range<usize>,
+    signature: Range<usize>,
+    public_key: PublicKeyLayout,
+}
+
+impl CertificateLayout {
+    /// Derives the layout from the DER encoded `template` certificate.
+    ///
+    /// Returns `None` if the template is malformed or if its public key is
+    /// neither an RSA key nor an uncompressed EC point.
+    pub fn from_der(template: &[u8]) -> Option<Self> {
+        let mut walker = Walker {
+            der: template,
+            offset: 0,
+            constants: Vec::new(),
+        };
+
+        let certificate = walker.enter(Some(TAG_SEQUENCE))?;
+
+        let tbs_start = walker.offset;
+        let tbs_contents = walker.enter(Some(TAG_SEQUENCE))?;
+        let tbs = tbs_start..tbs_contents.end;
+        // The optional version, the serial number, the signature algorithm,
+        // the issuer, the validity and the subject.
+        let has_version = template.get(walker.offset) == Some(&TAG_VERSION);
+        for _ in 0..(if has_version { 6 } else { 5 }) {
+            walker.skip()?;
+        }
+
+        let spki = walker.enter(Some(TAG_SEQUENCE))?;
+        let algorithm = walker.enter(Some(TAG_SEQUENCE))?;
+        let key_type = &template[algorithm.clone()];
+        walker.constant(algorithm.len())?;
+        let key = walker.enter(Some(TAG_BIT_STRING))?;
+        // No unused bits.
+        if key.is_empty() || template[key.start] != 0 {
+            return None;
+        }
+        walker.constant(1)?;
+
+        let public_key = if key_type.starts_with(OID_RSA_ENCRYPTION) {
+            let rsa_key = walker.enter(Some(TAG_SEQUENCE))?;
+            let modulus = walker.enter(Some(TAG_INTEGER))?;
+            walker.take(modulus.len())?;
+            let exponent = walker.enter(Some(TAG_INTEGER))?;
+            walker.take(exponent.len())?;
+            walker.end(&rsa_key)?;
+            PublicKeyLayout::Rsa { modulus, exponent }
+        } else if key_type.starts_with(OID_EC_PUBLIC_KEY) {
+            // An uncompressed point.
+            if key.len() < 2 || template[walker.offset] != 0x04 || key.len() % 2 != 0 {
+                return None;
+            }
+            walker.constant(1)?;
+            let coordinate_len = (key.len() - 1) / 2;
+            let x = walker.take(coordinate_len)?;
+            let y = walker.take(coordinate_len)?;
+            PublicKeyLayout::Ec { x, y }
+        } else {
+            return None;
+        };
+        walker.end(&key)?;
+        walker.end(&spki)?;
+        // The issuer and subject unique identifiers and the extensions.
+        walker.offset = tbs.end;
+
+        let signature_algorithm = walker.constant_element(TAG_SEQUENCE)?;
+        let signature_value = walker.enter(Some(TAG_BIT_STRING))?;
+        if signature_value.is_empty() || template[signature_value.start] != 0 {
+            return None;
+        }
+        walker.constant(1)?;
+        let signature = walker.take(signature_value.len() - 1)?;
+        walker.end(&certificate)?;
+        if walker.offset != template.len() {
+            return None;
+        }
+
+        Some(CertificateLayout {
+            len: template.len(),
+            constants: walker.constants,
+            tbs,
+            signature_algorithm,
+            signature,
+            public_key,
+        })
+    }
+
+    /// The length of the certificates in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the certificates have no bytes, which a valid layout
+    /// never describes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The range of the signed to-be-signed certificate, including its
+    /// header.
+    pub fn tbs_range(&self) -> Range<usize> {
+        self.tbs.clone()
+    }
+}
+
+/// The public key of a certificate.
+#[derive(Clone)]
+pub enum PublicKey<'a> {
+    /// An RSA key, with the big-endian modulus and public exponent.
+    ///
+    /// As they are DER integers, both may start with a zero byte.
+    Rsa {
+        modulus: &'a [UInt8],
+        exponent: &'a [UInt8],
+    },
+    /// An EC key, with the big-endian coordinates of the point.
+    Ec { x: &'a [UInt8], y: &'a [UInt8] },
+}
+
+/// The fields of a parsed certificate.
+#[derive(Clone)]
+pub struct Certificate<'a> {
+    /// The signed to-be-signed certificate, including its header.
+    pub tbs: &'a [UInt8],
+    /// The DER encoded signature algorithm identifier.
+    pub signature_algorithm: &'a [UInt8],
+    /// The signature, without the unused bits byte of the bit string.
+    pub signature: &'a [UInt8],
+    pub public_key: PublicKey<'a>,
+}
+
+/// Parses the DER encoded certificate `certificate` with the given `layout`.
+///
+/// # Panics
+///
+/// Panics if the length of `certificate` doesn't match the layout.
+pub fn parse_certificate<'a, Scalar, CS>(
+    mut cs: CS,
+    layout: &CertificateLayout,
+    certificate: &'a [UInt8],
+) -> Result<Certificate<'a>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    assert_eq!(certificate.len(), layout.len);
+
+    for (offset, expected) in &layout.constants {
+        enforce_constant_bytes(
+            cs.namespace(|| format!("constant at {}", offset)),
+            &certificate[*offset..*offset + expected.len()],
+            expected,
+        )?;
+    }
+
+    let public_key = match &layout.public_key {
+        PublicKeyLayout::Rsa { modulus, exponent } => PublicKey::Rsa {
+            modulus: &certificate[modulus.clone()],
+            exponent: &certificate[exponent.clone()],
+        },
+        PublicKeyLayout::Ec { x, y } => PublicKey::Ec {
+            x: &certificate[x.clone()],
+            y: &certificate[y.clone()],
+        },
+    };
+
+    Ok(Certificate {
+        tbs: &certificate[layout.tbs.clone()],
+        signature_algorithm: &certificate[layout.signature_algorithm.clone()],
+        signature: &certificate[layout.signature.clone()],
+        public_key,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use blstrs::Scalar as Fr;
+
+    use super::*;
+    use crate::gadgets::test::*;
+
+    fn tlv(tag: u8, contents: &[u8]) -> Vec<u8> {
+        let mut der = DerHeader {
+            tag,
+            len: contents.len(),
+        }
+        .to_der();
+        der.extend_from_slice(contents);
+        der
+    }
+
+    /// A certificate shaped structure with the given key, padded to take
+    /// long form lengths.
+    fn certificate(algorithm: &[u8], key: &[u8], subject: &[u8], signature: &[u8]) -> Vec<u8> {
+        let name = |cn: &[u8]| tlv(0x30, &tlv(0x31, &tlv(0x30, &tlv(0x0c, cn))));
+        let tbs = tlv(
+            0x30,
+            &[
+                tlv(0xa0, &tlv(0x02, &[2])),
+                tlv(0x02, &[0x01, 0x23]),
+                tlv(0x30, &tlv(0x06, &[0x2a, 0x03])),
+                name(b"issuer"),
+                tlv(0x30, &[0x17; 30]),
+                name(subject),
+                tlv(0x30, &[tlv(0x30, algorithm), tlv(0x03, key)].concat()),
+                tlv(0xa3, &[0x55; 150]),
+            ]
+            .concat(),
+        );
+        let mut signature_value = vec![0];
+        signature_value.extend_from_slice(signature);
+        tlv(
+            0x30,
+            &[
+                tbs,
+                tlv(0x30, &tlv(0x06, &[0x2a, 0x03])),
+                tlv(0x03, &signature_value),
+            ]
+            .concat(),
+        )
+    }
+
+    fn rsa_certificate(modulus: &[u8], subject: &[u8], signature: &[u8]) -> Vec<u8> {
+        let mut algorithm = OID_RSA_ENCRYPTION.to_vec();
+        algorithm.extend_from_slice(&[0x05, 0x00]);
+        let mut key = vec![0];
+        key.extend(tlv(
+            0x30,
+            &[tlv(0x02, modulus), tlv(0x02, &[0x01, 0x00, 0x01])].concat(),
+        ));
+        certificate(&algorithm, &key, subject, signature)
+    }
+
+    fn ec_certificate(x: &[u8], y: &[u8], subject: &[u8], signature: &[u8]) -> Vec<u8> {
+        let mut algorithm = OID_EC_PUBLIC_KEY.to_vec();
+        algorithm.extend(tlv(0x06, &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07]));
+        let key = [&[0, 0x04][..], x, y].concat();
+        certificate(&algorithm, &key, subject, signature)
+    }
+
+    fn alloc_bytes<CS: ConstraintSystem<Fr>>(mut cs: CS, bytes: &[u8]) -> Vec<UInt8> {
+        bytes
+            .iter()
+            .enumerate()
+            .map(|(i, &byte)| {
+                UInt8::alloc(cs.namespace(|| format!("byte {}", i)), Some(byte)).unwrap()
+            })
+            .collect()
+    }
+
+    fn values(bytes: &[UInt8]) -> Vec<u8> {
+        bytes.iter().map(|byte| byte.get_value().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_der_header() {
+        for len in [0, 1, 0x7f, 0x80, 0xff, 0x100, 0x1_0000] {
+            let header = DerHeader { tag: 0x30, len };
+            let mut der = header.to_der();
+            let header_len = der.len();
+            der.resize(header_len + len, 0);
+            assert_eq!(DerHeader::parse(&der), Some((header, header_len)));
+        }
+
+        // Non-minimal length, truncated contents and multi-byte tags.
+        assert_eq!(DerHeader::parse(&[0x30, 0x81, 0x01, 0x00]), None);
+        assert_eq!(DerHeader::parse(&[0x30, 0x02, 0x00]), None);
+        assert_eq!(DerHeader::parse(&[0x1f, 0x01, 0x00]), None);
+    }
+
+    #[test]
+    fn test_der_element() {
+        let der = [tlv(0x04, &[0xab; 200]), vec![0xcd]].concat();
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let bytes = alloc_bytes(cs.namespace(|| "der"), &der);
+        let header = DerHeader {
+            tag: 0x04,
+            len: 200,
+        };
+        let (contents, rest) = der_element(cs.namespace(|| "element"), &bytes, &header).unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(values(contents), vec![0xab; 200]);
+        assert_eq!(values(rest), vec![0xcd]);
+
+        let header = DerHeader {
+            tag: 0x03,
+            len: 200,
+        };
+        der_element(cs.namespace(|| "wrong tag"), &bytes, &header).unwrap();
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_parse_rsa_certificate() {
+        let template = rsa_certificate(&[0x80; 129], b"alice", &[0x11; 128]);
+        let layout = CertificateLayout::from_der(&template).unwrap();
+
+        let modulus = (0..129).map(|i| (i as u8) | 0x80).collect::<Vec<_>>();
+        let der = rsa_certificate(&modulus, b"bobby", &[0x22; 128]);
+        assert_eq!(CertificateLayout::from_der(&der).as_ref(), Some(&layout));
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let bytes = alloc_bytes(cs.namespace(|| "certificate"), &der);
+        let certificate = parse_certificate(cs.namespace(|| "parse"), &layout, &bytes).unwrap();
+        assert!(cs.is_satisfied());
+
+        match certificate.public_key {
+            PublicKey::Rsa {
+                modulus: n,
+                exponent,
+            } => {
+                assert_eq!(values(n), modulus);
+                assert_eq!(values(exponent), vec![0x01, 0x00, 0x01]);
+            }
+            PublicKey::Ec { .. } => panic!("expected an RSA key"),
+        }
+        assert_eq!(values(certificate.tbs), der[layout.tbs_range()].to_vec());
+        assert_eq!(values(certificate.signature), vec![0x22; 128]);
+    }
+
+    #[test]
+    fn test_parse_ec_certificate() {
+        let template = ec_certificate(&[0; 32], &[0; 32], b"alice", &[0x11; 70]);
+        let layout = CertificateLayout::from_der(&template).unwrap();
+
+        let der = ec_certificate(&[0x33; 32], &[0x44; 32], b"bobby", &[0x22; 70]);
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let bytes = alloc_bytes(cs.namespace(|| "certificate"), &der);
+        let certificate = parse_certificate(cs.namespace(|| "parse"), &layout, &bytes).unwrap();
+        assert!(cs.is_satisfied());
+
+        match certificate.public_key {
+            PublicKey::Ec { x, y } => {
+                assert_eq!(values(x), vec![0x33; 32]);
+                assert_eq!(values(y), vec![0x44; 32]);
+            }
+            PublicKey::Rsa { .. } => panic!("expected an EC key"),
+        }
+
+        // A certificate with an RSA key of the same length doesn't match the
+        // layout.
+        let der = rsa_certificate(&[0x80; 62], b"bobby", &[0x22; 70]);
+        assert_eq!(der.len(), layout.len());
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let bytes = alloc_bytes(cs.namespace(|| "certificate"), &der);
+        parse_certificate(cs.namespace(|| "parse"), &layout, &bytes).unwrap();
+        assert!(!cs.is_satisfied());
+    }
+}
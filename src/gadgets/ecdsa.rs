@@ -0,0 +1,159 @@
+//! Verification of ECDSA signatures over short Weierstrass curves with emulated coordinates, such
+//! as the secp256k1 signatures of Bitcoin and Ethereum.
+//!
+//! A signature of a message hash `z` by the public key `Q = [d]G` is a pair of scalars `(r, s)`
+//! modulo the order `n` of the curve, with `r` the `x` coordinate of `[k]G` modulo `n` and
+//! `s = (z + r d) / k`. [`verify`] accepts it if `r` and `s` are in `[1, n)` and the `x`
+//! coordinate of `[z / s]G + [r / s]Q` is `r` modulo `n`. Hashing the message, e.g. with
+//! Keccak-256 for Ethereum, is left to the caller.
+//!
+//! The addition of [`AffinePoint`]s is incomplete, so the double scalar multiplication starts
+//! from a constant multiple `T` of the generator and subtracts it at the end, which makes the
+//! exceptions unlikely for honest signatures. Invalid signatures may fail to synthesize with
+//! [`SynthesisError::Unsatisfiable`] instead of leaving constraints unsatisfied.
+//!
+//! The multiplication costs two additions, a doubling and two selections per bit of the scalars,
+//! which adds up to a few million constraints for secp256k1.
+
+use ff::PrimeFieldBits;
+
+use super::bignat::BigNat;
+use super::weierstrass::{AffinePoint, WeierstrassCurve};
+use crate::{ConstraintSystem, SynthesisError};
+
+/// The multiple of the generator that the double scalar multiplication starts from.
+const OFFSET: &[u64] = &[0x5eed_0ff5_e7c0_ffee];
+
+/// Enforces that `(r, s)` is a signature of the message hash `message` by `public_key`.
+///
+/// The numbers must have the limb width of the curve.
+pub fn verify<Scalar, C, CS>(
+    mut cs: CS,
+    public_key: &AffinePoint<Scalar, C>,
+    message: &BigNat<Scalar>,
+    r: &BigNat<Scalar>,
+    s: &BigNat<Scalar>,
+) -> Result<(), SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    C: WeierstrassCurve,
+    CS: ConstraintSystem<Scalar>,
+{
+    // r and s must be reduced, and the inverses prove that they aren't zero.
+    r.enforce_less_than(cs.namespace(|| "r range"), C::ORDER)?;
+    s.enforce_less_than(cs.namespace(|| "s range"), C::ORDER)?;
+    r.inverse_mod(cs.namespace(|| "r inverse"), C::ORDER)?;
+    let w = s.inverse_mod(cs.namespace(|| "s inverse"), C::ORDER)?;
+
+    let u1 = message.mul_mod(cs.namespace(|| "u1"), &w, C::ORDER)?;
+    let u2 = r.mul_mod(cs.namespace(|| "u2"), &w, C::ORDER)?;
+    let u1_bits = u1.to_bits(cs.namespace(|| "u1 bits"))?;
+    let u2_bits = u2.to_bits(cs.namespace(|| "u2 bits"))?;
+
+    // T + [u1]G + [u2]Q, adding [2^i]G and [2^i]Q for the bits i of u1 and u2.
+    let offset = C::mul_point(C::GENERATOR, OFFSET).expect("offset is a multiple of the order");
+    let mut acc = AffinePoint::<Scalar, C>::constant((&offset.0, &offset.1));
+    let mut generator = (C::GENERATOR.0.to_vec(), C::GENERATOR.1.to_vec());
+    let mut public_key = public_key.clone();
+    for (i, (u1, u2)) in u1_bits.iter().zip(&u2_bits).enumerate() {
+        let mut cs = cs.namespace(|| format!("bit {}", i));
+
+        let base = AffinePoint::constant((&generator.0, &generator.1));
+        let sum = acc.add(cs.namespace(|| "add G"), &base)?;
+        acc = AffinePoint::select(cs.namespace(|| "select G"), u1, &sum, &acc)?;
+        let sum = acc.add(cs.namespace(|| "add Q"), &public_key)?;
+        acc = AffinePoint::select(cs.namespace(|| "select Q"), u2, &sum, &acc)?;
+
+        if i + 1 < u1_bits.len() {
+            public_key = public_key.double(cs.namespace(|| "double Q"))?;
+            generator = C::add_points((&generator.0, &generator.1), (&generator.0, &generator.1))
+                .expect("generator of even order");
+        }
+    }
+    let negated_offset = C::negate_point((&offset.0, &offset.1));
+    let result = acc.add(
+        cs.namespace(|| "subtract offset"),
+        &AffinePoint::constant((&negated_offset.0, &negated_offset.1)),
+    )?;
+
+    let x = result.get_x();
+    x.enforce_less_than(cs.namespace(|| "x range"), C::P)?;
+    x.enforce_equal_mod(cs.namespace(|| "verification equation"), r, C::ORDER)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+
+    use crate::gadgets::bignat::Nat;
+    use crate::gadgets::test::*;
+
+    /// `y^2 = x^3 + 7` over the field of 1048783, of prime order 1050337, in 2 limbs of 11 bits.
+    struct Toy;
+
+    impl WeierstrassCurve for Toy {
+        const LIMB_WIDTH: usize = 11;
+        const N_LIMBS: usize = 2;
+
+        const P: &'static [u64] = &[1048783];
+        const A: &'static [u64] = &[];
+        const B: &'static [u64] = &[7];
+        const ORDER: &'static [u64] = &[1050337];
+        const GENERATOR: (&'static [u64], &'static [u64]) = (&[1], &[490740]);
+    }
+
+    #[test]
+    fn test_ecdsa_toy() {
+        let order = Nat::new(Toy::ORDER.to_vec());
+        let secret = Nat::new(vec![123457]);
+        let nonce = Nat::new(vec![654321]);
+        let hash = Nat::new(vec![756005]);
+
+        // Sign the hash.
+        let public_key = Toy::mul_point(Toy::GENERATOR, &secret.0).unwrap();
+        let r = Toy::mul_point(Toy::GENERATOR, &nonce.0).unwrap().0;
+        let r = Nat::new(r).divrem(&order).1;
+        let s = hash
+            .add(&r.mul(&secret))
+            .mul(&nonce.inverse_mod(&order).unwrap())
+            .divrem(&order)
+            .1;
+
+        let verifies = |hash: &Nat, r: &Nat, s: &Nat| -> Result<bool, SynthesisError> {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let alloc = |cs: &mut TestConstraintSystem<Fr>, name: &str, value: &Nat| {
+                BigNat::alloc(
+                    cs.namespace(|| name),
+                    Some(&value.0[..]),
+                    Toy::LIMB_WIDTH,
+                    Toy::N_LIMBS,
+                )
+                .unwrap()
+            };
+            let public_key = AffinePoint::<Fr, Toy>::alloc(
+                cs.namespace(|| "public key"),
+                Some((&public_key.0[..], &public_key.1[..])),
+            )
+            .unwrap();
+            let hash = alloc(&mut cs, "hash", hash);
+            let r = alloc(&mut cs, "r", r);
+            let s = alloc(&mut cs, "s", s);
+
+            verify(cs.namespace(|| "verify"), &public_key, &hash, &r, &s)?;
+            Ok(cs.is_satisfied())
+        };
+
+        assert!(matches!(verifies(&hash, &r, &s), Ok(true)));
+        assert!(matches!(
+            verifies(&hash.add(&Nat::one()), &r, &s),
+            Ok(false)
+        ));
+        // r + n also satisfies the verification equation, but isn't reduced.
+        assert!(matches!(
+            verifies(&hash, &r.add(&order), &s),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+}
@@ -0,0 +1,232 @@
+//! Verification of EdDSA signatures over twisted Edwards curves over the scalar field, such as
+//! JubJub, with the hash of the challenge as a parameter.
+//!
+//! A signature of a message `M` by the public key `A = [a]G` is a point `R = [k]G` and a scalar
+//! `s = k + h a` modulo the order of `G`, with the challenge `h = H(R, A, M)`. [`verify`] accepts
+//! it if `s` is less than the order and `[c][s]G = [c](R + [h]A)` for the cofactor `c`, like
+//! verifiers that multiply by the cofactor, so `R` and `A` may be outside of the prime order
+//! subgroup.
+//!
+//! Curves over other fields, such as Ed25519 over BLS12-381, would have to emulate their field,
+//! see [`bignat`](super::bignat), and aren't supported.
+
+use ff::{PrimeField, PrimeFieldBits};
+
+use super::blake2s::blake2s;
+use super::boolean::Boolean;
+use super::cmp;
+use super::edwards::{EdwardsCurve, EdwardsPoint};
+use super::multipack;
+use crate::{ConstraintSystem, SynthesisError};
+
+/// The hash of the challenge of a signature.
+pub trait ChallengeHash<Scalar: PrimeField> {
+    /// Hashes the encodings of `R` and of the public key, see [`EdwardsPoint::encode`], and the
+    /// message into the bits of the challenge, least significant bit first.
+    fn hash<CS>(
+        cs: CS,
+        r: &[Boolean],
+        public_key: &[Boolean],
+        message: &[Boolean],
+    ) -> Result<Vec<Boolean>, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>;
+}
+
+/// The personalization of [`Blake2sChallenge`].
+pub const BLAKE2S_PERSONALIZATION: &[u8; 8] = b"EdDSA_H_";
+
+/// The challenge `BLAKE2s(R || A || M)` with the personalization [`BLAKE2S_PERSONALIZATION`],
+/// interpreted as a little-endian number. The message must consist of whole bytes.
+#[derive(Clone, Copy, Debug)]
+pub struct Blake2sChallenge;
+
+impl<Scalar: PrimeField> ChallengeHash<Scalar> for Blake2sChallenge {
+    fn hash<CS>(
+        cs: CS,
+        r: &[Boolean],
+        public_key: &[Boolean],
+        message: &[Boolean],
+    ) -> Result<Vec<Boolean>, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let input = [r, public_key, message].concat();
+        blake2s(cs, &input, BLAKE2S_PERSONALIZATION)
+    }
+}
+
+/// Enforces that `(r, s)` is a signature of `message` by `public_key`, with `s` given by its
+/// bits, least significant bit first.
+///
+/// Panics unless `s` has fewer bits than the capacity of the field.
+pub fn verify<Scalar, C, H, CS>(
+    mut cs: CS,
+    public_key: &EdwardsPoint<Scalar, C>,
+    r: &EdwardsPoint<Scalar, C>,
+    s: &[Boolean],
+    message: &[Boolean],
+) -> Result<(), SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    C: EdwardsCurve<Scalar>,
+    H: ChallengeHash<Scalar>,
+    CS: ConstraintSystem<Scalar>,
+{
+    // s must be reduced, otherwise s + order would be another signature.
+    let s_num = multipack::pack_bits(cs.namespace(|| "s"), s)?;
+    cmp::enforce_less_than_constant(cs.namespace(|| "s range"), &s_num, C::order(), s.len())?;
+
+    let r_bits = r.encode(cs.namespace(|| "encode r"))?;
+    let public_key_bits = public_key.encode(cs.namespace(|| "encode public key"))?;
+    let h = H::hash(
+        cs.namespace(|| "challenge"),
+        &r_bits,
+        &public_key_bits,
+        message,
+    )?;
+
+    let s_g = EdwardsPoint::<Scalar, C>::mul_generator(cs.namespace(|| "[s]G"), s)?;
+    let h_a = public_key.mul_bits(cs.namespace(|| "[h]A"), &h)?;
+    let r_h_a = r.add(cs.namespace(|| "R + [h]A"), &h_a)?;
+
+    let lhs = s_g.mul_by_cofactor(cs.namespace(|| "[c][s]G"))?;
+    let rhs = r_h_a.mul_by_cofactor(cs.namespace(|| "[c](R + [h]A)"))?;
+    lhs.enforce_equal(cs.namespace(|| "verification equation"), &rhs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use blake2s_simd::Params as Blake2sParams;
+    use blstrs::Scalar as Fr;
+    use rand_core::{RngCore, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    use crate::gadgets::bignat::Nat;
+    use crate::gadgets::boolean::AllocatedBit;
+    use crate::gadgets::edwards::JubJub;
+    use crate::gadgets::test::*;
+
+    fn to_nat(value: Fr) -> Nat {
+        let bits = value.to_le_bits().into_iter().collect::<Vec<_>>();
+        Nat::new(
+            bits.chunks(64)
+                .map(|word| {
+                    word.iter()
+                        .rev()
+                        .fold(0, |word, bit| (word << 1) | *bit as u64)
+                })
+                .collect(),
+        )
+    }
+
+    fn to_bits(value: &Nat, num_bits: usize) -> Vec<bool> {
+        (0..num_bits).map(|i| value.bit(i)).collect()
+    }
+
+    fn encode((x, y): (Fr, Fr)) -> Vec<u8> {
+        let mut bits = y
+            .to_le_bits()
+            .into_iter()
+            .take(Fr::NUM_BITS as usize)
+            .collect::<Vec<_>>();
+        bits.push(x.is_odd().into());
+        bits.chunks(8)
+            .map(|byte| {
+                byte.iter()
+                    .rev()
+                    .fold(0, |byte, bit| (byte << 1) | *bit as u8)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_eddsa_jubjub() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let order = to_nat(JubJub::order());
+        let order_bits = order.bits();
+        let mut random_scalar = || {
+            Nat::new((0..4).map(|_| rng.next_u64()).collect())
+                .divrem(&order)
+                .1
+        };
+
+        // Sign a message.
+        let generator = JubJub::generator();
+        let secret = random_scalar();
+        let nonce = random_scalar();
+        let public_key = JubJub::mul_point(generator, &to_bits(&secret, order_bits));
+        let r = JubJub::mul_point(generator, &to_bits(&nonce, order_bits));
+        let message = b"attested";
+
+        let mut h = Blake2sParams::new()
+            .hash_length(32)
+            .personal(BLAKE2S_PERSONALIZATION)
+            .to_state();
+        h.update(&encode(r));
+        h.update(&encode(public_key));
+        h.update(message);
+        let challenge = Nat::new(
+            h.finalize()
+                .as_bytes()
+                .chunks(8)
+                .map(|word| {
+                    word.iter()
+                        .rev()
+                        .fold(0, |word, byte| (word << 8) | *byte as u64)
+                })
+                .collect(),
+        );
+        let s = nonce.add(&challenge.mul(&secret)).divrem(&order).1;
+
+        let verifies = |message: &[u8], s: &Nat, s_bits: usize| {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let public_key =
+                EdwardsPoint::<Fr, JubJub>::alloc(cs.namespace(|| "public key"), Some(public_key))
+                    .unwrap();
+            let r = EdwardsPoint::alloc(cs.namespace(|| "r"), Some(r)).unwrap();
+            let s = to_bits(s, s_bits)
+                .into_iter()
+                .enumerate()
+                .map(|(i, bit)| {
+                    Boolean::from(
+                        AllocatedBit::alloc(cs.namespace(|| format!("s {}", i)), Some(bit))
+                            .unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let message = message
+                .iter()
+                .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+                .enumerate()
+                .map(|(i, bit)| {
+                    Boolean::from(
+                        AllocatedBit::alloc(cs.namespace(|| format!("message {}", i)), Some(bit))
+                            .unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            verify::<_, _, Blake2sChallenge, _>(
+                cs.namespace(|| "verify"),
+                &public_key,
+                &r,
+                &s,
+                &message,
+            )
+            .unwrap();
+            cs.is_satisfied()
+        };
+
+        assert!(verifies(message, &s, order_bits));
+        assert!(!verifies(b"forged!!", &s, order_bits));
+        // s + order satisfies the verification equation, but isn't reduced.
+        assert!(!verifies(message, &s.add(&order), order_bits + 1));
+    }
+}
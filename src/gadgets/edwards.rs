@@ -0,0 +1,621 @@
+//! Points of twisted Edwards curves over the scalar field, such as JubJub over the scalar field
+//! of BLS12-381.
+//!
+//! The curves `a x^2 + y^2 = 1 + d x^2 y^2` must have a square `a` and a non-square `d`, which
+//! makes their addition law complete: it has no exceptions, including for doubling and for the
+//! identity `(0, 1)`.
+//!
+//! Allocated points are only checked to be on the curve. [`EdwardsPoint::enforce_in_subgroup`]
+//! and [`EdwardsPoint::enforce_not_small_order`] check their order, e.g. for keys supplied by
+//! the prover.
+
+use std::marker::PhantomData;
+
+use ff::{Field, PrimeField, PrimeFieldBits};
+
+use super::bignat::Nat;
+use super::boolean::Boolean;
+use super::lookup::lookup3_xy;
+use super::num::AllocatedNum;
+use super::Assignment;
+use crate::{ConstraintSystem, LinearCombination, SynthesisError};
+
+/// The parameters of a twisted Edwards curve over `Scalar`.
+pub trait EdwardsCurve<Scalar: PrimeField> {
+    /// The coefficient `a`, a square.
+    fn a() -> Scalar;
+
+    /// The coefficient `d`, a non-square.
+    fn d() -> Scalar;
+
+    /// The generator of the prime order subgroup.
+    fn generator() -> (Scalar, Scalar);
+
+    /// The order of the prime order subgroup, which is less than the modulus of `Scalar`.
+    fn order() -> Scalar;
+
+    /// The order of the curve divided by the order of the subgroup.
+    fn cofactor() -> u64;
+
+    /// Whether the point is on the curve.
+    fn is_on_curve((x, y): (Scalar, Scalar)) -> bool {
+        let (x2, y2) = (x.square(), y.square());
+        Self::a() * x2 + y2 == Scalar::ONE + Self::d() * x2 * y2
+    }
+
+    /// Adds points outside of the circuit.
+    fn add_points((x1, y1): (Scalar, Scalar), (x2, y2): (Scalar, Scalar)) -> (Scalar, Scalar) {
+        let t = Self::d() * x1 * x2 * y1 * y2;
+        let x3 = (x1 * y2 + y1 * x2) * (Scalar::ONE + t).invert().unwrap();
+        let y3 = (y1 * y2 - Self::a() * x1 * x2) * (Scalar::ONE - t).invert().unwrap();
+        (x3, y3)
+    }
+
+    /// Multiplies a point by a scalar given by its bits, least significant bit first, outside of
+    /// the circuit.
+    fn mul_point(point: (Scalar, Scalar), bits: &[bool]) -> (Scalar, Scalar) {
+        let mut acc = (Scalar::ZERO, Scalar::ONE);
+        let mut base = point;
+        for bit in bits {
+            if *bit {
+                acc = Self::add_points(acc, base);
+            }
+            base = Self::add_points(base, base);
+        }
+        acc
+    }
+}
+
+/// The JubJub curve over the scalar field of BLS12-381, `-x^2 + y^2 = 1 - (10240/10241) x^2 y^2`.
+#[derive(Clone, Copy, Debug)]
+pub struct JubJub;
+
+impl EdwardsCurve<blstrs::Scalar> for JubJub {
+    fn a() -> blstrs::Scalar {
+        -blstrs::Scalar::ONE
+    }
+
+    fn d() -> blstrs::Scalar {
+        -(blstrs::Scalar::from(10240) * blstrs::Scalar::from(10241).invert().unwrap())
+    }
+
+    /// The cofactor times the point with the smallest `y` greater than one and an even `x`.
+    fn generator() -> (blstrs::Scalar, blstrs::Scalar) {
+        let mut y = blstrs::Scalar::ONE;
+        loop {
+            y += blstrs::Scalar::ONE;
+            let y2 = y.square();
+            let x2 = (blstrs::Scalar::ONE - y2) * (Self::a() - Self::d() * y2).invert().unwrap();
+            if let Some(x) = Option::<blstrs::Scalar>::from(x2.sqrt()) {
+                let x = if bool::from(x.is_odd()) { -x } else { x };
+                let cofactor = [false, false, false, true];
+                return Self::mul_point((x, y), &cofactor);
+            }
+        }
+    }
+
+    fn order() -> blstrs::Scalar {
+        blstrs::Scalar::from_str_vartime(
+            "6554484396890773809930967563523245729705921265872317281365359162392183254199",
+        )
+        .unwrap()
+    }
+
+    fn cofactor() -> u64 {
+        8
+    }
+}
+
+/// A point of the curve `C` with allocated coordinates.
+pub struct EdwardsPoint<Scalar: PrimeField, C> {
+    x: AllocatedNum<Scalar>,
+    y: AllocatedNum<Scalar>,
+    _curve: PhantomData<C>,
+}
+
+impl<Scalar: PrimeField, C> Clone for EdwardsPoint<Scalar, C> {
+    fn clone(&self) -> Self {
+        EdwardsPoint {
+            x: self.x.clone(),
+            y: self.y.clone(),
+            _curve: PhantomData,
+        }
+    }
+}
+
+impl<Scalar: PrimeFieldBits, C: EdwardsCurve<Scalar>> EdwardsPoint<Scalar, C> {
+    /// Allocates a point and enforces that it is on the curve, which costs 3 constraints. The
+    /// point isn't checked to be in the prime order subgroup.
+    pub fn alloc<CS>(mut cs: CS, value: Option<(Scalar, Scalar)>) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let x = AllocatedNum::alloc(cs.namespace(|| "x"), || Ok(value.get()?.0))?;
+        let y = AllocatedNum::alloc(cs.namespace(|| "y"), || Ok(value.get()?.1))?;
+        let x2 = x.square(cs.namespace(|| "x^2"))?;
+        let y2 = y.square(cs.namespace(|| "y^2"))?;
+
+        // Constrain: d x^2 * y^2 = a x^2 + y^2 - 1
+        cs.enforce(
+            || "curve equation",
+            |lc| lc + (C::d(), x2.get_variable()),
+            |lc| lc + y2.get_variable(),
+            |lc| lc + (C::a(), x2.get_variable()) + y2.get_variable() - CS::one(),
+        );
+
+        Ok(EdwardsPoint {
+            x,
+            y,
+            _curve: PhantomData,
+        })
+    }
+
+    pub fn get_x(&self) -> &AllocatedNum<Scalar> {
+        &self.x
+    }
+
+    pub fn get_y(&self) -> &AllocatedNum<Scalar> {
+        &self.y
+    }
+
+    pub fn get_value(&self) -> Option<(Scalar, Scalar)> {
+        self.x.get_value().zip(self.y.get_value())
+    }
+
+    /// Adds the points, which costs 7 constraints.
+    pub fn add<CS>(&self, mut cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let x1y2 = self.x.mul(cs.namespace(|| "x1 * y2"), &other.y)?;
+        let y1x2 = self.y.mul(cs.namespace(|| "y1 * x2"), &other.x)?;
+        let x1x2 = self.x.mul(cs.namespace(|| "x1 * x2"), &other.x)?;
+        let y1y2 = self.y.mul(cs.namespace(|| "y1 * y2"), &other.y)?;
+
+        // Constrain: d x1 y2 * y1 x2 = t
+        let t = AllocatedNum::alloc(cs.namespace(|| "t"), || {
+            Ok(C::d() * x1y2.get_value().get()? * y1x2.get_value().get()?)
+        })?;
+        cs.enforce(
+            || "t computation",
+            |lc| lc + (C::d(), x1y2.get_variable()),
+            |lc| lc + y1x2.get_variable(),
+            |lc| lc + t.get_variable(),
+        );
+
+        // Constrain: (1 + t) * x3 = x1 y2 + y1 x2
+        let x3 = AllocatedNum::alloc(cs.namespace(|| "x3"), || {
+            let denominator = Scalar::ONE + t.get_value().get()?;
+            let inverse = Option::<Scalar>::from(denominator.invert())
+                .ok_or(SynthesisError::DivisionByZero)?;
+            Ok((*x1y2.get_value().get()? + y1x2.get_value().get()?) * inverse)
+        })?;
+        cs.enforce(
+            || "x3 computation",
+            |lc| lc + CS::one() + t.get_variable(),
+            |lc| lc + x3.get_variable(),
+            |lc| lc + x1y2.get_variable() + y1x2.get_variable(),
+        );
+
+        // Constrain: (1 - t) * y3 = y1 y2 - a x1 x2
+        let y3 = AllocatedNum::alloc(cs.namespace(|| "y3"), || {
+            let denominator = Scalar::ONE - t.get_value().get()?;
+            let inverse = Option::<Scalar>::from(denominator.invert())
+                .ok_or(SynthesisError::DivisionByZero)?;
+            Ok((*y1y2.get_value().get()? - C::a() * x1x2.get_value().get()?) * inverse)
+        })?;
+        cs.enforce(
+            || "y3 computation",
+            |lc| lc + CS::one() - t.get_variable(),
+            |lc| lc + y3.get_variable(),
+            |lc| lc + y1y2.get_variable() - (C::a(), x1x2.get_variable()),
+        );
+
+        Ok(EdwardsPoint {
+            x: x3,
+            y: y3,
+            _curve: PhantomData,
+        })
+    }
+
+    /// Doubles the point, which costs 7 constraints.
+    pub fn double<CS>(&self, cs: CS) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.add(cs, self)
+    }
+
+    /// Multiplies the point by the cofactor, which must be a power of two, by doubling it.
+    pub fn mul_by_cofactor<CS>(&self, mut cs: CS) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let cofactor = C::cofactor();
+        assert!(cofactor.is_power_of_two(), "cofactor of {}", cofactor);
+
+        let mut point = self.clone();
+        for i in 0..cofactor.trailing_zeros() {
+            point = point.double(cs.namespace(|| format!("double {}", i)))?;
+        }
+        Ok(point)
+    }
+
+    /// Multiplies the point by a scalar given by its non-empty bits, least significant bit
+    /// first, by doubling and adding, which costs 16 constraints per bit.
+    pub fn mul_bits<CS>(&self, mut cs: CS, bits: &[Boolean]) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let mut base = self.clone();
+        let mut acc: Option<Self> = None;
+        for (i, bit) in bits.iter().enumerate() {
+            let mut cs = cs.namespace(|| format!("bit {}", i));
+            if i > 0 {
+                base = base.double(cs.namespace(|| "double"))?;
+            }
+
+            let term = select_or_identity(
+                cs.namespace(|| "select"),
+                bit,
+                (
+                    LinearCombination::zero() + base.x.get_variable(),
+                    base.x.get_value(),
+                ),
+                (
+                    LinearCombination::zero() + base.y.get_variable(),
+                    base.y.get_value(),
+                ),
+            )?;
+            acc = Some(match acc {
+                Some(acc) => acc.add(cs.namespace(|| "add"), &term)?,
+                None => term,
+            });
+        }
+
+        Ok(acc.expect("multiplication by no bits"))
+    }
+
+    /// Multiplies the generator of the curve by a scalar given by its non-empty bits, least
+    /// significant bit first, see [`EdwardsPoint::mul_fixed_base`].
+    pub fn mul_generator<CS>(cs: CS, bits: &[Boolean]) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        Self::mul_fixed_base(cs, C::generator(), bits)
+    }
+
+    /// Multiplies a constant point by a scalar given by its non-empty bits, least significant bit
+    /// first. The multiples of the point for each window of 3 bits are looked up in a table,
+    /// which costs 10 constraints per window.
+    pub fn mul_fixed_base<CS>(
+        mut cs: CS,
+        base: (Scalar, Scalar),
+        bits: &[Boolean],
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let mut base = base;
+        let mut acc: Option<Self> = None;
+        for (i, window) in bits.chunks(3).enumerate() {
+            let mut cs = cs.namespace(|| format!("window {}", i));
+            let mut window = window.to_vec();
+            window.resize(3, Boolean::constant(false));
+
+            // The multiples [j]base for j in 0..8, starting with the identity.
+            let mut table = vec![(Scalar::ZERO, Scalar::ONE)];
+            for j in 1..8 {
+                table.push(C::add_points(table[j - 1], base));
+            }
+            let term = Self::lookup(cs.namespace(|| "lookup"), &window, &table)?;
+
+            acc = Some(match acc {
+                Some(acc) => acc.add(cs.namespace(|| "add"), &term)?,
+                None => term,
+            });
+            base = C::add_points(table[7], base);
+        }
+
+        Ok(acc.expect("multiplication by no bits"))
+    }
+
+    /// Enforces that the point doesn't have a small order, i.e. that its multiple by the cofactor
+    /// isn't the identity, which costs 7 constraints per doubling and 1 more.
+    pub fn enforce_not_small_order<CS>(&self, mut cs: CS) -> Result<(), SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        // Only the identity has x = 0 among the multiples of the cofactor, which have an odd
+        // order and so aren't (0, -1).
+        let cleared = self.mul_by_cofactor(cs.namespace(|| "clear cofactor"))?;
+        cleared.x.assert_nonzero(cs.namespace(|| "x nonzero"))
+    }
+
+    /// Enforces that the point is in the prime order subgroup, which consists of the multiples
+    /// of the cofactor, by allocating the point `Q` with `[c]Q = P`. This costs 5 constraints
+    /// and 7 per doubling.
+    pub fn enforce_in_subgroup<CS>(&self, mut cs: CS) -> Result<(), SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let order = order_nat::<Scalar, C>();
+        // Q = [1/c]P modulo the order.
+        let inverse = Nat::new(vec![C::cofactor()])
+            .inverse_mod(&order)
+            .expect("cofactor shares a factor with the order");
+        let inverse = (0..order.bits())
+            .map(|i| inverse.bit(i))
+            .collect::<Vec<_>>();
+
+        let root = Self::alloc(
+            cs.namespace(|| "root"),
+            self.get_value().map(|point| C::mul_point(point, &inverse)),
+        )?;
+        let cleared = root.mul_by_cofactor(cs.namespace(|| "clear cofactor"))?;
+        cleared.enforce_equal(cs.namespace(|| "cofactor multiple"), self)
+    }
+
+    /// Enforces that the points are equal, which costs 2 constraints.
+    pub fn enforce_equal<CS>(&self, mut cs: CS, other: &Self) -> Result<(), SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        cs.enforce(
+            || "x equality",
+            |lc| lc + self.x.get_variable() - other.x.get_variable(),
+            |lc| lc + CS::one(),
+            |lc| lc,
+        );
+        cs.enforce(
+            || "y equality",
+            |lc| lc + self.y.get_variable() - other.y.get_variable(),
+            |lc| lc + CS::one(),
+            |lc| lc,
+        );
+
+        Ok(())
+    }
+
+    /// Encodes the point like Ed25519 and JubJub points: the bits of `y`, least significant bit
+    /// first and padded to whole bytes, with the least significant bit of `x` as the most
+    /// significant bit.
+    pub fn encode<CS>(&self, mut cs: CS) -> Result<Vec<Boolean>, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let mut bits = self.y.to_bits_le_strict(cs.namespace(|| "y bits"))?;
+        let sign = self.x.to_bits_le_strict(cs.namespace(|| "x bits"))?[0].clone();
+
+        while bits.len() % 8 != 7 {
+            bits.push(Boolean::constant(false));
+        }
+        bits.push(sign);
+
+        Ok(bits)
+    }
+}
+
+impl<Scalar: PrimeField, C> EdwardsPoint<Scalar, C> {
+    /// Looks up the point of `table` at the index given by 3 bits, least significant bit first,
+    /// which costs 3 constraints. The points of the table must be on the curve.
+    pub(crate) fn lookup<CS>(
+        cs: CS,
+        bits: &[Boolean],
+        table: &[(Scalar, Scalar)],
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let (x, y) = lookup3_xy(cs, bits, table)?;
+
+        Ok(EdwardsPoint {
+            x,
+            y,
+            _curve: PhantomData,
+        })
+    }
+}
+
+/// The order of the prime order subgroup of `C`.
+pub(crate) fn order_nat<Scalar, C>() -> Nat
+where
+    Scalar: PrimeFieldBits,
+    C: EdwardsCurve<Scalar>,
+{
+    let bits = C::order().to_le_bits().into_iter().collect::<Vec<_>>();
+    Nat::new(
+        bits.chunks(64)
+            .map(|word| {
+                word.iter()
+                    .rev()
+                    .fold(0, |word, bit| (word << 1) | *bit as u64)
+            })
+            .collect(),
+    )
+}
+
+/// Allocates the point with the coordinates `x` and `y` if `bit` is set and the identity
+/// `(0, 1)` otherwise, which costs 2 constraints.
+fn select_or_identity<Scalar, C, CS>(
+    mut cs: CS,
+    bit: &Boolean,
+    (x, x_value): (LinearCombination<Scalar>, Option<Scalar>),
+    (y, y_value): (LinearCombination<Scalar>, Option<Scalar>),
+) -> Result<EdwardsPoint<Scalar, C>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    // Constrain: x * bit = selected x
+    let selected_x = AllocatedNum::alloc(cs.namespace(|| "x"), || {
+        if *bit.get_value().get()? {
+            Ok(*x_value.get()?)
+        } else {
+            Ok(Scalar::ZERO)
+        }
+    })?;
+    cs.enforce(
+        || "x selection",
+        |_| x,
+        |_| bit.lc(CS::one(), Scalar::ONE),
+        |lc| lc + selected_x.get_variable(),
+    );
+
+    // Constrain: (y - 1) * bit = selected y - 1
+    let selected_y = AllocatedNum::alloc(cs.namespace(|| "y"), || {
+        if *bit.get_value().get()? {
+            Ok(*y_value.get()?)
+        } else {
+            Ok(Scalar::ONE)
+        }
+    })?;
+    cs.enforce(
+        || "y selection",
+        |_| y - CS::one(),
+        |_| bit.lc(CS::one(), Scalar::ONE),
+        |lc| lc + selected_y.get_variable() - CS::one(),
+    );
+
+    Ok(EdwardsPoint {
+        x: selected_x,
+        y: selected_y,
+        _curve: PhantomData,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+    use rand_core::{RngCore, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    use crate::gadgets::boolean::AllocatedBit;
+    use crate::gadgets::test::*;
+
+    #[test]
+    fn test_jubjub_parameters() {
+        let identity = (Fr::ZERO, Fr::ONE);
+        let generator = JubJub::generator();
+        assert!(JubJub::is_on_curve(generator));
+        assert_ne!(generator, identity);
+
+        let order = JubJub::order().to_le_bits().into_iter().collect::<Vec<_>>();
+        assert_eq!(JubJub::mul_point(generator, &order), identity);
+    }
+
+    #[test]
+    fn test_edwards_point() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let k = (0..32).map(|_| rng.next_u32() & 1 == 1).collect::<Vec<_>>();
+        let p = JubJub::mul_point(JubJub::generator(), &k);
+        let q = JubJub::mul_point(p, &[true, true]);
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let p_point = EdwardsPoint::<Fr, JubJub>::alloc(cs.namespace(|| "p"), Some(p)).unwrap();
+        let q_point = EdwardsPoint::<Fr, JubJub>::alloc(cs.namespace(|| "q"), Some(q)).unwrap();
+        assert_eq!(cs.num_constraints(), 2 * 3);
+
+        let sum = p_point.add(cs.namespace(|| "p + q"), &q_point).unwrap();
+        assert_eq!(sum.get_value(), Some(JubJub::add_points(p, q)));
+        let double = p_point.double(cs.namespace(|| "2p")).unwrap();
+        assert_eq!(double.get_value(), Some(JubJub::add_points(p, p)));
+        assert_eq!(cs.num_constraints(), 2 * 3 + 2 * 7);
+
+        let bits = k
+            .iter()
+            .enumerate()
+            .map(|(i, bit)| {
+                Boolean::from(
+                    AllocatedBit::alloc(cs.namespace(|| format!("k {}", i)), Some(*bit)).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let fixed =
+            EdwardsPoint::<Fr, JubJub>::mul_generator(cs.namespace(|| "[k]G"), &bits).unwrap();
+        fixed
+            .enforce_equal(cs.namespace(|| "[k]G = p"), &p_point)
+            .unwrap();
+        let variable = q_point.mul_bits(cs.namespace(|| "[k]q"), &bits).unwrap();
+        assert_eq!(variable.get_value(), Some(JubJub::mul_point(q, &k)));
+        let cleared = p_point.mul_by_cofactor(cs.namespace(|| "[8]p")).unwrap();
+        assert_eq!(
+            cleared.get_value(),
+            Some(JubJub::mul_point(p, &[false, false, false, true]))
+        );
+        assert!(cs.is_satisfied());
+
+        // Points off the curve are rejected.
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        EdwardsPoint::<Fr, JubJub>::alloc(cs.namespace(|| "p"), Some((Fr::ONE, Fr::ONE))).unwrap();
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_edwards_fixed_base_and_subgroup() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let k = (0..33).map(|_| rng.next_u32() & 1 == 1).collect::<Vec<_>>();
+        let p = JubJub::mul_point(JubJub::generator(), &k);
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let bits = k
+            .iter()
+            .enumerate()
+            .map(|(i, bit)| {
+                Boolean::from(
+                    AllocatedBit::alloc(cs.namespace(|| format!("k {}", i)), Some(*bit)).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let before = cs.num_constraints();
+        let fixed =
+            EdwardsPoint::<Fr, JubJub>::mul_generator(cs.namespace(|| "[k]G"), &bits).unwrap();
+        assert_eq!(fixed.get_value(), Some(p));
+        assert_eq!(cs.num_constraints() - before, 11 * 10 - 7);
+        // A scalar that isn't a multiple of the window is padded.
+        let fixed =
+            EdwardsPoint::<Fr, JubJub>::mul_fixed_base(cs.namespace(|| "[k]p"), p, &bits[1..])
+                .unwrap();
+        assert_eq!(fixed.get_value(), Some(JubJub::mul_point(p, &k[1..])));
+
+        let p_point = EdwardsPoint::<Fr, JubJub>::alloc(cs.namespace(|| "p"), Some(p)).unwrap();
+        p_point
+            .enforce_not_small_order(cs.namespace(|| "p not small order"))
+            .unwrap();
+        p_point
+            .enforce_in_subgroup(cs.namespace(|| "p in subgroup"))
+            .unwrap();
+        assert!(cs.is_satisfied());
+
+        // Adding the point of order 2 leaves the subgroup, but not to a small order.
+        let order_two = (Fr::ZERO, -Fr::ONE);
+        let q = JubJub::add_points(p, order_two);
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let q_point = EdwardsPoint::<Fr, JubJub>::alloc(cs.namespace(|| "q"), Some(q)).unwrap();
+        q_point
+            .enforce_not_small_order(cs.namespace(|| "q not small order"))
+            .unwrap();
+        assert!(cs.is_satisfied());
+        q_point
+            .enforce_in_subgroup(cs.namespace(|| "q in subgroup"))
+            .unwrap();
+        assert!(!cs.is_satisfied());
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let small =
+            EdwardsPoint::<Fr, JubJub>::alloc(cs.namespace(|| "small"), Some(order_two)).unwrap();
+        assert!(small
+            .enforce_not_small_order(cs.namespace(|| "small not small order"))
+            .is_err());
+    }
+}
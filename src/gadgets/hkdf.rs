@@ -0,0 +1,145 @@
+//! Circuits for the [HKDF] key derivation function with HMAC-SHA256.
+//!
+//! [HKDF]: https://tools.ietf.org/html/rfc5869
+
+use ff::PrimeField;
+
+use super::hmac::hmac_sha256;
+use super::uint8::UInt8;
+use crate::{ConstraintSystem, SynthesisError};
+
+/// The output length of SHA-256 in bytes.
+const HASH_LEN: usize = 32;
+
+/// HKDF-Extract, derives a pseudorandom key from the input keying material
+/// `ikm` and the `salt`.
+///
+/// An empty `salt` is equivalent to the default salt of `HASH_LEN` zero
+/// bytes.
+pub fn hkdf_extract<Scalar, CS>(
+    cs: CS,
+    salt: &[UInt8],
+    ikm: &[UInt8],
+) -> Result<[UInt8; 32], SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    hmac_sha256(cs, salt, ikm)
+}
+
+/// HKDF-Expand, derives `len` bytes of output keying material from the
+/// pseudorandom key `prk`, bound to the context `info`.
+///
+/// # Panics
+///
+/// Panics if `len` is larger than `255 * 32` bytes.
+pub fn hkdf_expand<Scalar, CS>(
+    mut cs: CS,
+    prk: &[UInt8],
+    info: &[UInt8],
+    len: usize,
+) -> Result<Vec<UInt8>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    assert!(len <= 255 * HASH_LEN, "HKDF output is at most 255 blocks");
+
+    let mut okm = Vec::with_capacity(len);
+    let mut t = Vec::new();
+    for i in 1..=(len + HASH_LEN - 1) / HASH_LEN {
+        let mut message = t;
+        message.extend_from_slice(info);
+        message.push(UInt8::constant(i as u8));
+
+        t = hmac_sha256(cs.namespace(|| format!("block {}", i)), prk, &message)?.to_vec();
+        okm.extend_from_slice(&t);
+    }
+    okm.truncate(len);
+
+    Ok(okm)
+}
+
+/// HKDF, HKDF-Extract followed by HKDF-Expand, derives `len` bytes from the
+/// input keying material `ikm`.
+pub fn hkdf<Scalar, CS>(
+    mut cs: CS,
+    salt: &[UInt8],
+    ikm: &[UInt8],
+    info: &[UInt8],
+    len: usize,
+) -> Result<Vec<UInt8>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    let prk = hkdf_extract(cs.namespace(|| "extract"), salt, ikm)?;
+    hkdf_expand(cs.namespace(|| "expand"), &prk, info, len)
+}
+
+#[cfg(test)]
+mod test {
+    use blstrs::Scalar as Fr;
+    use hex_literal::hex;
+
+    use super::{hkdf, hkdf_expand, hkdf_extract};
+    use crate::gadgets::test::*;
+    use crate::gadgets::uint8::UInt8;
+    use crate::ConstraintSystem;
+
+    fn alloc_bytes<CS: ConstraintSystem<Fr>>(mut cs: CS, bytes: &[u8]) -> Vec<UInt8> {
+        bytes
+            .iter()
+            .enumerate()
+            .map(|(i, &byte)| {
+                UInt8::alloc(cs.namespace(|| format!("byte {}", i)), Some(byte)).unwrap()
+            })
+            .collect()
+    }
+
+    fn assert_bytes(bytes: &[UInt8], expected: &[u8]) {
+        assert_eq!(bytes.len(), expected.len());
+        for (byte, expected) in bytes.iter().zip(expected.iter()) {
+            assert_eq!(byte.get_value(), Some(*expected));
+        }
+    }
+
+    #[test]
+    fn test_against_vectors() {
+        // Test cases 1 and 3 of RFC 5869.
+        let ikm = [0x0b; 22];
+        let salt = hex!("000102030405060708090a0b0c");
+        let info = hex!("f0f1f2f3f4f5f6f7f8f9");
+        let vectors: [(&[u8], &[u8], [u8; 32], [u8; 42]); 2] = [
+            (
+                &salt,
+                &info,
+                hex!("077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5"),
+                hex!("3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"),
+            ),
+            (
+                &[],
+                &[],
+                hex!("19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04"),
+                hex!("8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8"),
+            ),
+        ];
+
+        for (salt, info, expected_prk, expected_okm) in vectors {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let ikm = alloc_bytes(cs.namespace(|| "ikm"), &ikm);
+            let salt = alloc_bytes(cs.namespace(|| "salt"), salt);
+            let info = alloc_bytes(cs.namespace(|| "info"), info);
+
+            let prk = hkdf_extract(cs.namespace(|| "extract"), &salt, &ikm).unwrap();
+            assert_bytes(&prk, &expected_prk);
+            let okm = hkdf_expand(cs.namespace(|| "expand"), &prk, &info, 42).unwrap();
+            assert_bytes(&okm, &expected_okm);
+            let okm = hkdf(cs.namespace(|| "hkdf"), &salt, &ikm, &info, 42).unwrap();
+            assert_bytes(&okm, &expected_okm);
+
+            assert!(cs.is_satisfied());
+        }
+    }
+}
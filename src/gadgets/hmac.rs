@@ -0,0 +1,111 @@
+//! Circuits for [HMAC] with the [SHA-256] hash function.
+//!
+//! [HMAC]: https://tools.ietf.org/html/rfc2104
+//! [SHA-256]: https://tools.ietf.org/html/rfc6234
+
+use ff::PrimeField;
+
+use super::sha256::sha256_bytes;
+use super::uint8::UInt8;
+use crate::{ConstraintSystem, SynthesisError};
+
+/// The block size of SHA-256 in bytes.
+const BLOCK_LEN: usize = 64;
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
+
+/// Computes HMAC-SHA256 of `message` under `key`.
+///
+/// Keys longer than a block are hashed first, shorter ones are padded with
+/// zeros, as in RFC 2104. The lengths of `key` and `message` are fixed by the
+/// circuit.
+pub fn hmac_sha256<Scalar, CS>(
+    mut cs: CS,
+    key: &[UInt8],
+    message: &[UInt8],
+) -> Result<[UInt8; 32], SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    let mut key = if key.len() > BLOCK_LEN {
+        sha256_bytes(cs.namespace(|| "hash key"), key)?.to_vec()
+    } else {
+        key.to_vec()
+    };
+    key.resize(BLOCK_LEN, UInt8::constant(0));
+
+    let mut inner = pad_key(cs.namespace(|| "inner key"), &key, IPAD)?;
+    inner.extend_from_slice(message);
+    let inner_hash = sha256_bytes(cs.namespace(|| "inner hash"), &inner)?;
+
+    let mut outer = pad_key(cs.namespace(|| "outer key"), &key, OPAD)?;
+    outer.extend_from_slice(&inner_hash);
+    sha256_bytes(cs.namespace(|| "outer hash"), &outer)
+}
+
+/// XORs every byte of `key` with the constant `pad`, which doesn't add any
+/// constraints.
+fn pad_key<Scalar, CS>(mut cs: CS, key: &[UInt8], pad: u8) -> Result<Vec<UInt8>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    let pad = UInt8::constant(pad);
+    key.iter()
+        .enumerate()
+        .map(|(i, byte)| byte.xor(cs.namespace(|| format!("byte {}", i)), &pad))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use blstrs::Scalar as Fr;
+    use hex_literal::hex;
+
+    use super::hmac_sha256;
+    use crate::gadgets::test::*;
+    use crate::gadgets::uint8::UInt8;
+    use crate::ConstraintSystem;
+
+    fn alloc_bytes<CS: ConstraintSystem<Fr>>(mut cs: CS, bytes: &[u8]) -> Vec<UInt8> {
+        bytes
+            .iter()
+            .enumerate()
+            .map(|(i, &byte)| {
+                UInt8::alloc(cs.namespace(|| format!("byte {}", i)), Some(byte)).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_against_vectors() {
+        // Test cases 2 and 6 of RFC 4231.
+        let vectors: [(&[u8], &[u8], [u8; 32]); 2] = [
+            (
+                b"Jefe",
+                b"what do ya want for nothing?",
+                hex!("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"),
+            ),
+            (
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First",
+                hex!("60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"),
+            ),
+        ];
+
+        for (key, message, expected) in vectors {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let key = alloc_bytes(cs.namespace(|| "key"), key);
+            let message = alloc_bytes(cs.namespace(|| "message"), message);
+
+            let mac = hmac_sha256(cs.namespace(|| "hmac"), &key, &message).unwrap();
+
+            assert!(cs.is_satisfied());
+            for (byte, expected) in mac.iter().zip(expected.iter()) {
+                assert_eq!(byte.get_value(), Some(*expected));
+            }
+        }
+    }
+}
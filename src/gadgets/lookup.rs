@@ -1,11 +1,18 @@
 //! Window table lookup gadgets.
+//!
+//! [`lookup`] looks up a row of a constant table with a window of any size,
+//! [`lookup_witnessed`] a row of a table of allocated numbers. Their costs are
+//! given by [`lookup_cost`] and [`lookup_witnessed_cost`], and
+//! [`optimal_window_size`] picks the window size that minimizes the cost of
+//! covering a number of bits.
 
 use ff::PrimeField;
 
 use super::boolean::Boolean;
 use super::num::{AllocatedNum, Num};
+use super::select::select_by_bits;
 use super::*;
-use crate::ConstraintSystem;
+use crate::{ConstraintSystem, LinearCombination};
 
 // Synthesize the constants for each base pattern.
 fn synth<'a, Scalar: PrimeField, I>(window_size: usize, constants: I, assignment: &mut [Scalar])
@@ -27,6 +34,139 @@ where
     }
 }
 
+/// Looks up the row of the constant `table` at the index given by `bits`,
+/// in little-endian order. Every row must have the same number of columns.
+///
+/// The columns are multilinear polynomials in the bits, so the lookup
+/// costs the products of the bits but the first, and one constraint per
+/// column, see [`lookup_cost`]. [`lookup3_xy`] is the 3-bit lookup of two
+/// columns.
+pub fn lookup<Scalar, CS>(
+    mut cs: CS,
+    bits: &[Boolean],
+    table: &[Vec<Scalar>],
+) -> Result<Vec<AllocatedNum<Scalar>>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    let window_size = bits.len();
+    assert!(window_size >= 1);
+    assert_eq!(table.len(), 1 << window_size);
+    let width = table[0].len();
+    assert!(table.iter().all(|row| row.len() == width));
+
+    let index = bits.iter().rev().try_fold(0usize, |index, bit| {
+        bit.get_value().map(|bit| (index << 1) | bit as usize)
+    });
+
+    // products[m] is the product of the bits after the first selected by
+    // the mask m, computed from the product without the lowest of them.
+    let mut products = vec![Boolean::constant(true)];
+    for m in 1..1usize << (window_size - 1) {
+        let lowest = &bits[1 + m.trailing_zeros() as usize];
+        let rest = m & (m - 1);
+        let product = if rest == 0 {
+            lowest.clone()
+        } else {
+            Boolean::and(
+                cs.namespace(|| format!("product {}", m)),
+                &products[rest],
+                lowest,
+            )?
+        };
+        products.push(product);
+    }
+
+    let one = CS::one();
+    (0..width)
+        .map(|j| {
+            let mut coeffs = vec![Scalar::ZERO; 1 << window_size];
+            synth::<Scalar, _>(window_size, table.iter().map(|row| &row[j]), &mut coeffs);
+
+            let res = AllocatedNum::alloc(cs.namespace(|| format!("column {}", j)), || {
+                Ok(table[*index.get()?][j])
+            })?;
+
+            // The terms with and without the first bit.
+            let (with_first, without_first) = products.iter().enumerate().fold(
+                (LinearCombination::zero(), LinearCombination::zero()),
+                |(with_first, without_first), (m, product)| {
+                    (
+                        with_first + &product.lc::<Scalar>(one, coeffs[(m << 1) | 1]),
+                        without_first + &product.lc::<Scalar>(one, coeffs[m << 1]),
+                    )
+                },
+            );
+
+            cs.enforce(
+                || format!("column {} lookup", j),
+                |_| with_first,
+                |lc| lc + &bits[0].lc::<Scalar>(one, Scalar::ONE),
+                |lc| lc + res.get_variable() - &without_first,
+            );
+
+            Ok(res)
+        })
+        .collect()
+}
+
+/// Looks up the row of `table` at the index given by `bits`, in
+/// little-endian order, where the entries of the table are allocated. Every
+/// row must have the same number of columns.
+///
+/// Every column is selected with a binary tree of multiplexers, see
+/// [`lookup_witnessed_cost`].
+pub fn lookup_witnessed<Scalar, CS>(
+    mut cs: CS,
+    bits: &[Boolean],
+    table: &[Vec<AllocatedNum<Scalar>>],
+) -> Result<Vec<AllocatedNum<Scalar>>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    assert_eq!(table.len(), 1 << bits.len());
+    let width = table[0].len();
+    assert!(table.iter().all(|row| row.len() == width));
+
+    (0..width)
+        .map(|j| {
+            let column = table.iter().map(|row| row[j].clone()).collect::<Vec<_>>();
+            select_by_bits(cs.namespace(|| format!("column {}", j)), &column, bits)
+        })
+        .collect()
+}
+
+/// The number of constraints of [`lookup`] with a window of `window_size`
+/// bits and `width` columns, if none of the bits are constant.
+pub fn lookup_cost(window_size: usize, width: usize) -> usize {
+    assert!(window_size >= 1);
+    (1 << (window_size - 1)) - window_size + width
+}
+
+/// The number of constraints of [`lookup_witnessed`] with a window of
+/// `window_size` bits and `width` columns, if none of the bits are constant.
+pub fn lookup_witnessed_cost(window_size: usize, width: usize) -> usize {
+    ((1 << window_size) - 1) * width
+}
+
+/// The window size up to `max_window_size` that minimizes the constraints
+/// of covering `num_bits` bits with windows that cost `window_cost(window_size)`
+/// constraints each, e.g. a [`lookup_cost`] and the cost of accumulating the
+/// result. Ties go to the smaller window, whose table is smaller.
+pub fn optimal_window_size<F>(num_bits: usize, max_window_size: usize, window_cost: F) -> usize
+where
+    F: Fn(usize) -> usize,
+{
+    (1..=max_window_size)
+        .min_by_key(|&window_size| {
+            let num_windows = (num_bits + window_size - 1) / window_size;
+            (num_windows * window_cost(window_size), window_size)
+        })
+        .expect("at least one window size")
+}
+
 /// Performs a 3-bit window table lookup. `bits` is in
 /// little-endian order.
 pub fn lookup3_xy<Scalar: PrimeField, CS>(
@@ -287,6 +427,131 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_lookup() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for window_size in 1..=5 {
+            for width in 1..=3 {
+                let table = (0..1 << window_size)
+                    .map(|_| (0..width).map(|_| Fr::random(&mut rng)).collect())
+                    .collect::<Vec<Vec<Fr>>>();
+                let index = rng.next_u32() as usize % (1 << window_size);
+
+                let mut cs = TestConstraintSystem::<Fr>::new();
+                let bits = (0..window_size)
+                    .map(|i| {
+                        Boolean::from(
+                            AllocatedBit::alloc(
+                                cs.namespace(|| format!("bit {}", i)),
+                                Some((index >> i) & 1 == 1),
+                            )
+                            .unwrap(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                let res = lookup(cs.namespace(|| "lookup"), &bits, &table).unwrap();
+                assert!(cs.is_satisfied());
+                assert_eq!(
+                    cs.num_constraints(),
+                    window_size + lookup_cost(window_size, width)
+                );
+                let values = res
+                    .iter()
+                    .map(|num| num.get_value().unwrap())
+                    .collect::<Vec<_>>();
+                assert_eq!(values, table[index]);
+
+                // Every other row is rejected.
+                let other = (index + 1) % (1 << window_size);
+                cs.set("lookup/column 0/num", table[other][0]);
+                assert_eq!(cs.which_is_unsatisfied(), Some("lookup/column 0 lookup"));
+
+                // Constant bits are cheaper.
+                let mut cs = TestConstraintSystem::<Fr>::new();
+                let bits = (0..window_size)
+                    .map(|i| Boolean::constant((index >> i) & 1 == 1))
+                    .collect::<Vec<_>>();
+                let res = lookup(cs.namespace(|| "lookup"), &bits, &table).unwrap();
+                assert!(cs.is_satisfied());
+                assert_eq!(cs.num_constraints(), width);
+                assert_eq!(res[0].get_value().unwrap(), table[index][0]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lookup_witnessed() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for window_size in 1..=4 {
+            let width = 2;
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let table = (0..1 << window_size)
+                .map(|i| {
+                    (0..width)
+                        .map(|j| {
+                            let value = Fr::random(&mut rng);
+                            AllocatedNum::alloc(
+                                cs.namespace(|| format!("entry {} {}", i, j)),
+                                || Ok(value),
+                            )
+                            .unwrap()
+                        })
+                        .collect()
+                })
+                .collect::<Vec<Vec<_>>>();
+            let index = rng.next_u32() as usize % (1 << window_size);
+            let bits = (0..window_size)
+                .map(|i| {
+                    Boolean::from(
+                        AllocatedBit::alloc(
+                            cs.namespace(|| format!("bit {}", i)),
+                            Some((index >> i) & 1 == 1),
+                        )
+                        .unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            let res = lookup_witnessed(cs.namespace(|| "lookup"), &bits, &table).unwrap();
+            assert!(cs.is_satisfied());
+            assert_eq!(
+                cs.num_constraints(),
+                window_size + lookup_witnessed_cost(window_size, width)
+            );
+            for (res, entry) in res.iter().zip(&table[index]) {
+                assert_eq!(res.get_value(), entry.get_value());
+            }
+        }
+    }
+
+    #[test]
+    fn test_optimal_window_size() {
+        // Fixed-base multiplication of 252 bits, with two coordinates and
+        // an addition of 6 constraints per window: 3 and 4 bit windows tie
+        // at 756 constraints.
+        let window_size =
+            optimal_window_size(252, 8, |window_size| lookup_cost(window_size, 2) + 6);
+        assert_eq!(window_size, 3);
+        assert_eq!(lookup_cost(3, 2), 3);
+
+        // Without overhead, 2-bit windows need one constraint per window,
+        // and witnessed tables are cheapest bit by bit.
+        assert_eq!(optimal_window_size(64, 8, |w| lookup_cost(w, 1)), 2);
+        assert_eq!(
+            optimal_window_size(64, 8, |w| lookup_witnessed_cost(w, 1)),
+            1
+        );
+    }
+
     #[allow(clippy::needless_range_loop)]
     #[test]
     fn test_synth() {
@@ -316,4 +581,47 @@ mod test {
             assert_eq!(acc, constants[b]);
         }
     }
+
+    #[test]
+    fn test_enforce_lookup() {
+        use crate::{LinearCombination, LookupConstraintSystem, LookupTable};
+
+        // The S-box of PRESENT.
+        let sbox = [
+            0xc, 0x5, 0x6, 0xb, 0x9, 0x0, 0xa, 0xd, 0x3, 0xe, 0xf, 0x8, 0x4, 0x7, 0x1, 0x2,
+        ];
+        let rows = sbox
+            .iter()
+            .enumerate()
+            .map(|(x, y)| vec![Fr::from(x as u64), Fr::from(*y)])
+            .collect();
+        let table = LookupTable::new("present", rows).unwrap();
+        assert_eq!(table.width(), 2);
+
+        for x in 0..16 {
+            for y in 0..16 {
+                let mut cs = TestConstraintSystem::<Fr>::new();
+                cs.register_table(&table);
+                let x_num = AllocatedNum::alloc(cs.namespace(|| "x"), || Ok(Fr::from(x))).unwrap();
+                let y_num = AllocatedNum::alloc(cs.namespace(|| "y"), || Ok(Fr::from(y))).unwrap();
+                let row = [
+                    LinearCombination::zero() + x_num.get_variable(),
+                    LinearCombination::zero() + y_num.get_variable(),
+                ];
+                cs.enforce_lookup(|| "sbox", &table, &row, || Ok(x as usize))
+                    .unwrap();
+
+                assert_eq!(cs.num_constraints(), 16 + 1 + 2);
+                assert_eq!(cs.is_satisfied(), sbox[x as usize] == y);
+            }
+        }
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let row = [LinearCombination::zero() + TestConstraintSystem::<Fr>::one()];
+        assert!(cs
+            .enforce_lookup(|| "width", &table, &row, || Ok(0))
+            .is_err());
+        assert!(LookupTable::new("ragged", vec![vec![Fr::ONE], vec![]]).is_err());
+        assert!(LookupTable::<Fr>::new("empty", vec![]).is_err());
+    }
 }
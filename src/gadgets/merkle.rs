@@ -0,0 +1,474 @@
+//! Gadgets for Merkle trees over field elements, such as Poseidon trees.
+//!
+//! The hash function is provided through [`MerkleHasher`]. SHA-256 and BLAKE2s
+//! are implemented by [`Sha256Hasher`] and [`Blake2sHasher`]. This crate
+//! doesn't contain a Poseidon gadget itself, it can be plugged in by wrapping
+//! the Poseidon gadget of `neptune`.
+
+use ff::{PrimeField, PrimeFieldBits};
+
+use super::blake2s::blake2s;
+use super::boolean::Boolean;
+use super::multipack;
+use super::num::AllocatedNum;
+use super::select::select_by_bits;
+use super::sha256::sha256;
+use crate::{ConstraintSystem, SynthesisError};
+
+/// A hash gadget compressing the children of a Merkle tree node.
+pub trait MerkleHasher<Scalar: PrimeField> {
+    /// Hashes the `children` of a node, separated from other uses of the
+    /// hash function by the domain `tag`.
+    fn hash<CS>(
+        &self,
+        cs: CS,
+        tag: Scalar,
+        children: &[AllocatedNum<Scalar>],
+    ) -> Result<AllocatedNum<Scalar>, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>;
+
+    /// The domain separation tag of the nodes at `level`, where the parents
+    /// of the leaves are at level 0.
+    ///
+    /// Defaults to `level * 2^8 + arity`, override it to match the layout
+    /// of an existing tree.
+    fn level_tag(&self, arity: usize, level: usize) -> Scalar {
+        Scalar::from(((level as u64) << 8) | arity as u64)
+    }
+}
+
+/// Hashes the tag and the children with SHA-256, each encoded as a
+/// little-endian number of `ceil(NUM_BITS / 8)` bytes. The digest is read as a
+/// little-endian number and truncated to the capacity of the field.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sha256Hasher;
+
+impl<Scalar: PrimeFieldBits> MerkleHasher<Scalar> for Sha256Hasher {
+    fn hash<CS>(
+        &self,
+        mut cs: CS,
+        tag: Scalar,
+        children: &[AllocatedNum<Scalar>],
+    ) -> Result<AllocatedNum<Scalar>, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        // SHA-256 takes the bits of every byte most significant bit first.
+        let input = input_bits(cs.namespace(|| "input"), tag, children)?
+            .chunks(8)
+            .flat_map(|byte| byte.iter().rev().cloned())
+            .collect::<Vec<_>>();
+        let digest = sha256(cs.namespace(|| "sha256"), &input)?
+            .chunks(8)
+            .flat_map(|byte| byte.iter().rev().cloned())
+            .collect::<Vec<_>>();
+        pack_digest(cs.namespace(|| "digest"), &digest)
+    }
+}
+
+/// Hashes the tag and the children with BLAKE2s and the given
+/// personalization, with the encoding of [`Sha256Hasher`].
+#[derive(Clone, Copy, Debug)]
+pub struct Blake2sHasher {
+    /// The BLAKE2s personalization.
+    pub personalization: [u8; 8],
+}
+
+impl<Scalar: PrimeFieldBits> MerkleHasher<Scalar> for Blake2sHasher {
+    fn hash<CS>(
+        &self,
+        mut cs: CS,
+        tag: Scalar,
+        children: &[AllocatedNum<Scalar>],
+    ) -> Result<AllocatedNum<Scalar>, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let input = input_bits(cs.namespace(|| "input"), tag, children)?;
+        let digest = blake2s(cs.namespace(|| "blake2s"), &input, &self.personalization)?;
+        pack_digest(cs.namespace(|| "digest"), &digest)
+    }
+}
+
+/// The bits of the tag and the children, least significant bit first, each
+/// padded to whole bytes.
+fn input_bits<Scalar, CS>(
+    mut cs: CS,
+    tag: Scalar,
+    children: &[AllocatedNum<Scalar>],
+) -> Result<Vec<Boolean>, SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: ConstraintSystem<Scalar>,
+{
+    let element_bits = (Scalar::NUM_BITS as usize + 7) / 8 * 8;
+
+    let mut bits = tag
+        .to_le_bits()
+        .into_iter()
+        .map(Boolean::constant)
+        .collect::<Vec<_>>();
+    bits.resize(element_bits, Boolean::constant(false));
+    for (i, child) in children.iter().enumerate() {
+        let mut child_bits = child.to_bits_le_strict(cs.namespace(|| format!("child {}", i)))?;
+        child_bits.resize(element_bits, Boolean::constant(false));
+        bits.extend(child_bits);
+    }
+
+    Ok(bits)
+}
+
+/// Packs the first `CAPACITY` bits of a digest, least significant bit first.
+fn pack_digest<Scalar, CS>(
+    cs: CS,
+    digest: &[Boolean],
+) -> Result<AllocatedNum<Scalar>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    multipack::pack_bits(cs, &digest[..Scalar::CAPACITY as usize])
+}
+
+/// Computes the root of the Merkle tree of the given `arity` that contains
+/// `leaf` at the position given by `index_bits`, in little-endian order.
+///
+/// `siblings` holds the `arity - 1` siblings of the node on the path at each
+/// level, starting with the siblings of the leaf. Every level consumes
+/// `log2(arity)` index bits.
+pub fn merkle_root<Scalar, CS, H>(
+    mut cs: CS,
+    hasher: &H,
+    arity: usize,
+    leaf: &AllocatedNum<Scalar>,
+    index_bits: &[Boolean],
+    siblings: &[Vec<AllocatedNum<Scalar>>],
+) -> Result<AllocatedNum<Scalar>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+    H: MerkleHasher<Scalar>,
+{
+    assert!(arity >= 2 && arity.is_power_of_two());
+    let bits_per_level = arity.trailing_zeros() as usize;
+    assert_eq!(index_bits.len(), siblings.len() * bits_per_level);
+
+    let mut cur = leaf.clone();
+    for (level, (level_siblings, bits)) in siblings
+        .iter()
+        .zip(index_bits.chunks(bits_per_level))
+        .enumerate()
+    {
+        assert_eq!(level_siblings.len(), arity - 1);
+        let mut cs = cs.namespace(|| format!("level {}", level));
+
+        // The child at `position` is the current node if it is the one
+        // selected by the index bits, otherwise it's one of the siblings,
+        // which are ordered around the current node.
+        let children = (0..arity)
+            .map(|position| {
+                let candidates = (0..arity)
+                    .map(|index| match index.cmp(&position) {
+                        std::cmp::Ordering::Equal => cur.clone(),
+                        std::cmp::Ordering::Less => level_siblings[position - 1].clone(),
+                        std::cmp::Ordering::Greater => level_siblings[position].clone(),
+                    })
+                    .collect::<Vec<_>>();
+                select_by_bits(
+                    cs.namespace(|| format!("child {}", position)),
+                    &candidates,
+                    bits,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        cur = hasher.hash(
+            cs.namespace(|| "hash"),
+            hasher.level_tag(arity, level),
+            &children,
+        )?;
+    }
+
+    Ok(cur)
+}
+
+/// Enforces that `leaf` is at the position given by `index_bits` in the
+/// Merkle tree with the given `root`, see [`merkle_root`] for the layout of
+/// the authentication path.
+pub fn verify_merkle_path<Scalar, CS, H>(
+    mut cs: CS,
+    hasher: &H,
+    arity: usize,
+    leaf: &AllocatedNum<Scalar>,
+    index_bits: &[Boolean],
+    siblings: &[Vec<AllocatedNum<Scalar>>],
+    root: &AllocatedNum<Scalar>,
+) -> Result<(), SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+    H: MerkleHasher<Scalar>,
+{
+    let computed = merkle_root(
+        cs.namespace(|| "root"),
+        hasher,
+        arity,
+        leaf,
+        index_bits,
+        siblings,
+    )?;
+    cs.enforce(
+        || "root equality",
+        |lc| lc + computed.get_variable(),
+        |lc| lc + CS::one(),
+        |lc| lc + root.get_variable(),
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use blake2s_simd::Params as Blake2sParams;
+    use blstrs::Scalar as Fr;
+    use ff::{Field, PrimeField};
+    use rand_core::{RngCore, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+    use sha2::{Digest, Sha256};
+
+    use super::{merkle_root, verify_merkle_path, Blake2sHasher, MerkleHasher, Sha256Hasher};
+    use crate::gadgets::boolean::{AllocatedBit, Boolean};
+    use crate::gadgets::num::AllocatedNum;
+    use crate::gadgets::test::*;
+    use crate::{ConstraintSystem, LinearCombination, SynthesisError};
+
+    /// A position dependent toy hash, `tag + sum (i + 1) * c_i^2`.
+    struct SquareSumHasher;
+
+    fn native_hash(tag: Fr, children: &[Fr]) -> Fr {
+        children
+            .iter()
+            .enumerate()
+            .fold(tag, |acc, (i, c)| acc + Fr::from(i as u64 + 1) * c.square())
+    }
+
+    impl MerkleHasher<Fr> for SquareSumHasher {
+        fn hash<CS>(
+            &self,
+            mut cs: CS,
+            tag: Fr,
+            children: &[AllocatedNum<Fr>],
+        ) -> Result<AllocatedNum<Fr>, SynthesisError>
+        where
+            CS: ConstraintSystem<Fr>,
+        {
+            let squares = children
+                .iter()
+                .enumerate()
+                .map(|(i, c)| c.square(cs.namespace(|| format!("square {}", i))))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let values = children
+                .iter()
+                .map(|c| c.get_value())
+                .collect::<Option<Vec<_>>>();
+            let out = AllocatedNum::alloc(cs.namespace(|| "out"), || {
+                values
+                    .map(|values| native_hash(tag, &values))
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?;
+
+            let sum = squares.iter().enumerate().fold(
+                LinearCombination::zero() + (tag, CS::one()),
+                |lc, (i, square)| lc + (Fr::from(i as u64 + 1), square.get_variable()),
+            );
+            cs.enforce(
+                || "sum",
+                |_| sum,
+                |lc| lc + CS::one(),
+                |lc| lc + out.get_variable(),
+            );
+
+            Ok(out)
+        }
+    }
+
+    #[test]
+    fn test_merkle_root() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for (arity, depth) in [(2usize, 4usize), (4, 3), (8, 2)] {
+            let bits_per_level = arity.trailing_zeros() as usize;
+            let leaf = Fr::random(&mut rng);
+            let index = rng.next_u32() as usize % arity.pow(depth as u32);
+            let siblings = (0..depth)
+                .map(|_| {
+                    (0..arity - 1)
+                        .map(|_| Fr::random(&mut rng))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+
+            let mut expected = leaf;
+            for (level, level_siblings) in siblings.iter().enumerate() {
+                let position = (index / arity.pow(level as u32)) % arity;
+                let mut children = level_siblings.clone();
+                children.insert(position, expected);
+                let tag = SquareSumHasher.level_tag(arity, level);
+                expected = native_hash(tag, &children);
+            }
+
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let leaf_num = AllocatedNum::alloc(cs.namespace(|| "leaf"), || Ok(leaf)).unwrap();
+            let index_bits = (0..depth * bits_per_level)
+                .map(|i| {
+                    Boolean::from(
+                        AllocatedBit::alloc(
+                            cs.namespace(|| format!("index bit {}", i)),
+                            Some((index >> i) & 1 == 1),
+                        )
+                        .unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let sibling_nums = siblings
+                .iter()
+                .enumerate()
+                .map(|(level, level_siblings)| {
+                    level_siblings
+                        .iter()
+                        .enumerate()
+                        .map(|(i, s)| {
+                            AllocatedNum::alloc(
+                                cs.namespace(|| format!("sibling {} {}", level, i)),
+                                || Ok(*s),
+                            )
+                            .unwrap()
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+
+            let root = merkle_root(
+                cs.namespace(|| "merkle"),
+                &SquareSumHasher,
+                arity,
+                &leaf_num,
+                &index_bits,
+                &sibling_nums,
+            )
+            .unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(root.get_value().unwrap(), expected);
+
+            // The witness is bound to the index bits.
+            let flipped = if index & 1 == 1 { Fr::ZERO } else { Fr::ONE };
+            cs.set("index bit 0/boolean", flipped);
+            assert!(!cs.is_satisfied());
+        }
+    }
+
+    /// Reads a digest as a little-endian number truncated to 254 bits.
+    fn native_digest(digest: &[u8]) -> Fr {
+        let mut repr = [0u8; 32];
+        repr.copy_from_slice(digest);
+        repr[31] &= 0x3f;
+        Fr::from_repr(repr).unwrap()
+    }
+
+    fn check_merkle_path<H: MerkleHasher<Fr>>(hasher: &H, native_hash: impl Fn(Fr, &[Fr]) -> Fr) {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let (arity, depth, index) = (2, 2, 2);
+        let leaf = Fr::random(&mut rng);
+        let siblings = (0..depth).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+
+        let mut expected = leaf;
+        for (level, sibling) in siblings.iter().enumerate() {
+            let children = if (index >> level) & 1 == 1 {
+                [*sibling, expected]
+            } else {
+                [expected, *sibling]
+            };
+            expected = native_hash(hasher.level_tag(arity, level), &children);
+        }
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let leaf_num = AllocatedNum::alloc(cs.namespace(|| "leaf"), || Ok(leaf)).unwrap();
+        let index_bits = (0..depth)
+            .map(|i| {
+                Boolean::from(
+                    AllocatedBit::alloc(
+                        cs.namespace(|| format!("index bit {}", i)),
+                        Some((index >> i) & 1 == 1),
+                    )
+                    .unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let sibling_nums = siblings
+            .iter()
+            .enumerate()
+            .map(|(level, s)| {
+                vec![
+                    AllocatedNum::alloc(cs.namespace(|| format!("sibling {}", level)), || Ok(*s))
+                        .unwrap(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let root = AllocatedNum::alloc(cs.namespace(|| "root"), || Ok(expected)).unwrap();
+
+        verify_merkle_path(
+            cs.namespace(|| "merkle"),
+            hasher,
+            arity,
+            &leaf_num,
+            &index_bits,
+            &sibling_nums,
+            &root,
+        )
+        .unwrap();
+        assert!(cs.is_satisfied());
+
+        cs.set("root/num", expected + Fr::ONE);
+        assert_eq!(cs.which_is_unsatisfied(), Some("merkle/root equality"));
+    }
+
+    #[test]
+    fn test_verify_merkle_path_sha256() {
+        check_merkle_path(&Sha256Hasher, |tag, children| {
+            let mut h = Sha256::new();
+            h.update(tag.to_repr());
+            for child in children {
+                h.update(child.to_repr());
+            }
+            native_digest(&h.finalize())
+        });
+    }
+
+    #[test]
+    fn test_verify_merkle_path_blake2s() {
+        let hasher = Blake2sHasher {
+            personalization: *b"MerkleBS",
+        };
+        check_merkle_path(&hasher, |tag, children| {
+            let mut h = Blake2sParams::new()
+                .hash_length(32)
+                .personal(&hasher.personalization)
+                .to_state();
+            h.update(&tag.to_repr());
+            for child in children {
+                h.update(&child.to_repr());
+            }
+            native_digest(h.finalize().as_bytes())
+        });
+    }
+}
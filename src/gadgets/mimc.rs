@@ -0,0 +1,323 @@
+//! Gadgets for the MiMC permutations over the scalar field, see
+//! <https://eprint.iacr.org/2016/492>.
+//!
+//! [`mimc`] is MiMC-p/p, the block cipher that maps `x` to `x + k` after the
+//! rounds `x := (x + k + c_i)^e`. It's only a permutation if `e` is coprime to
+//! `p - 1`, which rules out `e = 3` for BLS12-381 but not `e = 5`.
+//! [`mimc_feistel`] is MiMC-2p/p, the Feistel network with the rounds
+//! `xL, xR := xR + (xL + k + c_i)^e, xL`, which is a permutation for any
+//! exponent, such as `LongsightF322p3` of the first zk-SNARK examples.
+//!
+//! The round constants are given by [`MimcParams`] to be compatible with
+//! existing deployments. A round costs one constraint per squaring and per
+//! multiplication of the exponentiation, e.g. 2 constraints for `e = 3` and 3
+//! constraints for `e = 5`.
+
+use ff::PrimeField;
+
+use super::num::AllocatedNum;
+use super::Assignment;
+use crate::{ConstraintSystem, LinearCombination, SynthesisError};
+
+/// The exponent and the round constants of a MiMC permutation.
+#[derive(Clone, Debug)]
+pub struct MimcParams<Scalar: PrimeField> {
+    exponent: u64,
+    constants: Vec<Scalar>,
+}
+
+impl<Scalar: PrimeField> MimcParams<Scalar> {
+    /// Creates the parameters with one round per constant. The first constant
+    /// is usually zero.
+    ///
+    /// Panics unless the exponent is odd and at least 3.
+    pub fn new(exponent: u64, constants: Vec<Scalar>) -> Self {
+        assert!(exponent >= 3 && exponent % 2 == 1);
+        MimcParams {
+            exponent,
+            constants,
+        }
+    }
+
+    pub fn exponent(&self) -> u64 {
+        self.exponent
+    }
+
+    pub fn constants(&self) -> &[Scalar] {
+        &self.constants
+    }
+
+    /// MiMC-p/p with the key `key`, computed natively.
+    pub fn encrypt(&self, x: Scalar, key: Scalar) -> Scalar {
+        self.constants
+            .iter()
+            .fold(x, |x, c| (x + key + c).pow_vartime([self.exponent]))
+            + key
+    }
+
+    /// MiMC-2p/p with the key `key`, computed natively.
+    pub fn feistel(&self, xl: Scalar, xr: Scalar, key: Scalar) -> (Scalar, Scalar) {
+        self.constants.iter().fold((xl, xr), |(xl, xr), c| {
+            (xr + (xl + key + c).pow_vartime([self.exponent]), xl)
+        })
+    }
+}
+
+/// MiMC-p/p of `x`, with the key `key` if there is one, which is the same as
+/// the zero key.
+pub fn mimc<Scalar, CS>(
+    mut cs: CS,
+    params: &MimcParams<Scalar>,
+    x: &AllocatedNum<Scalar>,
+    key: Option<&AllocatedNum<Scalar>>,
+) -> Result<AllocatedNum<Scalar>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    let (key_lc, key_value) = key_terms(key);
+
+    let mut x = x.clone();
+    for (i, c) in params.constants.iter().enumerate() {
+        let base = key_lc.clone() + x.get_variable() + (*c, CS::one());
+        let base_value = x.get_value().zip(key_value).map(|(x, k)| x + k + c);
+
+        // The key is added to the output of the last round.
+        let (offset, offset_value) = if i + 1 == params.constants.len() {
+            (key_lc.clone(), key_value)
+        } else {
+            (LinearCombination::zero(), Some(Scalar::ZERO))
+        };
+
+        x = pow_plus(
+            cs.namespace(|| format!("round {}", i)),
+            params.exponent,
+            &base,
+            base_value,
+            &offset,
+            offset_value,
+        )?;
+    }
+
+    Ok(x)
+}
+
+/// MiMC-2p/p of `(xl, xr)`, with the key `key` if there is one, which is the
+/// same as the zero key.
+pub fn mimc_feistel<Scalar, CS>(
+    mut cs: CS,
+    params: &MimcParams<Scalar>,
+    xl: &AllocatedNum<Scalar>,
+    xr: &AllocatedNum<Scalar>,
+    key: Option<&AllocatedNum<Scalar>>,
+) -> Result<(AllocatedNum<Scalar>, AllocatedNum<Scalar>), SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    let (key_lc, key_value) = key_terms(key);
+
+    let mut xl = xl.clone();
+    let mut xr = xr.clone();
+    for (i, c) in params.constants.iter().enumerate() {
+        let base = key_lc.clone() + xl.get_variable() + (*c, CS::one());
+        let base_value = xl.get_value().zip(key_value).map(|(x, k)| x + k + c);
+
+        let new_xl = pow_plus(
+            cs.namespace(|| format!("round {}", i)),
+            params.exponent,
+            &base,
+            base_value,
+            &(LinearCombination::zero() + xr.get_variable()),
+            xr.get_value(),
+        )?;
+        xr = std::mem::replace(&mut xl, new_xl);
+    }
+
+    Ok((xl, xr))
+}
+
+/// The linear combination and the value of the key, zero if there is none.
+fn key_terms<Scalar: PrimeField>(
+    key: Option<&AllocatedNum<Scalar>>,
+) -> (LinearCombination<Scalar>, Option<Scalar>) {
+    match key {
+        Some(key) => (
+            LinearCombination::zero() + key.get_variable(),
+            key.get_value(),
+        ),
+        None => (LinearCombination::zero(), Some(Scalar::ZERO)),
+    }
+}
+
+/// Allocates `base^exponent + offset` by squaring and multiplying, with the
+/// offset folded into the last multiplication.
+fn pow_plus<Scalar, CS>(
+    mut cs: CS,
+    exponent: u64,
+    base: &LinearCombination<Scalar>,
+    base_value: Option<Scalar>,
+    offset: &LinearCombination<Scalar>,
+    offset_value: Option<Scalar>,
+) -> Result<AllocatedNum<Scalar>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    // Every bit below the most significant one squares the accumulator, and
+    // the set bits also multiply it by the base.
+    let msb = 63 - exponent.leading_zeros();
+    let steps = (0..msb)
+        .rev()
+        .flat_map(|i| {
+            let multiply = (exponent >> i) & 1 == 1;
+            std::iter::once(false).chain(multiply.then_some(true))
+        })
+        .collect::<Vec<_>>();
+
+    let mut acc = base.clone();
+    let mut acc_value = base_value;
+    for (i, &multiply) in steps.iter().enumerate() {
+        let (factor, factor_value) = if multiply {
+            (base.clone(), base_value)
+        } else {
+            (acc.clone(), acc_value)
+        };
+        let product_value = acc_value.zip(factor_value).map(|(a, b)| a * b);
+
+        if i + 1 == steps.len() {
+            let result = AllocatedNum::alloc(cs.namespace(|| "result"), || {
+                Ok(*product_value.get()? + offset_value.get()?)
+            })?;
+            cs.enforce(
+                || "result product",
+                |lc| lc + &acc,
+                |lc| lc + &factor,
+                |lc| lc + result.get_variable() - offset,
+            );
+            return Ok(result);
+        }
+
+        let product = cs.alloc(
+            || format!("step {}", i),
+            || product_value.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+        cs.enforce(
+            || format!("step {} product", i),
+            |lc| lc + &acc,
+            |lc| lc + &factor,
+            |lc| lc + product,
+        );
+        acc = LinearCombination::zero() + product;
+        acc_value = product_value;
+    }
+
+    unreachable!("the exponent is at least 3")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+    use ff::Field;
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use crate::gadgets::test::*;
+
+    /// `LongsightF322p3`, as in the MiMC example of the tests.
+    fn longsight(mut xl: Fr, mut xr: Fr, constants: &[Fr]) -> Fr {
+        for c in constants {
+            let tmp = xl + c;
+            (xl, xr) = (xr + tmp.square() * tmp, xl);
+        }
+        xl
+    }
+
+    #[test]
+    fn test_mimc_feistel() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let constants = (0..322).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let params = MimcParams::new(3, constants.clone());
+        let (xl, xr, key) = (
+            Fr::random(&mut rng),
+            Fr::random(&mut rng),
+            Fr::random(&mut rng),
+        );
+        assert_eq!(
+            params.feistel(xl, xr, Fr::ZERO).0,
+            longsight(xl, xr, &constants)
+        );
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let xl_num = AllocatedNum::alloc(cs.namespace(|| "xl"), || Ok(xl)).unwrap();
+        let xr_num = AllocatedNum::alloc(cs.namespace(|| "xr"), || Ok(xr)).unwrap();
+        let key_num = AllocatedNum::alloc(cs.namespace(|| "key"), || Ok(key)).unwrap();
+
+        let (out_l, out_r) =
+            mimc_feistel(cs.namespace(|| "unkeyed"), &params, &xl_num, &xr_num, None).unwrap();
+        assert_eq!(
+            (out_l.get_value().unwrap(), out_r.get_value().unwrap()),
+            params.feistel(xl, xr, Fr::ZERO)
+        );
+        assert_eq!(cs.num_constraints(), 2 * 322);
+
+        let (out_l, out_r) = mimc_feistel(
+            cs.namespace(|| "keyed"),
+            &params,
+            &xl_num,
+            &xr_num,
+            Some(&key_num),
+        )
+        .unwrap();
+        assert_eq!(
+            (out_l.get_value().unwrap(), out_r.get_value().unwrap()),
+            params.feistel(xl, xr, key)
+        );
+        assert!(cs.is_satisfied());
+
+        cs.set("keyed/round 5/step 0", Fr::ONE);
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_mimc() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for exponent in [5, 7] {
+            let constants = std::iter::once(Fr::ZERO)
+                .chain((1..110).map(|_| Fr::random(&mut rng)))
+                .collect();
+            let params = MimcParams::new(exponent, constants);
+            let (x, key) = (Fr::random(&mut rng), Fr::random(&mut rng));
+
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let x_num = AllocatedNum::alloc(cs.namespace(|| "x"), || Ok(x)).unwrap();
+            let key_num = AllocatedNum::alloc(cs.namespace(|| "key"), || Ok(key)).unwrap();
+
+            let out = mimc(cs.namespace(|| "mimc"), &params, &x_num, Some(&key_num)).unwrap();
+            assert!(cs.is_satisfied());
+            assert_eq!(out.get_value().unwrap(), params.encrypt(x, key));
+            let per_round = if exponent == 5 { 3 } else { 4 };
+            assert_eq!(cs.num_constraints(), per_round * 110);
+
+            let out = mimc(cs.namespace(|| "unkeyed"), &params, &x_num, None).unwrap();
+            assert!(cs.is_satisfied());
+            assert_eq!(out.get_value().unwrap(), params.encrypt(x, Fr::ZERO));
+
+            cs.set("mimc/round 109/result/num", Fr::ONE);
+            assert_eq!(
+                cs.which_is_unsatisfied(),
+                Some("mimc/round 109/result product")
+            );
+        }
+    }
+}
@@ -1,11 +1,12 @@
 //! Helpers for packing vectors of bits into scalar field elements.
 
-use ff::PrimeField;
+use ff::{PrimeField, PrimeFieldBits};
 
 use super::boolean::Boolean;
+use super::cmp;
 use super::num::{AllocatedNum, Num};
 use super::Assignment;
-use crate::{ConstraintSystem, SynthesisError};
+use crate::{ConstraintSystem, LinearCombination, SynthesisError};
 
 /// Takes a sequence of booleans and exposes them as compact
 /// public inputs
@@ -104,6 +105,49 @@ where
     Ok(alloc_num)
 }
 
+/// Decomposes `num` into `num_bits` bits, least significant bit first,
+/// enforcing that they are its canonical representation, i.e. that they
+/// compose a number less than the modulus, and that the bits above
+/// `num_bits` are zero. This inverts [`pack_bits`] and the packing of one
+/// public input by [`pack_into_inputs`].
+///
+/// Panics if `num_bits` is greater than the number of bits of the field.
+pub fn unpack_into_bits_strict<Scalar, CS>(
+    mut cs: CS,
+    num: &AllocatedNum<Scalar>,
+    num_bits: usize,
+) -> Result<Vec<Boolean>, SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: ConstraintSystem<Scalar>,
+{
+    assert!(num_bits <= Scalar::NUM_BITS as usize);
+
+    // Fewer bits than the capacity can't compose a number above the modulus.
+    if num_bits < Scalar::CAPACITY as usize {
+        let bits = cmp::decompose(
+            cs,
+            num.get_value(),
+            LinearCombination::zero() + num.get_variable(),
+            num_bits,
+        )?;
+        return Ok(bits.into_iter().map(Boolean::from).collect());
+    }
+
+    let mut bits = num.to_bits_le_strict(cs.namespace(|| "bits"))?;
+    for (i, bit) in bits.iter().enumerate().skip(num_bits) {
+        cs.enforce(
+            || format!("bit {} is zero", i),
+            |_| bit.lc(CS::one(), Scalar::ONE),
+            |lc| lc + CS::one(),
+            |lc| lc,
+        );
+    }
+    bits.truncate(num_bits);
+
+    Ok(bits)
+}
+
 #[test]
 fn test_multipacking() {
     use crate::ConstraintSystem;
@@ -142,3 +186,61 @@ fn test_multipacking() {
         assert!(cs.verify(&expected_inputs));
     }
 }
+
+#[test]
+fn test_unpack_into_bits_strict() {
+    use crate::ConstraintSystem;
+    use blstrs::Scalar as Fr;
+    use ff::Field;
+    use rand_core::{RngCore, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    use crate::gadgets::test::*;
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    for num_bits in [0, 1, 64, 253, 254, 255] {
+        let bits: Vec<bool> = (0..num_bits).map(|_| rng.next_u32() % 2 != 0).collect();
+        let value = if num_bits == 255 {
+            Fr::random(&mut rng)
+        } else {
+            compute_multipacking::<Fr>(&bits)
+                .first()
+                .copied()
+                .unwrap_or(Fr::ZERO)
+        };
+        let expected = value
+            .to_le_bits()
+            .into_iter()
+            .take(num_bits)
+            .collect::<Vec<_>>();
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let input = AllocatedNum::alloc_input(cs.namespace(|| "input"), || Ok(value)).unwrap();
+        let unpacked =
+            unpack_into_bits_strict(cs.namespace(|| "unpack"), &input, num_bits).unwrap();
+
+        assert!(cs.is_satisfied());
+        assert!(cs.verify(&[value]));
+        assert_eq!(
+            unpacked
+                .iter()
+                .map(|bit| bit.get_value().unwrap())
+                .collect::<Vec<_>>(),
+            expected
+        );
+
+        // A number with more bits doesn't unpack.
+        if num_bits < 255 {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let too_large = Fr::from(2).pow_vartime([num_bits as u64]);
+            let input =
+                AllocatedNum::alloc_input(cs.namespace(|| "input"), || Ok(too_large)).unwrap();
+            unpack_into_bits_strict(cs.namespace(|| "unpack"), &input, num_bits).unwrap();
+            assert!(!cs.is_satisfied());
+        }
+    }
+}
@@ -3,9 +3,12 @@
 use ff::{PrimeField, PrimeFieldBits};
 use serde::{Deserialize, Serialize};
 
-use crate::{ConstraintSystem, LinearCombination, SynthesisError, Variable};
+use crate::{
+    ConstraintSystem, LinearCombination, LookupConstraintSystem, SynthesisError, Variable,
+};
 
 use super::Assignment;
+use super::{cmp, range};
 
 use super::boolean::{self, AllocatedBit, Boolean};
 
@@ -25,6 +28,10 @@ impl<Scalar: PrimeField> Clone for AllocatedNum<Scalar> {
 }
 
 impl<Scalar: PrimeField> AllocatedNum<Scalar> {
+    /// The widest numbers [`AllocatedNum::divrem`] accepts, so that `quotient * divisor +
+    /// remainder` can't wrap around the modulus. This is 126 bits for the BLS12-381 scalar field.
+    pub const MAX_DIVREM_BITS: usize = (Scalar::CAPACITY as usize - 1) / 2;
+
     /// Allocate a `Variable(Aux)` in a `ConstraintSystem`.
     pub fn alloc<CS, F>(mut cs: CS, value: F) -> Result<Self, SynthesisError>
     where
@@ -329,6 +336,152 @@ impl<Scalar: PrimeField> AllocatedNum<Scalar> {
         })
     }
 
+    /// Returns the inverse of this number. Zero has no inverse, so it is not
+    /// satisfiable, and fails to synthesize with
+    /// [`SynthesisError::DivisionByZero`].
+    pub fn invert<CS>(&self, mut cs: CS) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let mut value = None;
+
+        let var = cs.alloc(
+            || "inverse num",
+            || {
+                let tmp: Option<Scalar> = self.value.get()?.invert().into();
+                let tmp = tmp.ok_or(SynthesisError::DivisionByZero)?;
+
+                value = Some(tmp);
+
+                Ok(tmp)
+            },
+        )?;
+
+        // Constrain: a * a_inv = 1
+        // which has no solution for zero.
+        cs.enforce(
+            || "inversion constraint",
+            |lc| lc + self.variable,
+            |lc| lc + var,
+            |lc| lc + CS::one(),
+        );
+
+        Ok(AllocatedNum {
+            value,
+            variable: var,
+        })
+    }
+
+    /// Divides this number by `divisor` in the field. Division by zero is
+    /// not satisfiable, and fails to synthesize with
+    /// [`SynthesisError::DivisionByZero`].
+    pub fn div<CS>(&self, mut cs: CS, divisor: &Self) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        // Without it, b * q = a would hold for any q if a and b were zero.
+        divisor.assert_nonzero(cs.namespace(|| "nonzero divisor"))?;
+
+        let mut value = None;
+
+        let var = cs.alloc(
+            || "quotient num",
+            || {
+                let inv: Option<Scalar> = divisor.value.get()?.invert().into();
+                let tmp = *self.value.get()? * inv.ok_or(SynthesisError::DivisionByZero)?;
+
+                value = Some(tmp);
+
+                Ok(tmp)
+            },
+        )?;
+
+        // Constrain: b * q = a
+        cs.enforce(
+            || "division constraint",
+            |lc| lc + divisor.variable,
+            |lc| lc + var,
+            |lc| lc + self.variable,
+        );
+
+        Ok(AllocatedNum {
+            value,
+            variable: var,
+        })
+    }
+
+    /// Enforces that the number fits into `num_bits` bits, see [`range::enforce_range`].
+    pub fn assert_bits<CS>(&self, cs: CS, num_bits: usize) -> Result<(), SynthesisError>
+    where
+        CS: LookupConstraintSystem<Scalar>,
+        Scalar: PrimeFieldBits,
+    {
+        range::enforce_range(cs, self, num_bits)
+    }
+
+    /// Divides this number by `divisor` as integers, returning the quotient and the remainder.
+    /// Both numbers must be known to be less than `2^num_bits`, e.g. with
+    /// [`AllocatedNum::assert_bits`]. Division by zero is not satisfiable.
+    ///
+    /// Panics if `num_bits` is greater than [`AllocatedNum::MAX_DIVREM_BITS`].
+    pub fn divrem<CS>(
+        &self,
+        mut cs: CS,
+        divisor: &Self,
+        num_bits: usize,
+    ) -> Result<(Self, Self), SynthesisError>
+    where
+        CS: LookupConstraintSystem<Scalar>,
+        CS::Root: LookupConstraintSystem<Scalar>,
+        Scalar: PrimeFieldBits,
+    {
+        assert!(
+            num_bits <= Self::MAX_DIVREM_BITS,
+            "division of {}-bit numbers",
+            num_bits
+        );
+
+        let (quotient_value, remainder_value) = match (self.value, divisor.value) {
+            (Some(_), Some(b)) if bool::from(b.is_zero()) => {
+                return Err(SynthesisError::DivisionByZero)
+            }
+            (Some(a), Some(b)) => {
+                let (q, r) = divrem_value(a, b, num_bits);
+                (Some(q), Some(r))
+            }
+            _ => (None, None),
+        };
+
+        let quotient = Self::alloc(cs.namespace(|| "quotient"), || {
+            quotient_value.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let remainder = Self::alloc(cs.namespace(|| "remainder"), || {
+            remainder_value.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        quotient.assert_bits(cs.namespace(|| "quotient range"), num_bits)?;
+        remainder.assert_bits(cs.namespace(|| "remainder range"), num_bits)?;
+
+        // Constrain: quotient * divisor = a - remainder
+        // which holds over the integers as all of them are less than 2^num_bits.
+        cs.enforce(
+            || "division constraint",
+            |lc| lc + quotient.variable,
+            |lc| lc + divisor.variable,
+            |lc| lc + self.variable - remainder.variable,
+        );
+
+        // Constrain: remainder < divisor
+        // which is untrue for a divisor of zero.
+        cmp::enforce_less_than(
+            cs.namespace(|| "remainder bound"),
+            &remainder,
+            divisor,
+            num_bits,
+        )?;
+
+        Ok((quotient, remainder))
+    }
+
     pub fn assert_nonzero<CS>(&self, mut cs: CS) -> Result<(), SynthesisError>
     where
         CS: ConstraintSystem<Scalar>,
@@ -359,6 +512,92 @@ impl<Scalar: PrimeField> AllocatedNum<Scalar> {
         Ok(())
     }
 
+    /// Returns the inverse of this number, or zero if it is zero, together
+    /// with a boolean which is true iff this number is zero.
+    pub fn inverse_or_zero<CS>(&self, mut cs: CS) -> Result<(Self, Boolean), SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let (inv, is_zero) = inverse_and_is_zero_lc(
+            &mut cs,
+            self.value,
+            LinearCombination::zero() + self.variable,
+        )?;
+
+        // Constrain: inv * is_zero = 0
+        // if x is zero, this forces inv to be zero.
+        cs.enforce(
+            || "zero inverse constraint",
+            |lc| lc + inv.variable,
+            |_| is_zero.lc(CS::one(), Scalar::ONE),
+            |lc| lc,
+        );
+
+        Ok((inv, is_zero))
+    }
+
+    /// Returns a boolean which is true iff this number is zero.
+    pub fn is_zero<CS>(&self, cs: CS) -> Result<Boolean, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        is_zero_lc(cs, self.value, LinearCombination::zero() + self.variable)
+    }
+
+    /// Returns a boolean which is true iff this number is equal to `other`.
+    pub fn is_equal<CS>(&self, cs: CS, other: &Self) -> Result<Boolean, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let value = match (self.value, other.value) {
+            (Some(a), Some(b)) => Some(a - b),
+            _ => None,
+        };
+
+        is_zero_lc(
+            cs,
+            value,
+            LinearCombination::zero() + self.variable - other.variable,
+        )
+    }
+
+    /// Returns `a` if the condition is true, and `b` otherwise, with one
+    /// constraint. The condition is a [`Boolean`], so it is constrained to
+    /// be zero or one.
+    pub fn conditionally_select<CS>(
+        mut cs: CS,
+        condition: &Boolean,
+        a: &Self,
+        b: &Self,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        match condition {
+            Boolean::Constant(true) => return Ok(a.clone()),
+            Boolean::Constant(false) => return Ok(b.clone()),
+            _ => {}
+        }
+
+        let c = Self::alloc(cs.namespace(|| "conditional selection result"), || {
+            if *condition.get_value().get()? {
+                Ok(*a.value.get()?)
+            } else {
+                Ok(*b.value.get()?)
+            }
+        })?;
+
+        // Constrain: (a - b) * condition = c - b
+        cs.enforce(
+            || "conditional selection",
+            |lc| lc + a.variable - b.variable,
+            |_| condition.lc(CS::one(), Scalar::ONE),
+            |lc| lc + c.variable - b.variable,
+        );
+
+        Ok(c)
+    }
+
     /// Takes two allocated numbers (a, b) and returns
     /// (b, a) if the condition is true, and (a, b)
     /// otherwise.
@@ -413,6 +652,99 @@ impl<Scalar: PrimeField> AllocatedNum<Scalar> {
     }
 }
 
+/// Long division of the `num_bits` least significant bits of `a` by `b`, both read as integers.
+fn divrem_value<Scalar: PrimeFieldBits>(a: Scalar, b: Scalar, num_bits: usize) -> (Scalar, Scalar) {
+    let less_than = |x: &Scalar, y: &Scalar| {
+        let (x, y) = (x.to_le_bits(), y.to_le_bits());
+        x.iter()
+            .zip(y.iter())
+            .rev()
+            .find(|(x, y)| **x != **y)
+            .map_or(false, |(_, y)| *y)
+    };
+
+    let (mut quotient, mut remainder) = (Scalar::ZERO, Scalar::ZERO);
+    for bit in a.to_le_bits().iter().take(num_bits).rev() {
+        quotient = quotient.double();
+        remainder = remainder.double();
+        if *bit {
+            remainder += Scalar::ONE;
+        }
+        if !less_than(&remainder, &b) {
+            remainder -= b;
+            quotient += Scalar::ONE;
+        }
+    }
+    (quotient, remainder)
+}
+
+/// Returns a boolean which is true iff the linear combination `lc`,
+/// which evaluates to `value`, is zero.
+pub(crate) fn is_zero_lc<Scalar, CS>(
+    cs: CS,
+    value: Option<Scalar>,
+    lc: LinearCombination<Scalar>,
+) -> Result<Boolean, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    Ok(inverse_and_is_zero_lc(cs, value, lc)?.1)
+}
+
+/// Returns a boolean which is true iff the linear combination `lc`,
+/// which evaluates to `value`, is zero, together with the inverse of
+/// `lc` if it isn't. The inverse is only constrained if `lc` is nonzero.
+fn inverse_and_is_zero_lc<Scalar, CS>(
+    mut cs: CS,
+    value: Option<Scalar>,
+    lc: LinearCombination<Scalar>,
+) -> Result<(AllocatedNum<Scalar>, Boolean), SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    let is_zero_value = value.map(|x| bool::from(x.is_zero()));
+
+    // The inverse only needs to exist if the value is nonzero,
+    // so it is left free otherwise.
+    let inv = AllocatedNum::alloc(cs.namespace(|| "inverse"), || {
+        Ok(Option::from(value.get()?.invert()).unwrap_or(Scalar::ZERO))
+    })?;
+    let is_zero = cs.alloc(
+        || "is zero",
+        || {
+            if *is_zero_value.get()? {
+                Ok(Scalar::ONE)
+            } else {
+                Ok(Scalar::ZERO)
+            }
+        },
+    )?;
+
+    // Constrain: x * inv = 1 - is_zero
+    // if x is zero, this forces is_zero to be one.
+    cs.enforce(
+        || "inverse constraint",
+        |zero| zero + &lc,
+        |lc| lc + inv.variable,
+        |lc| lc + CS::one() - is_zero,
+    );
+
+    // Constrain: x * is_zero = 0
+    // if x is nonzero, this forces is_zero to be zero.
+    cs.enforce(
+        || "is zero constraint",
+        |zero| zero + &lc,
+        |lc| lc + is_zero,
+        |lc| lc,
+    );
+
+    let is_zero = AllocatedBit::from_constrained(is_zero, is_zero_value);
+
+    Ok((inv, Boolean::from(is_zero)))
+}
+
 #[derive(Clone)]
 pub struct Num<Scalar: PrimeField> {
     value: Option<Scalar>,
@@ -495,13 +827,13 @@ impl<Scalar: PrimeField> Num<Scalar> {
 mod test {
     use std::ops::{AddAssign, MulAssign, SubAssign};
 
-    use crate::ConstraintSystem;
+    use crate::{ConstraintSystem, SynthesisError};
     use blstrs::Scalar as Fr;
     use ff::{Field, PrimeField, PrimeFieldBits};
-    use rand_core::SeedableRng;
+    use rand_core::{RngCore, SeedableRng};
     use rand_xorshift::XorShiftRng;
 
-    use super::{AllocatedNum, Boolean, Num};
+    use super::{AllocatedBit, AllocatedNum, Boolean, Num};
     use crate::gadgets::test::*;
 
     #[test]
@@ -542,6 +874,94 @@ mod test {
         assert!(!cs.is_satisfied());
     }
 
+    #[test]
+    fn test_num_invert() {
+        let mut cs = TestConstraintSystem::<Fr>::new();
+
+        let n = AllocatedNum::alloc(&mut cs, || Ok(Fr::from(3u64))).unwrap();
+        let inv = n.invert(&mut cs).unwrap();
+
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 1);
+        assert_eq!(inv.value.unwrap() * Fr::from(3u64), Fr::ONE);
+        cs.set("inverse num", Fr::ONE);
+        assert!(!cs.is_satisfied());
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let zero = AllocatedNum::alloc(&mut cs, || Ok(Fr::ZERO)).unwrap();
+        assert!(matches!(
+            zero.invert(&mut cs),
+            Err(SynthesisError::DivisionByZero)
+        ));
+    }
+
+    #[test]
+    fn test_num_division() {
+        let mut cs = TestConstraintSystem::<Fr>::new();
+
+        let a = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Fr::from(12u64))).unwrap();
+        let b = AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(Fr::from(5u64))).unwrap();
+        let q = a.div(&mut cs, &b).unwrap();
+
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 2);
+        assert_eq!(q.value.unwrap() * Fr::from(5u64), Fr::from(12u64));
+        cs.set("quotient num", Fr::from(2u64));
+        assert!(cs.which_is_unsatisfied() == Some("division constraint"));
+
+        // 0 / 0 would be satisfied by any quotient without the nonzero check.
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let a = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Fr::ZERO)).unwrap();
+        let b = AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(Fr::ZERO)).unwrap();
+        assert!(matches!(
+            a.div(&mut cs, &b),
+            Err(SynthesisError::DivisionByZero)
+        ));
+    }
+
+    #[test]
+    fn test_num_conditional_selection() {
+        for condition in [true, false] {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let a = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Fr::from(5u64))).unwrap();
+            let b = AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(Fr::from(7u64))).unwrap();
+            let bit = AllocatedBit::alloc(cs.namespace(|| "condition"), Some(condition)).unwrap();
+            let c = AllocatedNum::conditionally_select(
+                cs.namespace(|| "select"),
+                &Boolean::from(bit),
+                &a,
+                &b,
+            )
+            .unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(cs.num_constraints(), 2);
+            let expected = if condition { 5u64 } else { 7u64 };
+            assert_eq!(c.value.unwrap(), Fr::from(expected));
+
+            // A condition that isn't a bit would select a mix of a and b.
+            cs.set("condition/boolean", Fr::from(2u64));
+            cs.set("select/conditional selection result/num", Fr::from(3u64));
+            assert_eq!(
+                cs.which_is_unsatisfied(),
+                Some("condition/boolean constraint")
+            );
+
+            let c = AllocatedNum::conditionally_select(
+                cs.namespace(|| "constant"),
+                &Boolean::constant(condition),
+                &a,
+                &b,
+            )
+            .unwrap();
+            assert_eq!(
+                c.get_variable(),
+                if condition { a.variable } else { b.variable }
+            );
+        }
+    }
+
     #[test]
     fn test_num_conditional_reversal() {
         let mut rng = XorShiftRng::from_seed([
@@ -597,6 +1017,86 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_num_inverse_or_zero() {
+        {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let n = AllocatedNum::alloc(&mut cs, || Ok(Fr::from(3u64))).unwrap();
+            let (inv, is_zero) = n.inverse_or_zero(&mut cs).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(cs.num_constraints(), 3);
+            assert_eq!(inv.get_value().unwrap(), Fr::from(3u64).invert().unwrap());
+            assert_eq!(is_zero.get_value(), Some(false));
+
+            cs.set("is zero", Fr::ONE);
+            assert!(cs.which_is_unsatisfied() == Some("inverse constraint"));
+        }
+        {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let n = AllocatedNum::alloc(&mut cs, || Ok(Fr::ZERO)).unwrap();
+            let (inv, is_zero) = n.inverse_or_zero(&mut cs).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(inv.get_value().unwrap(), Fr::ZERO);
+            assert_eq!(is_zero.get_value(), Some(true));
+
+            cs.set("inverse/num", Fr::from(5u64));
+            assert!(cs.which_is_unsatisfied() == Some("zero inverse constraint"));
+            cs.set("inverse/num", Fr::ZERO);
+            cs.set("is zero", Fr::ZERO);
+            assert!(cs.which_is_unsatisfied() == Some("inverse constraint"));
+        }
+    }
+
+    #[test]
+    fn test_num_is_zero() {
+        for value in [Fr::ZERO, Fr::ONE, Fr::from(7u64)] {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let n = AllocatedNum::alloc(&mut cs, || Ok(value)).unwrap();
+            let is_zero = n.is_zero(&mut cs).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(cs.num_constraints(), 2);
+            assert_eq!(is_zero.get_value(), Some(bool::from(value.is_zero())));
+
+            // Claiming the opposite result must not be satisfiable.
+            let flipped = if bool::from(value.is_zero()) {
+                Fr::ZERO
+            } else {
+                Fr::ONE
+            };
+            cs.set("is zero", flipped);
+            assert!(!cs.is_satisfied());
+        }
+    }
+
+    #[test]
+    fn test_num_is_equal() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let a_value = Fr::random(&mut rng);
+        for (b_value, expected) in [(a_value, true), (Fr::random(&mut rng), false)] {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let a = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(a_value)).unwrap();
+            let b = AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(b_value)).unwrap();
+            let eq = a.is_equal(cs.namespace(|| "eq"), &b).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(eq.get_value(), Some(expected));
+
+            cs.set("eq/is zero", if expected { Fr::ZERO } else { Fr::ONE });
+            assert!(!cs.is_satisfied());
+        }
+    }
+
     #[test]
     fn test_into_bits_strict() {
         let negone = -Fr::ONE;
@@ -668,6 +1168,80 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_num_divrem() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let from_u128 = |value: u128| {
+            Fr::from((value >> 64) as u64) * Fr::from(2).pow_vartime([64]) + Fr::from(value as u64)
+        };
+        let next_u128 =
+            |rng: &mut XorShiftRng| u128::from(rng.next_u64()) << 64 | u128::from(rng.next_u64());
+
+        // Numbers of the largest supported size, 126 bits, where the product
+        // of the quotient and the divisor must not wrap around the modulus.
+        assert_eq!(AllocatedNum::<Fr>::MAX_DIVREM_BITS, 126);
+        let max = (1u128 << 126) - 1;
+        let mut cases = vec![
+            (max, 1),
+            (max, max),
+            (max, 1 << 125),
+            (1 << 64, (1 << 64) + 1),
+        ];
+        for width in 0..126 {
+            let a = next_u128(&mut rng) & max;
+            let b = ((next_u128(&mut rng) & max) >> width).max(1);
+            cases.push((a, b));
+        }
+
+        for (a, b) in cases {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let a_num = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(from_u128(a))).unwrap();
+            let b_num = AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(from_u128(b))).unwrap();
+            let (q, r) = a_num
+                .divrem(cs.namespace(|| "divrem"), &b_num, 126)
+                .unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(q.get_value(), Some(from_u128(a / b)));
+            assert_eq!(r.get_value(), Some(from_u128(a % b)));
+        }
+
+        // In the field, 100 = 34 * 3 - 2 as well, which the range check of
+        // the remainder has to rule out.
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let a = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Fr::from(100))).unwrap();
+        let b = AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(Fr::from(3))).unwrap();
+        let (q, r) = a.divrem(cs.namespace(|| "divrem"), &b, 16).unwrap();
+        assert_eq!(
+            (q.get_value(), r.get_value()),
+            (Some(Fr::from(33)), Some(Fr::ONE))
+        );
+        assert!(cs.is_satisfied());
+        cs.set("divrem/quotient/num", Fr::from(34));
+        cs.set("divrem/remainder/num", -Fr::from(2));
+        assert!(!cs.is_satisfied());
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let a = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Fr::from(100))).unwrap();
+        let b = AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(Fr::ZERO)).unwrap();
+        assert!(matches!(
+            a.divrem(cs.namespace(|| "divrem"), &b, 16),
+            Err(SynthesisError::DivisionByZero)
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "division of 127-bit numbers")]
+    fn test_num_divrem_too_wide() {
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let a = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Fr::ONE)).unwrap();
+        let _ = a.divrem(cs.namespace(|| "divrem"), &a, 127);
+    }
+
     #[test]
     fn test_num_scale() {
         use crate::{Index, LinearCombination, Variable};
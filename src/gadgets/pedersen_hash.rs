@@ -0,0 +1,412 @@
+//! Pedersen hashes and commitments over twisted Edwards curves, such as the Pedersen hash of
+//! Sapling over JubJub.
+//!
+//! The input, prefixed with a constant personalization, is split into chunks of 3 bits
+//! `(s0, s1, s2)`, which are encoded as `(1 + s0 + 2 s1) (1 - 2 s2)`, and into segments of `c`
+//! chunks. The hash is `sum_i [sum_j enc(m_ij) 16^j] G_i` with a generator `G_i` per segment,
+//! where `c` is the most chunks for which the sums of a segment are distinct modulo the order,
+//! 63 for JubJub.
+//!
+//! In the circuit, the multiple of the generator for each chunk is looked up in a table, which
+//! costs at most 10 constraints per chunk. [`Generators::hash`] computes the same hash outside of
+//! the circuit.
+
+use std::marker::PhantomData;
+
+use blake2s_simd::Params as Blake2sParams;
+use ff::{Field, PrimeField, PrimeFieldBits};
+
+use super::bignat::Nat;
+use super::boolean::Boolean;
+use super::edwards::{self, EdwardsCurve, EdwardsPoint};
+use crate::{ConstraintSystem, SynthesisError};
+
+/// The generators of a Pedersen hash, one per segment of the input.
+pub struct Generators<Scalar: PrimeField, C> {
+    points: Vec<(Scalar, Scalar)>,
+    _curve: PhantomData<C>,
+}
+
+impl<Scalar: PrimeField, C> Clone for Generators<Scalar, C> {
+    fn clone(&self) -> Self {
+        Generators {
+            points: self.points.clone(),
+            _curve: PhantomData,
+        }
+    }
+}
+
+impl<Scalar: PrimeFieldBits, C: EdwardsCurve<Scalar>> Generators<Scalar, C> {
+    /// Generators given as points of the prime order subgroup.
+    ///
+    /// Panics if a point isn't on the curve or is the identity.
+    pub fn new(points: Vec<(Scalar, Scalar)>) -> Self {
+        for point in &points {
+            assert!(
+                C::is_on_curve(*point) && *point != (Scalar::ZERO, Scalar::ONE),
+                "invalid generator"
+            );
+        }
+
+        Generators {
+            points,
+            _curve: PhantomData,
+        }
+    }
+
+    /// Derives `count` generators with unknown discrete logarithms by hashing a counter with
+    /// BLAKE2s, personalized with `domain`, to points of the curve and clearing their cofactor.
+    pub fn derive(domain: &[u8; 8], count: usize) -> Self {
+        let mut points = Vec::with_capacity(count);
+        let mut counter = 0u32;
+        while points.len() < count {
+            let hash = Blake2sParams::new()
+                .hash_length(32)
+                .personal(domain)
+                .hash(&counter.to_le_bytes());
+            if let Some(point) = hash_to_point::<Scalar, C>(hash.as_bytes()) {
+                points.push(point);
+            }
+            counter += 1;
+        }
+
+        Self::new(points)
+    }
+
+    pub fn points(&self) -> &[(Scalar, Scalar)] {
+        &self.points
+    }
+
+    /// The most bits of the personalization and the input that the generators can hash.
+    pub fn max_bits(&self) -> usize {
+        3 * chunks_per_segment::<Scalar, C>() * self.points.len()
+    }
+
+    /// Computes the hash outside of the circuit, see [`pedersen_hash`].
+    ///
+    /// Panics if the personalization and the input are empty or too long.
+    pub fn hash(&self, personalization: &[bool], input: &[bool]) -> (Scalar, Scalar) {
+        let bits = [personalization, input].concat();
+        assert!(!bits.is_empty(), "hash of no bits");
+
+        self.tables(bits.len()).iter().zip(bits.chunks(3)).fold(
+            (Scalar::ZERO, Scalar::ONE),
+            |acc, (table, chunk)| {
+                let index = chunk
+                    .iter()
+                    .rev()
+                    .fold(0, |index, bit| (index << 1) | *bit as usize);
+                C::add_points(acc, table[index])
+            },
+        )
+    }
+
+    /// Computes the commitment outside of the circuit, see [`pedersen_commitment`].
+    pub fn commit(
+        &self,
+        personalization: &[bool],
+        input: &[bool],
+        randomness_base: (Scalar, Scalar),
+        randomness: &[bool],
+    ) -> (Scalar, Scalar) {
+        C::add_points(
+            self.hash(personalization, input),
+            C::mul_point(randomness_base, randomness),
+        )
+    }
+
+    /// The lookup tables of the chunks of `num_bits` bits.
+    fn tables(&self, num_bits: usize) -> Vec<[(Scalar, Scalar); 8]> {
+        assert!(
+            num_bits <= self.max_bits(),
+            "input of {} bits for {} generators",
+            num_bits,
+            self.points.len()
+        );
+
+        let chunks_per_segment = chunks_per_segment::<Scalar, C>();
+        self.points
+            .iter()
+            .flat_map(|generator| {
+                // The chunk j of the segment uses [16^j]G.
+                let mut base = *generator;
+                (0..chunks_per_segment).map(move |_| {
+                    let table = chunk_table::<Scalar, C>(base);
+                    base = (0..4).fold(base, |base, _| C::add_points(base, base));
+                    table
+                })
+            })
+            .take((num_bits + 2) / 3)
+            .collect()
+    }
+}
+
+/// Computes the Pedersen hash of `input`, prefixed with the constant `personalization`, which
+/// costs at most 10 constraints per chunk of 3 bits, see the [module documentation](self).
+///
+/// Panics if the personalization and the input are empty or too long for the generators, see
+/// [`Generators::max_bits`].
+pub fn pedersen_hash<Scalar, C, CS>(
+    mut cs: CS,
+    generators: &Generators<Scalar, C>,
+    personalization: &[bool],
+    input: &[Boolean],
+) -> Result<EdwardsPoint<Scalar, C>, SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    C: EdwardsCurve<Scalar>,
+    CS: ConstraintSystem<Scalar>,
+{
+    let bits = personalization
+        .iter()
+        .map(|bit| Boolean::constant(*bit))
+        .chain(input.iter().cloned())
+        .collect::<Vec<_>>();
+
+    let mut acc: Option<EdwardsPoint<Scalar, C>> = None;
+    for (i, (table, chunk)) in generators
+        .tables(bits.len())
+        .iter()
+        .zip(bits.chunks(3))
+        .enumerate()
+    {
+        let mut cs = cs.namespace(|| format!("chunk {}", i));
+        let mut chunk = chunk.to_vec();
+        chunk.resize(3, Boolean::constant(false));
+
+        let term = EdwardsPoint::lookup(cs.namespace(|| "lookup"), &chunk, table)?;
+        acc = Some(match acc {
+            Some(acc) => acc.add(cs.namespace(|| "add"), &term)?,
+            None => term,
+        });
+    }
+
+    Ok(acc.expect("hash of no bits"))
+}
+
+/// Commits to `input` with the randomness given by its non-empty bits, least significant bit
+/// first, as its Pedersen hash plus `[randomness]randomness_base`. The randomness base should be
+/// derived independently of the generators.
+pub fn pedersen_commitment<Scalar, C, CS>(
+    mut cs: CS,
+    generators: &Generators<Scalar, C>,
+    personalization: &[bool],
+    input: &[Boolean],
+    randomness_base: (Scalar, Scalar),
+    randomness: &[Boolean],
+) -> Result<EdwardsPoint<Scalar, C>, SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    C: EdwardsCurve<Scalar>,
+    CS: ConstraintSystem<Scalar>,
+{
+    let hash = pedersen_hash(cs.namespace(|| "hash"), generators, personalization, input)?;
+    let blinding =
+        EdwardsPoint::mul_fixed_base(cs.namespace(|| "randomness"), randomness_base, randomness)?;
+    hash.add(cs.namespace(|| "commitment"), &blinding)
+}
+
+/// The most chunks per segment for which the sums of their encodings are distinct modulo the
+/// order, i.e. `4 (16^c - 1) / 15 <= (order - 1) / 2`.
+fn chunks_per_segment<Scalar, C>() -> usize
+where
+    Scalar: PrimeFieldBits,
+    C: EdwardsCurve<Scalar>,
+{
+    let half = edwards::order_nat::<Scalar, C>()
+        .divrem(&Nat::new(vec![2]))
+        .0;
+    let (mut max, mut power) = (Nat::default(), Nat::new(vec![4]));
+    let mut chunks = 0;
+    loop {
+        max = max.add(&power);
+        if max > half {
+            return chunks;
+        }
+        power = power.mul(&Nat::new(vec![16]));
+        chunks += 1;
+    }
+}
+
+/// The multiples `[(1 + s0 + 2 s1) (1 - 2 s2)]base`, indexed by `s0 + 2 s1 + 4 s2`.
+fn chunk_table<Scalar, C>(base: (Scalar, Scalar)) -> [(Scalar, Scalar); 8]
+where
+    Scalar: PrimeField,
+    C: EdwardsCurve<Scalar>,
+{
+    let double = C::add_points(base, base);
+    let multiples = [
+        base,
+        double,
+        C::add_points(double, base),
+        C::add_points(double, double),
+    ];
+
+    let mut table = [(Scalar::ZERO, Scalar::ONE); 8];
+    for (i, (x, y)) in multiples.into_iter().enumerate() {
+        table[i] = (x, y);
+        table[i + 4] = (-x, y);
+    }
+    table
+}
+
+/// Interprets 32 bytes as `y`, reduced modulo the field, with the most significant bit as the
+/// sign of `x`, and clears the cofactor of the point. `None` if there is no such point or it is
+/// of small order.
+fn hash_to_point<Scalar, C>(hash: &[u8]) -> Option<(Scalar, Scalar)>
+where
+    Scalar: PrimeField,
+    C: EdwardsCurve<Scalar>,
+{
+    let sign = hash[31] >> 7 == 1;
+    let base = Scalar::from(256);
+    let y = hash
+        .iter()
+        .rev()
+        .enumerate()
+        .fold(Scalar::ZERO, |y, (i, byte)| {
+            let byte = if i == 0 { byte & 0x7f } else { *byte };
+            y * base + Scalar::from(byte as u64)
+        });
+
+    // a x^2 + y^2 = 1 + d x^2 y^2, where a - d y^2 isn't zero as a / d isn't a square.
+    let y2 = y.square();
+    let x2 = (Scalar::ONE - y2) * Option::<Scalar>::from((C::a() - C::d() * y2).invert())?;
+    let x = Option::<Scalar>::from(x2.sqrt())?;
+    let x = if bool::from(x.is_odd()) == sign {
+        x
+    } else {
+        -x
+    };
+
+    let cofactor = (0..64)
+        .map(|i| (C::cofactor() >> i) & 1 == 1)
+        .collect::<Vec<_>>();
+    let point = C::mul_point((x, y), &cofactor);
+    if bool::from(point.0.is_zero()) {
+        None
+    } else {
+        Some(point)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+    use rand_core::{RngCore, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    use crate::gadgets::boolean::AllocatedBit;
+    use crate::gadgets::edwards::JubJub;
+    use crate::gadgets::test::*;
+
+    fn alloc_bits(cs: &mut TestConstraintSystem<Fr>, name: &str, bits: &[bool]) -> Vec<Boolean> {
+        bits.iter()
+            .enumerate()
+            .map(|(i, bit)| {
+                Boolean::from(
+                    AllocatedBit::alloc(cs.namespace(|| format!("{} {}", name, i)), Some(*bit))
+                        .unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pedersen_hash_jubjub() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        assert_eq!(chunks_per_segment::<Fr, JubJub>(), 63);
+        let generators = Generators::<Fr, JubJub>::derive(b"Test_PH_", 2);
+        let order = JubJub::order().to_le_bits().into_iter().collect::<Vec<_>>();
+        for generator in generators.points() {
+            assert!(JubJub::is_on_curve(*generator));
+            assert_eq!(JubJub::mul_point(*generator, &order), (Fr::ZERO, Fr::ONE));
+        }
+        assert_eq!(generators.max_bits(), 2 * 3 * 63);
+
+        let personalization = [true, false, true, true, false, false];
+        for num_bits in [1, 30, 3 * 63 + 10, 2 * 3 * 63 - personalization.len()] {
+            let input = (0..num_bits)
+                .map(|_| rng.next_u32() & 1 == 1)
+                .collect::<Vec<_>>();
+            let expected = generators.hash(&personalization, &input);
+
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let input_bits = alloc_bits(&mut cs, "input", &input);
+            let hash = pedersen_hash(
+                cs.namespace(|| "hash"),
+                &generators,
+                &personalization,
+                &input_bits,
+            )
+            .unwrap();
+            assert!(cs.is_satisfied());
+            assert_eq!(hash.get_value(), Some(expected));
+            // The lookups multiply the last two bits of a chunk unless either is a constant.
+            let chunks = (personalization.len() + num_bits + 2) / 3;
+            let allocated = |i| i >= personalization.len() && i < personalization.len() + num_bits;
+            let products = (0..chunks)
+                .filter(|k| allocated(3 * k + 1) && allocated(3 * k + 2))
+                .count();
+            assert_eq!(
+                cs.num_constraints(),
+                num_bits + 2 * chunks + products + 7 * (chunks - 1)
+            );
+        }
+
+        // A short input is a single multiple of the first generator.
+        let input = [false, true, true, true, false, false, true, true];
+        let bits = [&personalization[..], &input[..]].concat();
+        let scalar = bits.chunks(3).enumerate().fold(0i128, |sum, (j, chunk)| {
+            let bit = |i| chunk.get(i).copied().unwrap_or(false) as i128;
+            sum + (1 + bit(0) + 2 * bit(1)) * (1 - 2 * bit(2)) * 16i128.pow(j as u32)
+        });
+        let magnitude = (0..128)
+            .map(|i| (scalar.unsigned_abs() >> i) & 1 == 1)
+            .collect::<Vec<_>>();
+        let (x, y) = JubJub::mul_point(generators.points()[0], &magnitude);
+        let expected = if scalar < 0 { (-x, y) } else { (x, y) };
+        assert_eq!(generators.hash(&personalization, &input), expected);
+    }
+
+    #[test]
+    fn test_pedersen_commitment_jubjub() {
+        let generators = Generators::<Fr, JubJub>::derive(b"Test_PH_", 1);
+        let randomness_base = Generators::<Fr, JubJub>::derive(b"Test_PR_", 1).points()[0];
+        let input = [true, false, false, true, true];
+
+        let mut commitments = vec![];
+        for randomness in [[true, false, true, true], [false, true, true, true]] {
+            let expected = generators.commit(&[], &input, randomness_base, &randomness);
+
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let input_bits = alloc_bits(&mut cs, "input", &input);
+            let randomness_bits = alloc_bits(&mut cs, "randomness", &randomness);
+            let commitment = pedersen_commitment(
+                cs.namespace(|| "commitment"),
+                &generators,
+                &[],
+                &input_bits,
+                randomness_base,
+                &randomness_bits,
+            )
+            .unwrap();
+            assert!(cs.is_satisfied());
+            assert_eq!(commitment.get_value(), Some(expected));
+            commitments.push(expected);
+        }
+
+        // The randomness hides the input.
+        assert_ne!(commitments[0], commitments[1]);
+        assert_eq!(
+            generators.commit(&[], &input, randomness_base, &[]),
+            generators.hash(&[], &input)
+        );
+    }
+}
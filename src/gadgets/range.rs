@@ -0,0 +1,187 @@
+//! Gadgets proving that numbers fit into a number of bits.
+
+use ff::PrimeFieldBits;
+
+use crate::{LinearCombination, LookupConstraintSystem, LookupTable, SynthesisError};
+
+use super::boolean::AllocatedBit;
+use super::num::AllocatedNum;
+
+/// The size of the chunks [`enforce_range`] looks up if the backend has native lookups.
+pub const DEFAULT_CHUNK_BITS: usize = 3;
+
+/// How [`enforce_range_with`] proves that a number fits into its bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RangeStrategy {
+    /// Decomposes the number into bits: a constraint per bit and one to pack them.
+    Bits,
+    /// Decomposes the number into chunks of `chunk_bits` bits, the last one may be smaller, and
+    /// looks every chunk up in a table of the numbers of that many bits, plus a constraint to
+    /// pack them. This pays off if lookups are native arguments of the backend, lowered to R1CS a
+    /// lookup costs `2^chunk_bits + 2` constraints.
+    Lookup { chunk_bits: usize },
+}
+
+/// Enforces that `num < 2^num_bits`, with lookups of [`DEFAULT_CHUNK_BITS`] bits if the backend
+/// has native lookups, with a decomposition into bits otherwise.
+pub fn enforce_range<Scalar, CS>(
+    cs: CS,
+    num: &AllocatedNum<Scalar>,
+    num_bits: usize,
+) -> Result<(), SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: LookupConstraintSystem<Scalar>,
+{
+    let strategy = if cs.has_native_lookups() {
+        RangeStrategy::Lookup {
+            chunk_bits: DEFAULT_CHUNK_BITS,
+        }
+    } else {
+        RangeStrategy::Bits
+    };
+    enforce_range_with(cs, num, num_bits, strategy)
+}
+
+/// Enforces that `num < 2^num_bits` with the given strategy.
+///
+/// Panics if `num_bits` isn't less than the capacity of the field, or if the chunks of a lookup
+/// are empty or have more than 16 bits.
+pub fn enforce_range_with<Scalar, CS>(
+    mut cs: CS,
+    num: &AllocatedNum<Scalar>,
+    num_bits: usize,
+    strategy: RangeStrategy,
+) -> Result<(), SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: LookupConstraintSystem<Scalar>,
+{
+    assert!(
+        num_bits < Scalar::CAPACITY as usize,
+        "range check of {} bits",
+        num_bits
+    );
+
+    let bits = num.get_value().map(|value| {
+        value
+            .to_le_bits()
+            .into_iter()
+            .take(num_bits)
+            .collect::<Vec<_>>()
+    });
+    let chunk_bits = match strategy {
+        RangeStrategy::Bits => 1,
+        RangeStrategy::Lookup { chunk_bits } => {
+            assert!(
+                (1..=16).contains(&chunk_bits),
+                "lookups of {}-bit chunks",
+                chunk_bits
+            );
+            chunk_bits
+        }
+    };
+
+    let mut packed = LinearCombination::zero();
+    let mut coeff = Scalar::ONE;
+    for (i, start) in (0..num_bits).step_by(chunk_bits).enumerate() {
+        let width = chunk_bits.min(num_bits - start);
+        let value = bits.as_ref().map(|bits| {
+            bits[start..start + width]
+                .iter()
+                .rev()
+                .fold(0usize, |value, bit| (value << 1) | *bit as usize)
+        });
+
+        let variable = match strategy {
+            RangeStrategy::Bits => AllocatedBit::alloc(
+                cs.namespace(|| format!("bit {}", i)),
+                value.map(|value| value == 1),
+            )?
+            .get_variable(),
+            RangeStrategy::Lookup { .. } => {
+                let chunk = AllocatedNum::alloc(cs.namespace(|| format!("chunk {}", i)), || {
+                    value
+                        .map(|value| Scalar::from(value as u64))
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })?;
+                let table = range_table(width);
+                cs.register_table(&table);
+                cs.enforce_lookup(
+                    || format!("lookup {}", i),
+                    &table,
+                    &[LinearCombination::zero() + chunk.get_variable()],
+                    || value.ok_or(SynthesisError::AssignmentMissing),
+                )?;
+                chunk.get_variable()
+            }
+        };
+
+        packed = packed + (coeff, variable);
+        for _ in 0..width {
+            coeff = coeff.double();
+        }
+    }
+
+    cs.enforce(
+        || "packing constraint",
+        |_| packed,
+        |lc| lc + CS::one(),
+        |lc| lc + num.get_variable(),
+    );
+
+    Ok(())
+}
+
+/// The table of the numbers of `bits` bits, named `range {bits}`.
+fn range_table<Scalar: PrimeFieldBits>(bits: usize) -> LookupTable<Scalar> {
+    let rows = (0..1u64 << bits).map(|i| vec![Scalar::from(i)]).collect();
+    LookupTable::new(format!("range {}", bits), rows).expect("rows of width 1")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+    use ff::Field;
+
+    use crate::gadgets::test::*;
+    use crate::ConstraintSystem;
+
+    #[test]
+    fn test_enforce_range() {
+        let strategies = [
+            (RangeStrategy::Bits, 10 + 1),
+            // Chunks of 3, 3, 3 and 1 bits.
+            (
+                RangeStrategy::Lookup { chunk_bits: 3 },
+                3 * (8 + 2) + (2 + 2) + 1,
+            ),
+            (RangeStrategy::Lookup { chunk_bits: 2 }, 5 * (4 + 2) + 1),
+        ];
+        for (strategy, constraints) in strategies {
+            for (value, fits) in [
+                (Fr::ZERO, true),
+                (Fr::from(1023), true),
+                (Fr::from(0x2aa), true),
+                (Fr::from(1024), false),
+                (-Fr::ONE, false),
+            ] {
+                let mut cs = TestConstraintSystem::<Fr>::new();
+                let num = AllocatedNum::alloc(cs.namespace(|| "num"), || Ok(value)).unwrap();
+                enforce_range_with(cs.namespace(|| "range"), &num, 10, strategy).unwrap();
+                assert_eq!(cs.is_satisfied(), fits, "{:?} of {:?}", strategy, value);
+                assert_eq!(cs.num_constraints(), constraints);
+            }
+        }
+
+        // Without native lookups the bits are cheaper.
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let num = AllocatedNum::alloc(cs.namespace(|| "num"), || Ok(Fr::from(5))).unwrap();
+        assert!(!cs.has_native_lookups());
+        num.assert_bits(cs.namespace(|| "range"), 3).unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 4);
+    }
+}
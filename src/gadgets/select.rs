@@ -0,0 +1,676 @@
+//! Gadgets for selecting an element of a vector by an allocated index,
+//! or one of two values by a condition, and for swapping two values by a
+//! condition.
+
+use ff::{PrimeField, PrimeFieldBits};
+
+use super::boolean::{AllocatedBit, Boolean};
+use super::num::{self, AllocatedNum};
+use super::uint32::{self, UInt32};
+use super::Assignment;
+use crate::{ConstraintSystem, LinearCombination, SynthesisError};
+
+/// The way [`select_by_index`] selects an element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectStrategy {
+    /// Compares the index with every position and sums up the element
+    /// at the matching one. Uses about `3n` constraints.
+    LinearScan,
+    /// Decomposes the index into bits and selects the element with a
+    /// binary tree of multiplexers. Uses about `n + 2 log n` constraints.
+    Mux,
+}
+
+/// Returns `values[index]`. Indices outside of `values` are not
+/// satisfiable.
+pub fn select_by_index<Scalar, CS>(
+    mut cs: CS,
+    values: &[AllocatedNum<Scalar>],
+    index: &AllocatedNum<Scalar>,
+    strategy: SelectStrategy,
+) -> Result<AllocatedNum<Scalar>, SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: ConstraintSystem<Scalar>,
+{
+    assert!(!values.is_empty());
+
+    match strategy {
+        SelectStrategy::LinearScan => linear_scan(cs, values, index),
+        SelectStrategy::Mux => {
+            let num_bits = (usize::BITS - (values.len() - 1).leading_zeros()) as usize;
+            let bits = alloc_bits_le(
+                cs.namespace(|| "index bits"),
+                index.get_value(),
+                LinearCombination::zero() + index.get_variable(),
+                num_bits,
+            )?;
+
+            // The padding is never selected, as `index + 2^k - n` only
+            // fits into `k` bits if `index < n`.
+            let padding = (1 << num_bits) - values.len();
+            if padding > 0 {
+                let offset = Scalar::from(padding as u64);
+                alloc_bits_le(
+                    cs.namespace(|| "range check"),
+                    index.get_value().map(|index| index + offset),
+                    LinearCombination::zero() + index.get_variable() + (offset, CS::one()),
+                    num_bits,
+                )?;
+            }
+
+            let mut padded = values.to_vec();
+            padded.resize(1 << num_bits, values[values.len() - 1].clone());
+
+            select_by_bits(cs.namespace(|| "mux"), &padded, &bits)
+        }
+    }
+}
+
+/// Returns `values[index]`, where `index` is given by its bits in
+/// little-endian order. There must be exactly one value for every
+/// possible index.
+pub fn select_by_bits<Scalar, CS>(
+    mut cs: CS,
+    values: &[AllocatedNum<Scalar>],
+    bits: &[Boolean],
+) -> Result<AllocatedNum<Scalar>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    assert_eq!(values.len(), 1 << bits.len());
+
+    let mut layer = values.to_vec();
+    for (i, bit) in bits.iter().enumerate() {
+        layer = layer
+            .chunks(2)
+            .enumerate()
+            .map(|(j, pair)| {
+                mux(
+                    cs.namespace(|| format!("layer {} mux {}", i, j)),
+                    bit,
+                    &pair[0],
+                    &pair[1],
+                )
+            })
+            .collect::<Result<_, _>>()?;
+    }
+
+    Ok(layer.pop().expect("one value is left"))
+}
+
+/// Values that can be selected by a condition.
+pub trait ConditionallySelectable<Scalar: PrimeField>: Sized {
+    /// Returns `a` if the condition is true, and `b` otherwise.
+    fn conditionally_select<CS>(
+        cs: CS,
+        condition: &Boolean,
+        a: &Self,
+        b: &Self,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>;
+}
+
+impl<Scalar: PrimeField> ConditionallySelectable<Scalar> for Boolean {
+    fn conditionally_select<CS>(
+        cs: CS,
+        condition: &Boolean,
+        a: &Self,
+        b: &Self,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        uint32::select_bit(cs, condition, a, b)
+    }
+}
+
+impl<Scalar: PrimeField> ConditionallySelectable<Scalar> for AllocatedNum<Scalar> {
+    fn conditionally_select<CS>(
+        cs: CS,
+        condition: &Boolean,
+        a: &Self,
+        b: &Self,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        AllocatedNum::conditionally_select(cs, condition, a, b)
+    }
+}
+
+impl<Scalar: PrimeField> ConditionallySelectable<Scalar> for UInt32 {
+    fn conditionally_select<CS>(
+        cs: CS,
+        condition: &Boolean,
+        a: &Self,
+        b: &Self,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        UInt32::conditionally_select(cs, condition, a, b)
+    }
+}
+
+/// Returns `a` if the condition is true, and `b` otherwise, selecting
+/// element by element.
+///
+/// Panics unless the slices have the same length.
+pub fn conditionally_select_slice<Scalar, CS, T>(
+    mut cs: CS,
+    condition: &Boolean,
+    a: &[T],
+    b: &[T],
+) -> Result<Vec<T>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+    T: ConditionallySelectable<Scalar>,
+{
+    assert_eq!(a.len(), b.len());
+
+    a.iter()
+        .zip(b)
+        .enumerate()
+        .map(|(i, (a, b))| {
+            T::conditionally_select(cs.namespace(|| format!("element {}", i)), condition, a, b)
+        })
+        .collect()
+}
+
+/// Values that can be swapped by a condition.
+pub trait ConditionallySwappable<Scalar: PrimeField>: Sized {
+    /// Returns `(b, a)` if the condition is true, and `(a, b)` otherwise,
+    /// with two constraints.
+    fn conditionally_swap<CS>(
+        cs: CS,
+        condition: &Boolean,
+        a: &Self,
+        b: &Self,
+    ) -> Result<(Self, Self), SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>;
+}
+
+impl<Scalar: PrimeField> ConditionallySwappable<Scalar> for AllocatedNum<Scalar> {
+    fn conditionally_swap<CS>(
+        cs: CS,
+        condition: &Boolean,
+        a: &Self,
+        b: &Self,
+    ) -> Result<(Self, Self), SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        AllocatedNum::conditionally_reverse(cs, a, b, condition)
+    }
+}
+
+impl<Scalar: PrimeField> ConditionallySwappable<Scalar> for Boolean {
+    fn conditionally_swap<CS>(
+        mut cs: CS,
+        condition: &Boolean,
+        a: &Self,
+        b: &Self,
+    ) -> Result<(Self, Self), SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        match condition {
+            Boolean::Constant(true) => return Ok((b.clone(), a.clone())),
+            Boolean::Constant(false) => return Ok((a.clone(), b.clone())),
+            _ => {}
+        }
+
+        let x = uint32::select_bit(cs.namespace(|| "first"), condition, b, a)?;
+        if x.is_constant() {
+            // The bits are equal constants.
+            return Ok((x.clone(), x));
+        }
+
+        // Constrain: y = a + b - x
+        // which is the other bit, so it is boolean.
+        let value = match condition.get_value() {
+            Some(true) => a.get_value(),
+            Some(false) => b.get_value(),
+            None => None,
+        };
+        let y = cs.alloc(
+            || "second",
+            || {
+                if *value.get()? {
+                    Ok(Scalar::ONE)
+                } else {
+                    Ok(Scalar::ZERO)
+                }
+            },
+        )?;
+        cs.enforce(
+            || "swap constraint",
+            |_| {
+                a.lc(CS::one(), Scalar::ONE) + &b.lc(CS::one(), Scalar::ONE)
+                    - &x.lc(CS::one(), Scalar::ONE)
+            },
+            |lc| lc + CS::one(),
+            |lc| lc + y,
+        );
+
+        Ok((x, AllocatedBit::from_constrained(y, value).into()))
+    }
+}
+
+/// Returns `(b, a)` if the condition is true, and `(a, b)` otherwise,
+/// swapping element by element, e.g. to order the children of a node on a
+/// Merkle path or in a sorting network.
+///
+/// Panics unless the slices have the same length.
+pub fn conditionally_swap_slice<Scalar, CS, T>(
+    mut cs: CS,
+    condition: &Boolean,
+    a: &[T],
+    b: &[T],
+) -> Result<(Vec<T>, Vec<T>), SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+    T: ConditionallySwappable<Scalar>,
+{
+    assert_eq!(a.len(), b.len());
+
+    let pairs = a
+        .iter()
+        .zip(b)
+        .enumerate()
+        .map(|(i, (a, b))| {
+            T::conditionally_swap(cs.namespace(|| format!("element {}", i)), condition, a, b)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(pairs.into_iter().unzip())
+}
+
+/// Returns `b` if `bit` is set, and `a` otherwise.
+fn mux<Scalar, CS>(
+    mut cs: CS,
+    bit: &Boolean,
+    a: &AllocatedNum<Scalar>,
+    b: &AllocatedNum<Scalar>,
+) -> Result<AllocatedNum<Scalar>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    let out = AllocatedNum::alloc(&mut cs, || {
+        if *bit.get_value().get()? {
+            Ok(*b.get_value().get()?)
+        } else {
+            Ok(*a.get_value().get()?)
+        }
+    })?;
+
+    // Constrain: bit * (b - a) = out - a
+    cs.enforce(
+        || "mux constraint",
+        |_| bit.lc(CS::one(), Scalar::ONE),
+        |lc| lc + b.get_variable() - a.get_variable(),
+        |lc| lc + out.get_variable() - a.get_variable(),
+    );
+
+    Ok(out)
+}
+
+fn linear_scan<Scalar, CS>(
+    mut cs: CS,
+    values: &[AllocatedNum<Scalar>],
+    index: &AllocatedNum<Scalar>,
+) -> Result<AllocatedNum<Scalar>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    let mut matches = LinearCombination::zero();
+    let mut selected = LinearCombination::zero();
+    let mut selected_value = Some(Scalar::ZERO);
+
+    for (i, value) in values.iter().enumerate() {
+        let mut cs = cs.namespace(|| format!("position {}", i));
+        let position = Scalar::from(i as u64);
+
+        let is_match = num::is_zero_lc(
+            cs.namespace(|| "is match"),
+            index.get_value().map(|index| index - position),
+            LinearCombination::zero() + index.get_variable() - (position, CS::one()),
+        )?;
+
+        let product = AllocatedNum::alloc(cs.namespace(|| "product"), || {
+            if *is_match.get_value().get()? {
+                Ok(*value.get_value().get()?)
+            } else {
+                Ok(Scalar::ZERO)
+            }
+        })?;
+
+        // Constrain: is_match * value = product
+        cs.enforce(
+            || "product constraint",
+            |_| is_match.lc(CS::one(), Scalar::ONE),
+            |lc| lc + value.get_variable(),
+            |lc| lc + product.get_variable(),
+        );
+
+        matches = matches + &is_match.lc(CS::one(), Scalar::ONE);
+        selected = selected + product.get_variable();
+        selected_value = selected_value
+            .zip(product.get_value())
+            .map(|(sum, product)| sum + product);
+    }
+
+    // Exactly one position matches iff the index is in range.
+    cs.enforce(
+        || "index range constraint",
+        |_| matches,
+        |lc| lc + CS::one(),
+        |lc| lc + CS::one(),
+    );
+
+    let result = AllocatedNum::alloc(cs.namespace(|| "result"), || Ok(*selected_value.get()?))?;
+
+    cs.enforce(
+        || "result constraint",
+        |_| selected,
+        |lc| lc + CS::one(),
+        |lc| lc + result.get_variable(),
+    );
+
+    Ok(result)
+}
+
+/// Allocates the lowest `num_bits` bits of `value` in little-endian
+/// order, and constrains them to pack into `lc`.
+fn alloc_bits_le<Scalar, CS>(
+    mut cs: CS,
+    value: Option<Scalar>,
+    lc: LinearCombination<Scalar>,
+    num_bits: usize,
+) -> Result<Vec<Boolean>, SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: ConstraintSystem<Scalar>,
+{
+    let value_bits = value.map(|value| value.to_le_bits());
+
+    let mut packed = LinearCombination::zero();
+    let mut coeff = Scalar::ONE;
+    let mut bits = Vec::with_capacity(num_bits);
+    for i in 0..num_bits {
+        let bit = AllocatedBit::alloc(
+            cs.namespace(|| format!("bit {}", i)),
+            value_bits.as_ref().map(|bits| bits[i]),
+        )?;
+        packed = packed + (coeff, bit.get_variable());
+        coeff = coeff.double();
+        bits.push(Boolean::from(bit));
+    }
+
+    cs.enforce(
+        || "packing constraint",
+        |_| packed,
+        |lc| lc + CS::one(),
+        |_| lc,
+    );
+
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod test {
+    use blstrs::Scalar as Fr;
+    use ff::Field;
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use super::{
+        conditionally_select_slice, conditionally_swap_slice, select_by_bits, select_by_index,
+        SelectStrategy,
+    };
+    use crate::gadgets::boolean::{AllocatedBit, Boolean};
+    use crate::gadgets::num::AllocatedNum;
+    use crate::gadgets::test::*;
+    use crate::gadgets::uint32::UInt32;
+    use crate::ConstraintSystem;
+
+    fn alloc_values<CS: ConstraintSystem<Fr>>(cs: &mut CS, values: &[Fr]) -> Vec<AllocatedNum<Fr>> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                AllocatedNum::alloc(cs.namespace(|| format!("value {}", i)), || Ok(*value)).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_select_by_index() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for strategy in [SelectStrategy::LinearScan, SelectStrategy::Mux] {
+            for len in [1, 2, 5, 8] {
+                let values = (0..len).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+
+                for index in 0..len + 2 {
+                    let mut cs = TestConstraintSystem::<Fr>::new();
+
+                    let allocated = alloc_values(&mut cs, &values);
+                    let index_num =
+                        AllocatedNum::alloc(cs.namespace(|| "index"), || Ok(Fr::from(index)))
+                            .unwrap();
+                    let selected = select_by_index(
+                        cs.namespace(|| "select"),
+                        &allocated,
+                        &index_num,
+                        strategy,
+                    )
+                    .unwrap();
+
+                    if index < len {
+                        assert!(cs.is_satisfied());
+                        assert_eq!(selected.get_value().unwrap(), values[index as usize]);
+                    } else {
+                        assert!(!cs.is_satisfied());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_select_by_bits() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let values = (0..8).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        for index in 0..8usize {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let allocated = alloc_values(&mut cs, &values);
+            // The highest bit is given negated, to cover all kinds of booleans.
+            let bits = vec![
+                Boolean::from(
+                    AllocatedBit::alloc(cs.namespace(|| "bit 0"), Some(index & 1 == 1)).unwrap(),
+                ),
+                Boolean::constant(index & 2 == 2),
+                Boolean::from(
+                    AllocatedBit::alloc(cs.namespace(|| "bit 2"), Some(index & 4 == 0)).unwrap(),
+                )
+                .not(),
+            ];
+            let selected = select_by_bits(cs.namespace(|| "select"), &allocated, &bits).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(selected.get_value().unwrap(), values[index]);
+
+            cs.set("select/layer 2 mux 0/num", values[index] + Fr::ONE);
+            assert!(cs.which_is_unsatisfied() == Some("select/layer 2 mux 0/mux constraint"));
+        }
+    }
+
+    #[test]
+    fn test_conditionally_select_slice() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for condition in [true, false] {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let a_values = (0..5).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+            let b_values = (0..5).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+            let a = alloc_values(&mut cs.namespace(|| "a"), &a_values);
+            let b = alloc_values(&mut cs.namespace(|| "b"), &b_values);
+            let words = [0xdead_beef, 0x0123_4567]
+                .iter()
+                .enumerate()
+                .map(|(i, word)| {
+                    UInt32::alloc(cs.namespace(|| format!("word {}", i)), Some(*word)).unwrap()
+                })
+                .collect::<Vec<_>>();
+            let bit = Boolean::from(
+                AllocatedBit::alloc(cs.namespace(|| "condition"), Some(condition)).unwrap(),
+            );
+            let before = cs.num_constraints();
+
+            let selected =
+                conditionally_select_slice(cs.namespace(|| "nums"), &bit, &a, &b).unwrap();
+            assert_eq!(cs.num_constraints() - before, 5);
+            let expected = if condition { &a_values } else { &b_values };
+            for (selected, expected) in selected.iter().zip(expected) {
+                assert_eq!(selected.get_value().unwrap(), *expected);
+            }
+
+            let reversed = [words[1].clone(), words[0].clone()];
+            let selected =
+                conditionally_select_slice(cs.namespace(|| "words"), &bit, &words, &reversed)
+                    .unwrap();
+            let expected = if condition {
+                [0xdead_beef, 0x0123_4567]
+            } else {
+                [0x0123_4567, 0xdead_beef]
+            };
+            assert_eq!(
+                selected
+                    .iter()
+                    .map(|word| {
+                        word.clone()
+                            .into_bits()
+                            .iter()
+                            .rev()
+                            .fold(0u32, |acc, bit| {
+                                (acc << 1) | bit.get_value().unwrap() as u32
+                            })
+                    })
+                    .collect::<Vec<_>>(),
+                expected
+            );
+
+            assert!(cs.is_satisfied());
+            cs.set("nums/element 3/conditional selection result/num", Fr::ONE);
+            assert_eq!(
+                cs.which_is_unsatisfied(),
+                Some("nums/element 3/conditional selection")
+            );
+        }
+    }
+
+    #[test]
+    fn test_conditionally_swap_slice() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for condition in [true, false] {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let a_values = (0..3).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+            let b_values = (0..3).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+            let a = alloc_values(&mut cs.namespace(|| "a"), &a_values);
+            let b = alloc_values(&mut cs.namespace(|| "b"), &b_values);
+            let alloc_bits = |cs: &mut TestConstraintSystem<Fr>, name: &str, values: [bool; 4]| {
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| {
+                        Boolean::from(
+                            AllocatedBit::alloc(
+                                cs.namespace(|| format!("{} {}", name, i)),
+                                Some(*value),
+                            )
+                            .unwrap(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let a_bit_values = [true, true, false, false];
+            let b_bit_values = [true, false, true, false];
+            let mut a_bits = alloc_bits(&mut cs, "a bit", a_bit_values);
+            let b_bits = alloc_bits(&mut cs, "b bit", b_bit_values);
+            a_bits[0] = a_bits[0].not();
+            let a_bit_values = [false, true, false, false];
+            let bit = Boolean::from(
+                AllocatedBit::alloc(cs.namespace(|| "condition"), Some(condition)).unwrap(),
+            );
+            let before = cs.num_constraints();
+
+            let (x, y) = conditionally_swap_slice(cs.namespace(|| "nums"), &bit, &a, &b).unwrap();
+            assert_eq!(cs.num_constraints() - before, 2 * 3);
+            let (expected_x, expected_y) = if condition {
+                (&b_values, &a_values)
+            } else {
+                (&a_values, &b_values)
+            };
+            for (x, expected) in x.iter().zip(expected_x) {
+                assert_eq!(x.get_value().unwrap(), *expected);
+            }
+            for (y, expected) in y.iter().zip(expected_y) {
+                assert_eq!(y.get_value().unwrap(), *expected);
+            }
+
+            let before = cs.num_constraints();
+            let (x, y) =
+                conditionally_swap_slice(cs.namespace(|| "bits"), &bit, &a_bits, &b_bits).unwrap();
+            assert_eq!(cs.num_constraints() - before, 2 * 4);
+            let (expected_x, expected_y) = if condition {
+                (b_bit_values, a_bit_values)
+            } else {
+                (a_bit_values, b_bit_values)
+            };
+            assert_eq!(
+                x.iter().map(|x| x.get_value().unwrap()).collect::<Vec<_>>(),
+                expected_x
+            );
+            assert_eq!(
+                y.iter().map(|y| y.get_value().unwrap()).collect::<Vec<_>>(),
+                expected_y
+            );
+
+            assert!(cs.is_satisfied());
+            cs.set(
+                "bits/element 2/second",
+                Fr::ONE - cs.get("bits/element 2/second"),
+            );
+            assert_eq!(
+                cs.which_is_unsatisfied(),
+                Some("bits/element 2/swap constraint")
+            );
+        }
+    }
+}
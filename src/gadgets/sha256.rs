@@ -10,6 +10,7 @@ use ff::PrimeField;
 use super::boolean::Boolean;
 use super::multieq::MultiEq;
 use super::uint32::UInt32;
+use super::uint8::UInt8;
 use crate::{ConstraintSystem, SynthesisError};
 
 #[allow(clippy::unreadable_literal)]
@@ -76,6 +77,26 @@ where
     Ok(cur.into_iter().flat_map(|e| e.into_bits_be()).collect())
 }
 
+/// Computes the SHA-256 hash of `input`, taking and returning bytes in
+/// the order they are hashed.
+pub fn sha256_bytes<Scalar, CS>(cs: CS, input: &[UInt8]) -> Result<[UInt8; 32], SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    let input_bits = input
+        .iter()
+        .flat_map(|byte| byte.clone().into_bits_be())
+        .collect::<Vec<_>>();
+
+    let hash = sha256(cs, &input_bits)?
+        .chunks(8)
+        .map(UInt8::from_bits_be)
+        .collect::<Vec<_>>();
+
+    Ok(hash.try_into().ok().expect("SHA-256 output is 32 bytes"))
+}
+
 fn get_sha256_iv() -> Vec<UInt32> {
     IV.iter().map(|&v| UInt32::constant(v)).collect()
 }
@@ -414,4 +435,35 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_sha256_bytes() {
+        use sha2::{Digest, Sha256};
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x3d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for input_len in [0, 1, 55, 56, 64, 100] {
+            let data: Vec<u8> = (0..input_len).map(|_| rng.next_u32() as u8).collect();
+            let hash_result = Sha256::digest(&data);
+
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let input = data
+                .iter()
+                .enumerate()
+                .map(|(i, &byte)| {
+                    UInt8::alloc(cs.namespace(|| format!("input byte {}", i)), Some(byte)).unwrap()
+                })
+                .collect::<Vec<_>>();
+
+            let r = sha256_bytes(cs.namespace(|| "sha256"), &input).unwrap();
+
+            assert!(cs.is_satisfied());
+            for (byte, expected) in r.iter().zip(hash_result.iter()) {
+                assert_eq!(byte.get_value(), Some(*expected));
+            }
+        }
+    }
 }
@@ -9,7 +9,10 @@ use blake2s_simd::{Params as Blake2sParams, State as Blake2sState};
 use byteorder::{BigEndian, ByteOrder};
 use ff::PrimeField;
 
-use crate::{ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+pub use crate::util_cs::test_cs::UnsatisfiedConstraint;
+use crate::{
+    ConstraintSystem, Index, LinearCombination, LookupConstraintSystem, SynthesisError, Variable,
+};
 
 #[derive(Debug)]
 enum NamedObject {
@@ -224,20 +227,27 @@ impl<Scalar: PrimeField> TestConstraintSystem<Scalar> {
         s
     }
 
-    pub fn which_is_unsatisfied(&self) -> Option<&str> {
-        for &(ref a, ref b, ref c, ref path) in &self.constraints {
-            let mut a = eval_lc::<Scalar>(a, &self.inputs, &self.aux);
+    fn unsatisfied(&self) -> impl Iterator<Item = UnsatisfiedConstraint<'_, Scalar>> {
+        self.constraints.iter().filter_map(move |(a, b, c, path)| {
+            let a = eval_lc::<Scalar>(a, &self.inputs, &self.aux);
             let b = eval_lc::<Scalar>(b, &self.inputs, &self.aux);
             let c = eval_lc::<Scalar>(c, &self.inputs, &self.aux);
 
-            a.mul_assign(&b);
-
-            if a != c {
-                return Some(path);
+            if a * b != c {
+                Some(UnsatisfiedConstraint { path, a, b, c })
+            } else {
+                None
             }
-        }
+        })
+    }
 
-        None
+    pub fn which_is_unsatisfied(&self) -> Option<&str> {
+        self.unsatisfied().next().map(|constraint| constraint.path)
+    }
+
+    /// Returns every constraint that doesn't hold, in the order they were enforced.
+    pub fn all_unsatisfied(&self) -> Vec<UnsatisfiedConstraint<'_, Scalar>> {
+        self.unsatisfied().collect()
     }
 
     pub fn is_satisfied(&self) -> bool {
@@ -356,7 +366,8 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for TestConstraintSystem<Scala
     {
         let index = self.aux.len();
         let path = compute_path(&self.current_namespace, annotation().into());
-        self.aux.push((f()?, path.clone()));
+        let value = f().map_err(|e| e.at_path(path.clone()))?;
+        self.aux.push((value, path.clone()));
         let var = Variable::new_unchecked(Index::Aux(index));
         self.set_named_obj(path, NamedObject::Var(var));
 
@@ -371,7 +382,8 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for TestConstraintSystem<Scala
     {
         let index = self.inputs.len();
         let path = compute_path(&self.current_namespace, annotation().into());
-        self.inputs.push((f()?, path.clone()));
+        let value = f().map_err(|e| e.at_path(path.clone()))?;
+        self.inputs.push((value, path.clone()));
         let var = Variable::new_unchecked(Index::Input(index));
         self.set_named_obj(path, NamedObject::Var(var));
 
@@ -417,6 +429,8 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for TestConstraintSystem<Scala
     }
 }
 
+impl<Scalar: PrimeField> LookupConstraintSystem<Scalar> for TestConstraintSystem<Scalar> {}
+
 #[test]
 fn test_cs() {
     use blstrs::Scalar as Fr;
@@ -3,9 +3,10 @@
 
 use ff::PrimeField;
 
-use crate::{ConstraintSystem, LinearCombination, SynthesisError};
+use crate::{ConstraintSystem, LinearCombination, SynthesisError, Variable};
 
 use super::boolean::{AllocatedBit, Boolean};
+use super::Assignment;
 
 use super::multieq::MultiEq;
 
@@ -199,6 +200,304 @@ impl UInt32 {
         }
     }
 
+    /// Returns `a` if the condition is true, and `b` otherwise, with at
+    /// most one constraint per bit.
+    pub fn conditionally_select<Scalar, CS>(
+        mut cs: CS,
+        condition: &Boolean,
+        a: &Self,
+        b: &Self,
+    ) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let bits = a
+            .bits
+            .iter()
+            .zip(&b.bits)
+            .enumerate()
+            .map(|(i, (a, b))| select_bit(cs.namespace(|| format!("bit {}", i)), condition, a, b))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let value = match condition.get_value() {
+            Some(true) => a.value,
+            Some(false) => b.value,
+            None => None,
+        };
+
+        Ok(UInt32 { bits, value })
+    }
+
+    /// Rotate right by the amount given by `by`, in little-endian bit
+    /// order.
+    pub fn rotr_var<Scalar, CS>(&self, cs: CS, by: &[Boolean]) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.barrel_shift(
+            cs,
+            by,
+            |bits, by| (0..32).map(|i| bits[(i + by) % 32].clone()).collect(),
+            |v, by| v.rotate_right(by),
+        )
+    }
+
+    /// Rotate left by the amount given by `by`, in little-endian bit
+    /// order.
+    pub fn rotl_var<Scalar, CS>(&self, cs: CS, by: &[Boolean]) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.barrel_shift(
+            cs,
+            by,
+            |bits, by| (0..32).map(|i| bits[(i + 32 - by) % 32].clone()).collect(),
+            |v, by| v.rotate_left(by),
+        )
+    }
+
+    /// Logical shift right by the amount given by `by`, in little-endian
+    /// bit order.
+    pub fn shr_var<Scalar, CS>(&self, cs: CS, by: &[Boolean]) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.barrel_shift(
+            cs,
+            by,
+            |bits, by| {
+                (0..32)
+                    .map(|i| {
+                        bits.get(i + by)
+                            .cloned()
+                            .unwrap_or(Boolean::constant(false))
+                    })
+                    .collect()
+            },
+            |v, by| v >> by,
+        )
+    }
+
+    /// Shift left by the amount given by `by`, in little-endian bit order.
+    pub fn shl_var<Scalar, CS>(&self, cs: CS, by: &[Boolean]) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.barrel_shift(
+            cs,
+            by,
+            |bits, by| {
+                (0..32)
+                    .map(|i| {
+                        if i >= by {
+                            bits[i - by].clone()
+                        } else {
+                            Boolean::constant(false)
+                        }
+                    })
+                    .collect()
+            },
+            |v, by| v << by,
+        )
+    }
+
+    /// Shifts the bits with a barrel shifter: stage `j` shifts them by `2^j`
+    /// with `shift` if bit `j` of `by` is set. `by` holds the 5 bits of the
+    /// amount.
+    fn barrel_shift<Scalar, CS, F, V>(
+        &self,
+        mut cs: CS,
+        by: &[Boolean],
+        shift: F,
+        value_fn: V,
+    ) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+        F: Fn(&[Boolean], usize) -> Vec<Boolean>,
+        V: Fn(u32, u32) -> u32,
+    {
+        assert_eq!(by.len(), 5);
+
+        let amount = by.iter().rev().try_fold(0u32, |acc, bit| {
+            bit.get_value().map(|bit| (acc << 1) | bit as u32)
+        });
+        let new_value = match (self.value, amount) {
+            (Some(v), Some(amount)) => Some(value_fn(v, amount)),
+            _ => None,
+        };
+
+        let mut bits = self.bits.clone();
+        for (j, condition) in by.iter().enumerate() {
+            let shifted = shift(&bits, 1 << j);
+            bits = bits
+                .iter()
+                .zip(shifted.iter())
+                .enumerate()
+                .map(|(i, (unshifted, shifted))| {
+                    select_bit(
+                        cs.namespace(|| format!("stage {} bit {}", j, i)),
+                        condition,
+                        shifted,
+                        unshifted,
+                    )
+                })
+                .collect::<Result<_, _>>()?;
+        }
+
+        Ok(UInt32 {
+            bits,
+            value: new_value,
+        })
+    }
+
+    /// Multiply this `UInt32` with another `UInt32`, returning the high
+    /// and the low word of the 64-bit product.
+    pub fn mul_full<Scalar, CS>(
+        &self,
+        mut cs: CS,
+        other: &Self,
+    ) -> Result<(Self, Self), SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        // The product must not overflow in the scalar field
+        assert!(Scalar::NUM_BITS > 64);
+
+        let product = match (self.value, other.value) {
+            (Some(a), Some(b)) => Some(u64::from(a) * u64::from(b)),
+            _ => None,
+        };
+
+        let (bits, product_lc) = alloc_bits_le(cs.namespace(|| "product"), product, 64)?;
+
+        // Constrain: a * b = product
+        cs.enforce(
+            || "multiplication constraint",
+            |_| self.lc(CS::one()),
+            |_| other.lc(CS::one()),
+            |_| product_lc,
+        );
+
+        let hi = UInt32 {
+            bits: bits[32..].to_vec(),
+            value: product.map(|p| (p >> 32) as u32),
+        };
+        let lo = UInt32 {
+            bits: bits[..32].to_vec(),
+            value: product.map(|p| p as u32),
+        };
+
+        Ok((hi, lo))
+    }
+
+    /// Multiply this `UInt32` with another `UInt32`, modulo 2^32.
+    pub fn wrapping_mul<Scalar, CS>(&self, cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        Ok(self.mul_full(cs, other)?.1)
+    }
+
+    /// Multiply this `UInt32` with another `UInt32`. Products which don't
+    /// fit into 32 bits are not satisfiable.
+    pub fn checked_mul<Scalar, CS>(&self, mut cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        assert!(Scalar::NUM_BITS > 64);
+
+        let product = match (self.value, other.value) {
+            (Some(a), Some(b)) => Some(u64::from(a) * u64::from(b)),
+            _ => None,
+        };
+
+        let (bits, product_lc) = alloc_bits_le(cs.namespace(|| "product"), product, 32)?;
+
+        // Constrain: a * b = product
+        // which only holds if the product has no high bits.
+        cs.enforce(
+            || "multiplication constraint",
+            |_| self.lc(CS::one()),
+            |_| other.lc(CS::one()),
+            |_| product_lc,
+        );
+
+        Ok(UInt32 {
+            bits,
+            value: product.and_then(|p| u32::try_from(p).ok()),
+        })
+    }
+
+    /// Divide this `UInt32` by `divisor`, returning the quotient and the
+    /// remainder. Division by zero is not satisfiable.
+    pub fn divrem<Scalar, CS>(
+        &self,
+        mut cs: CS,
+        divisor: &Self,
+    ) -> Result<(Self, Self), SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        assert!(Scalar::NUM_BITS > 64);
+
+        let (quotient_value, remainder_value) = match (self.value, divisor.value) {
+            (Some(_), Some(0)) => return Err(SynthesisError::DivisionByZero),
+            (Some(a), Some(b)) => (Some(a / b), Some(a % b)),
+            _ => (None, None),
+        };
+
+        let quotient = UInt32::alloc(cs.namespace(|| "quotient"), quotient_value)?;
+        let remainder = UInt32::alloc(cs.namespace(|| "remainder"), remainder_value)?;
+
+        // Constrain: quotient * divisor = a - remainder
+        // both sides are far smaller than the modulus, so this
+        // holds over the integers.
+        cs.enforce(
+            || "division constraint",
+            |_| quotient.lc(CS::one()),
+            |_| divisor.lc(CS::one()),
+            |_| self.lc(CS::one()) - &remainder.lc(CS::one()),
+        );
+
+        // Constrain: remainder < divisor
+        // by requiring that divisor - remainder - 1 fits into 32 bits,
+        // which is untrue for a divisor of zero.
+        let gap = match (divisor.value, remainder_value) {
+            (Some(d), Some(r)) => Some(u64::from(d - r - 1)),
+            _ => None,
+        };
+        let (_, gap_lc) = alloc_bits_le(cs.namespace(|| "remainder bound"), gap, 32)?;
+        cs.enforce(
+            || "remainder bound constraint",
+            |_| divisor.lc(CS::one()) - &remainder.lc(CS::one()) - (Scalar::ONE, CS::one()),
+            |lc| lc + CS::one(),
+            |_| gap_lc,
+        );
+
+        Ok((quotient, remainder))
+    }
+
+    /// The linear combination of the bits of this `UInt32`.
+    fn lc<Scalar: PrimeField>(&self, one: Variable) -> LinearCombination<Scalar> {
+        let mut lc = LinearCombination::zero();
+        let mut coeff = Scalar::ONE;
+        for bit in &self.bits {
+            lc = lc + &bit.lc(one, coeff);
+            coeff = coeff.double();
+        }
+        lc
+    }
+
     fn triop<Scalar, CS, F, U>(
         mut cs: CS,
         a: &Self,
@@ -407,13 +706,89 @@ impl UInt32 {
     }
 }
 
+/// Allocates the lowest `num_bits` bits of `value`, least significant bit
+/// first, and returns them together with their linear combination.
+fn alloc_bits_le<Scalar, CS>(
+    mut cs: CS,
+    value: Option<u64>,
+    num_bits: usize,
+) -> Result<(Vec<Boolean>, LinearCombination<Scalar>), SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    let mut bits = Vec::with_capacity(num_bits);
+    let mut lc = LinearCombination::zero();
+    let mut coeff = Scalar::ONE;
+    for i in 0..num_bits {
+        let bit = AllocatedBit::alloc(
+            cs.namespace(|| format!("allocated bit {}", i)),
+            value.map(|v| (v >> i) & 1 == 1),
+        )?;
+        lc = lc + (coeff, bit.get_variable());
+        coeff = coeff.double();
+        bits.push(Boolean::from(bit));
+    }
+
+    Ok((bits, lc))
+}
+
+/// Returns `a` if `condition` is true, and `b` otherwise.
+pub(crate) fn select_bit<Scalar, CS>(
+    mut cs: CS,
+    condition: &Boolean,
+    a: &Boolean,
+    b: &Boolean,
+) -> Result<Boolean, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    match (condition, a, b) {
+        (&Boolean::Constant(true), a, _) => return Ok(a.clone()),
+        (&Boolean::Constant(false), _, b) => return Ok(b.clone()),
+        (_, &Boolean::Constant(a_val), &Boolean::Constant(b_val)) if a_val == b_val => {
+            return Ok(Boolean::constant(a_val))
+        }
+        _ => {}
+    }
+
+    let value = match condition.get_value() {
+        Some(true) => a.get_value(),
+        Some(false) => b.get_value(),
+        None => None,
+    };
+
+    let var = cs.alloc(
+        || "selected bit",
+        || {
+            if *value.get()? {
+                Ok(Scalar::ONE)
+            } else {
+                Ok(Scalar::ZERO)
+            }
+        },
+    )?;
+
+    // Constrain: condition * (a - b) = selected - b
+    // the selected bit is either a or b, so it is boolean.
+    cs.enforce(
+        || "selection constraint",
+        |_| condition.lc(CS::one(), Scalar::ONE),
+        |lc| lc + &a.lc(CS::one(), Scalar::ONE) - &b.lc(CS::one(), Scalar::ONE),
+        |lc| lc + var - &b.lc(CS::one(), Scalar::ONE),
+    );
+
+    Ok(AllocatedBit::from_constrained(var, value).into())
+}
+
 #[cfg(test)]
 mod test {
     use super::UInt32;
-    use crate::gadgets::boolean::Boolean;
+    use crate::gadgets::boolean::{AllocatedBit, Boolean};
     use crate::gadgets::multieq::MultiEq;
     use crate::gadgets::test::*;
-    use crate::ConstraintSystem;
+    use crate::{ConstraintSystem, SynthesisError};
     use blstrs::Scalar as Fr;
     use ff::Field;
     use rand_core::{RngCore, SeedableRng};
@@ -691,6 +1066,211 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_uint32_variable_shifts() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        type Gadget = fn(
+            &UInt32,
+            &mut TestConstraintSystem<Fr>,
+            &[Boolean],
+        ) -> Result<UInt32, SynthesisError>;
+        type Native = fn(u32, u32) -> u32;
+        let ops: [(Gadget, Native); 4] = [
+            (|a, cs, by| a.rotr_var(cs, by), u32::rotate_right),
+            (|a, cs, by| a.rotl_var(cs, by), u32::rotate_left),
+            (|a, cs, by| a.shr_var(cs, by), |v, by| v >> by),
+            (|a, cs, by| a.shl_var(cs, by), |v, by| v << by),
+        ];
+
+        for by in 0..32u32 {
+            let num = rng.next_u32();
+            for (gadget, op) in ops.iter() {
+                let mut cs = TestConstraintSystem::<Fr>::new();
+
+                let a = UInt32::alloc(cs.namespace(|| "a"), Some(num)).unwrap();
+                let by_bits = (0..5)
+                    .map(|i| {
+                        AllocatedBit::alloc(
+                            cs.namespace(|| format!("by {}", i)),
+                            Some((by >> i) & 1 == 1),
+                        )
+                        .unwrap()
+                        .into()
+                    })
+                    .collect::<Vec<Boolean>>();
+
+                let r = gadget(&a, &mut cs, &by_bits).unwrap();
+
+                assert!(cs.is_satisfied());
+
+                let expected = op(num, by);
+                assert_eq!(r.value, Some(expected));
+                for (i, bit) in r.bits.iter().enumerate() {
+                    assert_eq!(bit.get_value(), Some((expected >> i) & 1 == 1));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_uint32_conditionally_select() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for condition in [true, false] {
+            let (a, b) = (rng.next_u32(), rng.next_u32());
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let a_bits = UInt32::alloc(cs.namespace(|| "a"), Some(a)).unwrap();
+            let b_bits = UInt32::alloc(cs.namespace(|| "b"), Some(b)).unwrap();
+            let condition_bit = Boolean::from(
+                AllocatedBit::alloc(cs.namespace(|| "condition"), Some(condition)).unwrap(),
+            );
+            let before = cs.num_constraints();
+            let r = UInt32::conditionally_select(
+                cs.namespace(|| "select"),
+                &condition_bit,
+                &a_bits,
+                &b_bits,
+            )
+            .unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(cs.num_constraints() - before, 32);
+            let expected = if condition { a } else { b };
+            assert_eq!(r.value, Some(expected));
+            for (i, bit) in r.bits.iter().enumerate() {
+                assert_eq!(bit.get_value(), Some((expected >> i) & 1 == 1));
+            }
+
+            // Constant inputs and conditions need no constraints.
+            let before = cs.num_constraints();
+            let r = UInt32::conditionally_select(
+                cs.namespace(|| "constants"),
+                &condition_bit,
+                &UInt32::constant(a),
+                &UInt32::constant(a),
+            )
+            .unwrap();
+            let s = UInt32::conditionally_select(
+                cs.namespace(|| "constant condition"),
+                &Boolean::constant(condition),
+                &a_bits,
+                &b_bits,
+            )
+            .unwrap();
+            assert_eq!(cs.num_constraints(), before);
+            assert_eq!(r.value, Some(a));
+            assert_eq!(s.value, Some(expected));
+        }
+    }
+
+    #[test]
+    fn test_uint32_mul() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for _ in 0..100 {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let a = rng.next_u32();
+            let b = rng.next_u32();
+            let expected = u64::from(a) * u64::from(b);
+
+            let a_bit = UInt32::alloc(cs.namespace(|| "a"), Some(a)).unwrap();
+            let b_bit = UInt32::alloc(cs.namespace(|| "b"), Some(b)).unwrap();
+
+            let (hi, lo) = a_bit.mul_full(cs.namespace(|| "full"), &b_bit).unwrap();
+            let wrapped = a_bit
+                .wrapping_mul(cs.namespace(|| "wrapping"), &b_bit)
+                .unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(hi.value, Some((expected >> 32) as u32));
+            assert_eq!(lo.value, Some(expected as u32));
+            assert_eq!(wrapped.value, Some(a.wrapping_mul(b)));
+            for (i, bit) in lo.bits.iter().chain(hi.bits.iter()).enumerate() {
+                assert_eq!(bit.get_value(), Some((expected >> i) & 1 == 1));
+            }
+
+            // Flip a bit of the product
+            let flipped = Fr::ONE - cs.get("full/product/allocated bit 0/boolean");
+            cs.set("full/product/allocated bit 0/boolean", flipped);
+            assert!(cs.which_is_unsatisfied() == Some("full/multiplication constraint"));
+        }
+    }
+
+    #[test]
+    fn test_uint32_checked_mul() {
+        for (a, b, fits) in [(0xffff, 0x10001, true), (0x10000, 0x10000, false)] {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let a_bit = UInt32::alloc(cs.namespace(|| "a"), Some(a)).unwrap();
+            let b_bit = UInt32::alloc(cs.namespace(|| "b"), Some(b)).unwrap();
+            let r = a_bit.checked_mul(cs.namespace(|| "mul"), &b_bit).unwrap();
+
+            assert_eq!(cs.is_satisfied(), fits);
+            assert_eq!(r.value, a.checked_mul(b));
+        }
+    }
+
+    #[test]
+    fn test_uint32_divrem() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for i in 0..100 {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let a = rng.next_u32();
+            // Cover small divisors as well as ones larger than the dividend.
+            let b = (rng.next_u32() >> (i % 32)).max(1);
+
+            let a_bit = UInt32::alloc(cs.namespace(|| "a"), Some(a)).unwrap();
+            let b_bit = UInt32::alloc(cs.namespace(|| "b"), Some(b)).unwrap();
+            let (q, r) = a_bit.divrem(cs.namespace(|| "divrem"), &b_bit).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(q.value, Some(a / b));
+            assert_eq!(r.value, Some(a % b));
+        }
+
+        {
+            // A remainder that isn't smaller than the divisor is rejected:
+            // 7 = 2 * 3 + 1 must not be provable as 7 = 1 * 3 + 4.
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let a_bit = UInt32::alloc(cs.namespace(|| "a"), Some(7)).unwrap();
+            let b_bit = UInt32::alloc(cs.namespace(|| "b"), Some(3)).unwrap();
+            a_bit.divrem(cs.namespace(|| "divrem"), &b_bit).unwrap();
+            assert!(cs.is_satisfied());
+
+            cs.set("divrem/quotient/allocated bit 0/boolean", Fr::ONE);
+            cs.set("divrem/quotient/allocated bit 1/boolean", Fr::ZERO);
+            cs.set("divrem/remainder/allocated bit 0/boolean", Fr::ZERO);
+            cs.set("divrem/remainder/allocated bit 2/boolean", Fr::ONE);
+            assert!(cs.which_is_unsatisfied() == Some("divrem/remainder bound constraint"));
+        }
+
+        {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let a_bit = UInt32::alloc(cs.namespace(|| "a"), Some(7)).unwrap();
+            let b_bit = UInt32::alloc(cs.namespace(|| "b"), Some(0)).unwrap();
+            assert!(a_bit.divrem(cs.namespace(|| "divrem"), &b_bit).is_err());
+        }
+    }
+
     #[test]
     fn test_uint32_sha256_maj() {
         let mut rng = XorShiftRng::from_seed([
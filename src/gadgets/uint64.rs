@@ -0,0 +1,451 @@
+//! Circuit representation of a [`u64`].
+
+use ff::PrimeField;
+
+use crate::{ConstraintSystem, LinearCombination, SynthesisError, Variable};
+
+use super::boolean::{AllocatedBit, Boolean};
+use super::uint32::select_bit;
+
+/// Represents an interpretation of 64 `Boolean` objects as an
+/// unsigned integer.
+#[derive(Clone)]
+pub struct UInt64 {
+    // Least significant bit first
+    bits: Vec<Boolean>,
+    value: Option<u64>,
+}
+
+impl UInt64 {
+    /// Construct a constant `UInt64` from a `u64`
+    pub fn constant(value: u64) -> Self {
+        let bits = (0..64)
+            .map(|i| Boolean::constant((value >> i) & 1 == 1))
+            .collect();
+
+        UInt64 {
+            bits,
+            value: Some(value),
+        }
+    }
+
+    /// Allocate a `UInt64` in the constraint system
+    pub fn alloc<Scalar, CS>(cs: CS, value: Option<u64>) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let (bits, _) = alloc_bits_le(cs, value, 64)?;
+
+        Ok(UInt64 { bits, value })
+    }
+
+    pub fn get_value(&self) -> Option<u64> {
+        self.value
+    }
+
+    /// Turns this `UInt64` into its bits, most significant bit first.
+    pub fn into_bits_be(self) -> Vec<Boolean> {
+        let mut ret = self.bits;
+        ret.reverse();
+        ret
+    }
+
+    /// Converts bits, most significant bit first, into a `UInt64`.
+    pub fn from_bits_be(bits: &[Boolean]) -> Self {
+        assert_eq!(bits.len(), 64);
+
+        let mut bits = bits.to_vec();
+        bits.reverse();
+        Self::from_bits(&bits)
+    }
+
+    /// Turns this `UInt64` into its bits, least significant bit first.
+    pub fn into_bits(self) -> Vec<Boolean> {
+        self.bits
+    }
+
+    /// Converts bits, least significant bit first, into a `UInt64`.
+    pub fn from_bits(bits: &[Boolean]) -> Self {
+        assert_eq!(bits.len(), 64);
+
+        let value = bits.iter().rev().try_fold(0u64, |value, bit| {
+            bit.get_value().map(|bit| (value << 1) | bit as u64)
+        });
+
+        UInt64 {
+            value,
+            bits: bits.to_vec(),
+        }
+    }
+
+    /// Rotate right by the amount given by `by`, in little-endian bit
+    /// order.
+    pub fn rotr_var<Scalar, CS>(&self, cs: CS, by: &[Boolean]) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.barrel_shift(
+            cs,
+            by,
+            |bits, by| (0..64).map(|i| bits[(i + by) % 64].clone()).collect(),
+            |v, by| v.rotate_right(by),
+        )
+    }
+
+    /// Rotate left by the amount given by `by`, in little-endian bit
+    /// order.
+    pub fn rotl_var<Scalar, CS>(&self, cs: CS, by: &[Boolean]) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.barrel_shift(
+            cs,
+            by,
+            |bits, by| (0..64).map(|i| bits[(i + 64 - by) % 64].clone()).collect(),
+            |v, by| v.rotate_left(by),
+        )
+    }
+
+    /// Logical shift right by the amount given by `by`, in little-endian
+    /// bit order.
+    pub fn shr_var<Scalar, CS>(&self, cs: CS, by: &[Boolean]) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.barrel_shift(
+            cs,
+            by,
+            |bits, by| {
+                (0..64)
+                    .map(|i| {
+                        bits.get(i + by)
+                            .cloned()
+                            .unwrap_or(Boolean::constant(false))
+                    })
+                    .collect()
+            },
+            |v, by| v >> by,
+        )
+    }
+
+    /// Shift left by the amount given by `by`, in little-endian bit order.
+    pub fn shl_var<Scalar, CS>(&self, cs: CS, by: &[Boolean]) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.barrel_shift(
+            cs,
+            by,
+            |bits, by| {
+                (0..64)
+                    .map(|i| {
+                        if i >= by {
+                            bits[i - by].clone()
+                        } else {
+                            Boolean::constant(false)
+                        }
+                    })
+                    .collect()
+            },
+            |v, by| v << by,
+        )
+    }
+
+    /// Shifts the bits with a barrel shifter: stage `j` shifts them by `2^j`
+    /// with `shift` if bit `j` of `by` is set. `by` holds the 6 bits of the
+    /// amount.
+    fn barrel_shift<Scalar, CS, F, V>(
+        &self,
+        mut cs: CS,
+        by: &[Boolean],
+        shift: F,
+        value_fn: V,
+    ) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+        F: Fn(&[Boolean], usize) -> Vec<Boolean>,
+        V: Fn(u64, u32) -> u64,
+    {
+        assert_eq!(by.len(), 6);
+
+        let amount = by.iter().rev().try_fold(0u32, |acc, bit| {
+            bit.get_value().map(|bit| (acc << 1) | bit as u32)
+        });
+        let new_value = match (self.value, amount) {
+            (Some(v), Some(amount)) => Some(value_fn(v, amount)),
+            _ => None,
+        };
+
+        let mut bits = self.bits.clone();
+        for (j, condition) in by.iter().enumerate() {
+            let shifted = shift(&bits, 1 << j);
+            bits = bits
+                .iter()
+                .zip(shifted.iter())
+                .enumerate()
+                .map(|(i, (unshifted, shifted))| {
+                    select_bit(
+                        cs.namespace(|| format!("stage {} bit {}", j, i)),
+                        condition,
+                        shifted,
+                        unshifted,
+                    )
+                })
+                .collect::<Result<_, _>>()?;
+        }
+
+        Ok(UInt64 {
+            bits,
+            value: new_value,
+        })
+    }
+
+    /// Divide this `UInt64` by `divisor`, returning the quotient and the
+    /// remainder. Division by zero is not satisfiable.
+    pub fn divrem<Scalar, CS>(
+        &self,
+        mut cs: CS,
+        divisor: &Self,
+    ) -> Result<(Self, Self), SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        // The product of the quotient and the divisor must not overflow in the scalar field
+        assert!(Scalar::NUM_BITS > 128);
+
+        let (quotient_value, remainder_value) = match (self.value, divisor.value) {
+            (Some(_), Some(0)) => return Err(SynthesisError::DivisionByZero),
+            (Some(a), Some(b)) => (Some(a / b), Some(a % b)),
+            _ => (None, None),
+        };
+
+        let quotient = UInt64::alloc(cs.namespace(|| "quotient"), quotient_value)?;
+        let remainder = UInt64::alloc(cs.namespace(|| "remainder"), remainder_value)?;
+
+        // Constrain: quotient * divisor = a - remainder
+        // both sides are far smaller than the modulus, so this
+        // holds over the integers.
+        cs.enforce(
+            || "division constraint",
+            |_| quotient.lc(CS::one()),
+            |_| divisor.lc(CS::one()),
+            |_| self.lc(CS::one()) - &remainder.lc(CS::one()),
+        );
+
+        // Constrain: remainder < divisor
+        // by requiring that divisor - remainder - 1 fits into 64 bits,
+        // which is untrue for a divisor of zero.
+        let gap = match (divisor.value, remainder_value) {
+            (Some(d), Some(r)) => Some(d - r - 1),
+            _ => None,
+        };
+        let (_, gap_lc) = alloc_bits_le(cs.namespace(|| "remainder bound"), gap, 64)?;
+        cs.enforce(
+            || "remainder bound constraint",
+            |_| divisor.lc(CS::one()) - &remainder.lc(CS::one()) - (Scalar::ONE, CS::one()),
+            |lc| lc + CS::one(),
+            |_| gap_lc,
+        );
+
+        Ok((quotient, remainder))
+    }
+
+    /// The linear combination of the bits of this `UInt64`.
+    fn lc<Scalar: PrimeField>(&self, one: Variable) -> LinearCombination<Scalar> {
+        let mut lc = LinearCombination::zero();
+        let mut coeff = Scalar::ONE;
+        for bit in &self.bits {
+            lc = lc + &bit.lc(one, coeff);
+            coeff = coeff.double();
+        }
+        lc
+    }
+}
+
+/// Allocates the `num_bits` least significant bits of `value`, returning
+/// them and the linear combination they compose.
+fn alloc_bits_le<Scalar, CS>(
+    mut cs: CS,
+    value: Option<u64>,
+    num_bits: usize,
+) -> Result<(Vec<Boolean>, LinearCombination<Scalar>), SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    let mut bits = Vec::with_capacity(num_bits);
+    let mut lc = LinearCombination::zero();
+    let mut coeff = Scalar::ONE;
+    for i in 0..num_bits {
+        let bit = AllocatedBit::alloc(
+            cs.namespace(|| format!("allocated bit {}", i)),
+            value.map(|v| (v >> i) & 1 == 1),
+        )?;
+        lc = lc + (coeff, bit.get_variable());
+        coeff = coeff.double();
+        bits.push(Boolean::from(bit));
+    }
+
+    Ok((bits, lc))
+}
+
+#[cfg(test)]
+mod test {
+    use super::UInt64;
+    use crate::gadgets::boolean::{AllocatedBit, Boolean};
+    use crate::gadgets::test::*;
+    use crate::{ConstraintSystem, SynthesisError};
+    use blstrs::Scalar as Fr;
+    use ff::Field;
+    use rand_core::{RngCore, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_uint64_bits() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for _ in 0..100 {
+            let value = rng.next_u64();
+
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let allocated = UInt64::alloc(&mut cs, Some(value)).unwrap();
+            assert!(cs.is_satisfied());
+            assert_eq!(cs.num_constraints(), 64);
+            assert_eq!(allocated.get_value(), Some(value));
+
+            let bits = allocated.clone().into_bits_be();
+            for (i, bit) in bits.iter().enumerate() {
+                assert_eq!(bit.get_value(), Some((value >> (63 - i)) & 1 == 1));
+            }
+            assert_eq!(UInt64::from_bits_be(&bits).get_value(), Some(value));
+            assert_eq!(
+                UInt64::from_bits(&allocated.into_bits()).get_value(),
+                Some(value)
+            );
+
+            let constant = UInt64::constant(value).into_bits();
+            assert!(constant.iter().all(Boolean::is_constant));
+            assert_eq!(UInt64::from_bits(&constant).get_value(), Some(value));
+        }
+    }
+
+    #[test]
+    fn test_uint64_divrem() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        // The ends of the 64-bit range, then divisors of every width.
+        let mut cases = vec![
+            (u64::MAX, 1),
+            (u64::MAX, u64::MAX),
+            (u64::MAX - 1, u64::MAX),
+            (1 << 63, 3),
+            (0, 5),
+        ];
+        cases.extend((0..64).map(|width| (rng.next_u64(), (rng.next_u64() >> width).max(1))));
+
+        for (a, b) in cases {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let a_bits = UInt64::alloc(cs.namespace(|| "a"), Some(a)).unwrap();
+            let b_bits = UInt64::alloc(cs.namespace(|| "b"), Some(b)).unwrap();
+            let (q, r) = a_bits.divrem(cs.namespace(|| "divrem"), &b_bits).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(q.get_value(), Some(a / b));
+            assert_eq!(r.get_value(), Some(a % b));
+        }
+
+        {
+            // u64::MAX = 1 * 2^63 + (2^63 - 1) must not be provable with a
+            // quotient of zero and a remainder of u64::MAX, which only the
+            // full 64 bits of the remainder bound rule out.
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let a_bits = UInt64::alloc(cs.namespace(|| "a"), Some(u64::MAX)).unwrap();
+            let b_bits = UInt64::alloc(cs.namespace(|| "b"), Some(1 << 63)).unwrap();
+            a_bits.divrem(cs.namespace(|| "divrem"), &b_bits).unwrap();
+            assert!(cs.is_satisfied());
+
+            cs.set("divrem/quotient/allocated bit 0/boolean", Fr::ZERO);
+            cs.set("divrem/remainder/allocated bit 63/boolean", Fr::ONE);
+            assert_eq!(
+                cs.which_is_unsatisfied(),
+                Some("divrem/remainder bound constraint")
+            );
+        }
+
+        {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let a_bits = UInt64::alloc(cs.namespace(|| "a"), Some(u64::MAX)).unwrap();
+            let b_bits = UInt64::alloc(cs.namespace(|| "b"), Some(0)).unwrap();
+            assert!(matches!(
+                a_bits.divrem(cs.namespace(|| "divrem"), &b_bits),
+                Err(SynthesisError::DivisionByZero)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_uint64_variable_shifts() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        type Gadget = fn(
+            &UInt64,
+            &mut TestConstraintSystem<Fr>,
+            &[Boolean],
+        ) -> Result<UInt64, SynthesisError>;
+        type Native = fn(u64, u32) -> u64;
+        let ops: [(Gadget, Native); 4] = [
+            (|a, cs, by| a.rotr_var(cs, by), u64::rotate_right),
+            (|a, cs, by| a.rotl_var(cs, by), u64::rotate_left),
+            (|a, cs, by| a.shr_var(cs, by), |v, by| v >> by),
+            (|a, cs, by| a.shl_var(cs, by), |v, by| v << by),
+        ];
+
+        for by in 0..64u32 {
+            let num = rng.next_u64();
+            for (gadget, op) in ops.iter() {
+                let mut cs = TestConstraintSystem::<Fr>::new();
+
+                let a = UInt64::alloc(cs.namespace(|| "a"), Some(num)).unwrap();
+                let by_bits = (0..6)
+                    .map(|i| {
+                        AllocatedBit::alloc(
+                            cs.namespace(|| format!("by {}", i)),
+                            Some((by >> i) & 1 == 1),
+                        )
+                        .unwrap()
+                        .into()
+                    })
+                    .collect::<Vec<Boolean>>();
+
+                let r = gadget(&a, &mut cs, &by_bits).unwrap();
+
+                assert!(cs.is_satisfied());
+
+                let expected = op(num, by);
+                assert_eq!(r.value, Some(expected));
+                for (i, bit) in r.bits.iter().enumerate() {
+                    assert_eq!(bit.get_value(), Some((expected >> i) & 1 == 1));
+                }
+            }
+        }
+    }
+}
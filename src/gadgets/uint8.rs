@@ -0,0 +1,347 @@
+//! Circuit representation of a [`u8`], and helpers for byte strings.
+//!
+//! Byte strings are converted to bits in one of two fixed orders: `be` takes the bytes in order
+//! with the most significant bit of every byte first, like SHA-256 does, `le` takes the bytes in
+//! order with the least significant bit first, which packs the bytes as a little-endian integer.
+
+use ff::{PrimeField, PrimeFieldBits};
+
+use crate::{ConstraintSystem, LinearCombination, SynthesisError};
+
+use super::boolean::{AllocatedBit, Boolean};
+use super::multipack;
+use super::num::AllocatedNum;
+
+/// Represents an interpretation of 8 `Boolean` objects as an
+/// unsigned integer.
+#[derive(Clone)]
+pub struct UInt8 {
+    // Least significant bit first
+    bits: Vec<Boolean>,
+    value: Option<u8>,
+}
+
+impl UInt8 {
+    /// Construct a constant `UInt8` from a `u8`
+    pub fn constant(value: u8) -> Self {
+        let bits = (0..8)
+            .map(|i| Boolean::constant((value >> i) & 1 == 1))
+            .collect();
+
+        UInt8 {
+            bits,
+            value: Some(value),
+        }
+    }
+
+    /// Allocate a `UInt8` in the constraint system
+    pub fn alloc<Scalar, CS>(mut cs: CS, value: Option<u8>) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let bits = (0..8)
+            .map(|i| {
+                Ok(Boolean::from(AllocatedBit::alloc(
+                    cs.namespace(|| format!("allocated bit {}", i)),
+                    value.map(|value| (value >> i) & 1 == 1),
+                )?))
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+        Ok(UInt8 { bits, value })
+    }
+
+    /// Construct a constant `UInt8` per byte.
+    pub fn constant_vec(values: &[u8]) -> Vec<Self> {
+        values.iter().copied().map(Self::constant).collect()
+    }
+
+    /// Allocate a `UInt8` per byte, in namespaces named by their index.
+    pub fn alloc_vec<Scalar, CS>(
+        mut cs: CS,
+        values: &[Option<u8>],
+    ) -> Result<Vec<Self>, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| Self::alloc(cs.namespace(|| format!("byte {}", i)), *value))
+            .collect()
+    }
+
+    pub fn get_value(&self) -> Option<u8> {
+        self.value
+    }
+
+    /// Turns this `UInt8` into its bits, most significant bit first.
+    pub fn into_bits_be(self) -> Vec<Boolean> {
+        let mut ret = self.bits;
+        ret.reverse();
+        ret
+    }
+
+    /// Converts bits, most significant bit first, into a `UInt8`.
+    pub fn from_bits_be(bits: &[Boolean]) -> Self {
+        assert_eq!(bits.len(), 8);
+
+        let value = bits.iter().try_fold(0u8, |value, bit| {
+            bit.get_value().map(|bit| (value << 1) | bit as u8)
+        });
+
+        UInt8 {
+            value,
+            bits: bits.iter().rev().cloned().collect(),
+        }
+    }
+
+    /// Turns this `UInt8` into its bits, least significant bit first.
+    pub fn into_bits(self) -> Vec<Boolean> {
+        self.bits
+    }
+
+    /// Converts bits, least significant bit first, into a `UInt8`.
+    pub fn from_bits(bits: &[Boolean]) -> Self {
+        assert_eq!(bits.len(), 8);
+
+        let value = bits.iter().rev().try_fold(0u8, |value, bit| {
+            bit.get_value().map(|bit| (value << 1) | bit as u8)
+        });
+
+        UInt8 {
+            value,
+            bits: bits.to_vec(),
+        }
+    }
+
+    /// XOR this `UInt8` with another `UInt8`
+    pub fn xor<Scalar, CS>(&self, mut cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let new_value = match (self.value, other.value) {
+            (Some(a), Some(b)) => Some(a ^ b),
+            _ => None,
+        };
+
+        let bits = self
+            .bits
+            .iter()
+            .zip(other.bits.iter())
+            .enumerate()
+            .map(|(i, (a, b))| Boolean::xor(cs.namespace(|| format!("xor of bit {}", i)), a, b))
+            .collect::<Result<_, _>>()?;
+
+        Ok(UInt8 {
+            bits,
+            value: new_value,
+        })
+    }
+}
+
+/// The values of the bytes, if all of them are known.
+pub fn bytes_values(bytes: &[UInt8]) -> Option<Vec<u8>> {
+    bytes.iter().map(UInt8::get_value).collect()
+}
+
+/// The bits of the bytes in order, most significant bit of every byte first.
+pub fn bytes_to_bits_be(bytes: &[UInt8]) -> Vec<Boolean> {
+    bytes
+        .iter()
+        .flat_map(|byte| byte.clone().into_bits_be())
+        .collect()
+}
+
+/// The bits of the bytes in order, least significant bit of every byte first.
+pub fn bytes_to_bits_le(bytes: &[UInt8]) -> Vec<Boolean> {
+    bytes
+        .iter()
+        .flat_map(|byte| byte.clone().into_bits())
+        .collect()
+}
+
+/// The inverse of [`bytes_to_bits_be`]. Panics if the number of bits isn't a multiple of 8.
+pub fn bytes_from_bits_be(bits: &[Boolean]) -> Vec<UInt8> {
+    assert_eq!(bits.len() % 8, 0, "bits of a partial byte");
+    bits.chunks(8).map(UInt8::from_bits_be).collect()
+}
+
+/// The inverse of [`bytes_to_bits_le`]. Panics if the number of bits isn't a multiple of 8.
+pub fn bytes_from_bits_le(bits: &[Boolean]) -> Vec<UInt8> {
+    assert_eq!(bits.len() % 8, 0, "bits of a partial byte");
+    bits.chunks(8).map(UInt8::from_bits).collect()
+}
+
+/// The number of bytes that fit into a field element, see [`pack_bytes`].
+pub fn bytes_per_element<Scalar: PrimeField>() -> usize {
+    Scalar::CAPACITY as usize / 8
+}
+
+/// Packs the bytes into field elements, [`bytes_per_element`] bytes per element but the last
+/// one, each chunk as a little-endian integer. Costs a constraint per element.
+pub fn pack_bytes<Scalar, CS>(
+    mut cs: CS,
+    bytes: &[UInt8],
+) -> Result<Vec<AllocatedNum<Scalar>>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    bytes
+        .chunks(bytes_per_element::<Scalar>())
+        .enumerate()
+        .map(|(i, chunk)| {
+            multipack::pack_bits(
+                cs.namespace(|| format!("chunk {}", i)),
+                &bytes_to_bits_le(chunk),
+            )
+        })
+        .collect()
+}
+
+/// Unpacks the `len` bytes of a field element packed by [`pack_bytes`], the inverse of packing
+/// a single chunk. The constraints are unsatisfiable if `num` doesn't fit into `len` bytes.
+///
+/// Panics if `len` is larger than [`bytes_per_element`].
+pub fn unpack_bytes<Scalar, CS>(
+    mut cs: CS,
+    num: &AllocatedNum<Scalar>,
+    len: usize,
+) -> Result<Vec<UInt8>, SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: ConstraintSystem<Scalar>,
+{
+    assert!(
+        len <= bytes_per_element::<Scalar>(),
+        "{} bytes don't fit into a field element",
+        len
+    );
+
+    let values = match num.get_value() {
+        Some(value) => {
+            let bits = value.to_le_bits();
+            (0..len)
+                .map(|i| Some((0..8).fold(0u8, |byte, j| byte | (u8::from(bits[8 * i + j]) << j))))
+                .collect()
+        }
+        None => vec![None; len],
+    };
+    let bytes = UInt8::alloc_vec(cs.namespace(|| "bytes"), &values)?;
+
+    let mut lc = LinearCombination::zero();
+    let mut coeff = Scalar::ONE;
+    for bit in bytes_to_bits_le(&bytes) {
+        lc = lc + &bit.lc(CS::one(), coeff);
+        coeff = coeff.double();
+    }
+    cs.enforce(
+        || "unpacking constraint",
+        |_| lc,
+        |lc| lc + CS::one(),
+        |lc| lc + num.get_variable(),
+    );
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::gadgets::boolean::Boolean;
+    use crate::gadgets::test::*;
+    use crate::ConstraintSystem;
+    use blstrs::Scalar as Fr;
+    use rand_core::{RngCore, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_uint8_bits_be() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for _ in 0..100 {
+            let value = rng.next_u32() as u8;
+
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let allocated = UInt8::alloc(&mut cs, Some(value)).unwrap();
+            assert!(cs.is_satisfied());
+            assert_eq!(allocated.get_value(), Some(value));
+
+            let bits = allocated.into_bits_be();
+            for (i, bit) in bits.iter().enumerate() {
+                assert_eq!(bit.get_value(), Some((value >> (7 - i)) & 1 == 1));
+            }
+
+            let constant = UInt8::constant(value).into_bits_be();
+            assert!(constant.iter().all(Boolean::is_constant));
+            assert_eq!(UInt8::from_bits_be(&constant).get_value(), Some(value));
+            assert_eq!(UInt8::from_bits_be(&bits).get_value(), Some(value));
+        }
+    }
+
+    #[test]
+    fn test_uint8_byte_strings() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        // More than fits into a field element.
+        let mut values = [0u8; 40];
+        rng.fill_bytes(&mut values);
+        values[39] = 0xff;
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let options = values.iter().copied().map(Some).collect::<Vec<_>>();
+        let bytes = UInt8::alloc_vec(cs.namespace(|| "bytes"), &options).unwrap();
+        assert_eq!(bytes_values(&bytes), Some(values.to_vec()));
+
+        let bit_values = |bits: &[Boolean]| {
+            bits.iter()
+                .map(|bit| bit.get_value().unwrap())
+                .collect::<Vec<_>>()
+        };
+        let bits_be = bytes_to_bits_be(&bytes);
+        let bits_le = bytes_to_bits_le(&bytes);
+        assert_eq!(bit_values(&bits_be), multipack::bytes_to_bits(&values));
+        assert_eq!(bit_values(&bits_le), multipack::bytes_to_bits_le(&values));
+        assert_eq!(
+            bytes_values(&bytes_from_bits_be(&bits_be)),
+            Some(values.to_vec())
+        );
+        assert_eq!(
+            bytes_values(&bytes_from_bits_le(&bits_le)),
+            Some(values.to_vec())
+        );
+        assert_eq!(
+            bytes_values(&UInt8::constant_vec(&values)),
+            Some(values.to_vec())
+        );
+
+        let nums = pack_bytes(cs.namespace(|| "pack"), &bytes).unwrap();
+        assert_eq!(bytes_per_element::<Fr>(), 31);
+        assert_eq!(nums.len(), 2);
+        for (num, chunk) in nums.iter().zip(values.chunks(31)) {
+            let expected =
+                multipack::compute_multipacking::<Fr>(&multipack::bytes_to_bits_le(chunk));
+            assert_eq!(num.get_value(), Some(expected[0]));
+        }
+
+        let unpacked = unpack_bytes(cs.namespace(|| "unpack"), &nums[1], 9).unwrap();
+        assert_eq!(bytes_values(&unpacked), Some(values[31..].to_vec()));
+        assert!(cs.is_satisfied());
+
+        // The packed bytes don't fit into fewer bytes.
+        unpack_bytes(cs.namespace(|| "truncate"), &nums[1], 8).unwrap();
+        assert_eq!(
+            cs.which_is_unsatisfied(),
+            Some("truncate/unpacking constraint")
+        );
+    }
+}
@@ -0,0 +1,543 @@
+//! Points of short Weierstrass curves over fields other than the scalar field, such as secp256k1,
+//! with coordinates emulated by [`BigNat`]s.
+//!
+//! The curves `y^2 = x^3 + a x + b` must have a prime order, so that no point has `y = 0`. The
+//! identity isn't representable, and the affine addition law has exceptions: [`AffinePoint::add`]
+//! is unsatisfiable for points with the same `x`, including a point and itself. Gadgets built on
+//! it must make sure that honest provers don't run into them.
+//!
+//! The coordinates are reduced modulo `p`, but not necessarily less than `p`, see
+//! [`BigNat::reduce`]. An addition or a doubling costs several thousand constraints with 64-bit
+//! limbs, most of them range checks of the allocated limbs and carries.
+
+use std::marker::PhantomData;
+
+use ff::{PrimeField, PrimeFieldBits};
+
+use super::bignat::{BigNat, Nat};
+use super::boolean::Boolean;
+use crate::{ConstraintSystem, SynthesisError};
+
+/// The parameters of a short Weierstrass curve of prime order, with numbers in 64-bit words,
+/// least significant word first.
+pub trait WeierstrassCurve {
+    /// The width of the limbs of the coordinates.
+    const LIMB_WIDTH: usize;
+
+    /// The number of limbs of the coordinates, which must fit `p`.
+    const N_LIMBS: usize;
+
+    /// The modulus of the base field, a prime.
+    const P: &'static [u64];
+
+    /// The coefficient `a`.
+    const A: &'static [u64];
+
+    /// The coefficient `b`.
+    const B: &'static [u64];
+
+    /// The order of the curve, a prime.
+    const ORDER: &'static [u64];
+
+    /// The generator.
+    const GENERATOR: (&'static [u64], &'static [u64]);
+
+    /// Whether the point is on the curve, with coordinates less than `p`.
+    fn is_on_curve((x, y): (&[u64], &[u64])) -> bool {
+        let native = Native::new::<Self>();
+        let (x, y) = (Nat::new(x.to_vec()), Nat::new(y.to_vec()));
+        let x3_ax = native.mul(&native.add(&native.mul(&x, &x), &native.a), &x);
+        let rhs = native.add(&x3_ax, &Nat::new(Self::B.to_vec()));
+        x < native.p && y < native.p && native.mul(&y, &y) == rhs
+    }
+
+    /// Negates a point outside of the circuit.
+    fn negate_point((x, y): (&[u64], &[u64])) -> (Vec<u64>, Vec<u64>) {
+        let native = Native::new::<Self>();
+        let y = native.sub(&Nat::default(), &Nat::new(y.to_vec()));
+        (Nat::new(x.to_vec()).0, y.0)
+    }
+
+    /// Adds points outside of the circuit, returning `None` for the identity.
+    fn add_points(a: (&[u64], &[u64]), b: (&[u64], &[u64])) -> Option<(Vec<u64>, Vec<u64>)> {
+        let native = Native::new::<Self>();
+        native
+            .add_points(&native_point(a), &native_point(b))
+            .map(|(x, y)| (x.0, y.0))
+    }
+
+    /// Multiplies a point by a scalar outside of the circuit, returning `None` for the identity.
+    fn mul_point(point: (&[u64], &[u64]), scalar: &[u64]) -> Option<(Vec<u64>, Vec<u64>)> {
+        let native = Native::new::<Self>();
+        native
+            .mul_point(&native_point(point), &Nat::new(scalar.to_vec()))
+            .map(|(x, y)| (x.0, y.0))
+    }
+}
+
+/// The secp256k1 curve `y^2 = x^3 + 7` of Bitcoin and Ethereum, in 4 limbs of 64 bits.
+#[derive(Clone, Copy, Debug)]
+pub struct Secp256k1;
+
+impl WeierstrassCurve for Secp256k1 {
+    const LIMB_WIDTH: usize = 64;
+    const N_LIMBS: usize = 4;
+
+    const P: &'static [u64] = &[0xffff_fffe_ffff_fc2f, u64::MAX, u64::MAX, u64::MAX];
+    const A: &'static [u64] = &[];
+    const B: &'static [u64] = &[7];
+
+    const ORDER: &'static [u64] = &[
+        0xbfd2_5e8c_d036_4141,
+        0xbaae_dce6_af48_a03b,
+        0xffff_ffff_ffff_fffe,
+        0xffff_ffff_ffff_ffff,
+    ];
+
+    const GENERATOR: (&'static [u64], &'static [u64]) = (
+        &[
+            0x59f2_815b_16f8_1798,
+            0x029b_fcdb_2dce_28d9,
+            0x55a0_6295_ce87_0b07,
+            0x79be_667e_f9dc_bbac,
+        ],
+        &[
+            0x9c47_d08f_fb10_d4b8,
+            0xfd17_b448_a685_5419,
+            0x5da4_fbfc_0e11_08a8,
+            0x483a_da77_26a3_c465,
+        ],
+    );
+}
+
+/// A point of the curve `C` with coordinates in limbs.
+pub struct AffinePoint<Scalar: PrimeField, C> {
+    x: BigNat<Scalar>,
+    y: BigNat<Scalar>,
+    _curve: PhantomData<C>,
+}
+
+impl<Scalar: PrimeField, C> Clone for AffinePoint<Scalar, C> {
+    fn clone(&self) -> Self {
+        AffinePoint {
+            x: self.x.clone(),
+            y: self.y.clone(),
+            _curve: PhantomData,
+        }
+    }
+}
+
+impl<Scalar: PrimeFieldBits, C: WeierstrassCurve> AffinePoint<Scalar, C> {
+    /// Allocates a point with range checked coordinates and enforces that it is on the curve.
+    pub fn alloc<CS>(mut cs: CS, value: Option<(&[u64], &[u64])>) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let x = BigNat::alloc(
+            cs.namespace(|| "x"),
+            value.map(|(x, _)| x),
+            C::LIMB_WIDTH,
+            C::N_LIMBS,
+        )?;
+        let y = BigNat::alloc(
+            cs.namespace(|| "y"),
+            value.map(|(_, y)| y),
+            C::LIMB_WIDTH,
+            C::N_LIMBS,
+        )?;
+
+        // Constrain: y^2 = x^3 + a x + b
+        let y2 = y.mul(cs.namespace(|| "y^2"), &y)?;
+        let x2 = x.mul(cs.namespace(|| "x^2"), &x)?;
+        let x3 = x2.mul(cs.namespace(|| "x^3"), &x)?;
+        let ax = x.mul(cs.namespace(|| "a x"), &constant::<Scalar, C>(C::A))?;
+        let rhs = x3.add(&ax).add(&constant::<Scalar, C>(C::B));
+        y2.enforce_equal_mod(cs.namespace(|| "curve equation"), &rhs, C::P)?;
+
+        Ok(AffinePoint {
+            x,
+            y,
+            _curve: PhantomData,
+        })
+    }
+
+    /// A constant point.
+    ///
+    /// Panics if the point isn't on the curve.
+    pub fn constant((x, y): (&[u64], &[u64])) -> Self {
+        assert!(C::is_on_curve((x, y)), "point isn't on the curve");
+
+        AffinePoint {
+            x: constant::<Scalar, C>(x),
+            y: constant::<Scalar, C>(y),
+            _curve: PhantomData,
+        }
+    }
+
+    pub fn get_x(&self) -> &BigNat<Scalar> {
+        &self.x
+    }
+
+    pub fn get_y(&self) -> &BigNat<Scalar> {
+        &self.y
+    }
+
+    /// The coordinates, which may be unreduced.
+    pub fn get_value(&self) -> Option<(Vec<u64>, Vec<u64>)> {
+        self.x.get_value().zip(self.y.get_value())
+    }
+
+    /// Adds a point with a different `x`, which is enforced.
+    ///
+    /// Returns [`SynthesisError::Unsatisfiable`] if the points have the same `x`.
+    pub fn add<CS>(&self, mut cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let native = Native::new::<C>();
+        let values = match (self.native_value(), other.native_value()) {
+            (Some(a), Some(b)) => {
+                let inverse = native
+                    .sub(&b.0, &a.0)
+                    .inverse_mod(&native.p)
+                    .ok_or(SynthesisError::Unsatisfiable)?;
+                let lambda = native.mul(&native.sub(&b.1, &a.1), &inverse);
+                let (x, y) = native.apply_slope(&lambda, &a, &b.0);
+                Some((inverse, lambda, x, y))
+            }
+            _ => None,
+        };
+
+        let inverse = alloc::<Scalar, C, _>(
+            cs.namespace(|| "inverse"),
+            values.as_ref().map(|values| &values.0),
+        )?;
+        let lambda = alloc::<Scalar, C, _>(
+            cs.namespace(|| "lambda"),
+            values.as_ref().map(|values| &values.1),
+        )?;
+        let x = alloc::<Scalar, C, _>(
+            cs.namespace(|| "x"),
+            values.as_ref().map(|values| &values.2),
+        )?;
+        let y = alloc::<Scalar, C, _>(
+            cs.namespace(|| "y"),
+            values.as_ref().map(|values| &values.3),
+        )?;
+
+        // Constrain: inverse * x2 = inverse * x1 + 1, so that x1 != x2
+        let inverse_x1 = inverse.mul(cs.namespace(|| "inverse * x1"), &self.x)?;
+        let inverse_x2 = inverse.mul(cs.namespace(|| "inverse * x2"), &other.x)?;
+        inverse_x2.enforce_equal_mod(
+            cs.namespace(|| "distinct x"),
+            &inverse_x1.add(&constant::<Scalar, C>(&[1])),
+            C::P,
+        )?;
+
+        // Constrain: lambda * x2 + y1 = lambda * x1 + y2
+        let lambda_x1 = lambda.mul(cs.namespace(|| "lambda * x1"), &self.x)?;
+        let lambda_x2 = lambda.mul(cs.namespace(|| "lambda * x2"), &other.x)?;
+        lambda_x2.add(&self.y).enforce_equal_mod(
+            cs.namespace(|| "lambda equation"),
+            &lambda_x1.add(&other.y),
+            C::P,
+        )?;
+
+        // Constrain: x3 + x1 + x2 = lambda^2
+        let lambda2 = lambda.mul(cs.namespace(|| "lambda^2"), &lambda)?;
+        x.add(&self.x).add(&other.x).enforce_equal_mod(
+            cs.namespace(|| "x equation"),
+            &lambda2,
+            C::P,
+        )?;
+
+        // Constrain: y3 + y1 + lambda * x3 = lambda * x1
+        let lambda_x3 = lambda.mul(cs.namespace(|| "lambda * x3"), &x)?;
+        y.add(&self.y).add(&lambda_x3).enforce_equal_mod(
+            cs.namespace(|| "y equation"),
+            &lambda_x1,
+            C::P,
+        )?;
+
+        Ok(AffinePoint {
+            x,
+            y,
+            _curve: PhantomData,
+        })
+    }
+
+    /// Doubles the point, which has no exceptions on curves of prime order.
+    pub fn double<CS>(&self, mut cs: CS) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let native = Native::new::<C>();
+        let values = match self.native_value() {
+            Some(point) => {
+                let lambda = native
+                    .double_slope(&point)
+                    .ok_or(SynthesisError::Unsatisfiable)?;
+                let (x, y) = native.apply_slope(&lambda, &point, &point.0);
+                Some((lambda, x, y))
+            }
+            None => None,
+        };
+
+        let lambda = alloc::<Scalar, C, _>(
+            cs.namespace(|| "lambda"),
+            values.as_ref().map(|values| &values.0),
+        )?;
+        let x = alloc::<Scalar, C, _>(
+            cs.namespace(|| "x"),
+            values.as_ref().map(|values| &values.1),
+        )?;
+        let y = alloc::<Scalar, C, _>(
+            cs.namespace(|| "y"),
+            values.as_ref().map(|values| &values.2),
+        )?;
+
+        // Constrain: lambda * 2 y = 3 x^2 + a
+        let lambda_2y = lambda.mul(cs.namespace(|| "lambda * 2y"), &self.y.add(&self.y))?;
+        let x2 = self.x.mul(cs.namespace(|| "x^2"), &self.x)?;
+        lambda_2y.enforce_equal_mod(
+            cs.namespace(|| "lambda equation"),
+            &x2.add(&x2).add(&x2).add(&constant::<Scalar, C>(C::A)),
+            C::P,
+        )?;
+
+        // Constrain: x3 + 2 x = lambda^2
+        let lambda2 = lambda.mul(cs.namespace(|| "lambda^2"), &lambda)?;
+        x.add(&self.x).add(&self.x).enforce_equal_mod(
+            cs.namespace(|| "x equation"),
+            &lambda2,
+            C::P,
+        )?;
+
+        // Constrain: y3 + y + lambda * x3 = lambda * x
+        let lambda_x = lambda.mul(cs.namespace(|| "lambda * x"), &self.x)?;
+        let lambda_x3 = lambda.mul(cs.namespace(|| "lambda * x3"), &x)?;
+        y.add(&self.y).add(&lambda_x3).enforce_equal_mod(
+            cs.namespace(|| "y equation"),
+            &lambda_x,
+            C::P,
+        )?;
+
+        Ok(AffinePoint {
+            x,
+            y,
+            _curve: PhantomData,
+        })
+    }
+
+    /// Returns `a` if `condition` is set and `b` otherwise, which costs a constraint per limb of
+    /// the coordinates.
+    pub fn select<CS>(
+        mut cs: CS,
+        condition: &Boolean,
+        a: &Self,
+        b: &Self,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        Ok(AffinePoint {
+            x: BigNat::select(cs.namespace(|| "x"), condition, &a.x, &b.x)?,
+            y: BigNat::select(cs.namespace(|| "y"), condition, &a.y, &b.y)?,
+            _curve: PhantomData,
+        })
+    }
+
+    fn native_value(&self) -> Option<(Nat, Nat)> {
+        self.get_value().and_then(|(x, y)| native_point((&x, &y)))
+    }
+}
+
+fn constant<Scalar: PrimeFieldBits, C: WeierstrassCurve>(value: &[u64]) -> BigNat<Scalar> {
+    BigNat::constant(value, C::LIMB_WIDTH, C::N_LIMBS)
+}
+
+fn alloc<Scalar, C, CS>(cs: CS, value: Option<&Nat>) -> Result<BigNat<Scalar>, SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    C: WeierstrassCurve,
+    CS: ConstraintSystem<Scalar>,
+{
+    BigNat::alloc_nat(cs, value, C::LIMB_WIDTH, C::N_LIMBS)
+}
+
+/// A point outside of the circuit, `None` being the identity.
+type NativePoint = Option<(Nat, Nat)>;
+
+fn native_point((x, y): (&[u64], &[u64])) -> NativePoint {
+    Some((Nat::new(x.to_vec()), Nat::new(y.to_vec())))
+}
+
+/// Arithmetic modulo `p` and on points outside of the circuit. Results are reduced, arguments
+/// need not be.
+struct Native {
+    p: Nat,
+    a: Nat,
+}
+
+impl Native {
+    fn new<C: WeierstrassCurve + ?Sized>() -> Self {
+        Native {
+            p: Nat::new(C::P.to_vec()),
+            a: Nat::new(C::A.to_vec()),
+        }
+    }
+
+    fn reduce(&self, x: &Nat) -> Nat {
+        x.divrem(&self.p).1
+    }
+
+    fn add(&self, x: &Nat, y: &Nat) -> Nat {
+        self.reduce(&x.add(y))
+    }
+
+    fn sub(&self, x: &Nat, y: &Nat) -> Nat {
+        self.reduce(&x.add(&self.p).sub(&self.reduce(y)))
+    }
+
+    fn mul(&self, x: &Nat, y: &Nat) -> Nat {
+        self.reduce(&x.mul(y))
+    }
+
+    /// `None` if `y` is zero.
+    fn div(&self, x: &Nat, y: &Nat) -> Option<Nat> {
+        Some(self.mul(x, &y.inverse_mod(&self.p)?))
+    }
+
+    /// The slope of the tangent at the point, `None` if it is vertical.
+    fn double_slope(&self, (x, y): &(Nat, Nat)) -> Option<Nat> {
+        let x2 = self.mul(x, x);
+        let numerator = self.add(&self.add(&x2, &x2), &self.add(&x2, &self.a));
+        self.div(&numerator, &self.add(y, y))
+    }
+
+    /// The sum of `(x1, y1)` and the point with `x2` on the line through it with slope `lambda`.
+    fn apply_slope(&self, lambda: &Nat, (x1, y1): &(Nat, Nat), x2: &Nat) -> (Nat, Nat) {
+        let x3 = self.sub(&self.sub(&self.mul(lambda, lambda), x1), x2);
+        let y3 = self.sub(&self.mul(lambda, &self.sub(x1, &x3)), y1);
+        (x3, y3)
+    }
+
+    fn add_points(&self, a: &NativePoint, b: &NativePoint) -> NativePoint {
+        let (a, b) = match (a, b) {
+            (Some(a), Some(b)) => (a, b),
+            (None, point) | (point, None) => return point.clone(),
+        };
+
+        if self.reduce(&a.0) == self.reduce(&b.0) {
+            return if self.reduce(&a.1) == self.reduce(&b.1) {
+                self.double(a)
+            } else {
+                None
+            };
+        }
+        let lambda = self.div(&self.sub(&b.1, &a.1), &self.sub(&b.0, &a.0))?;
+        Some(self.apply_slope(&lambda, a, &b.0))
+    }
+
+    fn double(&self, point: &(Nat, Nat)) -> NativePoint {
+        let lambda = self.double_slope(point)?;
+        Some(self.apply_slope(&lambda, point, &point.0))
+    }
+
+    fn mul_point(&self, point: &NativePoint, scalar: &Nat) -> NativePoint {
+        let mut acc = None;
+        let mut base = point.clone();
+        for i in 0..scalar.bits() {
+            if scalar.bit(i) {
+                acc = self.add_points(&acc, &base);
+            }
+            base = base.and_then(|base| self.double(&base));
+        }
+        acc
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+
+    use crate::gadgets::boolean::AllocatedBit;
+    use crate::gadgets::test::*;
+
+    #[test]
+    fn test_secp256k1_parameters() {
+        let generator = Secp256k1::GENERATOR;
+        assert!(Secp256k1::is_on_curve(generator));
+        assert!(!Secp256k1::is_on_curve((generator.0, &[1][..])));
+
+        let double = Secp256k1::add_points(generator, generator).unwrap();
+        let triple = Secp256k1::add_points(generator, (&double.0, &double.1)).unwrap();
+        assert!(Secp256k1::is_on_curve((&double.0, &double.1)));
+        assert_eq!(Secp256k1::mul_point(generator, &[3]), Some(triple));
+
+        let negated = Secp256k1::negate_point(generator);
+        assert!(Secp256k1::is_on_curve((&negated.0, &negated.1)));
+        assert_eq!(
+            Secp256k1::add_points(generator, (&negated.0, &negated.1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_affine_point_secp256k1() {
+        let generator = Secp256k1::GENERATOR;
+        let double = Secp256k1::add_points(generator, generator).unwrap();
+        let triple = Secp256k1::add_points(generator, (&double.0, &double.1)).unwrap();
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let a = AffinePoint::<Fr, Secp256k1>::alloc(cs.namespace(|| "a"), Some(generator)).unwrap();
+        let b = a.double(cs.namespace(|| "double")).unwrap();
+        assert_eq!(b.get_value(), Some(double.clone()));
+        let c = b.add(cs.namespace(|| "add"), &a).unwrap();
+        assert_eq!(c.get_value(), Some(triple.clone()));
+        let d = AffinePoint::<Fr, Secp256k1>::constant((&double.0, &double.1))
+            .add(cs.namespace(|| "add constant"), &a)
+            .unwrap();
+        assert_eq!(d.get_value(), Some(triple));
+
+        for condition in [false, true] {
+            let bit = Boolean::from(
+                AllocatedBit::alloc(
+                    cs.namespace(|| format!("condition {}", condition)),
+                    Some(condition),
+                )
+                .unwrap(),
+            );
+            let selected = AffinePoint::select(
+                cs.namespace(|| format!("select {}", condition)),
+                &bit,
+                &b,
+                &a,
+            )
+            .unwrap();
+            assert_eq!(
+                selected.get_value(),
+                if condition {
+                    b.get_value()
+                } else {
+                    a.get_value()
+                }
+            );
+        }
+        assert!(cs.is_satisfied());
+
+        // Adding a point to itself is an exception of the addition law.
+        assert!(matches!(
+            a.add(cs.namespace(|| "exception"), &a),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+
+        // A point that isn't on the curve is rejected.
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        AffinePoint::<Fr, Secp256k1>::alloc(cs.namespace(|| "a"), Some((generator.0, &[1][..])))
+            .unwrap();
+        assert!(!cs.is_satisfied());
+    }
+}
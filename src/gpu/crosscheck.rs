@@ -0,0 +1,130 @@
+//! Validation of GPU results against the CPU.
+//!
+//! GPUs that are overclocked or have memory without ECC can silently return wrong results, which
+//! then lead to invalid proofs. With `BELLMAN_GPU_CROSSCHECK` set to a fraction in [0,1], that
+//! fraction of the GPU FFTs and multiexps is recomputed on the CPU and compared. On a mismatch
+//! the mismatch is logged and the CPU result is used instead. The log only contains sizes,
+//! indices and counts, as the values are derived from the witness.
+
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use log::error;
+
+static CALLS: AtomicU64 = AtomicU64::new(0);
+static CHECKED: AtomicU64 = AtomicU64::new(0);
+static MISMATCHES: AtomicU64 = AtomicU64::new(0);
+
+/// The number of GPU results that were recomputed on the CPU and how many of them differed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CrossCheckStats {
+    pub checked: u64,
+    pub mismatches: u64,
+}
+
+/// Returns the cross-check statistics since the start of the process.
+pub fn cross_check_stats() -> CrossCheckStats {
+    CrossCheckStats {
+        checked: CHECKED.load(Ordering::Relaxed),
+        mismatches: MISMATCHES.load(Ordering::Relaxed),
+    }
+}
+
+fn get_cross_check_fraction() -> f64 {
+    env::var("BELLMAN_GPU_CROSSCHECK")
+        .map_or(0f64, |v| match v.parse() {
+            Ok(val) => val,
+            Err(_) => {
+                error!("Invalid BELLMAN_GPU_CROSSCHECK! Defaulting to 0...");
+                0f64
+            }
+        })
+        .clamp(0f64, 1f64)
+}
+
+/// Returns whether the `call`-th GPU result should be checked. The checks are spread evenly, so
+/// that exactly the configured fraction of results is checked.
+fn is_sampled(call: u64, fraction: f64) -> bool {
+    ((call + 1) as f64 * fraction).floor() > (call as f64 * fraction).floor()
+}
+
+/// Returns whether the next GPU result should be recomputed on the CPU.
+pub(crate) fn should_check() -> bool {
+    let fraction = get_cross_check_fraction();
+    if fraction == 0f64 {
+        return false;
+    }
+    is_sampled(CALLS.fetch_add(1, Ordering::Relaxed), fraction)
+}
+
+/// Compares the result of a GPU multiexp of `size` terms with the CPU one, returns whether they
+/// are equal.
+pub(crate) fn check_multiexp<G: PartialEq>(size: usize, gpu: &G, cpu: &G) -> bool {
+    CHECKED.fetch_add(1, Ordering::Relaxed);
+    if gpu == cpu {
+        return true;
+    }
+
+    MISMATCHES.fetch_add(1, Ordering::Relaxed);
+    error!("GPU Multiexp of size {} differs from the CPU!", size);
+    false
+}
+
+/// Compares the results of a batch of GPU FFTs with the CPU ones, returns whether they are
+/// equal.
+pub(crate) fn check_fft<F: PartialEq>(gpu: &[&mut [F]], cpu: &[Vec<F>]) -> bool {
+    CHECKED.fetch_add(1, Ordering::Relaxed);
+    let mut equal = true;
+    for (index, (gpu, cpu)) in gpu.iter().zip(cpu.iter()).enumerate() {
+        let mut differing = gpu
+            .iter()
+            .zip(cpu.iter())
+            .enumerate()
+            .filter(|(_, (gpu, cpu))| gpu != cpu);
+        if let Some((first, _)) = differing.next() {
+            equal = false;
+            error!(
+                "GPU FFT {} of size {} differs from the CPU in {} coefficients! First at {}",
+                index,
+                cpu.len(),
+                differing.count() + 1,
+                first
+            );
+        }
+    }
+
+    if !equal {
+        MISMATCHES.fetch_add(1, Ordering::Relaxed);
+    }
+    equal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampling() {
+        for (fraction, expected) in [(0.0, 0), (0.25, 25), (0.5, 50), (1.0, 100)] {
+            let sampled = (0..100).filter(|&call| is_sampled(call, fraction)).count();
+            assert_eq!(sampled, expected, "fraction {}", fraction);
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let mut gpu = vec![vec![1u64, 2, 3], vec![4, 5, 6]];
+        let cpu = gpu.clone();
+        {
+            let gpu = gpu.iter_mut().map(|c| &mut c[..]).collect::<Vec<_>>();
+            assert!(check_fft(&gpu, &cpu));
+        }
+
+        gpu[1][0] = 0;
+        gpu[1][2] = 0;
+        let gpu = gpu.iter_mut().map(|c| &mut c[..]).collect::<Vec<_>>();
+        assert!(!check_fft(&gpu, &cpu));
+        assert!(check_multiexp(3, &1u64, &1));
+        assert!(!check_multiexp(3, &1u64, &2));
+    }
+}
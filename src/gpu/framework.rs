@@ -0,0 +1,103 @@
+//! Selection of the framework the GPU kernels run on, for builds with both CUDA and OpenCL
+//! support.
+
+/// A framework the GPU kernels can run on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GpuFramework {
+    Cuda,
+    Opencl,
+}
+
+/// Selects the framework of the GPU kernels per device.
+///
+/// Devices without an entry in `devices` use `default`. If that isn't set either, the framework
+/// is selected by the `EC_GPU_FRAMEWORK` environment variable, or is the default of the device.
+/// Selecting a framework that bellperson wasn't compiled with makes the kernel creation fail,
+/// the prover then falls back to the CPU.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FrameworkSelection {
+    /// The framework of all devices without an entry in `devices`.
+    pub default: Option<GpuFramework>,
+    /// The frameworks of single devices, by their unique id as it is displayed by
+    /// `rust_gpu_tools::Device::unique_id`, i.e. a PCI id like `01:00` or a UUID.
+    pub devices: Vec<(String, GpuFramework)>,
+}
+
+impl FrameworkSelection {
+    /// Runs the kernels of all devices on `framework`.
+    pub fn all(framework: GpuFramework) -> Self {
+        FrameworkSelection {
+            default: Some(framework),
+            devices: Vec::new(),
+        }
+    }
+
+    /// Runs the kernels of the device with the given unique id on `framework`.
+    pub fn with_device<S: Into<String>>(mut self, unique_id: S, framework: GpuFramework) -> Self {
+        self.devices.push((unique_id.into(), framework));
+        self
+    }
+
+    /// Returns the framework selected for the device with the given unique id, `None` means
+    /// that the environment decides.
+    pub fn framework(&self, unique_id: &str) -> Option<GpuFramework> {
+        self.devices
+            .iter()
+            .find(|(id, _)| id == unique_id)
+            .map(|&(_, framework)| framework)
+            .or(self.default)
+    }
+}
+
+/// Creates the program of the kernels for `device` on the framework selected for it.
+#[cfg(any(feature = "cuda", feature = "opencl"))]
+pub(crate) fn program(
+    device: &ec_gpu_gen::rust_gpu_tools::Device,
+    frameworks: &FrameworkSelection,
+) -> ec_gpu_gen::EcResult<ec_gpu_gen::rust_gpu_tools::Program> {
+    use ec_gpu_gen::rust_gpu_tools::{GPUError, Program};
+
+    match frameworks.framework(&device.unique_id().to_string()) {
+        None => ec_gpu_gen::program!(device),
+        #[cfg(feature = "cuda")]
+        Some(GpuFramework::Cuda) => {
+            let kernel = include_bytes!(env!("_EC_GPU_CUDA_KERNEL_FATBIN"));
+            let cuda_device = device.cuda_device().ok_or(GPUError::DeviceNotFound)?;
+            let program =
+                ec_gpu_gen::rust_gpu_tools::cuda::Program::from_bytes(cuda_device, kernel)?;
+            Ok(Program::Cuda(program))
+        }
+        #[cfg(not(feature = "cuda"))]
+        Some(GpuFramework::Cuda) => Err(ec_gpu_gen::EcError::Simple(
+            "CUDA framework is not supported, please compile with the `cuda` feature enabled.",
+        )),
+        #[cfg(feature = "opencl")]
+        Some(GpuFramework::Opencl) => {
+            let source = include_str!(env!("_EC_GPU_OPENCL_KERNEL_SOURCE"));
+            let opencl_device = device.opencl_device().ok_or(GPUError::DeviceNotFound)?;
+            let program =
+                ec_gpu_gen::rust_gpu_tools::opencl::Program::from_opencl(opencl_device, source)?;
+            Ok(Program::Opencl(program))
+        }
+        #[cfg(not(feature = "opencl"))]
+        Some(GpuFramework::Opencl) => Err(ec_gpu_gen::EcError::Simple(
+            "OpenCL framework is not supported, please compile with the `opencl` feature enabled.",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_framework_selection() {
+        let frameworks = FrameworkSelection::default();
+        assert_eq!(frameworks.framework("01:00"), None);
+
+        let frameworks =
+            FrameworkSelection::all(GpuFramework::Opencl).with_device("01:00", GpuFramework::Cuda);
+        assert_eq!(frameworks.framework("01:00"), Some(GpuFramework::Cuda));
+        assert_eq!(frameworks.framework("02:00"), Some(GpuFramework::Opencl));
+    }
+}
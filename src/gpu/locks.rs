@@ -10,7 +10,7 @@ use group::prime::PrimeCurveAffine;
 use log::{debug, info, warn};
 
 use crate::gpu::error::{GpuError, GpuResult};
-use crate::gpu::{CpuGpuMultiexpKernel, GpuName};
+use crate::gpu::{self, CpuGpuMultiexpKernel, FrameworkSelection, GpuName};
 
 const GPU_LOCK_NAME: &str = "bellman.gpu.lock";
 const PRIORITY_LOCK_NAME: &str = "bellman.priority.lock";
@@ -189,7 +189,15 @@ impl Drop for PriorityLock {
     }
 }
 
-fn create_fft_kernel<'a, F>(priority: bool) -> Option<(FftKernel<'a, F>, GPULock<'a>)>
+/// Returns the memory in bytes of every available GPU.
+pub fn device_memory() -> Vec<u64> {
+    Device::all().iter().map(|device| device.memory()).collect()
+}
+
+fn create_fft_kernel<'a, F>(
+    priority: bool,
+    frameworks: &FrameworkSelection,
+) -> Option<(FftKernel<'a, F>, GPULock<'a>)>
 where
     F: Field + GpuName,
 {
@@ -197,7 +205,7 @@ where
     let programs = lock
         .devices()
         .iter()
-        .map(|device| ec_gpu_gen::program!(device))
+        .map(|device| gpu::program(device, frameworks))
         .collect::<Result<_, _>>()
         .ok()?;
 
@@ -221,6 +229,7 @@ where
 
 fn create_multiexp_kernel<'a, G>(
     priority: bool,
+    frameworks: &FrameworkSelection,
 ) -> Option<(CpuGpuMultiexpKernel<'a, G>, GPULock<'a>)>
 where
     G: PrimeCurveAffine + GpuName,
@@ -229,10 +238,10 @@ where
     let devices = lock.devices();
 
     let kernel = if priority {
-        CpuGpuMultiexpKernel::create(&devices)
+        CpuGpuMultiexpKernel::create(&devices, frameworks)
     } else {
         // Low priority kernels may be aborted if a high priority kernel wants to run/is running.
-        CpuGpuMultiexpKernel::create_with_abort(&devices, &PriorityLock::is_taken)
+        CpuGpuMultiexpKernel::create_with_abort(&devices, frameworks, &PriorityLock::is_taken)
     };
     match kernel {
         Ok(k) => {
@@ -247,6 +256,10 @@ where
 }
 
 /// Wrap the kernel so that only a single one runs on the GPU at a time.
+///
+/// The kernel (and with it the compiled program) is kept across calls, the device buffers are
+/// not: they are allocated and freed by `ec-gpu-gen` within every FFT and multiexp call. Pooling
+/// them across calls requires that allocation to be pluggable in `ec-gpu-gen`.
 macro_rules! locked_kernel {
     ($kernel:ty, $func:ident, $name:expr, pub struct $class:ident<$lifetime:lifetime, $generic:ident>
         where $(
@@ -259,6 +272,7 @@ macro_rules! locked_kernel {
         )+
         {
             priority: bool,
+            frameworks: FrameworkSelection,
             // Keep the GPU lock alongside the kernel, so that the lock is automatically dropped
             // if the kernel is dropped.
             kernel_and_lock: Option<($kernel, GPULock<'a>)>,
@@ -270,8 +284,15 @@ macro_rules! locked_kernel {
         )+
         {
             pub fn new(priority: bool) -> Self {
+                Self::with_frameworks(priority, FrameworkSelection::default())
+            }
+
+            /// Like [`Self::new`], but the kernel runs on the frameworks selected by `frameworks`
+            /// instead of the ones selected by the environment.
+            pub fn with_frameworks(priority: bool, frameworks: FrameworkSelection) -> Self {
                 Self {
                     priority,
+                    frameworks,
                     kernel_and_lock: None,
                 }
             }
@@ -283,7 +304,7 @@ macro_rules! locked_kernel {
                 if self.kernel_and_lock.is_none() {
                     PriorityLock::wait(self.priority);
                     info!("GPU is available for {}!", $name);
-                    if let Some((kernel, lock)) = $func(self.priority) {
+                    if let Some((kernel, lock)) = $func(self.priority, &self.frameworks) {
                         self.kernel_and_lock = Some((kernel, lock));
                     }
                 }
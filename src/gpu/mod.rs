@@ -1,7 +1,15 @@
 mod error;
+mod framework;
 
 pub use self::error::*;
+#[cfg(any(feature = "cuda", feature = "opencl"))]
+pub(crate) use self::framework::program;
+pub use self::framework::{FrameworkSelection, GpuFramework};
 
+// The FFT and multiexp kernels are launched by `ec-gpu-gen` (through `rust-gpu-tools`), which
+// doesn't expose the CUDA streams it launches on. Batching those launches, e.g. by capturing them
+// into CUDA graphs that are replayed per proof, therefore needs support there. This crate only
+// decides which kernel runs when and on which devices.
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 mod locks;
 
@@ -11,6 +19,14 @@ pub use self::locks::*;
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 mod multiexp;
 
+#[cfg(any(feature = "cuda", feature = "opencl"))]
+mod crosscheck;
+
+#[cfg(any(feature = "cuda", feature = "opencl"))]
+pub(crate) use self::crosscheck::{check_fft, check_multiexp, should_check};
+#[cfg(any(feature = "cuda", feature = "opencl"))]
+pub use self::crosscheck::{cross_check_stats, CrossCheckStats};
+
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 pub use self::multiexp::CpuGpuMultiexpKernel;
 
@@ -11,7 +11,7 @@ use ff::PrimeField;
 use group::{prime::PrimeCurveAffine, Group};
 use log::{error, info};
 
-use crate::gpu::GpuName;
+use crate::gpu::{self, FrameworkSelection, GpuName};
 
 pub fn get_cpu_utilization() -> f64 {
     env::var("BELLMAN_CPU_UTILIZATION")
@@ -61,37 +61,44 @@ impl<'a, G> CpuGpuMultiexpKernel<'a, G>
 where
     G: PrimeCurveAffine + GpuName,
 {
-    /// Create new kernels, one for each given device.
-    pub fn create(devices: &[&Device]) -> EcResult<Self> {
+    /// Create new kernels, one for each given device, on the frameworks selected for them.
+    pub fn create(devices: &[&Device], frameworks: &FrameworkSelection) -> EcResult<Self> {
         info!("Multiexp: CPU utilization: {}.", get_cpu_utilization());
         set_custom_gpu_env_var();
         let programs = devices
             .iter()
-            .map(|device| ec_gpu_gen::program!(device))
+            .map(|device| gpu::program(device, frameworks))
             .collect::<Result<_, _>>()?;
         let kernel = MultiexpKernel::create(programs, devices)?;
         Ok(Self(kernel))
     }
 
-    /// Create new kernels, one for each given device, with early abort hook.
+    /// Create new kernels, one for each given device, on the frameworks selected for them, with
+    /// early abort hook.
     ///
     /// The `maybe_abort` function is called when it is possible to abort the computation, without
     /// leaving the GPU in a weird state. If that function returns `true`, execution is aborted.
     pub fn create_with_abort(
         devices: &[&Device],
+        frameworks: &FrameworkSelection,
         maybe_abort: &'a (dyn Fn() -> bool + Send + Sync),
     ) -> EcResult<Self> {
         info!("Multiexp: CPU utilization: {}.", get_cpu_utilization());
         set_custom_gpu_env_var();
         let programs = devices
             .iter()
-            .map(|device| ec_gpu_gen::program!(device))
+            .map(|device| gpu::program(device, frameworks))
             .collect::<Result<_, _>>()?;
         let kernel = MultiexpKernel::create_with_abort(programs, devices, maybe_abort)?;
         Ok(Self(kernel))
     }
 
     /// Calculate multiexp.
+    ///
+    /// The bases are uploaded to the device on every call, as the device buffers are allocated
+    /// and owned by `ec-gpu-gen`'s `MultiexpKernel` for the duration of a single
+    /// `parallel_multiexp()`. Keeping the parameter bases resident on the device across proofs
+    /// needs an API there that accepts buffers which were uploaded before.
     pub fn multiexp(
         &mut self,
         pool: &Worker,
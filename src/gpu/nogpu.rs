@@ -8,7 +8,7 @@ use group::prime::PrimeCurveAffine;
 use std::marker::PhantomData;
 use std::sync::Arc;
 
-use crate::gpu::GpuName;
+use crate::gpu::{FrameworkSelection, GpuName};
 
 pub struct MultiexpKernel<G>(PhantomData<G>)
 where
@@ -37,6 +37,11 @@ where
     }
 }
 
+/// Returns the memory in bytes of every available GPU, which is none without GPU support.
+pub fn device_memory() -> Vec<u64> {
+    Vec::new()
+}
+
 macro_rules! locked_kernel {
     (pub struct $class:ident<$generic:ident>
         where $(
@@ -54,6 +59,10 @@ macro_rules! locked_kernel {
                 Self(PhantomData)
             }
 
+            pub fn with_frameworks(_: bool, _: FrameworkSelection) -> Self {
+                Self(PhantomData)
+            }
+
             pub fn with<Fun, R, K>(&mut self, _: Fun) -> GpuResult<R>
             where
                 Fun: FnMut(&mut K) -> GpuResult<R>,
@@ -1,16 +1,18 @@
+#[cfg(all(feature = "multicore", not(target_arch = "wasm32")))]
 use crossbeam_channel::{bounded, Receiver, Sender};
 use ff::Field;
 use group::{Curve, Group};
 use pairing::{Engine, MillerLoopResult, MultiMillerLoop};
 use rand_core::RngCore;
-use rayon::prelude::*;
 
+use crate::multicore::prelude::*;
 use crate::SynthesisError;
 use std::ops::Mul;
 use std::sync::{
     atomic::{AtomicBool, Ordering::SeqCst},
     Arc, Mutex,
 };
+#[cfg(all(feature = "multicore", not(target_arch = "wasm32")))]
 use std::thread;
 
 /// Holds the logic for merging multiple pairing checks of the form
@@ -29,30 +31,31 @@ where
 {
     /// Circuit breaker to allow canceling all checks and marking the whole check as failed.
     valid: Arc<AtomicBool>,
-    merge_send: Sender<Result<PairingCheck<E>, SynthesisError>>,
-    valid_recv: Receiver<Result<bool, SynthesisError>>,
+    merger: Merger<E>,
     /// Random number generator used for generating the random coefficients.
     rng: Mutex<R>,
     /// Ensures that the non randomized check is only added exactly once.
     non_random_check_done: AtomicBool,
 }
 
-impl<E, R> PairingChecks<E, R>
-where
-    E: MultiMillerLoop,
-    R: RngCore + Send,
-{
+/// Merges the checks on a separate thread, so that merging doesn't block the callers.
+#[cfg(all(feature = "multicore", not(target_arch = "wasm32")))]
+#[derive(Debug)]
+struct Merger<E: MultiMillerLoop> {
+    merge_send: Sender<Result<PairingCheck<E>, SynthesisError>>,
+    valid_recv: Receiver<Result<bool, SynthesisError>>,
+}
+
+#[cfg(all(feature = "multicore", not(target_arch = "wasm32")))]
+impl<E: MultiMillerLoop> Merger<E> {
     #[allow(clippy::type_complexity)]
-    pub fn new(rng: R) -> Self {
+    fn new(valid: Arc<AtomicBool>) -> Self {
         let (merge_send, merge_recv): (
             Sender<Result<PairingCheck<E>, SynthesisError>>,
             Receiver<Result<PairingCheck<E>, SynthesisError>>,
         ) = bounded(10);
         let (valid_send, valid_recv) = bounded(1);
 
-        let valid = Arc::new(AtomicBool::new(true));
-        let valid_copy = valid.clone();
-
         // Spawn this thread outside of the Rayon thread pool, so that it can always receive
         // messages, even if the thread pool is fully occupied.
         thread::spawn(move || {
@@ -61,7 +64,7 @@ where
                 match tuple {
                     Ok(check) => {
                         // only do work as long as we know we are still valid
-                        if valid_copy.load(SeqCst) {
+                        if valid.load(SeqCst) {
                             acc.merge(&check);
                         } else {
                             return;
@@ -69,21 +72,99 @@ where
                     }
                     Err(e) => {
                         // we signal an invalid proof - malformed for example
-                        valid_copy.store(false, SeqCst);
+                        valid.store(false, SeqCst);
                         valid_send.send(Err(e)).expect("failed to send error");
                         return;
                     }
                 }
             }
-            if valid_copy.load(SeqCst) {
+            if valid.load(SeqCst) {
                 valid_send.send(Ok(acc.verify())).expect("failed to send");
             }
         });
 
-        PairingChecks {
-            valid,
+        Merger {
             merge_send,
             valid_recv,
+        }
+    }
+
+    /// Returns whether the check could be sent, which it can't once the checks are invalid.
+    fn send(&self, check: Result<PairingCheck<E>, SynthesisError>) -> bool {
+        self.merge_send.send(check).is_ok()
+    }
+
+    /// Waits for all checks to be merged and verifies them.
+    fn finish(self, valid: &AtomicBool) -> Result<bool, SynthesisError> {
+        let Self {
+            merge_send,
+            valid_recv,
+        } = self;
+
+        drop(merge_send); // stop the merge process
+
+        if !valid.load(SeqCst) {
+            return Ok(false);
+        }
+        valid_recv.recv().unwrap()
+    }
+}
+
+/// Merges the checks on the calling thread.
+#[cfg(any(not(feature = "multicore"), target_arch = "wasm32"))]
+#[derive(Debug)]
+struct Merger<E: MultiMillerLoop> {
+    valid: Arc<AtomicBool>,
+    acc: Mutex<Result<PairingCheck<E>, SynthesisError>>,
+}
+
+#[cfg(any(not(feature = "multicore"), target_arch = "wasm32"))]
+impl<E: MultiMillerLoop> Merger<E> {
+    fn new(valid: Arc<AtomicBool>) -> Self {
+        Merger {
+            valid,
+            acc: Mutex::new(Ok(PairingCheck::new())),
+        }
+    }
+
+    /// Returns whether the check could be merged, which it can't once the checks are invalid.
+    fn send(&self, check: Result<PairingCheck<E>, SynthesisError>) -> bool {
+        if !self.valid.load(SeqCst) {
+            return false;
+        }
+        let mut acc = self.acc.lock().unwrap();
+        match (&mut *acc, check) {
+            (Ok(acc), Ok(check)) => acc.merge(&check),
+            // we signal an invalid proof - malformed for example
+            (acc, Err(e)) => {
+                self.valid.store(false, SeqCst);
+                *acc = Err(e);
+            }
+            (Err(_), Ok(_)) => {}
+        }
+        true
+    }
+
+    /// Verifies the merged checks.
+    fn finish(self, valid: &AtomicBool) -> Result<bool, SynthesisError> {
+        if !valid.load(SeqCst) {
+            return Ok(false);
+        }
+        Ok(self.acc.into_inner().unwrap()?.verify())
+    }
+}
+
+impl<E, R> PairingChecks<E, R>
+where
+    E: MultiMillerLoop,
+    R: RngCore + Send,
+{
+    pub fn new(rng: R) -> Self {
+        let valid = Arc::new(AtomicBool::new(true));
+
+        PairingChecks {
+            merger: Merger::new(valid.clone()),
+            valid,
             rng: Mutex::new(rng),
             non_random_check_done: AtomicBool::new(false),
         }
@@ -95,9 +176,8 @@ where
     }
 
     pub fn report_err(&self, e: SynthesisError) {
-        self.merge_send
-            .send(Err(e))
-            .expect("expect to send on channel");
+        let sent = self.merger.send(Err(e));
+        assert!(sent, "expect to send on channel");
     }
 
     fn merge_pair(
@@ -171,26 +251,14 @@ where
         // that receives those messages, hence also the receiving channel is closed.
         // This means that if the aggrigation is invalid, it is expected that the message cannot
         // be sent.
-        let sent = self.merge_send.send(Ok(check));
-        if sent.is_err() && self.valid.load(SeqCst) {
+        let sent = self.merger.send(Ok(check));
+        if !sent && self.valid.load(SeqCst) {
             panic!("Channel was closed although it is still valid.")
         }
     }
 
     pub fn verify(self) -> Result<bool, SynthesisError> {
-        let Self {
-            valid,
-            merge_send,
-            valid_recv,
-            ..
-        } = self;
-
-        drop(merge_send); // stop the merge process
-
-        if !valid.load(SeqCst) {
-            return Ok(false);
-        }
-        valid_recv.recv().unwrap()
+        self.merger.finish(&self.valid)
     }
 }
 
@@ -28,9 +28,9 @@
 use std::ops::AddAssign;
 
 use group::{prime::PrimeCurveAffine, Curve};
-use rayon::prelude::*;
 
 use crate::groth16::aggregate::inner_product;
+use crate::multicore::prelude::*;
 use crate::SynthesisError;
 use pairing::{Engine, MultiMillerLoop};
 
@@ -0,0 +1,45 @@
+//! Serde support for [`AggregateProof`] in the compressed encoding of
+//! [`AggregateProof::write`], to be used with `#[serde(with = "...")]`:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Response {
+//!     #[serde(with = "bellperson::groth16::aggregate::compressed")]
+//!     proof: AggregateProof<Bls12>,
+//! }
+//! ```
+//!
+//! Human readable formats like JSON get the proof as a hex string, binary
+//! formats as a byte string. Unlike the derived implementation, which encodes
+//! every field on its own, the target group elements are compressed.
+
+use blstrs::Compress;
+use pairing::{Engine, MultiMillerLoop};
+use serde::{Deserializer, Serializer};
+
+use super::AggregateProof;
+use crate::groth16::serde_bytes;
+
+/// Serializes `proof` in its compressed encoding.
+pub fn serialize<E, S>(proof: &AggregateProof<E>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    E: MultiMillerLoop,
+    <E as Engine>::Gt: Compress,
+    S: Serializer,
+{
+    let mut bytes = Vec::new();
+    proof.write(&mut bytes).map_err(serde::ser::Error::custom)?;
+    serde_bytes::serialize(&bytes, serializer)
+}
+
+/// Deserializes a proof serialized with [`serialize`].
+pub fn deserialize<'de, E, D>(deserializer: D) -> Result<AggregateProof<E>, D::Error>
+where
+    E: MultiMillerLoop,
+    <E as Engine>::Gt: Compress,
+    D: Deserializer<'de>,
+{
+    serde_bytes::deserialize(deserializer, "a compressed aggregate proof", |source| {
+        AggregateProof::read(source)
+    })
+}
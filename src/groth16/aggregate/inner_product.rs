@@ -1,16 +1,19 @@
+//! Inner products between vectors of group elements, the relations proven by
+//! TIPP and MIPP.
+
 use ff::PrimeField;
 use group::prime::PrimeCurveAffine;
 use pairing::{MillerLoopResult, MultiMillerLoop};
-use rayon::prelude::*;
 
 use crate::groth16::multiscalar::*;
+use crate::multicore::prelude::*;
 use crate::SynthesisError;
 
 /// Returns the miller loop evaluated on inputs, i.e.
 /// e(l_1,r_1)e(l_2,r_2)...
 /// NOTE: the result is not in the final subgroup, one must run
 /// `E::final_exponentiation` to use the final result.
-pub(crate) fn pairing_miller_affine<E: MultiMillerLoop>(
+pub fn pairing_miller_affine<E: MultiMillerLoop>(
     left: &[E::G1Affine],
     right: &[E::G2Affine],
 ) -> Result<<E as MultiMillerLoop>::Result, SynthesisError> {
@@ -30,17 +33,16 @@ pub(crate) fn pairing_miller_affine<E: MultiMillerLoop>(
 }
 
 /// Returns the miller loop result of the inner pairing product
-pub(crate) fn pairing<E: MultiMillerLoop>(
+pub fn pairing<E: MultiMillerLoop>(
     left: &[E::G1Affine],
     right: &[E::G2Affine],
 ) -> Result<E::Gt, SynthesisError> {
     Ok(pairing_miller_affine::<E>(left, right)?.final_exponentiation())
 }
 
-pub(crate) fn multiexponentiation<G>(
-    left: &[G],
-    right: &[G::Scalar],
-) -> Result<G::Curve, SynthesisError>
+/// Returns the multi-exponentiation of `left` with the scalars `right`, i.e.
+/// $\sum_i left_i \cdot right_i$.
+pub fn multiexponentiation<G>(left: &[G], right: &[G::Scalar]) -> Result<G::Curve, SynthesisError>
 where
     G: PrimeCurveAffine,
     <G::Scalar as PrimeField>::Repr: Sync,
@@ -3,19 +3,22 @@ use std::ops::AddAssign;
 
 use ff::Field;
 use group::{prime::PrimeCurveAffine, Curve};
-use rayon::prelude::*;
+
+use crate::multicore::prelude::*;
 
 #[macro_use]
 mod macros;
 
 mod accumulator;
 mod commit;
-mod inner_product;
+pub mod compressed;
+pub mod inner_product;
 mod msm;
 mod poly;
 mod proof;
 mod prove;
 mod srs;
+pub mod tipp_mipp;
 mod transcript;
 mod verify;
 
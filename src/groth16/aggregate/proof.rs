@@ -7,6 +7,7 @@ use pairing::{Engine, MultiMillerLoop};
 use serde::{Deserialize, Serialize};
 
 use crate::groth16::aggregate::{commit, srs};
+use crate::groth16::cbor;
 use crate::SynthesisError;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -66,6 +67,9 @@ where
 /// AggregateProof contains all elements to verify n aggregated Groth16 proofs
 /// using inner pairing product arguments. This proof can be created by any
 /// party in possession of valid Groth16 proofs.
+///
+/// Besides the derived serde implementation, [`compressed`](super::compressed)
+/// serializes it in the compact encoding of [`AggregateProof::write`].
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AggregateProof<E>
 where
@@ -210,6 +214,38 @@ where
             tmipp,
         })
     }
+
+    /// Writes the aggregated proof as deterministic CBOR. The proof is encoded as nested arrays
+    /// following the structure of [`AggregateProof`], with target group elements and curve
+    /// points as byte strings of their compressed encoding.
+    pub fn write_cbor(&self, mut out: impl Write) -> std::io::Result<()> {
+        cbor::write_array_header(&mut out, 5)?;
+        write_output_cbor::<E, _>(&mut out, &self.com_ab)?;
+        write_output_cbor::<E, _>(&mut out, &self.com_c)?;
+        write_gt_cbor::<E, _>(&mut out, &self.ip_ab)?;
+        cbor::write_point(&mut out, &self.agg_c.to_affine())?;
+        self.tmipp.write_cbor(&mut out)?;
+
+        Ok(())
+    }
+
+    /// Reads an aggregated proof written by [`AggregateProof::write_cbor`].
+    pub fn read_cbor(mut source: impl Read) -> std::io::Result<Self> {
+        cbor::read_array_header_exact(&mut source, 5)?;
+        let com_ab = read_output_cbor::<E, _>(&mut source)?;
+        let com_c = read_output_cbor::<E, _>(&mut source)?;
+        let ip_ab = read_gt_cbor::<E, _>(&mut source)?;
+        let agg_c = cbor::read_point::<_, E::G1Affine>(&mut source)?.to_curve();
+        let tmipp = TippMippProof::read_cbor(&mut source)?;
+
+        Ok(AggregateProof {
+            com_ab,
+            com_c,
+            ip_ab,
+            agg_c,
+            tmipp,
+        })
+    }
 }
 
 /// It contains all elements derived in the GIPA loop for both TIPP and MIPP at
@@ -429,6 +465,105 @@ where
     }
 }
 
+impl<E> GipaProof<E>
+where
+    E: MultiMillerLoop,
+    <E as Engine>::Gt: Compress,
+{
+    fn write_cbor(&self, mut out: impl Write) -> std::io::Result<()> {
+        cbor::write_array_header(&mut out, 10)?;
+        cbor::write_u32(&mut out, self.nproofs)?;
+
+        for comms in [&self.comms_ab, &self.comms_c] {
+            cbor::write_array_header(&mut out, comms.len())?;
+            for (x, y) in comms {
+                cbor::write_array_header(&mut out, 2)?;
+                write_output_cbor::<E, _>(&mut out, x)?;
+                write_output_cbor::<E, _>(&mut out, y)?;
+            }
+        }
+
+        cbor::write_array_header(&mut out, self.z_ab.len())?;
+        for z in &self.z_ab {
+            write_output_cbor::<E, _>(&mut out, z)?;
+        }
+
+        cbor::write_array_header(&mut out, self.z_c.len())?;
+        for (x, y) in &self.z_c {
+            cbor::write_array_header(&mut out, 2)?;
+            cbor::write_point(&mut out, &x.to_affine())?;
+            cbor::write_point(&mut out, &y.to_affine())?;
+        }
+
+        cbor::write_point(&mut out, &self.final_a)?;
+        cbor::write_point(&mut out, &self.final_b)?;
+        cbor::write_point(&mut out, &self.final_c)?;
+        write_pair_cbor(&mut out, &self.final_vkey)?;
+        write_pair_cbor(&mut out, &self.final_wkey)?;
+
+        Ok(())
+    }
+
+    fn read_cbor(mut source: impl Read) -> std::io::Result<Self> {
+        cbor::read_array_header_exact(&mut source, 10)?;
+        let nproofs = cbor::read_u32(&mut source)?;
+        if nproofs < 2 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "number of proofs is invalid",
+            ));
+        }
+        let log_proofs = log_proofs(nproofs as usize);
+
+        let read_comms = |source: &mut dyn Read| -> std::io::Result<Vec<_>> {
+            cbor::read_array_header_exact(&mut *source, log_proofs)?;
+            (0..log_proofs)
+                .map(|_| {
+                    cbor::read_array_header_exact(&mut *source, 2)?;
+                    Ok((
+                        read_output_cbor::<E, _>(&mut *source)?,
+                        read_output_cbor::<E, _>(&mut *source)?,
+                    ))
+                })
+                .collect()
+        };
+        let comms_ab = read_comms(&mut source)?;
+        let comms_c = read_comms(&mut source)?;
+
+        cbor::read_array_header_exact(&mut source, log_proofs)?;
+        let z_ab = (0..log_proofs)
+            .map(|_| read_output_cbor::<E, _>(&mut source))
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        cbor::read_array_header_exact(&mut source, log_proofs)?;
+        let z_c = (0..log_proofs)
+            .map(|_| {
+                let (x, y): (E::G1Affine, E::G1Affine) = read_pair_cbor(&mut source)?;
+                Ok((x.to_curve(), y.to_curve()))
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        let final_a = cbor::read_point(&mut source)?;
+        let final_b = cbor::read_point(&mut source)?;
+        let final_c = cbor::read_point(&mut source)?;
+        let final_vkey = read_pair_cbor(&mut source)?;
+        let final_wkey = read_pair_cbor(&mut source)?;
+
+        Ok(GipaProof {
+            nproofs,
+            comms_ab,
+            comms_c,
+            z_ab,
+            z_c,
+            final_a,
+            final_b,
+            final_c,
+            final_vkey,
+            final_wkey,
+        })
+    }
+}
+
 /// It contains the GIPA recursive elements as well as the KZG openings for v
 /// and w
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -503,6 +638,28 @@ where
             wkey_opening,
         })
     }
+
+    fn write_cbor(&self, mut out: impl Write) -> std::io::Result<()> {
+        cbor::write_array_header(&mut out, 3)?;
+        self.gipa.write_cbor(&mut out)?;
+        write_pair_cbor(&mut out, &self.vkey_opening)?;
+        write_pair_cbor(&mut out, &self.wkey_opening)?;
+
+        Ok(())
+    }
+
+    fn read_cbor(mut source: impl Read) -> std::io::Result<Self> {
+        cbor::read_array_header_exact(&mut source, 3)?;
+        let gipa = GipaProof::read_cbor(&mut source)?;
+        let vkey_opening = read_pair_cbor(&mut source)?;
+        let wkey_opening = read_pair_cbor(&mut source)?;
+
+        Ok(TippMippProof {
+            gipa,
+            vkey_opening,
+            wkey_opening,
+        })
+    }
 }
 
 impl<E> AggregateProofAndInstance<E>
@@ -650,6 +807,73 @@ fn read_affine<G: PrimeCurveAffine, R: std::io::Read>(mut source: R) -> std::io:
     Ok(affine)
 }
 
+fn write_gt_cbor<E, W>(mut out: W, gt: &<E as Engine>::Gt) -> std::io::Result<()>
+where
+    E: Engine,
+    <E as Engine>::Gt: Compress,
+    W: Write,
+{
+    let mut bytes = Vec::new();
+    gt.write_compressed(&mut bytes)?;
+    cbor::write_bytes(&mut out, &bytes)
+}
+
+fn read_gt_cbor<E, R>(mut source: R) -> std::io::Result<<E as Engine>::Gt>
+where
+    E: Engine,
+    <E as Engine>::Gt: Compress,
+    R: Read,
+{
+    let bytes = cbor::read_bytes(&mut source)?;
+    let mut bytes = &bytes[..];
+    let gt = <<E as Engine>::Gt as Compress>::read_compressed(&mut bytes)?;
+    if !bytes.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "trailing bytes in target group element",
+        ));
+    }
+    Ok(gt)
+}
+
+fn write_output_cbor<E, W>(mut out: W, output: &commit::Output<E>) -> std::io::Result<()>
+where
+    E: Engine,
+    <E as Engine>::Gt: Compress,
+    W: Write,
+{
+    cbor::write_array_header(&mut out, 2)?;
+    write_gt_cbor::<E, _>(&mut out, &output.0)?;
+    write_gt_cbor::<E, _>(&mut out, &output.1)
+}
+
+fn read_output_cbor<E, R>(mut source: R) -> std::io::Result<commit::Output<E>>
+where
+    E: Engine,
+    <E as Engine>::Gt: Compress,
+    R: Read,
+{
+    cbor::read_array_header_exact(&mut source, 2)?;
+    Ok((
+        read_gt_cbor::<E, _>(&mut source)?,
+        read_gt_cbor::<E, _>(&mut source)?,
+    ))
+}
+
+fn write_pair_cbor<G: GroupEncoding, W: Write>(mut out: W, pair: &(G, G)) -> std::io::Result<()> {
+    cbor::write_array_header(&mut out, 2)?;
+    cbor::write_point(&mut out, &pair.0)?;
+    cbor::write_point(&mut out, &pair.1)
+}
+
+fn read_pair_cbor<G: GroupEncoding, R: Read>(mut source: R) -> std::io::Result<(G, G)> {
+    cbor::read_array_header_exact(&mut source, 2)?;
+    Ok((
+        cbor::read_point(&mut source)?,
+        cbor::read_point(&mut source)?,
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -705,6 +929,60 @@ mod tests {
         let ser_proof = bincode::serialize(&proof).unwrap();
         let des_proof: AggregateProof<Bls12> = bincode::deserialize(&ser_proof).unwrap();
         assert_eq!(des_proof, proof);
+
+        let mut cbor_proof = Vec::new();
+        proof.write_cbor(&mut cbor_proof).unwrap();
+        let des_proof = AggregateProof::<Bls12>::read_cbor(&cbor_proof[..]).unwrap();
+        assert_eq!(des_proof, proof);
+
+        // The encoding is deterministic.
+        let mut cbor_proof2 = Vec::new();
+        des_proof.write_cbor(&mut cbor_proof2).unwrap();
+        assert_eq!(cbor_proof, cbor_proof2);
+    }
+
+    #[test]
+    fn test_proof_wire() {
+        use crate::groth16::wire;
+
+        let proof = fake_proof();
+        let bytes = wire::encode_aggregate_proof(&proof);
+        let des_proof = wire::decode_aggregate_proof::<Bls12>(&bytes).unwrap();
+        assert_eq!(des_proof, proof);
+        assert_eq!(wire::encode_aggregate_proof(&des_proof), bytes);
+
+        // The GIPA rounds have to match the number of proofs.
+        let mut invalid = proof;
+        invalid.tmipp.gipa.nproofs = 8;
+        let bytes = wire::encode_aggregate_proof(&invalid);
+        assert!(wire::decode_aggregate_proof::<Bls12>(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_proof_compressed_serde() {
+        #[derive(Serialize, Deserialize)]
+        struct Wrapper {
+            #[serde(with = "crate::groth16::aggregate::compressed")]
+            proof: AggregateProof<Bls12>,
+        }
+
+        let proof = fake_proof();
+        let wrapper = Wrapper {
+            proof: proof.clone(),
+        };
+
+        // The length prefix of the byte string followed by the compressed proof.
+        let bytes = bincode::serialize(&wrapper).unwrap();
+        assert_eq!(bytes.len(), 8 + proof.serialized_len());
+        let des: Wrapper = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(des.proof, proof);
+
+        // Trailing bytes are rejected.
+        let mut buffer = Vec::new();
+        proof.write(&mut buffer).unwrap();
+        buffer.push(0);
+        let bytes = bincode::serialize(&buffer).unwrap();
+        assert!(bincode::deserialize::<Wrapper>(&bytes).is_err());
     }
 
     #[test]
@@ -5,7 +5,6 @@ use blstrs::Compress;
 use ff::{Field, PrimeField};
 use group::{prime::PrimeCurveAffine, Curve};
 use log::{debug, info};
-use rayon::prelude::*;
 use serde::Serialize;
 
 use super::{
@@ -14,11 +13,13 @@ use super::{
     compress, inner_product,
     poly::DensePolynomial,
     structured_scalar_power,
+    tipp_mipp::derive_challenges,
     transcript::Transcript,
     AggregateProof, AggregateProofAndInstance, GipaProof, KZGOpening, ProverSRS,
     ProverSRSInputAggregation, TippMippProof,
 };
 use crate::groth16::{aggregate::AggregateVersion, multiscalar::*, Proof};
+use crate::multicore::{self, prelude::*};
 use crate::SynthesisError;
 use pairing::{Engine, MultiMillerLoop};
 
@@ -58,17 +59,42 @@ where
         return Err(SynthesisError::NonPowerOfTwo);
     }
 
-    if !srs.has_correct_len(proofs.len()) {
-        return Err(SynthesisError::MalformedSrs);
-    }
-    // We first commit to A B and C - these commitments are what the verifier
-    // will use later to verify the TIPP and MIPP proofs
     par! {
         let a = proofs.iter().map(|proof| proof.a).collect::<Vec<_>>(),
         let b = proofs.iter().map(|proof| proof.b).collect::<Vec<_>>(),
         let c = proofs.iter().map(|proof| proof.c).collect::<Vec<_>>()
     };
 
+    commit_and_prove_tipp_mipp(srs, transcript_include, a, b, c, version)
+}
+
+/// Commits to A, B and C and proves the TIPP relation between A and B as well
+/// as the MIPP relation with C, for a random linear combination derived from
+/// the commitments and `transcript_include`. A, B and C must be of the same
+/// power of two length.
+pub(super) fn commit_and_prove_tipp_mipp<E>(
+    srs: &ProverSRS<E>,
+    transcript_include: &[u8],
+    a: Vec<E::G1Affine>,
+    b: Vec<E::G2Affine>,
+    c: Vec<E::G1Affine>,
+    version: AggregateVersion,
+) -> Result<AggregateProof<E>, SynthesisError>
+where
+    E: MultiMillerLoop + std::fmt::Debug,
+    E::Fr: Serialize,
+    <E::Fr as PrimeField>::Repr: Send + Sync,
+    <E as Engine>::Gt: Compress + Serialize,
+    E::G1: Serialize,
+    E::G1Affine: Serialize,
+    E::G2Affine: Serialize,
+{
+    if !srs.has_correct_len(a.len()) {
+        return Err(SynthesisError::MalformedSrs);
+    }
+
+    // We first commit to A B and C - these commitments are what the verifier
+    // will use later to verify the TIPP and MIPP proofs
     // A and B are committed together in this scheme
     // we need to take the reference so the macro doesn't consume the value
     // first
@@ -80,19 +106,10 @@ where
         let com_c = commit::single_g1::<E>(&srs.vkey, refc)
     };
 
-    let hcom = Transcript::<E>::new("hcom")
-        .write(&com_ab)
-        .write(&com_c)
-        .into_challenge();
-
-    // Derive a random scalar to perform a linear combination of proofs
-    let r = Transcript::<E>::new("random-r")
-        .write(&hcom)
-        .write(&transcript_include)
-        .into_challenge();
+    let (hcom, r) = derive_challenges::<E>(&com_ab, &com_c, transcript_include);
 
     // 1,r, r^2, r^3, r^4 ...
-    let r_vec: Vec<E::Fr> = structured_scalar_power(proofs.len(), &*r);
+    let r_vec: Vec<E::Fr> = structured_scalar_power(a.len(), &*r);
     // 1,r^-1, r^-2, r^-3
     let r_inv = r_vec
         .par_iter()
@@ -228,16 +245,8 @@ where
 
     let pi_agg = aggregate_proofs(srs, &transcript_new, proofs, version).unwrap();
 
-    let hcom = Transcript::<E>::new("hcom")
-        .write(&pi_agg.com_ab)
-        .write(&pi_agg.com_c)
-        .into_challenge();
-
     // Random linear combination of proofs
-    let r = Transcript::<E>::new("random-r")
-        .write(&hcom)
-        .write(&transcript_new)
-        .into_challenge();
+    let (_, r) = derive_challenges::<E>(&pi_agg.com_ab, &pi_agg.com_c, &transcript_new);
 
     for poly_f_j in poly_f {
         let mut poly_eval = E::Fr::ZERO;
@@ -738,7 +747,7 @@ where
     // on the curve we are on). that's the extra cost of the commitment scheme
     // used which is compatible with Groth16 CRS insteaf of the original paper
     // of Bunz'19
-    Ok(rayon::join(
+    Ok(multicore::join(
         || {
             par_multiscalar::<_, G>(
                 &ScalarList::Getter(getter, srs_powers_len),
@@ -1,6 +1,7 @@
 use super::msm;
 use crate::groth16::aggregate::commit::*;
 use crate::groth16::multiscalar::{precompute_fixed_window, MultiscalarPrecompOwned, WINDOW_SIZE};
+use crate::multicore::prelude::*;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use digest::Digest;
 use ff::{Field, PrimeField, PrimeFieldBits};
@@ -11,7 +12,6 @@ use group::{
 #[cfg(not(target_arch = "wasm32"))]
 use memmap2::Mmap;
 use pairing::Engine;
-use rayon::prelude::*;
 use sha2::Sha256;
 use std::convert::TryFrom;
 use std::io::{self, Error, ErrorKind, Read, Write};
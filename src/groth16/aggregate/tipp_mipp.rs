@@ -0,0 +1,198 @@
+//! The TIPP and MIPP arguments the Groth16 aggregation is built on, usable on
+//! their own, e.g. for vector commitment openings.
+//!
+//! The prover commits to vectors $A \in G_1^n$ and $B \in G_2^n$ with
+//! [`pair`](super::pair) and to $C \in G_1^n$ with [`single_g1`](super::single_g1), where $n$
+//! is a power of two. From those commitments and an application provided
+//! `transcript_include` a challenge $r$ is derived, see [`challenge`]. The
+//! proof then shows for the committed vectors that
+//!
+//! * $Z_{AB} = \prod_{i=0}^{n-1} e(A_i, B_i^{r^i})$ (TIPP) and
+//! * $Z_C = \sum_{i=0}^{n-1} C_i^{r^i}$ (MIPP).
+//!
+//! The statement and the proof are an [`AggregateProof`]: `com_ab` and `com_c`
+//! are the commitments, `ip_ab` is $Z_{AB}$ and `agg_c` is $Z_C$.
+
+use blstrs::Compress;
+use ff::PrimeField;
+use pairing::{Engine, MultiMillerLoop};
+use serde::Serialize;
+
+use super::{
+    accumulator::PairingChecks,
+    commit::Output,
+    prove::commit_and_prove_tipp_mipp,
+    transcript::{Challenge, Transcript},
+    verify::verify_tipp_mipp,
+    AggregateProof, AggregateVersion, ProverSRS, VerifierSRS,
+};
+use crate::SynthesisError;
+
+/// Proves the TIPP relation between `a` and `b` and the MIPP relation of `c`,
+/// as described in the [module documentation](self). The SRS must be
+/// specialized to the length of the vectors.
+pub fn prove<E>(
+    srs: &ProverSRS<E>,
+    transcript_include: &[u8],
+    a: &[E::G1Affine],
+    b: &[E::G2Affine],
+    c: &[E::G1Affine],
+    version: AggregateVersion,
+) -> Result<AggregateProof<E>, SynthesisError>
+where
+    E: MultiMillerLoop + std::fmt::Debug,
+    E::Fr: Serialize,
+    <E::Fr as PrimeField>::Repr: Send + Sync,
+    <E as Engine>::Gt: Compress + Serialize,
+    E::G1: Serialize,
+    E::G1Affine: Serialize,
+    E::G2Affine: Serialize,
+{
+    if a.len() != b.len() || a.len() != c.len() {
+        return Err(SynthesisError::IncompatibleLengthVector(
+            "tipp_mipp::prove a, b and c".to_string(),
+        ));
+    }
+    if a.len() < 2 {
+        return Err(SynthesisError::MalformedProofs(
+            "proving less than 2 elements is not allowed".to_string(),
+        ));
+    }
+    if !a.len().is_power_of_two() {
+        return Err(SynthesisError::NonPowerOfTwo);
+    }
+
+    commit_and_prove_tipp_mipp(
+        srs,
+        transcript_include,
+        a.to_vec(),
+        b.to_vec(),
+        c.to_vec(),
+        version,
+    )
+}
+
+/// Verifies a proof created by [`prove`] with the same `transcript_include`.
+pub fn verify<E, R>(
+    v_srs: &VerifierSRS<E>,
+    rng: R,
+    transcript_include: &[u8],
+    proof: &AggregateProof<E>,
+    version: AggregateVersion,
+) -> Result<bool, SynthesisError>
+where
+    E: MultiMillerLoop + std::fmt::Debug,
+    E::Fr: Serialize,
+    <E as Engine>::Gt: Compress + Serialize,
+    E::G1: Serialize,
+    E::G1Affine: Serialize,
+    E::G2Affine: Serialize,
+    R: rand_core::RngCore + Send,
+{
+    proof.parsing_check()?;
+
+    let (hcom, r) = derive_challenges::<E>(&proof.com_ab, &proof.com_c, transcript_include);
+
+    let pairing_checks = PairingChecks::new(rng);
+    verify_tipp_mipp::<E, R>(v_srs, proof, &r, &pairing_checks, &hcom, version);
+    pairing_checks.verify()
+}
+
+/// Returns the challenge $r$ for the given commitments, the base of the powers
+/// the inner products are taken with.
+pub fn challenge<E>(com_ab: &Output<E>, com_c: &Output<E>, transcript_include: &[u8]) -> E::Fr
+where
+    E: Engine,
+    E::Fr: Serialize,
+    <E as Engine>::Gt: Serialize,
+{
+    *derive_challenges::<E>(com_ab, com_c, transcript_include).1
+}
+
+/// Derives the hash of the commitments and the challenge $r$ from it.
+pub(super) fn derive_challenges<E>(
+    com_ab: &Output<E>,
+    com_c: &Output<E>,
+    transcript_include: &[u8],
+) -> (Challenge<E>, Challenge<E>)
+where
+    E: Engine,
+    E::Fr: Serialize,
+    <E as Engine>::Gt: Serialize,
+{
+    let hcom = Transcript::<E>::new("hcom")
+        .write(com_ab)
+        .write(com_c)
+        .into_challenge();
+
+    let r = Transcript::<E>::new("random-r")
+        .write(&hcom)
+        .write(&transcript_include)
+        .into_challenge();
+
+    (hcom, r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groth16::aggregate::{
+        commit, inner_product, setup_fake_srs, structured_scalar_power,
+    };
+    use blstrs::{Bls12, G1Projective, G2Projective};
+    use group::{Curve, Group};
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_tipp_mipp_standalone() {
+        let n = 8;
+        let mut rng = rand_chacha::ChaChaRng::seed_from_u64(0u64);
+        let srs = setup_fake_srs::<Bls12, _>(&mut rng, n);
+        let (pk, vk) = srs.specialize(n);
+
+        let a = (0..n)
+            .map(|_| G1Projective::random(&mut rng).to_affine())
+            .collect::<Vec<_>>();
+        let b = (0..n)
+            .map(|_| G2Projective::random(&mut rng).to_affine())
+            .collect::<Vec<_>>();
+        let c = (0..n)
+            .map(|_| G1Projective::random(&mut rng).to_affine())
+            .collect::<Vec<_>>();
+
+        for version in [AggregateVersion::V1, AggregateVersion::V2] {
+            let proof = prove(&pk, b"statement", &a, &b, &c, version).unwrap();
+
+            assert_eq!(
+                proof.com_ab,
+                commit::pair::<Bls12>(&pk.vkey, &pk.wkey, &a, &b).unwrap()
+            );
+            assert_eq!(
+                proof.com_c,
+                commit::single_g1::<Bls12>(&pk.vkey, &c).unwrap()
+            );
+
+            // The inner products are taken with the powers of the challenge.
+            let r = challenge::<Bls12>(&proof.com_ab, &proof.com_c, b"statement");
+            let r_vec = structured_scalar_power(n, &r);
+            let b_r = b
+                .iter()
+                .zip(r_vec.iter())
+                .map(|(b, r)| (b * r).to_affine())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                proof.ip_ab,
+                inner_product::pairing::<Bls12>(&a, &b_r).unwrap()
+            );
+            assert_eq!(
+                proof.agg_c,
+                inner_product::multiexponentiation(&c, &r_vec).unwrap()
+            );
+
+            assert!(verify(&vk, &mut rng, b"statement", &proof, version).unwrap());
+            assert!(!verify(&vk, &mut rng, b"other statement", &proof, version).unwrap());
+        }
+
+        assert!(prove(&pk, b"statement", &a, &b[1..], &c, AggregateVersion::V2).is_err());
+    }
+}
@@ -1,10 +1,8 @@
 use blstrs::Compress;
-use crossbeam_channel::bounded;
 use ff::{Field, PrimeField};
 use group::{prime::PrimeCurveAffine, Curve, Group};
 use log::{debug, info};
 use pairing::{Engine, MultiMillerLoop};
-use rayon::prelude::*;
 use serde::Serialize;
 
 use super::{
@@ -12,6 +10,7 @@ use super::{
     inner_product,
     prove::polynomial_evaluation_product_form_from_transcript,
     structured_scalar_power,
+    tipp_mipp::derive_challenges,
     transcript::{Challenge, Transcript},
     AggregateProof, AggregateProofAndInstance, KZGOpening, VerifierSRS,
 };
@@ -20,6 +19,7 @@ use crate::groth16::{
     multiscalar::{par_multiscalar, MultiscalarPrecomp, ScalarList},
     PreparedVerifyingKey,
 };
+use crate::multicore::{self, prelude::*};
 use crate::SynthesisError;
 
 use std::default::Default;
@@ -72,16 +72,8 @@ where
         ));
     }
 
-    let hcom = Transcript::<E>::new("hcom")
-        .write(&proof.com_ab)
-        .write(&proof.com_c)
-        .into_challenge();
-
     // Random linear combination of proofs
-    let r = Transcript::<E>::new("random-r")
-        .write(&hcom)
-        .write(&transcript_include)
-        .into_challenge();
+    let (hcom, r) = derive_challenges::<E>(&proof.com_ab, &proof.com_c, transcript_include);
 
     let pairing_checks = PairingChecks::new(rng);
     let pairing_checks_copy = &pairing_checks;
@@ -117,12 +109,8 @@ where
     // randomized already. When merging all pairing checks together, this will be the only one
     // non-randomized.
     //
-    let (r_vec_sender, r_vec_receiver) = bounded(1);
-
     let now = Instant::now();
-    r_vec_sender
-        .send(structured_scalar_power(public_inputs.len(), &*r))
-        .unwrap();
+    let powers = structured_scalar_power(public_inputs.len(), &*r);
     let elapsed = now.elapsed().as_millis();
     debug!("generation of r vector: {}ms", elapsed);
 
@@ -158,8 +146,6 @@ where
             let mut g_ic = pvk.ic_projective[0];
             g_ic.mul_assign(r_sum);
 
-            let powers = r_vec_receiver.recv().unwrap();
-
             let now = Instant::now();
             // now we do the multi exponentiation
             let getter = |i: usize| -> <E::Fr as PrimeField>::Repr {
@@ -241,16 +227,8 @@ where
         .write(&transcript_include)
         .into_bytes();
 
-    let hcom = Transcript::<E>::new("hcom")
-        .write(&proof.com_ab)
-        .write(&proof.com_c)
-        .into_challenge();
-
     // Random linear combination of proofs
-    let r = Transcript::<E>::new("random-r")
-        .write(&hcom)
-        .write(&transcript_new)
-        .into_challenge();
+    let (hcom, r) = derive_challenges::<E>(&proof.com_ab, &proof.com_c, &transcript_new);
 
     let r_f = (*r).pow_vartime(&[1u64]);
 
@@ -310,7 +288,7 @@ where
         );
     }
 
-    rayon::scope(move |_s| {
+    multicore::scope(move |_s| {
         // 1.Check TIPA proof ab
         // 2.Check TIPA proof c
         //        s.spawn(move |_| {
@@ -404,7 +382,7 @@ where
 /// verify_tipp_mipp returns a pairing equation to check the tipp proof.  $r$ is
 /// the randomness used to produce a random linear combination of A and B and
 /// used in the MIPP part with C
-fn verify_tipp_mipp<E, R>(
+pub(super) fn verify_tipp_mipp<E, R>(
     v_srs: &VerifierSRS<E>,
     proof: &AggregateProof<E>,
     r_shift: &E::Fr,
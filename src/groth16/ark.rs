@@ -0,0 +1,274 @@
+//! Conversions between the Groth16 types of this crate and the ones of [`ark_groth16`] over
+//! BLS12-381.
+//!
+//! Points are converted through their uncompressed encoding, which is the same in both, so no
+//! point is checked again. The A and B queries of [`ark_groth16::ProvingKey`] have a point for
+//! every variable, including the points at infinity of the variables that aren't in the query,
+//! the ones of [`Parameters`] leave them out. Expanding the queries of [`Parameters`] therefore
+//! needs their [`QueryDensities`].
+
+use std::sync::Arc;
+
+use ark_bls12_381::Bls12_381;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use blstrs::{Bls12, G1Affine, G2Affine};
+use group::{prime::PrimeCurveAffine, UncompressedEncoding};
+
+use super::{Parameters, Proof, QueryDensities, VerifyingKey};
+use crate::SynthesisError;
+
+impl From<ark_groth16::Proof<Bls12_381>> for Proof<Bls12> {
+    fn from(proof: ark_groth16::Proof<Bls12_381>) -> Self {
+        Proof {
+            a: g1_from_ark(&proof.a),
+            b: g2_from_ark(&proof.b),
+            c: g1_from_ark(&proof.c),
+        }
+    }
+}
+
+impl From<Proof<Bls12>> for ark_groth16::Proof<Bls12_381> {
+    fn from(proof: Proof<Bls12>) -> Self {
+        ark_groth16::Proof {
+            a: g1_to_ark(&proof.a),
+            b: g2_to_ark(&proof.b),
+            c: g1_to_ark(&proof.c),
+        }
+    }
+}
+
+/// `beta_g1` and `delta_g1` are only needed for proving and are not part of the key of
+/// `ark_groth16`, they are set to the point at infinity. Convert a
+/// [`ProvingKey`](ark_groth16::ProvingKey) to get them as well.
+impl From<ark_groth16::VerifyingKey<Bls12_381>> for VerifyingKey<Bls12> {
+    fn from(vk: ark_groth16::VerifyingKey<Bls12_381>) -> Self {
+        VerifyingKey {
+            alpha_g1: g1_from_ark(&vk.alpha_g1),
+            beta_g1: G1Affine::identity(),
+            beta_g2: g2_from_ark(&vk.beta_g2),
+            gamma_g2: g2_from_ark(&vk.gamma_g2),
+            delta_g1: G1Affine::identity(),
+            delta_g2: g2_from_ark(&vk.delta_g2),
+            ic: vk.gamma_abc_g1.iter().map(g1_from_ark).collect(),
+        }
+    }
+}
+
+impl From<VerifyingKey<Bls12>> for ark_groth16::VerifyingKey<Bls12_381> {
+    fn from(vk: VerifyingKey<Bls12>) -> Self {
+        ark_groth16::VerifyingKey {
+            alpha_g1: g1_to_ark(&vk.alpha_g1),
+            beta_g2: g2_to_ark(&vk.beta_g2),
+            gamma_g2: g2_to_ark(&vk.gamma_g2),
+            delta_g2: g2_to_ark(&vk.delta_g2),
+            gamma_abc_g1: vk.ic.iter().map(g1_to_ark).collect(),
+        }
+    }
+}
+
+/// The points at infinity of the A and B queries are left out, the query densities are derived
+/// from where they are.
+impl From<ark_groth16::ProvingKey<Bls12_381>> for Parameters<Bls12> {
+    fn from(pk: ark_groth16::ProvingKey<Bls12_381>) -> Self {
+        let num_inputs = pk.vk.gamma_abc_g1.len();
+        let a_query: Vec<_> = pk.a_query.iter().map(g1_from_ark).collect();
+        let b_g1_query: Vec<_> = pk.b_g1_query.iter().map(g1_from_ark).collect();
+        let b_g2_query: Vec<_> = pk.b_g2_query.iter().map(g2_from_ark).collect();
+        let is_dense = |point: &G1Affine| !bool::from(point.is_identity());
+
+        let densities = QueryDensities::new(
+            num_inputs,
+            pk.l_query.len(),
+            a_query.iter().skip(num_inputs).map(is_dense),
+            b_g1_query.iter().take(num_inputs).map(is_dense),
+            b_g1_query.iter().skip(num_inputs).map(is_dense),
+        );
+        let mut vk = VerifyingKey::from(pk.vk);
+        vk.beta_g1 = g1_from_ark(&pk.beta_g1);
+        vk.delta_g1 = g1_from_ark(&pk.delta_g1);
+
+        Parameters {
+            vk,
+            h: Arc::new(pk.h_query.iter().map(g1_from_ark).collect()),
+            l: Arc::new(pk.l_query.iter().map(g1_from_ark).collect()),
+            a: Arc::new(a_query.into_iter().filter(is_dense).collect()),
+            b_g1: Arc::new(b_g1_query.into_iter().filter(is_dense).collect()),
+            b_g2: Arc::new(
+                b_g2_query
+                    .into_iter()
+                    .filter(|point| !bool::from(point.is_identity()))
+                    .collect(),
+            ),
+            densities: Some(Arc::new(densities)),
+            fingerprint: None,
+        }
+    }
+}
+
+/// Fails with [`SynthesisError::MissingDensities`] if the parameters don't come with their
+/// query densities, and if the densities don't match the queries.
+impl TryFrom<&Parameters<Bls12>> for ark_groth16::ProvingKey<Bls12_381> {
+    type Error = SynthesisError;
+
+    fn try_from(params: &Parameters<Bls12>) -> Result<Self, Self::Error> {
+        let densities = params
+            .densities
+            .as_ref()
+            .ok_or(SynthesisError::MissingDensities)?;
+        densities.check(params.vk.ic.len(), params.l.len())?;
+
+        Ok(ark_groth16::ProvingKey {
+            vk: params.vk.clone().into(),
+            beta_g1: g1_to_ark(&params.vk.beta_g1),
+            delta_g1: g1_to_ark(&params.vk.delta_g1),
+            a_query: expand_query(&params.a, densities.a_query(), g1_to_ark, "A")?,
+            b_g1_query: expand_query(&params.b_g1, densities.b_query(), g1_to_ark, "B G1")?,
+            b_g2_query: expand_query(&params.b_g2, densities.b_query(), g2_to_ark, "B G2")?,
+            h_query: params.h.iter().map(g1_to_ark).collect(),
+            l_query: params.l.iter().map(g1_to_ark).collect(),
+        })
+    }
+}
+
+/// Puts the points of a query at the variables that are in it and the point at infinity at all
+/// others.
+fn expand_query<P, Q, D, F>(
+    points: &[P],
+    density: D,
+    convert: F,
+    query: &str,
+) -> Result<Vec<Q>, SynthesisError>
+where
+    Q: Default,
+    D: Iterator<Item = bool>,
+    F: Fn(&P) -> Q,
+{
+    let mut points = points.iter();
+    let expanded = density
+        .map(|dense| {
+            if dense {
+                points.next().map(&convert)
+            } else {
+                // The default of an affine point of arkworks is the point at infinity.
+                Some(Q::default())
+            }
+        })
+        .collect::<Option<Vec<_>>>();
+
+    match expanded {
+        Some(expanded) if points.next().is_none() => Ok(expanded),
+        _ => Err(SynthesisError::IncompatibleLengthVector(format!(
+            "the {} query doesn't match its density",
+            query
+        ))),
+    }
+}
+
+fn g1_from_ark(point: &ark_bls12_381::G1Affine) -> G1Affine {
+    let mut bytes = [0; 96];
+    point
+        .serialize_uncompressed(&mut bytes[..])
+        .expect("buffer has the size of an uncompressed point");
+    Option::from(G1Affine::from_uncompressed_unchecked(&bytes)).expect("arkworks points are valid")
+}
+
+fn g2_from_ark(point: &ark_bls12_381::G2Affine) -> G2Affine {
+    let mut bytes = [0; 192];
+    point
+        .serialize_uncompressed(&mut bytes[..])
+        .expect("buffer has the size of an uncompressed point");
+    Option::from(G2Affine::from_uncompressed_unchecked(&bytes)).expect("arkworks points are valid")
+}
+
+fn g1_to_ark(point: &G1Affine) -> ark_bls12_381::G1Affine {
+    ark_bls12_381::G1Affine::deserialize_uncompressed_unchecked(&point.to_uncompressed()[..])
+        .expect("blstrs points are valid")
+}
+
+fn g2_to_ark(point: &G2Affine) -> ark_bls12_381::G2Affine {
+    ark_bls12_381::G2Affine::deserialize_uncompressed_unchecked(&point.to_uncompressed()[..])
+        .expect("blstrs points are valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ark_groth16::Groth16;
+    use blstrs::Scalar as Fr;
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use crate::groth16::{create_random_proof, generate_random_parameters};
+    use crate::{Circuit, ConstraintSystem};
+
+    /// `x * y = z` with `z` public and a variable that is only in C.
+    struct Product {
+        x: Option<u64>,
+        y: Option<u64>,
+    }
+
+    impl Circuit<Fr> for Product {
+        fn synthesize<CS: ConstraintSystem<Fr>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            let value = |v: Option<u64>| v.map(Fr::from).ok_or(SynthesisError::AssignmentMissing);
+            let x = cs.alloc(|| "x", || value(self.x))?;
+            let y = cs.alloc(|| "y", || value(self.y))?;
+            let z = cs.alloc_input(|| "z", || value(self.x.zip(self.y).map(|(x, y)| x * y)))?;
+            let w = cs.alloc(|| "w", || value(self.x))?;
+            cs.enforce(|| "x * y = z", |lc| lc + x, |lc| lc + y, |lc| lc + z);
+            cs.enforce(|| "w = x", |lc| lc + x, |lc| lc + CS::one(), |lc| lc + w);
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_ark_conversions() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let params =
+            generate_random_parameters::<Bls12, _, _>(Product { x: None, y: None }, &mut rng)
+                .unwrap();
+        let proof = create_random_proof(
+            Product {
+                x: Some(3),
+                y: Some(4),
+            },
+            &params,
+            &mut rng,
+        )
+        .unwrap();
+
+        // A proof of this crate verifies with arkworks.
+        let ark_vk = ark_groth16::VerifyingKey::from(params.vk.clone());
+        let ark_proof = ark_groth16::Proof::from(proof.clone());
+        let pvk = ark_groth16::prepare_verifying_key(&ark_vk);
+        let inputs = [ark_bls12_381::Fr::from(12u64)];
+        assert!(Groth16::<Bls12_381>::verify_proof(&pvk, &ark_proof, &inputs).unwrap());
+
+        assert_eq!(Proof::from(ark_proof), proof);
+        let mut vk = VerifyingKey::from(ark_vk);
+        vk.beta_g1 = params.vk.beta_g1;
+        vk.delta_g1 = params.vk.delta_g1;
+        assert_eq!(vk, params.vk);
+
+        let pk = ark_groth16::ProvingKey::try_from(&params).unwrap();
+        // Two inputs and three aux variables, of which only `x` is in the A and only `y` in the B
+        // query.
+        assert_eq!(pk.a_query.len(), 5);
+        assert_eq!(pk.b_g1_query.len(), 5);
+        assert_eq!(pk.b_g2_query.len(), 5);
+        let converted = Parameters::from(pk);
+        assert!(converted == params);
+        assert_eq!(converted.densities, params.densities);
+
+        let mut params = params;
+        params.densities = None;
+        assert!(matches!(
+            ark_groth16::ProvingKey::try_from(&params),
+            Err(SynthesisError::MissingDensities)
+        ));
+    }
+}
@@ -0,0 +1,225 @@
+//! Deterministic CBOR ([RFC 8949]) encoding of proof artifacts.
+//!
+//! Only the subset of CBOR needed to describe proofs is supported: unsigned integers, byte strings
+//! and definite-length arrays. Encoding always uses the shortest possible header, which is what
+//! the "core deterministic encoding" of RFC 8949 (and DAG-CBOR) requires, so the same value always
+//! encodes to the same bytes. Decoding is strict and rejects anything a deterministic encoder
+//! would not have produced.
+//!
+//! Curve points are encoded as byte strings holding their compressed encoding, field elements as
+//! byte strings holding their canonical little-endian representation.
+//!
+//! [RFC 8949]: https://www.rfc-editor.org/rfc/rfc8949.html
+
+use std::io::{self, Read, Write};
+
+use ff::PrimeField;
+use group::GroupEncoding;
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_ARRAY: u8 = 4;
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid CBOR: {}", msg))
+}
+
+fn write_header<W: Write>(mut writer: W, major: u8, value: u64) -> io::Result<()> {
+    let major = major << 5;
+    if value < 24 {
+        writer.write_all(&[major | value as u8])
+    } else if value <= u64::from(u8::MAX) {
+        writer.write_all(&[major | 24, value as u8])
+    } else if value <= u64::from(u16::MAX) {
+        writer.write_all(&[major | 25])?;
+        writer.write_all(&(value as u16).to_be_bytes())
+    } else if value <= u64::from(u32::MAX) {
+        writer.write_all(&[major | 26])?;
+        writer.write_all(&(value as u32).to_be_bytes())
+    } else {
+        writer.write_all(&[major | 27])?;
+        writer.write_all(&value.to_be_bytes())
+    }
+}
+
+fn read_header<R: Read>(mut reader: R, expected_major: u8) -> io::Result<u64> {
+    let mut initial = [0u8; 1];
+    reader.read_exact(&mut initial)?;
+    if initial[0] >> 5 != expected_major {
+        return Err(invalid_data("unexpected major type"));
+    }
+
+    let (value, min) = match initial[0] & 0x1f {
+        info @ 0..=23 => return Ok(u64::from(info)),
+        24 => {
+            let mut buf = [0u8; 1];
+            reader.read_exact(&mut buf)?;
+            (u64::from(buf[0]), 24)
+        }
+        25 => {
+            let mut buf = [0u8; 2];
+            reader.read_exact(&mut buf)?;
+            (u64::from(u16::from_be_bytes(buf)), u64::from(u8::MAX) + 1)
+        }
+        26 => {
+            let mut buf = [0u8; 4];
+            reader.read_exact(&mut buf)?;
+            (u64::from(u32::from_be_bytes(buf)), u64::from(u16::MAX) + 1)
+        }
+        27 => {
+            let mut buf = [0u8; 8];
+            reader.read_exact(&mut buf)?;
+            (u64::from_be_bytes(buf), u64::from(u32::MAX) + 1)
+        }
+        _ => return Err(invalid_data("indefinite or reserved length")),
+    };
+
+    if value < min {
+        return Err(invalid_data("non-canonical length"));
+    }
+    Ok(value)
+}
+
+pub(crate) fn write_u32<W: Write>(writer: W, value: u32) -> io::Result<()> {
+    write_header(writer, MAJOR_UNSIGNED, u64::from(value))
+}
+
+pub(crate) fn read_u32<R: Read>(reader: R) -> io::Result<u32> {
+    let value = read_header(reader, MAJOR_UNSIGNED)?;
+    u32::try_from(value).map_err(|_| invalid_data("integer out of range"))
+}
+
+pub(crate) fn write_array_header<W: Write>(writer: W, len: usize) -> io::Result<()> {
+    write_header(writer, MAJOR_ARRAY, len as u64)
+}
+
+/// Reads an array header and returns the number of elements.
+pub(crate) fn read_array_header<R: Read>(reader: R) -> io::Result<usize> {
+    let len = read_header(reader, MAJOR_ARRAY)?;
+    usize::try_from(len).map_err(|_| invalid_data("array too long"))
+}
+
+/// Reads an array header and checks it has exactly `expected` elements.
+pub(crate) fn read_array_header_exact<R: Read>(reader: R, expected: usize) -> io::Result<()> {
+    if read_array_header(reader)? != expected {
+        return Err(invalid_data("unexpected array length"));
+    }
+    Ok(())
+}
+
+pub(crate) fn write_bytes<W: Write>(mut writer: W, bytes: &[u8]) -> io::Result<()> {
+    write_header(&mut writer, MAJOR_BYTES, bytes.len() as u64)?;
+    writer.write_all(bytes)
+}
+
+/// Reads a byte string of exactly `out.len()` bytes into `out`.
+pub(crate) fn read_bytes_exact<R: Read>(mut reader: R, out: &mut [u8]) -> io::Result<()> {
+    if read_header(&mut reader, MAJOR_BYTES)? != out.len() as u64 {
+        return Err(invalid_data("unexpected byte string length"));
+    }
+    reader.read_exact(out)
+}
+
+/// Reads a byte string of arbitrary length.
+pub(crate) fn read_bytes<R: Read>(mut reader: R) -> io::Result<Vec<u8>> {
+    let len = read_header(&mut reader, MAJOR_BYTES)?;
+    let mut out = Vec::new();
+    reader.take(len).read_to_end(&mut out)?;
+    if out.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(out)
+}
+
+pub(crate) fn write_point<W: Write, G: GroupEncoding>(writer: W, point: &G) -> io::Result<()> {
+    write_bytes(writer, point.to_bytes().as_ref())
+}
+
+pub(crate) fn read_point<R: Read, G: GroupEncoding>(reader: R) -> io::Result<G> {
+    let mut repr = G::Repr::default();
+    read_bytes_exact(reader, repr.as_mut())?;
+    Option::from(G::from_bytes(&repr)).ok_or_else(|| invalid_data("invalid point"))
+}
+
+pub(crate) fn write_scalar<W: Write, F: PrimeField>(writer: W, scalar: &F) -> io::Result<()> {
+    write_bytes(writer, scalar.to_repr().as_ref())
+}
+
+pub(crate) fn read_scalar<R: Read, F: PrimeField>(reader: R) -> io::Result<F> {
+    let mut repr = F::Repr::default();
+    read_bytes_exact(reader, repr.as_mut())?;
+    Option::from(F::from_repr(repr)).ok_or_else(|| invalid_data("non-canonical field element"))
+}
+
+/// Writes public inputs as a CBOR array of field elements.
+pub fn write_public_inputs_cbor<F: PrimeField, W: Write>(
+    inputs: &[F],
+    mut writer: W,
+) -> io::Result<()> {
+    write_array_header(&mut writer, inputs.len())?;
+    for input in inputs {
+        write_scalar(&mut writer, input)?;
+    }
+    Ok(())
+}
+
+/// Reads public inputs written by [`write_public_inputs_cbor`].
+pub fn read_public_inputs_cbor<F: PrimeField, R: Read>(mut reader: R) -> io::Result<Vec<F>> {
+    let len = read_array_header(&mut reader)?;
+    (0..len).map(|_| read_scalar(&mut reader)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+    use ff::Field;
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_header_shortest_form() {
+        for (value, expected) in [
+            (0u64, vec![0x00]),
+            (23, vec![0x17]),
+            (24, vec![0x18, 0x18]),
+            (255, vec![0x18, 0xff]),
+            (256, vec![0x19, 0x01, 0x00]),
+            (65536, vec![0x1a, 0x00, 0x01, 0x00, 0x00]),
+            (1 << 32, vec![0x1b, 0, 0, 0, 1, 0, 0, 0, 0]),
+        ] {
+            let mut buf = Vec::new();
+            write_header(&mut buf, MAJOR_UNSIGNED, value).unwrap();
+            assert_eq!(buf, expected);
+            assert_eq!(read_header(&buf[..], MAJOR_UNSIGNED).unwrap(), value);
+        }
+
+        // Non-shortest and indefinite lengths are rejected.
+        assert!(read_header(&[0x18, 0x17][..], MAJOR_UNSIGNED).is_err());
+        assert!(read_header(&[0x19, 0x00, 0xff][..], MAJOR_UNSIGNED).is_err());
+        assert!(read_header(&[0x5f][..], MAJOR_BYTES).is_err());
+        // Wrong major type is rejected.
+        assert!(read_header(&[0x40][..], MAJOR_ARRAY).is_err());
+    }
+
+    #[test]
+    fn test_public_inputs_roundtrip() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let inputs = (0..30).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+
+        let mut buf = Vec::new();
+        write_public_inputs_cbor(&inputs, &mut buf).unwrap();
+        // array(30) header, then 30 times bytes(32) header and payload.
+        assert_eq!(buf.len(), 2 + 30 * (2 + 32));
+        assert_eq!(buf[0], 0x98);
+
+        let read: Vec<Fr> = read_public_inputs_cbor(&buf[..]).unwrap();
+        assert_eq!(read, inputs);
+
+        assert!(read_public_inputs_cbor::<Fr, _>(&buf[..buf.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,439 @@
+//! Commit-and-prove Groth16, in the style of [LegoGroth16].
+//!
+//! The last [`CommitmentKey::num_committed`] inputs of a circuit are not disclosed to the
+//! verifier. Instead, the proof carries a Pedersen commitment to them, which takes their place
+//! in the verification equation. The commitment is
+//!
+//! `D = sum(a_i * ic_i) + v * g1^{eta / gamma}`
+//!
+//! where `a_i` are the committed inputs, `ic_i` their points of the verifying key and `v` a
+//! random opening. The prover compensates for the blinding term in `C`. Whoever knows the
+//! committed values and the opening can recompute the commitment with
+//! [`CommitmentKey::commit`], which links the proof to commitments outside of the circuit.
+//!
+//! As the prover chooses `D`, the verification equation alone doesn't stop it from moving any
+//! point into `D`, e.g. the ones of the disclosed inputs. The proof therefore also carries a
+//! link proof, which proves knowledge of an opening of `D` over the bases `ic_i` of the
+//! committed inputs and `g1^{eta / gamma}` only. It is the QA-NIZK of LegoGroth16 for a single
+//! commitment: the setup draws secret `k` and `a`, the prover gets `k` times the bases and the
+//! verifier checks `e(D, g2^{k * a}) = e(pi, g2^a)`.
+//!
+//! [LegoGroth16]: https://eprint.iacr.org/2019/142
+
+use std::iter;
+use std::ops::{AddAssign, Mul, SubAssign};
+
+use ff::{Field, PrimeField};
+use group::{prime::PrimeCurveAffine, Curve, Group, WnafGroup};
+use pairing::{Engine, MillerLoopResult, MultiMillerLoop};
+use rand_core::RngCore;
+
+use super::verifier::{accumulate_inputs, verify_proof_with_inputs_acc};
+use super::{
+    create_proof_from_assignments, generate_parameters, ParameterSource, Parameters,
+    PreparedVerifyingKey, Proof, ProvingAssignment, VerifyingKey,
+};
+use crate::gpu::GpuName;
+use crate::{Circuit, SynthesisError};
+
+/// The key material of commit-and-prove proofs that is generated along with the parameters of a
+/// circuit, see [`generate_random_parameters_committed`].
+#[derive(Clone, Debug)]
+pub struct CommitmentKey<E: Engine> {
+    /// The number of inputs that are committed to instead of disclosed, they are the last
+    /// inputs of the circuit.
+    pub num_committed: usize,
+    /// `g1^{eta / gamma}`, the base of the opening of commitments.
+    pub eta_gamma_g1: E::G1Affine,
+    /// `g1^{eta / delta}`, the prover removes the opening from `C` with it.
+    pub eta_delta_g1: E::G1Affine,
+    /// `k` times the bases of the commitment, i.e. of the committed inputs followed by
+    /// `eta_gamma_g1`, the link proof is computed with them.
+    pub link_bases: Vec<E::G1Affine>,
+    /// `g2^a`, for verifying link proofs.
+    pub link_a_g2: E::G2Affine,
+    /// `g2^{k * a}`, for verifying link proofs.
+    pub link_ka_g2: E::G2Affine,
+}
+
+impl<E: Engine> CommitmentKey<E> {
+    /// Returns the points of the verifying key the committed inputs are committed with.
+    pub fn bases<'a>(&self, vk: &'a VerifyingKey<E>) -> Result<&'a [E::G1Affine], SynthesisError> {
+        if self.num_committed >= vk.ic.len() {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+        Ok(&vk.ic[vk.ic.len() - self.num_committed..])
+    }
+
+    /// Commits to the values of the committed inputs with the opening `v`, the same way the
+    /// prover does.
+    pub fn commit(
+        &self,
+        vk: &VerifyingKey<E>,
+        values: &[E::Fr],
+        v: E::Fr,
+    ) -> Result<E::G1Affine, SynthesisError> {
+        let bases = self.bases(vk)?;
+        opening::<E>(bases, &self.eta_gamma_g1, values, v)
+    }
+
+    /// Computes the link proof of the commitment to `values` with the opening `v`.
+    pub fn link(&self, values: &[E::Fr], v: E::Fr) -> Result<E::G1Affine, SynthesisError> {
+        let (opening_base, bases) = self
+            .link_bases
+            .split_last()
+            .ok_or(SynthesisError::MalformedVerifyingKey)?;
+        opening::<E>(bases, opening_base, values, v)
+    }
+}
+
+/// Computes `sum(values_i * bases_i) + v * opening_base`.
+fn opening<E: Engine>(
+    bases: &[E::G1Affine],
+    opening_base: &E::G1Affine,
+    values: &[E::Fr],
+    v: E::Fr,
+) -> Result<E::G1Affine, SynthesisError> {
+    if values.len() != bases.len() {
+        return Err(SynthesisError::IncompatibleLengthVector(format!(
+            "{} committed values, {} committed inputs",
+            values.len(),
+            bases.len()
+        )));
+    }
+
+    let mut commitment = opening_base.mul(v);
+    for (base, value) in bases.iter().zip(values.iter()) {
+        commitment.add_assign(&base.mul(*value));
+    }
+    Ok(commitment.to_affine())
+}
+
+/// A Groth16 proof together with the commitment to the committed inputs.
+#[derive(Clone, Debug)]
+pub struct CommittedProof<E: Engine> {
+    pub proof: Proof<E>,
+    pub commitment: E::G1Affine,
+    /// The proof of knowledge of an opening of the commitment, see [`CommitmentKey::link`].
+    pub link_proof: E::G1Affine,
+}
+
+impl<E: Engine> PartialEq for CommittedProof<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.proof == other.proof
+            && self.commitment == other.commitment
+            && self.link_proof == other.link_proof
+    }
+}
+
+/// Generates random parameters for a circuit whose last `num_committed` inputs are committed
+/// to, together with the matching commitment key.
+///
+/// The parameters are regular Groth16 parameters, the committed inputs are ordinary inputs of
+/// the circuit. Only the commitment key, which depends on the same toxic waste, is extra.
+pub fn generate_random_parameters_committed<E, C, R>(
+    circuit: C,
+    num_committed: usize,
+    rng: &mut R,
+) -> Result<(Parameters<E>, CommitmentKey<E>), SynthesisError>
+where
+    E: MultiMillerLoop,
+    <E as Engine>::G1: WnafGroup,
+    <E as Engine>::G2: WnafGroup,
+    C: Circuit<E::Fr>,
+    R: RngCore,
+    E::Fr: GpuName,
+{
+    let g1 = E::G1::random(&mut *rng);
+    let g2 = E::G2::random(&mut *rng);
+    let alpha = E::Fr::random(&mut *rng);
+    let beta = E::Fr::random(&mut *rng);
+    let gamma = E::Fr::random(&mut *rng);
+    let delta = E::Fr::random(&mut *rng);
+    let tau = E::Fr::random(&mut *rng);
+    let eta = E::Fr::random(&mut *rng);
+    let k = E::Fr::random(&mut *rng);
+    let a = E::Fr::random(&mut *rng);
+
+    let params = generate_parameters::<E, C>(circuit, g1, g2, alpha, beta, gamma, delta, tau)?;
+    // The "one" input can't be committed to.
+    if num_committed >= params.vk.ic.len() {
+        return Err(SynthesisError::IncompatibleLengthVector(format!(
+            "{} committed inputs, circuit with {} inputs",
+            num_committed,
+            params.vk.ic.len() - 1
+        )));
+    }
+
+    let gamma_inverse: E::Fr =
+        Option::from(gamma.invert()).ok_or(SynthesisError::UnexpectedIdentity)?;
+    let delta_inverse: E::Fr =
+        Option::from(delta.invert()).ok_or(SynthesisError::UnexpectedIdentity)?;
+
+    let eta_gamma_g1 = g1.mul(eta * gamma_inverse).to_affine();
+    let committed_ic = &params.vk.ic[params.vk.ic.len() - num_committed..];
+    let link_bases = committed_ic
+        .iter()
+        .chain(iter::once(&eta_gamma_g1))
+        .map(|base| base.mul(k).to_affine())
+        .collect();
+
+    let ck = CommitmentKey {
+        num_committed,
+        eta_gamma_g1,
+        eta_delta_g1: g1.mul(eta * delta_inverse).to_affine(),
+        link_bases,
+        link_a_g2: g2.mul(a).to_affine(),
+        link_ka_g2: g2.mul(k * a).to_affine(),
+    };
+
+    Ok((params, ck))
+}
+
+/// Creates a commit-and-prove proof with random `r`, `s` and opening. Returns the proof and
+/// the opening of its commitment.
+pub fn create_random_proof_committed<E, C, R, P>(
+    circuit: C,
+    params: P,
+    ck: &CommitmentKey<E>,
+    rng: &mut R,
+) -> Result<(CommittedProof<E>, E::Fr), SynthesisError>
+where
+    E: MultiMillerLoop,
+    C: Circuit<E::Fr>,
+    P: ParameterSource<E>,
+    R: RngCore,
+    E::Fr: GpuName,
+    E::G1Affine: GpuName,
+    E::G2Affine: GpuName,
+{
+    let r = E::Fr::random(&mut *rng);
+    let s = E::Fr::random(&mut *rng);
+    let v = E::Fr::random(&mut *rng);
+
+    let proof = create_proof_committed(circuit, params, ck, r, s, v)?;
+    Ok((proof, v))
+}
+
+/// Creates a commit-and-prove proof, committing to the last inputs of the circuit with the
+/// opening `v`.
+pub fn create_proof_committed<E, C, P>(
+    circuit: C,
+    params: P,
+    ck: &CommitmentKey<E>,
+    r: E::Fr,
+    s: E::Fr,
+    v: E::Fr,
+) -> Result<CommittedProof<E>, SynthesisError>
+where
+    E: MultiMillerLoop,
+    C: Circuit<E::Fr>,
+    P: ParameterSource<E>,
+    E::Fr: GpuName,
+    E::G1Affine: GpuName,
+    E::G2Affine: GpuName,
+{
+    let assignment = ProvingAssignment::synthesize(circuit)?;
+    let inputs = assignment.input_assignment();
+    if ck.num_committed >= inputs.len() {
+        return Err(SynthesisError::IncompatibleLengthVector(format!(
+            "{} committed inputs, circuit with {} inputs",
+            ck.num_committed,
+            inputs.len() - 1
+        )));
+    }
+    let committed = &inputs[inputs.len() - ck.num_committed..];
+    let commitment = ck.commit(params.get_vk(inputs.len())?, committed, v)?;
+    let link_proof = ck.link(committed, v)?;
+
+    let mut proof = create_proof_from_assignments(assignment, params, r, s)?;
+
+    // C - v * g1^{eta / delta}
+    let mut c = proof.c.to_curve();
+    c.sub_assign(&ck.eta_delta_g1.mul(v));
+    proof.c = c.to_affine();
+
+    Ok(CommittedProof {
+        proof,
+        commitment,
+        link_proof,
+    })
+}
+
+/// Verifies a commit-and-prove proof against the inputs that are disclosed, i.e. all but the
+/// last [`CommitmentKey::num_committed`] ones.
+pub fn verify_committed_proof<E>(
+    pvk: &PreparedVerifyingKey<E>,
+    ck: &CommitmentKey<E>,
+    proof: &CommittedProof<E>,
+    public_inputs: &[E::Fr],
+) -> Result<bool, SynthesisError>
+where
+    E: MultiMillerLoop,
+    <<E as Engine>::Fr as PrimeField>::Repr: Sync,
+{
+    if (public_inputs.len() + ck.num_committed + 1) != pvk.ic.len() {
+        return Err(SynthesisError::MalformedVerifyingKey);
+    }
+
+    // e(D, g2^{k * a}) = e(pi, g2^a), i.e. D is opened over the bases of the commitment.
+    let link = E::multi_miller_loop(&[
+        (&proof.commitment, &ck.link_ka_g2.into()),
+        (&(-proof.link_proof), &ck.link_a_g2.into()),
+    ])
+    .final_exponentiation();
+    if !bool::from(link.is_identity()) {
+        return Ok(false);
+    }
+
+    Ok(verify_proof_with_inputs_acc(pvk, &proof.proof, || {
+        let mut acc = accumulate_inputs(pvk, 0, public_inputs);
+        acc.add_assign(&pvk.ic[0]);
+        acc.add_assign(&proof.commitment);
+        acc
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::{Bls12, Scalar as Fr};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use crate::groth16::prepare_verifying_key;
+    use crate::ConstraintSystem;
+
+    /// Proves knowledge of `y` with `x * y = z`, where `x` and `z` are public and `y` is
+    /// committed to.
+    #[derive(Clone)]
+    struct Product {
+        x: Option<Fr>,
+        y: Option<Fr>,
+    }
+
+    impl Circuit<Fr> for Product {
+        fn synthesize<CS: ConstraintSystem<Fr>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            let z_value = self.x.zip(self.y).map(|(x, y)| x * y);
+            let x = cs.alloc_input(|| "x", || self.x.ok_or(SynthesisError::AssignmentMissing))?;
+            let z = cs.alloc_input(|| "z", || z_value.ok_or(SynthesisError::AssignmentMissing))?;
+            let y = cs.alloc_input(|| "y", || self.y.ok_or(SynthesisError::AssignmentMissing))?;
+            let w = cs.alloc(|| "w", || z_value.ok_or(SynthesisError::AssignmentMissing))?;
+
+            cs.enforce(|| "x * y = w", |lc| lc + x, |lc| lc + y, |lc| lc + w);
+            cs.enforce(|| "w = z", |lc| lc + w, |lc| lc + CS::one(), |lc| lc + z);
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_committed_proof() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let (params, ck) = generate_random_parameters_committed::<Bls12, _, _>(
+            Product { x: None, y: None },
+            1,
+            &mut rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let (x, y) = (Fr::from(3), Fr::from(7));
+        let circuit = Product {
+            x: Some(x),
+            y: Some(y),
+        };
+        let (proof, v) =
+            create_random_proof_committed(circuit.clone(), &params, &ck, &mut rng).unwrap();
+
+        assert!(verify_committed_proof(&pvk, &ck, &proof, &[x, x * y]).unwrap());
+        assert!(!verify_committed_proof(&pvk, &ck, &proof, &[x, x * x]).unwrap());
+        assert!(verify_committed_proof(&pvk, &ck, &proof, &[x]).is_err());
+
+        // The commitment opens to the committed input.
+        assert_eq!(ck.commit(&params.vk, &[y], v).unwrap(), proof.commitment);
+        assert_ne!(ck.commit(&params.vk, &[x], v).unwrap(), proof.commitment);
+
+        // Replacing the commitment by one to another value breaks the proof.
+        let mut forged = proof.clone();
+        forged.commitment = ck.commit(&params.vk, &[x], v).unwrap();
+        assert!(!verify_committed_proof(&pvk, &ck, &forged, &[x, x * y]).unwrap());
+        forged.link_proof = ck.link(&[x], v).unwrap();
+        assert!(!verify_committed_proof(&pvk, &ck, &forged, &[x, x * y]).unwrap());
+
+        // So does a link proof for another commitment.
+        let mut forged = proof.clone();
+        forged.link_proof = ck.link(&[x], v).unwrap();
+        assert!(!verify_committed_proof(&pvk, &ck, &forged, &[x, x * y]).unwrap());
+
+        // Commitments to the same value are hiding.
+        let (other, _) = create_random_proof_committed(circuit, &params, &ck, &mut rng).unwrap();
+        assert!(verify_committed_proof(&pvk, &ck, &other, &[x, x * y]).unwrap());
+        assert_ne!(other.commitment, proof.commitment);
+
+        assert!(generate_random_parameters_committed::<Bls12, _, _>(
+            Product { x: None, y: None },
+            4,
+            &mut rng,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_committed_proof_forgery() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let (params, ck) = generate_random_parameters_committed::<Bls12, _, _>(
+            Product { x: None, y: None },
+            1,
+            &mut rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+        let vk = &params.vk;
+
+        // Any disclosed inputs, for which there is no witness.
+        let inputs = [Fr::from(5), Fr::from(11)];
+
+        // D = -(ic_0 + sum(x_i * ic_i)) - g1^{eta / gamma} cancels the inputs and the opening
+        // term, C = g1^{eta / delta} cancels the latter, which leaves e(A, B) = e(alpha, beta).
+        let mut d = ck.eta_gamma_g1.to_curve();
+        d.add_assign(&vk.ic[0]);
+        for (input, ic) in inputs.iter().zip(&vk.ic[1..]) {
+            d.add_assign(&ic.mul(*input));
+        }
+        let forged = CommittedProof {
+            proof: Proof {
+                a: vk.alpha_g1,
+                b: vk.beta_g2,
+                c: ck.eta_delta_g1,
+            },
+            commitment: (-d).to_affine(),
+            // Only the part of the link proof for the opening term can be computed.
+            link_proof: -ck.link_bases[ck.num_committed],
+        };
+
+        // The forgery is well-formed and passes the verification equation on its own ...
+        let mut bytes = vec![];
+        forged.proof.write(&mut bytes).unwrap();
+        assert_eq!(Proof::<Bls12>::read(&bytes[..]).unwrap(), forged.proof);
+        assert!(!bool::from(forged.commitment.is_identity()));
+        assert!(verify_proof_with_inputs_acc(&pvk, &forged.proof, || {
+            let mut acc = accumulate_inputs(&pvk, 0, &inputs);
+            acc.add_assign(&pvk.ic[0]);
+            acc.add_assign(&forged.commitment);
+            acc
+        }));
+
+        // ... but D isn't opened over the bases of the commitment.
+        assert!(!verify_committed_proof(&pvk, &ck, &forged, &inputs).unwrap());
+    }
+}
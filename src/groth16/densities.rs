@@ -0,0 +1,329 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use ec_gpu_gen::multiexp_cpu::DensityTracker;
+
+use crate::SynthesisError;
+
+/// The densities of the A and B queries of a circuit, i.e. which variables appear in the A and B
+/// polynomials.
+///
+/// They only depend on the shape of the circuit, so they are computed once when the parameters
+/// are generated, see [`Parameters::densities`](super::Parameters::densities). If the parameter
+/// source provides them, the prover doesn't track them during synthesis. They must belong to the
+/// same circuit as the parameters, otherwise the proofs don't verify.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryDensities {
+    num_inputs: usize,
+    num_aux: usize,
+    // Bit sets, least significant bit first.
+    a_aux: Vec<u8>,
+    b_input: Vec<u8>,
+    b_aux: Vec<u8>,
+}
+
+impl QueryDensities {
+    pub(crate) fn new<A, BI, BA>(
+        num_inputs: usize,
+        num_aux: usize,
+        a_aux: A,
+        b_input: BI,
+        b_aux: BA,
+    ) -> Self
+    where
+        A: IntoIterator<Item = bool>,
+        BI: IntoIterator<Item = bool>,
+        BA: IntoIterator<Item = bool>,
+    {
+        QueryDensities {
+            num_inputs,
+            num_aux,
+            a_aux: pack(num_aux, a_aux),
+            b_input: pack(num_inputs, b_input),
+            b_aux: pack(num_aux, b_aux),
+        }
+    }
+
+    /// The number of inputs of the circuit, including the "one" input.
+    pub fn num_inputs(&self) -> usize {
+        self.num_inputs
+    }
+
+    /// The number of auxiliary variables of the circuit.
+    pub fn num_aux(&self) -> usize {
+        self.num_aux
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_u32::<BigEndian>(self.num_inputs as u32)?;
+        writer.write_u32::<BigEndian>(self.num_aux as u32)?;
+        writer.write_all(&self.a_aux)?;
+        writer.write_all(&self.b_input)?;
+        writer.write_all(&self.b_aux)?;
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let num_inputs = reader.read_u32::<BigEndian>()? as usize;
+        let num_aux = reader.read_u32::<BigEndian>()? as usize;
+
+        let mut read_bits = |len: usize| -> io::Result<Vec<u8>> {
+            let mut bits = vec![0; (len + 7) / 8];
+            reader.read_exact(&mut bits)?;
+            Ok(bits)
+        };
+        let a_aux = read_bits(num_aux)?;
+        let b_input = read_bits(num_inputs)?;
+        let b_aux = read_bits(num_aux)?;
+
+        Ok(QueryDensities {
+            num_inputs,
+            num_aux,
+            a_aux,
+            b_input,
+            b_aux,
+        })
+    }
+
+    /// Checks that the densities belong to a circuit with the given number of variables.
+    pub(crate) fn check(&self, num_inputs: usize, num_aux: usize) -> Result<(), SynthesisError> {
+        if self.num_inputs != num_inputs || self.num_aux != num_aux {
+            return Err(SynthesisError::IncompatibleLengthVector(format!(
+                "query densities for {} inputs and {} aux variables, circuit with {} inputs and {} \
+                 aux variables",
+                self.num_inputs, self.num_aux, num_inputs, num_aux
+            )));
+        }
+        Ok(())
+    }
+
+    /// Summarizes the densities, i.e. how many variables end up in which query.
+    pub fn report(&self) -> DensityReport {
+        let mut report = DensityReport {
+            num_inputs: self.num_inputs,
+            num_aux: self.num_aux,
+            b_input: (0..self.num_inputs)
+                .filter(|&i| bit(&self.b_input, i))
+                .count(),
+            ..Default::default()
+        };
+        for i in 0..self.num_aux {
+            match (bit(&self.a_aux, i), bit(&self.b_aux, i)) {
+                (true, true) => report.a_and_b_aux += 1,
+                (true, false) => report.a_only_aux += 1,
+                (false, true) => report.b_only_aux += 1,
+                (false, false) => report.neither_aux += 1,
+            }
+        }
+        report
+    }
+
+    /// Returns the trackers of the A query of the aux variables and of the B query of the inputs
+    /// and of the aux variables, as the prover would have tracked them during synthesis.
+    pub(crate) fn trackers(&self) -> (DensityTracker, DensityTracker, DensityTracker) {
+        (
+            tracker(self.num_aux, &self.a_aux),
+            tracker(self.num_inputs, &self.b_input),
+            tracker(self.num_aux, &self.b_aux),
+        )
+    }
+
+    /// Returns for every variable, inputs first, whether it has a point in the A query.
+    #[cfg_attr(not(feature = "arkworks"), allow(dead_code))]
+    pub(crate) fn a_query(&self) -> impl Iterator<Item = bool> + '_ {
+        // Inputs have full density in the A query, see the input constraints.
+        std::iter::repeat(true)
+            .take(self.num_inputs)
+            .chain((0..self.num_aux).map(|i| bit(&self.a_aux, i)))
+    }
+
+    /// Returns for every variable, inputs first, whether it has a point in the B queries.
+    #[cfg_attr(not(feature = "arkworks"), allow(dead_code))]
+    pub(crate) fn b_query(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.num_inputs)
+            .map(|i| bit(&self.b_input, i))
+            .chain((0..self.num_aux).map(|i| bit(&self.b_aux, i)))
+    }
+}
+
+/// A summary of the densities of the A and B queries of a circuit, see
+/// [`QueryDensities::report`].
+///
+/// The sizes of the multiexps of a proof follow from it: the A query contains all inputs and the
+/// aux variables of the A polynomials, the B query (in G1 and G2) the inputs and aux variables of
+/// the B polynomials and the L query all aux variables. Aux variables that only appear in the C
+/// polynomials are therefore the cheapest ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DensityReport {
+    /// The number of inputs, including the "one" input.
+    pub num_inputs: usize,
+    /// The number of auxiliary variables.
+    pub num_aux: usize,
+    /// The number of inputs in the B query.
+    pub b_input: usize,
+    /// The number of aux variables in the A but not in the B query.
+    pub a_only_aux: usize,
+    /// The number of aux variables in the B but not in the A query.
+    pub b_only_aux: usize,
+    /// The number of aux variables in both the A and the B query.
+    pub a_and_b_aux: usize,
+    /// The number of aux variables in neither the A nor the B query.
+    pub neither_aux: usize,
+}
+
+impl DensityReport {
+    /// The number of aux variables in the A query.
+    pub fn a_aux(&self) -> usize {
+        self.a_only_aux + self.a_and_b_aux
+    }
+
+    /// The number of aux variables in the B query.
+    pub fn b_aux(&self) -> usize {
+        self.b_only_aux + self.a_and_b_aux
+    }
+
+    /// The number of points in the A query, the size of its multiexp.
+    pub fn a_query_size(&self) -> usize {
+        self.num_inputs + self.a_aux()
+    }
+
+    /// The number of points in the B query, the size of its multiexps in G1 and in G2.
+    pub fn b_query_size(&self) -> usize {
+        self.b_input + self.b_aux()
+    }
+
+    /// The number of points in the L query, the size of its multiexp.
+    pub fn l_query_size(&self) -> usize {
+        self.num_aux
+    }
+}
+
+impl fmt::Display for DensityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |count: usize, total: usize| {
+            if total == 0 {
+                0.0
+            } else {
+                100.0 * count as f64 / total as f64
+            }
+        };
+        let vars = self.num_inputs + self.num_aux;
+
+        writeln!(f, "inputs: {}, aux: {}", self.num_inputs, self.num_aux)?;
+        writeln!(
+            f,
+            "A query: {} ({:.1}% dense)",
+            self.a_query_size(),
+            percent(self.a_query_size(), vars)
+        )?;
+        writeln!(
+            f,
+            "B query: {} ({:.1}% dense)",
+            self.b_query_size(),
+            percent(self.b_query_size(), vars)
+        )?;
+        writeln!(f, "L query: {}", self.l_query_size())?;
+        write!(
+            f,
+            "aux in A only: {}, in B only: {}, in A and B: {}, in neither: {}",
+            self.a_only_aux, self.b_only_aux, self.a_and_b_aux, self.neither_aux
+        )
+    }
+}
+
+fn bit(bits: &[u8], i: usize) -> bool {
+    (bits[i / 8] >> (i % 8)) & 1 == 1
+}
+
+fn pack<I: IntoIterator<Item = bool>>(len: usize, bits: I) -> Vec<u8> {
+    let mut packed = vec![0; (len + 7) / 8];
+    for (i, bit) in bits.into_iter().take(len).enumerate() {
+        packed[i / 8] |= (bit as u8) << (i % 8);
+    }
+    packed
+}
+
+fn tracker(len: usize, bits: &[u8]) -> DensityTracker {
+    let mut tracker = DensityTracker::new();
+    for i in 0..len {
+        tracker.add_element();
+        if bit(bits, i) {
+            tracker.inc(i);
+        }
+    }
+    tracker
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::{Bls12, Scalar as Fr};
+    use ff::Field;
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use crate::groth16::{circuit_densities, generate_random_parameters};
+    use crate::{Circuit, ConstraintSystem};
+
+    struct Sparse;
+
+    impl Circuit<Fr> for Sparse {
+        fn synthesize<CS: ConstraintSystem<Fr>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            let mut alloc = |name: &'static str| cs.alloc(|| name, || Ok(Fr::ONE));
+            let a = alloc("a")?;
+            let b = alloc("b")?;
+            let c = alloc("c")?;
+            let d = alloc("d")?;
+            let e = alloc("e")?;
+            let x = cs.alloc_input(|| "x", || Ok(Fr::from(2)))?;
+
+            cs.enforce(|| "a * b = c", |lc| lc + a, |lc| lc + b, |lc| lc + c);
+            cs.enforce(
+                || "(a + d) * 1 = x",
+                |lc| lc + a + d,
+                |lc| lc + CS::one(),
+                |lc| lc + x,
+            );
+            cs.enforce(|| "e * e = e", |lc| lc + e, |lc| lc + e, |lc| lc + e);
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_density_report() {
+        let report = circuit_densities(Sparse).unwrap().report();
+        assert_eq!(
+            report,
+            DensityReport {
+                num_inputs: 2,
+                num_aux: 5,
+                b_input: 1,
+                a_only_aux: 2,
+                b_only_aux: 1,
+                a_and_b_aux: 1,
+                neither_aux: 1,
+            }
+        );
+        assert_eq!(report.a_query_size(), 5);
+        assert_eq!(report.b_query_size(), 3);
+        assert_eq!(report.l_query_size(), 5);
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let mut params = generate_random_parameters::<Bls12, _, _>(Sparse, &mut rng).unwrap();
+        assert_eq!(params.density_report(), Some(report));
+        assert_eq!(params.a.len(), report.a_query_size());
+        assert_eq!(params.b_g1.len(), report.b_query_size());
+        assert_eq!(params.b_g2.len(), report.b_query_size());
+        assert_eq!(params.l.len(), report.l_query_size());
+
+        params.densities = None;
+        assert_eq!(params.density_report(), None);
+    }
+}
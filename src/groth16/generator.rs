@@ -1,18 +1,21 @@
-use std::ops::{AddAssign, Mul, MulAssign};
+use std::io::Write;
+use std::ops::{AddAssign, Mul, MulAssign, Range};
 
 use std::sync::Arc;
 
-use ff::{Field, PrimeField};
+use byteorder::{BigEndian, WriteBytesExt};
+use ff::{BatchInvert, Field, PrimeField};
 use group::{
     prime::{PrimeCurve, PrimeCurveAffine},
-    Curve, Group, Wnaf, WnafGroup,
+    Curve, Group, UncompressedEncoding, Wnaf, WnafGroup,
 };
 use pairing::{Engine, MultiMillerLoop};
 use rand_core::RngCore;
 
-use super::{Parameters, VerifyingKey};
+use super::{Parameters, QueryDensities, VerifyingKey};
 
-use crate::domain::EvaluationDomain;
+use crate::domain::{self, EvaluationDomain};
+use crate::util_cs::shape_cs::ShapeCS;
 use crate::{gpu, Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
 use ec_gpu_gen::threadpool::Worker;
 
@@ -41,144 +44,32 @@ where
     generate_parameters::<E, C>(circuit, g1, g2, alpha, beta, gamma, delta, tau)
 }
 
-/// This is our assembly structure that we'll use to synthesize the
-/// circuit into a QAP.
-struct KeypairAssembly<Scalar: PrimeField> {
-    num_inputs: usize,
-    num_aux: usize,
-    num_constraints: usize,
-    at_inputs: Vec<Vec<(Scalar, usize)>>,
-    bt_inputs: Vec<Vec<(Scalar, usize)>>,
-    ct_inputs: Vec<Vec<(Scalar, usize)>>,
-    at_aux: Vec<Vec<(Scalar, usize)>>,
-    bt_aux: Vec<Vec<(Scalar, usize)>>,
-    ct_aux: Vec<Vec<(Scalar, usize)>>,
+/// Synthesizes a circuit and returns the densities of its A and B queries, without generating
+/// any parameters.
+///
+/// See [`QueryDensities::report`] to find out which variables end up in which multiexp.
+pub fn circuit_densities<Scalar, C>(circuit: C) -> Result<QueryDensities, SynthesisError>
+where
+    Scalar: PrimeField,
+    C: Circuit<Scalar>,
+{
+    Ok(ShapeCS::synthesize(circuit)?.densities())
 }
 
-impl<Scalar: PrimeField> ConstraintSystem<Scalar> for KeypairAssembly<Scalar> {
-    type Root = Self;
-
-    fn new() -> Self {
-        KeypairAssembly {
-            num_inputs: 0,
-            num_aux: 0,
-            num_constraints: 0,
-            at_inputs: vec![],
-            bt_inputs: vec![],
-            ct_inputs: vec![],
-            at_aux: vec![],
-            bt_aux: vec![],
-            ct_aux: vec![],
-        }
-    }
-
-    /// Explicitly declare this `ConstraintSystem` is not extensible as a reminder to future implementers.
-    /// By forbidding use of `ConstraintSystem::extend` when generating Groth parameters, we enforce
-    /// the requirement of a well-defined sequential circuit synthesis. This also means we know that any
-    /// synthesized `ProvingAssignment` is well-formed if it leads to a verifiable proof using the resulting
-    /// groth parameters and verifying key. This is true even if the `ProvingAssignment` was synthesized
-    /// in parallel components which were then joined by `ConstraintSystem::extend`.
-    fn is_extensible() -> bool {
-        false
-    }
-
-    fn alloc<F, A, AR>(&mut self, _: A, _: F) -> Result<Variable, SynthesisError>
-    where
-        F: FnOnce() -> Result<Scalar, SynthesisError>,
-        A: FnOnce() -> AR,
-        AR: Into<String>,
-    {
-        // There is no assignment, so we don't even invoke the
-        // function for obtaining one.
-
-        let index = self.num_aux;
-        self.num_aux += 1;
-
-        self.at_aux.push(vec![]);
-        self.bt_aux.push(vec![]);
-        self.ct_aux.push(vec![]);
-
-        Ok(Variable(Index::Aux(index)))
-    }
-
-    fn alloc_input<F, A, AR>(&mut self, _: A, _: F) -> Result<Variable, SynthesisError>
-    where
-        F: FnOnce() -> Result<Scalar, SynthesisError>,
-        A: FnOnce() -> AR,
-        AR: Into<String>,
-    {
-        // There is no assignment, so we don't even invoke the
-        // function for obtaining one.
-
-        let index = self.num_inputs;
-        self.num_inputs += 1;
-
-        self.at_inputs.push(vec![]);
-        self.bt_inputs.push(vec![]);
-        self.ct_inputs.push(vec![]);
-
-        Ok(Variable(Index::Input(index)))
-    }
-
-    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
-    where
-        A: FnOnce() -> AR,
-        AR: Into<String>,
-        LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
-        LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
-        LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
-    {
-        fn eval<Scalar: PrimeField>(
-            l: LinearCombination<Scalar>,
-            inputs: &mut [Vec<(Scalar, usize)>],
-            aux: &mut [Vec<(Scalar, usize)>],
-            this_constraint: usize,
-        ) {
-            for (index, coeff) in l.iter() {
-                match index {
-                    Variable(Index::Input(id)) => inputs[id].push((*coeff, this_constraint)),
-                    Variable(Index::Aux(id)) => aux[id].push((*coeff, this_constraint)),
-                }
-            }
-        }
-
-        eval(
-            a(LinearCombination::zero()),
-            &mut self.at_inputs,
-            &mut self.at_aux,
-            self.num_constraints,
-        );
-        eval(
-            b(LinearCombination::zero()),
-            &mut self.bt_inputs,
-            &mut self.bt_aux,
-            self.num_constraints,
-        );
-        eval(
-            c(LinearCombination::zero()),
-            &mut self.ct_inputs,
-            &mut self.ct_aux,
-            self.num_constraints,
-        );
-
-        self.num_constraints += 1;
-    }
-
-    fn push_namespace<NR, N>(&mut self, _: N)
-    where
-        NR: Into<String>,
-        N: FnOnce() -> NR,
-    {
-        // Do nothing; we don't care about namespaces in this context.
-    }
-
-    fn pop_namespace(&mut self) {
-        // Do nothing; we don't care about namespaces in this context.
-    }
-
-    fn get_root(&mut self) -> &mut Self::Root {
-        self
-    }
+/// Synthesizes a circuit and returns the fingerprint of its constraints, see
+/// [`ShapeCS::fingerprint`]. The witness is never computed.
+///
+/// Generated parameters record the fingerprint of their circuit and
+/// [`Parameters::write_with_header`] persists it. The prover checks circuits against it if
+/// [`ProverConfig::check_fingerprint`](super::ProverConfig::check_fingerprint) is set, so that
+/// proofs for a circuit that changed since the parameters were generated fail instead of being
+/// silently invalid.
+pub fn circuit_fingerprint<Scalar, C>(circuit: C) -> Result<[u8; 32], SynthesisError>
+where
+    Scalar: PrimeField,
+    C: Circuit<Scalar>,
+{
+    Ok(ShapeCS::synthesize(circuit)?.fingerprint())
 }
 
 /// Create parameters for a circuit, given some toxic waste.
@@ -200,19 +91,9 @@ where
     C: Circuit<E::Fr>,
     E::Fr: gpu::GpuName,
 {
-    let mut assembly = KeypairAssembly::new();
-
-    // Allocate the "one" input variable
-    assembly.alloc_input(|| "", || Ok(<E::Fr as Field>::ONE))?;
-
-    // Synthesize the circuit.
-    circuit.synthesize(&mut assembly)?;
-
-    // Input constraints to ensure full density of IC query
-    // x * 0 = 0
-    for i in 0..assembly.num_inputs {
-        assembly.enforce(|| "", |lc| lc + Variable(Index::Input(i)), |lc| lc, |lc| lc);
-    }
+    let assembly = ShapeCS::synthesize(circuit)?;
+    let densities = assembly.densities();
+    let fingerprint = assembly.fingerprint();
 
     // Create bases for blind evaluation of polynomials at tau
     let powers_of_tau = vec![E::Fr::ZERO; assembly.num_constraints];
@@ -509,5 +390,450 @@ where
                 .filter(|e| !bool::from(e.is_identity()))
                 .collect(),
         ),
+        densities: Some(Arc::new(densities)),
+        fingerprint: Some(fingerprint),
     })
 }
+
+/// Create parameters for a circuit, given some toxic waste, and write them to `writer` as they
+/// are computed, in the format of [`Parameters::write`]. The result is the same as writing the
+/// parameters of [`generate_parameters`] with the same toxic waste.
+///
+/// The auxiliary variables are processed `chunk_size` at a time: only the linear combinations of
+/// the variables of one chunk, their Lagrange coefficients at tau and their points are kept in
+/// memory. The constraints of the circuit are not kept, instead the circuit is synthesized again
+/// for every chunk of every query: once to count the variables and constraints and record the
+/// inputs, whose points are part of the verifying key, and four times per chunk for the L, A,
+/// B G1 and B G2 queries. Synthesis must therefore be deterministic, and `chunk_size` should be
+/// as large as memory allows. The query densities and the fingerprint of the circuit are not
+/// part of the written parameters, see [`circuit_densities`] and [`circuit_fingerprint`] to
+/// compute them.
+///
+/// Returns the verifying key, which is also written at the start of the parameters.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_parameters_streaming<E, C, W>(
+    circuit: C,
+    g1: E::G1,
+    g2: E::G2,
+    alpha: E::Fr,
+    beta: E::Fr,
+    gamma: E::Fr,
+    delta: E::Fr,
+    tau: E::Fr,
+    mut writer: W,
+    chunk_size: usize,
+) -> Result<VerifyingKey<E>, SynthesisError>
+where
+    E: MultiMillerLoop,
+    <E as Engine>::G1: WnafGroup,
+    <E as Engine>::G2: WnafGroup,
+    C: Circuit<E::Fr> + Clone,
+    W: Write,
+{
+    assert!(chunk_size > 0, "chunks must not be empty");
+
+    let inputs = WindowShapeCS::synthesize(circuit.clone(), true, 0..0)?;
+    let shape = inputs.shape();
+    let num_aux = inputs.num_aux;
+    let num_vars = inputs.num_inputs + num_aux;
+    let (m, _, omega) = domain::domain_size::<E::Fr>(inputs.num_constraints)?;
+
+    // The window tables are sized like the ones of `generate_parameters`.
+    let mut g1_wnaf = Wnaf::new();
+    let g1_wnaf = g1_wnaf.base(g1, (m - 1) + 3 * num_vars);
+    let mut g2_wnaf = Wnaf::new();
+    let g2_wnaf = g2_wnaf.base(g2, num_vars);
+
+    let gamma_inverse: E::Fr =
+        Option::from(gamma.invert()).ok_or(SynthesisError::UnexpectedIdentity)?;
+    let delta_inverse: E::Fr =
+        Option::from(delta.invert()).ok_or(SynthesisError::UnexpectedIdentity)?;
+
+    let worker = Worker::new();
+
+    // t(tau), it is only zero if tau is in the evaluation domain.
+    let z_tau = tau.pow_vartime(&[m as u64]) - E::Fr::ONE;
+    if bool::from(z_tau.is_zero()) {
+        return Err(SynthesisError::UnexpectedIdentity);
+    }
+
+    let lagrange = LagrangeAtTau::new(m, omega, tau, z_tau);
+
+    // Evaluate for inputs, they are kept in memory as their IC query is part of the verifying
+    // key, which comes first.
+    let at_inputs = eval_at_tau(&worker, &lagrange, &inputs.at_inputs);
+    let bt_inputs = eval_at_tau(&worker, &lagrange, &inputs.bt_inputs);
+    let ct_inputs = eval_at_tau(&worker, &lagrange, &inputs.ct_inputs);
+    drop(inputs);
+    let ic = ext_scalars(
+        &at_inputs,
+        &bt_inputs,
+        &ct_inputs,
+        alpha,
+        beta,
+        gamma_inverse,
+    );
+    let a_inputs = batch_exp(&worker, &g1_wnaf, &at_inputs);
+    let b_g1_inputs = batch_exp(&worker, &g1_wnaf, &bt_inputs);
+    let b_g2_inputs = batch_exp(&worker, &g2_wnaf, &bt_inputs);
+
+    let g1 = g1.to_affine();
+    let g2 = g2.to_affine();
+    let vk = VerifyingKey::<E> {
+        alpha_g1: g1.mul(alpha).to_affine(),
+        beta_g1: g1.mul(beta).to_affine(),
+        beta_g2: g2.mul(beta).to_affine(),
+        gamma_g2: g2.mul(gamma).to_affine(),
+        delta_g1: g1.mul(delta).to_affine(),
+        delta_g2: g2.mul(delta).to_affine(),
+        ic: batch_exp(&worker, &g1_wnaf, &ic),
+    };
+    vk.write(&mut writer)?;
+
+    // H query, elements of the form ((tau^i * t(tau)) / delta) for i between 0 and m-2.
+    let coeff = z_tau * delta_inverse;
+    writer.write_u32::<BigEndian>((m - 1) as u32)?;
+    for start in (0..m - 1).step_by(chunk_size) {
+        let mut h = vec![E::Fr::ZERO; chunk_size.min(m - 1 - start)];
+        worker.scope(h.len(), |scope, chunk| {
+            for (i, h) in h.chunks_mut(chunk).enumerate() {
+                scope.execute(move || {
+                    let mut current = tau.pow_vartime(&[(start + i * chunk) as u64]) * coeff;
+                    for h in h {
+                        *h = current;
+                        current.mul_assign(&tau);
+                    }
+                });
+            }
+        });
+        write_points(&mut writer, &batch_exp(&worker, &g1_wnaf, &h))?;
+    }
+
+    // The aux variables are processed in chunks, once per query, as every query is a section of
+    // its own. The circuit is synthesized again for every chunk, only recording the linear
+    // combinations of the variables of the chunk.
+    let aux_chunks = || {
+        (0..num_aux)
+            .step_by(chunk_size)
+            .map(|start| start..num_aux.min(start + chunk_size))
+    };
+    let synthesize_chunk = |window: Range<usize>| -> Result<_, SynthesisError> {
+        let cs = WindowShapeCS::synthesize(circuit.clone(), false, window)?;
+        if cs.shape() != shape {
+            return Err(SynthesisError::IncompatibleLengthVector(format!(
+                "the circuit synthesized to {:?} inputs, aux variables and constraints, after {:?}",
+                cs.shape(),
+                shape
+            )));
+        }
+        Ok(cs)
+    };
+
+    // L query, it is fully dense as variables must not be unconstrained. The densities of the A
+    // and B queries are counted along the way, as their lengths are written before their points.
+    let is_dense = |t: &E::Fr| !bool::from(t.is_zero());
+    let mut a_len = at_inputs.iter().filter(|&t| is_dense(t)).count();
+    let mut b_len = bt_inputs.iter().filter(|&t| is_dense(t)).count();
+    writer.write_u32::<BigEndian>(num_aux as u32)?;
+    for range in aux_chunks() {
+        let cs = synthesize_chunk(range)?;
+        let at = eval_at_tau(&worker, &lagrange, &cs.at_aux);
+        let bt = eval_at_tau(&worker, &lagrange, &cs.bt_aux);
+        let ct = eval_at_tau(&worker, &lagrange, &cs.ct_aux);
+        a_len += at.iter().filter(|&t| is_dense(t)).count();
+        b_len += bt.iter().filter(|&t| is_dense(t)).count();
+        let l = ext_scalars(&at, &bt, &ct, alpha, beta, delta_inverse);
+        if l.iter().any(|l| bool::from(l.is_zero())) {
+            return Err(SynthesisError::UnconstrainedVariable);
+        }
+        write_points(&mut writer, &batch_exp(&worker, &g1_wnaf, &l))?;
+    }
+
+    // A and B queries, points at infinity are omitted.
+    writer.write_u32::<BigEndian>(a_len as u32)?;
+    write_points(&mut writer, &a_inputs)?;
+    for range in aux_chunks() {
+        let at = eval_at_tau(&worker, &lagrange, &synthesize_chunk(range)?.at_aux);
+        write_points(&mut writer, &batch_exp(&worker, &g1_wnaf, &at))?;
+    }
+
+    writer.write_u32::<BigEndian>(b_len as u32)?;
+    write_points(&mut writer, &b_g1_inputs)?;
+    for range in aux_chunks() {
+        let bt = eval_at_tau(&worker, &lagrange, &synthesize_chunk(range)?.bt_aux);
+        write_points(&mut writer, &batch_exp(&worker, &g1_wnaf, &bt))?;
+    }
+
+    writer.write_u32::<BigEndian>(b_len as u32)?;
+    write_points(&mut writer, &b_g2_inputs)?;
+    for range in aux_chunks() {
+        let bt = eval_at_tau(&worker, &lagrange, &synthesize_chunk(range)?.bt_aux);
+        write_points(&mut writer, &batch_exp(&worker, &g2_wnaf, &bt))?;
+    }
+
+    writer.flush()?;
+
+    Ok(vk)
+}
+
+/// A constraint system that records the shape of a circuit like [`ShapeCS`], but only the
+/// linear combinations of the inputs if `inputs` is set, and of the aux variables in `window`.
+/// All other variables are only counted.
+struct WindowShapeCS<Scalar: PrimeField> {
+    inputs: bool,
+    window: Range<usize>,
+    num_inputs: usize,
+    num_aux: usize,
+    num_constraints: usize,
+    at_inputs: Vec<Vec<(Scalar, usize)>>,
+    bt_inputs: Vec<Vec<(Scalar, usize)>>,
+    ct_inputs: Vec<Vec<(Scalar, usize)>>,
+    // The linear combinations of the aux variables in the window, starting at `window.start`.
+    at_aux: Vec<Vec<(Scalar, usize)>>,
+    bt_aux: Vec<Vec<(Scalar, usize)>>,
+    ct_aux: Vec<Vec<(Scalar, usize)>>,
+}
+
+impl<Scalar: PrimeField> WindowShapeCS<Scalar> {
+    /// Synthesizes a circuit with the "one" input and the input constraints, like
+    /// [`ShapeCS::synthesize`].
+    fn synthesize<C: Circuit<Scalar>>(
+        circuit: C,
+        inputs: bool,
+        window: Range<usize>,
+    ) -> Result<Self, SynthesisError> {
+        let mut cs = WindowShapeCS {
+            inputs,
+            window,
+            num_inputs: 0,
+            num_aux: 0,
+            num_constraints: 0,
+            at_inputs: vec![],
+            bt_inputs: vec![],
+            ct_inputs: vec![],
+            at_aux: vec![],
+            bt_aux: vec![],
+            ct_aux: vec![],
+        };
+        cs.alloc_input(|| "", || Ok(Scalar::ONE))?;
+        circuit.synthesize(&mut cs)?;
+        for i in 0..cs.num_inputs {
+            cs.enforce(|| "", |lc| lc + Variable(Index::Input(i)), |lc| lc, |lc| lc);
+        }
+        Ok(cs)
+    }
+
+    /// The number of inputs, aux variables and constraints.
+    fn shape(&self) -> (usize, usize, usize) {
+        (self.num_inputs, self.num_aux, self.num_constraints)
+    }
+}
+
+impl<Scalar: PrimeField> ConstraintSystem<Scalar> for WindowShapeCS<Scalar> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, _: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let index = self.num_aux;
+        self.num_aux += 1;
+        if self.window.contains(&index) {
+            self.at_aux.push(vec![]);
+            self.bt_aux.push(vec![]);
+            self.ct_aux.push(vec![]);
+        }
+        Ok(Variable(Index::Aux(index)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, _: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let index = self.num_inputs;
+        self.num_inputs += 1;
+        if self.inputs {
+            self.at_inputs.push(vec![]);
+            self.bt_inputs.push(vec![]);
+            self.ct_inputs.push(vec![]);
+        }
+        Ok(Variable(Index::Input(index)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    {
+        let this_constraint = self.num_constraints;
+        for (l, inputs, aux) in [
+            (
+                a(LinearCombination::zero()),
+                &mut self.at_inputs,
+                &mut self.at_aux,
+            ),
+            (
+                b(LinearCombination::zero()),
+                &mut self.bt_inputs,
+                &mut self.bt_aux,
+            ),
+            (
+                c(LinearCombination::zero()),
+                &mut self.ct_inputs,
+                &mut self.ct_aux,
+            ),
+        ] {
+            for (index, coeff) in l.iter() {
+                match index {
+                    Variable(Index::Input(id)) if self.inputs => {
+                        inputs[id].push((*coeff, this_constraint))
+                    }
+                    Variable(Index::Aux(id)) if self.window.contains(&id) => {
+                        aux[id - self.window.start].push((*coeff, this_constraint))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.num_constraints += 1;
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self) {}
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+/// The Lagrange coefficients of the evaluation domain of size `m` at tau.
+///
+/// They are the inverse FFT of the powers of tau, which `generate_parameters` computes. Here they
+/// are computed directly when needed, `L_i(tau) = t(tau) * omega^i / (m * (tau - omega^i))`, so
+/// that neither the powers of tau nor a coefficient per constraint are kept in memory.
+struct LagrangeAtTau<Scalar> {
+    omega: Scalar,
+    tau: Scalar,
+    /// `t(tau) / m`.
+    coeff: Scalar,
+}
+
+impl<Scalar: PrimeField> LagrangeAtTau<Scalar> {
+    fn new(m: usize, omega: Scalar, tau: Scalar, z_tau: Scalar) -> Self {
+        let minv = Option::<Scalar>::from(Scalar::from(m as u64).invert())
+            .expect("the domain size is not a multiple of the field characteristic");
+        LagrangeAtTau {
+            omega,
+            tau,
+            coeff: z_tau * minv,
+        }
+    }
+
+    /// Computes the coefficients at the given indices of the domain, the denominators are
+    /// inverted at once.
+    fn coeffs(&self, indices: impl Iterator<Item = usize>) -> Vec<Scalar> {
+        let omegas = indices
+            .map(|i| self.omega.pow_vartime(&[i as u64]))
+            .collect::<Vec<_>>();
+        let mut lagrange = omegas.iter().map(|w| self.tau - w).collect::<Vec<_>>();
+        lagrange.iter_mut().batch_invert();
+        for (l, w) in lagrange.iter_mut().zip(&omegas) {
+            l.mul_assign(&(*w * self.coeff));
+        }
+        lagrange
+    }
+}
+
+/// Evaluates the given QAP polynomials at tau.
+fn eval_at_tau<Scalar: PrimeField>(
+    worker: &Worker,
+    lagrange: &LagrangeAtTau<Scalar>,
+    polynomials: &[Vec<(Scalar, usize)>],
+) -> Vec<Scalar> {
+    let mut evals = vec![Scalar::ZERO; polynomials.len()];
+    worker.scope(polynomials.len(), |scope, chunk| {
+        for (evals, polynomials) in evals.chunks_mut(chunk).zip(polynomials.chunks(chunk)) {
+            scope.execute(move || {
+                let indices = polynomials.iter().flatten().map(|&(_, index)| index);
+                let mut lagrange = lagrange.coeffs(indices).into_iter();
+                for (eval, p) in evals.iter_mut().zip(polynomials.iter()) {
+                    for ((coeff, _), mut n) in p.iter().zip(&mut lagrange) {
+                        n.mul_assign(coeff);
+                        eval.add_assign(&n);
+                    }
+                }
+            });
+        }
+    });
+    evals
+}
+
+/// Computes `(beta * at + alpha * bt + ct) * inv`, the exponents of the IC and L queries.
+fn ext_scalars<Scalar: PrimeField>(
+    at: &[Scalar],
+    bt: &[Scalar],
+    ct: &[Scalar],
+    alpha: Scalar,
+    beta: Scalar,
+    inv: Scalar,
+) -> Vec<Scalar> {
+    at.iter()
+        .zip(bt.iter())
+        .zip(ct.iter())
+        .map(|((at, bt), ct)| (*at * beta + *bt * alpha + ct) * inv)
+        .collect()
+}
+
+/// Exponentiates the base of the window table with every scalar, zero scalars result in the
+/// point at infinity.
+fn batch_exp<G: PrimeCurve>(
+    worker: &Worker,
+    wnaf: &Wnaf<usize, &[G], &mut Vec<i64>>,
+    scalars: &[G::Scalar],
+) -> Vec<G::Affine> {
+    let mut affine = vec![G::Affine::identity(); scalars.len()];
+    worker.scope(scalars.len(), |scope, chunk| {
+        for (affine, scalars) in affine.chunks_mut(chunk).zip(scalars.chunks(chunk)) {
+            let mut wnaf = wnaf.shared();
+            scope.execute(move || {
+                let points = scalars
+                    .iter()
+                    .map(|scalar| {
+                        if bool::from(scalar.is_zero()) {
+                            G::identity()
+                        } else {
+                            wnaf.scalar(scalar)
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                G::batch_normalize(&points, affine);
+            });
+        }
+    });
+    affine
+}
+
+/// Writes the points that aren't the point at infinity, uncompressed.
+fn write_points<G, W>(writer: &mut W, points: &[G]) -> std::io::Result<()>
+where
+    G: PrimeCurveAffine + UncompressedEncoding,
+    W: Write,
+{
+    for point in points.iter().filter(|p| !bool::from(p.is_identity())) {
+        writer.write_all(point.to_uncompressed().as_ref())?;
+    }
+    Ok(())
+}
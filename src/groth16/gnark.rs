@@ -0,0 +1,277 @@
+//! The binary encodings of [gnark] for Groth16 proofs, verifying keys and public witnesses over
+//! BLS12-381, as of gnark 0.9.
+//!
+//! Points are encoded like the compressed and uncompressed encodings of this crate, the flags in
+//! the three most significant bits are the same. Reading accepts both, writing always compresses,
+//! like `WriteTo` of gnark does. The differences are in the layout:
+//!
+//! - Proofs are `A || B || C`, followed by the number of Pedersen commitments as a big endian
+//!   `u32`, the commitments, and the proof of knowledge of the commitments as a G1 point, the
+//!   point at infinity if there are none.
+//! - Verifying keys are laid out like the ones of [`VerifyingKey::write_compressed`], followed by
+//!   the public wires of every commitment and the number of commitment keys, both empty.
+//! - Public witnesses are the number of public and secret elements as big endian `u32`s, followed
+//!   by the length prefixed elements as 32 byte big endian integers.
+//!
+//! Commitments are a gnark extension of Groth16 that this crate doesn't support, reading fails if
+//! there are any.
+//!
+//! [gnark]: https://github.com/Consensys/gnark
+
+use std::io::{self, Read, Write};
+
+use blstrs::{Bls12, G1Affine, G2Affine, Scalar};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use group::{prime::PrimeCurveAffine, GroupEncoding, UncompressedEncoding};
+
+use super::{Proof, VerifyingKey};
+
+/// The flag of compressed points in the most significant byte.
+const COMPRESSED_FLAG: u8 = 0b1000_0000;
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid gnark encoding: {}", msg),
+    )
+}
+
+fn write_point<W: Write, G: GroupEncoding>(mut writer: W, point: &G) -> io::Result<()> {
+    writer.write_all(point.to_bytes().as_ref())
+}
+
+/// Reads a compressed or uncompressed point, depending on its compression flag.
+fn read_point<R, G>(mut reader: R) -> io::Result<G>
+where
+    R: Read,
+    G: GroupEncoding + UncompressedEncoding,
+{
+    let mut compressed = <G as GroupEncoding>::Repr::default();
+    reader.read_exact(&mut compressed.as_mut()[..1])?;
+    let point = if compressed.as_ref()[0] & COMPRESSED_FLAG != 0 {
+        reader.read_exact(&mut compressed.as_mut()[1..])?;
+        G::from_bytes(&compressed)
+    } else {
+        let mut uncompressed = G::Uncompressed::default();
+        uncompressed.as_mut()[0] = compressed.as_ref()[0];
+        reader.read_exact(&mut uncompressed.as_mut()[1..])?;
+        G::from_uncompressed(&uncompressed)
+    };
+    Option::from(point).ok_or_else(|| invalid_data("invalid point"))
+}
+
+/// Reads the commitment part of proofs and verifying keys, which has to be empty.
+fn read_no_commitments<R: Read>(mut reader: R) -> io::Result<()> {
+    if reader.read_u32::<BigEndian>()? != 0 {
+        return Err(invalid_data("commitments are not supported"));
+    }
+    Ok(())
+}
+
+impl Proof<Bls12> {
+    /// Writes the proof in the encoding of `Proof.WriteTo` of gnark, without commitments.
+    pub fn write_gnark<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write_point(&mut writer, &self.a)?;
+        write_point(&mut writer, &self.b)?;
+        write_point(&mut writer, &self.c)?;
+        writer.write_u32::<BigEndian>(0)?;
+        write_point(&mut writer, &G1Affine::identity())
+    }
+
+    /// Reads a proof written by `Proof.WriteTo` or `Proof.WriteRawTo` of gnark, or by
+    /// [`Proof::write_gnark`].
+    pub fn read_gnark<R: Read>(mut reader: R) -> io::Result<Self> {
+        let a: G1Affine = read_point(&mut reader)?;
+        let b: G2Affine = read_point(&mut reader)?;
+        let c: G1Affine = read_point(&mut reader)?;
+        read_no_commitments(&mut reader)?;
+        let commitment_pok: G1Affine = read_point(&mut reader)?;
+
+        if (a.is_identity() | b.is_identity() | c.is_identity()).into() {
+            return Err(invalid_data("point at infinity"));
+        }
+        if !bool::from(commitment_pok.is_identity()) {
+            return Err(invalid_data("commitments are not supported"));
+        }
+
+        Ok(Proof { a, b, c })
+    }
+}
+
+impl VerifyingKey<Bls12> {
+    /// Writes the verifying key in the encoding of `VerifyingKey.WriteTo` of gnark, without
+    /// commitments.
+    pub fn write_gnark<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write_point(&mut writer, &self.alpha_g1)?;
+        write_point(&mut writer, &self.beta_g1)?;
+        write_point(&mut writer, &self.beta_g2)?;
+        write_point(&mut writer, &self.gamma_g2)?;
+        write_point(&mut writer, &self.delta_g1)?;
+        write_point(&mut writer, &self.delta_g2)?;
+        writer.write_u32::<BigEndian>(self.ic.len() as u32)?;
+        for ic in &self.ic {
+            write_point(&mut writer, ic)?;
+        }
+        // The public wires of the commitments and the commitment keys.
+        writer.write_u32::<BigEndian>(0)?;
+        writer.write_u32::<BigEndian>(0)
+    }
+
+    /// Reads a verifying key written by `VerifyingKey.WriteTo` or `VerifyingKey.WriteRawTo` of
+    /// gnark, or by [`VerifyingKey::write_gnark`].
+    pub fn read_gnark<R: Read>(mut reader: R) -> io::Result<Self> {
+        let alpha_g1 = read_point(&mut reader)?;
+        let beta_g1 = read_point(&mut reader)?;
+        let beta_g2 = read_point(&mut reader)?;
+        let gamma_g2 = read_point(&mut reader)?;
+        let delta_g1 = read_point(&mut reader)?;
+        let delta_g2 = read_point(&mut reader)?;
+
+        let ic_len = reader.read_u32::<BigEndian>()? as usize;
+        let mut ic = vec![];
+        for _ in 0..ic_len {
+            let g1: G1Affine = read_point(&mut reader)?;
+            if g1.is_identity().into() {
+                return Err(invalid_data("point at infinity"));
+            }
+            ic.push(g1);
+        }
+        read_no_commitments(&mut reader)?;
+        read_no_commitments(&mut reader)?;
+
+        Ok(VerifyingKey {
+            alpha_g1,
+            beta_g1,
+            beta_g2,
+            gamma_g2,
+            delta_g1,
+            delta_g2,
+            ic,
+        })
+    }
+}
+
+/// Writes public inputs in the encoding of `Witness.MarshalBinary` of gnark for public witnesses.
+pub fn write_gnark_public_witness<W: Write>(inputs: &[Scalar], mut writer: W) -> io::Result<()> {
+    // The number of public and secret elements, followed by the length of the vector.
+    writer.write_u32::<BigEndian>(inputs.len() as u32)?;
+    writer.write_u32::<BigEndian>(0)?;
+    writer.write_u32::<BigEndian>(inputs.len() as u32)?;
+    for input in inputs {
+        writer.write_all(&input.to_bytes_be())?;
+    }
+    Ok(())
+}
+
+/// Reads public inputs written by `Witness.MarshalBinary` of gnark for public witnesses, or by
+/// [`write_gnark_public_witness`].
+pub fn read_gnark_public_witness<R: Read>(mut reader: R) -> io::Result<Vec<Scalar>> {
+    let num_public = reader.read_u32::<BigEndian>()? as usize;
+    let num_secret = reader.read_u32::<BigEndian>()?;
+    let len = reader.read_u32::<BigEndian>()? as usize;
+    if num_secret != 0 || len != num_public {
+        return Err(invalid_data("not a public witness"));
+    }
+
+    (0..len)
+        .map(|_| {
+            let mut bytes = [0; 32];
+            reader.read_exact(&mut bytes)?;
+            Option::from(Scalar::from_bytes_be(&bytes))
+                .ok_or_else(|| invalid_data("non-canonical field element"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ff::Field;
+    use group::{Curve, Group};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use blstrs::{G1Projective, G2Projective};
+
+    #[test]
+    fn test_gnark_proof() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let proof = Proof::<Bls12> {
+            a: G1Projective::random(&mut rng).to_affine(),
+            b: G2Projective::random(&mut rng).to_affine(),
+            c: G1Projective::random(&mut rng).to_affine(),
+        };
+
+        let mut bytes = vec![];
+        proof.write_gnark(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 192 + 4 + 48);
+        // The proof of knowledge is the compressed point at infinity.
+        assert_eq!(bytes[196], 0xc0);
+        assert_eq!(Proof::read_gnark(&bytes[..]).unwrap(), proof);
+
+        // The raw encoding of gnark has uncompressed points.
+        let mut raw = vec![];
+        raw.extend_from_slice(proof.a.to_uncompressed().as_ref());
+        raw.extend_from_slice(proof.b.to_uncompressed().as_ref());
+        raw.extend_from_slice(proof.c.to_uncompressed().as_ref());
+        raw.extend_from_slice(&[0; 4]);
+        raw.extend_from_slice(G1Affine::identity().to_uncompressed().as_ref());
+        assert_eq!(raw[0] & COMPRESSED_FLAG, 0);
+        assert_eq!(Proof::read_gnark(&raw[..]).unwrap(), proof);
+
+        // Proofs with commitments are rejected.
+        bytes[195] = 1;
+        assert!(Proof::read_gnark(&bytes[..]).is_err());
+    }
+
+    #[test]
+    fn test_gnark_verifying_key() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let mut g1 = || G1Projective::random(&mut rng).to_affine();
+        let vk = VerifyingKey::<Bls12> {
+            alpha_g1: g1(),
+            beta_g1: g1(),
+            beta_g2: G2Affine::generator(),
+            gamma_g2: G2Affine::generator(),
+            delta_g1: g1(),
+            delta_g2: G2Affine::generator(),
+            ic: vec![g1(), g1()],
+        };
+
+        let mut bytes = vec![];
+        vk.write_gnark(&mut bytes).unwrap();
+        // Up to the commitments, it's the compressed encoding of this crate.
+        let mut compressed = vec![];
+        vk.write_compressed(&mut compressed).unwrap();
+        assert_eq!(bytes[..compressed.len()], compressed[..]);
+        assert_eq!(bytes[compressed.len()..], [0; 8]);
+        assert!(VerifyingKey::read_gnark(&bytes[..]).unwrap() == vk);
+
+        // Verifying keys with commitment keys are rejected.
+        let len = bytes.len();
+        bytes[len - 1] = 1;
+        assert!(VerifyingKey::read_gnark(&bytes[..]).is_err());
+    }
+
+    #[test]
+    fn test_gnark_public_witness() {
+        let inputs = vec![Scalar::ONE, -Scalar::ONE];
+        let mut bytes = vec![];
+        write_gnark_public_witness(&inputs, &mut bytes).unwrap();
+        assert_eq!(bytes.len(), 12 + 2 * 32);
+        assert_eq!(bytes[..12], [0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 2]);
+        assert_eq!(bytes[43], 1);
+        assert_eq!(read_gnark_public_witness(&bytes[..]).unwrap(), inputs);
+
+        // Secret elements are rejected.
+        bytes[7] = 1;
+        assert!(read_gnark_public_witness(&bytes[..]).is_err());
+    }
+}
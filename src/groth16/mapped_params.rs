@@ -5,7 +5,6 @@ use crate::SynthesisError;
 
 #[cfg(not(target_arch = "wasm32"))]
 use memmap2::Mmap;
-use rayon::prelude::*;
 
 use std::fs::File;
 use std::io;
@@ -14,6 +13,45 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use super::{ParameterSource, PreparedVerifyingKey, VerifyingKey};
+use crate::multicore::prelude::*;
+
+/// The queries that [`MappedParameters`] map, see
+/// [`Parameters::build_mapped_parameters_with`](super::Parameters::build_mapped_parameters_with).
+/// The verifying key is always loaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuerySelection {
+    pub h: bool,
+    pub l: bool,
+    pub a: bool,
+    pub b_g1: bool,
+    pub b_g2: bool,
+}
+
+impl QuerySelection {
+    /// All queries, as needed for proving.
+    pub const ALL: Self = QuerySelection {
+        h: true,
+        l: true,
+        a: true,
+        b_g1: true,
+        b_g2: true,
+    };
+
+    /// No queries, only the verifying key, e.g. for verification.
+    pub const NONE: Self = QuerySelection {
+        h: false,
+        l: false,
+        a: false,
+        b_g1: false,
+        b_g2: false,
+    };
+}
+
+impl Default for QuerySelection {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
 
 pub struct MappedParameters<E>
 where
@@ -51,6 +89,29 @@ where
     pub b_g2: Vec<Range<usize>>,
 
     pub checked: bool,
+
+    /// The queries that are mapped. The ranges of the other ones are empty and accessing them
+    /// through the [`ParameterSource`] fails.
+    pub selection: QuerySelection,
+
+    /// The fingerprint of the circuit, if the file has a header that records it.
+    pub fingerprint: Option<[u8; 32]>,
+}
+
+impl<E> MappedParameters<E>
+where
+    E: MultiMillerLoop,
+{
+    fn check_selected(&self, selected: bool, query: &str) -> Result<(), SynthesisError> {
+        if selected {
+            Ok(())
+        } else {
+            Err(SynthesisError::IoError(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("the {} query of the parameters is not mapped", query),
+            )))
+        }
+    }
 }
 
 impl<'a, E> ParameterSource<E> for &'a MappedParameters<E>
@@ -64,7 +125,16 @@ where
         Ok(&self.vk)
     }
 
+    fn domain_size(&self) -> Option<usize> {
+        self.selection.h.then(|| self.h.len() + 1)
+    }
+
+    fn fingerprint(&self) -> Option<[u8; 32]> {
+        self.fingerprint
+    }
+
     fn get_h(&self, _num_h: usize) -> Result<Self::G1Builder, SynthesisError> {
+        self.check_selected(self.selection.h, "h")?;
         let builder = self
             .h
             .par_iter()
@@ -76,6 +146,7 @@ where
     }
 
     fn get_l(&self, _num_l: usize) -> Result<Self::G1Builder, SynthesisError> {
+        self.check_selected(self.selection.l, "l")?;
         let builder = self
             .l
             .par_iter()
@@ -91,6 +162,7 @@ where
         num_inputs: usize,
         _num_a: usize,
     ) -> Result<(Self::G1Builder, Self::G1Builder), SynthesisError> {
+        self.check_selected(self.selection.a, "a")?;
         let builder = self
             .a
             .par_iter()
@@ -108,6 +180,7 @@ where
         num_inputs: usize,
         _num_b_g1: usize,
     ) -> Result<(Self::G1Builder, Self::G1Builder), SynthesisError> {
+        self.check_selected(self.selection.b_g1, "b_g1")?;
         let builder = self
             .b_g1
             .par_iter()
@@ -125,6 +198,7 @@ where
         num_inputs: usize,
         _num_b_g2: usize,
     ) -> Result<(Self::G2Builder, Self::G2Builder), SynthesisError> {
+        self.check_selected(self.selection.b_g2, "b_g2")?;
         let builder = self
             .b_g2
             .par_iter()
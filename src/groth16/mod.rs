@@ -8,24 +8,55 @@
 mod tests;
 
 pub mod aggregate;
+#[cfg(feature = "arkworks")]
+mod ark;
+mod cbor;
+mod committed;
+mod densities;
 mod ext;
 mod generator;
+mod gnark;
 #[cfg(not(target_arch = "wasm32"))]
 mod mapped_params;
+pub mod mpc;
 mod params;
+mod params_header;
 mod proof;
+mod prove_many;
 mod prover;
+#[cfg(not(target_arch = "wasm32"))]
+mod remote_params;
+mod serde_bytes;
+#[cfg(not(target_arch = "wasm32"))]
+mod sharded_params;
+#[cfg(feature = "json")]
+mod snarkjs;
+pub mod solidity;
+mod verification_cache;
 mod verifier;
 mod verifying_key;
+pub mod wire;
+mod zkey;
 
 mod multiscalar;
 
+pub use self::cbor::{read_public_inputs_cbor, write_public_inputs_cbor};
+pub use self::committed::*;
+pub use self::densities::{DensityReport, QueryDensities};
 pub use self::ext::*;
 pub use self::generator::*;
+pub use self::gnark::{read_gnark_public_witness, write_gnark_public_witness};
 #[cfg(not(target_arch = "wasm32"))]
 pub use self::mapped_params::*;
 pub use self::params::*;
 pub use self::proof::*;
+pub use self::prove_many::*;
 pub use self::prover::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use self::remote_params::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use self::sharded_params::*;
+pub use self::verification_cache::{set_verification_cache, verification_cache, VerificationCache};
 pub use self::verifier::*;
 pub use self::verifying_key::*;
+pub use self::zkey::SnarkjsParameters;
@@ -0,0 +1,561 @@
+//! Phase 2 of a multi-party computation (MPC) ceremony for Groth16 parameters.
+//!
+//! Parameters from [`generate_parameters`](super::generate_parameters) depend on toxic waste,
+//! of which `delta` is specific to the circuit. In the ceremony every participant multiplies
+//! `delta` by a secret of their own, so that the final parameters are secure as long as a single
+//! participant destroyed their secret. Every contribution comes with a proof of knowledge of the
+//! secret, bound to all previous contributions, so that a participant can't cancel out the
+//! contributions of others.
+//!
+//! The ceremony starts with [`MpcParameters::new`], then every participant calls
+//! [`MpcParameters::contribute`] on the latest parameters, which anyone can check with
+//! [`verify_contribution`]. [`MpcParameters::verify_transcript`] checks all contributions
+//! against the initial parameters at once.
+
+use std::io::{self, Read, Write};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use ff::Field;
+use group::{prime::PrimeCurveAffine, Curve, Group, UncompressedEncoding};
+use pairing::{Engine, MultiMillerLoop};
+use rand_chacha::ChaChaRng;
+use rand_core::{RngCore, SeedableRng};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use super::Parameters;
+use crate::multicore::prelude::*;
+use crate::{wipe, SynthesisError};
+
+/// The number of points that are merged into a single pair at a time, see [`merge_pairs`].
+const MERGE_CHUNK_SIZE: usize = 1024;
+
+/// A contribution to the ceremony, the public record of a participant multiplying `delta` by
+/// their secret.
+#[derive(Clone, Debug)]
+pub struct Contribution<E: Engine> {
+    /// `delta` in G1 after the contribution.
+    pub delta_after: E::G1Affine,
+    /// A random point and its multiple by the secret, the proof of knowledge of the secret.
+    pub s: E::G1Affine,
+    pub s_delta: E::G1Affine,
+    /// The multiple by the secret of the point that is derived from the transcript, `s` and
+    /// `s_delta`, which binds the proof of knowledge to the previous contributions.
+    pub r_delta: E::G2Affine,
+    /// The hash of the ceremony before this contribution, see [`MpcParameters::transcript`].
+    pub transcript: [u8; 32],
+}
+
+impl<E: Engine> PartialEq for Contribution<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.delta_after == other.delta_after
+            && self.s == other.s
+            && self.s_delta == other.s_delta
+            && self.r_delta == other.r_delta
+            && self.transcript == other.transcript
+    }
+}
+
+impl<E: Engine> Contribution<E> {
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(self.delta_after.to_uncompressed().as_ref())?;
+        writer.write_all(self.s.to_uncompressed().as_ref())?;
+        writer.write_all(self.s_delta.to_uncompressed().as_ref())?;
+        writer.write_all(self.r_delta.to_uncompressed().as_ref())?;
+        writer.write_all(&self.transcript)?;
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        fn read_point<C: UncompressedEncoding, R: Read>(reader: &mut R) -> io::Result<C> {
+            let mut repr = C::Uncompressed::default();
+            reader.read_exact(repr.as_mut())?;
+            Option::from(C::from_uncompressed(&repr))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not on curve"))
+        }
+
+        let delta_after = read_point(&mut reader)?;
+        let s = read_point(&mut reader)?;
+        let s_delta = read_point(&mut reader)?;
+        let r_delta = read_point(&mut reader)?;
+        let mut transcript = [0; 32];
+        reader.read_exact(&mut transcript)?;
+
+        Ok(Contribution {
+            delta_after,
+            s,
+            s_delta,
+            r_delta,
+            transcript,
+        })
+    }
+
+    /// The hash of the contribution, which participants publish to attest their contribution.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        self.write(&mut HashWriter(&mut hasher))
+            .expect("writing to a hasher cannot fail");
+        hasher.finalize().into()
+    }
+}
+
+/// Parameters that go through the ceremony, together with all contributions so far.
+#[derive(Clone)]
+pub struct MpcParameters<E: MultiMillerLoop> {
+    params: Parameters<E>,
+    /// The hash of the initial parameters, it identifies the circuit.
+    cs_hash: [u8; 32],
+    contributions: Vec<Contribution<E>>,
+}
+
+impl<E: MultiMillerLoop> MpcParameters<E> {
+    /// Starts a ceremony for the given parameters.
+    pub fn new(params: Parameters<E>) -> io::Result<Self> {
+        let cs_hash = hash_parameters(&params)?;
+        Ok(MpcParameters {
+            params,
+            cs_hash,
+            contributions: Vec::new(),
+        })
+    }
+
+    /// The current parameters, which can be used for proving.
+    pub fn params(&self) -> &Parameters<E> {
+        &self.params
+    }
+
+    /// The hash of the initial parameters.
+    pub fn cs_hash(&self) -> [u8; 32] {
+        self.cs_hash
+    }
+
+    pub fn contributions(&self) -> &[Contribution<E>] {
+        &self.contributions
+    }
+
+    /// The hash of the ceremony so far, i.e. of the initial parameters and all contributions.
+    /// The next contribution is bound to it.
+    pub fn transcript(&self) -> [u8; 32] {
+        transcript(&self.cs_hash, &self.contributions)
+    }
+
+    /// Contributes a secret drawn from `rng` and returns the hash of the contribution.
+    ///
+    /// The secret is wiped from memory afterwards, see the `zeroize` feature.
+    pub fn contribute<R: RngCore>(&mut self, rng: &mut R) -> [u8; 32] {
+        let mut secrets = loop {
+            let d = E::Fr::random(&mut *rng);
+            if let Some(d_inv) = Option::<E::Fr>::from(d.invert()) {
+                break [d, d_inv];
+            }
+        };
+        // The secrets are used in place, copies of them wouldn't be wiped.
+        let [d, d_inv] = &secrets;
+
+        let s = E::G1::random(&mut *rng).to_affine();
+        let s_delta = (s * d).to_affine();
+        let transcript = self.transcript();
+        let r = hash_to_g2::<E>(&transcript, &s, &s_delta);
+        let r_delta = (r * d).to_affine();
+
+        let vk = &mut self.params.vk;
+        vk.delta_g1 = (vk.delta_g1 * d).to_affine();
+        vk.delta_g2 = (vk.delta_g2 * d).to_affine();
+        self.params.h = Arc::new(batch_mul(&self.params.h, d_inv));
+        self.params.l = Arc::new(batch_mul(&self.params.l, d_inv));
+        wipe::wipe_scalars(&mut secrets);
+
+        let contribution = Contribution {
+            delta_after: self.params.vk.delta_g1,
+            s,
+            s_delta,
+            r_delta,
+            transcript,
+        };
+        let hash = contribution.hash();
+        self.contributions.push(contribution);
+        hash
+    }
+
+    /// Verifies all contributions against the initial parameters and returns their hashes.
+    ///
+    /// The random coefficients of the checks are drawn from `rng`.
+    pub fn verify_transcript<R: RngCore>(
+        &self,
+        initial: &Parameters<E>,
+        rng: &mut R,
+    ) -> Result<Vec<[u8; 32]>, SynthesisError> {
+        if hash_parameters(initial)? != self.cs_hash {
+            return Err(invalid("the initial parameters don't match the ceremony"));
+        }
+        check_update(initial, &self.params, rng)?;
+
+        let mut delta = initial.vk.delta_g1;
+        for (i, contribution) in self.contributions.iter().enumerate() {
+            let transcript = transcript(&self.cs_hash, &self.contributions[..i]);
+            check_contribution(&transcript, &delta, contribution)?;
+            delta = contribution.delta_after;
+        }
+        if delta != self.params.vk.delta_g1 {
+            return Err(invalid("delta doesn't match the last contribution"));
+        }
+
+        Ok(self.contributions.iter().map(Contribution::hash).collect())
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        self.params.write(&mut writer)?;
+        writer.write_all(&self.cs_hash)?;
+        writer.write_u32::<BigEndian>(self.contributions.len() as u32)?;
+        for contribution in &self.contributions {
+            contribution.write(&mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(mut reader: R, checked: bool) -> io::Result<Self> {
+        let params = Parameters::read(&mut reader, checked)?;
+        let mut cs_hash = [0; 32];
+        reader.read_exact(&mut cs_hash)?;
+        let len = reader.read_u32::<BigEndian>()? as usize;
+        let contributions = (0..len)
+            .map(|_| Contribution::read(&mut reader))
+            .collect::<io::Result<_>>()?;
+
+        Ok(MpcParameters {
+            params,
+            cs_hash,
+            contributions,
+        })
+    }
+}
+
+/// Verifies that `after` is `before` with a single valid contribution on top and returns the
+/// hash of that contribution.
+///
+/// The random coefficients of the checks are drawn from `rng`.
+pub fn verify_contribution<E, R>(
+    before: &MpcParameters<E>,
+    after: &MpcParameters<E>,
+    rng: &mut R,
+) -> Result<[u8; 32], SynthesisError>
+where
+    E: MultiMillerLoop,
+    R: RngCore,
+{
+    if before.cs_hash != after.cs_hash {
+        return Err(invalid("the parameters belong to different ceremonies"));
+    }
+    let contribution = match after.contributions.split_last() {
+        Some((contribution, previous)) if previous == &before.contributions[..] => contribution,
+        _ => {
+            return Err(invalid(
+                "the contributions are not the previous ones and a single new one",
+            ))
+        }
+    };
+
+    check_update(&before.params, &after.params, rng)?;
+    check_contribution(
+        &before.transcript(),
+        &before.params.vk.delta_g1,
+        contribution,
+    )?;
+    if contribution.delta_after != after.params.vk.delta_g1 {
+        return Err(invalid("delta doesn't match the contribution"));
+    }
+
+    Ok(contribution.hash())
+}
+
+/// Checks that `after` only differs from `before` by a factor of `delta`: `delta` in G1 and G2
+/// is multiplied by it and the H and L queries are divided by it.
+fn check_update<E, R>(
+    before: &Parameters<E>,
+    after: &Parameters<E>,
+    rng: &mut R,
+) -> Result<(), SynthesisError>
+where
+    E: MultiMillerLoop,
+    R: RngCore,
+{
+    let (b, a) = (&before.vk, &after.vk);
+    if b.alpha_g1 != a.alpha_g1
+        || b.beta_g1 != a.beta_g1
+        || b.beta_g2 != a.beta_g2
+        || b.gamma_g2 != a.gamma_g2
+        || b.ic != a.ic
+        || before.a != after.a
+        || before.b_g1 != after.b_g1
+        || before.b_g2 != after.b_g2
+        || before.h.len() != after.h.len()
+        || before.l.len() != after.l.len()
+    {
+        return Err(invalid("parameters other than delta changed"));
+    }
+    if bool::from(a.delta_g1.is_identity()) {
+        return Err(invalid("delta is the point at infinity"));
+    }
+
+    if !same_ratio::<E>((b.delta_g1, a.delta_g1), (b.delta_g2, a.delta_g2)) {
+        return Err(invalid("delta in G1 and G2 differ"));
+    }
+    if !same_ratio::<E>(
+        merge_pairs(&after.h, &before.h, rng),
+        (b.delta_g2, a.delta_g2),
+    ) {
+        return Err(invalid("the H query is not divided by delta"));
+    }
+    if !same_ratio::<E>(
+        merge_pairs(&after.l, &before.l, rng),
+        (b.delta_g2, a.delta_g2),
+    ) {
+        return Err(invalid("the L query is not divided by delta"));
+    }
+
+    Ok(())
+}
+
+/// Checks the proof of knowledge of `contribution` and that it turns `delta_before` into its
+/// `delta_after`.
+fn check_contribution<E: MultiMillerLoop>(
+    transcript: &[u8; 32],
+    delta_before: &E::G1Affine,
+    contribution: &Contribution<E>,
+) -> Result<(), SynthesisError> {
+    if &contribution.transcript != transcript {
+        return Err(invalid(
+            "the contribution is bound to a different transcript",
+        ));
+    }
+    if bool::from(contribution.s.is_identity()) {
+        return Err(invalid("the proof of knowledge is the point at infinity"));
+    }
+
+    let r = hash_to_g2::<E>(transcript, &contribution.s, &contribution.s_delta);
+    if !same_ratio::<E>(
+        (contribution.s, contribution.s_delta),
+        (r, contribution.r_delta),
+    ) {
+        return Err(invalid("invalid proof of knowledge"));
+    }
+    if !same_ratio::<E>(
+        (*delta_before, contribution.delta_after),
+        (r, contribution.r_delta),
+    ) {
+        return Err(invalid("delta wasn't multiplied by the proven secret"));
+    }
+
+    Ok(())
+}
+
+fn invalid(msg: &str) -> SynthesisError {
+    SynthesisError::InvalidContribution(msg.to_string())
+}
+
+/// Checks that the ratio of the G1 points equals the ratio of the G2 points.
+fn same_ratio<E: MultiMillerLoop>(
+    g1: (E::G1Affine, E::G1Affine),
+    g2: (E::G2Affine, E::G2Affine),
+) -> bool {
+    E::pairing(&g1.0, &g2.1) == E::pairing(&g1.1, &g2.0)
+}
+
+/// Merges the pairs `(v1[i], v2[i])` into a single pair by a random linear combination. If the
+/// merged pair has a certain ratio, all pairs have it, except with negligible probability.
+fn merge_pairs<G, R>(v1: &[G], v2: &[G], rng: &mut R) -> (G, G)
+where
+    G: PrimeCurveAffine,
+    R: RngCore,
+{
+    assert_eq!(v1.len(), v2.len());
+
+    let coeffs = (0..v1.len())
+        .map(|_| G::Scalar::random(&mut *rng))
+        .collect::<Vec<_>>();
+    let (s1, s2) = v1
+        .par_chunks(MERGE_CHUNK_SIZE)
+        .zip(v2.par_chunks(MERGE_CHUNK_SIZE))
+        .zip(coeffs.par_chunks(MERGE_CHUNK_SIZE))
+        .map(|((v1, v2), coeffs)| {
+            let mut s1 = G::Curve::identity();
+            let mut s2 = G::Curve::identity();
+            for ((p1, p2), coeff) in v1.iter().zip(v2.iter()).zip(coeffs.iter()) {
+                s1 += *p1 * coeff;
+                s2 += *p2 * coeff;
+            }
+            (s1, s2)
+        })
+        .reduce(
+            || (G::Curve::identity(), G::Curve::identity()),
+            |(a1, a2), (b1, b2)| (a1 + b1, a2 + b2),
+        );
+
+    (s1.to_affine(), s2.to_affine())
+}
+
+/// Multiplies every point by `scalar`.
+fn batch_mul<G: PrimeCurveAffine>(points: &[G], scalar: &G::Scalar) -> Vec<G> {
+    let projective = points
+        .par_iter()
+        .map(|point| *point * scalar)
+        .collect::<Vec<_>>();
+    let mut affine = vec![G::identity(); projective.len()];
+    G::Curve::batch_normalize(&projective, &mut affine);
+    affine
+}
+
+/// Derives a point in G2 from the transcript and the proof of knowledge, whose discrete
+/// logarithm is unknown.
+fn hash_to_g2<E: Engine>(
+    transcript: &[u8; 32],
+    s: &E::G1Affine,
+    s_delta: &E::G1Affine,
+) -> E::G2Affine {
+    let seed = Sha256::new()
+        .chain_update(transcript)
+        .chain_update(s.to_uncompressed())
+        .chain_update(s_delta.to_uncompressed())
+        .finalize();
+    E::G2::random(ChaChaRng::from_seed(seed.into())).to_affine()
+}
+
+fn transcript<E: Engine>(cs_hash: &[u8; 32], contributions: &[Contribution<E>]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(cs_hash);
+    for contribution in contributions {
+        contribution
+            .write(&mut HashWriter(&mut hasher))
+            .expect("writing to a hasher cannot fail");
+    }
+    hasher.finalize().into()
+}
+
+fn hash_parameters<E: MultiMillerLoop>(params: &Parameters<E>) -> io::Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    params.write(&mut HashWriter(&mut hasher))?;
+    Ok(hasher.finalize().into())
+}
+
+/// Feeds everything that is written into the hasher.
+struct HashWriter<'a>(&'a mut Sha256);
+
+impl Write for HashWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::{Bls12, Scalar as Fr};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use crate::groth16::{
+        create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    };
+    use crate::{Circuit, ConstraintSystem};
+
+    /// Proves knowledge of the factors `a` and `b` of the public input `c`.
+    struct Product {
+        a: Option<Fr>,
+        b: Option<Fr>,
+    }
+
+    impl Circuit<Fr> for Product {
+        fn synthesize<CS: ConstraintSystem<Fr>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            let a = cs.alloc(|| "a", || self.a.ok_or(SynthesisError::AssignmentMissing))?;
+            let b = cs.alloc(|| "b", || self.b.ok_or(SynthesisError::AssignmentMissing))?;
+            let c = cs.alloc_input(
+                || "c",
+                || {
+                    let (a, b) = self
+                        .a
+                        .zip(self.b)
+                        .ok_or(SynthesisError::AssignmentMissing)?;
+                    Ok(a * b)
+                },
+            )?;
+            cs.enforce(|| "a * b = c", |lc| lc + a, |lc| lc + b, |lc| lc + c);
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_contributions() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let initial =
+            generate_random_parameters::<Bls12, _, _>(Product { a: None, b: None }, &mut rng)
+                .unwrap();
+
+        let mut mpc = MpcParameters::new(initial.clone()).unwrap();
+        let mut hashes = vec![];
+        for _ in 0..2 {
+            let before = mpc.clone();
+            let hash = mpc.contribute(&mut rng);
+            assert_eq!(verify_contribution(&before, &mpc, &mut rng).unwrap(), hash);
+            assert!(mpc.params().vk.delta_g1 != before.params().vk.delta_g1);
+            hashes.push(hash);
+        }
+        assert_eq!(mpc.verify_transcript(&initial, &mut rng).unwrap(), hashes);
+
+        let mut serialized = vec![];
+        mpc.write(&mut serialized).unwrap();
+        let read = MpcParameters::<Bls12>::read(&serialized[..], true).unwrap();
+        assert!(read.params() == mpc.params());
+        assert_eq!(read.cs_hash(), mpc.cs_hash());
+        assert!(read.contributions() == mpc.contributions());
+
+        // The parameters after the ceremony are still valid.
+        let pvk = prepare_verifying_key(&mpc.params().vk);
+        let (a, b) = (Fr::from(3), Fr::from(5));
+        let proof = create_random_proof(
+            Product {
+                a: Some(a),
+                b: Some(b),
+            },
+            mpc.params(),
+            &mut rng,
+        )
+        .unwrap();
+        assert!(verify_proof(&pvk, &proof, &[a * b]).unwrap());
+
+        // Contributions that don't update all of the parameters are rejected.
+        let before = mpc.clone();
+        let mut after = mpc.clone();
+        after.contribute(&mut rng);
+        let mut forged = after.clone();
+        forged.params.h = before.params.h.clone();
+        assert!(verify_contribution(&before, &forged, &mut rng).is_err());
+        let mut forged = after.clone();
+        forged.params.vk.delta_g2 = before.params.vk.delta_g2;
+        assert!(verify_contribution(&before, &forged, &mut rng).is_err());
+
+        // A contribution with a proof of knowledge of a different secret is rejected.
+        let mut forged = after.clone();
+        let last = forged.contributions.last_mut().unwrap();
+        last.s_delta = (last.s_delta * Fr::from(2)).to_affine();
+        assert!(verify_contribution(&before, &forged, &mut rng).is_err());
+
+        // Contributions on top of other parameters are rejected.
+        let other = MpcParameters::new(initial.clone()).unwrap();
+        assert!(verify_contribution(&other, &after, &mut rng).is_err());
+        assert!(after.verify_transcript(before.params(), &mut rng).is_err());
+        assert!(after.verify_transcript(&initial, &mut rng).is_ok());
+    }
+}
@@ -3,7 +3,8 @@ use std::ops::AddAssign;
 
 use ff::PrimeField;
 use group::{prime::PrimeCurveAffine, Curve, Group};
-use rayon::prelude::*;
+
+use crate::multicore::prelude::*;
 
 pub const WINDOW_SIZE: usize = 8;
 
@@ -8,7 +8,7 @@ use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 
 #[cfg(not(target_arch = "wasm32"))]
 mod memmap_uses {
-    pub use crate::groth16::MappedParameters;
+    pub use crate::groth16::{MappedParameters, QuerySelection};
     pub use memmap2::{Mmap, MmapOptions};
     pub use std::fs::File;
     pub use std::mem;
@@ -21,7 +21,8 @@ use std::sync::Arc;
 #[cfg(not(target_arch = "wasm32"))]
 use memmap_uses::*;
 
-use super::VerifyingKey;
+use super::params_header::{self, ParamsHeader, SectionReader, SectionsWriter};
+use super::{DensityReport, QueryDensities, VerifyingKey};
 
 #[derive(Clone)]
 pub struct Parameters<E>
@@ -49,6 +50,14 @@ where
     // infinity for the same reason as the "A" polynomials.
     pub b_g1: Arc<Vec<E::G1Affine>>,
     pub b_g2: Arc<Vec<E::G2Affine>>,
+
+    // The densities of the A and B queries, set when the parameters are generated. They are not
+    // part of the serialized parameters, see `QueryDensities::write` to persist them alongside.
+    pub densities: Option<Arc<QueryDensities>>,
+
+    // The fingerprint of the circuit, set when the parameters are generated, see
+    // `circuit_fingerprint`. It is only persisted by `Parameters::write_with_header`.
+    pub fingerprint: Option<[u8; 32]>,
 }
 
 impl<E> PartialEq for Parameters<E>
@@ -56,6 +65,8 @@ where
     E: MultiMillerLoop,
 {
     fn eq(&self, other: &Self) -> bool {
+        // The densities and the fingerprint are derived from the circuit, they are not part of
+        // the parameters.
         self.vk == other.vk
             && self.h == other.h
             && self.l == other.l
@@ -69,33 +80,63 @@ impl<E> Parameters<E>
 where
     E: MultiMillerLoop,
 {
-    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
-        self.vk.write(&mut writer)?;
-
-        writer.write_u32::<BigEndian>(self.h.len() as u32)?;
-        for g in &self.h[..] {
-            writer.write_all(g.to_uncompressed().as_ref())?;
-        }
+    /// Summarizes the densities of the A and B queries, see [`QueryDensities::report`].
+    ///
+    /// Returns `None` if the parameters don't come with their densities, e.g. when they were read
+    /// from a file. Use [`circuit_densities`](super::circuit_densities) to compute them from the
+    /// circuit instead.
+    pub fn density_report(&self) -> Option<DensityReport> {
+        self.densities.as_ref().map(|densities| densities.report())
+    }
 
-        writer.write_u32::<BigEndian>(self.l.len() as u32)?;
-        for g in &self.l[..] {
-            writer.write_all(g.to_uncompressed().as_ref())?;
-        }
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        self.write_sections(&mut writer, |_| {})
+    }
 
-        writer.write_u32::<BigEndian>(self.a.len() as u32)?;
-        for g in &self.a[..] {
-            writer.write_all(g.to_uncompressed().as_ref())?;
-        }
+    /// Writes the parameters with a header that holds the version of the format, the curve, the
+    /// fingerprint of the circuit and the length and the BLAKE2s digest of every section. This way [`Parameters::read`] rejects
+    /// truncated or corrupted files and files for another curve with a meaningful error.
+    ///
+    /// [`Parameters::read`], [`Parameters::read_mmap`] and
+    /// [`Parameters::build_mapped_parameters`] read files with and without header, older
+    /// versions of bellperson only the ones without. Only [`Parameters::read`] checks the
+    /// digests.
+    pub fn write_with_header<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut sections = SectionsWriter::new();
+        self.write_sections(&mut sections, SectionsWriter::end_section)?;
+        ParamsHeader::new::<E>(self.fingerprint, sections.finish()).write(&mut writer)?;
+        self.write(writer)
+    }
 
-        writer.write_u32::<BigEndian>(self.b_g1.len() as u32)?;
-        for g in &self.b_g1[..] {
-            writer.write_all(g.to_uncompressed().as_ref())?;
+    /// Writes the sections of the parameters, `end_section` is called after each of them.
+    fn write_sections<W: Write>(
+        &self,
+        writer: &mut W,
+        mut end_section: impl FnMut(&mut W),
+    ) -> io::Result<()> {
+        fn write_points<G: UncompressedEncoding, W: Write>(
+            writer: &mut W,
+            points: &[G],
+        ) -> io::Result<()> {
+            writer.write_u32::<BigEndian>(points.len() as u32)?;
+            for g in points {
+                writer.write_all(g.to_uncompressed().as_ref())?;
+            }
+            Ok(())
         }
 
-        writer.write_u32::<BigEndian>(self.b_g2.len() as u32)?;
-        for g in &self.b_g2[..] {
-            writer.write_all(g.to_uncompressed().as_ref())?;
-        }
+        self.vk.write(&mut *writer)?;
+        end_section(writer);
+        write_points(writer, &self.h[..])?;
+        end_section(writer);
+        write_points(writer, &self.l[..])?;
+        end_section(writer);
+        write_points(writer, &self.a[..])?;
+        end_section(writer);
+        write_points(writer, &self.b_g1[..])?;
+        end_section(writer);
+        write_points(writer, &self.b_g2[..])?;
+        end_section(writer);
 
         Ok(())
     }
@@ -107,6 +148,18 @@ where
     pub fn build_mapped_parameters(
         param_file_path: PathBuf,
         checked: bool,
+    ) -> io::Result<MappedParameters<E>> {
+        Self::build_mapped_parameters_with(param_file_path, checked, QuerySelection::ALL)
+    }
+
+    /// Like [`Parameters::build_mapped_parameters`], but only records the offsets of the selected
+    /// queries, the others are skipped. Tools that only verify or inspect the parameters don't
+    /// need to go through the large A and B queries this way.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn build_mapped_parameters_with(
+        param_file_path: PathBuf,
+        checked: bool,
+        selection: QuerySelection,
     ) -> io::Result<MappedParameters<E>> {
         let mut offset: usize = 0;
         let param_file = File::open(&param_file_path)?;
@@ -129,9 +182,14 @@ where
         let get_offsets = |params: &Mmap,
                            offset: &mut usize,
                            param: &mut Vec<Range<usize>>,
-                           range_len: usize|
+                           range_len: usize,
+                           selected: bool|
          -> Result<(), std::io::Error> {
             let len = read_length(params, &mut *offset)?;
+            if !selected {
+                *offset += len * range_len;
+                return Ok(());
+            }
             for _ in 0..len {
                 (*param).push(Range {
                     start: *offset,
@@ -143,6 +201,8 @@ where
             Ok(())
         };
 
+        let header = params_header::read_header::<E>(&params)?;
+        offset += header.as_ref().map_or(0, ParamsHeader::len);
         let vk = VerifyingKey::<E>::read_mmap(&params, &mut offset)?;
 
         let mut h = vec![];
@@ -151,11 +211,11 @@ where
         let mut b_g1 = vec![];
         let mut b_g2 = vec![];
 
-        get_offsets(&params, &mut offset, &mut h, g1_len)?;
-        get_offsets(&params, &mut offset, &mut l, g1_len)?;
-        get_offsets(&params, &mut offset, &mut a, g1_len)?;
-        get_offsets(&params, &mut offset, &mut b_g1, g1_len)?;
-        get_offsets(&params, &mut offset, &mut b_g2, g2_len)?;
+        get_offsets(&params, &mut offset, &mut h, g1_len, selection.h)?;
+        get_offsets(&params, &mut offset, &mut l, g1_len, selection.l)?;
+        get_offsets(&params, &mut offset, &mut a, g1_len, selection.a)?;
+        get_offsets(&params, &mut offset, &mut b_g1, g1_len, selection.b_g1)?;
+        get_offsets(&params, &mut offset, &mut b_g2, g2_len, selection.b_g2)?;
 
         let pvk = super::prepare_verifying_key(&vk);
 
@@ -171,6 +231,8 @@ where
             b_g1,
             b_g2,
             checked,
+            selection,
+            fingerprint: header.and_then(|header| header.fingerprint()),
         })
     }
 
@@ -278,7 +340,8 @@ where
             Ok(())
         };
 
-        let mut offset: usize = 0;
+        let header = params_header::read_header::<E>(mmap)?;
+        let mut offset = header.as_ref().map_or(0, ParamsHeader::len);
         let vk = VerifyingKey::<E>::read_mmap(mmap, &mut offset)?;
 
         let mut h = vec![];
@@ -300,11 +363,65 @@ where
             a: Arc::new(a),
             b_g1: Arc::new(b_g1),
             b_g2: Arc::new(b_g2),
+            densities: None,
+            fingerprint: header.and_then(|header| header.fingerprint()),
         })
     }
 
+    /// Reads parameters with or without header, see [`Parameters::write_with_header`].
     pub fn read<R: Read>(mut reader: R, checked: bool) -> io::Result<Self> {
-        let read_g1 = |reader: &mut R| -> io::Result<E::G1Affine> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic == params_header::MAGIC {
+            let header = ParamsHeader::read::<E, _>(&mut reader)?;
+            Self::read_sections(SectionReader::new(reader, Some(header)), checked)
+        } else {
+            let reader = io::Cursor::new(magic).chain(reader);
+            Self::read_sections(SectionReader::new(reader, None), checked)
+        }
+    }
+
+    /// Reads parameters in the layout of the `Parameters` of bellman, e.g. the Sapling and
+    /// Sprout parameters of Zcash, so that the outputs of past ceremonies can be used with this
+    /// crate and its GPU prover. Write them with [`Parameters::write_with_header`] to load them
+    /// faster afterwards.
+    ///
+    /// The layout is the one of files written by [`Parameters::write`], but unlike
+    /// [`Parameters::read`], this always checks that the points are in the right subgroup, and
+    /// it checks that the queries fit together and that nothing follows them.
+    pub fn read_bellman<R: Read>(mut reader: R) -> io::Result<Self> {
+        let invalid_data = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic == params_header::MAGIC {
+            return Err(invalid_data(
+                "not a bellman file, it has a bellperson header",
+            ));
+        }
+        let mut reader = io::Cursor::new(magic).chain(reader);
+        let params = Self::read_sections(SectionReader::new(&mut reader, None), true)?;
+
+        if params.vk.ic.is_empty() {
+            return Err(invalid_data("the verifying key has no IC points"));
+        }
+        if !(params.h.len() + 1).is_power_of_two() {
+            return Err(invalid_data(
+                "the size of the H query is no power of two minus one",
+            ));
+        }
+        if params.b_g1.len() != params.b_g2.len() {
+            return Err(invalid_data("the B G1 and B G2 queries differ in size"));
+        }
+        if reader.read(&mut [0])? != 0 {
+            return Err(invalid_data("trailing data after the parameters"));
+        }
+
+        Ok(params)
+    }
+
+    fn read_sections<R: Read>(mut reader: SectionReader<R>, checked: bool) -> io::Result<Self> {
+        let read_g1 = |reader: &mut SectionReader<R>| -> io::Result<E::G1Affine> {
             let mut repr = <E::G1Affine as UncompressedEncoding>::Uncompressed::default();
             reader.read_exact(repr.as_mut())?;
 
@@ -328,7 +445,7 @@ where
             }
         };
 
-        let read_g2 = |reader: &mut R| -> io::Result<E::G2Affine> {
+        let read_g2 = |reader: &mut SectionReader<R>| -> io::Result<E::G2Affine> {
             let mut repr = <E::G2Affine as UncompressedEncoding>::Uncompressed::default();
             reader.read_exact(repr.as_mut())?;
 
@@ -352,7 +469,9 @@ where
             }
         };
 
+        let fingerprint = reader.header().and_then(ParamsHeader::fingerprint);
         let vk = VerifyingKey::<E>::read(&mut reader)?;
+        reader.end_section()?;
 
         let mut h = vec![];
         let mut l = vec![];
@@ -365,12 +484,14 @@ where
             for _ in 0..len {
                 h.push(read_g1(&mut reader)?);
             }
+            reader.end_section()?;
         }
         {
             let len = reader.read_u32::<BigEndian>()? as usize;
             for _ in 0..len {
                 l.push(read_g1(&mut reader)?);
             }
+            reader.end_section()?;
         }
 
         {
@@ -378,6 +499,7 @@ where
             for _ in 0..len {
                 a.push(read_g1(&mut reader)?);
             }
+            reader.end_section()?;
         }
 
         {
@@ -385,6 +507,7 @@ where
             for _ in 0..len {
                 b_g1.push(read_g1(&mut reader)?);
             }
+            reader.end_section()?;
         }
 
         {
@@ -392,6 +515,7 @@ where
             for _ in 0..len {
                 b_g2.push(read_g2(&mut reader)?);
             }
+            reader.end_section()?;
         }
 
         Ok(Parameters {
@@ -401,6 +525,8 @@ where
             a: Arc::new(a),
             b_g1: Arc::new(b_g1),
             b_g2: Arc::new(b_g2),
+            densities: None,
+            fingerprint,
         })
     }
 }
@@ -413,6 +539,28 @@ where
     type G2Builder: SourceBuilder<E::G2Affine>;
 
     fn get_vk(&self, num_ic: usize) -> Result<&VerifyingKey<E>, SynthesisError>;
+    /// Returns the size of the evaluation domain of the circuit, if it is known without loading
+    /// any of the queries.
+    fn domain_size(&self) -> Option<usize> {
+        None
+    }
+    /// Returns the densities of the A and B queries, if they are known. The prover then doesn't
+    /// track them during synthesis.
+    fn densities(&self) -> Option<Arc<QueryDensities>> {
+        None
+    }
+    /// Returns the fingerprint of the circuit the parameters were generated for, if it is known,
+    /// see [`circuit_fingerprint`](super::circuit_fingerprint).
+    fn fingerprint(&self) -> Option<[u8; 32]> {
+        None
+    }
+    /// Returns the odd exponent `k` for which the parameters were generated over the evaluation
+    /// domain of `E::Fr::ROOT_OF_UNITY^k`. It is 1 for the parameters of this crate, for others
+    /// the prover reorders the evaluations of the constraints, see
+    /// [`SnarkjsParameters`](super::SnarkjsParameters).
+    fn root_of_unity_exponent(&self) -> u64 {
+        1
+    }
     fn get_h(&self, num_h: usize) -> Result<Self::G1Builder, SynthesisError>;
     fn get_l(&self, num_l: usize) -> Result<Self::G1Builder, SynthesisError>;
     fn get_a(
@@ -443,6 +591,18 @@ where
         Ok(&self.vk)
     }
 
+    fn domain_size(&self) -> Option<usize> {
+        Some(self.h.len() + 1)
+    }
+
+    fn densities(&self) -> Option<Arc<QueryDensities>> {
+        self.densities.clone()
+    }
+
+    fn fingerprint(&self) -> Option<[u8; 32]> {
+        self.fingerprint
+    }
+
     fn get_h(&self, _: usize) -> Result<Self::G1Builder, SynthesisError> {
         Ok((self.h.clone(), 0))
     }
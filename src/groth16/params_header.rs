@@ -0,0 +1,249 @@
+//! The header of parameter files written with
+//! [`Parameters::write_with_header`](super::Parameters::write_with_header).
+//!
+//! All integers are big endian. The header consists of
+//!
+//!  - the magic bytes `\x89BPH`, the first byte has the compression flag set, which the first
+//!    byte of a file without header, an uncompressed point, never has,
+//!  - the version of the format as `u32`,
+//!  - an 8 byte id of the curve, derived from the generators of G1 and G2,
+//!  - since version 2, the 32 byte fingerprint of the circuit the parameters were generated for,
+//!    see [`circuit_fingerprint`](super::circuit_fingerprint), or zeros if it is unknown,
+//!  - the length in bytes as `u64` and the BLAKE2s digest of every section, i.e. of the verifying
+//!    key and of the H, L, A, B G1 and B G2 queries.
+//!
+//! The sections follow in the same format as in files without header.
+
+use std::io::{self, Read, Write};
+
+use blake2s_simd::State as Blake2sState;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use group::{prime::PrimeCurveAffine, UncompressedEncoding};
+use pairing::Engine;
+
+pub(crate) const MAGIC: [u8; 4] = *b"\x89BPH";
+const VERSION: u32 = 2;
+const SECTION_NAMES: [&str; 6] = ["vk", "h", "l", "a", "b_g1", "b_g2"];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Section {
+    len: u64,
+    digest: [u8; 32],
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ParamsHeader {
+    version: u32,
+    curve_id: [u8; 8],
+    fingerprint: Option<[u8; 32]>,
+    sections: [Section; 6],
+}
+
+impl ParamsHeader {
+    pub(crate) fn new<E: Engine>(fingerprint: Option<[u8; 32]>, sections: [Section; 6]) -> Self {
+        ParamsHeader {
+            version: VERSION,
+            curve_id: curve_id::<E>(),
+            fingerprint,
+            sections,
+        }
+    }
+
+    /// The length of the header, including the magic bytes.
+    pub(crate) fn len(&self) -> usize {
+        let fingerprint_len = if self.version >= 2 { 32 } else { 0 };
+        4 + 4 + 8 + fingerprint_len + SECTION_NAMES.len() * (8 + 32)
+    }
+
+    pub(crate) fn fingerprint(&self) -> Option<[u8; 32]> {
+        self.fingerprint
+    }
+
+    pub(crate) fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&MAGIC)?;
+        writer.write_u32::<BigEndian>(VERSION)?;
+        writer.write_all(&self.curve_id)?;
+        writer.write_all(&self.fingerprint.unwrap_or_default())?;
+        for section in &self.sections {
+            writer.write_u64::<BigEndian>(section.len)?;
+            writer.write_all(&section.digest)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the header that follows the magic bytes and checks that it describes parameters of
+    /// the curve of `E` in a known version of the format.
+    pub(crate) fn read<E: Engine, R: Read>(mut reader: R) -> io::Result<Self> {
+        let version = reader.read_u32::<BigEndian>()?;
+        if version == 0 || version > VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported parameter file version {}", version),
+            ));
+        }
+
+        let mut curve_id = [0; 8];
+        reader.read_exact(&mut curve_id)?;
+        if curve_id != self::curve_id::<E>() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "parameter file is for a different curve",
+            ));
+        }
+
+        let mut fingerprint = None;
+        if version >= 2 {
+            let mut bytes = [0; 32];
+            reader.read_exact(&mut bytes)?;
+            fingerprint = Some(bytes).filter(|bytes| *bytes != [0; 32]);
+        }
+
+        let mut sections = [Section::default(); 6];
+        for section in sections.iter_mut() {
+            section.len = reader.read_u64::<BigEndian>()?;
+            reader.read_exact(&mut section.digest)?;
+        }
+
+        Ok(ParamsHeader {
+            version,
+            curve_id,
+            fingerprint,
+            sections,
+        })
+    }
+}
+
+fn curve_id<E: Engine>() -> [u8; 8] {
+    let mut state = Blake2sState::new();
+    state.update(E::G1Affine::generator().to_uncompressed().as_ref());
+    state.update(E::G2Affine::generator().to_uncompressed().as_ref());
+    let mut id = [0; 8];
+    id.copy_from_slice(&state.finalize().as_bytes()[..8]);
+    id
+}
+
+/// Reads the header of a parameter file, if it has one. The sections start after
+/// [`ParamsHeader::len`] bytes then, their digests are not checked.
+pub(crate) fn read_header<E: Engine>(bytes: &[u8]) -> io::Result<Option<ParamsHeader>> {
+    if bytes.len() >= MAGIC.len() && bytes[..MAGIC.len()] == MAGIC {
+        ParamsHeader::read::<E, _>(&bytes[MAGIC.len()..]).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Computes the lengths and digests of the sections of the header from the serialized
+/// parameters.
+pub(crate) struct SectionsWriter {
+    sections: Vec<Section>,
+    state: Blake2sState,
+    len: u64,
+}
+
+impl SectionsWriter {
+    pub(crate) fn new() -> Self {
+        SectionsWriter {
+            sections: Vec::with_capacity(SECTION_NAMES.len()),
+            state: Blake2sState::new(),
+            len: 0,
+        }
+    }
+
+    pub(crate) fn end_section(&mut self) {
+        let mut digest = [0; 32];
+        digest.copy_from_slice(self.state.finalize().as_bytes());
+        self.sections.push(Section {
+            len: self.len,
+            digest,
+        });
+        self.state = Blake2sState::new();
+        self.len = 0;
+    }
+
+    pub(crate) fn finish(self) -> [Section; 6] {
+        let mut sections = [Section::default(); 6];
+        sections.copy_from_slice(&self.sections);
+        sections
+    }
+}
+
+impl Write for SectionsWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.state.update(buf);
+        self.len += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reads the sections of a parameter file and checks them against its header, if there is one.
+pub(crate) struct SectionReader<R> {
+    reader: R,
+    header: Option<ParamsHeader>,
+    section: usize,
+    state: Blake2sState,
+    len: u64,
+}
+
+impl<R: Read> SectionReader<R> {
+    pub(crate) fn new(reader: R, header: Option<ParamsHeader>) -> Self {
+        SectionReader {
+            reader,
+            header,
+            section: 0,
+            state: Blake2sState::new(),
+            len: 0,
+        }
+    }
+
+    pub(crate) fn header(&self) -> Option<&ParamsHeader> {
+        self.header.as_ref()
+    }
+
+    /// Checks the length and the digest of the section that was read last.
+    pub(crate) fn end_section(&mut self) -> io::Result<()> {
+        if let Some(header) = &self.header {
+            let expected = &header.sections[self.section];
+            if self.len != expected.len || self.state.finalize().as_bytes() != &expected.digest[..]
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "section {} of the parameter file is corrupted",
+                        SECTION_NAMES[self.section]
+                    ),
+                ));
+            }
+            self.state = Blake2sState::new();
+            self.len = 0;
+        }
+        self.section += 1;
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for SectionReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        if let Some(header) = &self.header {
+            if read == 0 && !buf.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!(
+                        "parameter file is truncated, section {} should be {} bytes",
+                        SECTION_NAMES[self.section], header.sections[self.section].len
+                    ),
+                ));
+            }
+            self.state.update(&buf[..read]);
+            self.len += read as u64;
+        }
+
+        Ok(read)
+    }
+}
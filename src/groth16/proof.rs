@@ -1,14 +1,15 @@
-use std::fmt;
 use std::io::{self, Read, Write};
-use std::marker::PhantomData;
 
-use group::{prime::PrimeCurveAffine, GroupEncoding};
-use pairing::Engine;
-use rayon::prelude::*;
+use ff::Field;
+use group::{prime::PrimeCurveAffine, Curve, GroupEncoding};
+use pairing::{Engine, MultiMillerLoop};
 
-use serde::de::Visitor;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use super::{cbor, serde_bytes, VerifyingKey};
+use crate::multicore::prelude::*;
+use crate::SynthesisError;
+
 #[derive(Clone, Debug)]
 pub struct Proof<E: Engine> {
     pub a: E::G1Affine,
@@ -16,37 +17,18 @@ pub struct Proof<E: Engine> {
     pub c: E::G1Affine,
 }
 
+/// Serializes the proof in the compressed encoding of [`Proof::write`].
 impl<E: Engine> Serialize for Proof<E> {
     fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
         let mut v = Vec::with_capacity(Proof::<E>::size());
-        self.write(&mut v).unwrap();
-        s.serialize_bytes(&v)
-    }
-}
-
-fn deserialize_proof<'de, D: Deserializer<'de>, E: Engine>(d: D) -> Result<Proof<E>, D::Error> {
-    struct BytesVisitor<E> {
-        _ph: PhantomData<E>,
-    }
-
-    impl<'de, E: Engine> Visitor<'de> for BytesVisitor<E> {
-        type Value = Proof<E>;
-
-        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "a proof")
-        }
-        #[inline]
-        fn visit_bytes<F: serde::de::Error>(self, v: &[u8]) -> Result<Self::Value, F> {
-            let p = Proof::read(v).unwrap();
-            Ok(p)
-        }
+        self.write(&mut v).map_err(serde::ser::Error::custom)?;
+        serde_bytes::serialize(&v, s)
     }
-    d.deserialize_bytes(BytesVisitor { _ph: PhantomData })
 }
 
 impl<'de, E: Engine> Deserialize<'de> for Proof<E> {
     fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
-        deserialize_proof(d)
+        serde_bytes::deserialize(d, "a proof", |source| Proof::read(source))
     }
 }
 
@@ -56,6 +38,16 @@ impl<E: Engine> PartialEq for Proof<E> {
     }
 }
 
+impl<E: Engine> From<&Proof<E>> for bellperson_verifier_core::Proof<E> {
+    fn from(proof: &Proof<E>) -> Self {
+        bellperson_verifier_core::Proof {
+            a: proof.a,
+            b: proof.b,
+            c: proof.c,
+        }
+    }
+}
+
 impl<E: Engine> Proof<E> {
     pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
         writer.write_all(self.a.to_bytes().as_ref())?;
@@ -73,6 +65,60 @@ impl<E: Engine> Proof<E> {
         Ok(proof)
     }
 
+    /// Writes the proof as a deterministic CBOR array of its three compressed points.
+    pub fn write_cbor<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        cbor::write_array_header(&mut writer, 3)?;
+        cbor::write_point(&mut writer, &self.a)?;
+        cbor::write_point(&mut writer, &self.b)?;
+        cbor::write_point(&mut writer, &self.c)?;
+
+        Ok(())
+    }
+
+    /// Reads a proof written by [`Proof::write_cbor`].
+    pub fn read_cbor<R: Read>(mut reader: R) -> io::Result<Self> {
+        cbor::read_array_header_exact(&mut reader, 3)?;
+        let a: E::G1Affine = cbor::read_point(&mut reader)?;
+        let b: E::G2Affine = cbor::read_point(&mut reader)?;
+        let c: E::G1Affine = cbor::read_point(&mut reader)?;
+
+        if (a.is_identity() | b.is_identity() | c.is_identity()).into() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "point at infinity",
+            ));
+        }
+
+        Ok(Proof { a, b, c })
+    }
+
+    /// Returns the encoding of [`Proof::write`] as a hex string.
+    pub fn to_hex(&self) -> String {
+        serde_bytes::to_hex(&self.to_bytes())
+    }
+
+    /// Reads a proof from the hex string of [`Proof::to_hex`], with or without a `0x` prefix.
+    pub fn from_hex(hex: &str) -> io::Result<Self> {
+        serde_bytes::read_hex(hex, |source| Proof::read(source))
+    }
+
+    /// Returns the encoding of [`Proof::write`] in padded standard base64.
+    pub fn to_base64(&self) -> String {
+        serde_bytes::to_base64(&self.to_bytes())
+    }
+
+    /// Reads a proof from the base64 string of [`Proof::to_base64`].
+    pub fn from_base64(base64: &str) -> io::Result<Self> {
+        serde_bytes::read_base64(base64, |source| Proof::read(source))
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::size());
+        self.write(&mut bytes)
+            .expect("writing to a Vec never fails");
+        bytes
+    }
+
     pub fn size() -> usize {
         let g1_compressed_size = <E::G1Affine as GroupEncoding>::Repr::default()
             .as_ref()
@@ -229,6 +275,29 @@ impl<E: Engine> Proof<E> {
     }
 }
 
+impl<E: MultiMillerLoop> Proof<E> {
+    /// Re-randomizes the proof, i.e. turns it into a fresh proof of the same statement that can't
+    /// be linked to the original one, without running the prover again.
+    ///
+    /// The new proof is `(A / r', r' B + r' s' δ, C + s' A)`, it verifies against the same
+    /// verifying key and public inputs. `r_prime` and `s_prime` must be chosen uniformly at random
+    /// for the result to be unlinkable, `r_prime` must not be zero.
+    pub fn rerandomize(
+        &self,
+        vk: &VerifyingKey<E>,
+        r_prime: E::Fr,
+        s_prime: E::Fr,
+    ) -> Result<Self, SynthesisError> {
+        let r_inv: E::Fr = Option::from(r_prime.invert()).ok_or(SynthesisError::DivisionByZero)?;
+
+        let a = (self.a * r_inv).to_affine();
+        let b = (self.b * r_prime + vk.delta_g2 * (r_prime * s_prime)).to_affine();
+        let c = (self.a * s_prime + self.c).to_affine();
+
+        Ok(Proof { a, b, c })
+    }
+}
+
 #[cfg(test)]
 mod test_with_bls12_381 {
     use std::ops::MulAssign;
@@ -236,7 +305,7 @@ mod test_with_bls12_381 {
     use super::*;
     use crate::groth16::{
         create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
-        Parameters,
+        Parameters, PreparedVerifyingKey, VerifyingKey,
     };
     use crate::{Circuit, ConstraintSystem, SynthesisError};
     use bincode::{deserialize, serialize};
@@ -299,9 +368,47 @@ mod test_with_bls12_381 {
 
             let de_params = Parameters::read(&v[..], false).unwrap();
             assert!(params == de_params);
+
+            // The layout is the one of bellman.
+            let de_params = Parameters::read_bellman(&v[..]).unwrap();
+            assert!(params == de_params);
+            assert!(Parameters::<Bls12>::read_bellman(&v[..v.len() - 1]).is_err());
+            let mut trailing = v.clone();
+            trailing.push(0);
+            assert!(Parameters::<Bls12>::read_bellman(&trailing[..]).is_err());
+            let mut with_header = vec![];
+            params.write_with_header(&mut with_header).unwrap();
+            assert!(Parameters::<Bls12>::read_bellman(&with_header[..]).is_err());
+
+            let mut cbor_vk = vec![];
+            params.vk.write_cbor(&mut cbor_vk).unwrap();
+            let de_vk = VerifyingKey::<Bls12>::read_cbor(&cbor_vk[..]).unwrap();
+            assert!(params.vk == de_vk);
+
+            // Serde uses compressed points, hex encoded in JSON.
+            let serialized_vk = serialize(&params.vk).unwrap();
+            assert_eq!(serialized_vk.len(), 8 + 3 * 48 + 3 * 96 + 4 + 2 * 48);
+            let de_vk: VerifyingKey<Bls12> = deserialize(&serialized_vk).unwrap();
+            assert!(params.vk == de_vk);
+            let json_vk = serde_json::to_string(&params.vk).unwrap();
+            let de_vk: VerifyingKey<Bls12> = serde_json::from_str(&json_vk).unwrap();
+            assert!(params.vk == de_vk);
+            assert!(
+                serde_json::from_str::<VerifyingKey<Bls12>>(&json_vk[..json_vk.len() - 3]).is_err()
+            );
+
+            let hex_vk = params.vk.to_hex();
+            assert_eq!(json_vk, format!("\"{}\"", hex_vk));
+            assert!(VerifyingKey::<Bls12>::from_hex(&hex_vk).unwrap() == params.vk);
+            let base64_vk = params.vk.to_base64();
+            assert!(VerifyingKey::<Bls12>::from_base64(&base64_vk).unwrap() == params.vk);
+            assert!(VerifyingKey::<Bls12>::from_hex(&format!("{}00", hex_vk)).is_err());
         }
 
         let pvk = prepare_verifying_key::<Bls12>(&params.vk);
+        let serialized_pvk = serialize(&pvk).unwrap();
+        let de_pvk: PreparedVerifyingKey<Bls12> = deserialize(&serialized_pvk).unwrap();
+        assert_eq!(de_pvk.vk_hash, pvk.vk_hash);
 
         for _ in 0..100 {
             let a = Fr::random(&mut *rng);
@@ -337,10 +444,45 @@ mod test_with_bls12_381 {
             assert!(verify_proof(&pvk, &proof, &[c]).unwrap());
             assert!(!verify_proof(&pvk, &proof, &[a]).unwrap());
 
+            // Test re-randomization
+            let rerandomized = proof
+                .rerandomize(&params.vk, Fr::random(&mut *rng), Fr::random(&mut *rng))
+                .unwrap();
+            assert_ne!(rerandomized, proof);
+            assert!(verify_proof(&pvk, &rerandomized, &[c]).unwrap());
+            assert!(!verify_proof(&pvk, &rerandomized, &[a]).unwrap());
+            assert!(proof
+                .rerandomize(&params.vk, Fr::ZERO, Fr::random(&mut *rng))
+                .is_err());
+
             // Test serialization
             let serialized_proof = serialize(&proof).unwrap();
             let de_proof: Proof<Bls12> = deserialize(&serialized_proof).unwrap();
             assert_eq!(de_proof, proof);
+            let json_proof = serde_json::to_string(&proof).unwrap();
+            assert_eq!(json_proof.len(), 2 + 2 * 192);
+            let de_proof: Proof<Bls12> = serde_json::from_str(&json_proof).unwrap();
+            assert_eq!(de_proof, proof);
+            assert!(verify_proof(&de_pvk, &de_proof, &[c]).unwrap());
+
+            // Test CBOR encoding
+            let mut cbor_proof = vec![];
+            proof.write_cbor(&mut cbor_proof).unwrap();
+            assert_eq!(cbor_proof.len(), 1 + (2 + 48) + (2 + 96) + (2 + 48));
+            let de_proof = Proof::<Bls12>::read_cbor(&cbor_proof[..]).unwrap();
+            assert_eq!(de_proof, proof);
+
+            // Test the text encodings
+            let hex_proof = proof.to_hex();
+            assert_eq!(hex_proof.len(), 2 * 192);
+            assert_eq!(Proof::<Bls12>::from_hex(&hex_proof).unwrap(), proof);
+            let prefixed = format!("0x{}", hex_proof);
+            assert_eq!(Proof::<Bls12>::from_hex(&prefixed).unwrap(), proof);
+            let base64_proof = proof.to_base64();
+            assert_eq!(base64_proof.len(), 192 / 3 * 4);
+            assert_eq!(Proof::<Bls12>::from_base64(&base64_proof).unwrap(), proof);
+            assert!(Proof::<Bls12>::from_hex(&hex_proof[2..]).is_err());
+            assert!(Proof::<Bls12>::from_base64(&hex_proof).is_err());
         }
     }
 }
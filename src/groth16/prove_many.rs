@@ -0,0 +1,140 @@
+use ff::Field;
+use log::debug;
+use pairing::MultiMillerLoop;
+use rand_core::RngCore;
+
+use super::{create_proof_batch_with_config, ParameterSource, Proof, ProverConfig};
+use crate::gpu::{self, FrameworkSelection, GpuName};
+use crate::{Circuit, SynthesisError};
+
+/// Configuration for [`prove_many`].
+#[derive(Clone, Debug)]
+pub struct ProveManyConfig {
+    /// The maximum number of circuits proven in a single batch. Batches may be smaller if the
+    /// circuits of a full batch would not fit into the memory of the smallest GPU.
+    pub max_batch_size: usize,
+    /// The number of batches that are proven concurrently. If it is `None`, one batch per GPU is
+    /// proven at a time, or a single one if there is no GPU.
+    pub concurrent_batches: Option<usize>,
+    /// Whether the proofs are created with high priority, see
+    /// [`create_proof_batch_priority`].
+    pub priority: bool,
+    /// The frameworks the GPU kernels run on, see [`ProverConfig::frameworks`].
+    pub frameworks: FrameworkSelection,
+}
+
+impl Default for ProveManyConfig {
+    fn default() -> Self {
+        ProveManyConfig {
+            max_batch_size: 16,
+            concurrent_batches: None,
+            priority: false,
+            frameworks: FrameworkSelection::default(),
+        }
+    }
+}
+
+impl ProveManyConfig {
+    /// Returns the number of circuits proven per batch, for circuits with the given evaluation
+    /// domain size and scalars of `scalar_len` bytes.
+    ///
+    /// Every circuit of a batch holds the evaluations of its A, B and C polynomials, the batch
+    /// is bounded so that those fit into the memory of the smallest GPU.
+    fn batch_size(&self, domain_size: Option<usize>, scalar_len: usize) -> usize {
+        let max_batch_size = self.max_batch_size.max(1);
+        let min_memory = gpu::device_memory().into_iter().min();
+        match (domain_size, min_memory) {
+            (Some(domain_size), Some(memory)) => {
+                let circuit_len = (3 * domain_size * scalar_len) as u64;
+                let fitting = (memory / circuit_len.max(1)) as usize;
+                fitting.clamp(1, max_batch_size)
+            }
+            _ => max_batch_size,
+        }
+    }
+
+    fn concurrent_batches(&self) -> usize {
+        self.concurrent_batches
+            .unwrap_or_else(|| gpu::device_memory().len())
+            .max(1)
+    }
+}
+
+/// Creates proofs for all `circuits`, grouping them into batches sized by the available GPU
+/// memory and proving several batches concurrently so that all GPUs are kept busy. The proofs
+/// are returned in the order of the circuits.
+///
+/// With `BELLPERSON_GPUS_PER_LOCK` set, concurrent batches are distributed over the devices by
+/// the GPU locks.
+pub fn prove_many<E, C, R, P>(
+    circuits: Vec<C>,
+    params: P,
+    rng: &mut R,
+    config: &ProveManyConfig,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: MultiMillerLoop,
+    C: Circuit<E::Fr> + Send,
+    R: RngCore,
+    P: ParameterSource<E> + Clone,
+    E::Fr: GpuName,
+    E::G1Affine: GpuName,
+    E::G2Affine: GpuName,
+{
+    let num_circuits = circuits.len();
+    let batch_size = config.batch_size(params.domain_size(), std::mem::size_of::<E::Fr>());
+    let concurrent_batches = config.concurrent_batches();
+    debug!(
+        "proving {} circuits in batches of {}, {} at a time",
+        num_circuits, batch_size, concurrent_batches
+    );
+
+    let mut batches = Vec::with_capacity((num_circuits + batch_size - 1) / batch_size);
+    let mut circuits = circuits.into_iter().peekable();
+    while circuits.peek().is_some() {
+        let batch = circuits.by_ref().take(batch_size).collect::<Vec<_>>();
+        let r_s = (0..batch.len())
+            .map(|_| E::Fr::random(&mut *rng))
+            .collect::<Vec<_>>();
+        let s_s = (0..batch.len())
+            .map(|_| E::Fr::random(&mut *rng))
+            .collect::<Vec<_>>();
+        batches.push((batch, r_s, s_s));
+    }
+
+    let prover_config = ProverConfig {
+        priority: config.priority,
+        frameworks: config.frameworks.clone(),
+        ..Default::default()
+    };
+    let prover_config = &prover_config;
+
+    let mut proofs = Vec::with_capacity(num_circuits);
+    let mut batches = batches.into_iter().peekable();
+    while batches.peek().is_some() {
+        let group = batches
+            .by_ref()
+            .take(concurrent_batches)
+            .collect::<Vec<_>>();
+        let results = std::thread::scope(|scope| {
+            let handles = group
+                .into_iter()
+                .map(|(batch, r_s, s_s)| {
+                    let params = params.clone();
+                    scope.spawn(move || {
+                        create_proof_batch_with_config(batch, params, r_s, s_s, prover_config)
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("prover thread panicked"))
+                .collect::<Vec<_>>()
+        });
+        for result in results {
+            proofs.extend(result?);
+        }
+    }
+
+    Ok(proofs)
+}
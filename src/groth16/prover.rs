@@ -1,22 +1,27 @@
 use std::ops::{AddAssign, Mul, MulAssign};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use ff::{Field, PrimeField};
 use group::{prime::PrimeCurveAffine, Curve};
 use pairing::MultiMillerLoop;
-use rand_core::RngCore;
-use rayon::prelude::*;
+use rand_chacha::ChaChaRng;
+use rand_core::{RngCore, SeedableRng};
+use sha2::{Digest, Sha256};
 
-use super::{ParameterSource, Proof};
+use super::{ParameterSource, Proof, QueryDensities, VerifyingKey};
+use crate::circuits::r1cs::R1cs;
 use crate::domain::EvaluationDomain;
-use crate::gpu::{GpuName, LockedFftKernel, LockedMultiexpKernel};
-use crate::multiexp::multiexp;
+use crate::gpu::{FrameworkSelection, GpuName, LockedFftKernel, LockedMultiexpKernel};
+use crate::multicore::prelude::*;
+use crate::multiexp::{multiexp, multiexp_serial};
+use crate::util_cs::shape_cs::ShapeCS;
 use crate::{
-    Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable, BELLMAN_VERSION,
+    wipe, Circuit, ConstraintSystem, Index, LinearCombination, LookupConstraintSystem,
+    SynthesisError, Variable, BELLMAN_VERSION,
 };
 use ec_gpu_gen::multiexp_cpu::{DensityTracker, FullDensity};
-use ec_gpu_gen::threadpool::{Worker, THREAD_POOL};
+use ec_gpu_gen::threadpool::{Waiter, Worker, THREAD_POOL};
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 use log::trace;
 use log::{debug, info};
@@ -24,8 +29,241 @@ use log::{debug, info};
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 use crate::gpu::PriorityLock;
 
-struct ProvingAssignment<Scalar: PrimeField> {
-    // Density of queries
+/// The default for [`small_circuit_threshold`].
+const DEFAULT_SMALL_CIRCUIT_THRESHOLD: usize = 1 << 12;
+
+/// Returns the number of constraints up to which circuits are proven on the CPU with serial
+/// multiexps, without dispatching any work to the GPU. For such small circuits the fixed cost of
+/// acquiring and setting up the GPU outweighs the work itself. The threshold can be set with the
+/// `BELLMAN_SMALL_CIRCUIT_THRESHOLD` environment variable, a value of `0` disables the fast path.
+fn small_circuit_threshold() -> usize {
+    std::env::var("BELLMAN_SMALL_CIRCUIT_THRESHOLD")
+        .ok()
+        .and_then(|threshold| threshold.parse().ok())
+        .unwrap_or(DEFAULT_SMALL_CIRCUIT_THRESHOLD)
+}
+
+/// How the prover schedules its stages, trading peak memory usage for concurrency.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MemorySchedule {
+    /// Load the parameters of the next stage in the background while the current one is running,
+    /// and keep the intermediates of all circuits of a batch until their stage is done. This is
+    /// the fastest option.
+    #[default]
+    MaximumOverlap,
+    /// Run the stages one after another, only load parameters once they are needed, and release
+    /// every intermediate (FFT evaluations, H coefficients, assignments) as soon as its consumer
+    /// is done.
+    LowPeakMemory,
+}
+
+/// Synthesizes a circuit into the prover's constraint system.
+type Synthesize<C, Scalar> = fn(C, &mut ProvingAssignment<Scalar>) -> Result<(), SynthesisError>;
+
+fn synthesize_circuit<Scalar: PrimeField, C: Circuit<Scalar>>(
+    circuit: C,
+    cs: &mut ProvingAssignment<Scalar>,
+) -> Result<(), SynthesisError> {
+    circuit.synthesize(cs)
+}
+
+/// A circuit that can be used as a trait object, so that circuits of different types can be
+/// proven in one batch, see [`create_proof_batch_dyn`].
+///
+/// It is implemented for every [`Circuit`]. The circuits of a batch must still synthesize to
+/// constraint systems of the same shape, as they share one parameter set.
+pub trait DynCircuit<Scalar: PrimeField>: Send {
+    fn synthesize_dyn(
+        self: Box<Self>,
+        cs: &mut ProvingAssignment<Scalar>,
+    ) -> Result<(), SynthesisError>;
+}
+
+impl<Scalar: PrimeField, C: Circuit<Scalar> + Send> DynCircuit<Scalar> for C {
+    fn synthesize_dyn(
+        self: Box<Self>,
+        cs: &mut ProvingAssignment<Scalar>,
+    ) -> Result<(), SynthesisError> {
+        (*self).synthesize(cs)
+    }
+}
+
+fn synthesize_dyn_circuit<Scalar: PrimeField>(
+    circuit: Box<dyn DynCircuit<Scalar> + '_>,
+    cs: &mut ProvingAssignment<Scalar>,
+) -> Result<(), SynthesisError> {
+    circuit.synthesize_dyn(cs)
+}
+
+/// Uses a precomputed assignment instead of synthesizing a circuit. Only the evaluations, the
+/// assignments and the densities are taken over, the prover keeps its configuration.
+fn replay_assignment<Scalar: PrimeField>(
+    mut assignment: ProvingAssignment<Scalar>,
+    prover: &mut ProvingAssignment<Scalar>,
+) -> Result<(), SynthesisError> {
+    if prover.track_density && !assignment.track_density {
+        return Err(SynthesisError::MissingDensities);
+    }
+    // There are no constraints whose shape could be recorded.
+    if prover.shape.is_some() {
+        return Err(SynthesisError::UncheckedFingerprint);
+    }
+    std::mem::swap(&mut prover.a_aux_density, &mut assignment.a_aux_density);
+    std::mem::swap(&mut prover.b_input_density, &mut assignment.b_input_density);
+    std::mem::swap(&mut prover.b_aux_density, &mut assignment.b_aux_density);
+    std::mem::swap(&mut prover.a, &mut assignment.a);
+    std::mem::swap(&mut prover.b, &mut assignment.b);
+    std::mem::swap(&mut prover.c, &mut assignment.c);
+    std::mem::swap(
+        &mut prover.input_assignment,
+        &mut assignment.input_assignment,
+    );
+    std::mem::swap(&mut prover.aux_assignment, &mut assignment.aux_assignment);
+    Ok(())
+}
+
+/// Synthesizes only the witness of a circuit, whose constraints were captured beforehand.
+fn synthesize_witness<Scalar: PrimeField, C: Circuit<Scalar>>(
+    (r1cs, circuit): (&R1cs<Scalar>, C),
+    prover: &mut ProvingAssignment<Scalar>,
+) -> Result<(), SynthesisError> {
+    replay_assignment(r1cs.synthesize_witness(circuit)?, prover)
+}
+
+/// Where the blinding scalars `r` and `s` of the proofs come from.
+enum Randomization<'a, Scalar> {
+    /// They are zero, the proofs are not zero-knowledge.
+    Zero,
+    /// They are given by the caller, one per proof.
+    Given(Vec<Scalar>, Vec<Scalar>),
+    /// They are derived from the witness and the given nonce, see [`derive_randomization`].
+    Derived(&'a [u8]),
+}
+
+/// Domain separator of the hash the blinding scalars of deterministic proofs are derived with.
+const DETERMINISTIC_PROOF_DOMAIN: &[u8] = b"bellperson-deterministic-proof-v1";
+
+/// Derives the blinding scalars `r` and `s` of every proof from a hash of the nonce and the
+/// witness of its circuit.
+fn derive_randomization<Scalar: PrimeField>(
+    nonce: &[u8],
+    input_assignments: &[Arc<Vec<Scalar::Repr>>],
+    aux_assignments: &[Arc<Vec<Scalar::Repr>>],
+) -> (Vec<Scalar>, Vec<Scalar>) {
+    input_assignments
+        .iter()
+        .zip(aux_assignments.iter())
+        .map(|(inputs, aux)| {
+            let mut hasher = Sha256::new();
+            hasher.update(DETERMINISTIC_PROOF_DOMAIN);
+            hasher.update((nonce.len() as u64).to_be_bytes());
+            hasher.update(nonce);
+            hasher.update((inputs.len() as u64).to_be_bytes());
+            hasher.update((aux.len() as u64).to_be_bytes());
+            for repr in inputs.iter().chain(aux.iter()) {
+                hasher.update(repr.as_ref());
+            }
+
+            let mut rng = ChaChaRng::from_seed(hasher.finalize().into());
+            (Scalar::random(&mut rng), Scalar::random(&mut rng))
+        })
+        .unzip()
+}
+
+/// A stage of the prover, see [`ProverConfig::progress`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProverStage {
+    /// The synthesis of the circuits, the elements are the constraints per circuit.
+    Synthesis,
+    /// The FFTs computing the coefficients of the H polynomials, the elements are the
+    /// constraints per circuit.
+    WitnessFft,
+    /// The multiexps with the H query, the elements are the constraints per circuit.
+    HMultiexp,
+    /// The multiexps with the L query, the elements are the aux variables per circuit.
+    LMultiexp,
+    /// The multiexps with the A and B queries in G1, the elements are the variables per circuit.
+    AbG1Multiexp,
+    /// The multiexps with the B query in G2, the elements are the size of the query.
+    BG2Multiexp,
+    /// Waiting for the results of the multiexps and assembling the proofs, the elements are the
+    /// proofs. Small circuits that are proven on the CPU go straight from the synthesis to this
+    /// stage.
+    Assembly,
+}
+
+/// Reports that the prover finished a stage, see [`ProverConfig::progress`].
+#[derive(Clone, Copy, Debug)]
+pub struct ProverProgress {
+    pub stage: ProverStage,
+    /// The number of proofs that are created.
+    pub num_proofs: usize,
+    /// The number of elements the stage processed, see [`ProverStage`].
+    pub elements: usize,
+    /// How long the stage took. Unless the [`MemorySchedule::LowPeakMemory`] schedule is used,
+    /// multiexps run in the background, their stages only cover starting them and the rest of
+    /// their time is spent in the [`ProverStage::Assembly`] stage.
+    pub duration: Duration,
+}
+
+/// A callback that receives the progress of the prover, see [`ProverConfig::progress`].
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(&ProverProgress) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new<F: Fn(&ProverProgress) + Send + Sync + 'static>(callback: F) -> Self {
+        ProgressCallback(Arc::new(callback))
+    }
+
+    fn report(&self, stage: ProverStage, num_proofs: usize, elements: usize, duration: Duration) {
+        (self.0)(&ProverProgress {
+            stage,
+            num_proofs,
+            elements,
+            duration,
+        });
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+/// Configuration of the prover, see [`create_proof_batch_with_config`].
+#[derive(Clone, Debug, Default)]
+pub struct ProverConfig {
+    /// Whether the proofs are created with high priority, see [`create_proof_batch_priority`].
+    pub priority: bool,
+    /// How the prover stages are scheduled.
+    pub schedule: MemorySchedule,
+    /// The frameworks the GPU kernels run on, per device. This only makes a difference if
+    /// bellperson is compiled with both CUDA and OpenCL support, by default the
+    /// `EC_GPU_FRAMEWORK` environment variable selects them.
+    pub frameworks: FrameworkSelection,
+    /// Called whenever the prover finished one of its stages, long running proofs can report
+    /// their progress this way.
+    pub progress: Option<ProgressCallback>,
+    /// Whether the namespaces of the circuits are tracked during synthesis, so that errors of
+    /// allocations carry the `path/to/namespace/variable` they occurred at, see
+    /// [`SynthesisError::Located`]. This costs evaluating the name of every namespace, it is
+    /// meant for debugging failing circuits.
+    pub namespace_paths: bool,
+    /// Whether the circuits are checked against the fingerprint of the parameters, if they have
+    /// one, see [`circuit_fingerprint`](super::circuit_fingerprint). Proving fails with
+    /// [`SynthesisError::FingerprintMismatch`] if the circuits changed since the parameters were
+    /// generated. This costs recording the shape of the circuits during synthesis. Precomputed
+    /// assignments can't be checked, proving them fails with
+    /// [`SynthesisError::UncheckedFingerprint`].
+    pub check_fingerprint: bool,
+}
+
+/// The constraint system the prover synthesizes circuits into, it records the evaluations of the
+/// A, B, C polynomials and the assignments of the variables.
+pub struct ProvingAssignment<Scalar: PrimeField> {
+    // Density of queries. They are not tracked if they are loaded from the parameters instead.
+    track_density: bool,
     a_aux_density: DensityTracker,
     b_input_density: DensityTracker,
     b_aux_density: DensityTracker,
@@ -38,6 +276,14 @@ struct ProvingAssignment<Scalar: PrimeField> {
     // Assignments of variables
     input_assignment: Vec<Scalar>,
     aux_assignment: Vec<Scalar>,
+
+    // The current namespace, only tracked if synthesis errors should carry their path, see
+    // [`ProverConfig::namespace_paths`].
+    namespace: Option<Vec<String>>,
+
+    // The shape of the circuit, only recorded if it is checked against the fingerprint of the
+    // parameters, see [`ProverConfig::check_fingerprint`].
+    shape: Option<ShapeCS<Scalar>>,
 }
 use std::fmt;
 
@@ -90,11 +336,116 @@ impl<Scalar: PrimeField> PartialEq for ProvingAssignment<Scalar> {
     }
 }
 
+impl<Scalar: PrimeField> ProvingAssignment<Scalar> {
+    /// Attaches the path of an allocation to an error, if the namespaces are tracked.
+    fn locate<A, AR>(&self, error: SynthesisError, annotation: A) -> SynthesisError
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        match &self.namespace {
+            Some(namespace) => {
+                let mut path = namespace.clone();
+                path.push(annotation().into());
+                error.at_path(path.join("/"))
+            }
+            None => error,
+        }
+    }
+
+    /// Synthesizes the circuit, without proving it. The assignment can be proven later with
+    /// [`create_proof_batch_from_assignments`], e.g. if the witness is generated elsewhere.
+    pub fn synthesize<C: Circuit<Scalar>>(circuit: C) -> Result<Self, SynthesisError> {
+        let mut prover = Self::new();
+        prover.alloc_input(|| "", || Ok(Scalar::ONE))?;
+        circuit.synthesize(&mut prover)?;
+        Ok(prover)
+    }
+
+    /// Assembles an assignment from a precomputed witness.
+    ///
+    /// `a`, `b` and `c` are the evaluations of the A, B, C linear combinations of every
+    /// constraint of the circuit, `input_assignment` are the public inputs without the "one"
+    /// input. The query densities only need to be given if the parameters the assignment is
+    /// proven with don't contain them.
+    pub fn from_assignments(
+        a: Vec<Scalar>,
+        b: Vec<Scalar>,
+        c: Vec<Scalar>,
+        input_assignment: &[Scalar],
+        aux_assignment: &[Scalar],
+        densities: Option<&QueryDensities>,
+    ) -> Result<Self, SynthesisError> {
+        if a.len() != b.len() || a.len() != c.len() {
+            return Err(SynthesisError::IncompatibleLengthVector(format!(
+                "{} A, {} B and {} C evaluations",
+                a.len(),
+                b.len(),
+                c.len()
+            )));
+        }
+
+        let mut prover = Self::new();
+        prover.a = a;
+        prover.b = b;
+        prover.c = c;
+        prover.input_assignment = std::iter::once(&Scalar::ONE)
+            .chain(input_assignment)
+            .copied()
+            .collect();
+        prover.aux_assignment = aux_assignment.to_vec();
+
+        match densities {
+            Some(densities) => {
+                densities.check(prover.num_inputs(), prover.num_aux())?;
+                let (a_aux_density, b_input_density, b_aux_density) = densities.trackers();
+                prover.a_aux_density = a_aux_density;
+                prover.b_input_density = b_input_density;
+                prover.b_aux_density = b_aux_density;
+            }
+            None => prover.track_density = false,
+        }
+
+        Ok(prover)
+    }
+
+    /// The number of inputs, including the "one" input.
+    pub fn num_inputs(&self) -> usize {
+        self.input_assignment.len()
+    }
+
+    /// The assignment of the inputs, including the "one" input.
+    pub fn input_assignment(&self) -> Vec<Scalar> {
+        self.input_assignment.clone()
+    }
+
+    /// The number of auxiliary variables.
+    pub fn num_aux(&self) -> usize {
+        self.aux_assignment.len()
+    }
+
+    /// The number of constraints, without the ones the prover adds for the inputs.
+    pub fn num_constraints(&self) -> usize {
+        self.a.len()
+    }
+}
+
+impl<Scalar: PrimeField> Drop for ProvingAssignment<Scalar> {
+    fn drop(&mut self) {
+        wipe::wipe_scalars(&mut self.a);
+        wipe::wipe_scalars(&mut self.b);
+        wipe::wipe_scalars(&mut self.c);
+        wipe::wipe_scalars(&mut self.input_assignment);
+        wipe::wipe_scalars(&mut self.aux_assignment);
+    }
+}
+
 impl<Scalar: PrimeField> ConstraintSystem<Scalar> for ProvingAssignment<Scalar> {
     type Root = Self;
 
     fn new() -> Self {
         Self {
+            track_density: true,
             a_aux_density: DensityTracker::new(),
             b_input_density: DensityTracker::new(),
             b_aux_density: DensityTracker::new(),
@@ -103,30 +454,44 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for ProvingAssignment<Scalar>
             c: vec![],
             input_assignment: vec![],
             aux_assignment: vec![],
+            namespace: None,
+            shape: None,
         }
     }
 
-    fn alloc<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    fn alloc<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
     where
         F: FnOnce() -> Result<Scalar, SynthesisError>,
         A: FnOnce() -> AR,
         AR: Into<String>,
     {
-        self.aux_assignment.push(f()?);
-        self.a_aux_density.add_element();
-        self.b_aux_density.add_element();
+        let value = f().map_err(|e| self.locate(e, annotation))?;
+        self.aux_assignment.push(value);
+        if self.track_density {
+            self.a_aux_density.add_element();
+            self.b_aux_density.add_element();
+        }
+        if let Some(shape) = &mut self.shape {
+            shape.alloc(|| "", || Ok(value))?;
+        }
 
         Ok(Variable(Index::Aux(self.aux_assignment.len() - 1)))
     }
 
-    fn alloc_input<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    fn alloc_input<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
     where
         F: FnOnce() -> Result<Scalar, SynthesisError>,
         A: FnOnce() -> AR,
         AR: Into<String>,
     {
-        self.input_assignment.push(f()?);
-        self.b_input_density.add_element();
+        let value = f().map_err(|e| self.locate(e, annotation))?;
+        self.input_assignment.push(value);
+        if self.track_density {
+            self.b_input_density.add_element();
+        }
+        if let Some(shape) = &mut self.shape {
+            shape.alloc_input(|| "", || Ok(value))?;
+        }
 
         Ok(Variable(Index::Input(self.input_assignment.len() - 1)))
     }
@@ -145,23 +510,24 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for ProvingAssignment<Scalar>
 
         let input_assignment = &self.input_assignment;
         let aux_assignment = &self.aux_assignment;
-        let a_aux_density = &mut self.a_aux_density;
-        let b_input_density = &mut self.b_input_density;
-        let b_aux_density = &mut self.b_aux_density;
+        let track_density = self.track_density;
+        let a_aux_density = track_density.then_some(&mut self.a_aux_density);
+        let b_input_density = track_density.then_some(&mut self.b_input_density);
+        let b_aux_density = track_density.then_some(&mut self.b_aux_density);
 
         let a_res = a.eval(
             // Inputs have full density in the A query
             // because there are constraints of the
             // form x * 0 = 0 for each input.
             None,
-            Some(a_aux_density),
+            a_aux_density,
             input_assignment,
             aux_assignment,
         );
 
         let b_res = b.eval(
-            Some(b_input_density),
-            Some(b_aux_density),
+            b_input_density,
+            b_aux_density,
             input_assignment,
             aux_assignment,
         );
@@ -180,18 +546,27 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for ProvingAssignment<Scalar>
         self.a.push(a_res);
         self.b.push(b_res);
         self.c.push(c_res);
+
+        if let Some(shape) = &mut self.shape {
+            shape.enforce(|| "", |_| a, |_| b, |_| c);
+        }
     }
 
-    fn push_namespace<NR, N>(&mut self, _: N)
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
     where
         NR: Into<String>,
         N: FnOnce() -> NR,
     {
-        // Do nothing; we don't care about namespaces in this context.
+        // Namespaces are only evaluated if synthesis errors should carry their path.
+        if let Some(namespace) = &mut self.namespace {
+            namespace.push(name_fn().into());
+        }
     }
 
     fn pop_namespace(&mut self) {
-        // Do nothing; we don't care about namespaces in this context.
+        if let Some(namespace) = &mut self.namespace {
+            namespace.pop();
+        }
     }
 
     fn get_root(&mut self) -> &mut Self::Root {
@@ -202,6 +577,17 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for ProvingAssignment<Scalar>
         true
     }
 
+    fn new_shard(&self) -> Self {
+        let mut shard = Self::new();
+        shard.track_density = self.track_density;
+        shard.namespace = self.namespace.clone();
+        shard.shape = self.shape.as_ref().map(|_| ShapeCS::new());
+        shard
+            .alloc_input(|| "", || Ok(Scalar::ONE))
+            .expect("allocating the one input cannot fail");
+        shard
+    }
+
     fn extend(&mut self, other: &Self) {
         self.a_aux_density.extend(&other.a_aux_density, false);
         self.b_input_density.extend(&other.b_input_density, true);
@@ -215,6 +601,35 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for ProvingAssignment<Scalar>
             // Skip first input, which must have been a temporarily allocated one variable.
             .extend(&other.input_assignment[1..]);
         self.aux_assignment.extend(&other.aux_assignment);
+
+        if let (Some(shape), Some(other)) = (&mut self.shape, &other.shape) {
+            shape.append(other);
+        }
+    }
+}
+
+impl<Scalar: PrimeField> LookupConstraintSystem<Scalar> for ProvingAssignment<Scalar> {}
+
+/// The secrets of a batch of proofs that are shared between the stages of the prover. They are
+/// wiped when dropped, see [`wipe`].
+struct Secrets<Scalar: PrimeField> {
+    input_assignments: Vec<Arc<Vec<Scalar::Repr>>>,
+    aux_assignments: Vec<Arc<Vec<Scalar::Repr>>>,
+    /// The coefficients of the H polynomials.
+    h_coeffs: Vec<Arc<Vec<Scalar::Repr>>>,
+    r_s: Vec<Scalar>,
+    s_s: Vec<Scalar>,
+}
+
+impl<Scalar: PrimeField> Drop for Secrets<Scalar> {
+    fn drop(&mut self) {
+        self.input_assignments
+            .iter_mut()
+            .chain(self.aux_assignments.iter_mut())
+            .chain(self.h_coeffs.iter_mut())
+            .for_each(wipe::wipe_shared_reprs);
+        wipe::wipe_scalars(&mut self.r_s);
+        wipe::wipe_scalars(&mut self.s_s);
     }
 }
 
@@ -242,6 +657,190 @@ where
     create_proof_batch_priority::<E, C, P>(circuits, params, r_s, s_s, priority)
 }
 
+/// Creates a proof whose blinding scalars `r` and `s` are derived from a keyed hash of the
+/// witness and `nonce` instead of being drawn from an RNG, so that proving the same witness with
+/// the same nonce always results in the same proof.
+///
+/// The proof only hides the witness as long as the nonce is secret. Proofs for the same witness
+/// and nonce are identical, hence linkable.
+pub fn create_proof_deterministic<E, C, P: ParameterSource<E>>(
+    circuit: C,
+    params: P,
+    nonce: &[u8],
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: MultiMillerLoop,
+    C: Circuit<E::Fr> + Send,
+    E::Fr: GpuName,
+    E::G1Affine: GpuName,
+    E::G2Affine: GpuName,
+{
+    let proofs = create_proof_batch_deterministic(vec![circuit], params, nonce, false)?;
+    Ok(proofs.into_iter().next().unwrap())
+}
+
+/// creates a batch of proofs where the randomization of every proof is derived from its witness
+/// and `nonce`, see [`create_proof_deterministic`]
+pub fn create_proof_batch_deterministic<E, C, P: ParameterSource<E>>(
+    circuits: Vec<C>,
+    params: P,
+    nonce: &[u8],
+    priority: bool,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: MultiMillerLoop,
+    C: Circuit<E::Fr> + Send,
+    E::Fr: GpuName,
+    E::G1Affine: GpuName,
+    E::G2Affine: GpuName,
+{
+    let config = ProverConfig {
+        priority,
+        ..Default::default()
+    };
+    create_proof_batch_priority_inner(
+        circuits,
+        synthesize_circuit,
+        params,
+        Randomization::Derived(nonce),
+        &config,
+    )
+}
+
+/// creates a batch of proofs for circuits of different types, which synthesize to constraint
+/// systems of the same shape, where the randomization vector is already predefined
+pub fn create_proof_batch_dyn<'a, E, P: ParameterSource<E>>(
+    circuits: Vec<Box<dyn DynCircuit<E::Fr> + 'a>>,
+    params: P,
+    r_s: Vec<E::Fr>,
+    s_s: Vec<E::Fr>,
+    priority: bool,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: MultiMillerLoop,
+    E::Fr: GpuName,
+    E::G1Affine: GpuName,
+    E::G2Affine: GpuName,
+{
+    let config = ProverConfig {
+        priority,
+        ..Default::default()
+    };
+    create_proof_batch_priority_inner(
+        circuits,
+        synthesize_dyn_circuit,
+        params,
+        Randomization::Given(r_s, s_s),
+        &config,
+    )
+}
+
+/// creates a batch of proofs for circuits of different types, see [`create_proof_batch_dyn`]
+pub fn create_random_proof_batch_dyn<'a, E, R, P: ParameterSource<E>>(
+    circuits: Vec<Box<dyn DynCircuit<E::Fr> + 'a>>,
+    params: P,
+    rng: &mut R,
+    priority: bool,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: MultiMillerLoop,
+    R: RngCore,
+    E::Fr: GpuName,
+    E::G1Affine: GpuName,
+    E::G2Affine: GpuName,
+{
+    let r_s = (0..circuits.len())
+        .map(|_| E::Fr::random(&mut *rng))
+        .collect();
+    let s_s = (0..circuits.len())
+        .map(|_| E::Fr::random(&mut *rng))
+        .collect();
+
+    create_proof_batch_dyn(circuits, params, r_s, s_s, priority)
+}
+
+/// creates a batch of proofs from precomputed assignments, see
+/// [`ProvingAssignment::synthesize`] and [`ProvingAssignment::from_assignments`], only the FFT
+/// and multiexp stages of the prover run
+pub fn create_proof_batch_from_assignments<E, P: ParameterSource<E>>(
+    assignments: Vec<ProvingAssignment<E::Fr>>,
+    params: P,
+    r_s: Vec<E::Fr>,
+    s_s: Vec<E::Fr>,
+    priority: bool,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: MultiMillerLoop,
+    E::Fr: GpuName,
+    E::G1Affine: GpuName,
+    E::G2Affine: GpuName,
+{
+    let config = ProverConfig {
+        priority,
+        ..Default::default()
+    };
+    create_proof_batch_priority_inner(
+        assignments,
+        replay_assignment,
+        params,
+        Randomization::Given(r_s, s_s),
+        &config,
+    )
+}
+
+/// creates a batch of proofs for instances of a circuit, whose constraints were captured once with
+/// [`R1cs::from_circuit`], where the randomization vector is already predefined. Only the
+/// witnesses of the circuits are synthesized, see [`R1cs::synthesize_witness`], which saves
+/// building the linear combinations of every constraint for every proof
+pub fn create_proof_batch_from_witnesses<E, C, P: ParameterSource<E>>(
+    r1cs: &R1cs<E::Fr>,
+    circuits: Vec<C>,
+    params: P,
+    r_s: Vec<E::Fr>,
+    s_s: Vec<E::Fr>,
+    priority: bool,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: MultiMillerLoop,
+    C: Circuit<E::Fr> + Send,
+    E::Fr: GpuName,
+    E::G1Affine: GpuName,
+    E::G2Affine: GpuName,
+{
+    let config = ProverConfig {
+        priority,
+        ..Default::default()
+    };
+    create_proof_batch_priority_inner(
+        circuits
+            .into_iter()
+            .map(|circuit| (r1cs, circuit))
+            .collect(),
+        synthesize_witness,
+        params,
+        Randomization::Given(r_s, s_s),
+        &config,
+    )
+}
+
+/// creates a proof from a precomputed assignment, see [`create_proof_batch_from_assignments`]
+pub fn create_proof_from_assignments<E, P: ParameterSource<E>>(
+    assignment: ProvingAssignment<E::Fr>,
+    params: P,
+    r: E::Fr,
+    s: E::Fr,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: MultiMillerLoop,
+    E::Fr: GpuName,
+    E::G1Affine: GpuName,
+    E::G2Affine: GpuName,
+{
+    let proofs =
+        create_proof_batch_from_assignments(vec![assignment], params, vec![r], vec![s], false)?;
+    Ok(proofs.into_iter().next().unwrap())
+}
+
 /// creates a batch of proofs where the randomization vector is set to zero.
 /// This allows for optimization of proving.
 pub fn create_proof_batch_priority_nonzk<E, C, P: ParameterSource<E>>(
@@ -256,7 +855,17 @@ where
     E::G1Affine: GpuName,
     E::G2Affine: GpuName,
 {
-    create_proof_batch_priority_inner(circuits, params, None, priority)
+    let config = ProverConfig {
+        priority,
+        ..Default::default()
+    };
+    create_proof_batch_priority_inner(
+        circuits,
+        synthesize_circuit,
+        params,
+        Randomization::Zero,
+        &config,
+    )
 }
 
 /// creates a batch of proofs where the randomization vector is already
@@ -276,7 +885,73 @@ where
     E::G1Affine: GpuName,
     E::G2Affine: GpuName,
 {
-    create_proof_batch_priority_inner(circuits, params, Some((r_s, s_s)), priority)
+    let config = ProverConfig {
+        priority,
+        ..Default::default()
+    };
+    create_proof_batch_priority_inner(
+        circuits,
+        synthesize_circuit,
+        params,
+        Randomization::Given(r_s, s_s),
+        &config,
+    )
+}
+
+/// creates a batch of proofs where the randomization vector is already
+/// predefined, scheduling the prover stages according to `schedule`
+pub fn create_proof_batch_priority_with_schedule<E, C, P: ParameterSource<E>>(
+    circuits: Vec<C>,
+    params: P,
+    r_s: Vec<E::Fr>,
+    s_s: Vec<E::Fr>,
+    priority: bool,
+    schedule: MemorySchedule,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: MultiMillerLoop,
+    C: Circuit<E::Fr> + Send,
+    E::Fr: GpuName,
+    E::G1Affine: GpuName,
+    E::G2Affine: GpuName,
+{
+    let config = ProverConfig {
+        priority,
+        schedule,
+        ..Default::default()
+    };
+    create_proof_batch_priority_inner(
+        circuits,
+        synthesize_circuit,
+        params,
+        Randomization::Given(r_s, s_s),
+        &config,
+    )
+}
+
+/// creates a batch of proofs where the randomization vector is already
+/// predefined, with the priority, the schedule and the GPU frameworks taken from `config`
+pub fn create_proof_batch_with_config<E, C, P: ParameterSource<E>>(
+    circuits: Vec<C>,
+    params: P,
+    r_s: Vec<E::Fr>,
+    s_s: Vec<E::Fr>,
+    config: &ProverConfig,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: MultiMillerLoop,
+    C: Circuit<E::Fr> + Send,
+    E::Fr: GpuName,
+    E::G1Affine: GpuName,
+    E::G2Affine: GpuName,
+{
+    create_proof_batch_priority_inner(
+        circuits,
+        synthesize_circuit,
+        params,
+        Randomization::Given(r_s, s_s),
+        config,
+    )
 }
 
 #[allow(clippy::drop_non_drop)]
@@ -284,37 +959,94 @@ where
 #[allow(clippy::needless_collect)]
 fn create_proof_batch_priority_inner<E, C, P: ParameterSource<E>>(
     circuits: Vec<C>,
+    synthesize: Synthesize<C, E::Fr>,
     params: P,
-    randomization: Option<(Vec<E::Fr>, Vec<E::Fr>)>,
-    priority: bool,
+    randomization: Randomization<'_, E::Fr>,
+    config: &ProverConfig,
 ) -> Result<Vec<Proof<E>>, SynthesisError>
 where
     E: MultiMillerLoop,
-    C: Circuit<E::Fr> + Send,
+    C: Send,
     E::Fr: GpuName,
     E::G1Affine: GpuName,
     E::G2Affine: GpuName,
 {
     info!("Bellperson {} is being used!", BELLMAN_VERSION);
+    let priority = config.priority;
+
+    let num_circuits = circuits.len();
+    let zk = !matches!(randomization, Randomization::Zero);
+    let (r_s, s_s, nonce) = match randomization {
+        Randomization::Zero => (
+            vec![E::Fr::ZERO; num_circuits],
+            vec![E::Fr::ZERO; num_circuits],
+            None,
+        ),
+        Randomization::Given(r_s, s_s) => (r_s, s_s, None),
+        Randomization::Derived(nonce) => (Vec::new(), Vec::new(), Some(nonce)),
+    };
+    let mut secrets = Secrets {
+        input_assignments: Vec::new(),
+        aux_assignments: Vec::new(),
+        h_coeffs: Vec::with_capacity(num_circuits),
+        r_s,
+        s_s,
+    };
+
+    let report = |stage, elements, duration| {
+        if let Some(progress) = &config.progress {
+            progress.report(stage, num_circuits, elements, duration);
+        }
+    };
 
-    let (start, mut provers, input_assignments, aux_assignments) =
-        synthesize_circuits_batch(circuits)?;
+    let densities = params.densities();
+    let root_exponent = params.root_of_unity_exponent();
+    let fingerprint = params.fingerprint().filter(|_| config.check_fingerprint);
+    let synthesis_start = Instant::now();
+    let (start, mut provers, input_assignments, aux_assignments) = synthesize_circuits_batch(
+        circuits,
+        synthesize,
+        densities.is_none(),
+        config.namespace_paths,
+        fingerprint.is_some(),
+    )?;
+    if let Some(fingerprint) = fingerprint {
+        for prover in provers.iter_mut() {
+            let shape = prover.shape.take().expect("the shape is recorded");
+            if shape.fingerprint() != fingerprint {
+                return Err(SynthesisError::FingerprintMismatch);
+            }
+        }
+    }
+    secrets.input_assignments = input_assignments;
+    secrets.aux_assignments = aux_assignments;
+    if let Some(nonce) = nonce {
+        (secrets.r_s, secrets.s_s) =
+            derive_randomization(nonce, &secrets.input_assignments, &secrets.aux_assignments);
+    }
+
+    if let Some(densities) = densities {
+        densities.check(
+            secrets.input_assignments[0].len(),
+            secrets.aux_assignments[0].len(),
+        )?;
+        for prover in provers.iter_mut() {
+            let (a_aux_density, b_input_density, b_aux_density) = densities.trackers();
+            prover.a_aux_density = a_aux_density;
+            prover.b_input_density = b_input_density;
+            prover.b_aux_density = b_aux_density;
+        }
+    }
 
     let worker = Worker::new();
-    let input_len = input_assignments[0].len();
+    let input_len = secrets.input_assignments[0].len();
     let vk = params.get_vk(input_len)?.clone();
     let n = provers[0].a.len();
     let a_aux_density_total = provers[0].a_aux_density.get_total_density();
     let b_input_density_total = provers[0].b_input_density.get_total_density();
     let b_aux_density_total = provers[0].b_aux_density.get_total_density();
-    let aux_assignment_len = provers[0].aux_assignment.len();
-    let num_circuits = provers.len();
-
-    let zk = randomization.is_some();
-    let (r_s, s_s) = randomization.unwrap_or((
-        vec![E::Fr::ZERO; num_circuits],
-        vec![E::Fr::ZERO; num_circuits],
-    ));
+    let aux_assignment_len = secrets.aux_assignments[0].len();
+    report(ProverStage::Synthesis, n, synthesis_start.elapsed());
 
     // Make sure all circuits have the same input len.
     for prover in &provers {
@@ -340,6 +1072,19 @@ where
         );
     }
 
+    if n <= small_circuit_threshold() {
+        debug!("small circuit with {} constraints, proving on the CPU", n);
+        let assembly_start = Instant::now();
+        let proofs = create_proof_batch_small(provers, &secrets, &params, zk)?;
+        report(
+            ProverStage::Assembly,
+            num_circuits,
+            assembly_start.elapsed(),
+        );
+        info!("prover time: {:?}", start.elapsed());
+        return Ok(proofs);
+    }
+
     #[cfg(any(feature = "cuda", feature = "opencl"))]
     let prio_lock = if priority {
         trace!("acquiring priority lock");
@@ -348,85 +1093,174 @@ where
         None
     };
 
-    let mut a_s = Vec::with_capacity(num_circuits);
-    let mut params_h = None;
+    let low_memory = config.schedule == MemorySchedule::LowPeakMemory;
     let worker = &worker;
-    let provers_ref = &mut provers;
     let params = &params;
 
-    THREAD_POOL.scoped(|s| -> Result<(), SynthesisError> {
-        let params_h = &mut params_h;
-        s.execute(move || {
-            debug!("get h");
-            *params_h = Some(params.get_h(n));
-        });
-
-        let mut fft_kern = Some(LockedFftKernel::new(priority));
-        for prover in provers_ref {
-            a_s.push(execute_fft(worker, prover, &mut fft_kern)?);
-        }
-        Ok(())
-    })?;
-
-    let mut multiexp_g1_kern = LockedMultiexpKernel::<E::G1Affine>::new(priority);
-    let params_h = params_h.unwrap()?;
-
     let mut h_s = Vec::with_capacity(num_circuits);
     let mut params_l = None;
-
-    THREAD_POOL.scoped(|s| {
-        let params_l = &mut params_l;
-        s.execute(move || {
-            debug!("get l");
-            *params_l = Some(params.get_l(aux_assignment_len));
-        });
-
-        debug!("multiexp h");
-        for a in a_s.into_iter() {
-            h_s.push(multiexp(
-                worker,
-                params_h.clone(),
-                FullDensity,
-                a,
-                &mut multiexp_g1_kern,
+    let mut multiexp_g1_kern;
+
+    if low_memory {
+        debug!("get h");
+        let params_h = params.get_h(n)?;
+
+        debug!("fft and multiexp h");
+        let mut fft_duration = Duration::ZERO;
+        let mut h_duration = Duration::ZERO;
+        for prover in provers.iter_mut() {
+            let fft_start = Instant::now();
+            // The FFT and the multiexp kernel can't be held at the same time, hence they are
+            // re-created for every circuit. This way the FFT result is released right after its
+            // multiexp.
+            let mut fft_kern = Some(LockedFftKernel::with_frameworks(
+                priority,
+                config.frameworks.clone(),
+            ));
+            let a = execute_fft(worker, prover, &mut fft_kern, root_exponent)?;
+            drop(fft_kern);
+            secrets.h_coeffs.push(a.clone());
+            fft_duration += fft_start.elapsed();
+
+            let h_start = Instant::now();
+            let mut multiexp_kern = LockedMultiexpKernel::<E::G1Affine>::with_frameworks(
+                priority,
+                config.frameworks.clone(),
+            );
+            h_s.push(settle(
+                multiexp(worker, params_h.clone(), FullDensity, a, &mut multiexp_kern),
+                low_memory,
             ));
+            h_duration += h_start.elapsed();
         }
-    });
+        drop(params_h);
+        report(ProverStage::WitnessFft, n, fft_duration);
+        report(ProverStage::HMultiexp, n, h_duration);
 
-    let params_l = params_l.unwrap()?;
-
-    let mut l_s = Vec::with_capacity(num_circuits);
-    let mut params_a = None;
+        multiexp_g1_kern = LockedMultiexpKernel::<E::G1Affine>::with_frameworks(
+            priority,
+            config.frameworks.clone(),
+        );
+        debug!("get l");
+        params_l = Some(params.get_l(aux_assignment_len));
+    } else {
+        let mut params_h = None;
+        let provers_ref = &mut provers;
+        let h_coeffs = &mut secrets.h_coeffs;
+
+        let fft_start = Instant::now();
+        THREAD_POOL.scoped(|s| -> Result<(), SynthesisError> {
+            let params_h = &mut params_h;
+            s.execute(move || {
+                debug!("get h");
+                *params_h = Some(params.get_h(n));
+            });
+
+            let mut fft_kern = Some(LockedFftKernel::with_frameworks(
+                priority,
+                config.frameworks.clone(),
+            ));
+            for prover in provers_ref {
+                h_coeffs.push(execute_fft(worker, prover, &mut fft_kern, root_exponent)?);
+            }
+            Ok(())
+        })?;
+        report(ProverStage::WitnessFft, n, fft_start.elapsed());
+
+        multiexp_g1_kern = LockedMultiexpKernel::<E::G1Affine>::with_frameworks(
+            priority,
+            config.frameworks.clone(),
+        );
+        let params_h = params_h.unwrap()?;
+
+        let h_start = Instant::now();
+        THREAD_POOL.scoped(|s| {
+            let params_l = &mut params_l;
+            s.execute(move || {
+                debug!("get l");
+                *params_l = Some(params.get_l(aux_assignment_len));
+            });
+
+            debug!("multiexp h");
+            for a in secrets.h_coeffs.iter() {
+                h_s.push(multiexp(
+                    worker,
+                    params_h.clone(),
+                    FullDensity,
+                    a.clone(),
+                    &mut multiexp_g1_kern,
+                ));
+            }
+        });
+        report(ProverStage::HMultiexp, n, h_start.elapsed());
+    }
+
+    let params_l = params_l.unwrap()?;
+
+    let mut l_s = Vec::with_capacity(num_circuits);
+    let mut params_a = None;
     let mut params_b_g1 = None;
     let mut params_b_g2 = None;
     let a_aux_density_total = provers[0].a_aux_density.get_total_density();
     let b_input_density_total = provers[0].b_input_density.get_total_density();
     let b_aux_density_total = provers[0].b_aux_density.get_total_density();
 
-    THREAD_POOL.scoped(|s| {
-        let params_a = &mut params_a;
-        let params_b_g1 = &mut params_b_g1;
-        let params_b_g2 = &mut params_b_g2;
-        s.execute(move || {
-            debug!("get_a b_g1 b_g2");
-            *params_a = Some(params.get_a(input_len, a_aux_density_total));
-            if zk {
-                *params_b_g1 = Some(params.get_b_g1(b_input_density_total, b_aux_density_total));
-            }
-            *params_b_g2 = Some(params.get_b_g2(b_input_density_total, b_aux_density_total));
-        });
-
+    let l_start = Instant::now();
+    if low_memory {
         debug!("multiexp l");
-        for aux in aux_assignments.iter() {
-            l_s.push(multiexp(
-                worker,
-                params_l.clone(),
-                FullDensity,
-                aux.clone(),
-                &mut multiexp_g1_kern,
+        for aux in secrets.aux_assignments.iter() {
+            l_s.push(settle(
+                multiexp(
+                    worker,
+                    params_l.clone(),
+                    FullDensity,
+                    aux.clone(),
+                    &mut multiexp_g1_kern,
+                ),
+                low_memory,
             ));
         }
-    });
+        drop(params_l);
+
+        // The B G2 query is only loaded after all G1 multiexps are done.
+        debug!("get_a b_g1");
+        params_a = Some(params.get_a(input_len, a_aux_density_total));
+        if zk {
+            params_b_g1 = Some(params.get_b_g1(b_input_density_total, b_aux_density_total));
+        }
+    } else {
+        THREAD_POOL.scoped(|s| {
+            let params_a = &mut params_a;
+            let params_b_g1 = &mut params_b_g1;
+            let params_b_g2 = &mut params_b_g2;
+            s.execute(move || {
+                debug!("get_a b_g1 b_g2");
+                *params_a = Some(params.get_a(input_len, a_aux_density_total));
+                if zk {
+                    *params_b_g1 =
+                        Some(params.get_b_g1(b_input_density_total, b_aux_density_total));
+                }
+                *params_b_g2 = Some(params.get_b_g2(b_input_density_total, b_aux_density_total));
+            });
+
+            debug!("multiexp l");
+            for aux in secrets.aux_assignments.iter() {
+                l_s.push(multiexp(
+                    worker,
+                    params_l.clone(),
+                    FullDensity,
+                    aux.clone(),
+                    &mut multiexp_g1_kern,
+                ));
+            }
+        });
+        drop(params_l);
+    }
+    report(
+        ProverStage::LMultiexp,
+        aux_assignment_len,
+        l_start.elapsed(),
+    );
 
     debug!("get a b_g1");
     let (a_inputs_source, a_aux_source) = params_a.unwrap()?;
@@ -448,9 +1282,11 @@ where
     drop(provers);
 
     debug!("multiexp a b_g1");
-    let inputs_g1 = input_assignments
+    let ab_g1_start = Instant::now();
+    let inputs_g1 = secrets
+        .input_assignments
         .iter()
-        .zip(aux_assignments.iter())
+        .zip(secrets.aux_assignments.iter())
         .zip(densities.iter())
         .map(
             |(
@@ -500,105 +1336,112 @@ where
         )
         .collect::<Vec<_>>();
     drop(multiexp_g1_kern);
+    report(
+        ProverStage::AbG1Multiexp,
+        input_len + aux_assignment_len,
+        ab_g1_start.elapsed(),
+    );
     drop(a_inputs_source);
     drop(a_aux_source);
     drop(params_b_g1_opt);
 
     // The multiexp kernel for G1 can only be initiated after the kernel for G1 was dropped. Else
     // it would block, trying to acquire the GPU lock.
-    let mut multiexp_g2_kern = LockedMultiexpKernel::<E::G2Affine>::new(priority);
+    let mut multiexp_g2_kern =
+        LockedMultiexpKernel::<E::G2Affine>::with_frameworks(priority, config.frameworks.clone());
 
     debug!("get b_g2");
-    let (b_g2_inputs_source, b_g2_aux_source) = params_b_g2.unwrap()?;
+    let b_g2_start = Instant::now();
+    let (b_g2_inputs_source, b_g2_aux_source) = match params_b_g2 {
+        Some(params_b_g2) => params_b_g2?,
+        None => params.get_b_g2(b_input_density_total, b_aux_density_total)?,
+    };
 
     debug!("multiexp b_g2");
-    let inputs_g2 = input_assignments
+    let inputs_g2 = secrets
+        .input_assignments
         .iter()
-        .zip(aux_assignments.iter())
-        .zip(densities.iter())
+        .zip(secrets.aux_assignments.iter())
+        .zip(densities)
         .map(
             |((input_assignment, aux_assignment), (_, b_input_density, b_aux_density))| {
                 let b_g2_inputs = multiexp(
                     worker,
                     b_g2_inputs_source.clone(),
-                    b_input_density.clone(),
+                    b_input_density,
                     input_assignment.clone(),
                     &mut multiexp_g2_kern,
                 );
                 let b_g2_aux = multiexp(
                     worker,
                     b_g2_aux_source.clone(),
-                    b_aux_density.clone(),
+                    b_aux_density,
                     aux_assignment.clone(),
                     &mut multiexp_g2_kern,
                 );
 
-                (b_g2_inputs, b_g2_aux)
+                (
+                    settle(b_g2_inputs, low_memory),
+                    settle(b_g2_aux, low_memory),
+                )
             },
         )
         .collect::<Vec<_>>();
     drop(multiexp_g2_kern);
-    drop(densities);
     drop(b_g2_inputs_source);
     drop(b_g2_aux_source);
+    report(
+        ProverStage::BG2Multiexp,
+        b_input_density_total + b_aux_density_total,
+        b_g2_start.elapsed(),
+    );
 
     debug!("proofs");
+    let assembly_start = Instant::now();
     let proofs = h_s
         .into_iter()
         .zip(l_s.into_iter())
         .zip(inputs_g1.into_iter())
         .zip(inputs_g2.into_iter())
-        .zip(r_s.into_iter())
-        .zip(s_s.into_iter())
+        .zip(secrets.r_s.iter())
+        .zip(secrets.s_s.iter())
         .map(
             |(
                 ((((h, l), (a_inputs, a_aux, b_g1_inputs_aux_opt)), (b_g2_inputs, b_g2_aux)), r),
                 s,
             )| {
-                if (vk.delta_g1.is_identity() | vk.delta_g2.is_identity()).into() {
-                    // If this element is zero, someone is trying to perform a
-                    // subversion-CRS attack.
-                    return Err(SynthesisError::UnexpectedIdentity);
-                }
-
-                let mut g_a = vk.delta_g1.mul(r);
-                g_a.add_assign(&vk.alpha_g1);
-                let mut g_b = vk.delta_g2.mul(s);
-                g_b.add_assign(&vk.beta_g2);
                 let mut a_answer = a_inputs.wait()?;
                 a_answer.add_assign(&a_aux.wait()?);
-                g_a.add_assign(&a_answer);
-                a_answer.mul_assign(s);
-                let mut g_c = a_answer;
 
                 let mut b2_answer = b_g2_inputs.wait()?;
                 b2_answer.add_assign(&b_g2_aux.wait()?);
 
-                g_b.add_assign(&b2_answer);
-
-                if let Some((b_g1_inputs, b_g1_aux)) = b_g1_inputs_aux_opt {
-                    let mut b1_answer = b_g1_inputs.wait()?;
-                    b1_answer.add_assign(&b_g1_aux.wait()?);
-                    b1_answer.mul_assign(r);
-                    g_c.add_assign(&b1_answer);
-                    let mut rs = r;
-                    rs.mul_assign(&s);
-                    g_c.add_assign(vk.delta_g1.mul(rs));
-                    g_c.add_assign(&vk.alpha_g1.mul(s));
-                    g_c.add_assign(&vk.beta_g1.mul(r));
-                }
-
-                g_c.add_assign(&h.wait()?);
-                g_c.add_assign(&l.wait()?);
-
-                Ok(Proof {
-                    a: g_a.to_affine(),
-                    b: g_b.to_affine(),
-                    c: g_c.to_affine(),
-                })
+                let b1_answer = b_g1_inputs_aux_opt
+                    .map(|(b_g1_inputs, b_g1_aux)| -> Result<_, SynthesisError> {
+                        let mut b1_answer = b_g1_inputs.wait()?;
+                        b1_answer.add_assign(&b_g1_aux.wait()?);
+                        Ok(b1_answer)
+                    })
+                    .transpose()?;
+
+                assemble_proof(
+                    &vk,
+                    *r,
+                    *s,
+                    a_answer,
+                    b2_answer,
+                    b1_answer,
+                    h.wait()?,
+                    l.wait()?,
+                )
             },
         )
         .collect::<Result<Vec<_>, SynthesisError>>()?;
+    report(
+        ProverStage::Assembly,
+        num_circuits,
+        assembly_start.elapsed(),
+    );
 
     #[cfg(any(feature = "cuda", feature = "opencl"))]
     {
@@ -612,17 +1455,179 @@ where
     Ok(proofs)
 }
 
+/// Waits for the result of a multiexp right away if `low_memory` is set, so that its inputs are
+/// released before the next multiexp is started.
+fn settle<T>(waiter: Waiter<T>, low_memory: bool) -> Waiter<T> {
+    if low_memory {
+        Waiter::done(waiter.wait())
+    } else {
+        waiter
+    }
+}
+
+/// Combines the results of the multiexps of a single circuit into a proof. `b1_answer` is only
+/// set when the proof is randomized.
+#[allow(clippy::too_many_arguments)]
+fn assemble_proof<E>(
+    vk: &VerifyingKey<E>,
+    r: E::Fr,
+    s: E::Fr,
+    mut a_answer: E::G1,
+    b2_answer: E::G2,
+    b1_answer: Option<E::G1>,
+    h: E::G1,
+    l: E::G1,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: MultiMillerLoop,
+{
+    if (vk.delta_g1.is_identity() | vk.delta_g2.is_identity()).into() {
+        // If this element is zero, someone is trying to perform a
+        // subversion-CRS attack.
+        return Err(SynthesisError::UnexpectedIdentity);
+    }
+
+    let mut g_a = vk.delta_g1.mul(r);
+    g_a.add_assign(&vk.alpha_g1);
+    let mut g_b = vk.delta_g2.mul(s);
+    g_b.add_assign(&vk.beta_g2);
+    g_a.add_assign(&a_answer);
+    a_answer.mul_assign(s);
+    let mut g_c = a_answer;
+
+    g_b.add_assign(&b2_answer);
+
+    if let Some(mut b1_answer) = b1_answer {
+        b1_answer.mul_assign(r);
+        g_c.add_assign(&b1_answer);
+        let mut rs = r;
+        rs.mul_assign(&s);
+        g_c.add_assign(vk.delta_g1.mul(rs));
+        g_c.add_assign(&vk.alpha_g1.mul(s));
+        g_c.add_assign(&vk.beta_g1.mul(r));
+    }
+
+    g_c.add_assign(&h);
+    g_c.add_assign(&l);
+
+    Ok(Proof {
+        a: g_a.to_affine(),
+        b: g_b.to_affine(),
+        c: g_c.to_affine(),
+    })
+}
+
+/// Proves a batch of small circuits entirely on the CPU. The circuits are proven in parallel,
+/// while the FFTs and multiexps of each circuit run serially on the thread proving it. No GPU is
+/// used and no GPU lock is acquired.
+#[allow(clippy::type_complexity)]
+fn create_proof_batch_small<E, P>(
+    provers: Vec<ProvingAssignment<E::Fr>>,
+    secrets: &Secrets<E::Fr>,
+    params: &P,
+    zk: bool,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: MultiMillerLoop,
+    E::Fr: GpuName,
+    P: ParameterSource<E>,
+{
+    let input_len = secrets.input_assignments[0].len();
+    let n = provers[0].a.len();
+    let aux_assignment_len = secrets.aux_assignments[0].len();
+    let a_aux_density_total = provers[0].a_aux_density.get_total_density();
+    let b_input_density_total = provers[0].b_input_density.get_total_density();
+    let b_aux_density_total = provers[0].b_aux_density.get_total_density();
+    let root_exponent = params.root_of_unity_exponent();
+
+    let vk = params.get_vk(input_len)?;
+    let params_h = params.get_h(n)?;
+    let params_l = params.get_l(aux_assignment_len)?;
+    let (a_inputs_source, a_aux_source) = params.get_a(input_len, a_aux_density_total)?;
+    let params_b_g1 = if zk {
+        Some(params.get_b_g1(b_input_density_total, b_aux_density_total)?)
+    } else {
+        None
+    };
+    let (b_g2_inputs_source, b_g2_aux_source) =
+        params.get_b_g2(b_input_density_total, b_aux_density_total)?;
+
+    provers
+        .into_par_iter()
+        .zip(secrets.input_assignments.par_iter())
+        .zip(secrets.aux_assignments.par_iter())
+        .zip(secrets.r_s.par_iter())
+        .zip(secrets.s_s.par_iter())
+        .map(
+            |((((mut prover, input_assignment), aux_assignment), r), s)| {
+                let mut a = execute_fft_serial(&mut prover, root_exponent)?;
+                let h = multiexp_serial(params_h.clone(), FullDensity, a.clone());
+                wipe::wipe_shared_reprs(&mut a);
+                let h = h?;
+                let l = multiexp_serial(params_l.clone(), FullDensity, aux_assignment.clone())?;
+
+                let a_aux_density = Arc::new(std::mem::take(&mut prover.a_aux_density));
+                let b_input_density = Arc::new(std::mem::take(&mut prover.b_input_density));
+                let b_aux_density = Arc::new(std::mem::take(&mut prover.b_aux_density));
+
+                let mut a_answer = multiexp_serial(
+                    a_inputs_source.clone(),
+                    FullDensity,
+                    input_assignment.clone(),
+                )?;
+                a_answer.add_assign(&multiexp_serial(
+                    a_aux_source.clone(),
+                    a_aux_density,
+                    aux_assignment.clone(),
+                )?);
+
+                let b1_answer = params_b_g1
+                    .as_ref()
+                    .map(
+                        |(b_g1_inputs_source, b_g1_aux_source)| -> Result<_, SynthesisError> {
+                            let mut b1_answer = multiexp_serial(
+                                b_g1_inputs_source.clone(),
+                                b_input_density.clone(),
+                                input_assignment.clone(),
+                            )?;
+                            b1_answer.add_assign(&multiexp_serial(
+                                b_g1_aux_source.clone(),
+                                b_aux_density.clone(),
+                                aux_assignment.clone(),
+                            )?);
+                            Ok(b1_answer)
+                        },
+                    )
+                    .transpose()?;
+
+                let mut b2_answer = multiexp_serial(
+                    b_g2_inputs_source.clone(),
+                    b_input_density,
+                    input_assignment.clone(),
+                )?;
+                b2_answer.add_assign(&multiexp_serial(
+                    b_g2_aux_source.clone(),
+                    b_aux_density,
+                    aux_assignment.clone(),
+                )?);
+
+                assemble_proof(vk, *r, *s, a_answer, b2_answer, b1_answer, h, l)
+            },
+        )
+        .collect()
+}
+
+/// `root_exponent` is the one of [`ParameterSource::root_of_unity_exponent`].
 fn execute_fft<F>(
     worker: &Worker,
     prover: &mut ProvingAssignment<F>,
     fft_kern: &mut Option<LockedFftKernel<F>>,
+    root_exponent: u64,
 ) -> Result<Arc<Vec<F::Repr>>, SynthesisError>
 where
     F: PrimeField + GpuName,
 {
-    let mut a = EvaluationDomain::from_coeffs(std::mem::take(&mut prover.a))?;
-    let mut b = EvaluationDomain::from_coeffs(std::mem::take(&mut prover.b))?;
-    let mut c = EvaluationDomain::from_coeffs(std::mem::take(&mut prover.c))?;
+    let [mut a, mut b, mut c] = fft_domains(prover, root_exponent)?;
 
     EvaluationDomain::ifft_many(&mut [&mut a, &mut b, &mut c], worker, fft_kern)?;
     EvaluationDomain::coset_fft_many(&mut [&mut a, &mut b, &mut c], worker, fft_kern)?;
@@ -635,19 +1640,92 @@ where
     a.divide_by_z_on_coset(worker);
     a.icoset_fft(worker, fft_kern)?;
 
-    let a = a.into_coeffs();
-    let a_len = a.len() - 1;
-    let a = a
-        .into_par_iter()
-        .take(a_len)
+    // Only the representations are returned, the domain is wiped when it is dropped.
+    let a_len = a.as_ref().len() - 1;
+    let a = a.as_ref()[..a_len]
+        .par_iter()
         .map(|s| s.to_repr())
         .collect::<Vec<_>>();
     Ok(Arc::new(a))
 }
 
+/// [`execute_fft`] on the calling thread and the CPU, for small circuits.
+fn execute_fft_serial<F>(
+    prover: &mut ProvingAssignment<F>,
+    root_exponent: u64,
+) -> Result<Arc<Vec<F::Repr>>, SynthesisError>
+where
+    F: PrimeField + GpuName,
+{
+    let [mut a, mut b, mut c] = fft_domains(prover, root_exponent)?;
+    EvaluationDomain::quotient_serial(&mut a, &mut b, &mut c);
+    drop(b);
+    drop(c);
+
+    let a_len = a.as_ref().len() - 1;
+    let a = a.as_ref()[..a_len]
+        .iter()
+        .map(|s| s.to_repr())
+        .collect::<Vec<_>>();
+    Ok(Arc::new(a))
+}
+
+/// Moves the evaluations of the A, B, C polynomials of the prover into evaluation domains, in
+/// the order of the FFTs.
+fn fft_domains<F>(
+    prover: &mut ProvingAssignment<F>,
+    root_exponent: u64,
+) -> Result<[EvaluationDomain<F>; 3], SynthesisError>
+where
+    F: PrimeField + GpuName,
+{
+    if root_exponent != 1 {
+        reorder_evaluations(&mut prover.a, root_exponent);
+        reorder_evaluations(&mut prover.b, root_exponent);
+        reorder_evaluations(&mut prover.c, root_exponent);
+    }
+    Ok([
+        EvaluationDomain::from_coeffs(std::mem::take(&mut prover.a))?,
+        EvaluationDomain::from_coeffs(std::mem::take(&mut prover.b))?,
+        EvaluationDomain::from_coeffs(std::mem::take(&mut prover.c))?,
+    ])
+}
+
+/// Pads the evaluations of the constraints to the size `n` of the domain and moves the one of
+/// constraint `j` to `k * j mod n`. The parameters evaluate constraint `j` at `(w^k)^j`, the FFTs
+/// at `w^(k * j)`, where `w` is the root of unity of the domain.
+fn reorder_evaluations<F: PrimeField>(values: &mut Vec<F>, k: u64) {
+    let n = values.len().next_power_of_two();
+    values.resize(n, F::ZERO);
+
+    // Follows the cycles of the permutation, every value is moved once.
+    let mut moved = vec![false; n];
+    for start in 0..n {
+        if moved[start] {
+            continue;
+        }
+        let mut value = values[start];
+        let mut j = start;
+        loop {
+            j = ((j as u128 * k as u128) % n as u128) as usize;
+            std::mem::swap(&mut values[j], &mut value);
+            moved[j] = true;
+            if j == start {
+                break;
+            }
+        }
+    }
+}
+
 #[allow(clippy::type_complexity)]
+/// Synthesizes the circuits, the query densities are only tracked if `track_density` is set, the
+/// namespaces only if `namespace_paths` is set.
 fn synthesize_circuits_batch<Scalar, C>(
     circuits: Vec<C>,
+    synthesize: Synthesize<C, Scalar>,
+    track_density: bool,
+    namespace_paths: bool,
+    record_shape: bool,
 ) -> Result<
     (
         Instant,
@@ -659,17 +1737,24 @@ fn synthesize_circuits_batch<Scalar, C>(
 >
 where
     Scalar: PrimeField,
-    C: Circuit<Scalar> + Send,
+    C: Send,
 {
     let start = Instant::now();
     let mut provers = circuits
         .into_par_iter()
         .map(|circuit| -> Result<_, SynthesisError> {
             let mut prover = ProvingAssignment::new();
+            prover.track_density = track_density;
+            if namespace_paths {
+                prover.namespace = Some(vec![]);
+            }
+            if record_shape {
+                prover.shape = Some(ShapeCS::new());
+            }
 
             prover.alloc_input(|| "", || Ok(Scalar::ONE))?;
 
-            circuit.synthesize(&mut prover)?;
+            synthesize(circuit, &mut prover)?;
 
             for i in 0..prover.input_assignment.len() {
                 prover.enforce(|| "", |lc| lc + Variable(Index::Input(i)), |lc| lc, |lc| lc);
@@ -687,33 +1772,29 @@ where
 
     let input_assignments = provers
         .par_iter_mut()
-        .map(|prover| {
-            let input_assignment = std::mem::take(&mut prover.input_assignment);
-            Arc::new(
-                input_assignment
-                    .into_iter()
-                    .map(|s| s.to_repr())
-                    .collect::<Vec<_>>(),
-            )
-        })
+        .map(|prover| assignment_reprs(&mut prover.input_assignment))
         .collect::<Vec<_>>();
 
     let aux_assignments = provers
         .par_iter_mut()
-        .map(|prover| {
-            let aux_assignment = std::mem::take(&mut prover.aux_assignment);
-            Arc::new(
-                aux_assignment
-                    .into_iter()
-                    .map(|s| s.to_repr())
-                    .collect::<Vec<_>>(),
-            )
-        })
+        .map(|prover| assignment_reprs(&mut prover.aux_assignment))
         .collect::<Vec<_>>();
 
     Ok((start, provers, input_assignments, aux_assignments))
 }
 
+/// Converts an assignment into the representation the multiexps consume. The field elements are
+/// wiped afterwards.
+fn assignment_reprs<Scalar: PrimeField>(assignment: &mut Vec<Scalar>) -> Arc<Vec<Scalar::Repr>> {
+    let mut assignment = std::mem::take(assignment);
+    let reprs = assignment
+        .iter()
+        .map(PrimeField::to_repr)
+        .collect::<Vec<_>>();
+    wipe::wipe_scalars(&mut assignment);
+    Arc::new(reprs)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -785,4 +1866,842 @@ mod tests {
             }
         }
     }
+
+    #[derive(Clone)]
+    struct SquareChain {
+        start: Option<Fr>,
+        len: usize,
+    }
+
+    impl Circuit<Fr> for SquareChain {
+        fn synthesize<CS: ConstraintSystem<Fr>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            let mut value = self.start;
+            let mut var = cs.alloc(
+                || "start",
+                || value.ok_or(SynthesisError::AssignmentMissing),
+            )?;
+            for i in 0..self.len {
+                let square = value.map(|v| v.square());
+                let square_var = if i + 1 == self.len {
+                    cs.alloc_input(
+                        || format!("square {}", i),
+                        || square.ok_or(SynthesisError::AssignmentMissing),
+                    )?
+                } else {
+                    cs.alloc(
+                        || format!("square {}", i),
+                        || square.ok_or(SynthesisError::AssignmentMissing),
+                    )?
+                };
+                cs.enforce(
+                    || format!("square {} constraint", i),
+                    |lc| lc + var,
+                    |lc| lc + var,
+                    |lc| lc + square_var,
+                );
+                value = square;
+                var = square_var;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_small_circuit_fast_path() {
+        use crate::groth16::{generate_random_parameters, prepare_verifying_key, verify_proof};
+        use blstrs::Bls12;
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let params = generate_random_parameters::<Bls12, _, _>(
+            SquareChain {
+                start: None,
+                len: 20,
+            },
+            &mut rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let start = Fr::random(&mut rng);
+        let circuits = vec![
+            SquareChain {
+                start: Some(start),
+                len: 20,
+            };
+            3
+        ];
+        let r_s = (0..3).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let s_s = (0..3).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+
+        let fast =
+            create_proof_batch_priority(circuits.clone(), &params, r_s.clone(), s_s.clone(), false)
+                .unwrap();
+        let regular = temp_env::with_var("BELLMAN_SMALL_CIRCUIT_THRESHOLD", Some("0"), || {
+            create_proof_batch_priority(circuits.clone(), &params, r_s, s_s, false).unwrap()
+        });
+        assert_eq!(fast, regular);
+
+        let fast_nonzk =
+            create_proof_batch_priority_nonzk(circuits.clone(), &params, false).unwrap();
+        let regular_nonzk =
+            temp_env::with_var("BELLMAN_SMALL_CIRCUIT_THRESHOLD", Some("0"), || {
+                create_proof_batch_priority_nonzk(circuits, &params, false).unwrap()
+            });
+        assert_eq!(fast_nonzk, regular_nonzk);
+
+        let mut output = start;
+        for _ in 0..20 {
+            output = output.square();
+        }
+        for proof in fast.iter().chain(fast_nonzk.iter()) {
+            assert!(verify_proof(&pvk, proof, &[output]).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_interleaved_inputs() {
+        use crate::groth16::{generate_random_parameters, prepare_verifying_key, verify_proof};
+        use blstrs::Bls12;
+
+        // Allocates inputs before, between and after auxiliary variables.
+        #[derive(Clone)]
+        struct Interleaved {
+            x: Option<Fr>,
+        }
+
+        impl Circuit<Fr> for Interleaved {
+            fn synthesize<CS: ConstraintSystem<Fr>>(
+                self,
+                cs: &mut CS,
+            ) -> Result<(), SynthesisError> {
+                let value = |v: Option<Fr>| v.ok_or(SynthesisError::AssignmentMissing);
+                let x = self.x;
+                let x_var = cs.alloc_input(|| "x", || value(x))?;
+                let y = x.map(|x| x.square());
+                let y_var = cs.alloc(|| "y", || value(y))?;
+                cs.enforce(
+                    || "x * x = y",
+                    |lc| lc + x_var,
+                    |lc| lc + x_var,
+                    |lc| lc + y_var,
+                );
+                let z = y.map(|y| y * x.unwrap());
+                let z_var = cs.alloc_input(|| "z", || value(z))?;
+                cs.enforce(
+                    || "y * x = z",
+                    |lc| lc + y_var,
+                    |lc| lc + x_var,
+                    |lc| lc + z_var,
+                );
+                let w = z.map(|z| z.square());
+                let w_var = cs.alloc(|| "w", || value(w))?;
+                cs.enforce(
+                    || "z * z = w",
+                    |lc| lc + z_var,
+                    |lc| lc + z_var,
+                    |lc| lc + w_var,
+                );
+                let v = w.map(|w| w + Fr::ONE);
+                let v_var = cs.alloc_input(|| "v", || value(v))?;
+                cs.enforce(
+                    || "w + 1 = v",
+                    |lc| lc + w_var + CS::one(),
+                    |lc| lc + CS::one(),
+                    |lc| lc + v_var,
+                );
+                Ok(())
+            }
+        }
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(Interleaved { x: None }, &mut rng).unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let x = Fr::random(&mut rng);
+        let circuits = vec![Interleaved { x: Some(x) }];
+        let r_s = vec![Fr::random(&mut rng)];
+        let s_s = vec![Fr::random(&mut rng)];
+        let fast =
+            create_proof_batch_priority(circuits.clone(), &params, r_s.clone(), s_s.clone(), false)
+                .unwrap();
+        let regular = temp_env::with_var("BELLMAN_SMALL_CIRCUIT_THRESHOLD", Some("0"), || {
+            create_proof_batch_priority(circuits, &params, r_s, s_s, false).unwrap()
+        });
+        assert_eq!(fast, regular);
+
+        // The public inputs are in the order they were allocated.
+        let z = x.square() * x;
+        let v = z.square() + Fr::ONE;
+        assert!(verify_proof(&pvk, &fast[0], &[x, z, v]).unwrap());
+        assert!(!verify_proof(&pvk, &fast[0], &[z, x, v]).unwrap());
+    }
+
+    #[test]
+    fn test_loaded_densities() {
+        use crate::groth16::{generate_random_parameters, QueryDensities};
+        use blstrs::Bls12;
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let mut params = generate_random_parameters::<Bls12, _, _>(
+            SquareChain {
+                start: None,
+                len: 20,
+            },
+            &mut rng,
+        )
+        .unwrap();
+        let densities = params.densities.clone().expect("densities are generated");
+
+        let mut serialized = vec![];
+        densities.write(&mut serialized).unwrap();
+        assert_eq!(&QueryDensities::read(&serialized[..]).unwrap(), &*densities);
+
+        // The generated densities are the ones the prover tracks.
+        let circuit = SquareChain {
+            start: Some(Fr::random(&mut rng)),
+            len: 20,
+        };
+        let (_, provers, _, _) = synthesize_circuits_batch(
+            vec![circuit.clone()],
+            synthesize_circuit,
+            true,
+            false,
+            false,
+        )
+        .unwrap();
+        let (a_aux_density, b_input_density, b_aux_density) = densities.trackers();
+        assert_eq!(provers[0].a_aux_density, a_aux_density);
+        assert_eq!(provers[0].b_input_density, b_input_density);
+        assert_eq!(provers[0].b_aux_density, b_aux_density);
+
+        let (_, provers, _, _) = synthesize_circuits_batch(
+            vec![circuit.clone()],
+            synthesize_circuit,
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(provers[0].a_aux_density.get_total_density(), 0);
+
+        let r_s = vec![Fr::random(&mut rng)];
+        let s_s = vec![Fr::random(&mut rng)];
+        let loaded = create_proof_batch_priority(
+            vec![circuit.clone()],
+            &params,
+            r_s.clone(),
+            s_s.clone(),
+            false,
+        )
+        .unwrap();
+        params.densities = None;
+        let tracked = create_proof_batch_priority(
+            vec![circuit.clone()],
+            &params,
+            r_s.clone(),
+            s_s.clone(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(loaded, tracked);
+
+        // Densities of a different circuit are rejected.
+        params.densities = Some(Arc::new(QueryDensities::new(
+            densities.num_inputs(),
+            densities.num_aux() + 1,
+            [false; 0],
+            [false; 0],
+            [false; 0],
+        )));
+        assert!(create_proof_batch_priority(vec![circuit], &params, r_s, s_s, false).is_err());
+    }
+
+    #[test]
+    fn test_memory_schedules() {
+        use crate::groth16::generate_random_parameters;
+        use blstrs::Bls12;
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let params = generate_random_parameters::<Bls12, _, _>(
+            SquareChain {
+                start: None,
+                len: 20,
+            },
+            &mut rng,
+        )
+        .unwrap();
+
+        let circuits = (0..3)
+            .map(|_| SquareChain {
+                start: Some(Fr::random(&mut rng)),
+                len: 20,
+            })
+            .collect::<Vec<_>>();
+        let r_s = (0..3).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let s_s = (0..3).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+
+        // Make sure the regular prover is used and not the small circuit fast path.
+        temp_env::with_var("BELLMAN_SMALL_CIRCUIT_THRESHOLD", Some("0"), || {
+            let overlap = create_proof_batch_priority_with_schedule(
+                circuits.clone(),
+                &params,
+                r_s.clone(),
+                s_s.clone(),
+                false,
+                MemorySchedule::MaximumOverlap,
+            )
+            .unwrap();
+            let low_memory = create_proof_batch_priority_with_schedule(
+                circuits,
+                &params,
+                r_s,
+                s_s,
+                false,
+                MemorySchedule::LowPeakMemory,
+            )
+            .unwrap();
+            assert_eq!(overlap, low_memory);
+        });
+    }
+
+    #[test]
+    fn test_progress_callback() {
+        use crate::groth16::generate_random_parameters;
+        use blstrs::Bls12;
+        use std::sync::Mutex;
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let params = generate_random_parameters::<Bls12, _, _>(
+            SquareChain {
+                start: None,
+                len: 20,
+            },
+            &mut rng,
+        )
+        .unwrap();
+
+        let circuits = (0..2)
+            .map(|_| SquareChain {
+                start: Some(Fr::random(&mut rng)),
+                len: 20,
+            })
+            .collect::<Vec<_>>();
+        let r_s = (0..2).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let s_s = (0..2).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+
+        for schedule in [
+            MemorySchedule::MaximumOverlap,
+            MemorySchedule::LowPeakMemory,
+        ] {
+            let reports = Arc::new(Mutex::new(Vec::new()));
+            let reports_ref = reports.clone();
+            let config = ProverConfig {
+                schedule,
+                progress: Some(ProgressCallback::new(move |progress| {
+                    reports_ref.lock().unwrap().push(*progress)
+                })),
+                ..Default::default()
+            };
+
+            // Make sure the regular prover is used and not the small circuit fast path.
+            temp_env::with_var("BELLMAN_SMALL_CIRCUIT_THRESHOLD", Some("0"), || {
+                create_proof_batch_with_config(
+                    circuits.clone(),
+                    &params,
+                    r_s.clone(),
+                    s_s.clone(),
+                    &config,
+                )
+                .unwrap()
+            });
+
+            let reports = reports.lock().unwrap();
+            let stages = reports
+                .iter()
+                .map(|report| report.stage)
+                .collect::<Vec<_>>();
+            assert_eq!(
+                stages,
+                [
+                    ProverStage::Synthesis,
+                    ProverStage::WitnessFft,
+                    ProverStage::HMultiexp,
+                    ProverStage::LMultiexp,
+                    ProverStage::AbG1Multiexp,
+                    ProverStage::BG2Multiexp,
+                    ProverStage::Assembly,
+                ]
+            );
+            assert!(reports.iter().all(|report| report.num_proofs == 2));
+            // 20 constraints and one input constraint for each of the two inputs.
+            assert_eq!(reports[0].elements, 22);
+            assert_eq!(reports[3].elements, 20);
+        }
+    }
+
+    #[test]
+    fn test_deterministic_proofs() {
+        use crate::groth16::{generate_random_parameters, prepare_verifying_key, verify_proof};
+        use blstrs::Bls12;
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let params = generate_random_parameters::<Bls12, _, _>(
+            SquareChain {
+                start: None,
+                len: 20,
+            },
+            &mut rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let start = Fr::random(&mut rng);
+        let circuit = SquareChain {
+            start: Some(start),
+            len: 20,
+        };
+        let mut output = start;
+        for _ in 0..20 {
+            output = output.square();
+        }
+
+        let proof = create_proof_deterministic(circuit.clone(), &params, b"nonce").unwrap();
+        assert!(verify_proof(&pvk, &proof, &[output]).unwrap());
+        let again = create_proof_deterministic(circuit.clone(), &params, b"nonce").unwrap();
+        assert_eq!(proof, again);
+
+        let other = create_proof_deterministic(circuit.clone(), &params, b"other nonce").unwrap();
+        assert!(verify_proof(&pvk, &other, &[output]).unwrap());
+        assert_ne!(proof, other);
+
+        // The randomization of every proof of a batch only depends on its own witness.
+        let other_circuit = SquareChain {
+            start: Some(Fr::random(&mut rng)),
+            len: 20,
+        };
+        let batch = create_proof_batch_deterministic(
+            vec![other_circuit, circuit],
+            &params,
+            b"nonce",
+            false,
+        )
+        .unwrap();
+        assert_eq!(batch[1], proof);
+        assert_ne!(batch[0], proof);
+    }
+
+    /// Same shape as [`SquareChain`], but a different type.
+    struct Squares(SquareChain);
+
+    impl Circuit<Fr> for Squares {
+        fn synthesize<CS: ConstraintSystem<Fr>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            self.0.synthesize(&mut cs.namespace(|| "squares"))
+        }
+    }
+
+    #[test]
+    fn test_heterogeneous_batch() {
+        use crate::groth16::{generate_random_parameters, prepare_verifying_key, verify_proof};
+        use blstrs::Bls12;
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let params = generate_random_parameters::<Bls12, _, _>(
+            SquareChain {
+                start: None,
+                len: 20,
+            },
+            &mut rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let circuits = (0..3)
+            .map(|_| SquareChain {
+                start: Some(Fr::random(&mut rng)),
+                len: 20,
+            })
+            .collect::<Vec<_>>();
+        let r_s = (0..3).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let s_s = (0..3).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+
+        let dyn_circuits: Vec<Box<dyn DynCircuit<Fr>>> = vec![
+            Box::new(circuits[0].clone()),
+            Box::new(Squares(circuits[1].clone())),
+            Box::new(circuits[2].clone()),
+        ];
+        let proofs =
+            create_proof_batch_dyn(dyn_circuits, &params, r_s.clone(), s_s.clone(), false).unwrap();
+        let expected =
+            create_proof_batch_priority(circuits.clone(), &params, r_s, s_s, false).unwrap();
+        assert_eq!(proofs, expected);
+
+        for (proof, circuit) in proofs.iter().zip(circuits.iter()) {
+            let mut output = circuit.start.unwrap();
+            for _ in 0..20 {
+                output = output.square();
+            }
+            assert!(verify_proof(&pvk, proof, &[output]).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_proof_from_assignments() {
+        use crate::groth16::generate_random_parameters;
+        use blstrs::Bls12;
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let mut params = generate_random_parameters::<Bls12, _, _>(
+            SquareChain {
+                start: None,
+                len: 20,
+            },
+            &mut rng,
+        )
+        .unwrap();
+        let densities = params.densities.clone().expect("densities are generated");
+
+        let circuit = SquareChain {
+            start: Some(Fr::random(&mut rng)),
+            len: 20,
+        };
+        let r = Fr::random(&mut rng);
+        let s = Fr::random(&mut rng);
+        let expected =
+            create_proof_batch_priority(vec![circuit.clone()], &params, vec![r], vec![s], false)
+                .unwrap()
+                .pop()
+                .unwrap();
+
+        let synthesized = ProvingAssignment::synthesize(circuit).unwrap();
+        assert_eq!(synthesized.num_inputs(), 2);
+        assert_eq!(synthesized.num_aux(), 20);
+        assert_eq!(synthesized.num_constraints(), 20);
+
+        let inputs = synthesized.input_assignment[1..].to_vec();
+        let aux = synthesized.aux_assignment.clone();
+        let from_parts = |densities: Option<&QueryDensities>| {
+            ProvingAssignment::from_assignments(
+                synthesized.a.clone(),
+                synthesized.b.clone(),
+                synthesized.c.clone(),
+                &inputs,
+                &aux,
+                densities,
+            )
+            .unwrap()
+        };
+        let without_densities = from_parts(None);
+        let with_densities = from_parts(Some(&densities));
+        let missing_densities = from_parts(None);
+
+        let proof = create_proof_from_assignments(synthesized, &params, r, s).unwrap();
+        assert_eq!(proof, expected);
+        let proof = create_proof_from_assignments(without_densities, &params, r, s).unwrap();
+        assert_eq!(proof, expected);
+
+        // Without densities in the parameters, the assignment has to provide them.
+        params.densities = None;
+        let proof = create_proof_from_assignments(with_densities, &params, r, s).unwrap();
+        assert_eq!(proof, expected);
+        assert!(create_proof_from_assignments(missing_densities, &params, r, s).is_err());
+    }
+
+    #[test]
+    fn test_proof_batch_from_witnesses() {
+        use crate::groth16::generate_random_parameters;
+        use blstrs::Bls12;
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let shape = SquareChain {
+            start: None,
+            len: 20,
+        };
+        let mut params =
+            generate_random_parameters::<Bls12, _, _>(shape.clone(), &mut rng).unwrap();
+        let r1cs = R1cs::from_circuit(shape).unwrap();
+
+        let circuits = (0..3)
+            .map(|_| SquareChain {
+                start: Some(Fr::random(&mut rng)),
+                len: 20,
+            })
+            .collect::<Vec<_>>();
+        let r_s = (0..3).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let s_s = (0..3).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let expected =
+            create_proof_batch_priority(circuits.clone(), &params, r_s.clone(), s_s.clone(), false)
+                .unwrap();
+
+        let proofs = create_proof_batch_from_witnesses(
+            &r1cs,
+            circuits.clone(),
+            &params,
+            r_s.clone(),
+            s_s.clone(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(proofs, expected);
+
+        // The densities come from the constraints when the parameters don't provide them.
+        params.densities = None;
+        let proofs =
+            create_proof_batch_from_witnesses(&r1cs, circuits, &params, r_s, s_s, false).unwrap();
+        assert_eq!(proofs, expected);
+
+        // A circuit of a different shape doesn't match the constraints.
+        let other = SquareChain {
+            start: Some(Fr::random(&mut rng)),
+            len: 19,
+        };
+        assert!(r1cs.synthesize_witness(other).is_err());
+    }
+
+    /// Independent square chains, synthesized in parallel or sequentially.
+    #[derive(Clone)]
+    struct Branches {
+        chains: Vec<SquareChain>,
+        parallel: bool,
+    }
+
+    impl Circuit<Fr> for Branches {
+        fn synthesize<CS: ConstraintSystem<Fr>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            if self.parallel {
+                return crate::synthesize_parallel(cs, self.chains);
+            }
+            for (i, chain) in self.chains.into_iter().enumerate() {
+                chain.synthesize(&mut cs.namespace(|| format!("sub-circuit {}", i)))?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_synthesize_parallel() {
+        use crate::groth16::{generate_random_parameters, prepare_verifying_key, verify_proof};
+        use blstrs::Bls12;
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let shape = Branches {
+            chains: vec![
+                SquareChain {
+                    start: None,
+                    len: 10,
+                };
+                4
+            ],
+            parallel: true,
+        };
+        let mut params =
+            generate_random_parameters::<Bls12, _, _>(shape.clone(), &mut rng).unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+        let r1cs = R1cs::from_circuit(shape).unwrap();
+
+        let starts = (0..4).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let branches = |parallel| Branches {
+            chains: starts
+                .iter()
+                .map(|start| SquareChain {
+                    start: Some(*start),
+                    len: 10,
+                })
+                .collect(),
+            parallel,
+        };
+        let r = Fr::random(&mut rng);
+        let s = Fr::random(&mut rng);
+        let expected =
+            create_proof_batch_priority(vec![branches(false)], &params, vec![r], vec![s], false)
+                .unwrap();
+
+        let proofs =
+            create_proof_batch_priority(vec![branches(true)], &params, vec![r], vec![s], false)
+                .unwrap();
+        assert_eq!(proofs, expected);
+        let proofs = create_proof_batch_from_witnesses(
+            &r1cs,
+            vec![branches(true)],
+            &params,
+            vec![r],
+            vec![s],
+            false,
+        )
+        .unwrap();
+        assert_eq!(proofs, expected);
+
+        // The densities of the shards are merged as well.
+        params.densities = None;
+        let proofs =
+            create_proof_batch_priority(vec![branches(true)], &params, vec![r], vec![s], false)
+                .unwrap();
+        assert_eq!(proofs, expected);
+
+        let outputs = starts
+            .iter()
+            .map(|start| (0..10).fold(*start, |output, _| output.square()))
+            .collect::<Vec<_>>();
+        assert!(verify_proof(&pvk, &proofs[0], &outputs).unwrap());
+    }
+
+    #[test]
+    fn test_check_fingerprint() {
+        use crate::groth16::{circuit_fingerprint, generate_random_parameters, Parameters};
+        use blstrs::Bls12;
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let branches = |lens: &[usize], start: Option<Fr>, parallel| Branches {
+            chains: lens.iter().map(|&len| SquareChain { start, len }).collect(),
+            parallel,
+        };
+        let shape = branches(&[10, 10, 10, 10], None, true);
+        let mut params =
+            generate_random_parameters::<Bls12, _, _>(shape.clone(), &mut rng).unwrap();
+        assert_eq!(
+            params.fingerprint,
+            Some(circuit_fingerprint(shape).unwrap())
+        );
+
+        let config = ProverConfig {
+            check_fingerprint: true,
+            ..Default::default()
+        };
+        let start = Some(Fr::random(&mut rng));
+        let prove = |params: &Parameters<Bls12>, circuit| {
+            create_proof_batch_with_config(
+                vec![circuit],
+                params,
+                vec![Fr::ONE],
+                vec![Fr::ONE],
+                &config,
+            )
+        };
+
+        // Shards that are synthesized in parallel are joined into the same shape.
+        for parallel in [false, true] {
+            assert!(prove(&params, branches(&[10, 10, 10, 10], start, parallel)).is_ok());
+        }
+
+        // The circuit has the same number of variables and constraints, but they differ.
+        let drifted = branches(&[9, 11, 10, 10], start, true);
+        assert!(matches!(
+            prove(&params, drifted.clone()),
+            Err(SynthesisError::FingerprintMismatch)
+        ));
+
+        // Precomputed assignments have no constraints to check.
+        let assignment = ProvingAssignment::synthesize(branches(&[10, 10, 10, 10], start, true));
+        assert!(matches!(
+            create_proof_batch_priority_inner(
+                vec![assignment.unwrap()],
+                replay_assignment,
+                &params,
+                Randomization::Given(vec![Fr::ONE], vec![Fr::ONE]),
+                &config,
+            ),
+            Err(SynthesisError::UncheckedFingerprint)
+        ));
+
+        // Parameters without fingerprint can't be checked.
+        params.fingerprint = None;
+        assert!(prove(&params, drifted).is_ok());
+    }
+
+    #[test]
+    fn test_namespace_paths() {
+        use crate::groth16::generate_random_parameters;
+        use crate::util_cs::test_cs::TestConstraintSystem;
+        use blstrs::Bls12;
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        let chain = SquareChain {
+            start: None,
+            len: 5,
+        };
+        let branches = Branches {
+            chains: vec![chain; 2],
+            parallel: false,
+        };
+        let params = generate_random_parameters::<Bls12, _, _>(branches.clone(), &mut rng).unwrap();
+        let r_s = vec![Fr::random(&mut rng)];
+        let s_s = vec![Fr::random(&mut rng)];
+
+        let err = create_proof_batch_priority(
+            vec![branches.clone()],
+            &params,
+            r_s.clone(),
+            s_s.clone(),
+            false,
+        )
+        .unwrap_err();
+        assert!(matches!(err, SynthesisError::AssignmentMissing));
+
+        let config = ProverConfig {
+            namespace_paths: true,
+            ..Default::default()
+        };
+        let err =
+            create_proof_batch_with_config(vec![branches.clone()], &params, r_s, s_s, &config)
+                .unwrap_err();
+        assert_eq!(err.path(), Some("sub-circuit 0/start"));
+        assert!(matches!(err.unlocated(), SynthesisError::AssignmentMissing));
+        assert_eq!(
+            err.to_string(),
+            "sub-circuit 0/start: an assignment for a variable could not be computed"
+        );
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let err = branches
+            .synthesize(&mut cs.namespace(|| "outer"))
+            .unwrap_err();
+        assert_eq!(err.path(), Some("outer/sub-circuit 0/start"));
+    }
 }
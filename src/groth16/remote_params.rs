@@ -0,0 +1,80 @@
+//! Access to remote parameter files, which back a [`ShardSet`](super::ShardSet) that fetches
+//! its shards on demand.
+
+use std::io;
+#[cfg(feature = "remote-params")]
+use std::io::Read;
+use std::ops::Range;
+
+/// Fetches byte ranges of a remote parameter file, i.e. of the byte stream written by
+/// [`Parameters::write`](super::Parameters::write).
+///
+/// The fetched bytes are verified by the caller, implementations don't need to.
+pub trait RangeFetcher: Send + Sync {
+    /// Returns the bytes in the given range of the remote file.
+    fn fetch(&self, range: Range<u64>) -> io::Result<Vec<u8>>;
+}
+
+/// Fetches byte ranges of a parameter file served over HTTP(S), using range requests.
+#[cfg(feature = "remote-params")]
+pub struct HttpRangeFetcher {
+    agent: ureq::Agent,
+    url: String,
+}
+
+#[cfg(feature = "remote-params")]
+impl HttpRangeFetcher {
+    pub fn new(url: impl Into<String>) -> Self {
+        HttpRangeFetcher {
+            agent: ureq::Agent::new(),
+            url: url.into(),
+        }
+    }
+
+    /// Fetches a whole (small) file, e.g. the manifest of the parameter file.
+    ///
+    /// Note that the manifest is what the parameters are verified against, it should therefore
+    /// only be fetched from a trusted source.
+    pub fn get(&self, url: &str) -> io::Result<Vec<u8>> {
+        let response = self.agent.get(url).call().map_err(to_io_error)?;
+        let mut bytes = Vec::new();
+        response.into_reader().read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+#[cfg(feature = "remote-params")]
+impl RangeFetcher for HttpRangeFetcher {
+    fn fetch(&self, range: Range<u64>) -> io::Result<Vec<u8>> {
+        if range.start >= range.end {
+            return Ok(Vec::new());
+        }
+
+        let response = self
+            .agent
+            .get(&self.url)
+            .set("Range", &format!("bytes={}-{}", range.start, range.end - 1))
+            .call()
+            .map_err(to_io_error)?;
+        // A server that ignores the range would send the whole file.
+        if response.status() != 206 {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("{} does not support range requests", self.url),
+            ));
+        }
+
+        let len = range.end - range.start;
+        let mut bytes = Vec::with_capacity(len as usize);
+        response.into_reader().take(len).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(feature = "remote-params")]
+fn to_io_error(err: ureq::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err.to_string())
+}
@@ -0,0 +1,230 @@
+//! Serde support for types with a byte encoding. Human readable formats like JSON get the bytes
+//! as a hex string, binary formats as a byte string. Both accept a sequence of bytes as well.
+//!
+//! Also the hex and base64 encodings of the `to_hex` and `to_base64` methods.
+
+use std::fmt;
+use std::io;
+
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserializer, Serializer};
+
+pub(crate) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&to_hex(bytes))
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+/// Deserializes the bytes and decodes them with `read`, which has to consume all of them.
+pub(crate) fn deserialize<'de, D, T, F>(
+    deserializer: D,
+    expecting: &'static str,
+    read: F,
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    F: FnOnce(&mut &[u8]) -> io::Result<T>,
+{
+    struct BytesVisitor<F> {
+        expecting: &'static str,
+        read: F,
+    }
+
+    impl<'de, T, F> Visitor<'de> for BytesVisitor<F>
+    where
+        F: FnOnce(&mut &[u8]) -> io::Result<T>,
+    {
+        type Value = T;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.expecting)
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            let bytes = from_hex(v).ok_or_else(|| E::custom("invalid hex string"))?;
+            self.visit_bytes(&bytes)
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+            let mut source = v;
+            let value = (self.read)(&mut source).map_err(E::custom)?;
+            if !source.is_empty() {
+                return Err(E::invalid_length(v.len(), &self.expecting));
+            }
+            Ok(value)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(byte) = seq.next_element()? {
+                bytes.push(byte);
+            }
+            self.visit_bytes(&bytes)
+        }
+    }
+
+    let visitor = BytesVisitor { expecting, read };
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(visitor)
+    } else {
+        deserializer.deserialize_bytes(visitor)
+    }
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        hex.push(DIGITS[(byte >> 4) as usize] as char);
+        hex.push(DIGITS[(byte & 0xf) as usize] as char);
+    }
+    hex
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.strip_prefix("0x").unwrap_or(hex).as_bytes();
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    let digit = |c: u8| (c as char).to_digit(16).map(|d| d as u8);
+    hex.chunks(2)
+        .map(|pair| Some((digit(pair[0])? << 4) | digit(pair[1])?))
+        .collect()
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `bytes` in the standard base64 alphabet of RFC 4648, with padding.
+pub(crate) fn to_base64(bytes: &[u8]) -> String {
+    let mut base64 = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let mut group = [0; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let bits = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                base64.push(BASE64_ALPHABET[((bits >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                base64.push('=');
+            }
+        }
+    }
+    base64
+}
+
+/// Decodes the encoding of [`to_base64`]. Unpadded input and bits after the last byte are
+/// rejected, so every byte string has a single encoding.
+fn from_base64(base64: &str) -> Option<Vec<u8>> {
+    let base64 = base64.as_bytes();
+    if base64.len() % 4 != 0 {
+        return None;
+    }
+    let padding = base64.iter().rev().take_while(|&&c| c == b'=').count();
+    if padding > 2 {
+        return None;
+    }
+    let digit = |c: u8| {
+        BASE64_ALPHABET
+            .iter()
+            .position(|&d| d == c)
+            .map(|d| d as u32)
+    };
+
+    let mut bytes = Vec::with_capacity(base64.len() / 4 * 3);
+    for (i, chunk) in base64.chunks(4).enumerate() {
+        let len = if (i + 1) * 4 == base64.len() {
+            3 - padding
+        } else {
+            3
+        };
+        let mut bits = 0;
+        for (j, &c) in chunk.iter().enumerate() {
+            let value = if j <= len { digit(c)? } else { 0 };
+            bits = (bits << 6) | value;
+        }
+        let group = bits.to_be_bytes();
+        if group[1 + len..].iter().any(|&byte| byte != 0) {
+            return None;
+        }
+        bytes.extend_from_slice(&group[1..1 + len]);
+    }
+    Some(bytes)
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Decodes the hex string, with or without a `0x` prefix, with `read`, which has to consume all
+/// of the bytes.
+pub(crate) fn read_hex<T, F>(hex: &str, read: F) -> io::Result<T>
+where
+    F: FnOnce(&mut &[u8]) -> io::Result<T>,
+{
+    let bytes = from_hex(hex).ok_or_else(|| invalid_data("invalid hex string"))?;
+    read_all(&bytes, read)
+}
+
+/// Decodes the base64 string with `read`, which has to consume all of the bytes.
+pub(crate) fn read_base64<T, F>(base64: &str, read: F) -> io::Result<T>
+where
+    F: FnOnce(&mut &[u8]) -> io::Result<T>,
+{
+    let bytes = from_base64(base64).ok_or_else(|| invalid_data("invalid base64 string"))?;
+    read_all(&bytes, read)
+}
+
+fn read_all<T, F>(mut bytes: &[u8], read: F) -> io::Result<T>
+where
+    F: FnOnce(&mut &[u8]) -> io::Result<T>,
+{
+    let value = read(&mut bytes)?;
+    if !bytes.is_empty() {
+        return Err(invalid_data("trailing bytes"));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex() {
+        let bytes = [0x00, 0x01, 0xab, 0xff];
+        assert_eq!(to_hex(&bytes), "0001abff");
+        assert_eq!(from_hex("0001abff").unwrap(), bytes);
+        assert_eq!(from_hex("0x0001ABFF").unwrap(), bytes);
+        assert!(from_hex("0001a").is_none());
+        assert!(from_hex("0001ag").is_none());
+    }
+
+    #[test]
+    fn test_base64() {
+        // The test vectors of RFC 4648.
+        for (bytes, base64) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"fooba", "Zm9vYmE="),
+            (b"foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(to_base64(bytes), base64);
+            assert_eq!(from_base64(base64).unwrap(), bytes);
+        }
+        assert_eq!(to_base64(&[0xfb, 0xff]), "+/8=");
+        assert_eq!(from_base64("+/8=").unwrap(), [0xfb, 0xff]);
+
+        // Unpadded, misplaced padding, invalid characters and non-zero trailing bits.
+        assert!(from_base64("Zg").is_none());
+        assert!(from_base64("Z===").is_none());
+        assert!(from_base64("Zg==Zm9v").is_none());
+        assert!(from_base64("Zm9-").is_none());
+        assert!(from_base64("Zh==").is_none());
+    }
+}
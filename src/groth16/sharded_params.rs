@@ -0,0 +1,696 @@
+//! Parameters split into fixed-size shard files described by a manifest.
+//!
+//! The shards are the byte stream produced by [`Parameters::write`] cut into pieces of
+//! `shard_size` bytes, so concatenating them yields a regular parameter file. Next to the shards a
+//! manifest records the SHA-256 hash of every shard and the byte ranges of the individual
+//! sections. This allows distributing huge parameter sets through CDNs and fetching only the
+//! shards that are needed.
+//!
+//! [`ShardedParameters`] opens such a set and loads shards lazily, the first time a section that
+//! touches them is requested. Shards are loaded concurrently and verified against their hash.
+//!
+//! A shard set can also be backed by a remote parameter file, see [`ShardSet::open_remote`]. Then
+//! the shards that are missing locally are fetched from it, verified and cached in the directory
+//! of the shard set, so that proving can start before the whole file was downloaded.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use group::{prime::PrimeCurveAffine, UncompressedEncoding};
+use memmap2::{Mmap, MmapOptions};
+use pairing::MultiMillerLoop;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{ParameterSource, Parameters, PreparedVerifyingKey, RangeFetcher, VerifyingKey};
+use crate::multicore::prelude::*;
+use crate::SynthesisError;
+
+/// Byte ranges of the parameter sections within the concatenated shard stream. The ranges of the
+/// point sections exclude their length prefix.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParamSections {
+    pub vk: Range<u64>,
+    pub h: Range<u64>,
+    pub l: Range<u64>,
+    pub a: Range<u64>,
+    pub b_g1: Range<u64>,
+    pub b_g2: Range<u64>,
+}
+
+/// Describes a set of parameter shards.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardManifest {
+    /// The size of every shard, except the last one which may be smaller.
+    pub shard_size: u64,
+    /// The total size of all shards.
+    pub total_len: u64,
+    /// The SHA-256 hash of every shard.
+    pub shard_hashes: Vec<[u8; 32]>,
+    pub sections: ParamSections,
+}
+
+impl ShardManifest {
+    /// The path of the manifest of the shard set `name` within `dir`.
+    pub fn path(dir: &Path, name: &str) -> PathBuf {
+        dir.join(format!("{}.manifest", name))
+    }
+
+    /// The path of the shard with the given `index` of the shard set `name` within `dir`.
+    pub fn shard_path(dir: &Path, name: &str, index: usize) -> PathBuf {
+        dir.join(format!("{}.shard{:05}", name, index))
+    }
+
+    pub fn write<W: Write>(&self, writer: W) -> io::Result<()> {
+        bincode::serialize_into(writer, self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn read<R: Read>(reader: R) -> io::Result<Self> {
+        let manifest: Self = bincode::deserialize_from(reader)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        if manifest.shard_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "shard size must not be zero",
+            ));
+        }
+        let expected_shards = (manifest.total_len + manifest.shard_size - 1) / manifest.shard_size;
+        if expected_shards != manifest.shard_hashes.len() as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "shard count does not match the manifest size",
+            ));
+        }
+        let sections = &manifest.sections;
+        for range in [
+            &sections.vk,
+            &sections.h,
+            &sections.l,
+            &sections.a,
+            &sections.b_g1,
+            &sections.b_g2,
+        ] {
+            if range.start > range.end || range.end > manifest.total_len {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "section out of bounds",
+                ));
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    /// Computes the manifest of a regular parameter file, as if it was written with
+    /// [`Parameters::write_sharded`], without writing any shards.
+    ///
+    /// This is how a parameter file is prepared for [`ShardSet::open_remote`], the file itself
+    /// is served unchanged.
+    pub fn from_parameters<E, R>(reader: R, shard_size: u64) -> io::Result<Self>
+    where
+        E: MultiMillerLoop,
+        R: Read,
+    {
+        if shard_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "shard size must not be zero",
+            ));
+        }
+
+        let mut hasher = ShardHasher {
+            reader,
+            shard_size,
+            position: 0,
+            current: Sha256::new(),
+            shard_hashes: Vec::new(),
+        };
+
+        VerifyingKey::<E>::read(&mut hasher)?;
+        let vk = 0..hasher.position;
+
+        fn skip_points<G: UncompressedEncoding, R: Read>(
+            hasher: &mut ShardHasher<R>,
+        ) -> io::Result<Range<u64>> {
+            let len = hasher.read_u32::<BigEndian>()? as u64;
+            let start = hasher.position;
+            let point_len = std::mem::size_of::<G::Uncompressed>() as u64;
+            let skipped = io::copy(&mut hasher.by_ref().take(len * point_len), &mut io::sink())?;
+            if skipped != len * point_len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            Ok(start..hasher.position)
+        }
+
+        let h = skip_points::<E::G1Affine, _>(&mut hasher)?;
+        let l = skip_points::<E::G1Affine, _>(&mut hasher)?;
+        let a = skip_points::<E::G1Affine, _>(&mut hasher)?;
+        let b_g1 = skip_points::<E::G1Affine, _>(&mut hasher)?;
+        let b_g2 = skip_points::<E::G2Affine, _>(&mut hasher)?;
+        if hasher.position % shard_size != 0 {
+            hasher.shard_hashes.push(hasher.current.finalize().into());
+        }
+
+        Ok(ShardManifest {
+            shard_size,
+            total_len: hasher.position,
+            shard_hashes: hasher.shard_hashes,
+            sections: ParamSections {
+                vk,
+                h,
+                l,
+                a,
+                b_g1,
+                b_g2,
+            },
+        })
+    }
+}
+
+/// A reader that hashes everything it reads in shards, the reading counterpart of
+/// [`ShardWriter`].
+struct ShardHasher<R> {
+    reader: R,
+    shard_size: u64,
+    position: u64,
+    current: Sha256,
+    shard_hashes: Vec<[u8; 32]>,
+}
+
+impl<R: Read> Read for ShardHasher<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.shard_size - self.position % self.shard_size;
+        let len = buf.len().min(remaining as usize);
+        let len = self.reader.read(&mut buf[..len])?;
+        self.current.update(&buf[..len]);
+        self.position += len as u64;
+
+        if len > 0 && self.position % self.shard_size == 0 {
+            let hasher = std::mem::replace(&mut self.current, Sha256::new());
+            self.shard_hashes.push(hasher.finalize().into());
+        }
+        Ok(len)
+    }
+}
+
+/// A writer that spreads its output over shard files and hashes every shard.
+struct ShardWriter<'a> {
+    dir: &'a Path,
+    name: &'a str,
+    shard_size: u64,
+    position: u64,
+    current: Option<(BufWriter<File>, Sha256)>,
+    shard_hashes: Vec<[u8; 32]>,
+}
+
+impl ShardWriter<'_> {
+    fn finish_shard(&mut self) -> io::Result<()> {
+        if let Some((mut file, hasher)) = self.current.take() {
+            file.flush()?;
+            self.shard_hashes.push(hasher.finalize().into());
+        }
+        Ok(())
+    }
+}
+
+impl Write for ShardWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.current.is_none() {
+            let path = ShardManifest::shard_path(self.dir, self.name, self.shard_hashes.len());
+            self.current = Some((BufWriter::new(File::create(path)?), Sha256::new()));
+        }
+
+        let remaining = self.shard_size - self.position % self.shard_size;
+        let len = buf.len().min(remaining as usize);
+        let (file, hasher) = self.current.as_mut().expect("shard is open");
+        file.write_all(&buf[..len])?;
+        hasher.update(&buf[..len]);
+        self.position += len as u64;
+
+        if self.position % self.shard_size == 0 {
+            self.finish_shard()?;
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.current.as_mut() {
+            Some((file, _)) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<E> Parameters<E>
+where
+    E: MultiMillerLoop,
+{
+    /// Writes the parameters as shards of `shard_size` bytes named after `name` into `dir`,
+    /// together with a manifest describing them.
+    pub fn write_sharded(
+        &self,
+        dir: &Path,
+        name: &str,
+        shard_size: u64,
+    ) -> io::Result<ShardManifest> {
+        if shard_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "shard size must not be zero",
+            ));
+        }
+
+        let mut writer = ShardWriter {
+            dir,
+            name,
+            shard_size,
+            position: 0,
+            current: None,
+            shard_hashes: Vec::new(),
+        };
+
+        self.vk.write(&mut writer)?;
+        let vk = 0..writer.position;
+
+        fn write_points<G: UncompressedEncoding>(
+            writer: &mut ShardWriter<'_>,
+            points: &[G],
+        ) -> io::Result<Range<u64>> {
+            writer.write_u32::<BigEndian>(points.len() as u32)?;
+            let start = writer.position;
+            for g in points {
+                writer.write_all(g.to_uncompressed().as_ref())?;
+            }
+            Ok(start..writer.position)
+        }
+
+        let h = write_points(&mut writer, &self.h)?;
+        let l = write_points(&mut writer, &self.l)?;
+        let a = write_points(&mut writer, &self.a)?;
+        let b_g1 = write_points(&mut writer, &self.b_g1)?;
+        let b_g2 = write_points(&mut writer, &self.b_g2)?;
+        writer.finish_shard()?;
+
+        let manifest = ShardManifest {
+            shard_size,
+            total_len: writer.position,
+            shard_hashes: writer.shard_hashes,
+            sections: ParamSections {
+                vk,
+                h,
+                l,
+                a,
+                b_g1,
+                b_g2,
+            },
+        };
+
+        let mut manifest_file = File::create(ShardManifest::path(dir, name))?;
+        manifest.write(&mut manifest_file)?;
+        manifest_file.flush()?;
+
+        Ok(manifest)
+    }
+}
+
+/// A set of shards, loaded lazily as their contents are requested.
+pub struct ShardSet {
+    /// The directory containing the shards.
+    pub dir: PathBuf,
+    /// The name of the shard set.
+    pub name: String,
+    pub manifest: ShardManifest,
+
+    /// The shards that were loaded so far.
+    loaded: Vec<Mutex<Option<Arc<Mmap>>>>,
+    /// The remote parameter file that missing shards are fetched from.
+    remote: Option<Box<dyn RangeFetcher>>,
+}
+
+impl ShardSet {
+    /// Opens the shard set `name` in `dir`. Only the manifest is read.
+    pub fn open(dir: impl AsRef<Path>, name: &str) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let manifest = ShardManifest::read(File::open(ShardManifest::path(&dir, name))?)?;
+        let loaded = (0..manifest.shard_hashes.len())
+            .map(|_| Mutex::new(None))
+            .collect();
+
+        Ok(ShardSet {
+            dir,
+            name: name.to_string(),
+            manifest,
+            loaded,
+            remote: None,
+        })
+    }
+
+    /// Opens the shard set `name` in `dir`, which is backed by the remote parameter file that
+    /// `manifest` describes. Shards that are not in `dir` yet are fetched from `remote` once
+    /// they are needed and cached in `dir`.
+    ///
+    /// The manifest is what the fetched bytes are verified against, hence it must come from a
+    /// trusted source. It is written to `dir`, so that the cached shards can also be opened
+    /// with [`ShardSet::open`].
+    pub fn open_remote<F>(
+        dir: impl AsRef<Path>,
+        name: &str,
+        manifest: ShardManifest,
+        remote: F,
+    ) -> io::Result<Self>
+    where
+        F: RangeFetcher + 'static,
+    {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let mut manifest_file = File::create(ShardManifest::path(&dir, name))?;
+        manifest.write(&mut manifest_file)?;
+        manifest_file.flush()?;
+
+        let loaded = (0..manifest.shard_hashes.len())
+            .map(|_| Mutex::new(None))
+            .collect();
+
+        Ok(ShardSet {
+            dir,
+            name: name.to_string(),
+            manifest,
+            loaded,
+            remote: Some(Box::new(remote)),
+        })
+    }
+
+    /// The length of the shard with the given index.
+    fn shard_len(&self, index: usize) -> u64 {
+        if index + 1 == self.manifest.shard_hashes.len() {
+            self.manifest.total_len - index as u64 * self.manifest.shard_size
+        } else {
+            self.manifest.shard_size
+        }
+    }
+
+    /// Fetches the shard with the given index from the remote parameter file, verifies it and
+    /// stores it at `path`.
+    fn fetch(&self, remote: &dyn RangeFetcher, index: usize, path: &Path) -> io::Result<()> {
+        let start = index as u64 * self.manifest.shard_size;
+        let bytes = remote.fetch(start..start + self.shard_len(index))?;
+        if bytes.len() as u64 != self.shard_len(index) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("fetched shard {} has an invalid size", index),
+            ));
+        }
+        let hash: [u8; 32] = Sha256::digest(&bytes).into();
+        if hash != self.manifest.shard_hashes[index] {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("fetched shard {} does not match its hash", index),
+            ));
+        }
+
+        // Write to a temporary file first, so that an interrupted download never leaves a
+        // partial shard behind.
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".part");
+        fs::write(&partial, &bytes)?;
+        fs::rename(&partial, path)
+    }
+
+    /// Returns the shard with the given index, loading and verifying it if necessary.
+    fn shard(&self, index: usize) -> io::Result<Arc<Mmap>> {
+        let mut slot = self.loaded[index].lock().expect("shard lock poisoned");
+        if let Some(shard) = slot.as_ref() {
+            return Ok(shard.clone());
+        }
+
+        let path = ShardManifest::shard_path(&self.dir, &self.name, index);
+        let file = match (File::open(&path), &self.remote) {
+            (Err(err), Some(remote)) if err.kind() == io::ErrorKind::NotFound => {
+                self.fetch(remote.as_ref(), index, &path)?;
+                File::open(&path)?
+            }
+            (file, _) => file?,
+        };
+        if file.metadata()?.len() != self.shard_len(index) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("shard {} has an invalid size", path.display()),
+            ));
+        }
+
+        let mmap = unsafe { MmapOptions::new().map(&file)? };
+        let hash: [u8; 32] = Sha256::digest(&mmap[..]).into();
+        if hash != self.manifest.shard_hashes[index] {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("shard {} does not match its hash", path.display()),
+            ));
+        }
+
+        let mmap = Arc::new(mmap);
+        *slot = Some(mmap.clone());
+        Ok(mmap)
+    }
+
+    fn shard_indices(&self, range: &Range<u64>) -> Range<usize> {
+        if range.start >= range.end {
+            return 0..0;
+        }
+        let first = range.start / self.manifest.shard_size;
+        let last = (range.end - 1) / self.manifest.shard_size;
+        first as usize..last as usize + 1
+    }
+
+    /// Returns whether the shard with the given index is loaded.
+    pub fn is_loaded(&self, index: usize) -> bool {
+        self.loaded[index]
+            .lock()
+            .expect("shard lock poisoned")
+            .is_some()
+    }
+
+    /// Loads all shards overlapping the given byte range concurrently.
+    pub fn prefetch(&self, range: Range<u64>) -> io::Result<()> {
+        self.shard_indices(&range)
+            .into_par_iter()
+            .try_for_each(|index| self.shard(index).map(|_| ()))
+    }
+
+    /// Loads every shard concurrently.
+    pub fn prefetch_all(&self) -> io::Result<()> {
+        self.prefetch(0..self.manifest.total_len)
+    }
+
+    /// Returns the bytes in the given range of the concatenated shard stream.
+    pub fn read_range(&self, range: Range<u64>) -> io::Result<Vec<u8>> {
+        if range.start > range.end || range.end > self.manifest.total_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "range out of bounds",
+            ));
+        }
+        self.prefetch(range.clone())?;
+
+        let mut bytes = Vec::with_capacity((range.end - range.start) as usize);
+        for index in self.shard_indices(&range) {
+            let shard = self.shard(index)?;
+            let shard_start = index as u64 * self.manifest.shard_size;
+            let start = range.start.max(shard_start) - shard_start;
+            let end = range.end.min(shard_start + shard.len() as u64) - shard_start;
+            bytes.extend_from_slice(&shard[start as usize..end as usize]);
+        }
+        Ok(bytes)
+    }
+
+    /// Removes all shard files and the manifest of the shard set `name` in `dir`.
+    pub fn remove(dir: impl AsRef<Path>, name: &str) -> io::Result<()> {
+        let dir = dir.as_ref();
+        let manifest_path = ShardManifest::path(dir, name);
+        let manifest = ShardManifest::read(File::open(&manifest_path)?)?;
+        for index in 0..manifest.shard_hashes.len() {
+            fs::remove_file(ShardManifest::shard_path(dir, name, index))?;
+        }
+        fs::remove_file(manifest_path)
+    }
+}
+
+/// Parameters backed by a [`ShardSet`]. Each section is read from the shards when the prover
+/// requests it.
+pub struct ShardedParameters<E>
+where
+    E: MultiMillerLoop,
+{
+    pub shards: ShardSet,
+
+    /// This is always loaded (i.e. not lazily loaded).
+    pub vk: VerifyingKey<E>,
+    pub pvk: PreparedVerifyingKey<E>,
+
+    pub checked: bool,
+}
+
+impl<E> ShardedParameters<E>
+where
+    E: MultiMillerLoop,
+{
+    /// Opens the shard set `name` in `dir`. Only the manifest and the shards containing the
+    /// verifying key are loaded.
+    pub fn open(dir: impl AsRef<Path>, name: &str, checked: bool) -> io::Result<Self> {
+        let shards = ShardSet::open(dir, name)?;
+        Self::from_shards(shards, checked)
+    }
+
+    /// Opens the shard set `name` in `dir`, backed by a remote parameter file, see
+    /// [`ShardSet::open_remote`]. Only the shards containing the verifying key are fetched.
+    pub fn open_remote<F>(
+        dir: impl AsRef<Path>,
+        name: &str,
+        manifest: ShardManifest,
+        remote: F,
+        checked: bool,
+    ) -> io::Result<Self>
+    where
+        F: RangeFetcher + 'static,
+    {
+        let shards = ShardSet::open_remote(dir, name, manifest, remote)?;
+        Self::from_shards(shards, checked)
+    }
+
+    fn from_shards(shards: ShardSet, checked: bool) -> io::Result<Self> {
+        let vk_bytes = shards.read_range(shards.manifest.sections.vk.clone())?;
+        let vk = VerifyingKey::<E>::read(&vk_bytes[..])?;
+        let pvk = super::prepare_verifying_key(&vk);
+
+        Ok(ShardedParameters {
+            shards,
+            vk,
+            pvk,
+            checked,
+        })
+    }
+
+    fn read_points<G>(&self, range: Range<u64>) -> io::Result<Vec<G>>
+    where
+        G: PrimeCurveAffine + UncompressedEncoding,
+    {
+        let bytes = self.shards.read_range(range)?;
+        let point_len = std::mem::size_of::<G::Uncompressed>();
+        if bytes.len() % point_len != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "section is not a multiple of the point size",
+            ));
+        }
+
+        bytes
+            .par_chunks(point_len)
+            .map(|chunk| {
+                let mut repr = G::Uncompressed::default();
+                repr.as_mut().copy_from_slice(chunk);
+
+                let affine: G = {
+                    let affine_opt = if self.checked {
+                        G::from_uncompressed(&repr)
+                    } else {
+                        G::from_uncompressed_unchecked(&repr)
+                    };
+                    Option::from(affine_opt)
+                        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not on curve"))
+                }?;
+
+                if affine.is_identity().into() {
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "point at infinity",
+                    ))
+                } else {
+                    Ok(affine)
+                }
+            })
+            .collect()
+    }
+
+    /// Loads all shards and returns the full parameters.
+    pub fn load(&self) -> io::Result<Parameters<E>> {
+        self.shards.prefetch_all()?;
+
+        let sections = &self.shards.manifest.sections;
+        Ok(Parameters {
+            vk: self.vk.clone(),
+            h: Arc::new(self.read_points(sections.h.clone())?),
+            l: Arc::new(self.read_points(sections.l.clone())?),
+            a: Arc::new(self.read_points(sections.a.clone())?),
+            b_g1: Arc::new(self.read_points(sections.b_g1.clone())?),
+            b_g2: Arc::new(self.read_points(sections.b_g2.clone())?),
+            densities: None,
+            fingerprint: None,
+        })
+    }
+}
+
+impl<E> ParameterSource<E> for &ShardedParameters<E>
+where
+    E: MultiMillerLoop,
+{
+    type G1Builder = (Arc<Vec<E::G1Affine>>, usize);
+    type G2Builder = (Arc<Vec<E::G2Affine>>, usize);
+
+    fn get_vk(&self, _: usize) -> Result<&VerifyingKey<E>, SynthesisError> {
+        Ok(&self.vk)
+    }
+
+    fn domain_size(&self) -> Option<usize> {
+        let h = &self.shards.manifest.sections.h;
+        let g1_len = std::mem::size_of::<<E::G1Affine as UncompressedEncoding>::Uncompressed>();
+        Some((h.end - h.start) as usize / g1_len + 1)
+    }
+
+    fn get_h(&self, _num_h: usize) -> Result<Self::G1Builder, SynthesisError> {
+        let builder = self.read_points(self.shards.manifest.sections.h.clone())?;
+        Ok((Arc::new(builder), 0))
+    }
+
+    fn get_l(&self, _num_l: usize) -> Result<Self::G1Builder, SynthesisError> {
+        let builder = self.read_points(self.shards.manifest.sections.l.clone())?;
+        Ok((Arc::new(builder), 0))
+    }
+
+    fn get_a(
+        &self,
+        num_inputs: usize,
+        _num_a: usize,
+    ) -> Result<(Self::G1Builder, Self::G1Builder), SynthesisError> {
+        let builder: Arc<Vec<_>> =
+            Arc::new(self.read_points(self.shards.manifest.sections.a.clone())?);
+        Ok(((builder.clone(), 0), (builder, num_inputs)))
+    }
+
+    fn get_b_g1(
+        &self,
+        num_inputs: usize,
+        _num_b_g1: usize,
+    ) -> Result<(Self::G1Builder, Self::G1Builder), SynthesisError> {
+        let builder: Arc<Vec<_>> =
+            Arc::new(self.read_points(self.shards.manifest.sections.b_g1.clone())?);
+        Ok(((builder.clone(), 0), (builder, num_inputs)))
+    }
+
+    fn get_b_g2(
+        &self,
+        num_inputs: usize,
+        _num_b_g2: usize,
+    ) -> Result<(Self::G2Builder, Self::G2Builder), SynthesisError> {
+        let builder: Arc<Vec<_>> =
+            Arc::new(self.read_points(self.shards.manifest.sections.b_g2.clone())?);
+        Ok(((builder.clone(), 0), (builder, num_inputs)))
+    }
+}
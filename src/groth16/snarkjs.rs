@@ -0,0 +1,259 @@
+//! Conversion of verifying keys from and to the `verification_key.json` format of [snarkjs].
+//!
+//! Coordinates are decimal strings. Points are given in projective coordinates with `z = 1`, the
+//! coefficients of G2 coordinates are ordered `[c0, c1]`.
+//!
+//! [snarkjs]: https://github.com/iden3/snarkjs
+
+use std::io;
+
+use blstrs::{Bls12, G1Affine, G2Affine};
+use group::{prime::PrimeCurveAffine, UncompressedEncoding};
+use serde_json::{json, Value};
+
+use super::VerifyingKey;
+
+const CURVE: &str = "bls12381";
+/// The length of a big endian base field element.
+const FP_LEN: usize = 48;
+
+impl VerifyingKey<Bls12> {
+    /// Exports the key in the JSON format of snarkjs.
+    ///
+    /// `beta_g1` and `delta_g1` are only needed for proving and are not part of the format. The
+    /// precomputed pairing `vk_alphabeta_12`, which snarkjs doesn't need to verify, is left out.
+    pub fn to_snarkjs_json(&self) -> String {
+        let ic: Vec<_> = self.ic.iter().map(g1_to_json).collect();
+        let vk = json!({
+            "protocol": "groth16",
+            "curve": CURVE,
+            "nPublic": self.ic.len() - 1,
+            "vk_alpha_1": g1_to_json(&self.alpha_g1),
+            "vk_beta_2": g2_to_json(&self.beta_g2),
+            "vk_gamma_2": g2_to_json(&self.gamma_g2),
+            "vk_delta_2": g2_to_json(&self.delta_g2),
+            "IC": ic,
+        });
+
+        serde_json::to_string_pretty(&vk).expect("serializing a JSON value never fails")
+    }
+
+    /// Imports a key in the JSON format of snarkjs.
+    ///
+    /// The key can only be used for verifying, `beta_g1` and `delta_g1` are not part of the
+    /// format and are set to the point at infinity.
+    pub fn from_snarkjs_json(json: &str) -> io::Result<Self> {
+        let vk: Value = serde_json::from_str(json).map_err(invalid_data)?;
+        if vk["protocol"] != "groth16" {
+            return Err(invalid_data("not a Groth16 verifying key"));
+        }
+        if vk["curve"] != CURVE {
+            return Err(invalid_data(format!("unsupported curve {}", vk["curve"])));
+        }
+
+        let ic = vk["IC"]
+            .as_array()
+            .ok_or_else(|| invalid_data("IC is missing"))?
+            .iter()
+            .map(g1_from_json)
+            .collect::<io::Result<Vec<_>>>()?;
+        if ic.is_empty() || vk["nPublic"].as_u64() != Some(ic.len() as u64 - 1) {
+            return Err(invalid_data("nPublic doesn't match the length of IC"));
+        }
+
+        Ok(VerifyingKey {
+            alpha_g1: g1_from_json(&vk["vk_alpha_1"])?,
+            beta_g1: G1Affine::identity(),
+            beta_g2: g2_from_json(&vk["vk_beta_2"])?,
+            gamma_g2: g2_from_json(&vk["vk_gamma_2"])?,
+            delta_g1: G1Affine::identity(),
+            delta_g2: g2_from_json(&vk["vk_delta_2"])?,
+            ic,
+        })
+    }
+}
+
+fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn g1_to_json(point: &G1Affine) -> Value {
+    if bool::from(point.is_identity()) {
+        return json!(["0", "1", "0"]);
+    }
+    let bytes = point.to_uncompressed();
+    json!([
+        to_decimal(&bytes[..FP_LEN]),
+        to_decimal(&bytes[FP_LEN..]),
+        "1"
+    ])
+}
+
+fn g2_to_json(point: &G2Affine) -> Value {
+    if bool::from(point.is_identity()) {
+        return json!([["0", "0"], ["1", "0"], ["0", "0"]]);
+    }
+    // The coefficients are encoded as `c1 || c0`.
+    let bytes = point.to_uncompressed();
+    let fp = |i: usize| to_decimal(&bytes[i * FP_LEN..(i + 1) * FP_LEN]);
+    json!([[fp(1), fp(0)], [fp(3), fp(2)], ["1", "0"]])
+}
+
+fn g1_from_json(value: &Value) -> io::Result<G1Affine> {
+    let coords = coordinates(value)?;
+    if coords[2] != "1" {
+        return Err(invalid_data("G1 point is not normalized"));
+    }
+
+    let mut bytes = [0; 2 * FP_LEN];
+    bytes[..FP_LEN].copy_from_slice(&fp_from_json(&coords[0])?);
+    bytes[FP_LEN..].copy_from_slice(&fp_from_json(&coords[1])?);
+    Option::from(G1Affine::from_uncompressed(&bytes))
+        .ok_or_else(|| invalid_data("invalid G1 point"))
+}
+
+fn g2_from_json(value: &Value) -> io::Result<G2Affine> {
+    let coords = coordinates(value)?;
+    if coords[2] != json!(["1", "0"]) {
+        return Err(invalid_data("G2 point is not normalized"));
+    }
+
+    let mut bytes = [0; 4 * FP_LEN];
+    for (i, coord) in coords[..2].iter().enumerate() {
+        let coeffs = coord
+            .as_array()
+            .filter(|coeffs| coeffs.len() == 2)
+            .ok_or_else(|| invalid_data("G2 coordinate is not a pair"))?;
+        let offset = 2 * i * FP_LEN;
+        bytes[offset..offset + FP_LEN].copy_from_slice(&fp_from_json(&coeffs[1])?);
+        bytes[offset + FP_LEN..offset + 2 * FP_LEN].copy_from_slice(&fp_from_json(&coeffs[0])?);
+    }
+    Option::from(G2Affine::from_uncompressed(&bytes))
+        .ok_or_else(|| invalid_data("invalid G2 point"))
+}
+
+/// Returns the three projective coordinates of a point.
+fn coordinates(value: &Value) -> io::Result<&[Value]> {
+    value
+        .as_array()
+        .map(Vec::as_slice)
+        .filter(|coords| coords.len() == 3)
+        .ok_or_else(|| invalid_data("point doesn't have 3 coordinates"))
+}
+
+fn fp_from_json(value: &Value) -> io::Result<[u8; FP_LEN]> {
+    value
+        .as_str()
+        .and_then(from_decimal)
+        .ok_or_else(|| invalid_data("coordinate is not a decimal number"))
+}
+
+/// Formats a big endian number as decimal.
+fn to_decimal(bytes: &[u8]) -> String {
+    let mut num = bytes.to_vec();
+    let mut digits = Vec::new();
+    while num.iter().any(|&byte| byte != 0) {
+        let mut rem = 0u32;
+        for byte in num.iter_mut() {
+            let acc = (rem << 8) | *byte as u32;
+            *byte = (acc / 10) as u8;
+            rem = acc % 10;
+        }
+        digits.push(b'0' + rem as u8);
+    }
+    if digits.is_empty() {
+        digits.push(b'0');
+    }
+    digits.reverse();
+    String::from_utf8(digits).expect("digits are ASCII")
+}
+
+/// Parses a decimal number into big endian bytes, `None` if it doesn't fit.
+fn from_decimal(decimal: &str) -> Option<[u8; FP_LEN]> {
+    if decimal.is_empty() {
+        return None;
+    }
+    let mut num = [0; FP_LEN];
+    for c in decimal.chars() {
+        let mut carry = c.to_digit(10)?;
+        for byte in num.iter_mut().rev() {
+            let acc = *byte as u32 * 10 + carry;
+            *byte = acc as u8;
+            carry = acc >> 8;
+        }
+        if carry != 0 {
+            return None;
+        }
+    }
+    Some(num)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::{G1Projective, G2Projective};
+    use group::{Curve, Group};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_decimal() {
+        let mut bytes = [0; FP_LEN];
+        assert_eq!(to_decimal(&bytes), "0");
+        assert_eq!(from_decimal("0"), Some(bytes));
+
+        bytes[FP_LEN - 2] = 1;
+        assert_eq!(to_decimal(&bytes), "256");
+        assert_eq!(from_decimal("256"), Some(bytes));
+
+        bytes[FP_LEN - 8..].copy_from_slice(&u64::MAX.to_be_bytes());
+        assert_eq!(to_decimal(&bytes), "18446744073709551615");
+        assert_eq!(from_decimal("18446744073709551615"), Some(bytes));
+
+        assert_eq!(from_decimal(""), None);
+        assert_eq!(from_decimal("12a"), None);
+        assert_eq!(from_decimal(&"9".repeat(120)), None);
+    }
+
+    #[test]
+    fn test_snarkjs_json() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let vk = VerifyingKey::<Bls12> {
+            alpha_g1: G1Projective::random(&mut rng).to_affine(),
+            beta_g1: G1Affine::identity(),
+            beta_g2: G2Projective::random(&mut rng).to_affine(),
+            gamma_g2: G2Projective::random(&mut rng).to_affine(),
+            delta_g1: G1Affine::identity(),
+            delta_g2: G2Projective::random(&mut rng).to_affine(),
+            ic: (0..3)
+                .map(|_| G1Projective::random(&mut rng).to_affine())
+                .collect(),
+        };
+
+        let json = vk.to_snarkjs_json();
+        assert_eq!(VerifyingKey::from_snarkjs_json(&json).unwrap(), vk);
+
+        let mut value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["nPublic"], 2);
+        assert_eq!(value["vk_alpha_1"][2], "1");
+        assert_eq!(value["vk_beta_2"][2], json!(["1", "0"]));
+
+        value["nPublic"] = json!(3);
+        assert!(VerifyingKey::<Bls12>::from_snarkjs_json(&value.to_string()).is_err());
+        value["nPublic"] = json!(2);
+        value["curve"] = json!("bn128");
+        assert!(VerifyingKey::<Bls12>::from_snarkjs_json(&value.to_string()).is_err());
+        value["curve"] = json!(CURVE);
+        // Swapping the coefficients of a G2 coordinate gives a point that isn't on the curve.
+        let x = value["vk_beta_2"][0].clone();
+        value["vk_beta_2"][0] = json!([x[1], x[0]]);
+        assert!(VerifyingKey::<Bls12>::from_snarkjs_json(&value.to_string()).is_err());
+    }
+}
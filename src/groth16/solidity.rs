@@ -0,0 +1,285 @@
+//! Generation of Solidity contracts that verify Groth16 proofs on chain.
+//!
+//! The contracts verify proofs over BLS12-381 with the precompiles of [EIP-2537]. Field elements
+//! are encoded as 64 byte big endian numbers, G1 points as `x || y` and G2 points as
+//! `x.c0 || x.c1 || y.c0 || y.c1`, the point at infinity as zeros. Unlike the BN254 precompiles
+//! of [EIP-197], the coefficients of G2 coordinates are not reversed. Public inputs are passed
+//! as `uint256`, i.e. as the 32 byte big endian integers of the scalars.
+//!
+//! See [`Proof::to_evm_calldata`] and [`encode_public_inputs`] to encode the arguments of the
+//! generated contracts.
+//!
+//! [EIP-2537]: https://eips.ethereum.org/EIPS/eip-2537
+//! [EIP-197]: https://eips.ethereum.org/EIPS/eip-197
+
+use blstrs::{Bls12, G1Affine, G2Affine, Scalar};
+use group::{prime::PrimeCurveAffine, UncompressedEncoding};
+
+use super::{Proof, VerifyingKey};
+
+/// The length of a base field element in the encoding of the precompiles.
+const FP_LEN: usize = 64;
+/// The length of a big endian base field element in the uncompressed encoding of points.
+const FP_BYTES: usize = 48;
+
+/// The scalar field modulus of BLS12-381.
+const MODULUS: &str = "0x73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001";
+
+const TEMPLATE: &str = r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+/// Verifies Groth16 proofs over BLS12-381 of a single circuit. Needs the BLS12-381 precompiles
+/// of EIP-2537. Generated by bellperson.
+contract Groth16Verifier {
+    address constant G1_MSM = address(0x0c);
+    address constant PAIRING_CHECK = address(0x0f);
+
+    uint256 constant R = __MODULUS__;
+    uint256 constant N_PUBLIC = __N_PUBLIC__;
+    uint256 constant G1_LEN = 128;
+    uint256 constant G2_LEN = 256;
+
+    // The verifying key, beta, gamma and delta are negated so that a valid proof makes the
+    // product of the pairings one.
+    bytes constant ALPHA_G1 = hex"__ALPHA_G1__";
+    bytes constant NEG_BETA_G2 = hex"__NEG_BETA_G2__";
+    bytes constant NEG_GAMMA_G2 = hex"__NEG_GAMMA_G2__";
+    bytes constant NEG_DELTA_G2 = hex"__NEG_DELTA_G2__";
+    bytes constant IC = hex"__IC__";
+
+    /// Verifies a proof, encoded as `A || B || C`, of the given public inputs.
+    function verifyProof(bytes calldata proof, uint256[] calldata input)
+        public
+        view
+        returns (bool)
+    {
+        if (proof.length != 2 * G1_LEN + G2_LEN || input.length != N_PUBLIC) {
+            return false;
+        }
+
+        // The inputs are accumulated with IC[0] as the point of the "one" input.
+        bytes memory ic = IC;
+        bytes memory msmInput = new bytes((N_PUBLIC + 1) * (G1_LEN + 32));
+        for (uint256 i = 0; i <= N_PUBLIC; i++) {
+            uint256 scalar = i == 0 ? 1 : input[i - 1];
+            if (scalar >= R) {
+                return false;
+            }
+            assembly {
+                // A G1 point is 128 bytes, followed by its 32 byte scalar.
+                let src := add(add(ic, 32), mul(i, 128))
+                let dst := add(add(msmInput, 32), mul(i, 160))
+                mstore(dst, mload(src))
+                mstore(add(dst, 32), mload(add(src, 32)))
+                mstore(add(dst, 64), mload(add(src, 64)))
+                mstore(add(dst, 96), mload(add(src, 96)))
+                mstore(add(dst, 128), scalar)
+            }
+        }
+        (bool ok, bytes memory acc) = G1_MSM.staticcall(msmInput);
+        if (!ok || acc.length != G1_LEN) {
+            return false;
+        }
+
+        // e(A, B) * e(alpha, -beta) * e(acc, -gamma) * e(C, -delta) == 1
+        bytes memory pairingInput = abi.encodePacked(
+            proof[:G1_LEN + G2_LEN],
+            ALPHA_G1,
+            NEG_BETA_G2,
+            acc,
+            NEG_GAMMA_G2,
+            proof[G1_LEN + G2_LEN:],
+            NEG_DELTA_G2
+        );
+        bytes memory result;
+        (ok, result) = PAIRING_CHECK.staticcall(pairingInput);
+        return ok && result.length == 32 && abi.decode(result, (uint256)) == 1;
+    }
+}
+"#;
+
+/// Generates a Solidity contract that verifies proofs with the given key, see the
+/// [module documentation](self) for the encoding of proofs and inputs.
+pub fn generate_verifier(vk: &VerifyingKey<Bls12>) -> String {
+    let ic: Vec<u8> = vk.ic.iter().flat_map(encode_g1).collect();
+
+    TEMPLATE
+        .replace("__MODULUS__", MODULUS)
+        .replace("__N_PUBLIC__", &(vk.ic.len() - 1).to_string())
+        .replace("__ALPHA_G1__", &to_hex(&encode_g1(&vk.alpha_g1)))
+        .replace("__NEG_BETA_G2__", &to_hex(&encode_g2(&-vk.beta_g2)))
+        .replace("__NEG_GAMMA_G2__", &to_hex(&encode_g2(&-vk.gamma_g2)))
+        .replace("__NEG_DELTA_G2__", &to_hex(&encode_g2(&-vk.delta_g2)))
+        .replace("__IC__", &to_hex(&ic))
+}
+
+impl Proof<Bls12> {
+    /// Encodes the proof as `A || B || C` in the layout of the EIP-2537 precompiles, the
+    /// `proof` argument of the generated contracts.
+    pub fn to_evm_calldata(&self) -> Vec<u8> {
+        let mut encoded = encode_g1(&self.a);
+        encoded.extend(encode_g2(&self.b));
+        encoded.extend(encode_g1(&self.c));
+        encoded
+    }
+}
+
+/// Encodes public inputs as consecutive `uint256`, the elements of the `input` argument of the
+/// generated contracts.
+pub fn encode_public_inputs(inputs: &[Scalar]) -> Vec<u8> {
+    inputs.iter().flat_map(Scalar::to_bytes_be).collect()
+}
+
+fn encode_g1(point: &G1Affine) -> Vec<u8> {
+    let mut encoded = vec![0; 2 * FP_LEN];
+    if !bool::from(point.is_identity()) {
+        let bytes = point.to_uncompressed();
+        for (i, fp) in bytes.chunks(FP_BYTES).enumerate() {
+            encoded[(i + 1) * FP_LEN - FP_BYTES..(i + 1) * FP_LEN].copy_from_slice(fp);
+        }
+    }
+    encoded
+}
+
+fn encode_g2(point: &G2Affine) -> Vec<u8> {
+    let mut encoded = vec![0; 4 * FP_LEN];
+    if !bool::from(point.is_identity()) {
+        // The coefficients are encoded as `c1 || c0`.
+        let bytes = point.to_uncompressed();
+        for (i, fp) in bytes.chunks(FP_BYTES).enumerate() {
+            let i = i ^ 1;
+            encoded[(i + 1) * FP_LEN - FP_BYTES..(i + 1) * FP_LEN].copy_from_slice(fp);
+        }
+    }
+    encoded
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::{G1Projective, G2Prepared, G2Projective, Scalar as Fr};
+    use ff::{Field, PrimeField};
+    use group::{Curve, Group};
+    use pairing::{MillerLoopResult, MultiMillerLoop};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use crate::groth16::{create_random_proof, generate_random_parameters};
+    use crate::{Circuit, ConstraintSystem, SynthesisError};
+
+    /// Decodes a point in the encoding of the precompiles.
+    fn decode_g1(encoded: &[u8]) -> G1Affine {
+        let mut bytes = [0; 2 * FP_BYTES];
+        for (i, fp) in encoded.chunks(FP_LEN).enumerate() {
+            assert!(fp[..FP_LEN - FP_BYTES].iter().all(|&byte| byte == 0));
+            bytes[i * FP_BYTES..(i + 1) * FP_BYTES].copy_from_slice(&fp[FP_LEN - FP_BYTES..]);
+        }
+        G1Affine::from_uncompressed(&bytes).unwrap()
+    }
+
+    fn decode_g2(encoded: &[u8]) -> G2Affine {
+        let mut bytes = [0; 4 * FP_BYTES];
+        for (i, fp) in encoded.chunks(FP_LEN).enumerate() {
+            let i = i ^ 1;
+            bytes[i * FP_BYTES..(i + 1) * FP_BYTES].copy_from_slice(&fp[FP_LEN - FP_BYTES..]);
+        }
+        G2Affine::from_uncompressed(&bytes).unwrap()
+    }
+
+    struct Square {
+        x: Option<Fr>,
+    }
+
+    impl Circuit<Fr> for Square {
+        fn synthesize<CS: ConstraintSystem<Fr>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            let x = cs.alloc(|| "x", || self.x.ok_or(SynthesisError::AssignmentMissing))?;
+            let y = cs.alloc_input(
+                || "y",
+                || {
+                    self.x
+                        .map(|x| x.square())
+                        .ok_or(SynthesisError::AssignmentMissing)
+                },
+            )?;
+            cs.enforce(|| "x * x = y", |lc| lc + x, |lc| lc + x, |lc| lc + y);
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_encoding() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let g1 = G1Projective::random(&mut rng).to_affine();
+        let g2 = G2Projective::random(&mut rng).to_affine();
+        assert_eq!(decode_g1(&encode_g1(&g1)), g1);
+        assert_eq!(decode_g2(&encode_g2(&g2)), g2);
+        assert_eq!(encode_g1(&G1Affine::identity()), vec![0; 128]);
+        assert_eq!(encode_g2(&G2Affine::identity()), vec![0; 256]);
+
+        // The generator of G2, its first coefficient is x.c0.
+        let encoded = encode_g2(&G2Affine::generator());
+        assert_eq!(
+            encoded[16..64],
+            hex!("024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8")
+        );
+
+        // The modulus is one more than the largest field element.
+        let mut modulus = (-Fr::ONE).to_repr().as_ref().to_vec();
+        modulus.reverse();
+        let neg_one = modulus.clone();
+        modulus[31] += 1;
+        assert_eq!(MODULUS, format!("0x{}", to_hex(&modulus)));
+
+        let inputs = encode_public_inputs(&[Fr::from(0x0102), -Fr::ONE]);
+        assert_eq!(inputs.len(), 64);
+        assert_eq!(inputs[30..32], [1, 2]);
+        assert!(inputs[..30].iter().all(|&byte| byte == 0));
+        assert_eq!(inputs[32..], neg_one[..]);
+    }
+
+    #[test]
+    fn test_generate_verifier() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let params =
+            generate_random_parameters::<Bls12, _, _>(Square { x: None }, &mut rng).unwrap();
+        let x = Fr::from(3);
+        let proof = create_random_proof(Square { x: Some(x) }, &params, &mut rng).unwrap();
+        let vk = &params.vk;
+
+        let contract = generate_verifier(vk);
+        assert!(!contract.contains("__"));
+        assert!(contract.contains("uint256 constant N_PUBLIC = 1;"));
+        let ic: Vec<u8> = vk.ic.iter().flat_map(encode_g1).collect();
+        assert!(contract.contains(&format!("bytes constant IC = hex\"{}\";", to_hex(&ic))));
+
+        // The pairing check of the contract, with the points it is given.
+        let encoded = proof.to_evm_calldata();
+        assert_eq!(encoded.len(), 512);
+        let acc = (G1Projective::from(vk.ic[0]) + vk.ic[1] * x.square()).to_affine();
+        let neg = |point: &G2Affine| G2Prepared::from(decode_g2(&encode_g2(&-*point)));
+        let terms = [
+            (
+                decode_g1(&encoded[..128]),
+                G2Prepared::from(decode_g2(&encoded[128..384])),
+            ),
+            (vk.alpha_g1, neg(&vk.beta_g2)),
+            (acc, neg(&vk.gamma_g2)),
+            (decode_g1(&encoded[384..]), neg(&vk.delta_g2)),
+        ];
+        let terms: Vec<_> = terms.iter().map(|(g1, g2)| (g1, g2)).collect();
+        let result = Bls12::multi_miller_loop(&terms).final_exponentiation();
+        assert_eq!(result, <Bls12 as pairing::Engine>::Gt::identity());
+    }
+}
@@ -328,7 +328,7 @@ impl PrimeField for Fr {
         // Only the first two bytes should be utilized.
         assert!(repr[2..].iter().all(|byte| *byte == 0));
 
-        let repr: [u8; 2] = repr[2..].try_into().unwrap();
+        let repr: [u8; 2] = repr[..2].try_into().unwrap();
         let int = Wrapping(u16::from_le_bytes(repr) as u32);
         let is_valid = int < MODULUS_R;
         CtOption::new(Fr(int), Choice::from(is_valid as u8))
@@ -639,6 +639,802 @@ fn test_verify_random_batch() {
     }
 }
 
+#[test]
+fn test_verify_random_batch_multi() {
+    use crate::groth16::{
+        create_random_proof_batch, generate_random_parameters, verify_proofs_batch_multi,
+    };
+    use blstrs::{Bls12, Scalar as Fr};
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    let c = XorDemo::<Fr> {
+        a: None,
+        b: None,
+        _marker: PhantomData,
+    };
+    // Two sets of parameters for the same circuit have different verifying keys.
+    let params_1 = generate_random_parameters::<Bls12, _, _>(c.clone(), &mut rng).unwrap();
+    let params_2 = generate_random_parameters::<Bls12, _, _>(c, &mut rng).unwrap();
+    let pvk_1 = prepare_verifying_key(&params_1.vk);
+    let pvk_2 = prepare_verifying_key(&params_2.vk);
+
+    let c = XorDemo {
+        a: Some(true),
+        b: Some(false),
+        _marker: PhantomData,
+    };
+    let proofs_1 =
+        create_random_proof_batch(vec![c.clone(), c.clone()], &params_1, &mut rng).unwrap();
+    let proofs_2 = create_random_proof_batch(vec![c.clone(), c], &params_2, &mut rng).unwrap();
+    let one = [Fr::ONE];
+    let zero = [Fr::ZERO];
+
+    // The groups are interleaved.
+    let mut vks_with_proofs = vec![
+        (&pvk_1, &proofs_1[0], &one[..]),
+        (&pvk_2, &proofs_2[0], &one[..]),
+        (&pvk_1, &proofs_1[1], &one[..]),
+        (&pvk_2, &proofs_2[1], &one[..]),
+    ];
+    assert!(verify_proofs_batch_multi(&mut rng, &vks_with_proofs).unwrap());
+    assert!(verify_proofs_batch_multi::<Bls12, _>(&mut rng, &[]).unwrap());
+
+    // A proof checked against the verifying key of the other parameters.
+    vks_with_proofs[3].0 = &pvk_1;
+    assert!(!verify_proofs_batch_multi(&mut rng, &vks_with_proofs).unwrap());
+    vks_with_proofs[3].0 = &pvk_2;
+
+    // Wrong public inputs.
+    vks_with_proofs[2].2 = &zero[..];
+    assert!(!verify_proofs_batch_multi(&mut rng, &vks_with_proofs).unwrap());
+
+    // Public inputs that don't match the verifying key.
+    vks_with_proofs[2].2 = &[];
+    assert!(verify_proofs_batch_multi(&mut rng, &vks_with_proofs).is_err());
+}
+
+#[test]
+fn test_verify_all() {
+    use crate::groth16::{create_random_proof_batch, generate_random_parameters, verify_all};
+    use blstrs::{Bls12, Scalar as Fr};
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    let params = {
+        let c = XorDemo::<Fr> {
+            a: None,
+            b: None,
+            _marker: PhantomData,
+        };
+
+        generate_random_parameters::<Bls12, _, _>(c, &mut rng).unwrap()
+    };
+
+    let pvk = prepare_verifying_key(&params.vk);
+
+    let c = XorDemo {
+        a: Some(true),
+        b: Some(false),
+        _marker: PhantomData,
+    };
+    let proofs =
+        create_random_proof_batch(vec![c.clone(), c.clone(), c.clone(), c], &params, &mut rng)
+            .unwrap();
+    let proofs_ref = proofs.iter().collect::<Vec<_>>();
+    let mut inputs = vec![vec![Fr::ONE]; 4];
+
+    assert_eq!(verify_all(&pvk, &proofs_ref, &inputs).unwrap(), None);
+
+    // The lowest failing index is reported.
+    inputs[3] = vec![Fr::ZERO];
+    assert_eq!(verify_all(&pvk, &proofs_ref, &inputs).unwrap(), Some(3));
+    inputs[1] = vec![Fr::ZERO];
+    assert_eq!(verify_all(&pvk, &proofs_ref, &inputs).unwrap(), Some(1));
+
+    // Mismatched lengths are rejected.
+    assert!(verify_all(&pvk, &proofs_ref[..2], &inputs).is_err());
+}
+
+#[test]
+fn test_verify_with_config() {
+    use crate::groth16::{
+        create_random_proof_batch, generate_random_parameters, verify_all_with_config,
+        verify_proofs_batch_with_config, VerifierBackend, VerifierConfig,
+    };
+    use blstrs::{Bls12, Scalar as Fr};
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    let params = {
+        let c = XorDemo::<Fr> {
+            a: None,
+            b: None,
+            _marker: PhantomData,
+        };
+
+        generate_random_parameters::<Bls12, _, _>(c, &mut rng).unwrap()
+    };
+
+    let pvk = prepare_verifying_key(&params.vk);
+
+    let c = XorDemo {
+        a: Some(true),
+        b: Some(false),
+        _marker: PhantomData,
+    };
+    let proofs = create_random_proof_batch(vec![c; 5], &params, &mut rng).unwrap();
+    let proofs_ref = proofs.iter().collect::<Vec<_>>();
+    let mut inputs = vec![vec![Fr::ONE]; 5];
+
+    for (parallelism, chunk_size) in [(None, None), (Some(1), Some(2)), (Some(2), Some(1))] {
+        let config = VerifierConfig {
+            backend: VerifierBackend::Cpu,
+            parallelism,
+            chunk_size,
+        };
+        inputs[4] = vec![Fr::ONE];
+        assert!(
+            verify_proofs_batch_with_config(&config, &pvk, &mut rng, &proofs_ref, &inputs).unwrap()
+        );
+        assert_eq!(
+            verify_all_with_config(&config, &pvk, &proofs_ref, &inputs).unwrap(),
+            None
+        );
+
+        // A failure in the last chunk is found.
+        inputs[4] = vec![Fr::ZERO];
+        assert!(
+            !verify_proofs_batch_with_config(&config, &pvk, &mut rng, &proofs_ref, &inputs)
+                .unwrap()
+        );
+        assert_eq!(
+            verify_all_with_config(&config, &pvk, &proofs_ref, &inputs).unwrap(),
+            Some(4)
+        );
+    }
+
+    // Without GPU support, `Auto` runs on the CPU and `Gpu` is rejected.
+    #[cfg(not(any(feature = "cuda", feature = "opencl")))]
+    {
+        let mut config = VerifierConfig::default();
+        assert_eq!(config.backend, VerifierBackend::Auto);
+        assert!(
+            !verify_proofs_batch_with_config(&config, &pvk, &mut rng, &proofs_ref, &inputs)
+                .unwrap()
+        );
+
+        config.backend = VerifierBackend::Gpu;
+        assert!(
+            verify_proofs_batch_with_config(&config, &pvk, &mut rng, &proofs_ref, &inputs).is_err()
+        );
+    }
+
+    assert!(verify_proofs_batch_with_config(
+        &VerifierConfig::default(),
+        &pvk,
+        &mut rng,
+        &proofs_ref[..2],
+        &inputs
+    )
+    .is_err());
+}
+
+#[test]
+fn test_verify_streaming() {
+    use crate::groth16::{
+        create_random_proof_batch, generate_random_parameters, verify_proofs_batch_streaming,
+        VerifierBackend, VerifierConfig,
+    };
+    use blstrs::{Bls12, Scalar as Fr};
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    let params = {
+        let c = XorDemo::<Fr> {
+            a: None,
+            b: None,
+            _marker: PhantomData,
+        };
+
+        generate_random_parameters::<Bls12, _, _>(c, &mut rng).unwrap()
+    };
+
+    let pvk = prepare_verifying_key(&params.vk);
+
+    let c = XorDemo {
+        a: Some(true),
+        b: Some(false),
+        _marker: PhantomData,
+    };
+    let proofs = create_random_proof_batch(vec![c; 5], &params, &mut rng).unwrap();
+
+    for chunk_size in [None, Some(1), Some(2)] {
+        let config = VerifierConfig {
+            backend: VerifierBackend::Cpu,
+            chunk_size,
+            ..Default::default()
+        };
+        let stream = |last_input| {
+            proofs
+                .clone()
+                .into_iter()
+                .enumerate()
+                .map(move |(i, proof)| {
+                    let input = if i == 4 { last_input } else { Fr::ONE };
+                    (proof, vec![input])
+                })
+        };
+
+        assert!(verify_proofs_batch_streaming(&config, &pvk, &mut rng, stream(Fr::ONE)).unwrap());
+        // A failure in the last chunk is found.
+        assert!(!verify_proofs_batch_streaming(&config, &pvk, &mut rng, stream(Fr::ZERO)).unwrap());
+    }
+
+    let config = VerifierConfig::default();
+    assert!(verify_proofs_batch_streaming(&config, &pvk, &mut rng, Vec::new()).unwrap());
+    assert!(verify_proofs_batch_streaming(
+        &config,
+        &pvk,
+        &mut rng,
+        vec![(proofs[0].clone(), vec![Fr::ONE, Fr::ONE])]
+    )
+    .is_err());
+}
+
+#[test]
+fn test_prepared_inputs() {
+    use crate::groth16::{
+        create_random_proof, generate_random_parameters, prepare_inputs,
+        verify_proof_with_prepared_inputs,
+    };
+    use blstrs::{Bls12, Scalar as Fr};
+
+    /// Exposes each of its values as a public input.
+    struct Inputs(Vec<Option<Fr>>);
+
+    impl Circuit<Fr> for Inputs {
+        fn synthesize<CS: ConstraintSystem<Fr>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            for (i, value) in self.0.into_iter().enumerate() {
+                let aux = cs.alloc(
+                    || format!("aux {}", i),
+                    || value.ok_or(SynthesisError::AssignmentMissing),
+                )?;
+                let input = cs.alloc_input(
+                    || format!("input {}", i),
+                    || value.ok_or(SynthesisError::AssignmentMissing),
+                )?;
+                cs.enforce(
+                    || format!("aux {} = input {}", i, i),
+                    |lc| lc + aux,
+                    |lc| lc + CS::one(),
+                    |lc| lc + input,
+                );
+            }
+            Ok(())
+        }
+    }
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    let params =
+        generate_random_parameters::<Bls12, _, _>(Inputs(vec![None; 6]), &mut rng).unwrap();
+    let pvk = prepare_verifying_key(&params.vk);
+
+    let prefix = (0..4).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+    let prepared = prepare_inputs(&pvk, &prefix).unwrap();
+    assert_eq!(prepared.num_inputs(), 4);
+
+    for _ in 0..3 {
+        let remaining = (0..2).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let inputs = prefix
+            .iter()
+            .chain(remaining.iter())
+            .copied()
+            .collect::<Vec<_>>();
+        let proof = create_random_proof(
+            Inputs(inputs.iter().copied().map(Some).collect()),
+            &params,
+            &mut rng,
+        )
+        .unwrap();
+
+        assert!(verify_proof(&pvk, &proof, &inputs).unwrap());
+        assert!(verify_proof_with_prepared_inputs(&pvk, &proof, &prepared, &remaining).unwrap());
+        assert!(!verify_proof_with_prepared_inputs(
+            &pvk,
+            &proof,
+            &prepared,
+            &[remaining[1], remaining[0]]
+        )
+        .unwrap());
+        assert!(
+            verify_proof_with_prepared_inputs(&pvk, &proof, &prepared, &remaining[..1]).is_err()
+        );
+    }
+
+    // All inputs can be prepared, but not more.
+    let all = (0..6).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+    assert!(prepare_inputs(&pvk, &all).is_ok());
+    let too_many = (0..7).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+    assert!(prepare_inputs(&pvk, &too_many).is_err());
+}
+
+#[test]
+fn test_verification_cache() {
+    use super::verification_cache::CacheKey;
+    use crate::groth16::{
+        create_random_proof, generate_random_parameters, set_verification_cache, VerificationCache,
+    };
+    use blstrs::{Bls12, Scalar as Fr};
+    use std::sync::Arc;
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    let params = {
+        let c = XorDemo::<Fr> {
+            a: None,
+            b: None,
+            _marker: PhantomData,
+        };
+
+        generate_random_parameters::<Bls12, _, _>(c, &mut rng).unwrap()
+    };
+
+    let pvk = prepare_verifying_key(&params.vk);
+
+    let c = XorDemo {
+        a: Some(true),
+        b: Some(false),
+        _marker: PhantomData,
+    };
+    let proof = create_random_proof(c, &params, &mut rng).unwrap();
+
+    // Other tests may verify concurrently, so the cache is large enough for all of them.
+    let cache = Arc::new(VerificationCache::new(1 << 16));
+    set_verification_cache(Some(cache.clone()));
+
+    assert!(verify_proof(&pvk, &proof, &[Fr::ONE]).unwrap());
+    let key = CacheKey::new(&pvk, &proof, &[Fr::ONE]);
+    assert_eq!(cache.get(&key), Some(true));
+    assert!(!verify_proof(&pvk, &proof, &[Fr::ZERO]).unwrap());
+    assert_eq!(
+        cache.get(&CacheKey::new(&pvk, &proof, &[Fr::ZERO])),
+        Some(false)
+    );
+
+    // The cached result is returned without verifying again.
+    cache.insert(key, false);
+    assert!(!verify_proof(&pvk, &proof, &[Fr::ONE]).unwrap());
+
+    set_verification_cache(None);
+    assert!(verify_proof(&pvk, &proof, &[Fr::ONE]).unwrap());
+}
+
+#[test]
+fn test_sharded_parameters() {
+    use crate::groth16::{
+        create_random_proof, generate_random_parameters, ShardManifest, ShardedParameters,
+    };
+    use blstrs::{Bls12, Scalar as Fr};
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    let params = {
+        let c = XorDemo::<Fr> {
+            a: None,
+            b: None,
+            _marker: PhantomData,
+        };
+
+        generate_random_parameters::<Bls12, _, _>(c, &mut rng).unwrap()
+    };
+
+    let dir = tempfile::tempdir().unwrap();
+    let manifest = params.write_sharded(dir.path(), "xor", 256).unwrap();
+
+    // The concatenated shards are a regular parameter file.
+    let mut serialized = vec![];
+    params.write(&mut serialized).unwrap();
+    assert_eq!(manifest.total_len, serialized.len() as u64);
+    assert_eq!(manifest.shard_hashes.len(), (serialized.len() + 255) / 256);
+    let concatenated = (0..manifest.shard_hashes.len())
+        .flat_map(|i| std::fs::read(ShardManifest::shard_path(dir.path(), "xor", i)).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(concatenated, serialized);
+
+    // Only the shards holding the verifying key are loaded on open.
+    let sharded = ShardedParameters::<Bls12>::open(dir.path(), "xor", true).unwrap();
+    assert!(sharded.vk == params.vk);
+    let last = manifest.shard_hashes.len() - 1;
+    assert!(!sharded.shards.is_loaded(last));
+    assert!(sharded.load().unwrap() == params);
+    assert!(sharded.shards.is_loaded(last));
+
+    let c = XorDemo {
+        a: Some(true),
+        b: Some(false),
+        _marker: PhantomData,
+    };
+    let proof = create_random_proof(c, &sharded, &mut rng).unwrap();
+    assert!(verify_proof(&sharded.pvk, &proof, &[Fr::ONE]).unwrap());
+
+    // Corrupted shards are rejected.
+    let path = ShardManifest::shard_path(dir.path(), "xor", last);
+    let mut shard = std::fs::read(&path).unwrap();
+    shard[0] ^= 1;
+    std::fs::write(&path, shard).unwrap();
+    let sharded = ShardedParameters::<Bls12>::open(dir.path(), "xor", true).unwrap();
+    assert!(sharded.load().is_err());
+}
+
+#[test]
+fn test_parameters_header() {
+    use crate::groth16::{generate_random_parameters, Parameters};
+    use blstrs::{Bls12, Scalar as Fr};
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    let params = {
+        let c = XorDemo::<Fr> {
+            a: None,
+            b: None,
+            _marker: PhantomData,
+        };
+
+        generate_random_parameters::<Bls12, _, _>(c, &mut rng).unwrap()
+    };
+
+    let mut without_header = vec![];
+    params.write(&mut without_header).unwrap();
+    let mut with_header = vec![];
+    params.write_with_header(&mut with_header).unwrap();
+    assert_eq!(&with_header[4..8], &[0, 0, 0, 2]);
+    assert_eq!(
+        &with_header[with_header.len() - without_header.len()..],
+        &without_header[..]
+    );
+
+    // Files with and without header are read.
+    for serialized in [&with_header, &without_header] {
+        assert!(Parameters::<Bls12>::read(&serialized[..], true).unwrap() == params);
+    }
+    // Only the header records the fingerprint of the circuit.
+    assert!(params.fingerprint.is_some());
+    let read = Parameters::<Bls12>::read(&with_header[..], true).unwrap();
+    assert_eq!(read.fingerprint, params.fingerprint);
+    let read = Parameters::<Bls12>::read(&without_header[..], true).unwrap();
+    assert_eq!(read.fingerprint, None);
+
+    // Headers of version 1 have no fingerprint.
+    let mut version_1 = with_header.clone();
+    version_1.drain(16..48);
+    version_1[7] = 1;
+    let read = Parameters::<Bls12>::read(&version_1[..], true).unwrap();
+    assert!(read == params);
+    assert_eq!(read.fingerprint, None);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("params");
+    std::fs::write(&path, &with_header).unwrap();
+    let mmap = unsafe { memmap2::Mmap::map(&std::fs::File::open(&path).unwrap()).unwrap() };
+    let read = Parameters::<Bls12>::read_mmap(&mmap, true).unwrap();
+    assert!(read == params);
+    assert_eq!(read.fingerprint, params.fingerprint);
+    let mapped = Parameters::<Bls12>::build_mapped_parameters(path, true).unwrap();
+    assert!(mapped.vk == params.vk);
+    assert_eq!(mapped.fingerprint, params.fingerprint);
+
+    // Truncated and corrupted files are rejected with an error naming the section.
+    let err = Parameters::<Bls12>::read(&with_header[..with_header.len() - 1], true)
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("section b_g2"), "{}", err);
+    let mut corrupted = with_header.clone();
+    let last = corrupted.len() - 1;
+    corrupted[last] ^= 1;
+    let err = Parameters::<Bls12>::read(&corrupted[..], false)
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("section b_g2"), "{}", err);
+
+    let mut future = with_header;
+    future[7] = 3;
+    assert!(Parameters::<Bls12>::read(&future[..], true).is_err());
+}
+
+#[test]
+fn test_selective_mapped_parameters() {
+    use crate::groth16::{generate_random_parameters, ParameterSource, Parameters, QuerySelection};
+    use blstrs::{Bls12, Scalar as Fr};
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    let params = {
+        let c = XorDemo::<Fr> {
+            a: None,
+            b: None,
+            _marker: PhantomData,
+        };
+
+        generate_random_parameters::<Bls12, _, _>(c, &mut rng).unwrap()
+    };
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("params");
+    let mut serialized = vec![];
+    params.write(&mut serialized).unwrap();
+    std::fs::write(&path, &serialized).unwrap();
+
+    let selection = QuerySelection {
+        h: true,
+        l: true,
+        ..QuerySelection::NONE
+    };
+    let mapped =
+        Parameters::<Bls12>::build_mapped_parameters_with(path.clone(), true, selection).unwrap();
+    assert!(mapped.vk == params.vk);
+    assert_eq!(mapped.h.len(), params.h.len());
+    assert!(mapped.a.is_empty());
+    assert!(mapped.b_g2.is_empty());
+
+    let source = &mapped;
+    assert_eq!(source.domain_size(), Some(params.h.len() + 1));
+    assert_eq!(*source.get_h(0).unwrap().0, *params.h);
+    assert_eq!(*source.get_l(0).unwrap().0, *params.l);
+    assert!(source.get_a(1, 0).is_err());
+    assert!(source.get_b_g1(1, 0).is_err());
+    assert!(source.get_b_g2(1, 0).is_err());
+
+    let mapped =
+        Parameters::<Bls12>::build_mapped_parameters_with(path, true, QuerySelection::NONE)
+            .unwrap();
+    assert!(mapped.vk == params.vk);
+    assert_eq!((&mapped).domain_size(), None);
+    assert!((&mapped).get_h(0).is_err());
+}
+
+#[test]
+fn test_remote_parameters() {
+    use std::ops::Range;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use crate::groth16::{
+        create_random_proof, generate_random_parameters, RangeFetcher, ShardManifest,
+        ShardedParameters,
+    };
+    use blstrs::{Bls12, Scalar as Fr};
+
+    /// Serves a parameter file from memory and counts the fetched bytes.
+    struct MemoryFetcher {
+        file: Vec<u8>,
+        fetched: Arc<AtomicUsize>,
+    }
+
+    impl RangeFetcher for MemoryFetcher {
+        fn fetch(&self, range: Range<u64>) -> std::io::Result<Vec<u8>> {
+            self.fetched
+                .fetch_add((range.end - range.start) as usize, Ordering::SeqCst);
+            Ok(self.file[range.start as usize..range.end as usize].to_vec())
+        }
+    }
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    let params = {
+        let c = XorDemo::<Fr> {
+            a: None,
+            b: None,
+            _marker: PhantomData,
+        };
+
+        generate_random_parameters::<Bls12, _, _>(c, &mut rng).unwrap()
+    };
+    let mut file = vec![];
+    params.write(&mut file).unwrap();
+
+    // The manifest of a regular parameter file is the one of its shards.
+    let manifest = ShardManifest::from_parameters::<Bls12, _>(&file[..], 256).unwrap();
+    let shard_dir = tempfile::tempdir().unwrap();
+    assert_eq!(
+        manifest,
+        params.write_sharded(shard_dir.path(), "xor", 256).unwrap()
+    );
+
+    // Only the shards holding the verifying key are fetched on open.
+    let dir = tempfile::tempdir().unwrap();
+    let fetched = Arc::new(AtomicUsize::new(0));
+    let remote = ShardedParameters::<Bls12>::open_remote(
+        dir.path(),
+        "xor",
+        manifest.clone(),
+        MemoryFetcher {
+            file: file.clone(),
+            fetched: fetched.clone(),
+        },
+        true,
+    )
+    .unwrap();
+    assert!(remote.vk == params.vk);
+    assert!(fetched.load(Ordering::SeqCst) < file.len());
+
+    let c = XorDemo {
+        a: Some(true),
+        b: Some(false),
+        _marker: PhantomData,
+    };
+    let proof = create_random_proof(c, &remote, &mut rng).unwrap();
+    assert!(verify_proof(&remote.pvk, &proof, &[Fr::ONE]).unwrap());
+    assert!(remote.load().unwrap() == params);
+    // Every shard is fetched exactly once.
+    assert_eq!(fetched.load(Ordering::SeqCst), file.len());
+
+    // The fetched shards are cached, the remote file isn't needed anymore.
+    let cached = ShardedParameters::<Bls12>::open(dir.path(), "xor", true).unwrap();
+    assert!(cached.load().unwrap() == params);
+
+    // A remote file that doesn't match the manifest is rejected and nothing is cached.
+    let mut corrupted = file.clone();
+    let last = manifest.shard_hashes.len() - 1;
+    *corrupted.last_mut().unwrap() ^= 1;
+    let dir = tempfile::tempdir().unwrap();
+    let remote = ShardedParameters::<Bls12>::open_remote(
+        dir.path(),
+        "xor",
+        manifest,
+        MemoryFetcher {
+            file: corrupted,
+            fetched: Arc::new(AtomicUsize::new(0)),
+        },
+        true,
+    )
+    .unwrap();
+    assert!(remote.load().is_err());
+    assert!(!ShardManifest::shard_path(dir.path(), "xor", last).exists());
+}
+
+#[test]
+fn test_streaming_parameters() {
+    use crate::groth16::{create_random_proof, generate_parameters_streaming, Parameters};
+    use blstrs::{Bls12, G1Projective, G2Projective, Scalar as Fr};
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    let g1 = G1Projective::random(&mut rng);
+    let g2 = G2Projective::random(&mut rng);
+    let alpha = Fr::random(&mut rng);
+    let beta = Fr::random(&mut rng);
+    let gamma = Fr::random(&mut rng);
+    let delta = Fr::random(&mut rng);
+    let tau = Fr::random(&mut rng);
+    let c = || XorDemo::<Fr> {
+        a: None,
+        b: None,
+        _marker: PhantomData,
+    };
+
+    let params =
+        generate_parameters::<Bls12, _>(c(), g1, g2, alpha, beta, gamma, delta, tau).unwrap();
+    let mut expected = vec![];
+    params.write(&mut expected).unwrap();
+
+    // The streamed parameters are the same as the in-memory ones, whatever the chunk size.
+    for chunk_size in [1, 3, 1024] {
+        let mut streamed = vec![];
+        let vk = generate_parameters_streaming::<Bls12, _, _>(
+            c(),
+            g1,
+            g2,
+            alpha,
+            beta,
+            gamma,
+            delta,
+            tau,
+            &mut streamed,
+            chunk_size,
+        )
+        .unwrap();
+        assert!(vk == params.vk);
+        assert_eq!(streamed, expected);
+    }
+
+    let params = Parameters::<Bls12>::read(&expected[..], true).unwrap();
+    let pvk = prepare_verifying_key(&params.vk);
+    let c = XorDemo {
+        a: Some(true),
+        b: Some(false),
+        _marker: PhantomData,
+    };
+    let proof = create_random_proof(c, &params, &mut rng).unwrap();
+    assert!(verify_proof(&pvk, &proof, &[Fr::ONE]).unwrap());
+}
+
+#[test]
+fn test_prove_many() {
+    use crate::groth16::{generate_random_parameters, prove_many, ProveManyConfig};
+    use blstrs::{Bls12, Scalar as Fr};
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    let params = {
+        let c = XorDemo::<Fr> {
+            a: None,
+            b: None,
+            _marker: PhantomData,
+        };
+
+        generate_random_parameters::<Bls12, _, _>(c, &mut rng).unwrap()
+    };
+    let pvk = prepare_verifying_key(&params.vk);
+
+    let inputs = [
+        (true, false),
+        (true, true),
+        (false, false),
+        (false, true),
+        (true, true),
+    ];
+    let circuits = inputs
+        .iter()
+        .map(|&(a, b)| XorDemo {
+            a: Some(a),
+            b: Some(b),
+            _marker: PhantomData,
+        })
+        .collect::<Vec<_>>();
+
+    let config = ProveManyConfig {
+        max_batch_size: 2,
+        concurrent_batches: Some(2),
+        ..Default::default()
+    };
+    let proofs = prove_many(circuits, &params, &mut rng, &config).unwrap();
+
+    // The proofs are returned in the order of the circuits.
+    assert_eq!(proofs.len(), inputs.len());
+    for (proof, (a, b)) in proofs.iter().zip(inputs.iter()) {
+        let c = if a ^ b { Fr::ONE } else { Fr::ZERO };
+        assert!(verify_proof(&pvk, proof, &[c]).unwrap());
+    }
+}
+
 struct MultWithZeroCoeffs<F> {
     a: Option<F>,
     b: Option<F>,
@@ -0,0 +1,197 @@
+//! Memoization of proof verification results.
+//!
+//! Gateways that see the same proofs over and over again, e.g. through gossip or retries, can
+//! install a [`VerificationCache`] with [`set_verification_cache`]. [`verify_proof`] then only
+//! does the pairings for proofs it hasn't seen recently.
+//!
+//! [`verify_proof`]: super::verify_proof
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, RwLock};
+
+use ff::PrimeField;
+use pairing::MultiMillerLoop;
+use sha2::{Digest, Sha256};
+
+use super::{PreparedVerifyingKey, Proof};
+
+static GLOBAL_CACHE: RwLock<Option<Arc<VerificationCache>>> = RwLock::new(None);
+
+/// Installs the cache that is consulted by [`verify_proof`](super::verify_proof), `None` disables
+/// caching again. Caching is disabled by default.
+pub fn set_verification_cache(cache: Option<Arc<VerificationCache>>) {
+    *GLOBAL_CACHE
+        .write()
+        .expect("verification cache lock poisoned") = cache;
+}
+
+/// Returns the currently installed cache.
+pub fn verification_cache() -> Option<Arc<VerificationCache>> {
+    GLOBAL_CACHE
+        .read()
+        .expect("verification cache lock poisoned")
+        .clone()
+}
+
+/// Identifies a verification, the hash of the verifying key, the serialized proof and the hash
+/// of the public inputs.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct CacheKey {
+    vk_hash: [u8; 32],
+    proof: Vec<u8>,
+    inputs_hash: [u8; 32],
+}
+
+impl CacheKey {
+    pub(crate) fn new<E>(pvk: &PreparedVerifyingKey<E>, proof: &Proof<E>, inputs: &[E::Fr]) -> Self
+    where
+        E: MultiMillerLoop,
+    {
+        let mut proof_bytes = Vec::with_capacity(Proof::<E>::size());
+        proof
+            .write(&mut proof_bytes)
+            .expect("writing to a vector never fails");
+
+        let mut hasher = Sha256::new();
+        for input in inputs {
+            hasher.update(input.to_repr());
+        }
+
+        CacheKey {
+            vk_hash: pvk.vk_hash,
+            proof: proof_bytes,
+            inputs_hash: hasher.finalize().into(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Lru {
+    /// The cached results and the tick of their last use.
+    entries: HashMap<CacheKey, (bool, u64)>,
+    /// The keys by the tick of their last use.
+    recency: BTreeMap<u64, CacheKey>,
+    tick: u64,
+}
+
+impl Lru {
+    fn touch(&mut self, key: &CacheKey) -> Option<bool> {
+        self.tick += 1;
+        let (valid, last_use) = self.entries.get_mut(key)?;
+        let key = self
+            .recency
+            .remove(last_use)
+            .expect("entries and recency are in sync");
+        *last_use = self.tick;
+        self.recency.insert(self.tick, key);
+        Some(*valid)
+    }
+}
+
+/// A least recently used cache of verification results.
+#[derive(Debug)]
+pub struct VerificationCache {
+    capacity: usize,
+    lru: Mutex<Lru>,
+}
+
+impl VerificationCache {
+    /// Creates a cache holding the results of up to `capacity` verifications.
+    pub fn new(capacity: usize) -> Self {
+        VerificationCache {
+            capacity,
+            lru: Mutex::new(Lru::default()),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of cached results.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all cached results.
+    pub fn clear(&self) {
+        *self.lock() = Lru::default();
+    }
+
+    /// Returns the cached result and marks it as recently used.
+    pub(crate) fn get(&self, key: &CacheKey) -> Option<bool> {
+        self.lock().touch(key)
+    }
+
+    /// Caches a result, evicting the least recently used one if the cache is full.
+    pub(crate) fn insert(&self, key: CacheKey, valid: bool) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut lru = self.lock();
+        if lru.touch(&key).is_some() {
+            lru.entries.get_mut(&key).expect("touched entry exists").0 = valid;
+            return;
+        }
+
+        if lru.entries.len() == self.capacity {
+            let oldest = *lru.recency.keys().next().expect("the cache is full");
+            let evicted = lru.recency.remove(&oldest).expect("key exists");
+            lru.entries.remove(&evicted);
+        }
+        lru.tick += 1;
+        let tick = lru.tick;
+        lru.recency.insert(tick, key.clone());
+        lru.entries.insert(key, (valid, tick));
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Lru> {
+        self.lru.lock().expect("verification cache lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(i: u8) -> CacheKey {
+        CacheKey {
+            vk_hash: [0; 32],
+            proof: vec![i],
+            inputs_hash: [0; 32],
+        }
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let cache = VerificationCache::new(2);
+        cache.insert(key(0), true);
+        cache.insert(key(1), false);
+        assert_eq!(cache.len(), 2);
+
+        // Using the first entry makes the second one the least recently used.
+        assert_eq!(cache.get(&key(0)), Some(true));
+        cache.insert(key(2), true);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&key(1)), None);
+        assert_eq!(cache.get(&key(0)), Some(true));
+        assert_eq!(cache.get(&key(2)), Some(true));
+
+        // Updating doesn't evict.
+        cache.insert(key(2), false);
+        assert_eq!(cache.get(&key(2)), Some(false));
+        assert_eq!(cache.get(&key(0)), Some(true));
+
+        cache.clear();
+        assert!(cache.is_empty());
+
+        let disabled = VerificationCache::new(0);
+        disabled.insert(key(0), true);
+        assert!(disabled.is_empty());
+    }
+}
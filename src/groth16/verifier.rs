@@ -1,13 +1,146 @@
+use std::collections::HashMap;
+#[cfg(all(feature = "multicore", not(target_arch = "wasm32")))]
+use std::io;
 use std::ops::{AddAssign, Mul, MulAssign};
-
+use std::sync::Arc;
+#[cfg(all(feature = "multicore", not(target_arch = "wasm32")))]
+use std::sync::Mutex;
+
+#[cfg(not(target_arch = "wasm32"))]
+use ec_gpu_gen::multiexp_cpu::FullDensity;
+#[cfg(not(target_arch = "wasm32"))]
+use ec_gpu_gen::threadpool::Worker;
 use ff::{Field, PrimeField};
 use group::{prime::PrimeCurveAffine, Curve, Group};
 use pairing::{Engine, MillerLoopResult, MultiMillerLoop};
-use rayon::prelude::*;
+use sha2::{Digest, Sha256};
 
+use super::verification_cache::{verification_cache, CacheKey};
 use super::{multiscalar, PreparedVerifyingKey, Proof, VerifyingKey};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::gpu::{self, LockedMultiexpKernel};
+use crate::gpu::{GpuError, GpuName};
+use crate::multicore::{self, prelude::*};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::multiexp::multiexp;
 use crate::{le_bytes_to_u64s, SynthesisError};
 
+/// Where the multi-exponentiations of a batch verification run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VerifierBackend {
+    /// Always run on the CPU.
+    Cpu,
+    /// Run on the GPU, it is an error if no GPU is available. Failures at runtime fall back to
+    /// the CPU, like they do when proving. There is no GPU on `wasm32`.
+    Gpu,
+    /// Run on the GPU if one is available, otherwise on the CPU.
+    #[default]
+    Auto,
+}
+
+/// Runtime configuration of the batch verification entry points, see
+/// [`verify_proofs_batch_with_config`] and [`verify_all_with_config`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerifierConfig {
+    pub backend: VerifierBackend,
+    /// The number of CPU threads to verify with. `None` uses the global rayon thread pool. Without
+    /// the `multicore` feature and on `wasm32`, verification always runs on the calling thread.
+    pub parallelism: Option<usize>,
+    /// The maximum number of proofs that are verified at once. `None` verifies all of them at
+    /// once, smaller chunks need less memory.
+    pub chunk_size: Option<usize>,
+}
+
+/// The thread pools of the configs with a `parallelism`, by their number of threads. They are
+/// built on first use and then shared by all configs with the same number of threads.
+#[cfg(all(feature = "multicore", not(target_arch = "wasm32")))]
+static THREAD_POOLS: Mutex<Vec<(usize, Arc<rayon::ThreadPool>)>> = Mutex::new(Vec::new());
+
+impl VerifierConfig {
+    /// Returns the thread pool with `num_threads` threads, building it on first use.
+    #[cfg(all(feature = "multicore", not(target_arch = "wasm32")))]
+    fn thread_pool(num_threads: usize) -> Result<Arc<rayon::ThreadPool>, SynthesisError> {
+        let mut pools = THREAD_POOLS.lock().expect("thread pool lock poisoned");
+        if let Some((_, pool)) = pools.iter().find(|(n, _)| *n == num_threads) {
+            return Ok(pool.clone());
+        }
+
+        let pool = Arc::new(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?,
+        );
+        pools.push((num_threads, pool.clone()));
+        Ok(pool)
+    }
+
+    /// Runs `op` within the configured thread pool.
+    fn install<OP, T>(&self, op: OP) -> Result<T, SynthesisError>
+    where
+        OP: FnOnce() -> Result<T, SynthesisError> + Send,
+        T: Send,
+    {
+        #[cfg(all(feature = "multicore", not(target_arch = "wasm32")))]
+        if let Some(num_threads) = self.parallelism {
+            return Self::thread_pool(num_threads)?.install(op);
+        }
+
+        op()
+    }
+
+    fn chunk_size(&self, len: usize) -> usize {
+        self.chunk_size.unwrap_or(len).max(1)
+    }
+}
+
+/// A multi-exponentiation in G1 that is dispatched to the GPU.
+type GpuMultiexp<E> = fn(
+    Arc<Vec<<E as Engine>::G1Affine>>,
+    Arc<Vec<<<E as Engine>::Fr as PrimeField>::Repr>>,
+) -> Result<<E as Engine>::G1, SynthesisError>;
+
+impl VerifierBackend {
+    fn gpu_multiexp<E>(self) -> Result<Option<GpuMultiexp<E>>, SynthesisError>
+    where
+        E: Engine,
+        E::G1Affine: GpuName,
+    {
+        // The thread pool the GPU multiexp runs on needs threads.
+        #[cfg(target_arch = "wasm32")]
+        let available: Option<GpuMultiexp<E>> = None;
+        #[cfg(not(target_arch = "wasm32"))]
+        let available = (!gpu::device_memory().is_empty())
+            .then_some(gpu_multiexp::<E::G1Affine> as GpuMultiexp<E>);
+        match self {
+            VerifierBackend::Cpu => Ok(None),
+            VerifierBackend::Gpu if available.is_none() => {
+                Err(GpuError::Simple("no GPU is available for verification").into())
+            }
+            VerifierBackend::Gpu | VerifierBackend::Auto => Ok(available),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn gpu_multiexp<G>(
+    bases: Arc<Vec<G>>,
+    exponents: Arc<Vec<<G::Scalar as PrimeField>::Repr>>,
+) -> Result<G::Curve, SynthesisError>
+where
+    G: PrimeCurveAffine + GpuName,
+{
+    let mut kern = LockedMultiexpKernel::<G>::new(false);
+    Ok(multiexp(
+        &Worker::new(),
+        (bases, 0),
+        FullDensity,
+        exponents,
+        &mut kern,
+    )
+    .wait()?)
+}
+
 /// Generate a prepared verifying key, required to verify a proofs.
 pub fn prepare_verifying_key<E: Engine + MultiMillerLoop>(
     vk: &VerifyingKey<E>,
@@ -20,6 +153,10 @@ where
 
     let multiscalar = multiscalar::precompute_fixed_window(&vk.ic, multiscalar::WINDOW_SIZE);
 
+    let mut hasher = Sha256::new();
+    vk.write(&mut hasher)
+        .expect("writing to a hasher never fails");
+
     PreparedVerifyingKey {
         alpha_g1_beta_g2: E::pairing(&vk.alpha_g1, &vk.beta_g2),
         neg_gamma_g2: neg_gamma.into(),
@@ -31,78 +168,443 @@ where
         alpha_g1: vk.alpha_g1.to_curve(),
         beta_g2: vk.beta_g2.into(),
         ic_projective: vk.ic.par_iter().map(|i| i.to_curve()).collect(),
+        vk_hash: hasher.finalize().into(),
+        vk: vk.clone(),
     }
 }
 
 /// Verify a single Proof.
+///
+/// If a [`VerificationCache`](super::VerificationCache) is installed, the result is looked up
+/// there first and cached afterwards.
 pub fn verify_proof<'a, E>(
     pvk: &'a PreparedVerifyingKey<E>,
     proof: &Proof<E>,
     public_inputs: &[E::Fr],
 ) -> Result<bool, SynthesisError>
+where
+    E: MultiMillerLoop,
+    <<E as Engine>::Fr as PrimeField>::Repr: Sync,
+{
+    if (public_inputs.len() + 1) != pvk.ic.len() {
+        return Err(SynthesisError::MalformedVerifyingKey);
+    }
+
+    match verification_cache() {
+        Some(cache) => {
+            let key = CacheKey::new(pvk, proof, public_inputs);
+            if let Some(valid) = cache.get(&key) {
+                return Ok(valid);
+            }
+            let valid = verify_proof_uncached(pvk, proof, public_inputs);
+            cache.insert(key, valid);
+            Ok(valid)
+        }
+        None => Ok(verify_proof_uncached(pvk, proof, public_inputs)),
+    }
+}
+
+fn verify_proof_uncached<E>(
+    pvk: &PreparedVerifyingKey<E>,
+    proof: &Proof<E>,
+    public_inputs: &[E::Fr],
+) -> bool
+where
+    E: MultiMillerLoop,
+    <<E as Engine>::Fr as PrimeField>::Repr: Sync,
+{
+    verify_proof_with_inputs_acc(pvk, proof, || {
+        let mut acc = accumulate_inputs(pvk, 0, public_inputs);
+        acc.add_assign(&pvk.ic[0]);
+        acc
+    })
+}
+
+/// Computes the sum of `inputs[i] * ic[offset + i + 1]`, i.e. the contribution of the public
+/// inputs starting at `offset` to the input term of the verification equation.
+pub(super) fn accumulate_inputs<E>(
+    pvk: &PreparedVerifyingKey<E>,
+    offset: usize,
+    inputs: &[E::Fr],
+) -> E::G1
 where
     E: MultiMillerLoop,
     <<E as Engine>::Fr as PrimeField>::Repr: Sync,
 {
     use multiscalar::MultiscalarPrecomp;
 
-    if (public_inputs.len() + 1) != pvk.ic.len() {
+    let subset = pvk.multiscalar.at_point(offset + 1);
+    let inputs_repr: Vec<_> = inputs.iter().map(PrimeField::to_repr).collect();
+
+    multiscalar::par_multiscalar::<&multiscalar::Getter<E::G1Affine>, E::G1Affine>(
+        &multiscalar::ScalarList::Slice(&inputs_repr),
+        &subset,
+        std::mem::size_of::<<E::Fr as PrimeField>::Repr>() * 8,
+    )
+}
+
+/// Checks the verification equation, `inputs_acc` computes the input term. The equation itself
+/// is the one of `bellperson-verifier-core`, so that both crates accept the same proofs.
+pub(super) fn verify_proof_with_inputs_acc<E, F>(
+    pvk: &PreparedVerifyingKey<E>,
+    proof: &Proof<E>,
+    inputs_acc: F,
+) -> bool
+where
+    E: MultiMillerLoop,
+    F: FnOnce() -> E::G1,
+{
+    bellperson_verifier_core::check_verification_equation(
+        &pvk.alpha_g1_beta_g2,
+        &pvk.neg_gamma_g2,
+        &pvk.neg_delta_g2,
+        &proof.into(),
+        &inputs_acc().to_affine(),
+    )
+}
+
+/// A prefix of the public inputs, accumulated once so that it can be shared by many proofs, see
+/// [`prepare_inputs`] and [`verify_proof_with_prepared_inputs`].
+pub struct PreparedInputs<E: Engine> {
+    /// The input term of the verification equation for the prefix, including `ic[0]`.
+    acc: E::G1,
+    num_inputs: usize,
+    vk_hash: [u8; 32],
+}
+
+impl<E: Engine> Clone for PreparedInputs<E> {
+    fn clone(&self) -> Self {
+        PreparedInputs {
+            acc: self.acc,
+            num_inputs: self.num_inputs,
+            vk_hash: self.vk_hash,
+        }
+    }
+}
+
+impl<E: Engine> PreparedInputs<E> {
+    /// The number of public inputs that are accumulated.
+    pub fn num_inputs(&self) -> usize {
+        self.num_inputs
+    }
+}
+
+/// Accumulates the public inputs `prefix`, which are the first inputs of every proof verified
+/// with the result, see [`verify_proof_with_prepared_inputs`].
+pub fn prepare_inputs<E>(
+    pvk: &PreparedVerifyingKey<E>,
+    prefix: &[E::Fr],
+) -> Result<PreparedInputs<E>, SynthesisError>
+where
+    E: MultiMillerLoop,
+    <<E as Engine>::Fr as PrimeField>::Repr: Sync,
+{
+    if prefix.len() + 1 > pvk.ic.len() {
         return Err(SynthesisError::MalformedVerifyingKey);
     }
 
-    // The original verification equation is:
-    // A * B = alpha * beta + inputs * gamma + C * delta
-    // ... however, we rearrange it so that it is:
-    // A * B - inputs * gamma - C * delta = alpha * beta
-    // or equivalently:
-    // A * B + inputs * (-gamma) + C * (-delta) = alpha * beta
-    // which allows us to do a single final exponentiation.
-
-    // Miller Loop for A * B
-    let mut ml_a_b = Default::default();
-    // Miller Loop for C * (-delta)
-    let mut ml_all = <E as MultiMillerLoop>::Result::default();
-    // Miller Loop for inputs * (-gamma)
-    let mut ml_acc = <E as MultiMillerLoop>::Result::default();
-
-    // Start the two independent miller loops
-    rayon::in_place_scope(|s| {
-        // - Thread 1: Calculate ML A * B
-        let ml_a_b = &mut ml_a_b;
-        s.spawn(move |_| {
-            *ml_a_b = E::multi_miller_loop(&[(&proof.a, &proof.b.into())]);
-        });
+    let mut acc = accumulate_inputs(pvk, 0, prefix);
+    acc.add_assign(&pvk.ic[0]);
 
-        // - Thread 2: Calculate ML C * (-delta)
-        let ml_all = &mut ml_all;
-        s.spawn(move |_| *ml_all = E::multi_miller_loop(&[(&proof.c, &pvk.neg_delta_g2)]));
+    Ok(PreparedInputs {
+        acc,
+        num_inputs: prefix.len(),
+        vk_hash: pvk.vk_hash,
+    })
+}
 
-        // - Accumulate inputs (on the current thread)
-        let subset = pvk.multiscalar.at_point(1);
-        let public_inputs_repr: Vec<_> = public_inputs.iter().map(PrimeField::to_repr).collect();
+/// Verifies a proof whose public inputs are the ones accumulated in `prepared` followed by
+/// `remaining_inputs`. Only the remaining inputs are accumulated, which saves most of the work
+/// on the inputs if many proofs share a long prefix of them.
+///
+/// The result is the same as the one of [`verify_proof`] with all public inputs, but it isn't
+/// cached, see [`set_verification_cache`](super::set_verification_cache).
+pub fn verify_proof_with_prepared_inputs<E>(
+    pvk: &PreparedVerifyingKey<E>,
+    proof: &Proof<E>,
+    prepared: &PreparedInputs<E>,
+    remaining_inputs: &[E::Fr],
+) -> Result<bool, SynthesisError>
+where
+    E: MultiMillerLoop,
+    <<E as Engine>::Fr as PrimeField>::Repr: Sync,
+{
+    if prepared.vk_hash != pvk.vk_hash
+        || prepared.num_inputs + remaining_inputs.len() + 1 != pvk.ic.len()
+    {
+        return Err(SynthesisError::MalformedVerifyingKey);
+    }
 
-        let mut acc = multiscalar::par_multiscalar::<&multiscalar::Getter<E::G1Affine>, E::G1Affine>(
-            &multiscalar::ScalarList::Slice(&public_inputs_repr),
-            &subset,
-            std::mem::size_of::<<E::Fr as PrimeField>::Repr>() * 8,
-        );
+    Ok(verify_proof_with_inputs_acc(pvk, proof, || {
+        let mut acc = accumulate_inputs(pvk, prepared.num_inputs, remaining_inputs);
+        acc.add_assign(&prepared.acc);
+        acc
+    }))
+}
 
-        acc.add_assign(&pvk.ic[0]);
+/// Verify each proof individually and in parallel, stopping at the first failure.
+///
+/// This is a complement to [`verify_proofs_batch`]: it does not amortize the pairing cost across
+/// proofs, but it terminates early and reports which proof is invalid. Returns `Ok(None)` if all
+/// proofs verify, otherwise `Ok(Some(index))` with the lowest index of a proof that failed.
+pub fn verify_all<E>(
+    pvk: &PreparedVerifyingKey<E>,
+    proofs: &[&Proof<E>],
+    public_inputs: &[Vec<E::Fr>],
+) -> Result<Option<usize>, SynthesisError>
+where
+    E: MultiMillerLoop,
+    <E::Fr as PrimeField>::Repr: Sync,
+{
+    if proofs.len() != public_inputs.len() {
+        return Err(SynthesisError::IncompatibleLengthVector(format!(
+            "proofs: {}, public inputs: {}",
+            proofs.len(),
+            public_inputs.len()
+        )));
+    }
 
-        // Calculate ML inputs * (-gamma)
-        let acc_aff = acc.to_affine();
-        ml_acc = E::multi_miller_loop(&[(&acc_aff, &pvk.neg_gamma_g2)]);
-    });
-    // Wait for the threaded miller loops to finish
+    proofs
+        .par_iter()
+        .zip(public_inputs.par_iter())
+        .enumerate()
+        .map(|(i, (proof, inputs))| verify_proof(pvk, proof, inputs).map(|valid| (i, valid)))
+        .find_first(|res| !matches!(res, Ok((_, true))))
+        .transpose()
+        .map(|failed| failed.map(|(i, _)| i))
+}
 
-    // Combine the results.
-    ml_all += ml_a_b;
-    ml_all += ml_acc;
+/// [`verify_all`] with a runtime configuration.
+///
+/// The proofs are verified chunk by chunk, no further chunks are verified once a proof failed.
+/// Single proofs are always verified on the CPU, so the backend doesn't matter.
+pub fn verify_all_with_config<E>(
+    config: &VerifierConfig,
+    pvk: &PreparedVerifyingKey<E>,
+    proofs: &[&Proof<E>],
+    public_inputs: &[Vec<E::Fr>],
+) -> Result<Option<usize>, SynthesisError>
+where
+    E: MultiMillerLoop,
+    <E::Fr as PrimeField>::Repr: Sync,
+{
+    if proofs.len() != public_inputs.len() {
+        return Err(SynthesisError::IncompatibleLengthVector(format!(
+            "proofs: {}, public inputs: {}",
+            proofs.len(),
+            public_inputs.len()
+        )));
+    }
 
-    // Calculate the final exponentiation
-    let actual = ml_all.final_exponentiation();
+    let chunk_size = config.chunk_size(proofs.len());
+    config.install(|| {
+        for (i, (proofs, public_inputs)) in proofs
+            .chunks(chunk_size)
+            .zip(public_inputs.chunks(chunk_size))
+            .enumerate()
+        {
+            if let Some(failed) = verify_all(pvk, proofs, public_inputs)? {
+                return Ok(Some(i * chunk_size + failed));
+            }
+        }
+        Ok(None)
+    })
+}
 
-    Ok(actual == pvk.alpha_g1_beta_g2)
+/// [`verify_proofs_batch`] with a runtime configuration.
+///
+/// Every chunk of proofs is batch verified with its own random coefficients, no further chunks
+/// are verified once a chunk failed.
+pub fn verify_proofs_batch_with_config<E, R>(
+    config: &VerifierConfig,
+    pvk: &PreparedVerifyingKey<E>,
+    rng: &mut R,
+    proofs: &[&Proof<E>],
+    public_inputs: &[Vec<E::Fr>],
+) -> Result<bool, SynthesisError>
+where
+    E: MultiMillerLoop,
+    <E::Fr as PrimeField>::Repr: Sync + Copy,
+    E::G1Affine: GpuName,
+    R: rand::RngCore + Send,
+{
+    if proofs.len() != public_inputs.len() {
+        return Err(SynthesisError::IncompatibleLengthVector(format!(
+            "proofs: {}, public inputs: {}",
+            proofs.len(),
+            public_inputs.len()
+        )));
+    }
+
+    let gpu_multiexp = config.backend.gpu_multiexp::<E>()?;
+    let chunk_size = config.chunk_size(proofs.len());
+    config.install(|| {
+        for (proofs, public_inputs) in proofs
+            .chunks(chunk_size)
+            .zip(public_inputs.chunks(chunk_size))
+        {
+            if !verify_batch(pvk, rng, proofs, public_inputs, gpu_multiexp)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    })
+}
+
+/// The number of proofs [`verify_proofs_batch_streaming`] combines at once if the configuration
+/// doesn't set a chunk size.
+const STREAMING_CHUNK_SIZE: usize = 1024;
+
+/// Randomized batch verification of a stream of proofs with their public inputs.
+///
+/// Unlike with [`verify_proofs_batch_with_config`], the proofs don't need to be in memory at
+/// once. They are consumed in chunks of [`VerifierConfig::chunk_size`] proofs, 1024 by default,
+/// and folded into a single random linear combination, which is checked with one final
+/// exponentiation after the stream ended. Returns `Ok(true)` if there are no proofs.
+pub fn verify_proofs_batch_streaming<E, R, I>(
+    config: &VerifierConfig,
+    pvk: &PreparedVerifyingKey<E>,
+    rng: &mut R,
+    proofs: I,
+) -> Result<bool, SynthesisError>
+where
+    E: MultiMillerLoop,
+    <E::Fr as PrimeField>::Repr: Sync + Copy,
+    E::G1Affine: GpuName,
+    R: rand::RngCore + Send,
+    I: IntoIterator<Item = (Proof<E>, Vec<E::Fr>)>,
+    I::IntoIter: Send,
+{
+    let gpu_multiexp = config.backend.gpu_multiexp::<E>()?;
+    let chunk_size = config.chunk_size.unwrap_or(STREAMING_CHUNK_SIZE).max(1);
+    let mut proofs = proofs.into_iter();
+    config.install(move || {
+        let mut acc = BatchAccumulator::<E>::new(pvk.ic.len());
+        loop {
+            let chunk = proofs.by_ref().take(chunk_size).collect::<Vec<_>>();
+            if chunk.is_empty() {
+                break;
+            }
+            acc.add(pvk, rng, &chunk, gpu_multiexp)?;
+        }
+        acc.check(pvk, gpu_multiexp)
+    })
+}
+
+/// The random linear combination of the pairing checks of a stream of proofs, see
+/// [`verify_proofs_batch_streaming`].
+struct BatchAccumulator<E: MultiMillerLoop> {
+    /// \sum z_j at index 0, followed by \sum(z_j * a_j,i) for every public input i.
+    input_scalars: Vec<E::Fr>,
+    /// Accum_Delta = \sum(z_j * pi_j,C)
+    acc_c: E::G1,
+    /// Accum_AB = mul_j(ml((zj*proof_aj), -proof_bj)), `None` before the first proof.
+    acc_ab: Option<<E as MultiMillerLoop>::Result>,
+}
+
+impl<E: MultiMillerLoop> BatchAccumulator<E>
+where
+    <E::Fr as PrimeField>::Repr: Sync + Copy,
+{
+    fn new(num_ic: usize) -> Self {
+        BatchAccumulator {
+            input_scalars: vec![E::Fr::ZERO; num_ic],
+            acc_c: E::G1::identity(),
+            acc_ab: None,
+        }
+    }
+
+    /// Adds the proofs of a chunk with fresh random coefficients.
+    fn add<R: rand::RngCore>(
+        &mut self,
+        pvk: &PreparedVerifyingKey<E>,
+        rng: &mut R,
+        chunk: &[(Proof<E>, Vec<E::Fr>)],
+        gpu_multiexp: Option<GpuMultiexp<E>>,
+    ) -> Result<(), SynthesisError> {
+        for (_, public_inputs) in chunk {
+            if (public_inputs.len() + 1) != pvk.ic.len() {
+                return Err(SynthesisError::MalformedVerifyingKey);
+            }
+        }
+
+        let rand_z = random_coefficients::<E::Fr, _>(rng, chunk.len());
+        let rand_z_repr: Vec<_> = rand_z.iter().map(PrimeField::to_repr).collect();
+
+        for ((_, public_inputs), z) in chunk.iter().zip(rand_z.iter()) {
+            self.input_scalars[0].add_assign(z);
+            for (acc, input) in self.input_scalars[1..].iter_mut().zip(public_inputs.iter()) {
+                acc.add_assign(&(*z * input));
+            }
+        }
+
+        let points: Vec<_> = chunk.iter().map(|(proof, _)| proof.c).collect();
+        let acc_c = match gpu_multiexp {
+            Some(gpu_multiexp) => gpu_multiexp(Arc::new(points), Arc::new(rand_z_repr))?,
+            None => {
+                let pre = multiscalar::precompute_fixed_window::<E::G1Affine>(&points, 1);
+                multiscalar::multiscalar::<E::G1Affine>(
+                    &rand_z_repr,
+                    &pre,
+                    std::mem::size_of::<<E::Fr as PrimeField>::Repr>() * 8,
+                )
+            }
+        };
+        self.acc_c.add_assign(&acc_c);
+
+        let accum_ab_mls: Vec<_> = chunk
+            .par_iter()
+            .zip(rand_z.par_iter())
+            .map(|((proof, _), z)| {
+                // [z_j] pi_j,A
+                let mul_a = proof.a.mul(*z);
+
+                // -pi_j,B
+                let cur_neg_b = -proof.b.to_curve();
+
+                E::multi_miller_loop(&[(&mul_a.to_affine(), &cur_neg_b.to_affine().into())])
+            })
+            .collect();
+        for accum in accum_ab_mls {
+            self.acc_ab = Some(match self.acc_ab.take() {
+                Some(acc_ab) => acc_ab + accum,
+                None => accum,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks the combined pairing equation of all proofs that were added.
+    fn check(
+        self,
+        pvk: &PreparedVerifyingKey<E>,
+        gpu_multiexp: Option<GpuMultiexp<E>>,
+    ) -> Result<bool, SynthesisError> {
+        let mut ml_all = match self.acc_ab {
+            Some(acc_ab) => acc_ab,
+            None => return Ok(true),
+        };
+
+        // \sum Accum_Gamma
+        let scalars: Vec<_> = self.input_scalars.iter().map(PrimeField::to_repr).collect();
+        let acc_g_psi = match gpu_multiexp {
+            Some(gpu_multiexp) => gpu_multiexp(Arc::new(pvk.ic.clone()), Arc::new(scalars))?,
+            None => multiscalar::par_multiscalar::<&multiscalar::Getter<E::G1Affine>, _>(
+                &multiscalar::ScalarList::Slice(&scalars),
+                &pvk.multiscalar,
+                256,
+            ),
+        };
+
+        ml_all += E::multi_miller_loop(&[(&acc_g_psi.to_affine(), &pvk.gamma_g2)]);
+        ml_all += E::multi_miller_loop(&[(&self.acc_c.to_affine(), &pvk.delta_g2)]);
+
+        // Y^-Accum_Y
+        let y = pvk.alpha_g1_beta_g2 * -self.input_scalars[0];
+
+        Ok(ml_all.final_exponentiation() == y)
+    }
 }
 
 /// Randomized batch verification - see Appendix B.2 in Zcash spec
@@ -112,6 +614,23 @@ pub fn verify_proofs_batch<'a, E, R>(
     proofs: &[&Proof<E>],
     public_inputs: &[Vec<E::Fr>],
 ) -> Result<bool, SynthesisError>
+where
+    E: MultiMillerLoop,
+    <E::Fr as PrimeField>::Repr: Sync + Copy,
+    R: rand::RngCore,
+{
+    verify_batch(pvk, rng, proofs, public_inputs, None)
+}
+
+/// Randomized batch verification, running the multi-exponentiations with `gpu_multiexp` if it's
+/// set.
+fn verify_batch<E, R>(
+    pvk: &PreparedVerifyingKey<E>,
+    rng: &mut R,
+    proofs: &[&Proof<E>],
+    public_inputs: &[Vec<E::Fr>],
+    gpu_multiexp: Option<GpuMultiexp<E>>,
+) -> Result<bool, SynthesisError>
 where
     E: MultiMillerLoop,
     <E::Fr as PrimeField>::Repr: Sync + Copy,
@@ -135,43 +654,14 @@ where
     let proof_num = proofs.len();
 
     // Choose random coefficients for combining the proofs.
-    let mut rand_z_repr: Vec<_> = Vec::with_capacity(proof_num);
-    let mut rand_z: Vec<_> = Vec::with_capacity(proof_num);
-    let mut accum_y = E::Fr::ZERO;
-
-    for _ in 0..proof_num {
-        use rand::Rng;
-
-        let t: u128 = rng.gen();
-
-        let mut repr = E::Fr::ZERO.to_repr();
-        let mut repr_u64s = le_bytes_to_u64s(repr.as_ref());
-        assert!(repr_u64s.len() > 1);
-
-        repr_u64s[0] = (t & (-1i64 as u128) >> 64) as u64;
-        repr_u64s[1] = (t >> 64) as u64;
-
-        for (i, limb) in repr_u64s.iter().enumerate() {
-            let start = i * 8;
-            let stop = start + 8;
-            repr.as_mut()[start..stop].copy_from_slice(&limb.to_le_bytes());
-        }
-
-        let fr = E::Fr::from_repr(repr).unwrap();
-        let repr = fr.to_repr();
-
-        // calculate sum
-        accum_y.add_assign(&fr);
-        // store FrRepr
-        rand_z_repr.push(repr);
-        // store Fr
-        rand_z.push(fr);
-    }
+    let rand_z = random_coefficients::<E::Fr, _>(rng, proof_num);
+    let rand_z_repr: Vec<_> = rand_z.iter().map(PrimeField::to_repr).collect();
+    let accum_y: E::Fr = rand_z.iter().sum();
 
     // MillerLoop(\sum Accum_Gamma)
-    let mut ml_g = <E as MultiMillerLoop>::Result::default();
+    let mut ml_g = Ok(<E as MultiMillerLoop>::Result::default());
     // MillerLoop(Accum_Delta)
-    let mut ml_d = <E as MultiMillerLoop>::Result::default();
+    let mut ml_d = Ok(<E as MultiMillerLoop>::Result::default());
     // MillerLoop(Accum_AB)
     let mut acc_ab = <E as MultiMillerLoop>::Result::default();
     // Y^-Accum_Y
@@ -180,7 +670,7 @@ where
     let accum_y = &accum_y;
     let rand_z_repr = &rand_z_repr;
 
-    rayon::in_place_scope(|s| {
+    multicore::in_place_scope(|s| {
         // - Thread 1: Calculate MillerLoop(\sum Accum_Gamma)
         let ml_g = &mut ml_g;
         s.spawn(move |_| {
@@ -207,14 +697,29 @@ where
             };
 
             // \sum Accum_Gamma
-            let acc_g_psi = multiscalar::par_multiscalar::<_, E::G1Affine>(
-                &multiscalar::ScalarList::Getter(scalar_getter, num_inputs + 1),
-                &pvk.multiscalar,
-                256,
-            );
+            let acc_g_psi = match gpu_multiexp {
+                Some(gpu_multiexp) => {
+                    let scalars = (0..num_inputs + 1).map(scalar_getter).collect();
+                    match gpu_multiexp(Arc::new(pvk.ic.clone()), Arc::new(scalars)) {
+                        Ok(acc_g_psi) => acc_g_psi,
+                        Err(e) => {
+                            *ml_g = Err(e);
+                            return;
+                        }
+                    }
+                }
+                None => multiscalar::par_multiscalar::<_, E::G1Affine>(
+                    &multiscalar::ScalarList::Getter(scalar_getter, num_inputs + 1),
+                    &pvk.multiscalar,
+                    256,
+                ),
+            };
 
             // MillerLoop(acc_g_psi, vk.gamma)
-            *ml_g = E::multi_miller_loop(&[(&acc_g_psi.to_affine(), &pvk.gamma_g2)]);
+            *ml_g = Ok(E::multi_miller_loop(&[(
+                &acc_g_psi.to_affine(),
+                &pvk.gamma_g2,
+            )]));
         });
 
         // - Thread 2: Calculate MillerLoop(Accum_Delta)
@@ -223,16 +728,27 @@ where
             let points: Vec<_> = proofs.iter().map(|p| p.c).collect();
 
             // Accum_Delta
-            let acc_d: E::G1 = {
-                let pre = multiscalar::precompute_fixed_window::<E::G1Affine>(&points, 1);
-                multiscalar::multiscalar::<E::G1Affine>(
-                    rand_z_repr,
-                    &pre,
-                    std::mem::size_of::<<E::Fr as PrimeField>::Repr>() * 8,
-                )
+            let acc_d: E::G1 = match gpu_multiexp {
+                Some(gpu_multiexp) => {
+                    match gpu_multiexp(Arc::new(points), Arc::new(rand_z_repr.clone())) {
+                        Ok(acc_d) => acc_d,
+                        Err(e) => {
+                            *ml_d = Err(e);
+                            return;
+                        }
+                    }
+                }
+                None => {
+                    let pre = multiscalar::precompute_fixed_window::<E::G1Affine>(&points, 1);
+                    multiscalar::multiscalar::<E::G1Affine>(
+                        rand_z_repr,
+                        &pre,
+                        std::mem::size_of::<<E::Fr as PrimeField>::Repr>() * 8,
+                    )
+                }
             };
 
-            *ml_d = E::multi_miller_loop(&[(&acc_d.to_affine(), &pvk.delta_g2)]);
+            *ml_d = Ok(E::multi_miller_loop(&[(&acc_d.to_affine(), &pvk.delta_g2)]));
         });
 
         // - Thread 3: Calculate MillerLoop(Accum_AB)
@@ -268,9 +784,157 @@ where
     });
 
     let mut ml_all = acc_ab;
-    ml_all += ml_d;
-    ml_all += ml_g;
+    ml_all += ml_d?;
+    ml_all += ml_g?;
+
+    let actual = ml_all.final_exponentiation();
+    Ok(actual == y)
+}
+
+/// Randomized batch verification of proofs for several circuits.
+///
+/// Every proof comes with the prepared verifying key of its circuit and its public inputs. The
+/// proofs are grouped by verifying key and the pairing checks of all groups are combined into a
+/// single multi Miller loop and final exponentiation, instead of verifying a batch per circuit.
+/// Returns `Ok(true)` if there are no proofs.
+pub fn verify_proofs_batch_multi<E, R>(
+    rng: &mut R,
+    vks_with_proofs: &[(&PreparedVerifyingKey<E>, &Proof<E>, &[E::Fr])],
+) -> Result<bool, SynthesisError>
+where
+    E: MultiMillerLoop,
+    <E::Fr as PrimeField>::Repr: Sync + Copy,
+    R: rand::RngCore,
+{
+    for (pvk, _, public_inputs) in vks_with_proofs {
+        if (public_inputs.len() + 1) != pvk.ic.len() {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+    }
+
+    if vks_with_proofs.is_empty() {
+        return Ok(true);
+    }
+
+    // Group the proofs by verifying key, in the order the keys first appear.
+    let mut group_indices = HashMap::new();
+    let mut groups: Vec<(&PreparedVerifyingKey<E>, Vec<usize>)> = Vec::new();
+    for (j, (pvk, _, _)) in vks_with_proofs.iter().enumerate() {
+        let group = *group_indices.entry(pvk.vk_hash).or_insert_with(|| {
+            groups.push((*pvk, Vec::new()));
+            groups.len() - 1
+        });
+        groups[group].1.push(j);
+    }
+
+    // Choose random coefficients for combining the proofs.
+    let rand_z = random_coefficients::<E::Fr, _>(rng, vks_with_proofs.len());
+
+    // For every group v:
+    // MillerLoop(\sum Accum_Gamma_v) * MillerLoop(Accum_Delta_v) and Y_v^-Accum_Y_v
+    let (acc_groups, y) = groups
+        .par_iter()
+        .map(|(pvk, indices)| {
+            // Accum_Y_v followed by \sum_j z_j * a_j,i
+            let mut scalars = vec![E::Fr::ZERO; pvk.ic.len()];
+            for &j in indices {
+                let (_, _, public_inputs) = vks_with_proofs[j];
+                scalars[0] += rand_z[j];
+                for (scalar, input) in scalars[1..].iter_mut().zip(public_inputs) {
+                    *scalar += rand_z[j] * input;
+                }
+            }
+            let accum_y = scalars[0];
+            let scalars: Vec<_> = scalars.iter().map(PrimeField::to_repr).collect();
+
+            // \sum Accum_Gamma_v
+            let acc_g_psi =
+                multiscalar::par_multiscalar::<&multiscalar::Getter<E::G1Affine>, E::G1Affine>(
+                    &multiscalar::ScalarList::Slice(&scalars),
+                    &pvk.multiscalar,
+                    256,
+                );
+
+            // Accum_Delta_v
+            let points: Vec<_> = indices.iter().map(|&j| vks_with_proofs[j].1.c).collect();
+            let rand_z_repr: Vec<_> = indices.iter().map(|&j| rand_z[j].to_repr()).collect();
+            let pre = multiscalar::precompute_fixed_window::<E::G1Affine>(&points, 1);
+            let acc_d = multiscalar::multiscalar::<E::G1Affine>(
+                &rand_z_repr,
+                &pre,
+                std::mem::size_of::<<E::Fr as PrimeField>::Repr>() * 8,
+            );
+
+            let ml = E::multi_miller_loop(&[
+                (&acc_g_psi.to_affine(), &pvk.gamma_g2),
+                (&acc_d.to_affine(), &pvk.delta_g2),
+            ]);
+            (ml, pvk.alpha_g1_beta_g2 * -accum_y)
+        })
+        .reduce(
+            || {
+                (
+                    <E as MultiMillerLoop>::Result::default(),
+                    <E as Engine>::Gt::identity(),
+                )
+            },
+            |(mut ml, y), (ml_group, y_group)| {
+                ml += ml_group;
+                (ml, y + y_group)
+            },
+        );
+
+    // Accum_AB = mul_j(ml((zj*proof_aj), -proof_bj))
+    let acc_ab = vks_with_proofs
+        .par_iter()
+        .zip(rand_z.par_iter())
+        .map(|((_, proof, _), rand)| {
+            // [z_j] pi_j,A
+            let mul_a = proof.a.mul(*rand);
+
+            // -pi_j,B
+            let cur_neg_b = -proof.b.to_curve();
+
+            E::multi_miller_loop(&[(&mul_a.to_affine(), &cur_neg_b.to_affine().into())])
+        })
+        .reduce(<E as MultiMillerLoop>::Result::default, |mut acc, ml| {
+            acc += ml;
+            acc
+        });
+
+    let mut ml_all = acc_ab;
+    ml_all += acc_groups;
 
     let actual = ml_all.final_exponentiation();
     Ok(actual == y)
 }
+
+/// Chooses `n` random 128-bit coefficients for combining proofs.
+fn random_coefficients<F, R>(rng: &mut R, n: usize) -> Vec<F>
+where
+    F: PrimeField,
+    R: rand::RngCore,
+{
+    (0..n)
+        .map(|_| {
+            use rand::Rng;
+
+            let t: u128 = rng.gen();
+
+            let mut repr = F::ZERO.to_repr();
+            let mut repr_u64s = le_bytes_to_u64s(repr.as_ref());
+            assert!(repr_u64s.len() > 1);
+
+            repr_u64s[0] = (t & (-1i64 as u128) >> 64) as u64;
+            repr_u64s[1] = (t >> 64) as u64;
+
+            for (i, limb) in repr_u64s.iter().enumerate() {
+                let start = i * 8;
+                let stop = start + 8;
+                repr.as_mut()[start..stop].copy_from_slice(&limb.to_le_bytes());
+            }
+
+            F::from_repr(repr).unwrap()
+        })
+        .collect()
+}
@@ -1,14 +1,15 @@
-use group::{prime::PrimeCurveAffine, UncompressedEncoding};
+use group::{prime::PrimeCurveAffine, GroupEncoding, UncompressedEncoding};
 use pairing::{Engine, MultiMillerLoop};
 
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 #[cfg(not(target_arch = "wasm32"))]
 use memmap2::Mmap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::io::{self, Read, Write};
 #[cfg(not(target_arch = "wasm32"))]
 use std::mem;
 
-use super::multiscalar;
+use super::{cbor, multiscalar, prepare_verifying_key, serde_bytes};
 
 #[derive(Debug, Clone)]
 pub struct VerifyingKey<E: Engine + MultiMillerLoop> {
@@ -50,11 +51,36 @@ impl<E: Engine + MultiMillerLoop> PartialEq for VerifyingKey<E> {
     }
 }
 
+/// Serializes the verifying key in the compressed encoding of [`VerifyingKey::write_compressed`].
+impl<E: Engine + MultiMillerLoop> Serialize for VerifyingKey<E> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut v = Vec::new();
+        self.write_compressed(&mut v)
+            .map_err(serde::ser::Error::custom)?;
+        serde_bytes::serialize(&v, s)
+    }
+}
+
+impl<'de, E: Engine + MultiMillerLoop> Deserialize<'de> for VerifyingKey<E> {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        serde_bytes::deserialize(d, "a verifying key", |source| {
+            VerifyingKey::read_compressed(source)
+        })
+    }
+}
+
 fn read_uncompressed_point<C: UncompressedEncoding>(repr: &C::Uncompressed) -> io::Result<C> {
     let opt = C::from_uncompressed(repr);
     Option::from(opt).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not on curve"))
 }
 
+fn read_compressed_point<C: GroupEncoding, R: Read>(mut reader: R) -> io::Result<C> {
+    let mut repr = C::Repr::default();
+    reader.read_exact(repr.as_mut())?;
+    let opt = C::from_bytes(&repr);
+    Option::from(opt).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not on curve"))
+}
+
 impl<E: Engine + MultiMillerLoop> VerifyingKey<E> {
     pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
         writer.write_all(self.alpha_g1.to_uncompressed().as_ref())?;
@@ -120,6 +146,135 @@ impl<E: Engine + MultiMillerLoop> VerifyingKey<E> {
         })
     }
 
+    /// Writes the verifying key like [`VerifyingKey::write`], but with compressed points.
+    pub fn write_compressed<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(self.alpha_g1.to_bytes().as_ref())?;
+        writer.write_all(self.beta_g1.to_bytes().as_ref())?;
+        writer.write_all(self.beta_g2.to_bytes().as_ref())?;
+        writer.write_all(self.gamma_g2.to_bytes().as_ref())?;
+        writer.write_all(self.delta_g1.to_bytes().as_ref())?;
+        writer.write_all(self.delta_g2.to_bytes().as_ref())?;
+        writer.write_u32::<BigEndian>(self.ic.len() as u32)?;
+        for ic in &self.ic {
+            writer.write_all(ic.to_bytes().as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a verifying key written by [`VerifyingKey::write_compressed`].
+    pub fn read_compressed<R: Read>(mut reader: R) -> io::Result<Self> {
+        let alpha_g1 = read_compressed_point(&mut reader)?;
+        let beta_g1 = read_compressed_point(&mut reader)?;
+        let beta_g2 = read_compressed_point(&mut reader)?;
+        let gamma_g2 = read_compressed_point(&mut reader)?;
+        let delta_g1 = read_compressed_point(&mut reader)?;
+        let delta_g2 = read_compressed_point(&mut reader)?;
+
+        let ic_len = reader.read_u32::<BigEndian>()? as usize;
+        let mut ic = vec![];
+        for _ in 0..ic_len {
+            let g1: E::G1Affine = read_compressed_point(&mut reader)?;
+            if g1.is_identity().into() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "point at infinity",
+                ));
+            }
+            ic.push(g1);
+        }
+
+        Ok(VerifyingKey {
+            alpha_g1,
+            beta_g1,
+            beta_g2,
+            gamma_g2,
+            delta_g1,
+            delta_g2,
+            ic,
+        })
+    }
+
+    /// Returns the encoding of [`VerifyingKey::write_compressed`] as a hex string.
+    pub fn to_hex(&self) -> String {
+        serde_bytes::to_hex(&self.to_compressed_bytes())
+    }
+
+    /// Reads a verifying key from the hex string of [`VerifyingKey::to_hex`], with or without a
+    /// `0x` prefix.
+    pub fn from_hex(hex: &str) -> io::Result<Self> {
+        serde_bytes::read_hex(hex, |source| Self::read_compressed(source))
+    }
+
+    /// Returns the encoding of [`VerifyingKey::write_compressed`] in padded standard base64.
+    pub fn to_base64(&self) -> String {
+        serde_bytes::to_base64(&self.to_compressed_bytes())
+    }
+
+    /// Reads a verifying key from the base64 string of [`VerifyingKey::to_base64`].
+    pub fn from_base64(base64: &str) -> io::Result<Self> {
+        serde_bytes::read_base64(base64, |source| Self::read_compressed(source))
+    }
+
+    fn to_compressed_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_compressed(&mut bytes)
+            .expect("writing to a Vec never fails");
+        bytes
+    }
+
+    /// Writes the verifying key as a deterministic CBOR array of its compressed points, where
+    /// the last element is the (nested) array of `ic` points.
+    pub fn write_cbor<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        cbor::write_array_header(&mut writer, 7)?;
+        cbor::write_point(&mut writer, &self.alpha_g1)?;
+        cbor::write_point(&mut writer, &self.beta_g1)?;
+        cbor::write_point(&mut writer, &self.beta_g2)?;
+        cbor::write_point(&mut writer, &self.gamma_g2)?;
+        cbor::write_point(&mut writer, &self.delta_g1)?;
+        cbor::write_point(&mut writer, &self.delta_g2)?;
+        cbor::write_array_header(&mut writer, self.ic.len())?;
+        for ic in &self.ic {
+            cbor::write_point(&mut writer, ic)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a verifying key written by [`VerifyingKey::write_cbor`].
+    pub fn read_cbor<R: Read>(mut reader: R) -> io::Result<Self> {
+        cbor::read_array_header_exact(&mut reader, 7)?;
+        let alpha_g1 = cbor::read_point(&mut reader)?;
+        let beta_g1 = cbor::read_point(&mut reader)?;
+        let beta_g2 = cbor::read_point(&mut reader)?;
+        let gamma_g2 = cbor::read_point(&mut reader)?;
+        let delta_g1 = cbor::read_point(&mut reader)?;
+        let delta_g2 = cbor::read_point(&mut reader)?;
+
+        let ic_len = cbor::read_array_header(&mut reader)?;
+        let mut ic = vec![];
+        for _ in 0..ic_len {
+            let g1: E::G1Affine = cbor::read_point(&mut reader)?;
+            if g1.is_identity().into() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "point at infinity",
+                ));
+            }
+            ic.push(g1);
+        }
+
+        Ok(VerifyingKey {
+            alpha_g1,
+            beta_g1,
+            beta_g2,
+            gamma_g2,
+            delta_g1,
+            delta_g2,
+            ic,
+        })
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     pub fn read_mmap(mmap: &Mmap, offset: &mut usize) -> io::Result<Self> {
         let u32_len = mem::size_of::<u32>();
@@ -197,6 +352,21 @@ impl<E: Engine + MultiMillerLoop> VerifyingKey<E> {
     }
 }
 
+/// The parts that are needed to verify proofs without the standard library.
+impl<E: Engine + MultiMillerLoop> From<&VerifyingKey<E>>
+    for bellperson_verifier_core::VerifyingKey<E>
+{
+    fn from(vk: &VerifyingKey<E>) -> Self {
+        bellperson_verifier_core::VerifyingKey {
+            alpha_g1: vk.alpha_g1,
+            beta_g2: vk.beta_g2,
+            gamma_g2: vk.gamma_g2,
+            delta_g2: vk.delta_g2,
+            ic: vk.ic.clone(),
+        }
+    }
+}
+
 pub struct PreparedVerifyingKey<E>
 where
     E: MultiMillerLoop,
@@ -220,4 +390,24 @@ where
     pub(crate) alpha_g1: E::G1,
     pub(crate) beta_g2: <E as MultiMillerLoop>::G2Prepared,
     pub(crate) ic_projective: Vec<E::G1>,
+
+    /// SHA-256 of the serialized `VerifyingKey`.
+    pub(crate) vk_hash: [u8; 32],
+
+    /// The key this one was prepared from, to serialize it.
+    pub(crate) vk: VerifyingKey<E>,
+}
+
+/// Serializes the verifying key the prepared key was prepared from, deserializing prepares it
+/// again.
+impl<E: MultiMillerLoop> Serialize for PreparedVerifyingKey<E> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        self.vk.serialize(s)
+    }
+}
+
+impl<'de, E: MultiMillerLoop> Deserialize<'de> for PreparedVerifyingKey<E> {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        VerifyingKey::deserialize(d).map(|vk| prepare_verifying_key(&vk))
+    }
 }
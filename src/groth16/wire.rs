@@ -0,0 +1,505 @@
+//! [Protocol Buffers] encodings of proofs, verifying keys and aggregate proofs, to exchange them
+//! with services in other languages without agreeing on a byte layout. The messages are defined
+//! in [`SCHEMA`], other languages generate their code from it.
+//!
+//! Encoding writes every field, in the order of the field numbers, so the same value always
+//! encodes to the same bytes. Decoding accepts the fields in any order and skips unknown fields,
+//! like generated code does, so the messages can be extended with new fields. All fields of the
+//! schema are required though, and points are checked to be in the subgroup.
+//!
+//! [Protocol Buffers]: https://protobuf.dev/programming-guides/encoding/
+
+use std::io;
+
+use blstrs::Compress;
+use group::{prime::PrimeCurveAffine, Curve, GroupEncoding};
+use pairing::{Engine, MultiMillerLoop};
+
+use super::aggregate::{AggregateProof, GipaProof, TippMippProof};
+use super::{Proof, VerifyingKey};
+
+/// The schema of the messages, in the proto3 language.
+pub const SCHEMA: &str = include_str!("wire.proto");
+
+const WIRE_VARINT: u64 = 0;
+const WIRE_FIXED64: u64 = 1;
+const WIRE_LEN: u64 = 2;
+const WIRE_FIXED32: u64 = 5;
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid protobuf message: {}", msg),
+    )
+}
+
+/// A message being encoded. Its fields have to be added in the order of their numbers.
+#[derive(Default)]
+struct Encoder(Vec<u8>);
+
+impl Encoder {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    fn key(&mut self, field: u32, wire_type: u64) {
+        self.varint((u64::from(field) << 3) | wire_type);
+    }
+
+    fn uint32(&mut self, field: u32, value: u32) {
+        self.key(field, WIRE_VARINT);
+        self.varint(u64::from(value));
+    }
+
+    fn bytes(&mut self, field: u32, bytes: &[u8]) {
+        self.key(field, WIRE_LEN);
+        self.varint(bytes.len() as u64);
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn point<G: GroupEncoding>(&mut self, field: u32, point: &G) {
+        self.bytes(field, point.to_bytes().as_ref());
+    }
+
+    fn gt<Gt: Compress + Copy>(&mut self, field: u32, gt: &Gt) {
+        let mut bytes = Vec::new();
+        (*gt)
+            .write_compressed(&mut bytes)
+            .expect("writing to a Vec never fails");
+        self.bytes(field, &bytes);
+    }
+
+    fn message(&mut self, field: u32, message: Encoder) {
+        self.bytes(field, &message.0);
+    }
+}
+
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+}
+
+/// The fields of a decoded message in the order they appear, without the fixed size ones, which
+/// the schema doesn't use.
+struct Fields<'a>(Vec<(u32, Value<'a>)>);
+
+impl<'a> Fields<'a> {
+    fn parse(mut bytes: &'a [u8]) -> io::Result<Self> {
+        let mut fields = Vec::new();
+        while !bytes.is_empty() {
+            let key = read_varint(&mut bytes)?;
+            let field = match u32::try_from(key >> 3) {
+                Ok(field) if field != 0 => field,
+                _ => return Err(invalid_data("invalid field number")),
+            };
+            let value = match key & 0x7 {
+                WIRE_VARINT => Value::Varint(read_varint(&mut bytes)?),
+                WIRE_LEN => {
+                    let len = read_varint(&mut bytes)?;
+                    Value::Bytes(take(&mut bytes, len)?)
+                }
+                WIRE_FIXED64 => {
+                    take(&mut bytes, 8)?;
+                    continue;
+                }
+                WIRE_FIXED32 => {
+                    take(&mut bytes, 4)?;
+                    continue;
+                }
+                _ => return Err(invalid_data("unsupported wire type")),
+            };
+            fields.push((field, value));
+        }
+        Ok(Fields(fields))
+    }
+
+    /// Returns the last value of a scalar field, like generated code does.
+    fn uint32(&self, field: u32) -> io::Result<u32> {
+        match self.0.iter().rev().find(|(number, _)| *number == field) {
+            Some((_, Value::Varint(value))) => {
+                u32::try_from(*value).map_err(|_| invalid_data("integer out of range"))
+            }
+            Some((_, Value::Bytes(_))) => Err(invalid_data("unexpected wire type")),
+            None => Err(invalid_data("missing field")),
+        }
+    }
+
+    fn repeated_bytes(&self, field: u32) -> io::Result<Vec<&'a [u8]>> {
+        self.0
+            .iter()
+            .filter(|(number, _)| *number == field)
+            .map(|(_, value)| match value {
+                Value::Bytes(bytes) => Ok(*bytes),
+                Value::Varint(_) => Err(invalid_data("unexpected wire type")),
+            })
+            .collect()
+    }
+
+    /// Returns the last value of a bytes or message field.
+    fn bytes(&self, field: u32) -> io::Result<&'a [u8]> {
+        self.repeated_bytes(field)?
+            .pop()
+            .ok_or_else(|| invalid_data("missing field"))
+    }
+
+    fn point<G: GroupEncoding>(&self, field: u32) -> io::Result<G> {
+        decode_point(self.bytes(field)?)
+    }
+
+    fn repeated_points<G: GroupEncoding>(&self, field: u32) -> io::Result<Vec<G>> {
+        self.repeated_bytes(field)?
+            .into_iter()
+            .map(decode_point)
+            .collect()
+    }
+
+    fn gt<Gt: Compress>(&self, field: u32) -> io::Result<Gt> {
+        let mut bytes = self.bytes(field)?;
+        let gt = Gt::read_compressed(&mut bytes)?;
+        if !bytes.is_empty() {
+            return Err(invalid_data("invalid target group element"));
+        }
+        Ok(gt)
+    }
+
+    fn message(&self, field: u32) -> io::Result<Fields<'a>> {
+        Fields::parse(self.bytes(field)?)
+    }
+
+    /// Returns the messages of a repeated field, which has to have `len` of them.
+    fn repeated_messages(&self, field: u32, len: usize) -> io::Result<Vec<Fields<'a>>> {
+        let messages = self.repeated_bytes(field)?;
+        if messages.len() != len {
+            return Err(invalid_data("unexpected number of elements"));
+        }
+        messages.into_iter().map(Fields::parse).collect()
+    }
+}
+
+fn read_varint(bytes: &mut &[u8]) -> io::Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes
+            .split_first()
+            .ok_or_else(|| invalid_data("truncated varint"))?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("varint too long"))
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: u64) -> io::Result<&'a [u8]> {
+    if len > bytes.len() as u64 {
+        return Err(invalid_data("truncated field"));
+    }
+    let (value, rest) = bytes.split_at(len as usize);
+    *bytes = rest;
+    Ok(value)
+}
+
+fn decode_point<G: GroupEncoding>(bytes: &[u8]) -> io::Result<G> {
+    let mut repr = G::Repr::default();
+    if bytes.len() != repr.as_ref().len() {
+        return Err(invalid_data("invalid point length"));
+    }
+    repr.as_mut().copy_from_slice(bytes);
+    Option::from(G::from_bytes(&repr)).ok_or_else(|| invalid_data("invalid point"))
+}
+
+fn encode_point_pair<G: GroupEncoding>(pair: &(G, G)) -> Encoder {
+    let mut message = Encoder::default();
+    message.point(1, &pair.0);
+    message.point(2, &pair.1);
+    message
+}
+
+fn decode_point_pair<G: GroupEncoding>(message: &Fields) -> io::Result<(G, G)> {
+    Ok((message.point(1)?, message.point(2)?))
+}
+
+fn encode_gt_pair<Gt: Compress + Copy>(pair: &(Gt, Gt)) -> Encoder {
+    let mut message = Encoder::default();
+    message.gt(1, &pair.0);
+    message.gt(2, &pair.1);
+    message
+}
+
+fn decode_gt_pair<Gt: Compress>(message: &Fields) -> io::Result<(Gt, Gt)> {
+    Ok((message.gt(1)?, message.gt(2)?))
+}
+
+fn encode_commitment_pair<Gt: Compress + Copy>(pair: &((Gt, Gt), (Gt, Gt))) -> Encoder {
+    let mut message = Encoder::default();
+    message.message(1, encode_gt_pair(&pair.0));
+    message.message(2, encode_gt_pair(&pair.1));
+    message
+}
+
+fn decode_commitment_pair<Gt: Compress>(message: &Fields) -> io::Result<((Gt, Gt), (Gt, Gt))> {
+    Ok((
+        decode_gt_pair(&message.message(1)?)?,
+        decode_gt_pair(&message.message(2)?)?,
+    ))
+}
+
+/// Encodes a proof as a `Proof` message.
+pub fn encode_proof<E: Engine>(proof: &Proof<E>) -> Vec<u8> {
+    let mut message = Encoder::default();
+    message.point(1, &proof.a);
+    message.point(2, &proof.b);
+    message.point(3, &proof.c);
+    message.0
+}
+
+/// Decodes a `Proof` message. Points at infinity are rejected, like [`Proof::read`] does.
+pub fn decode_proof<E: Engine>(bytes: &[u8]) -> io::Result<Proof<E>> {
+    let message = Fields::parse(bytes)?;
+    let a: E::G1Affine = message.point(1)?;
+    let b: E::G2Affine = message.point(2)?;
+    let c: E::G1Affine = message.point(3)?;
+    if (a.is_identity() | b.is_identity() | c.is_identity()).into() {
+        return Err(invalid_data("point at infinity"));
+    }
+
+    Ok(Proof { a, b, c })
+}
+
+/// Encodes a verifying key as a `VerifyingKey` message.
+pub fn encode_verifying_key<E: MultiMillerLoop>(vk: &VerifyingKey<E>) -> Vec<u8> {
+    let mut message = Encoder::default();
+    message.point(1, &vk.alpha_g1);
+    message.point(2, &vk.beta_g1);
+    message.point(3, &vk.beta_g2);
+    message.point(4, &vk.gamma_g2);
+    message.point(5, &vk.delta_g1);
+    message.point(6, &vk.delta_g2);
+    for ic in &vk.ic {
+        message.point(7, ic);
+    }
+    message.0
+}
+
+/// Decodes a `VerifyingKey` message. IC points at infinity are rejected, like
+/// [`VerifyingKey::read`] does.
+pub fn decode_verifying_key<E: MultiMillerLoop>(bytes: &[u8]) -> io::Result<VerifyingKey<E>> {
+    let message = Fields::parse(bytes)?;
+    let ic: Vec<E::G1Affine> = message.repeated_points(7)?;
+    if ic.iter().any(|point| bool::from(point.is_identity())) {
+        return Err(invalid_data("point at infinity"));
+    }
+
+    Ok(VerifyingKey {
+        alpha_g1: message.point(1)?,
+        beta_g1: message.point(2)?,
+        beta_g2: message.point(3)?,
+        gamma_g2: message.point(4)?,
+        delta_g1: message.point(5)?,
+        delta_g2: message.point(6)?,
+        ic,
+    })
+}
+
+/// Encodes an aggregate proof as an `AggregateProof` message.
+pub fn encode_aggregate_proof<E>(proof: &AggregateProof<E>) -> Vec<u8>
+where
+    E: MultiMillerLoop,
+    <E as Engine>::Gt: Compress,
+{
+    let gipa = &proof.tmipp.gipa;
+    let mut gipa_message = Encoder::default();
+    gipa_message.uint32(1, gipa.nproofs);
+    for comms in &gipa.comms_ab {
+        gipa_message.message(2, encode_commitment_pair(comms));
+    }
+    for comms in &gipa.comms_c {
+        gipa_message.message(3, encode_commitment_pair(comms));
+    }
+    for z in &gipa.z_ab {
+        gipa_message.message(4, encode_gt_pair(z));
+    }
+    for (x, y) in &gipa.z_c {
+        gipa_message.message(5, encode_point_pair(&(x.to_affine(), y.to_affine())));
+    }
+    gipa_message.point(6, &gipa.final_a);
+    gipa_message.point(7, &gipa.final_b);
+    gipa_message.point(8, &gipa.final_c);
+    gipa_message.message(9, encode_point_pair(&gipa.final_vkey));
+    gipa_message.message(10, encode_point_pair(&gipa.final_wkey));
+
+    let mut tmipp_message = Encoder::default();
+    tmipp_message.message(1, gipa_message);
+    tmipp_message.message(2, encode_point_pair(&proof.tmipp.vkey_opening));
+    tmipp_message.message(3, encode_point_pair(&proof.tmipp.wkey_opening));
+
+    let mut message = Encoder::default();
+    message.message(1, encode_gt_pair(&proof.com_ab));
+    message.message(2, encode_gt_pair(&proof.com_c));
+    message.gt(3, &proof.ip_ab);
+    message.point(4, &proof.agg_c.to_affine());
+    message.message(5, tmipp_message);
+    message.0
+}
+
+/// Decodes an `AggregateProof` message. The number of proofs has to be at least 2, and the GIPA
+/// rounds have to match it, like [`AggregateProof::read`] checks.
+pub fn decode_aggregate_proof<E>(bytes: &[u8]) -> io::Result<AggregateProof<E>>
+where
+    E: MultiMillerLoop,
+    <E as Engine>::Gt: Compress,
+{
+    let message = Fields::parse(bytes)?;
+    let tmipp_message = message.message(5)?;
+    let gipa_message = tmipp_message.message(1)?;
+
+    let nproofs = gipa_message.uint32(1)?;
+    if nproofs < 2 {
+        return Err(invalid_data("number of proofs is invalid"));
+    }
+    let log_proofs = (nproofs as f32).log2().ceil() as usize;
+    let comms_ab = gipa_message
+        .repeated_messages(2, log_proofs)?
+        .iter()
+        .map(decode_commitment_pair)
+        .collect::<io::Result<_>>()?;
+    let comms_c = gipa_message
+        .repeated_messages(3, log_proofs)?
+        .iter()
+        .map(decode_commitment_pair)
+        .collect::<io::Result<_>>()?;
+    let z_ab = gipa_message
+        .repeated_messages(4, log_proofs)?
+        .iter()
+        .map(decode_gt_pair)
+        .collect::<io::Result<_>>()?;
+    let z_c = gipa_message
+        .repeated_messages(5, log_proofs)?
+        .iter()
+        .map(|pair| {
+            let (x, y): (E::G1Affine, E::G1Affine) = decode_point_pair(pair)?;
+            Ok((x.to_curve(), y.to_curve()))
+        })
+        .collect::<io::Result<_>>()?;
+
+    let gipa = GipaProof {
+        nproofs,
+        comms_ab,
+        comms_c,
+        z_ab,
+        z_c,
+        final_a: gipa_message.point(6)?,
+        final_b: gipa_message.point(7)?,
+        final_c: gipa_message.point(8)?,
+        final_vkey: decode_point_pair(&gipa_message.message(9)?)?,
+        final_wkey: decode_point_pair(&gipa_message.message(10)?)?,
+    };
+
+    Ok(AggregateProof {
+        com_ab: decode_gt_pair(&message.message(1)?)?,
+        com_c: decode_gt_pair(&message.message(2)?)?,
+        ip_ab: message.gt(3)?,
+        agg_c: message.point::<E::G1Affine>(4)?.to_curve(),
+        tmipp: TippMippProof {
+            gipa,
+            vkey_opening: decode_point_pair(&tmipp_message.message(2)?)?,
+            wkey_opening: decode_point_pair(&tmipp_message.message(3)?)?,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::{Bls12, G1Affine, G1Projective, G2Affine, G2Projective};
+    use group::Group;
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_varint() {
+        for (value, expected) in [
+            (0u64, vec![0x00]),
+            (1, vec![0x01]),
+            (127, vec![0x7f]),
+            (128, vec![0x80, 0x01]),
+            (300, vec![0xac, 0x02]),
+            (
+                u64::MAX,
+                vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01],
+            ),
+        ] {
+            let mut encoder = Encoder::default();
+            encoder.varint(value);
+            assert_eq!(encoder.0, expected);
+            assert_eq!(read_varint(&mut &expected[..]).unwrap(), value);
+        }
+
+        assert!(read_varint(&mut &[0x80][..]).is_err());
+        assert!(read_varint(&mut &[0xff; 11][..]).is_err());
+    }
+
+    #[test]
+    fn test_wire_proof() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let proof = Proof::<Bls12> {
+            a: G1Projective::random(&mut rng).to_affine(),
+            b: G2Projective::random(&mut rng).to_affine(),
+            c: G1Projective::random(&mut rng).to_affine(),
+        };
+
+        let bytes = encode_proof(&proof);
+        assert_eq!(bytes.len(), (2 + 48) + (2 + 96) + (2 + 48));
+        // Field 1 of wire type 2, 48 bytes long.
+        assert_eq!(bytes[..2], [0x0a, 48]);
+        assert_eq!(bytes[2..50], proof.a.to_compressed());
+        assert_eq!(decode_proof::<Bls12>(&bytes).unwrap(), proof);
+
+        // Unknown fields are skipped and the order of the fields doesn't matter.
+        let mut extended = vec![0x20, 0x01, 0x2d, 0, 0, 0, 0];
+        extended.extend_from_slice(&bytes[50..]);
+        extended.extend_from_slice(&bytes[..50]);
+        assert_eq!(decode_proof::<Bls12>(&extended).unwrap(), proof);
+
+        // Missing, truncated and invalid points are rejected.
+        assert!(decode_proof::<Bls12>(&bytes[50..]).is_err());
+        assert!(decode_proof::<Bls12>(&bytes[..bytes.len() - 1]).is_err());
+        let mut identity = Encoder::default();
+        identity.point(1, &G1Affine::identity());
+        identity.point(2, &proof.b);
+        identity.point(3, &proof.c);
+        assert!(decode_proof::<Bls12>(&identity.0).is_err());
+    }
+
+    #[test]
+    fn test_wire_verifying_key() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let mut g1 = || G1Projective::random(&mut rng).to_affine();
+        let vk = VerifyingKey::<Bls12> {
+            alpha_g1: g1(),
+            beta_g1: g1(),
+            beta_g2: G2Affine::generator(),
+            gamma_g2: G2Affine::generator(),
+            delta_g1: g1(),
+            delta_g2: G2Affine::generator(),
+            ic: vec![g1(), g1(), g1()],
+        };
+
+        let bytes = encode_verifying_key(&vk);
+        assert_eq!(bytes.len(), 6 * 2 + 3 * 48 + 3 * 96 + 3 * (2 + 48));
+        assert!(decode_verifying_key::<Bls12>(&bytes).unwrap() == vk);
+        assert!(decode_verifying_key::<Bls12>(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,575 @@
+//! Import of the Groth16 proving keys of [snarkjs], its `.zkey` files over BLS12-381, e.g. of
+//! circom circuits after a phase 2 ceremony. They are proven with
+//! [`R1csCircuit`](crate::circuits::r1cs::R1csCircuit) or
+//! [`R1cs::proving_assignment`](crate::circuits::r1cs::R1cs::proving_assignment).
+//!
+//! The keys of snarkjs differ from the ones of this crate in two ways:
+//!
+//! - They are generated over other evaluation domains. snarkjs derives its roots of unity from
+//!   the smallest quadratic non-residue 5, this crate from the multiplicative generator 7. The
+//!   converted parameters therefore need to be wrapped in [`SnarkjsParameters`] for proving, so
+//!   that the prover evaluates the constraints at the same points.
+//! - The H query holds the Lagrange basis of the odd powers of the root of unity of twice the
+//!   size of the domain, instead of the powers of tau times the vanishing polynomial. It is
+//!   converted with an FFT over G1, which takes a while for large circuits. Write the converted
+//!   parameters with [`Parameters::write_with_header`] to convert them only once, e.g. to map
+//!   them with [`Parameters::build_mapped_parameters`].
+//!
+//! [snarkjs]: https://github.com/iden3/snarkjs
+
+use std::io::{self, BufReader, Read, Seek};
+use std::iter;
+use std::sync::Arc;
+
+use blstrs::{Bls12, Fp, G1Affine, G1Projective, G2Affine, Scalar};
+use byteorder::{LittleEndian, ReadBytesExt};
+use ff::{Field, PrimeField};
+use group::{prime::PrimeCurveAffine, Curve, UncompressedEncoding};
+
+use super::{ParameterSource, Parameters, QueryDensities, VerifyingKey};
+use crate::circuits::r1cs::{read_prime, read_sections, seek_section, Section};
+use crate::multicore::prelude::*;
+use crate::SynthesisError;
+
+const ZKEY_MAGIC: [u8; 4] = *b"zkey";
+const PROTOCOL_GROTH16: u32 = 1;
+
+const ZKEY_HEADER: u32 = 1;
+const ZKEY_GROTH16_HEADER: u32 = 2;
+const ZKEY_IC: u32 = 3;
+const ZKEY_A: u32 = 5;
+const ZKEY_B_G1: u32 = 6;
+const ZKEY_B_G2: u32 = 7;
+const ZKEY_L: u32 = 8;
+const ZKEY_H: u32 = 9;
+
+/// The length of a base field element.
+const FP_LEN: usize = 48;
+const G1_LEN: usize = 2 * FP_LEN;
+const G2_LEN: usize = 4 * FP_LEN;
+/// The smallest quadratic non-residue of the scalar field, the roots of unity of snarkjs are
+/// derived from it.
+const SNARKJS_NON_RESIDUE: u64 = 5;
+
+/// Parameters converted from a `.zkey` file, to prove with the evaluation domains of snarkjs.
+///
+/// Proofs created with the unwrapped parameters don't verify.
+pub struct SnarkjsParameters<P>(pub P);
+
+impl<P: ParameterSource<Bls12>> ParameterSource<Bls12> for SnarkjsParameters<P> {
+    type G1Builder = P::G1Builder;
+    type G2Builder = P::G2Builder;
+
+    fn get_vk(&self, num_ic: usize) -> Result<&VerifyingKey<Bls12>, SynthesisError> {
+        self.0.get_vk(num_ic)
+    }
+
+    fn domain_size(&self) -> Option<usize> {
+        self.0.domain_size()
+    }
+
+    fn densities(&self) -> Option<Arc<QueryDensities>> {
+        self.0.densities()
+    }
+
+    fn root_of_unity_exponent(&self) -> u64 {
+        snarkjs_root_exponent()
+    }
+
+    fn get_h(&self, num_h: usize) -> Result<Self::G1Builder, SynthesisError> {
+        self.0.get_h(num_h)
+    }
+
+    fn get_l(&self, num_l: usize) -> Result<Self::G1Builder, SynthesisError> {
+        self.0.get_l(num_l)
+    }
+
+    fn get_a(
+        &self,
+        num_inputs: usize,
+        num_aux: usize,
+    ) -> Result<(Self::G1Builder, Self::G1Builder), SynthesisError> {
+        self.0.get_a(num_inputs, num_aux)
+    }
+
+    fn get_b_g1(
+        &self,
+        num_inputs: usize,
+        num_aux: usize,
+    ) -> Result<(Self::G1Builder, Self::G1Builder), SynthesisError> {
+        self.0.get_b_g1(num_inputs, num_aux)
+    }
+
+    fn get_b_g2(
+        &self,
+        num_inputs: usize,
+        num_aux: usize,
+    ) -> Result<(Self::G2Builder, Self::G2Builder), SynthesisError> {
+        self.0.get_b_g2(num_inputs, num_aux)
+    }
+}
+
+/// The primitive `2^S`-th root of unity of snarkjs.
+fn snarkjs_root_of_unity() -> Scalar {
+    // The odd part `t` of `r - 1`, `S` is 32 for BLS12-381, a whole number of bytes.
+    let minus_one = (-Scalar::ONE).to_bytes_le();
+    let mut t = [0u64; 4];
+    for (limb, bytes) in t
+        .iter_mut()
+        .zip(minus_one[Scalar::S as usize / 8..].chunks(8))
+    {
+        let mut limb_bytes = [0; 8];
+        limb_bytes[..bytes.len()].copy_from_slice(bytes);
+        *limb = u64::from_le_bytes(limb_bytes);
+    }
+    Scalar::from(SNARKJS_NON_RESIDUE).pow_vartime(t)
+}
+
+/// The exponent `k` of `Scalar::ROOT_OF_UNITY^k = snarkjs_root_of_unity()`, bit by bit: after
+/// removing the known bits, the root has an order of at most `2^(S - i)`, raising it to
+/// `2^(S - i - 1)` gives -1 if the next bit is set.
+fn snarkjs_root_exponent() -> u64 {
+    let root = snarkjs_root_of_unity();
+    let mut k = 0u64;
+    for i in 0..Scalar::S {
+        let mut rest = root * Scalar::ROOT_OF_UNITY_INV.pow_vartime([k]);
+        for _ in 0..Scalar::S - i - 1 {
+            rest = rest.square();
+        }
+        if rest != Scalar::ONE {
+            k |= 1 << i;
+        }
+    }
+    k
+}
+
+fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+impl Parameters<Bls12> {
+    /// Reads the proving key of a `.zkey` file of snarkjs. All points are checked. Prove with
+    /// the parameters wrapped in [`SnarkjsParameters`], see the [module docs](self) for why, and
+    /// for how long the conversion takes.
+    pub fn read_zkey<R: Read + Seek>(reader: R) -> io::Result<Self> {
+        let mut reader = BufReader::new(reader);
+        let sections = read_sections(&mut reader, ZKEY_MAGIC, &[1])?;
+
+        seek_section(&mut reader, &sections, ZKEY_HEADER)?;
+        if reader.read_u32::<LittleEndian>()? != PROTOCOL_GROTH16 {
+            return Err(invalid_data("not a Groth16 zkey file"));
+        }
+
+        seek_section(&mut reader, &sections, ZKEY_GROTH16_HEADER)?;
+        if reader.read_u32::<LittleEndian>()? as usize != FP_LEN {
+            return Err(invalid_data("the zkey file is not over BLS12-381"));
+        }
+        let mut q = [0; FP_LEN];
+        reader.read_exact(&mut q)?;
+        if q[..] != fp_modulus_le() {
+            return Err(invalid_data("the zkey file is not over BLS12-381"));
+        }
+        read_prime::<Scalar, _>(&mut reader)?;
+        let num_vars = reader.read_u32::<LittleEndian>()? as usize;
+        let num_public = reader.read_u32::<LittleEndian>()? as usize;
+        let domain_size = reader.read_u32::<LittleEndian>()? as usize;
+        if num_public >= num_vars {
+            return Err(invalid_data("more public wires than wires"));
+        }
+        if !domain_size.is_power_of_two() || domain_size.trailing_zeros() >= Scalar::S {
+            return Err(invalid_data(format!("invalid domain size {}", domain_size)));
+        }
+        let alpha_g1 = read_g1(&mut reader)?;
+        let beta_g1 = read_g1(&mut reader)?;
+        let beta_g2 = read_g2(&mut reader)?;
+        let gamma_g2 = read_g2(&mut reader)?;
+        let delta_g1 = read_g1(&mut reader)?;
+        let delta_g2 = read_g2(&mut reader)?;
+
+        let num_inputs = num_public + 1;
+        let num_aux = num_vars - num_inputs;
+        let ic = read_section(&mut reader, &sections, ZKEY_IC, num_inputs, G1_LEN, read_g1)?;
+        let a_query = read_section(&mut reader, &sections, ZKEY_A, num_vars, G1_LEN, read_g1)?;
+        let b_g1_query =
+            read_section(&mut reader, &sections, ZKEY_B_G1, num_vars, G1_LEN, read_g1)?;
+        let b_g2_query =
+            read_section(&mut reader, &sections, ZKEY_B_G2, num_vars, G2_LEN, read_g2)?;
+        let l = read_section(&mut reader, &sections, ZKEY_L, num_aux, G1_LEN, read_g1)?;
+        let h = read_section(&mut reader, &sections, ZKEY_H, domain_size, G1_LEN, read_g1)?;
+        if ic
+            .iter()
+            .chain(&l)
+            .any(|point| bool::from(point.is_identity()))
+        {
+            return Err(invalid_data("point at infinity"));
+        }
+
+        let is_dense = |point: &G1Affine| !bool::from(point.is_identity());
+        let densities = QueryDensities::new(
+            num_inputs,
+            num_aux,
+            a_query.iter().skip(num_inputs).map(is_dense),
+            b_g1_query.iter().take(num_inputs).map(is_dense),
+            b_g1_query.iter().skip(num_inputs).map(is_dense),
+        );
+
+        Ok(Parameters {
+            vk: VerifyingKey {
+                alpha_g1,
+                beta_g1,
+                beta_g2,
+                gamma_g2,
+                delta_g1,
+                delta_g2,
+                ic,
+            },
+            h: Arc::new(h_query(h)),
+            l: Arc::new(l),
+            a: Arc::new(a_query.into_iter().filter(is_dense).collect()),
+            b_g1: Arc::new(b_g1_query.into_iter().filter(is_dense).collect()),
+            b_g2: Arc::new(
+                b_g2_query
+                    .into_iter()
+                    .filter(|point| !bool::from(point.is_identity()))
+                    .collect(),
+            ),
+            densities: Some(Arc::new(densities)),
+            fingerprint: None,
+        })
+    }
+}
+
+/// Reads a section of `len` points, of `point_len` bytes each.
+fn read_section<R, G, F>(
+    reader: &mut R,
+    sections: &[Section],
+    kind: u32,
+    len: usize,
+    point_len: usize,
+    read_point: F,
+) -> io::Result<Vec<G>>
+where
+    R: Read + Seek,
+    F: Fn(&mut R) -> io::Result<G>,
+{
+    if seek_section(reader, sections, kind)? != (len * point_len) as u64 {
+        return Err(invalid_data(format!("section {} has the wrong size", kind)));
+    }
+    (0..len).map(|_| read_point(reader)).collect()
+}
+
+/// The little endian modulus of the base field.
+fn fp_modulus_le() -> Vec<u8> {
+    let mut modulus = (-Fp::ONE).to_bytes_le().to_vec();
+    // The modulus is odd, adding one to `-1` doesn't carry.
+    modulus[0] += 1;
+    modulus
+}
+
+/// Reads a base field element in the little endian Montgomery form of snarkjs.
+fn read_fp<R: Read>(reader: &mut R) -> io::Result<Fp> {
+    let mut bytes = [0; FP_LEN];
+    reader.read_exact(&mut bytes)?;
+    let montgomery: Fp = Option::from(Fp::from_bytes_le(&bytes))
+        .ok_or_else(|| invalid_data("non-canonical field element"))?;
+    // The Montgomery factor is `2^384`.
+    let r_inv = Fp::from(2).pow_vartime([384]).invert().unwrap();
+    Ok(montgomery * r_inv)
+}
+
+/// Reads a point, given as `x || y`, the point at infinity as zeros.
+fn read_g1<R: Read>(reader: &mut R) -> io::Result<G1Affine> {
+    let x = read_fp(reader)?;
+    let y = read_fp(reader)?;
+    if bool::from(x.is_zero() & y.is_zero()) {
+        return Ok(G1Affine::identity());
+    }
+
+    let mut uncompressed = <G1Affine as UncompressedEncoding>::Uncompressed::default();
+    uncompressed.as_mut()[..FP_LEN].copy_from_slice(&x.to_bytes_be());
+    uncompressed.as_mut()[FP_LEN..].copy_from_slice(&y.to_bytes_be());
+    Option::from(G1Affine::from_uncompressed(&uncompressed))
+        .ok_or_else(|| invalid_data("invalid G1 point"))
+}
+
+/// Reads a point, given as `x.c0 || x.c1 || y.c0 || y.c1`, the point at infinity as zeros.
+fn read_g2<R: Read>(reader: &mut R) -> io::Result<G2Affine> {
+    let coordinates = [
+        read_fp(reader)?,
+        read_fp(reader)?,
+        read_fp(reader)?,
+        read_fp(reader)?,
+    ];
+    if coordinates.iter().all(|c| bool::from(c.is_zero())) {
+        return Ok(G2Affine::identity());
+    }
+
+    // The uncompressed encoding is `x.c1 || x.c0 || y.c1 || y.c0`.
+    let mut uncompressed = <G2Affine as UncompressedEncoding>::Uncompressed::default();
+    for (i, c) in [1, 0, 3, 2].into_iter().enumerate() {
+        uncompressed.as_mut()[i * FP_LEN..(i + 1) * FP_LEN]
+            .copy_from_slice(&coordinates[c].to_bytes_be());
+    }
+    Option::from(G2Affine::from_uncompressed(&uncompressed))
+        .ok_or_else(|| invalid_data("invalid G2 point"))
+}
+
+/// Converts the H query of snarkjs for a domain of size `n` into the one of this crate.
+///
+/// snarkjs has `H_j = L_{2j+1}(tau) / delta` for `j < n`, where `L_i` is the Lagrange polynomial
+/// of `w^i` for the root `w` of the domain of size `2n`. This crate has
+/// `tau^i * Z(tau) / delta` for `i < n - 1`, where `Z(x) = x^n - 1`. As `x^i * Z(x)` is of
+/// degree less than `2n`, and `Z(w^j)` is 0 for even `j` and -2 for odd `j`, it is
+/// `-2 * w^i * sum_j (w^2)^(i * j) * L_{2j+1}(x)`, an FFT of the H query of snarkjs.
+fn h_query(h: Vec<G1Affine>) -> Vec<G1Affine> {
+    let n = h.len();
+    let log_n = n.trailing_zeros();
+    let w = snarkjs_root_of_unity().pow_vartime([1u64 << (Scalar::S - log_n - 1)]);
+
+    let mut points: Vec<G1Projective> = h.par_iter().map(|point| point.to_curve()).collect();
+    fft(&mut points, w.square());
+    points.truncate(n - 1);
+
+    let factors: Vec<Scalar> = iter::successors(Some(-Scalar::from(2)), |factor| Some(factor * w))
+        .take(n - 1)
+        .collect();
+    points
+        .par_iter_mut()
+        .zip(factors.par_iter())
+        .for_each(|(point, factor)| *point *= factor);
+
+    let mut affine = vec![G1Affine::identity(); points.len()];
+    G1Projective::batch_normalize(&points, &mut affine);
+    affine
+}
+
+/// Replaces `points` with `sum_j omega^(i * j) * points[j]` for every `i`, with a radix-2 FFT.
+/// `omega` is a root of unity whose order is the number of points, a power of two.
+fn fft(points: &mut [G1Projective], omega: Scalar) {
+    let n = points.len();
+    if n == 1 {
+        return;
+    }
+    let log_n = n.trailing_zeros();
+    for i in 0..n {
+        let reversed = i.reverse_bits() >> (usize::BITS - log_n);
+        if i < reversed {
+            points.swap(i, reversed);
+        }
+    }
+
+    let mut half = 1;
+    while half < n {
+        let w = omega.pow_vartime([(n / (2 * half)) as u64]);
+        let twiddles: Vec<Scalar> = iter::successors(Some(Scalar::ONE), |t| Some(t * w))
+            .take(half)
+            .collect();
+        for chunk in points.chunks_mut(2 * half) {
+            let (low, high) = chunk.split_at_mut(half);
+            low.par_iter_mut()
+                .zip(high.par_iter_mut())
+                .zip(twiddles.par_iter())
+                .for_each(|((low, high), twiddle)| {
+                    let t = *high * twiddle;
+                    *high = *low - t;
+                    *low += t;
+                });
+        }
+        half *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use byteorder::WriteBytesExt;
+    use group::Group;
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use crate::circuits::r1cs::{Constraint, R1cs};
+    use crate::groth16::{create_proof_from_assignments, prepare_verifying_key, verify_proof};
+
+    fn write_fp(bytes: &mut Vec<u8>, be: &[u8]) {
+        let fp = Fp::from_bytes_be(be.try_into().unwrap()).unwrap();
+        let montgomery = fp * Fp::from(2).pow_vartime([384]);
+        bytes.extend_from_slice(&montgomery.to_bytes_le());
+    }
+
+    fn write_g1(bytes: &mut Vec<u8>, point: G1Projective) {
+        let point = point.to_affine();
+        if bool::from(point.is_identity()) {
+            bytes.extend_from_slice(&[0; G1_LEN]);
+            return;
+        }
+        for coordinate in point.to_uncompressed().chunks(FP_LEN) {
+            write_fp(bytes, coordinate);
+        }
+    }
+
+    fn write_g2(bytes: &mut Vec<u8>, point: blstrs::G2Projective) {
+        let point = point.to_affine();
+        if bool::from(point.is_identity()) {
+            bytes.extend_from_slice(&[0; G2_LEN]);
+            return;
+        }
+        let uncompressed = point.to_uncompressed();
+        let coordinates: Vec<_> = uncompressed.chunks(FP_LEN).collect();
+        for c in [1, 0, 3, 2] {
+            write_fp(bytes, coordinates[c]);
+        }
+    }
+
+    /// The Lagrange polynomial of `omega^i` over the domain of size `n`, at `tau`.
+    fn lagrange(tau: Scalar, omega: Scalar, n: u64, i: u64) -> Scalar {
+        let point = omega.pow_vartime([i]);
+        point
+            * (tau.pow_vartime([n]) - Scalar::ONE)
+            * (Scalar::from(n) * (tau - point)).invert().unwrap()
+    }
+
+    /// Sets up `x * y = z` for a public `z` like snarkjs does, and writes the `.zkey` file.
+    fn snarkjs_setup(rng: &mut XorShiftRng) -> Vec<u8> {
+        let [tau, alpha, beta, gamma, delta] = [(); 5].map(|_| Scalar::random(&mut *rng));
+        let g1 = G1Projective::generator();
+        let g2 = blstrs::G2Projective::generator();
+
+        // The wires `[1, z, x, y]`, the constraint `x * y = z`, followed by the constraints
+        // `1 * 0 = 0` and `z * 0 = 0` of the inputs.
+        let n = 4;
+        let omega = snarkjs_root_of_unity().pow_vartime([1u64 << (Scalar::S - 2)]);
+        let l = |i| lagrange(tau, omega, n, i);
+        let u = [l(1), l(2), l(0), Scalar::ZERO];
+        let v = [Scalar::ZERO, Scalar::ZERO, Scalar::ZERO, l(0)];
+        let w = [Scalar::ZERO, l(0), Scalar::ZERO, Scalar::ZERO];
+        let ic = |i: usize| (beta * u[i] + alpha * v[i] + w[i]) * gamma.invert().unwrap();
+        let l_query = |i: usize| (beta * u[i] + alpha * v[i] + w[i]) * delta.invert().unwrap();
+        let omega_2n = snarkjs_root_of_unity().pow_vartime([1u64 << (Scalar::S - 3)]);
+
+        let mut sections: Vec<(u32, Vec<u8>)> = vec![(ZKEY_HEADER, vec![1, 0, 0, 0])];
+        let mut header = vec![];
+        header.write_u32::<LittleEndian>(FP_LEN as u32).unwrap();
+        header.extend_from_slice(&fp_modulus_le());
+        header.write_u32::<LittleEndian>(32).unwrap();
+        let mut r = (-Scalar::ONE).to_bytes_le();
+        r[0] += 1;
+        header.extend_from_slice(&r);
+        for value in [4, 1, n as u32] {
+            header.write_u32::<LittleEndian>(value).unwrap();
+        }
+        write_g1(&mut header, g1 * alpha);
+        write_g1(&mut header, g1 * beta);
+        write_g2(&mut header, g2 * beta);
+        write_g2(&mut header, g2 * gamma);
+        write_g1(&mut header, g1 * delta);
+        write_g2(&mut header, g2 * delta);
+        sections.push((ZKEY_GROTH16_HEADER, header));
+
+        let g1_section = |kind, scalars: Vec<Scalar>| {
+            let mut bytes = vec![];
+            for scalar in scalars {
+                write_g1(&mut bytes, g1 * scalar);
+            }
+            (kind, bytes)
+        };
+        sections.push(g1_section(ZKEY_IC, (0..2).map(ic).collect()));
+        sections.push(g1_section(ZKEY_A, u.to_vec()));
+        sections.push(g1_section(ZKEY_B_G1, v.to_vec()));
+        let mut b_g2 = vec![];
+        for scalar in v {
+            write_g2(&mut b_g2, g2 * scalar);
+        }
+        sections.push((ZKEY_B_G2, b_g2));
+        sections.push(g1_section(ZKEY_L, (2..4).map(l_query).collect()));
+        let h = (0..n)
+            .map(|j| lagrange(tau, omega_2n, 2 * n, 2 * j + 1) * delta.invert().unwrap())
+            .collect();
+        sections.push(g1_section(ZKEY_H, h));
+
+        let mut zkey = ZKEY_MAGIC.to_vec();
+        zkey.write_u32::<LittleEndian>(1).unwrap();
+        zkey.write_u32::<LittleEndian>(sections.len() as u32)
+            .unwrap();
+        // snarkjs doesn't write the sections in order.
+        for (kind, content) in sections.into_iter().rev() {
+            zkey.write_u32::<LittleEndian>(kind).unwrap();
+            zkey.write_u64::<LittleEndian>(content.len() as u64)
+                .unwrap();
+            zkey.extend_from_slice(&content);
+        }
+        zkey
+    }
+
+    #[test]
+    fn test_snarkjs_root_of_unity() {
+        let root = snarkjs_root_of_unity();
+        assert_eq!(root.pow_vartime([1u64 << Scalar::S]), Scalar::ONE);
+        assert_eq!(root.pow_vartime([1u64 << (Scalar::S - 1)]), -Scalar::ONE);
+        assert_ne!(root, Scalar::ROOT_OF_UNITY);
+
+        let k = snarkjs_root_exponent();
+        assert_eq!(k % 2, 1);
+        assert_eq!(Scalar::ROOT_OF_UNITY.pow_vartime([k]), root);
+    }
+
+    #[test]
+    fn test_read_zkey() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let zkey = snarkjs_setup(&mut rng);
+        let params = Parameters::read_zkey(io::Cursor::new(&zkey)).unwrap();
+        assert_eq!(params.h.len(), 3);
+        assert_eq!(params.a.len(), 3);
+        assert_eq!(params.b_g1.len(), 1);
+
+        let terms = |wire| vec![(wire, Scalar::ONE)];
+        let r1cs = R1cs {
+            num_wires: 4,
+            num_public: 1,
+            constraints: vec![Constraint {
+                a: terms(2),
+                b: terms(3),
+                c: terms(1),
+            }],
+        };
+        let (x, y) = (Scalar::from(3), Scalar::from(5));
+        let witness = [Scalar::ONE, x * y, x, y];
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let assignment = r1cs.proving_assignment(&witness).unwrap();
+        let (r, s) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+        let proof =
+            create_proof_from_assignments(assignment, SnarkjsParameters(&params), r, s).unwrap();
+        assert!(verify_proof(&pvk, &proof, &[x * y]).unwrap());
+        assert!(!verify_proof(&pvk, &proof, &[x]).unwrap());
+
+        // Without the evaluation domains of snarkjs, the proof is invalid.
+        let assignment = r1cs.proving_assignment(&witness).unwrap();
+        let proof = create_proof_from_assignments(assignment, &params, r, s).unwrap();
+        assert!(!verify_proof(&pvk, &proof, &[x * y]).unwrap());
+
+        let mut truncated = zkey.clone();
+        truncated.truncate(zkey.len() - 1);
+        assert!(Parameters::read_zkey(io::Cursor::new(&truncated)).is_err());
+        let mut invalid = zkey.clone();
+        invalid[0] = b'r';
+        assert!(Parameters::read_zkey(io::Cursor::new(&invalid)).is_err());
+        // The size of the base field elements, which must not be allocated for, precedes the
+        // modulus in the Groth16 header.
+        let modulus = fp_modulus_le();
+        let at = zkey
+            .windows(modulus.len())
+            .position(|window| window == modulus)
+            .unwrap()
+            - 4;
+        let mut invalid = zkey;
+        invalid[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(Parameters::read_zkey(io::Cursor::new(&invalid)).is_err());
+    }
+}
@@ -1,4 +1,5 @@
-use std::ops::{Add, Sub};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
 use ff::PrimeField;
 use serde::{Deserialize, Serialize};
@@ -170,6 +171,11 @@ impl<Scalar: PrimeField> LinearCombination<Scalar> {
         self.aux.iter()
     }
 
+    /// The terms of the linear combination, by value.
+    pub fn terms(&self) -> impl Iterator<Item = (Variable, Scalar)> + '_ {
+        self.iter().map(|(var, coeff)| (var, *coeff))
+    }
+
     pub fn iter_mut(&mut self) -> impl Iterator<Item = (Variable, &mut Scalar)> + '_ {
         self.inputs
             .iter_mut()
@@ -243,6 +249,12 @@ impl<Scalar: PrimeField> LinearCombination<Scalar> {
         self.inputs.is_empty() && self.aux.is_empty()
     }
 
+    /// Whether all coefficients are zero. Unlike [`LinearCombination::is_empty`], this holds for
+    /// terms that cancelled out, as they aren't removed.
+    pub fn is_zero(&self) -> bool {
+        self.iter().all(|(_, coeff)| bool::from(coeff.is_zero()))
+    }
+
     pub fn eval(
         &self,
         mut input_density: Option<&mut DensityTracker>,
@@ -393,6 +405,121 @@ impl<'a, Scalar: PrimeField> Sub<(Scalar, &'a LinearCombination<Scalar>)>
     }
 }
 
+impl<Scalar: PrimeField> AddAssign<(Scalar, Variable)> for LinearCombination<Scalar> {
+    fn add_assign(&mut self, (coeff, var): (Scalar, Variable)) {
+        match var.0 {
+            Index::Input(new_var) => self.add_assign_unsimplified_input(new_var, coeff),
+            Index::Aux(new_var) => self.add_assign_unsimplified_aux(new_var, coeff),
+        }
+    }
+}
+
+impl<Scalar: PrimeField> SubAssign<(Scalar, Variable)> for LinearCombination<Scalar> {
+    fn sub_assign(&mut self, (coeff, var): (Scalar, Variable)) {
+        match var.0 {
+            Index::Input(new_var) => self.sub_assign_unsimplified_input(new_var, coeff),
+            Index::Aux(new_var) => self.sub_assign_unsimplified_aux(new_var, coeff),
+        }
+    }
+}
+
+impl<Scalar: PrimeField> AddAssign<Variable> for LinearCombination<Scalar> {
+    fn add_assign(&mut self, other: Variable) {
+        *self += (Scalar::ONE, other);
+    }
+}
+
+impl<Scalar: PrimeField> SubAssign<Variable> for LinearCombination<Scalar> {
+    fn sub_assign(&mut self, other: Variable) {
+        *self -= (Scalar::ONE, other);
+    }
+}
+
+impl<'a, Scalar: PrimeField> AddAssign<&'a LinearCombination<Scalar>>
+    for LinearCombination<Scalar>
+{
+    fn add_assign(&mut self, other: &'a LinearCombination<Scalar>) {
+        for (var, val) in other.inputs.iter() {
+            self.add_assign_unsimplified_input(*var, *val);
+        }
+
+        for (var, val) in other.aux.iter() {
+            self.add_assign_unsimplified_aux(*var, *val);
+        }
+    }
+}
+
+impl<'a, Scalar: PrimeField> SubAssign<&'a LinearCombination<Scalar>>
+    for LinearCombination<Scalar>
+{
+    fn sub_assign(&mut self, other: &'a LinearCombination<Scalar>) {
+        for (var, val) in other.inputs.iter() {
+            self.sub_assign_unsimplified_input(*var, *val);
+        }
+
+        for (var, val) in other.aux.iter() {
+            self.sub_assign_unsimplified_aux(*var, *val);
+        }
+    }
+}
+
+impl<Scalar: PrimeField> MulAssign<Scalar> for LinearCombination<Scalar> {
+    fn mul_assign(&mut self, coeff: Scalar) {
+        for (_, val) in self.iter_mut() {
+            *val *= coeff;
+        }
+    }
+}
+
+impl<Scalar: PrimeField> Mul<Scalar> for LinearCombination<Scalar> {
+    type Output = LinearCombination<Scalar>;
+
+    fn mul(mut self, coeff: Scalar) -> LinearCombination<Scalar> {
+        self *= coeff;
+        self
+    }
+}
+
+impl<Scalar: PrimeField> Neg for LinearCombination<Scalar> {
+    type Output = LinearCombination<Scalar>;
+
+    fn neg(mut self) -> LinearCombination<Scalar> {
+        for (_, val) in self.iter_mut() {
+            *val = -*val;
+        }
+        self
+    }
+}
+
+impl<Scalar: PrimeField> Sum for LinearCombination<Scalar> {
+    fn sum<I: Iterator<Item = LinearCombination<Scalar>>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |acc, lc| acc + &lc)
+    }
+}
+
+impl<'a, Scalar: PrimeField> Sum<&'a LinearCombination<Scalar>> for LinearCombination<Scalar> {
+    fn sum<I: Iterator<Item = &'a LinearCombination<Scalar>>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |acc, lc| acc + lc)
+    }
+}
+
+/// Adds the terms, in the form of [`LinearCombination::terms`].
+impl<Scalar: PrimeField> Extend<(Variable, Scalar)> for LinearCombination<Scalar> {
+    fn extend<I: IntoIterator<Item = (Variable, Scalar)>>(&mut self, iter: I) {
+        for (var, coeff) in iter {
+            *self += (coeff, var);
+        }
+    }
+}
+
+impl<Scalar: PrimeField> FromIterator<(Variable, Scalar)> for LinearCombination<Scalar> {
+    fn from_iter<I: IntoIterator<Item = (Variable, Scalar)>>(iter: I) -> Self {
+        let mut lc = Self::zero();
+        lc.extend(iter);
+        lc
+    }
+}
+
 #[cfg(all(test, feature = "groth16"))]
 mod tests {
     use super::*;
@@ -450,4 +577,53 @@ mod tests {
         assert_eq!(&indexer.values, &[(1, one), (2, two), (3, one)]);
         assert_eq!(&indexer.last_inserted, &Some((0, 1)));
     }
+
+    #[test]
+    fn test_arithmetic() {
+        let x = Variable(Index::Input(1));
+        let y = Variable(Index::Aux(0));
+        let z = Variable(Index::Aux(1));
+        let two = Scalar::from(2);
+        let three = Scalar::from(3);
+
+        let mut lc = LinearCombination::<Scalar>::zero();
+        lc += x;
+        lc += (two, y);
+        lc -= z;
+        assert_eq!(lc, LinearCombination::zero() + x + (two, y) - z);
+
+        let mut other = lc.clone();
+        other -= &lc;
+        assert!(!other.is_empty());
+        assert!(other.is_zero());
+        other += &lc;
+        assert_eq!(other, lc);
+        other -= (two, y);
+        other -= x;
+        assert_eq!(
+            other,
+            LinearCombination::zero() + (Scalar::ZERO, x) + (Scalar::ZERO, y) - z
+        );
+
+        let scaled = lc.clone() * three;
+        assert_eq!(
+            scaled.terms().collect::<Vec<_>>(),
+            vec![(x, three), (y, two * three), (z, -three)]
+        );
+        assert_eq!(-lc.clone(), lc.clone() * -Scalar::ONE);
+
+        let parts = vec![
+            LinearCombination::from_variable(x),
+            LinearCombination::from_coeff(y, two),
+            LinearCombination::from_coeff(z, -Scalar::ONE),
+        ];
+        assert_eq!(parts.iter().sum::<LinearCombination<_>>(), lc);
+        assert_eq!(parts.into_iter().sum::<LinearCombination<_>>(), lc);
+
+        let collected = lc.terms().collect::<LinearCombination<_>>();
+        assert_eq!(collected, lc);
+        let mut extended = LinearCombination::from_variable(x);
+        extended.extend(lc.terms());
+        assert_eq!(extended, lc.clone() + x);
+    }
 }
@@ -137,11 +137,17 @@
 
 #![cfg_attr(all(target_arch = "aarch64", nightly), feature(stdsimd))]
 
+#[cfg(all(target_arch = "wasm32", any(feature = "cuda", feature = "opencl")))]
+compile_error!("the `cuda` and `opencl` features are not supported on `wasm32`");
+
 #[cfg(test)]
 #[macro_use]
 extern crate hex_literal;
 
+pub mod circuits;
 pub mod domain;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod gadgets;
 pub mod gpu;
 #[cfg(feature = "groth16")]
@@ -149,10 +155,19 @@ pub mod groth16;
 pub mod multiexp;
 pub mod util_cs;
 
+mod multicore;
+#[cfg_attr(not(feature = "groth16"), allow(dead_code))]
+mod wipe;
+
 mod lc;
 pub use lc::{Index, LinearCombination, Variable};
 mod constraint_system;
-pub use constraint_system::{Circuit, ConstraintSystem, Namespace, SynthesisError};
+pub use constraint_system::{
+    synthesize_parallel, Circuit, ConstraintMetadata, ConstraintSystem, LookupConstraintSystem,
+    LookupTable, Namespace, Severity, SynthesisError,
+};
+mod dyn_cs;
+pub use dyn_cs::{DynConstraintSystem, ErasedConstraintSystem};
 
 pub const BELLMAN_VERSION: &str = env!("CARGO_PKG_VERSION");
 
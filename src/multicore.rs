@@ -0,0 +1,190 @@
+//! The parallelism of this crate's own code.
+//!
+//! With the `multicore` feature this is Rayon. Without it, and on `wasm32`, which has no threads,
+//! the same subset of the Rayon API is provided by sequential loops that run on the calling
+//! thread, so that neither Rayon nor crossbeam are needed.
+//!
+//! The FFTs and multi-exponentiations of the prover and the parameter generation run on the
+//! thread pool of `ec-gpu-gen` (see [`Worker`](ec_gpu_gen::threadpool::Worker)), which is not
+//! affected by this feature. Its CPU FFTs and multiexps are built on that pool, so it can't be
+//! replaced without `ec-gpu-gen` support, and `ec-gpu-gen` keeps Rayon, crossbeam-channel and
+//! yastl in the dependency tree either way. Without the feature, only verification, aggregation
+//! and parameter loading are free of threads.
+
+#[cfg(all(feature = "multicore", not(target_arch = "wasm32")))]
+pub(crate) use rayon::{in_place_scope, join, prelude, scope};
+
+#[cfg(any(not(feature = "multicore"), target_arch = "wasm32"))]
+pub(crate) use self::sequential::*;
+
+#[cfg(any(not(feature = "multicore"), target_arch = "wasm32"))]
+mod sequential {
+    use std::marker::PhantomData;
+
+    /// A scope whose spawned jobs run immediately on the calling thread.
+    pub(crate) struct Scope<'scope>(PhantomData<&'scope ()>);
+
+    impl<'scope> Scope<'scope> {
+        pub(crate) fn spawn<F>(&self, job: F)
+        where
+            F: FnOnce(&Scope<'scope>) + 'scope,
+        {
+            job(self)
+        }
+    }
+
+    pub(crate) fn in_place_scope<'scope, OP, R>(op: OP) -> R
+    where
+        OP: FnOnce(&Scope<'scope>) -> R,
+    {
+        op(&Scope(PhantomData))
+    }
+
+    pub(crate) use self::in_place_scope as scope;
+
+    pub(crate) fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+    where
+        A: FnOnce() -> RA,
+        B: FnOnce() -> RB,
+    {
+        (a(), b())
+    }
+
+    pub(crate) mod prelude {
+        use std::iter;
+
+        /// A sequential stand-in for Rayon's parallel iterators.
+        ///
+        /// It is an `Iterator`, the inherent methods shadow the `Iterator` methods whose
+        /// signatures differ in Rayon, like `fold` and `reduce`.
+        pub(crate) struct Seq<I>(I);
+
+        impl<I: Iterator> Iterator for Seq<I> {
+            type Item = I::Item;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.0.next()
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.0.size_hint()
+            }
+        }
+
+        impl<I: Iterator> Seq<I> {
+            pub(crate) fn map<B, F>(self, f: F) -> Seq<iter::Map<I, F>>
+            where
+                F: FnMut(I::Item) -> B,
+            {
+                Seq(self.0.map(f))
+            }
+
+            pub(crate) fn flat_map<U, F>(self, f: F) -> Seq<iter::FlatMap<I, U, F>>
+            where
+                U: IntoIterator,
+                F: FnMut(I::Item) -> U,
+            {
+                Seq(self.0.flat_map(f))
+            }
+
+            pub(crate) fn zip<U: IntoIterator>(self, other: U) -> Seq<iter::Zip<I, U::IntoIter>> {
+                Seq(self.0.zip(other))
+            }
+
+            pub(crate) fn enumerate(self) -> Seq<iter::Enumerate<I>> {
+                Seq(self.0.enumerate())
+            }
+
+            /// Folds everything into a single item, as Rayon does when running on one thread.
+            pub(crate) fn fold<T, ID, F>(self, identity: ID, f: F) -> Seq<iter::Once<T>>
+            where
+                ID: Fn() -> T,
+                F: FnMut(T, I::Item) -> T,
+            {
+                Seq(iter::once(self.0.fold(identity(), f)))
+            }
+
+            pub(crate) fn reduce<ID, OP>(self, identity: ID, op: OP) -> I::Item
+            where
+                ID: Fn() -> I::Item,
+                OP: FnMut(I::Item, I::Item) -> I::Item,
+            {
+                self.0.fold(identity(), op)
+            }
+
+            pub(crate) fn find_first<P>(mut self, predicate: P) -> Option<I::Item>
+            where
+                P: FnMut(&I::Item) -> bool,
+            {
+                self.0.find(predicate)
+            }
+
+            pub(crate) fn find_any<P>(mut self, predicate: P) -> Option<I::Item>
+            where
+                P: FnMut(&I::Item) -> bool,
+            {
+                self.0.find(predicate)
+            }
+
+            pub(crate) fn with_min_len(self, _min: usize) -> Self {
+                self
+            }
+        }
+
+        /// Sequential `into_par_iter()`.
+        pub(crate) trait IntoParallelIterator: IntoIterator + Sized {
+            fn into_par_iter(self) -> Seq<Self::IntoIter> {
+                Seq(self.into_iter())
+            }
+        }
+
+        impl<T: IntoIterator> IntoParallelIterator for T {}
+
+        /// Sequential `par_iter()`.
+        pub(crate) trait IntoParallelRefIterator<'data> {
+            type Iter: Iterator;
+
+            fn par_iter(&'data self) -> Seq<Self::Iter>;
+        }
+
+        impl<'data, T: 'data + ?Sized> IntoParallelRefIterator<'data> for T
+        where
+            &'data T: IntoIterator,
+        {
+            type Iter = <&'data T as IntoIterator>::IntoIter;
+
+            fn par_iter(&'data self) -> Seq<Self::Iter> {
+                Seq(self.into_iter())
+            }
+        }
+
+        /// Sequential `par_iter_mut()`.
+        pub(crate) trait IntoParallelRefMutIterator<'data> {
+            type Iter: Iterator;
+
+            fn par_iter_mut(&'data mut self) -> Seq<Self::Iter>;
+        }
+
+        impl<'data, T: 'data + ?Sized> IntoParallelRefMutIterator<'data> for T
+        where
+            &'data mut T: IntoIterator,
+        {
+            type Iter = <&'data mut T as IntoIterator>::IntoIter;
+
+            fn par_iter_mut(&'data mut self) -> Seq<Self::Iter> {
+                Seq(self.into_iter())
+            }
+        }
+
+        /// Sequential `par_chunks()`.
+        pub(crate) trait ParallelSlice<T> {
+            fn par_chunks(&self, chunk_size: usize) -> Seq<std::slice::Chunks<'_, T>>;
+        }
+
+        impl<T> ParallelSlice<T> for [T] {
+            fn par_chunks(&self, chunk_size: usize) -> Seq<std::slice::Chunks<'_, T>> {
+                Seq(self.chunks(chunk_size))
+            }
+        }
+    }
+}
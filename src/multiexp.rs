@@ -1,10 +1,11 @@
+use std::ops::AddAssign;
 use std::sync::Arc;
 
 use ec_gpu_gen::multiexp_cpu::{multiexp_cpu, QueryDensity, SourceBuilder};
 use ec_gpu_gen::threadpool::{Waiter, Worker};
 use ec_gpu_gen::EcError;
 use ff::PrimeField;
-use group::prime::PrimeCurveAffine;
+use group::{prime::PrimeCurveAffine, Group};
 
 use crate::gpu;
 pub use ec_gpu_gen::multiexp_cpu::DensityTracker;
@@ -33,6 +34,15 @@ where
         let (bss, skip) = bases.clone().get();
         k.multiexp(pool, bss, exps, skip).map_err(Into::into)
     }) {
+        if gpu::should_check() {
+            let size = exponents.len();
+            let result_cpu = multiexp_cpu(pool, bases, density_map, exponents).wait();
+            if let Ok(ref c) = result_cpu {
+                if !gpu::check_multiexp(size, &p, c) {
+                    return Waiter::done(result_cpu);
+                }
+            }
+        }
         return Waiter::done(Ok(p));
     }
 
@@ -62,3 +72,126 @@ where
 {
     multiexp_cpu(pool, bases, density_map, exponents)
 }
+
+/// Perform multi-exponentiation on the current thread, without dispatching to the GPU or the
+/// thread pool. This has far less overhead than [`multiexp`] for small inputs. The caller is
+/// responsible for ensuring the query size is the same as the number of exponents.
+pub fn multiexp_serial<Q, D, G, S>(
+    bases: S,
+    density_map: D,
+    exponents: Arc<Vec<<G::Scalar as PrimeField>::Repr>>,
+) -> Result<<G as PrimeCurveAffine>::Curve, EcError>
+where
+    for<'a> &'a Q: QueryDensity,
+    D: AsRef<Q>,
+    G: PrimeCurveAffine,
+    S: SourceBuilder<G>,
+{
+    let exps = density_map.as_ref().generate_exps::<G::Scalar>(exponents);
+    let (bases, skip) = bases.get();
+    let bases = bases
+        .get(skip..skip + exps.len())
+        .ok_or(EcError::Simple("Expected more bases from source."))?;
+    if bases.iter().any(|base| bool::from(base.is_identity())) {
+        return Err(EcError::Simple(
+            "Encountered an identity element in the CRS.",
+        ));
+    }
+
+    // Bucket method with a window size growing logarithmically with the input size.
+    let c: usize = if exps.len() < 32 {
+        3
+    } else {
+        (f64::from(exps.len() as u32)).ln().ceil() as usize
+    };
+    let num_bits = G::Scalar::NUM_BITS as usize;
+
+    let mut acc = G::Curve::identity();
+    let mut buckets = vec![G::Curve::identity(); (1 << c) - 1];
+    for window_start in (0..num_bits).step_by(c).rev() {
+        for _ in 0..c {
+            acc = acc.double();
+        }
+
+        buckets
+            .iter_mut()
+            .for_each(|bucket| *bucket = G::Curve::identity());
+        for (base, exp) in bases.iter().zip(exps.iter()) {
+            let index = window_bits(exp.as_ref(), window_start, c);
+            if index != 0 {
+                buckets[index - 1].add_assign(base);
+            }
+        }
+
+        let mut running_sum = G::Curve::identity();
+        for bucket in buckets.iter().rev() {
+            running_sum.add_assign(bucket);
+            acc.add_assign(&running_sum);
+        }
+    }
+
+    Ok(acc)
+}
+
+/// Returns the `count` bits starting at bit `start` of the little-endian integer `bytes`.
+fn window_bits(bytes: &[u8], start: usize, count: usize) -> usize {
+    (0..count)
+        .map(|i| start + i)
+        .take_while(|bit| bit / 8 < bytes.len())
+        .fold(0, |acc, bit| {
+            acc | ((((bytes[bit / 8] >> (bit % 8)) & 1) as usize) << (bit - start))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::{G1Affine, G1Projective, Scalar as Fr};
+    use ec_gpu_gen::multiexp_cpu::{DensityTracker, FullDensity};
+    use ff::Field;
+    use group::{Curve, Group};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_multiexp_serial() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for n in [1, 5, 31, 32, 100] {
+            let bases = (0..n)
+                .map(|_| G1Projective::random(&mut rng).to_affine())
+                .collect::<Vec<G1Affine>>();
+            let scalars = (0..n).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+            let exps = Arc::new(scalars.iter().map(|s| s.to_repr()).collect::<Vec<_>>());
+
+            let expected = bases
+                .iter()
+                .zip(scalars.iter())
+                .map(|(base, scalar)| base * scalar)
+                .sum::<G1Projective>();
+            let result =
+                multiexp_serial((Arc::new(bases.clone()), 0), FullDensity, exps.clone()).unwrap();
+            assert_eq!(result, expected);
+
+            // Only every other exponent is part of the query.
+            let mut density = DensityTracker::new();
+            for i in 0..n {
+                density.add_element();
+                if i % 2 == 0 {
+                    density.inc(i);
+                }
+            }
+            let expected = bases
+                .iter()
+                .zip(scalars.iter().step_by(2))
+                .map(|(base, scalar)| base * scalar)
+                .sum::<G1Projective>();
+            let result = multiexp_serial((Arc::new(bases), 0), Arc::new(density), exps).unwrap();
+            assert_eq!(result, expected);
+        }
+    }
+}
@@ -2,7 +2,9 @@ use std::marker::PhantomData;
 
 use ff::PrimeField;
 
-use crate::{ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+use crate::{
+    ConstraintSystem, Index, LinearCombination, LookupConstraintSystem, SynthesisError, Variable,
+};
 
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug)]
@@ -99,3 +101,5 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for BenchCS<Scalar> {
         self
     }
 }
+
+impl<Scalar: PrimeField> LookupConstraintSystem<Scalar> for BenchCS<Scalar> {}
@@ -0,0 +1,289 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use ff::PrimeField;
+
+use super::Comparable;
+use crate::{Index, LinearCombination};
+
+/// The differences between two versions of a circuit, see [`diff`]. Variables and constraints
+/// are identified by their path.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CircuitDiff {
+    pub added_inputs: Vec<String>,
+    pub removed_inputs: Vec<String>,
+    pub added_aux: Vec<String>,
+    pub removed_aux: Vec<String>,
+    pub added_constraints: Vec<String>,
+    pub removed_constraints: Vec<String>,
+    /// Constraints in both versions whose linear combinations differ.
+    pub changed_constraints: Vec<String>,
+    /// Variables and constraints in both versions that moved relative to the others, i.e. the
+    /// fewest that need to be moved to get the order of the second version.
+    pub reordered: Vec<String>,
+}
+
+impl CircuitDiff {
+    /// Whether both versions have the same variables and constraints in the same order, which
+    /// means they are compatible with the same parameters.
+    pub fn is_empty(&self) -> bool {
+        self == &CircuitDiff::default()
+    }
+}
+
+impl fmt::Display for CircuitDiff {
+    /// One line per difference, sorted by path, so that the differences of a namespace are next
+    /// to each other.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = [
+            ("added input", &self.added_inputs),
+            ("removed input", &self.removed_inputs),
+            ("added aux", &self.added_aux),
+            ("removed aux", &self.removed_aux),
+            ("added constraint", &self.added_constraints),
+            ("removed constraint", &self.removed_constraints),
+            ("changed constraint", &self.changed_constraints),
+            ("reordered", &self.reordered),
+        ]
+        .into_iter()
+        .flat_map(|(kind, paths)| paths.iter().map(move |path| (path, kind)))
+        .collect::<Vec<_>>();
+        lines.sort();
+
+        for (path, kind) in lines {
+            writeln!(f, "{}: {}", path, kind)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares two recorded versions of a circuit, e.g. [`TestConstraintSystem`]s or
+/// [`MetricCS`]s, and reports the variables and constraints that were added, removed, changed or
+/// reordered.
+///
+/// Constraints are compared by the paths of the variables they refer to, so a constraint that
+/// refers to a variable which moved is not changed, the variable is reordered. If the diff is
+/// [empty](CircuitDiff::is_empty), the circuits synthesize to the same constraint system.
+///
+/// [`TestConstraintSystem`]: super::test_cs::TestConstraintSystem
+/// [`MetricCS`]: super::metric_cs::MetricCS
+pub fn diff<Scalar, A, B>(a: &A, b: &B) -> CircuitDiff
+where
+    Scalar: PrimeField,
+    A: Comparable<Scalar>,
+    B: Comparable<Scalar>,
+{
+    let mut diff = CircuitDiff::default();
+
+    let (a_inputs, b_inputs) = (a.inputs(), b.inputs());
+    let (a_aux, b_aux) = (a.aux(), b.aux());
+    compare(
+        &a_inputs,
+        &b_inputs,
+        &mut diff.added_inputs,
+        &mut diff.removed_inputs,
+        &mut diff.reordered,
+    );
+    compare(
+        &a_aux,
+        &b_aux,
+        &mut diff.added_aux,
+        &mut diff.removed_aux,
+        &mut diff.reordered,
+    );
+
+    let a_constraints = constraints(a, &a_inputs, &a_aux);
+    let b_constraints = constraints(b, &b_inputs, &b_aux);
+    let a_paths = a_constraints.iter().map(|c| c.0).collect::<Vec<_>>();
+    let b_paths = b_constraints.iter().map(|c| c.0).collect::<Vec<_>>();
+    compare(
+        &a_paths,
+        &b_paths,
+        &mut diff.added_constraints,
+        &mut diff.removed_constraints,
+        &mut diff.reordered,
+    );
+
+    let b_constraints = b_constraints.into_iter().collect::<HashMap<_, _>>();
+    for (path, lcs) in &a_constraints {
+        if matches!(b_constraints.get(path), Some(other) if other != lcs) {
+            diff.changed_constraints.push(path.to_string());
+        }
+    }
+
+    diff
+}
+
+/// A linear combination with the terms by the path of their variable, without zero terms.
+type Terms<'a, Scalar> = BTreeMap<&'a str, Scalar>;
+
+#[allow(clippy::type_complexity)]
+fn constraints<'a, Scalar: PrimeField, C: Comparable<Scalar>>(
+    cs: &'a C,
+    inputs: &'a [String],
+    aux: &'a [String],
+) -> Vec<(&'a str, [Terms<'a, Scalar>; 3])> {
+    let terms = |lc: &LinearCombination<Scalar>| {
+        let mut terms = Terms::new();
+        for (var, coeff) in lc.iter() {
+            let path = match var.get_unchecked() {
+                Index::Input(i) => inputs[i].as_str(),
+                Index::Aux(i) => aux[i].as_str(),
+            };
+            *terms.entry(path).or_insert(Scalar::ZERO) += coeff;
+        }
+        terms.retain(|_, coeff| !bool::from(coeff.is_zero()));
+        terms
+    };
+
+    cs.constraints()
+        .iter()
+        .map(|(a, b, c, path)| (path.as_str(), [terms(a), terms(b), terms(c)]))
+        .collect()
+}
+
+/// Records the paths that are only in `b` as added, the ones only in `a` as removed and the
+/// common ones that moved as reordered.
+fn compare<S: AsRef<str>>(
+    a: &[S],
+    b: &[S],
+    added: &mut Vec<String>,
+    removed: &mut Vec<String>,
+    reordered: &mut Vec<String>,
+) {
+    let a_paths = a.iter().map(AsRef::as_ref).collect::<HashSet<_>>();
+    let b_positions = b
+        .iter()
+        .enumerate()
+        .map(|(i, path)| (path.as_ref(), i))
+        .collect::<HashMap<_, _>>();
+
+    added.extend(
+        b.iter()
+            .map(AsRef::as_ref)
+            .filter(|path| !a_paths.contains(path))
+            .map(String::from),
+    );
+    removed.extend(
+        a.iter()
+            .map(AsRef::as_ref)
+            .filter(|path| !b_positions.contains_key(path))
+            .map(String::from),
+    );
+
+    // The common paths in the order of `a`, with their position in `b`. The ones in a longest
+    // increasing subsequence of the positions stay in place, the others moved.
+    let common = a
+        .iter()
+        .filter_map(|path| Some((path.as_ref(), *b_positions.get(path.as_ref())?)))
+        .collect::<Vec<_>>();
+    let mut stays = vec![false; common.len()];
+    for i in longest_increasing_subsequence(&common.iter().map(|c| c.1).collect::<Vec<_>>()) {
+        stays[i] = true;
+    }
+    reordered.extend(
+        common
+            .iter()
+            .zip(stays)
+            .filter(|(_, stays)| !stays)
+            .map(|((path, _), _)| path.to_string()),
+    );
+}
+
+/// Returns the indices of a longest strictly increasing subsequence of `values`.
+fn longest_increasing_subsequence(values: &[usize]) -> Vec<usize> {
+    // `tails[k]` is the index of the smallest value ending an increasing subsequence of length
+    // `k + 1`, `previous[i]` the index before `i` in the subsequence `i` ends.
+    let mut tails: Vec<usize> = vec![];
+    let mut previous = vec![None; values.len()];
+    for (i, value) in values.iter().enumerate() {
+        let k = tails.partition_point(|&j| values[j] < *value);
+        if k > 0 {
+            previous[i] = Some(tails[k - 1]);
+        }
+        if k == tails.len() {
+            tails.push(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+
+    let mut indices = vec![];
+    let mut next = tails.last().copied();
+    while let Some(i) = next {
+        indices.push(i);
+        next = previous[i];
+    }
+    indices.reverse();
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::ConstraintSystem;
+
+    /// Synthesizes `x^2 = y` and `y + x = z` in a namespace, with some variations.
+    fn circuit(swap_aux: bool, double: bool, extra: bool) -> TestConstraintSystem<Fr> {
+        let mut cs = TestConstraintSystem::new();
+        let x = cs.alloc_input(|| "x", || Ok(Fr::from(2u64))).unwrap();
+        {
+            let mut cs = cs.namespace(|| "gadget");
+            let (y, z) = if swap_aux {
+                let z = cs.alloc(|| "z", || Ok(Fr::from(6u64))).unwrap();
+                (cs.alloc(|| "y", || Ok(Fr::from(4u64))).unwrap(), z)
+            } else {
+                let y = cs.alloc(|| "y", || Ok(Fr::from(4u64))).unwrap();
+                (y, cs.alloc(|| "z", || Ok(Fr::from(6u64))).unwrap())
+            };
+            cs.enforce(|| "square", |lc| lc + x, |lc| lc + x, |lc| lc + y);
+            let coeff = if double {
+                Fr::from(2u64)
+            } else {
+                Fr::from(1u64)
+            };
+            cs.enforce(
+                || "sum",
+                |lc| lc + y + (coeff, x),
+                |lc| lc + TestConstraintSystem::<Fr>::one(),
+                |lc| lc + z,
+            );
+            if extra {
+                let w = cs.alloc(|| "w", || Ok(Fr::from(1u64))).unwrap();
+                cs.enforce(|| "boolean", |lc| lc + w, |lc| lc + w, |lc| lc + w);
+            }
+        }
+        cs
+    }
+
+    #[test]
+    fn test_diff() {
+        let base = circuit(false, false, false);
+        assert!(diff(&base, &circuit(false, false, false)).is_empty());
+
+        // The constraints refer to the same variables, but these moved.
+        let swapped = diff(&base, &circuit(true, false, false));
+        assert_eq!(swapped.reordered, ["gadget/y"]);
+        assert!(swapped.changed_constraints.is_empty());
+
+        let changed = diff(&base, &circuit(false, true, true));
+        assert_eq!(changed.changed_constraints, ["gadget/sum"]);
+        assert_eq!(changed.added_aux, ["gadget/w"]);
+        assert_eq!(changed.added_constraints, ["gadget/boolean"]);
+        assert!(changed.reordered.is_empty());
+        assert_eq!(
+            changed.to_string(),
+            "gadget/boolean: added constraint\ngadget/sum: changed constraint\ngadget/w: added aux\n"
+        );
+
+        let removed = diff(&circuit(false, false, true), &base);
+        assert_eq!(removed.removed_aux, ["gadget/w"]);
+        assert_eq!(removed.removed_constraints, ["gadget/boolean"]);
+
+        assert_eq!(longest_increasing_subsequence(&[2, 0, 1, 4, 3]), [1, 2, 4]);
+    }
+}
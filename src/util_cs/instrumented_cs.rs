@@ -0,0 +1,284 @@
+//! A constraint system that profiles the synthesis of a circuit into another one.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+
+use crate::{
+    ConstraintMetadata, ConstraintSystem, LinearCombination, LookupConstraintSystem,
+    SynthesisError, Variable,
+};
+
+/// The number of variables, constraints and terms of linear combinations of a namespace,
+/// including the ones of the namespaces nested in it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NamespaceMetrics {
+    pub inputs: usize,
+    pub aux: usize,
+    pub constraints: usize,
+    /// The number of terms of the A, B and C linear combinations of the constraints.
+    pub terms: usize,
+}
+
+/// Wraps a constraint system and records, per namespace, how many variables and constraints
+/// the circuit allocates and enforces in it, while forwarding everything to the wrapped
+/// constraint system.
+///
+/// This way circuits can be profiled while they are synthesized into the constraint system they
+/// are used with, e.g. a `ProvingAssignment` or a `WitnessCS`. Lookups are lowered to R1CS, see
+/// [`LookupConstraintSystem::enforce_lookup`], so their constraints are counted as well.
+///
+/// The names of all namespaces are evaluated, which makes synthesis slower.
+#[derive(Debug)]
+pub struct InstrumentedCS<Scalar: PrimeField, CS: ConstraintSystem<Scalar>> {
+    cs: CS,
+    // The metrics of every namespace by path, the root namespace has the empty path.
+    metrics: Vec<(String, NamespaceMetrics)>,
+    indices: HashMap<String, usize>,
+    // The indices of the current namespace and the ones it is nested in.
+    current_namespace: Vec<usize>,
+    _scalar: PhantomData<Scalar>,
+}
+
+impl<Scalar: PrimeField, CS: ConstraintSystem<Scalar>> InstrumentedCS<Scalar, CS> {
+    pub fn wrap(cs: CS) -> Self {
+        InstrumentedCS {
+            cs,
+            metrics: vec![(String::new(), NamespaceMetrics::default())],
+            indices: HashMap::new(),
+            current_namespace: vec![0],
+            _scalar: PhantomData,
+        }
+    }
+
+    /// The wrapped constraint system.
+    pub fn inner(&self) -> &CS {
+        &self.cs
+    }
+
+    pub fn into_inner(self) -> CS {
+        self.cs
+    }
+
+    /// The metrics of the namespace at `path`, the root namespace has the empty path.
+    pub fn metrics(&self, path: &str) -> Option<NamespaceMetrics> {
+        if path.is_empty() {
+            return Some(self.metrics[0].1);
+        }
+        self.indices.get(path).map(|&index| self.metrics[index].1)
+    }
+
+    /// The metrics of every namespace, in the order they were entered first.
+    pub fn all_metrics(&self) -> impl Iterator<Item = (&str, NamespaceMetrics)> + '_ {
+        self.metrics
+            .iter()
+            .map(|(path, metrics)| (path.as_str(), *metrics))
+    }
+
+    /// Formats the metrics of every namespace as a table, one line per namespace.
+    pub fn report(&self) -> String {
+        let mut report = format!(
+            "{:>10} {:>10} {:>12} {:>12}  namespace\n",
+            "inputs", "aux", "constraints", "terms"
+        );
+        for (path, metrics) in self.all_metrics() {
+            writeln!(
+                report,
+                "{:>10} {:>10} {:>12} {:>12}  {}",
+                metrics.inputs,
+                metrics.aux,
+                metrics.constraints,
+                metrics.terms,
+                if path.is_empty() { "/" } else { path }
+            )
+            .expect("writing to string never fails");
+        }
+        report
+    }
+
+    fn record_constraint(
+        &mut self,
+        a: &LinearCombination<Scalar>,
+        b: &LinearCombination<Scalar>,
+        c: &LinearCombination<Scalar>,
+    ) {
+        let terms = a.len() + b.len() + c.len();
+        self.record(|metrics| {
+            metrics.constraints += 1;
+            metrics.terms += terms;
+        });
+    }
+
+    /// Counts an event in the current namespace and all namespaces it is nested in.
+    fn record(&mut self, f: impl Fn(&mut NamespaceMetrics)) {
+        for &index in &self.current_namespace {
+            f(&mut self.metrics[index].1);
+        }
+    }
+}
+
+impl<Scalar: PrimeField, CS: ConstraintSystem<Scalar>> ConstraintSystem<Scalar>
+    for InstrumentedCS<Scalar, CS>
+{
+    type Root = Self;
+
+    fn new() -> Self {
+        Self::wrap(CS::new())
+    }
+
+    fn alloc<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let var = self.cs.alloc(annotation, f)?;
+        self.record(|metrics| metrics.aux += 1);
+        Ok(var)
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let var = self.cs.alloc_input(annotation, f)?;
+        self.record(|metrics| metrics.inputs += 1);
+        Ok(var)
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, annotation: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    {
+        let a = a(LinearCombination::zero());
+        let b = b(LinearCombination::zero());
+        let c = c(LinearCombination::zero());
+        self.record_constraint(&a, &b, &c);
+
+        self.cs.enforce(annotation, |_| a, |_| b, |_| c);
+    }
+
+    fn enforce_with_metadata<A, AR, LA, LB, LC>(
+        &mut self,
+        annotation: A,
+        metadata: ConstraintMetadata,
+        a: LA,
+        b: LB,
+        c: LC,
+    ) where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    {
+        let a = a(LinearCombination::zero());
+        let b = b(LinearCombination::zero());
+        let c = c(LinearCombination::zero());
+        self.record_constraint(&a, &b, &c);
+
+        self.cs
+            .enforce_with_metadata(annotation, metadata, |_| a, |_| b, |_| c);
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        let name = name_fn().into();
+        let parent = &self.metrics[*self.current_namespace.last().expect("root namespace")].0;
+        let path = if parent.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", parent, name)
+        };
+        let metrics = &mut self.metrics;
+        let index = *self.indices.entry(path).or_insert_with_key(|path| {
+            metrics.push((path.clone(), NamespaceMetrics::default()));
+            metrics.len() - 1
+        });
+        self.current_namespace.push(index);
+
+        self.cs.push_namespace(|| name);
+    }
+
+    fn pop_namespace(&mut self) {
+        assert!(
+            self.current_namespace.len() > 1,
+            "tried to pop the root namespace"
+        );
+        self.current_namespace.pop();
+        self.cs.pop_namespace();
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+impl<Scalar: PrimeField, CS: ConstraintSystem<Scalar>> LookupConstraintSystem<Scalar>
+    for InstrumentedCS<Scalar, CS>
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+
+    use crate::util_cs::test_cs::TestConstraintSystem;
+
+    #[test]
+    fn test_instrumented_cs() {
+        let mut cs = InstrumentedCS::<Fr, _>::wrap(TestConstraintSystem::new());
+        let x = cs.alloc_input(|| "x", || Ok(Fr::from(3u64))).unwrap();
+        {
+            let mut cs = cs.namespace(|| "square");
+            for i in 0..2 {
+                let mut cs = cs.namespace(|| format!("round {}", i));
+                let y = cs.alloc(|| "y", || Ok(Fr::from(9u64))).unwrap();
+                cs.enforce(|| "x * x = y", |lc| lc + x, |lc| lc + x, |lc| lc + y);
+            }
+        }
+        cs.namespace(|| "sum").enforce(
+            || "x + x + x = 9",
+            |lc| lc + (Fr::from(3u64), x),
+            |lc| lc + TestConstraintSystem::<Fr>::one(),
+            |lc| lc + (Fr::from(9u64), TestConstraintSystem::<Fr>::one()),
+        );
+
+        let metrics = |inputs, aux, constraints, terms| NamespaceMetrics {
+            inputs,
+            aux,
+            constraints,
+            terms,
+        };
+        assert_eq!(cs.metrics(""), Some(metrics(1, 2, 3, 9)));
+        assert_eq!(cs.metrics("square"), Some(metrics(0, 2, 2, 6)));
+        assert_eq!(cs.metrics("square/round 1"), Some(metrics(0, 1, 1, 3)));
+        assert_eq!(cs.metrics("sum"), Some(metrics(0, 0, 1, 3)));
+        assert_eq!(cs.metrics("missing"), None);
+        assert_eq!(
+            cs.all_metrics().map(|(path, _)| path).collect::<Vec<_>>(),
+            ["", "square", "square/round 0", "square/round 1", "sum"]
+        );
+        assert_eq!(cs.report().lines().count(), 6);
+
+        // Everything is forwarded to the wrapped constraint system.
+        let mut inner = cs.into_inner();
+        assert!(inner.is_satisfied());
+        assert_eq!(inner.num_constraints(), 3);
+        assert_eq!(inner.get("square/round 1/y"), Fr::from(9u64));
+        assert_eq!(inner.num_inputs(), 2);
+    }
+}
@@ -4,8 +4,13 @@ use std::fmt::Write;
 
 use super::Comparable;
 use ff::PrimeField;
+use serde::Serialize;
+#[cfg(feature = "json")]
+use serde_json::Value;
 
-use crate::{ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+use crate::{
+    ConstraintSystem, Index, LinearCombination, LookupConstraintSystem, SynthesisError, Variable,
+};
 
 #[derive(Clone, Copy)]
 struct OrderedVariable(Variable);
@@ -43,6 +48,16 @@ impl Ord for OrderedVariable {
     }
 }
 
+/// The number of constraints and variables of a namespace, including the ones of the
+/// namespaces nested in it, see [`MetricCS::top_namespaces`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct NamespaceCost {
+    pub namespace: String,
+    pub constraints: usize,
+    pub inputs: usize,
+    pub aux: usize,
+}
+
 #[allow(clippy::upper_case_acronyms)]
 pub struct MetricCS<Scalar: PrimeField> {
     named_objects: HashMap<String, NamedObject>,
@@ -193,6 +208,81 @@ impl<Scalar: PrimeField> MetricCS<Scalar> {
         s
     }
 
+    /// Aggregates the constraints and variables per namespace: each of them counts for the
+    /// namespace it is in and for every namespace that one is nested in.
+    pub fn namespace_costs(&self) -> BTreeMap<String, NamespaceCost> {
+        let mut costs = BTreeMap::<String, NamespaceCost>::new();
+        let mut add = |path: &str, count: fn(&mut NamespaceCost)| {
+            for (i, _) in path.match_indices('/') {
+                let cost = costs
+                    .entry(path[..i].to_string())
+                    .or_insert_with(|| NamespaceCost {
+                        namespace: path[..i].to_string(),
+                        ..Default::default()
+                    });
+                count(cost);
+            }
+        };
+        for path in &self.inputs {
+            add(path, |cost| cost.inputs += 1);
+        }
+        for path in &self.aux {
+            add(path, |cost| cost.aux += 1);
+        }
+        for (_, _, _, path) in &self.constraints {
+            add(path, |cost| cost.constraints += 1);
+        }
+        costs
+    }
+
+    /// Returns the `n` namespaces with the most constraints, then the most auxiliary variables.
+    /// With a `depth`, only namespaces nested at most `depth` levels deep are considered, e.g.
+    /// only the top level namespaces with a depth of 1.
+    pub fn top_namespaces(&self, n: usize, depth: Option<usize>) -> Vec<NamespaceCost> {
+        let mut costs = self
+            .namespace_costs()
+            .into_values()
+            .filter(|cost| depth.map_or(true, |depth| cost.namespace.split('/').count() <= depth))
+            .collect::<Vec<_>>();
+        costs.sort_by(|a, b| {
+            b.constraints
+                .cmp(&a.constraints)
+                .then(b.aux.cmp(&a.aux))
+                .then_with(|| a.namespace.cmp(&b.namespace))
+        });
+        costs.truncate(n);
+        costs
+    }
+
+    /// Formats the [`MetricCS::top_namespaces`] as a table, with the share of all constraints
+    /// of each namespace.
+    pub fn top_namespaces_report(&self, n: usize, depth: Option<usize>) -> String {
+        let total = self.num_constraints().max(1) as f64;
+        let mut s = format!(
+            "{:>12} {:>7} {:>10} {:>10}  namespace\n",
+            "constraints", "share", "inputs", "aux"
+        );
+        for cost in self.top_namespaces(n, depth) {
+            writeln!(
+                s,
+                "{:>12} {:>6.2}% {:>10} {:>10}  {}",
+                cost.constraints,
+                cost.constraints as f64 * 100.0 / total,
+                cost.inputs,
+                cost.aux,
+                cost.namespace
+            )
+            .expect("writing to string never fails");
+        }
+        s
+    }
+
+    /// The [`MetricCS::top_namespaces`] as a JSON array.
+    #[cfg(feature = "json")]
+    pub fn top_namespaces_json(&self, n: usize, depth: Option<usize>) -> Value {
+        serde_json::to_value(self.top_namespaces(n, depth)).expect("costs serialize to JSON")
+    }
+
     fn set_named_obj(&mut self, path: String, to: NamedObject) {
         if self.named_objects.contains_key(&path) {
             panic!("tried to create object at existing path: {}", path);
@@ -282,6 +372,8 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for MetricCS<Scalar> {
     }
 }
 
+impl<Scalar: PrimeField> LookupConstraintSystem<Scalar> for MetricCS<Scalar> {}
+
 fn compute_path(ns: &[String], this: &str) -> String {
     if this.chars().any(|a| a == '/') {
         panic!("'/' is not allowed in names");
@@ -301,3 +393,87 @@ fn compute_path(ns: &[String], this: &str) -> String {
 
     name
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+
+    #[test]
+    fn test_top_namespaces() {
+        let mut cs = MetricCS::<Fr>::new();
+        let x = cs.alloc_input(|| "x", || Ok(Fr::from(3u64))).unwrap();
+        {
+            let mut cs = cs.namespace(|| "hash");
+            for i in 0..3 {
+                let mut cs = cs.namespace(|| format!("round {}", i));
+                let y = cs.alloc(|| "y", || Ok(Fr::from(9u64))).unwrap();
+                cs.enforce(|| "x * x = y", |lc| lc + x, |lc| lc + x, |lc| lc + y);
+                if i == 2 {
+                    cs.enforce(
+                        || "y * 1 = y",
+                        |lc| lc + y,
+                        |lc| lc + MetricCS::<Fr>::one(),
+                        |lc| lc + y,
+                    );
+                }
+            }
+        }
+        {
+            let mut cs = cs.namespace(|| "range");
+            let bit = cs.alloc(|| "bit", || Ok(Fr::from(1u64))).unwrap();
+            cs.enforce(
+                || "boolean",
+                |lc| lc + bit,
+                |lc| lc + MetricCS::<Fr>::one() - bit,
+                |lc| lc,
+            );
+            cs.namespace(|| "output")
+                .alloc_input(|| "bit", || Ok(Fr::from(1u64)))
+                .unwrap();
+        }
+
+        let costs = cs.namespace_costs();
+        assert_eq!(costs.len(), 6);
+        assert_eq!(
+            costs["range"],
+            NamespaceCost {
+                namespace: "range".to_string(),
+                constraints: 1,
+                inputs: 1,
+                aux: 1,
+            }
+        );
+
+        let top = cs.top_namespaces(3, None);
+        let names = top
+            .iter()
+            .map(|cost| cost.namespace.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["hash", "hash/round 2", "hash/round 0"]);
+        assert_eq!(top[0].constraints, 4);
+        assert_eq!(top[0].aux, 3);
+
+        let top = cs.top_namespaces(10, Some(1));
+        let names = top
+            .iter()
+            .map(|cost| cost.namespace.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["hash", "range"]);
+
+        let report = cs.top_namespaces_report(2, Some(1));
+        assert_eq!(report.lines().count(), 3);
+        assert!(report
+            .lines()
+            .nth(1)
+            .unwrap()
+            .ends_with("80.00%          0          3  hash"));
+
+        #[cfg(feature = "json")]
+        assert_eq!(
+            cs.top_namespaces_json(1, None),
+            serde_json::json!([{ "namespace": "hash", "constraints": 4, "inputs": 0, "aux": 3 }])
+        );
+    }
+}
@@ -2,10 +2,17 @@ use crate::LinearCombination;
 use ff::PrimeField;
 
 pub mod bench_cs;
+mod diff;
+pub mod instrumented_cs;
 pub mod metric_cs;
+pub mod r1cs_writer_cs;
+pub mod shape_cs;
+pub mod solver_cs;
 pub mod test_cs;
 pub mod witness_cs;
 
+pub use self::diff::{diff, CircuitDiff};
+
 pub type Constraint<Scalar> = (
     LinearCombination<Scalar>,
     LinearCombination<Scalar>,
@@ -0,0 +1,264 @@
+//! Export of synthesized circuits to the `.r1cs` format of circom, to analyze them with external
+//! R1CS tooling. See [`crate::circuits::r1cs`] for how variables map to wires.
+
+use std::io::{self, Write};
+
+use ff::PrimeField;
+
+use crate::circuits::r1cs::{Constraint, R1cs, Terms};
+use crate::{
+    ConstraintSystem, Index, LinearCombination, LookupConstraintSystem, SynthesisError, Variable,
+};
+
+/// Records the constraints of a circuit during synthesis, without computing a witness.
+///
+/// The inputs become the public wires, the auxiliary variables the private wires after them.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug)]
+pub struct R1csWriterCS<Scalar: PrimeField> {
+    current_namespace: Vec<String>,
+    constraints: Vec<(
+        LinearCombination<Scalar>,
+        LinearCombination<Scalar>,
+        LinearCombination<Scalar>,
+    )>,
+    inputs: Vec<String>,
+    aux: Vec<String>,
+}
+
+impl<Scalar: PrimeField> R1csWriterCS<Scalar> {
+    pub fn new() -> Self {
+        R1csWriterCS::default()
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn num_aux(&self) -> usize {
+        self.aux.len()
+    }
+
+    /// The recorded constraint system.
+    pub fn r1cs(&self) -> R1cs<Scalar> {
+        let num_inputs = self.inputs.len();
+        let terms = |lc: &LinearCombination<Scalar>| -> Terms<Scalar> {
+            lc.iter()
+                .filter(|(_, coeff)| !bool::from(coeff.is_zero()))
+                .map(|(var, coeff)| match var.get_unchecked() {
+                    Index::Input(i) => (i, *coeff),
+                    Index::Aux(i) => (num_inputs + i, *coeff),
+                })
+                .collect()
+        };
+
+        R1cs {
+            num_wires: num_inputs + self.aux.len(),
+            num_public: num_inputs - 1,
+            constraints: self
+                .constraints
+                .iter()
+                .map(|(a, b, c)| Constraint {
+                    a: terms(a),
+                    b: terms(b),
+                    c: terms(c),
+                })
+                .collect(),
+        }
+    }
+
+    /// Writes the recorded constraint system in the `.r1cs` format.
+    pub fn write_r1cs<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.r1cs().write(writer)
+    }
+
+    /// Writes the symbol table in the `.sym` format of circom, one `label,wire,component,name`
+    /// line per wire except the constant one. The names are the paths of the variables, within a
+    /// `main` component like the ones of circom. Commas separate the fields, in names they are
+    /// replaced with semicolons.
+    pub fn write_symbols<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for (wire, name) in self.inputs.iter().chain(&self.aux).enumerate().skip(1) {
+            writeln!(
+                writer,
+                "{},{},0,main.{}",
+                wire,
+                wire,
+                name.replace(',', ";")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<Scalar: PrimeField> Default for R1csWriterCS<Scalar> {
+    fn default() -> Self {
+        R1csWriterCS {
+            current_namespace: vec![],
+            constraints: vec![],
+            inputs: vec![String::from("ONE")],
+            aux: vec![],
+        }
+    }
+}
+
+impl<Scalar: PrimeField> ConstraintSystem<Scalar> for R1csWriterCS<Scalar> {
+    type Root = Self;
+
+    fn new() -> Self {
+        R1csWriterCS::default()
+    }
+
+    fn alloc<F, A, AR>(&mut self, annotation: A, _f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.aux.push(self.path(annotation().into()));
+
+        Ok(Variable::new_unchecked(Index::Aux(self.aux.len() - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, annotation: A, _f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.inputs.push(self.path(annotation().into()));
+
+        Ok(Variable::new_unchecked(Index::Input(self.inputs.len() - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    {
+        let a = a(LinearCombination::zero());
+        let b = b(LinearCombination::zero());
+        let c = c(LinearCombination::zero());
+
+        self.constraints.push((a, b, c));
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.current_namespace.push(name_fn().into());
+    }
+
+    fn pop_namespace(&mut self) {
+        assert!(self.current_namespace.pop().is_some());
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+impl<Scalar: PrimeField> LookupConstraintSystem<Scalar> for R1csWriterCS<Scalar> {}
+
+impl<Scalar: PrimeField> R1csWriterCS<Scalar> {
+    fn path(&self, name: String) -> String {
+        let mut path = self.current_namespace.join("/");
+        if !path.is_empty() {
+            path.push('/');
+        }
+        path + &name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use blstrs::Scalar as Fr;
+    use ff::Field;
+
+    use crate::circuits::r1cs::R1csCircuit;
+    use crate::gadgets::boolean::{AllocatedBit, Boolean};
+    use crate::gadgets::test::TestConstraintSystem;
+    use crate::Circuit;
+
+    /// Proves knowledge of the bits of a public input.
+    struct Bits {
+        bits: Option<[bool; 3]>,
+    }
+
+    impl Circuit<Fr> for Bits {
+        fn synthesize<CS: ConstraintSystem<Fr>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            let num = self.bits.map(|bits| {
+                bits.iter()
+                    .rev()
+                    .fold(0, |acc, &bit| (acc << 1) | bit as u64)
+            });
+            let input = cs.alloc_input(
+                || "num",
+                || num.map(Fr::from).ok_or(SynthesisError::AssignmentMissing),
+            )?;
+
+            let mut lc = LinearCombination::zero();
+            let mut coeff = Fr::ONE;
+            for i in 0..3 {
+                let bit = AllocatedBit::alloc(
+                    cs.namespace(|| format!("bit {}", i)),
+                    self.bits.map(|bits| bits[i]),
+                )?;
+                lc = lc + &Boolean::from(bit).lc(CS::one(), coeff);
+                coeff = coeff.double();
+            }
+            cs.enforce(|| "packing", |_| lc, |lc| lc + CS::one(), |lc| lc + input);
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_r1cs_writer_cs() {
+        let circuit = Bits {
+            bits: Some([true, false, true]),
+        };
+        let mut cs = R1csWriterCS::<Fr>::new();
+        circuit.synthesize(&mut cs).unwrap();
+        assert_eq!(cs.num_inputs(), 2);
+        assert_eq!(cs.num_aux(), 3);
+        assert_eq!(cs.num_constraints(), 4);
+
+        let mut written = Vec::new();
+        cs.write_r1cs(&mut written).unwrap();
+        let r1cs = R1cs::read(Cursor::new(written)).unwrap();
+        assert_eq!(r1cs, cs.r1cs());
+        assert_eq!(r1cs.num_wires, 5);
+        assert_eq!(r1cs.num_public, 1);
+        // The input wire is right after the constant one, the bits after the inputs.
+        assert_eq!(r1cs.constraints[3].c, vec![(1, Fr::ONE)]);
+        assert_eq!(r1cs.constraints[0].b, vec![(2, Fr::ONE)]);
+
+        let mut symbols = Vec::new();
+        cs.write_symbols(&mut symbols).unwrap();
+        assert_eq!(
+            String::from_utf8(symbols).unwrap(),
+            "1,1,0,main.num\n2,2,0,main.bit 0/boolean\n3,3,0,main.bit 1/boolean\n4,4,0,main.bit 2/boolean\n"
+        );
+
+        // The circuit read back is satisfied by the same witness.
+        let witness = [1, 5, 1, 0, 1].into_iter().map(Fr::from).collect();
+        let mut test_cs = TestConstraintSystem::<Fr>::new();
+        R1csCircuit::new(r1cs.into(), Some(witness))
+            .synthesize(&mut test_cs)
+            .unwrap();
+        assert!(test_cs.is_satisfied());
+    }
+}
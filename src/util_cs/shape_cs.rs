@@ -0,0 +1,346 @@
+//! A constraint system that records the shape of a circuit, without its witness.
+
+use blake2s_simd::State as Blake2s;
+use ff::PrimeField;
+
+#[cfg(feature = "groth16")]
+use crate::groth16::QueryDensities;
+#[cfg(feature = "groth16")]
+use crate::Circuit;
+use crate::{
+    ConstraintSystem, Index, LinearCombination, LookupConstraintSystem, SynthesisError, Variable,
+};
+
+/// A constraint system that only records the shape of a circuit: the number of variables and
+/// constraints, and for every variable its coefficients in the A, B and C linear combinations of
+/// the constraints, as pairs of the coefficient and the index of the constraint.
+///
+/// The closures computing the assignments of variables are never invoked, so circuits can be
+/// synthesized without a witness, or with witness computations that are expensive. Parameter
+/// generation synthesizes circuits with it, which makes it the reference for the shape of a
+/// circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShapeCS<Scalar: PrimeField> {
+    pub(crate) num_inputs: usize,
+    pub(crate) num_aux: usize,
+    pub(crate) num_constraints: usize,
+    pub(crate) at_inputs: Vec<Vec<(Scalar, usize)>>,
+    pub(crate) bt_inputs: Vec<Vec<(Scalar, usize)>>,
+    pub(crate) ct_inputs: Vec<Vec<(Scalar, usize)>>,
+    pub(crate) at_aux: Vec<Vec<(Scalar, usize)>>,
+    pub(crate) bt_aux: Vec<Vec<(Scalar, usize)>>,
+    pub(crate) ct_aux: Vec<Vec<(Scalar, usize)>>,
+}
+
+impl<Scalar: PrimeField> ConstraintSystem<Scalar> for ShapeCS<Scalar> {
+    type Root = Self;
+
+    fn new() -> Self {
+        ShapeCS {
+            num_inputs: 0,
+            num_aux: 0,
+            num_constraints: 0,
+            at_inputs: vec![],
+            bt_inputs: vec![],
+            ct_inputs: vec![],
+            at_aux: vec![],
+            bt_aux: vec![],
+            ct_aux: vec![],
+        }
+    }
+
+    /// Explicitly declare this `ConstraintSystem` is not extensible as a reminder to future implementers.
+    /// By forbidding use of `ConstraintSystem::extend` when generating Groth parameters, we enforce
+    /// the requirement of a well-defined sequential circuit synthesis. This also means we know that any
+    /// synthesized `ProvingAssignment` is well-formed if it leads to a verifiable proof using the resulting
+    /// groth parameters and verifying key. This is true even if the `ProvingAssignment` was synthesized
+    /// in parallel components which were then joined by `ConstraintSystem::extend`.
+    fn is_extensible() -> bool {
+        false
+    }
+
+    fn alloc<F, A, AR>(&mut self, _: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // There is no assignment, so we don't even invoke the
+        // function for obtaining one.
+
+        let index = self.num_aux;
+        self.num_aux += 1;
+
+        self.at_aux.push(vec![]);
+        self.bt_aux.push(vec![]);
+        self.ct_aux.push(vec![]);
+
+        Ok(Variable(Index::Aux(index)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, _: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // There is no assignment, so we don't even invoke the
+        // function for obtaining one.
+
+        let index = self.num_inputs;
+        self.num_inputs += 1;
+
+        self.at_inputs.push(vec![]);
+        self.bt_inputs.push(vec![]);
+        self.ct_inputs.push(vec![]);
+
+        Ok(Variable(Index::Input(index)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    {
+        fn eval<Scalar: PrimeField>(
+            l: LinearCombination<Scalar>,
+            inputs: &mut [Vec<(Scalar, usize)>],
+            aux: &mut [Vec<(Scalar, usize)>],
+            this_constraint: usize,
+        ) {
+            for (index, coeff) in l.iter() {
+                match index {
+                    Variable(Index::Input(id)) => inputs[id].push((*coeff, this_constraint)),
+                    Variable(Index::Aux(id)) => aux[id].push((*coeff, this_constraint)),
+                }
+            }
+        }
+
+        eval(
+            a(LinearCombination::zero()),
+            &mut self.at_inputs,
+            &mut self.at_aux,
+            self.num_constraints,
+        );
+        eval(
+            b(LinearCombination::zero()),
+            &mut self.bt_inputs,
+            &mut self.bt_aux,
+            self.num_constraints,
+        );
+        eval(
+            c(LinearCombination::zero()),
+            &mut self.ct_inputs,
+            &mut self.ct_aux,
+            self.num_constraints,
+        );
+
+        self.num_constraints += 1;
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn pop_namespace(&mut self) {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+impl<Scalar: PrimeField> LookupConstraintSystem<Scalar> for ShapeCS<Scalar> {}
+
+impl<Scalar: PrimeField> ShapeCS<Scalar> {
+    pub fn num_inputs(&self) -> usize {
+        self.num_inputs
+    }
+
+    pub fn num_aux(&self) -> usize {
+        self.num_aux
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.num_constraints
+    }
+
+    /// The coefficients of a variable in the A linear combinations, with the index of their
+    /// constraint, in the order the constraints were enforced.
+    pub fn at(&self, var: Variable) -> &[(Scalar, usize)] {
+        match var.get_unchecked() {
+            Index::Input(i) => &self.at_inputs[i],
+            Index::Aux(i) => &self.at_aux[i],
+        }
+    }
+
+    /// The coefficients of a variable in the B linear combinations, see [`ShapeCS::at`].
+    pub fn bt(&self, var: Variable) -> &[(Scalar, usize)] {
+        match var.get_unchecked() {
+            Index::Input(i) => &self.bt_inputs[i],
+            Index::Aux(i) => &self.bt_aux[i],
+        }
+    }
+
+    /// The coefficients of a variable in the C linear combinations, see [`ShapeCS::at`].
+    pub fn ct(&self, var: Variable) -> &[(Scalar, usize)] {
+        match var.get_unchecked() {
+            Index::Input(i) => &self.ct_inputs[i],
+            Index::Aux(i) => &self.ct_aux[i],
+        }
+    }
+
+    /// A BLAKE2s digest of the shape, i.e. of the number of variables and constraints and of
+    /// the coefficients of every variable in every constraint. Circuits that synthesize to the
+    /// same constraints have the same fingerprint, whatever their witness.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let mut state = Blake2s::new();
+        for len in [self.num_inputs, self.num_aux, self.num_constraints] {
+            state.update(&(len as u64).to_be_bytes());
+        }
+        for lcs in [
+            &self.at_inputs,
+            &self.bt_inputs,
+            &self.ct_inputs,
+            &self.at_aux,
+            &self.bt_aux,
+            &self.ct_aux,
+        ] {
+            for lc in lcs {
+                state.update(&(lc.len() as u64).to_be_bytes());
+                for (coeff, constraint) in lc {
+                    state.update(coeff.to_repr().as_ref());
+                    state.update(&(*constraint as u64).to_be_bytes());
+                }
+            }
+        }
+
+        let mut fingerprint = [0; 32];
+        fingerprint.copy_from_slice(state.finalize().as_bytes());
+        fingerprint
+    }
+
+    /// Appends the shape of a shard, the same way [`ConstraintSystem::extend`] joins shards:
+    /// the first input of the shard is the "one" input, the other variables and the constraints
+    /// follow the ones of `self`.
+    pub(crate) fn append(&mut self, other: &Self) {
+        let shift = |lcs: &[Vec<(Scalar, usize)>], offset: usize| {
+            lcs.iter()
+                .map(|lc| {
+                    lc.iter()
+                        .map(|(coeff, constraint)| (*coeff, constraint + offset))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let offset = self.num_constraints;
+        for (lcs, shard) in [
+            (&mut self.at_inputs, &other.at_inputs),
+            (&mut self.bt_inputs, &other.bt_inputs),
+            (&mut self.ct_inputs, &other.ct_inputs),
+        ] {
+            let mut shard = shift(shard, offset).into_iter();
+            if let Some(one) = shard.next() {
+                lcs[0].extend(one);
+            }
+            lcs.extend(shard);
+        }
+        for (lcs, shard) in [
+            (&mut self.at_aux, &other.at_aux),
+            (&mut self.bt_aux, &other.bt_aux),
+            (&mut self.ct_aux, &other.ct_aux),
+        ] {
+            lcs.extend(shift(shard, offset));
+        }
+
+        self.num_inputs += other.num_inputs.saturating_sub(1);
+        self.num_aux += other.num_aux;
+        self.num_constraints += other.num_constraints;
+    }
+}
+
+#[cfg(feature = "groth16")]
+impl<Scalar: PrimeField> ShapeCS<Scalar> {
+    /// Synthesizes the circuit together with the "one" input and the input constraints.
+    pub(crate) fn synthesize<C: Circuit<Scalar>>(circuit: C) -> Result<Self, SynthesisError> {
+        let mut assembly = ShapeCS::new();
+
+        // Allocate the "one" input variable
+        assembly.alloc_input(|| "", || Ok(Scalar::ONE))?;
+
+        // Synthesize the circuit.
+        circuit.synthesize(&mut assembly)?;
+
+        // Input constraints to ensure full density of IC query
+        // x * 0 = 0
+        for i in 0..assembly.num_inputs {
+            assembly.enforce(|| "", |lc| lc + Variable(Index::Input(i)), |lc| lc, |lc| lc);
+        }
+
+        Ok(assembly)
+    }
+
+    /// The densities of the A and B queries, if the circuit was synthesized for parameter
+    /// generation, with the "one" input and the input constraints.
+    pub fn densities(&self) -> QueryDensities {
+        // A variable is in a query if it has a non-zero coefficient in one of its linear
+        // combinations, the same way the prover tracks the densities. Inputs have full density
+        // in the A query, see the input constraints.
+        let is_dense = |lcs: &[Vec<(Scalar, usize)>]| {
+            lcs.iter()
+                .map(|lc| lc.iter().any(|(coeff, _)| !coeff.is_zero_vartime()))
+                .collect::<Vec<_>>()
+        };
+        QueryDensities::new(
+            self.num_inputs,
+            self.num_aux,
+            is_dense(&self.at_aux),
+            is_dense(&self.bt_inputs),
+            is_dense(&self.bt_aux),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+    use ff::Field;
+
+    #[test]
+    fn test_shape_cs() {
+        let mut cs = ShapeCS::<Fr>::new();
+        let x = cs
+            .alloc_input(|| "x", || panic!("the assignment is never computed"))
+            .unwrap();
+        let y = cs
+            .alloc(|| "y", || panic!("the assignment is never computed"))
+            .unwrap();
+        cs.enforce(|| "x * x = y", |lc| lc + x, |lc| lc + x, |lc| lc + y);
+        cs.enforce(
+            || "2y = x + 1",
+            |lc| lc + (Fr::from(2), y),
+            |lc| lc + ShapeCS::<Fr>::one(),
+            |lc| lc + x + ShapeCS::<Fr>::one(),
+        );
+
+        assert_eq!(cs.num_inputs(), 1);
+        assert_eq!(cs.num_aux(), 1);
+        assert_eq!(cs.num_constraints(), 2);
+        assert_eq!(cs.at(x), &[(Fr::ONE, 0)]);
+        assert_eq!(cs.bt(x), &[(Fr::ONE, 0)]);
+        assert_eq!(cs.ct(x), &[(Fr::ONE, 1)]);
+        assert_eq!(cs.at(y), &[(Fr::from(2), 1)]);
+        assert!(cs.bt(y).is_empty());
+        assert_eq!(cs.ct(y), &[(Fr::ONE, 0)]);
+    }
+}
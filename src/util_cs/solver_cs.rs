@@ -0,0 +1,340 @@
+//! A constraint system that derives missing assignments from the constraints.
+
+use std::collections::VecDeque;
+
+use ff::PrimeField;
+
+use crate::{
+    Circuit, ConstraintSystem, Index, LinearCombination, LookupConstraintSystem, SynthesisError,
+    Variable,
+};
+
+/// A `ConstraintSystem` that computes the witness of a circuit like [`WitnessCS`] does, but
+/// derives the assignments of variables whose closure fails with
+/// [`SynthesisError::AssignmentMissing`] from the constraints, after synthesis.
+///
+/// A variable is derived from a constraint `a * b = c` if it is the only unknown variable of the
+/// constraint and the constraint is linear in it: if it is in `c` while `a` and `b` are known, or
+/// if it is in `a` or `b` while the other two are known and the other factor isn't zero. Derived
+/// variables may in turn determine others, so gadgets only need to assign the variables that
+/// don't follow from their inputs, e.g. the bits of a decomposition or inverses.
+///
+/// [`WitnessCS`]: super::witness_cs::WitnessCS
+pub struct SolverCS<Scalar: PrimeField> {
+    inputs: Vec<Option<Scalar>>,
+    aux: Vec<Option<Scalar>>,
+    constraints: Vec<(
+        LinearCombination<Scalar>,
+        LinearCombination<Scalar>,
+        LinearCombination<Scalar>,
+    )>,
+    // The variables whose assignment is missing, with their path.
+    missing: Vec<(Variable, String)>,
+    current_namespace: Vec<String>,
+}
+
+/// The known part of a linear combination and its unknown terms.
+struct PartialEval<Scalar> {
+    known: Scalar,
+    unknown: Vec<(Variable, Scalar)>,
+}
+
+impl<Scalar: PrimeField> SolverCS<Scalar> {
+    /// Synthesizes the circuit and derives the missing assignments, see [`SolverCS::solve`].
+    /// Returns the witness in the order of the wires of [`R1cs`](crate::circuits::r1cs::R1cs):
+    /// the "one" input, the other inputs and the auxiliary variables.
+    pub fn solve_circuit<C: Circuit<Scalar>>(circuit: C) -> Result<Vec<Scalar>, SynthesisError> {
+        let mut cs = Self::new();
+        circuit.synthesize(&mut cs)?;
+        cs.solve()?;
+        Ok(cs.witness())
+    }
+
+    /// Derives the missing assignments from the constraints. Fails with
+    /// [`SynthesisError::AssignmentMissing`], located at the path of the variable, if one of
+    /// them isn't determined by the constraints.
+    pub fn solve(&mut self) -> Result<(), SynthesisError> {
+        // The constraints each unknown variable occurs in.
+        let mut occurrences = vec![vec![]; self.inputs.len() + self.aux.len()];
+        for (i, (a, b, c)) in self.constraints.iter().enumerate() {
+            for (var, _) in a.iter().chain(b.iter()).chain(c.iter()) {
+                if self.value(var).is_none() {
+                    occurrences[self.slot(var)].push(i);
+                }
+            }
+        }
+
+        let mut queue = (0..self.constraints.len()).collect::<VecDeque<_>>();
+        while let Some(i) = queue.pop_front() {
+            if let Some((var, value)) = self.solve_constraint(i) {
+                let slot = self.slot(var);
+                match var.get_unchecked() {
+                    Index::Input(index) => self.inputs[index] = Some(value),
+                    Index::Aux(index) => self.aux[index] = Some(value),
+                }
+                queue.extend(occurrences[slot].drain(..));
+            }
+        }
+
+        for (var, path) in &self.missing {
+            if self.value(*var).is_none() {
+                return Err(SynthesisError::AssignmentMissing.at_path(path.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The assignment of every wire, with the same layout as [`SolverCS::solve_circuit`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if an assignment is missing, i.e. if [`SolverCS::solve`] wasn't called or failed.
+    pub fn witness(&self) -> Vec<Scalar> {
+        self.inputs
+            .iter()
+            .chain(&self.aux)
+            .map(|value| value.expect("all assignments are known"))
+            .collect()
+    }
+
+    /// Whether all assignments are known and satisfy the constraints.
+    pub fn is_satisfied(&self) -> bool {
+        self.constraints.iter().all(|(a, b, c)| {
+            let eval = |lc: &LinearCombination<Scalar>| {
+                let eval = self.eval(lc);
+                eval.unknown.is_empty().then_some(eval.known)
+            };
+            match (eval(a), eval(b), eval(c)) {
+                (Some(a), Some(b), Some(c)) => a * b == c,
+                _ => false,
+            }
+        })
+    }
+
+    /// Records the path of a variable whose assignment is missing, or forwards the error.
+    fn assignment<F, A, AR>(
+        &mut self,
+        var: Variable,
+        annotation: A,
+        f: F,
+    ) -> Result<Option<Scalar>, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        match f() {
+            Ok(value) => Ok(Some(value)),
+            Err(SynthesisError::AssignmentMissing) => {
+                let mut path = self.current_namespace.clone();
+                path.push(annotation().into());
+                self.missing.push((var, path.join("/")));
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn value(&self, var: Variable) -> Option<Scalar> {
+        match var.get_unchecked() {
+            Index::Input(index) => self.inputs[index],
+            Index::Aux(index) => self.aux[index],
+        }
+    }
+
+    fn slot(&self, var: Variable) -> usize {
+        match var.get_unchecked() {
+            Index::Input(index) => index,
+            Index::Aux(index) => self.inputs.len() + index,
+        }
+    }
+
+    fn eval(&self, lc: &LinearCombination<Scalar>) -> PartialEval<Scalar> {
+        let mut eval = PartialEval {
+            known: Scalar::ZERO,
+            unknown: vec![],
+        };
+        for (var, coeff) in lc.iter() {
+            match self.value(var) {
+                Some(value) => eval.known += value * coeff,
+                None if !bool::from(coeff.is_zero()) => eval.unknown.push((var, *coeff)),
+                None => {}
+            }
+        }
+        eval
+    }
+
+    /// Returns the unknown variable the constraint determines, with its value.
+    fn solve_constraint(&self, i: usize) -> Option<(Variable, Scalar)> {
+        let (a, b, c) = &self.constraints[i];
+        let (a, b, c) = (self.eval(a), self.eval(b), self.eval(c));
+
+        // Solves `known + coeff * var = target` for the only unknown `var`.
+        let solve = |eval: &PartialEval<Scalar>, target: Scalar| {
+            let (var, coeff) = eval.unknown[0];
+            let inverse = Option::<Scalar>::from(coeff.invert())?;
+            Some((var, (target - eval.known) * inverse))
+        };
+        let divide = |c: Scalar, factor: Scalar| -> Option<Scalar> {
+            Option::<Scalar>::from(factor.invert()).map(|inverse| c * inverse)
+        };
+
+        match (a.unknown.len(), b.unknown.len(), c.unknown.len()) {
+            (0, 0, 1) => solve(&c, a.known * b.known),
+            (1, 0, 0) => solve(&a, divide(c.known, b.known)?),
+            (0, 1, 0) => solve(&b, divide(c.known, a.known)?),
+            _ => None,
+        }
+    }
+}
+
+impl<Scalar: PrimeField> Default for SolverCS<Scalar> {
+    fn default() -> Self {
+        SolverCS {
+            inputs: vec![Some(Scalar::ONE)],
+            aux: vec![],
+            constraints: vec![],
+            missing: vec![],
+            current_namespace: vec![],
+        }
+    }
+}
+
+impl<Scalar: PrimeField> ConstraintSystem<Scalar> for SolverCS<Scalar> {
+    type Root = Self;
+
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn alloc<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let var = Variable(Index::Aux(self.aux.len()));
+        let value = self.assignment(var, annotation, f)?;
+        self.aux.push(value);
+
+        Ok(var)
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let var = Variable(Index::Input(self.inputs.len()));
+        let value = self.assignment(var, annotation, f)?;
+        self.inputs.push(value);
+
+        Ok(var)
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    {
+        let a = a(LinearCombination::zero());
+        let b = b(LinearCombination::zero());
+        let c = c(LinearCombination::zero());
+
+        self.constraints.push((a, b, c));
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.current_namespace.push(name_fn().into());
+    }
+
+    fn pop_namespace(&mut self) {
+        assert!(self.current_namespace.pop().is_some());
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+impl<Scalar: PrimeField> LookupConstraintSystem<Scalar> for SolverCS<Scalar> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+    use ff::Field;
+
+    /// Proves knowledge of `x` with `(3 * x^2 + x) / x = out`, only `x` is assigned.
+    struct Derived {
+        x: Option<Fr>,
+    }
+
+    impl Circuit<Fr> for Derived {
+        fn synthesize<CS: ConstraintSystem<Fr>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            let missing = || Err(SynthesisError::AssignmentMissing);
+            let x = cs.alloc(|| "x", || self.x.ok_or(SynthesisError::AssignmentMissing))?;
+            let out = cs.alloc_input(|| "out", missing)?;
+            let mut cs = cs.namespace(|| "gadget");
+            // Allocated before the variables it is derived from.
+            let quotient = cs.alloc(|| "quotient", missing)?;
+            let square = cs.alloc(|| "square", missing)?;
+            let sum = cs.alloc(|| "sum", missing)?;
+            cs.enforce(
+                || "quotient",
+                |lc| lc + x,
+                |lc| lc + quotient,
+                |lc| lc + sum,
+            );
+            cs.enforce(
+                || "sum",
+                |lc| lc + (Fr::from(3u64), square) + x,
+                |lc| lc + CS::one(),
+                |lc| lc + sum,
+            );
+            cs.enforce(|| "square", |lc| lc + x, |lc| lc + x, |lc| lc + square);
+            cs.enforce(
+                || "out",
+                |lc| lc + quotient,
+                |lc| lc + CS::one(),
+                |lc| lc + out,
+            );
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_solver_cs() {
+        let x = Fr::from(5u64);
+        let witness = SolverCS::solve_circuit(Derived { x: Some(x) }).unwrap();
+        let square = x.square();
+        let sum = square * Fr::from(3u64) + x;
+        let quotient = sum * x.invert().unwrap();
+        assert_eq!(witness, [Fr::ONE, quotient, x, quotient, square, sum]);
+
+        let mut cs = SolverCS::<Fr>::new();
+        Derived { x: Some(x) }.synthesize(&mut cs).unwrap();
+        assert!(!cs.is_satisfied());
+        cs.solve().unwrap();
+        assert!(cs.is_satisfied());
+
+        // Without `x` nothing is determined.
+        let err = SolverCS::<Fr>::solve_circuit(Derived { x: None }).unwrap_err();
+        assert_eq!(err.path(), Some("x"));
+
+        // The quotient isn't determined by `0 * quotient = 0`.
+        let err = SolverCS::solve_circuit(Derived { x: Some(Fr::ZERO) }).unwrap_err();
+        assert_eq!(err.path(), Some("out"));
+        assert!(matches!(err.unlocated(), SynthesisError::AssignmentMissing));
+    }
+}
@@ -3,11 +3,20 @@ use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::fmt::Write;
 
-use super::Comparable;
-use crate::{ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+use super::{Comparable, Constraint};
+use crate::multicore::prelude::*;
+use crate::{
+    ConstraintMetadata, ConstraintSystem, Index, LinearCombination, LookupConstraintSystem,
+    SynthesisError, Variable,
+};
 use blake2s_simd::State as Blake2s;
 use byteorder::{BigEndian, ByteOrder};
 use ff::PrimeField;
+#[cfg(feature = "json")]
+use serde_json::{json, Value};
+
+/// The number of constraints below which evaluating them isn't split further across threads.
+const MIN_CONSTRAINTS_PER_JOB: usize = 1024;
 
 #[derive(Debug)]
 enum NamedObject {
@@ -27,10 +36,22 @@ pub struct TestConstraintSystem<Scalar: PrimeField> {
         LinearCombination<Scalar>,
         String,
     )>,
+    // The metadata of the constraints enforced with some, by index.
+    metadata: HashMap<usize, ConstraintMetadata>,
     inputs: Vec<(Scalar, String)>,
     aux: Vec<(Scalar, String)>,
 }
 
+/// A constraint `a * b = c` that doesn't hold, with its path and the values its linear
+/// combinations evaluate to, see [`TestConstraintSystem::all_unsatisfied`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnsatisfiedConstraint<'a, Scalar> {
+    pub path: &'a str,
+    pub a: Scalar,
+    pub b: Scalar,
+    pub c: Scalar,
+}
+
 #[derive(Clone, Copy)]
 struct OrderedVariable(Variable);
 
@@ -113,6 +134,73 @@ fn hash_lc<Scalar: PrimeField>(terms: &LinearCombination<Scalar>, h: &mut Blake2
     }
 }
 
+/// Formats a scalar as big-endian hex, like [`hash_lc`] hashes it.
+fn scalar_to_hex<Scalar: PrimeField>(scalar: &Scalar) -> String {
+    let mut s = String::from("0x");
+    for b in scalar.to_repr().as_ref().iter().rev() {
+        write!(s, "{:02x}", b).expect("writing to string never fails");
+    }
+    s
+}
+
+/// Escapes a string for a quoted Graphviz identifier.
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// The number of variables and constraints in a namespace and all namespaces below it.
+#[cfg(feature = "json")]
+#[derive(Default)]
+struct NamespaceStats {
+    variables: usize,
+    constraints: usize,
+    children: BTreeMap<String, NamespaceStats>,
+}
+
+#[cfg(feature = "json")]
+impl NamespaceStats {
+    /// Counts an object at `path` in every namespace it is nested in.
+    fn add(&mut self, path: &str, variable: bool) {
+        let mut names = path.split('/').collect::<Vec<_>>();
+        names.pop();
+        let mut stats = self;
+        stats.count(variable);
+        for name in names {
+            stats = stats.children.entry(name.to_string()).or_default();
+            stats.count(variable);
+        }
+    }
+
+    fn count(&mut self, variable: bool) {
+        if variable {
+            self.variables += 1;
+        } else {
+            self.constraints += 1;
+        }
+    }
+
+    /// Creates the namespace at `path`, which may be empty.
+    fn add_namespace(&mut self, path: &str) {
+        let mut stats = self;
+        for name in path.split('/') {
+            stats = stats.children.entry(name.to_string()).or_default();
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let children = self
+            .children
+            .iter()
+            .map(|(name, child)| (name.clone(), child.to_json()))
+            .collect::<serde_json::Map<_, _>>();
+        json!({
+            "variables": self.variables,
+            "constraints": self.constraints,
+            "namespaces": children,
+        })
+    }
+}
+
 fn _eval_lc2<Scalar: PrimeField>(
     terms: &LinearCombination<Scalar>,
     inputs: &[Scalar],
@@ -165,6 +253,7 @@ impl<Scalar: PrimeField> Default for TestConstraintSystem<Scalar> {
             named_objects: map,
             current_namespace: vec![],
             constraints: vec![],
+            metadata: HashMap::new(),
             inputs: vec![(Scalar::ONE, "ONE".into())],
             aux: vec![],
         }
@@ -197,8 +286,11 @@ impl<Scalar: PrimeField> TestConstraintSystem<Scalar> {
             result.push(format!("AUX {}", aux.1));
         }
 
-        for &(ref _a, ref _b, ref _c, ref name) in &self.constraints {
-            result.push(name.to_string());
+        for (i, (_a, _b, _c, name)) in self.constraints.iter().enumerate() {
+            match self.metadata.get(&i) {
+                Some(metadata) => result.push(format!("{} [{}]", name, metadata)),
+                None => result.push(name.to_string()),
+            }
         }
 
         result
@@ -235,20 +327,223 @@ impl<Scalar: PrimeField> TestConstraintSystem<Scalar> {
         s
     }
 
+    /// Exports the constraint system as JSON, for inspecting it with other tools.
+    ///
+    /// It has the `inputs` and `aux` variables with their paths and values, the `constraints`
+    /// with their paths and the terms of their A, B and C linear combinations, which refer to
+    /// variables by path, and the tree of `namespaces` with the number of variables and
+    /// constraints in each, including those of nested namespaces. Scalars are big-endian hex.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Value {
+        let variables = |vars: &[(Scalar, String)]| {
+            vars.iter()
+                .map(|(value, path)| json!({ "path": path, "value": scalar_to_hex(value) }))
+                .collect::<Vec<_>>()
+        };
+        let terms = |lc: &LinearCombination<Scalar>| {
+            proc_lc::<Scalar>(lc)
+                .iter()
+                .map(|(var, coeff)| {
+                    json!({ "variable": self.var_path(var.0), "coeff": scalar_to_hex(coeff) })
+                })
+                .collect::<Vec<_>>()
+        };
+        let constraints = self
+            .constraints
+            .iter()
+            .enumerate()
+            .map(|(i, (a, b, c, path))| {
+                let mut constraint =
+                    json!({ "path": path, "a": terms(a), "b": terms(b), "c": terms(c) });
+                if let Some(metadata) = self.metadata.get(&i) {
+                    constraint["metadata"] = json!({
+                        "tags": metadata.tags,
+                        "severity": metadata.severity.map(|severity| severity.to_string()),
+                        "location": metadata.location.map(|location| location.to_string()),
+                    });
+                }
+                constraint
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "inputs": variables(&self.inputs),
+            "aux": variables(&self.aux),
+            "constraints": constraints,
+            "namespaces": self.namespace_stats().to_json(),
+        })
+    }
+
+    /// Exports the connectivity of the constraint system as a Graphviz graph, with an ellipse
+    /// per variable, a box per constraint and an edge from every variable to the constraints it
+    /// occurs in, labelled with the linear combinations it occurs in.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph constraints {\n");
+        for (prefix, vars) in [("i", &self.inputs), ("a", &self.aux)] {
+            for (i, (_, path)) in vars.iter().enumerate() {
+                writeln!(dot, "  {}{} [label=\"{}\"];", prefix, i, dot_escape(path))
+                    .expect("writing to string never fails");
+            }
+        }
+        for (i, (a, b, c, path)) in self.constraints.iter().enumerate() {
+            writeln!(dot, "  c{} [shape=box, label=\"{}\"];", i, dot_escape(path))
+                .expect("writing to string never fails");
+
+            let mut edges = BTreeMap::<OrderedVariable, String>::new();
+            for (lc, name) in [(a, "a"), (b, "b"), (c, "c")] {
+                for var in proc_lc::<Scalar>(lc).keys() {
+                    edges.entry(*var).or_default().push_str(name);
+                }
+            }
+            for (var, label) in edges {
+                let node = match var.0.get_unchecked() {
+                    Index::Input(index) => format!("i{}", index),
+                    Index::Aux(index) => format!("a{}", index),
+                };
+                writeln!(dot, "  {} -> c{} [label=\"{}\"];", node, i, label)
+                    .expect("writing to string never fails");
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn var_path(&self, var: Variable) -> &str {
+        match var.get_unchecked() {
+            Index::Input(index) => &self.inputs[index].1,
+            Index::Aux(index) => &self.aux[index].1,
+        }
+    }
+
+    #[cfg(feature = "json")]
+    fn namespace_stats(&self) -> NamespaceStats {
+        let mut stats = NamespaceStats::default();
+        for (path, object) in &self.named_objects {
+            if let NamedObject::Namespace = object {
+                stats.add_namespace(path);
+            }
+        }
+        for (_, path) in self.inputs.iter().chain(&self.aux) {
+            stats.add(path, true);
+        }
+        for (_, _, _, path) in &self.constraints {
+            stats.add(path, false);
+        }
+        stats
+    }
+
+    /// Evaluates a constraint, returns it if it doesn't hold.
+    fn check<'a>(
+        &self,
+        (a, b, c, path): &'a Constraint<Scalar>,
+    ) -> Option<UnsatisfiedConstraint<'a, Scalar>> {
+        let a = eval_lc::<Scalar>(a, &self.inputs, &self.aux);
+        let b = eval_lc::<Scalar>(b, &self.inputs, &self.aux);
+        let c = eval_lc::<Scalar>(c, &self.inputs, &self.aux);
+
+        if a * b != c {
+            Some(UnsatisfiedConstraint { path, a, b, c })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the path of the first constraint that doesn't hold. The constraints are evaluated
+    /// in parallel, the ones after an unsatisfied constraint are skipped.
     pub fn which_is_unsatisfied(&self) -> Option<&str> {
-        for &(ref a, ref b, ref c, ref path) in &self.constraints {
-            let mut a = eval_lc::<Scalar>(a, &self.inputs, &self.aux);
-            let b = eval_lc::<Scalar>(b, &self.inputs, &self.aux);
-            let c = eval_lc::<Scalar>(c, &self.inputs, &self.aux);
+        self.constraints
+            .par_iter()
+            .with_min_len(MIN_CONSTRAINTS_PER_JOB)
+            .find_first(|constraint| self.check(constraint).is_some())
+            .map(|(_, _, _, path)| path.as_str())
+    }
 
-            a.mul_assign(&b);
+    /// Returns the path of any constraint that doesn't hold, not necessarily the first one. The
+    /// evaluation stops as soon as one is found, which makes it faster than
+    /// [`TestConstraintSystem::which_is_unsatisfied`] when several constraints don't hold.
+    pub fn find_unsatisfied(&self) -> Option<&str> {
+        self.constraints
+            .par_iter()
+            .with_min_len(MIN_CONSTRAINTS_PER_JOB)
+            .find_any(|constraint| self.check(constraint).is_some())
+            .map(|(_, _, _, path)| path.as_str())
+    }
 
-            if a != c {
-                return Some(path);
+    /// Returns every constraint that doesn't hold, in the order they were enforced.
+    pub fn all_unsatisfied(&self) -> Vec<UnsatisfiedConstraint<'_, Scalar>> {
+        self.constraints
+            .par_iter()
+            .with_min_len(MIN_CONSTRAINTS_PER_JOB)
+            .filter_map(|constraint| self.check(constraint))
+            .collect()
+    }
+
+    /// Explains how the constraint at `path` evaluates: for each of its linear combinations
+    /// A, B and C, every term with its coefficient, variable path and assigned value, followed by
+    /// the partial sum up to that term. Scalars are big-endian hex. The metadata of the
+    /// constraint, if it has any, comes first.
+    pub fn debug_constraint(&self, path: &str) -> String {
+        let (index, (a, b, c, _)) = match self.named_objects.get(path) {
+            Some(&NamedObject::Constraint(index)) => (index, &self.constraints[index]),
+            Some(e) => panic!(
+                "tried to debug constraint `{}`, but `{:?}` exists there (not a constraint)",
+                path, e
+            ),
+            _ => panic!("no constraint exists at path: {}", path),
+        };
+
+        let mut s = String::new();
+        if let Some(metadata) = self.metadata.get(&index) {
+            writeln!(s, "{}", metadata).expect("writing to string never fails");
+        }
+        let mut values = vec![];
+        for (lc, name) in [(a, "A"), (b, "B"), (c, "C")] {
+            writeln!(s, "{}:", name).expect("writing to string never fails");
+            let mut acc = Scalar::ZERO;
+            for (var, coeff) in lc.iter() {
+                let value = match var.get_unchecked() {
+                    Index::Input(index) => self.inputs[index].0,
+                    Index::Aux(index) => self.aux[index].0,
+                };
+                acc += value * coeff;
+                writeln!(
+                    s,
+                    "  {} * {} ({}) => {}",
+                    scalar_to_hex(coeff),
+                    self.var_path(var),
+                    scalar_to_hex(&value),
+                    scalar_to_hex(&acc)
+                )
+                .expect("writing to string never fails");
             }
+            writeln!(s, "  {} = {}", name, scalar_to_hex(&acc))
+                .expect("writing to string never fails");
+            values.push(acc);
         }
 
-        None
+        let satisfied = values[0] * values[1] == values[2];
+        writeln!(
+            s,
+            "A * B = {}, {}",
+            scalar_to_hex(&(values[0] * values[1])),
+            if satisfied {
+                "satisfied"
+            } else {
+                "unsatisfied"
+            }
+        )
+        .expect("writing to string never fails");
+
+        s
+    }
+
+    /// The metadata the constraint at `path` was enforced with, see
+    /// [`ConstraintSystem::enforce_with_metadata`].
+    pub fn metadata(&self, path: &str) -> Option<&ConstraintMetadata> {
+        match self.named_objects.get(path) {
+            Some(NamedObject::Constraint(index)) => self.metadata.get(index),
+            _ => None,
+        }
     }
 
     pub fn is_satisfied(&self) -> bool {
@@ -382,7 +677,8 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for TestConstraintSystem<Scala
     {
         let index = self.aux.len();
         let path = compute_path(&self.current_namespace, &annotation().into());
-        self.aux.push((f()?, path.clone()));
+        let value = f().map_err(|e| e.at_path(path.clone()))?;
+        self.aux.push((value, path.clone()));
         let var = Variable::new_unchecked(Index::Aux(index));
         self.set_named_obj(path, NamedObject::Var(var));
 
@@ -397,7 +693,8 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for TestConstraintSystem<Scala
     {
         let index = self.inputs.len();
         let path = compute_path(&self.current_namespace, &annotation().into());
-        self.inputs.push((f()?, path.clone()));
+        let value = f().map_err(|e| e.at_path(path.clone()))?;
+        self.inputs.push((value, path.clone()));
         let var = Variable::new_unchecked(Index::Input(index));
         self.set_named_obj(path, NamedObject::Var(var));
 
@@ -423,6 +720,24 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for TestConstraintSystem<Scala
         self.constraints.push((a, b, c, path));
     }
 
+    fn enforce_with_metadata<A, AR, LA, LB, LC>(
+        &mut self,
+        annotation: A,
+        metadata: ConstraintMetadata,
+        a: LA,
+        b: LB,
+        c: LC,
+    ) where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    {
+        self.metadata.insert(self.constraints.len(), metadata);
+        self.enforce(annotation, a, b, c);
+    }
+
     fn push_namespace<NR, N>(&mut self, name_fn: N)
     where
         NR: Into<String>,
@@ -443,6 +758,8 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for TestConstraintSystem<Scala
     }
 }
 
+impl<Scalar: PrimeField> LookupConstraintSystem<Scalar> for TestConstraintSystem<Scalar> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -450,6 +767,8 @@ mod tests {
     use blstrs::Scalar as Fr;
     use ff::Field;
 
+    use crate::Severity;
+
     #[test]
     fn test_compute_path() {
         assert_eq!(
@@ -505,4 +824,213 @@ mod tests {
 
         assert!(cs.get("test1/test2/hehe") == Fr::ONE);
     }
+
+    #[test]
+    fn test_all_unsatisfied() {
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let a = cs.alloc(|| "a", || Ok(Fr::from(4u64))).unwrap();
+        let b = cs.alloc(|| "b", || Ok(Fr::from(5u64))).unwrap();
+        let c = cs.alloc(|| "c", || Ok(Fr::from(20u64))).unwrap();
+        let one = TestConstraintSystem::<Fr>::one();
+
+        cs.enforce(|| "mult", |lc| lc + a, |lc| lc + b, |lc| lc + c);
+        cs.namespace(|| "ns")
+            .enforce(|| "eq", |lc| lc + a, |lc| lc + one, |lc| lc + b);
+        cs.enforce(|| "square", |lc| lc + a, |lc| lc + a, |lc| lc + c);
+        assert_eq!(cs.which_is_unsatisfied(), Some("ns/eq"));
+
+        let unsatisfied = cs.all_unsatisfied();
+        assert_eq!(
+            unsatisfied,
+            vec![
+                UnsatisfiedConstraint {
+                    path: "ns/eq",
+                    a: Fr::from(4u64),
+                    b: Fr::ONE,
+                    c: Fr::from(5u64),
+                },
+                UnsatisfiedConstraint {
+                    path: "square",
+                    a: Fr::from(4u64),
+                    b: Fr::from(4u64),
+                    c: Fr::from(20u64),
+                },
+            ]
+        );
+
+        cs.set("b", Fr::from(4u64));
+        cs.set("c", Fr::from(16u64));
+        assert!(cs.all_unsatisfied().is_empty());
+    }
+
+    #[test]
+    fn test_parallel_satisfiability() {
+        // Enough constraints to be split across several jobs, the second half is about `z`.
+        let n = 4 * MIN_CONSTRAINTS_PER_JOB;
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let x = cs.alloc(|| "x", || Ok(Fr::from(3u64))).unwrap();
+        let y = cs.alloc(|| "y", || Ok(Fr::from(9u64))).unwrap();
+        let z = cs.alloc(|| "z", || Ok(Fr::from(9u64))).unwrap();
+        for i in 0..n {
+            let out = if i < n / 2 { y } else { z };
+            cs.enforce(
+                || format!("square {}", i),
+                |lc| lc + x,
+                |lc| lc + x,
+                |lc| lc + out,
+            );
+        }
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.find_unsatisfied(), None);
+
+        cs.set("z", Fr::from(10u64));
+        assert!(!cs.is_satisfied());
+        let first = format!("square {}", n / 2);
+        assert_eq!(cs.which_is_unsatisfied(), Some(first.as_str()));
+        let any = cs.find_unsatisfied().unwrap();
+        assert!(any["square ".len()..].parse::<usize>().unwrap() >= n / 2);
+
+        let unsatisfied = cs.all_unsatisfied();
+        assert_eq!(unsatisfied.len(), n / 2);
+        assert_eq!(unsatisfied[0].path, first);
+        assert_eq!(unsatisfied[n / 2 - 1].path, format!("square {}", n - 1));
+    }
+
+    #[test]
+    fn test_debug_constraint() {
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let a = cs.alloc(|| "a", || Ok(Fr::from(2u64))).unwrap();
+        let b = cs.alloc(|| "b", || Ok(Fr::from(3u64))).unwrap();
+        let one = TestConstraintSystem::<Fr>::one();
+        cs.namespace(|| "ns").enforce(
+            || "sum",
+            |lc| lc + a + (Fr::from(2u64), b),
+            |lc| lc + one,
+            |lc| lc + (Fr::from(9u64), one),
+        );
+
+        let hex = |n: u64| format!("0x{:064x}", n);
+        let expected = [
+            "A:".to_string(),
+            format!("  {} * a ({}) => {}", hex(1), hex(2), hex(2)),
+            format!("  {} * b ({}) => {}", hex(2), hex(3), hex(8)),
+            format!("  A = {}", hex(8)),
+            "B:".to_string(),
+            format!("  {} * ONE ({}) => {}", hex(1), hex(1), hex(1)),
+            format!("  B = {}", hex(1)),
+            "C:".to_string(),
+            format!("  {} * ONE ({}) => {}", hex(9), hex(1), hex(9)),
+            format!("  C = {}", hex(9)),
+            format!("A * B = {}, unsatisfied", hex(8)),
+        ];
+        assert_eq!(
+            cs.debug_constraint("ns/sum").lines().collect::<Vec<_>>(),
+            expected
+        );
+
+        cs.set("a", Fr::from(3u64));
+        assert!(cs.debug_constraint("ns/sum").ends_with(", satisfied\n"));
+    }
+
+    #[test]
+    fn test_metadata() {
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let x = cs.alloc(|| "x", || Ok(Fr::ONE)).unwrap();
+        let metadata = ConstraintMetadata::new()
+            .with_tag("spec:4.2")
+            .with_tag("range check")
+            .with_severity(Severity::Critical);
+        cs.namespace(|| "bit").enforce_with_metadata(
+            || "boolean",
+            metadata.clone(),
+            |lc| lc + x,
+            |lc| lc + x,
+            |lc| lc + x,
+        );
+        cs.enforce(|| "plain", |lc| lc + x, |lc| lc + x, |lc| lc + x);
+
+        assert_eq!(cs.metadata("bit/boolean"), Some(&metadata));
+        assert_eq!(cs.metadata("plain"), None);
+        assert_eq!(cs.metadata("x"), None);
+
+        let location = metadata.location.unwrap().to_string();
+        assert!(location.starts_with(file!()));
+        let described = format!(
+            "severity: critical, tags: spec:4.2, range check, at {}",
+            location
+        );
+        assert_eq!(
+            cs.pretty_print_list()[2..],
+            [format!("bit/boolean [{}]", described), "plain".to_string()]
+        );
+        assert_eq!(
+            cs.debug_constraint("bit/boolean").lines().next(),
+            Some(described.as_str())
+        );
+        assert!(cs.debug_constraint("plain").starts_with("A:"));
+
+        #[cfg(feature = "json")]
+        {
+            let json = cs.to_json();
+            assert_eq!(
+                json["constraints"][0]["metadata"],
+                json!({
+                    "tags": ["spec:4.2", "range check"],
+                    "severity": "critical",
+                    "location": location,
+                })
+            );
+            assert!(json["constraints"][1].get("metadata").is_none());
+        }
+    }
+
+    #[test]
+    fn test_export() {
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let x = cs.alloc_input(|| "x", || Ok(Fr::from(3u64))).unwrap();
+        let y = {
+            let mut cs = cs.namespace(|| "square");
+            let y = cs.alloc(|| "y", || Ok(Fr::from(9u64))).unwrap();
+            cs.enforce(|| "x * x = y", |lc| lc + x, |lc| lc + x, |lc| lc + y);
+            y
+        };
+        cs.namespace(|| "empty");
+        cs.enforce(
+            || "y = 9",
+            |lc| lc + y,
+            |lc| lc + TestConstraintSystem::<Fr>::one(),
+            |lc| lc + (Fr::from(9u64), TestConstraintSystem::<Fr>::one()),
+        );
+
+        #[cfg(feature = "json")]
+        {
+            let json = cs.to_json();
+            assert_eq!(json["inputs"][1]["path"], "x");
+            assert_eq!(json["inputs"][1]["value"], format!("0x{}3", "0".repeat(63)));
+            assert_eq!(json["aux"][0]["path"], "square/y");
+            assert_eq!(json["constraints"][0]["path"], "square/x * x = y");
+            assert_eq!(json["constraints"][0]["a"][0]["variable"], "x");
+            assert_eq!(json["constraints"][1]["c"][0]["variable"], "ONE");
+            assert_eq!(
+                json["namespaces"],
+                json!({
+                    "variables": 3,
+                    "constraints": 2,
+                    "namespaces": {
+                        "empty": { "variables": 0, "constraints": 0, "namespaces": {} },
+                        "square": { "variables": 1, "constraints": 1, "namespaces": {} },
+                    },
+                })
+            );
+        }
+
+        let dot = cs.to_dot();
+        assert!(dot.starts_with("digraph constraints {\n"));
+        assert!(dot.contains("  a0 [label=\"square/y\"];\n"));
+        assert!(dot.contains("  c0 [shape=box, label=\"square/x * x = y\"];\n"));
+        assert!(dot.contains("  i1 -> c0 [label=\"ab\"];\n"));
+        assert!(dot.contains("  a0 -> c0 [label=\"c\"];\n"));
+        assert!(dot.contains("  i0 -> c1 [label=\"bc\"];\n"));
+        assert_eq!(dot_escape("a \"b\" \\"), "a \\\"b\\\" \\\\");
+    }
 }
@@ -2,7 +2,10 @@
 
 use ff::PrimeField;
 
-use crate::{ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+use crate::{
+    Circuit, ConstraintSystem, Index, LinearCombination, LookupConstraintSystem, SynthesisError,
+    Variable,
+};
 
 pub trait SizedWitness<Scalar: PrimeField> {
     fn num_constraints(&self) -> usize;
@@ -167,6 +170,8 @@ where
     }
 }
 
+impl<Scalar: PrimeField> LookupConstraintSystem<Scalar> for WitnessCS<Scalar> {}
+
 impl<Scalar: PrimeField> WitnessCS<Scalar> {
     pub fn scalar_inputs(&self) -> Vec<Scalar> {
         self.input_assignment.clone()
@@ -176,3 +181,65 @@ impl<Scalar: PrimeField> WitnessCS<Scalar> {
         self.aux_assignment.clone()
     }
 }
+
+/// Synthesizes a circuit with its witness and returns the values of its public
+/// inputs in allocation order, without the implicit "one" input.
+///
+/// These are exactly the inputs a proof of the circuit has to be verified with.
+pub fn compute_public_inputs<Scalar, C>(circuit: C) -> Result<Vec<Scalar>, SynthesisError>
+where
+    Scalar: PrimeField,
+    C: Circuit<Scalar>,
+{
+    let mut cs = WitnessCS::new();
+    circuit.synthesize(&mut cs)?;
+
+    let mut inputs = cs.input_assignment;
+    inputs.remove(0);
+    Ok(inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+
+    use crate::gadgets::compose::{Expose, Sequence, WiredCircuit};
+    use crate::gadgets::num::AllocatedNum;
+
+    struct Square(Option<Fr>);
+
+    impl WiredCircuit<Fr> for Square {
+        fn synthesize_wired<CS: ConstraintSystem<Fr>>(
+            self,
+            cs: &mut CS,
+            _inputs: &[AllocatedNum<Fr>],
+        ) -> Result<Vec<AllocatedNum<Fr>>, SynthesisError> {
+            let x = AllocatedNum::alloc(cs.namespace(|| "x"), || {
+                self.0.ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            let x2 = x.square(cs.namespace(|| "x^2"))?;
+            let x4 = x2.square(cs.namespace(|| "x^4"))?;
+            Ok(vec![x, x2, x4])
+        }
+    }
+
+    #[test]
+    fn test_compute_public_inputs() {
+        let inputs = compute_public_inputs(Expose::new(Square(Some(Fr::from(3))), vec![2, 1]));
+        assert_eq!(inputs.unwrap(), vec![Fr::from(81), Fr::from(9)]);
+
+        // Nested namespaces don't affect the order.
+        let inputs = compute_public_inputs(Sequence::new(
+            Expose::new(Square(Some(Fr::from(2))), vec![0]),
+            Expose::new(Square(Some(Fr::from(5))), vec![1]),
+        ));
+        assert_eq!(inputs.unwrap(), vec![Fr::from(2), Fr::from(25)]);
+
+        assert!(matches!(
+            compute_public_inputs(Expose::new(Square(None), vec![0])),
+            Err(SynthesisError::AssignmentMissing)
+        ));
+    }
+}
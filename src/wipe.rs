@@ -0,0 +1,74 @@
+//! Wiping of secret material.
+//!
+//! With the `zeroize` feature, the witness, the blinding scalars `r` and `s` of the prover and the
+//! intermediate buffers derived from them, like the evaluation domains, are overwritten with
+//! zeros once they are no longer needed, also if proving fails. Without the feature, wiping does
+//! nothing.
+//!
+//! Copies made by `ec-gpu-gen`, e.g. the exponents that are uploaded to the GPU, are out of reach
+//! and are not wiped.
+
+use std::sync::Arc;
+
+use ff::PrimeField;
+
+/// Overwrites the scalars with zeros.
+#[cfg(feature = "zeroize")]
+pub(crate) fn wipe_scalars<F: PrimeField>(scalars: &mut [F]) {
+    use std::sync::atomic::{self, Ordering};
+
+    for scalar in scalars.iter_mut() {
+        // A volatile write, so that it isn't optimized away as a dead store right before the
+        // memory is freed.
+        unsafe { std::ptr::write_volatile(scalar, F::ZERO) };
+    }
+    atomic::compiler_fence(Ordering::SeqCst);
+}
+
+#[cfg(not(feature = "zeroize"))]
+pub(crate) fn wipe_scalars<F: PrimeField>(_scalars: &mut [F]) {}
+
+/// Overwrites the canonical representations of scalars with zeros.
+#[cfg(feature = "zeroize")]
+pub(crate) fn wipe_reprs<R: AsMut<[u8]>>(reprs: &mut [R]) {
+    use zeroize::Zeroize;
+
+    for repr in reprs.iter_mut() {
+        repr.as_mut().zeroize();
+    }
+}
+
+#[cfg(not(feature = "zeroize"))]
+pub(crate) fn wipe_reprs<R: AsMut<[u8]>>(_reprs: &mut [R]) {}
+
+/// Overwrites shared representations with zeros if this is the last reference to them. Otherwise
+/// the last owner is still using them and they are left untouched.
+pub(crate) fn wipe_shared_reprs<R: AsMut<[u8]>>(reprs: &mut Arc<Vec<R>>) {
+    if let Some(reprs) = Arc::get_mut(reprs) {
+        wipe_reprs(reprs);
+    }
+}
+
+#[cfg(all(test, feature = "zeroize"))]
+mod tests {
+    use super::*;
+
+    use blstrs::Scalar as Fr;
+    use ff::Field;
+
+    #[test]
+    fn test_wipe() {
+        let mut scalars = vec![Fr::ONE; 3];
+        wipe_scalars(&mut scalars);
+        assert!(scalars.iter().all(|scalar| bool::from(scalar.is_zero())));
+
+        let mut reprs = Arc::new(vec![Fr::ONE.to_repr(); 3]);
+        let shared = reprs.clone();
+        wipe_shared_reprs(&mut reprs);
+        assert_eq!(reprs[0], Fr::ONE.to_repr());
+
+        drop(shared);
+        wipe_shared_reprs(&mut reprs);
+        assert!(reprs.iter().all(|repr| repr == &Fr::ZERO.to_repr()));
+    }
+}
@@ -12,7 +12,7 @@ use group::Group;
 use pairing::Engine;
 
 // We're going to use the BLS12-381 pairing-friendly elliptic curve.
-use blstrs::{Bls12, Scalar as Fr};
+use blstrs::{Bls12, G1Affine, Scalar as Fr};
 
 // We'll use these interfaces to construct our circuit.
 use bellperson::{Circuit, ConstraintSystem, SynthesisError};
@@ -303,3 +303,64 @@ fn test_mimc() {
         );
     }
 }
+
+#[test]
+fn test_mimc_verifier_core() {
+    let rng = &mut thread_rng();
+
+    let constants = (0..MIMC_ROUNDS)
+        .map(|_| Fr::random(&mut *rng))
+        .collect::<Vec<_>>();
+    let params = {
+        let c = MimcDemo::<Fr> {
+            xl: None,
+            xr: None,
+            constants: &constants,
+        };
+
+        generate_random_parameters::<Bls12, _, _>(c, &mut *rng).unwrap()
+    };
+
+    let xl = Fr::random(&mut *rng);
+    let xr = Fr::random(&mut *rng);
+    let image = mimc::<Fr>(xl, xr, &constants);
+    let c = MimcDemo {
+        xl: Some(xl),
+        xr: Some(xr),
+        constants: &constants,
+    };
+    let proof = create_random_proof(c, &params, &mut *rng).unwrap();
+
+    // The verifying key and the proof are read from their encoding ...
+    let mut vk_bytes = vec![];
+    params.vk.write(&mut vk_bytes).unwrap();
+    let vk = bellperson_verifier_core::VerifyingKey::<Bls12>::from_bytes(&vk_bytes).unwrap();
+    assert_eq!(vk, (&params.vk).into());
+    let mut proof_bytes = vec![];
+    proof.write(&mut proof_bytes).unwrap();
+    let core_proof = bellperson_verifier_core::Proof::<Bls12>::from_bytes(&proof_bytes).unwrap();
+    assert_eq!(core_proof, (&proof).into());
+    assert_eq!(
+        bellperson_verifier_core::Proof::<Bls12>::from_bytes(&proof_bytes[1..]),
+        Err(bellperson_verifier_core::VerificationError::InvalidData)
+    );
+    // Like `Proof::read`, an identity point is rejected.
+    let mut identity_a = proof.clone();
+    identity_a.a = G1Affine::identity();
+    let mut identity_bytes = vec![];
+    identity_a.write(&mut identity_bytes).unwrap();
+    assert!(Proof::<Bls12>::read(&identity_bytes[..]).is_err());
+    assert_eq!(
+        bellperson_verifier_core::Proof::<Bls12>::from_bytes(&identity_bytes),
+        Err(bellperson_verifier_core::VerificationError::InvalidData)
+    );
+
+    // ... and verify like they do with the standard library.
+    let pvk = bellperson_verifier_core::prepare_verifying_key(&vk);
+    assert!(bellperson_verifier_core::verify_proof(&pvk, &core_proof, &[image]).unwrap());
+    assert!(!bellperson_verifier_core::verify_proof(&pvk, &core_proof, &[xl]).unwrap());
+    assert_eq!(
+        bellperson_verifier_core::verify_proof(&pvk, &core_proof, &[]),
+        Err(bellperson_verifier_core::VerificationError::MalformedVerifyingKey)
+    );
+}
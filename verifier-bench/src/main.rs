@@ -135,6 +135,8 @@ where
         a: Arc::new(random_points::<E::G1, _>(count, &mut rng)),
         b_g1: Arc::new(random_points::<E::G1, _>(count, &mut rng)),
         b_g2: Arc::new(random_points::<E::G2, _>(count, &mut rng)),
+        densities: None,
+        fingerprint: None,
     }
 }
 
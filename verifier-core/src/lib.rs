@@ -0,0 +1,222 @@
+//! Verification of Groth16 proofs of [bellperson] without the standard library, e.g. within
+//! enclaves or on embedded targets. It only needs `alloc`, and works with any pairing engine, like
+//! the one of the `bls12_381` crate, which is `no_std` as well.
+//!
+//! The verifying keys and proofs are read from the encoding of bellperson, and bellperson
+//! converts its own types into the ones of this crate. Verification runs on the calling thread,
+//! batch verification, the GPU and the verification cache are only available in bellperson.
+//!
+//! [bellperson]: https://docs.rs/bellperson
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use group::{prime::PrimeCurveAffine, Curve, GroupEncoding, UncompressedEncoding};
+use pairing::{Engine, MillerLoopResult, MultiMillerLoop};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationError {
+    /// The bytes are not a valid verifying key or proof.
+    InvalidData,
+    /// The number of public inputs doesn't match the verifying key.
+    MalformedVerifyingKey,
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationError::InvalidData => write!(f, "invalid verifying key or proof"),
+            VerificationError::MalformedVerifyingKey => write!(f, "malformed verifying key"),
+        }
+    }
+}
+
+/// The parts of a verifying key that are needed for verification.
+#[derive(Clone, Debug)]
+pub struct VerifyingKey<E: Engine> {
+    pub alpha_g1: E::G1Affine,
+    pub beta_g2: E::G2Affine,
+    pub gamma_g2: E::G2Affine,
+    pub delta_g2: E::G2Affine,
+    pub ic: Vec<E::G1Affine>,
+}
+
+impl<E: Engine> PartialEq for VerifyingKey<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.alpha_g1 == other.alpha_g1
+            && self.beta_g2 == other.beta_g2
+            && self.gamma_g2 == other.gamma_g2
+            && self.delta_g2 == other.delta_g2
+            && self.ic == other.ic
+    }
+}
+
+impl<E: Engine> VerifyingKey<E> {
+    /// Reads a verifying key in the encoding of `VerifyingKey::write` of bellperson, i.e. of
+    /// uncompressed points. The points are checked, `beta_g1` and `delta_g1` are skipped.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, VerificationError> {
+        let mut reader = Reader(bytes);
+        let alpha_g1 = reader.read_uncompressed()?;
+        let _beta_g1: E::G1Affine = reader.read_uncompressed()?;
+        let beta_g2 = reader.read_uncompressed()?;
+        let gamma_g2 = reader.read_uncompressed()?;
+        let _delta_g1: E::G1Affine = reader.read_uncompressed()?;
+        let delta_g2 = reader.read_uncompressed()?;
+
+        let ic_len = u32::from_be_bytes(reader.take(4)?.try_into().expect("length is 4")) as usize;
+        let mut ic = Vec::new();
+        for _ in 0..ic_len {
+            let point: E::G1Affine = reader.read_uncompressed()?;
+            if point.is_identity().into() {
+                return Err(VerificationError::InvalidData);
+            }
+            ic.push(point);
+        }
+
+        Ok(VerifyingKey {
+            alpha_g1,
+            beta_g2,
+            gamma_g2,
+            delta_g2,
+            ic,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Proof<E: Engine> {
+    pub a: E::G1Affine,
+    pub b: E::G2Affine,
+    pub c: E::G1Affine,
+}
+
+impl<E: Engine> PartialEq for Proof<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.a == other.a && self.b == other.b && self.c == other.c
+    }
+}
+
+impl<E: Engine> Proof<E> {
+    /// Reads a proof in the encoding of `Proof::write` of bellperson, i.e. of compressed points.
+    /// The points are checked, like `Proof::read` of bellperson none of them may be the identity.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, VerificationError> {
+        let mut reader = Reader(bytes);
+        let proof = Proof {
+            a: reader.read_compressed()?,
+            b: reader.read_compressed()?,
+            c: reader.read_compressed()?,
+        };
+        if !reader.0.is_empty()
+            || bool::from(proof.a.is_identity() | proof.b.is_identity() | proof.c.is_identity())
+        {
+            return Err(VerificationError::InvalidData);
+        }
+
+        Ok(proof)
+    }
+}
+
+pub struct PreparedVerifyingKey<E: MultiMillerLoop> {
+    /// Pairing result of alpha*beta
+    alpha_g1_beta_g2: E::Gt,
+    /// -gamma in G2
+    neg_gamma_g2: E::G2Prepared,
+    /// -delta in G2
+    neg_delta_g2: E::G2Prepared,
+    ic: Vec<E::G1Affine>,
+}
+
+/// Generate a prepared verifying key, required to verify proofs.
+pub fn prepare_verifying_key<E: MultiMillerLoop>(vk: &VerifyingKey<E>) -> PreparedVerifyingKey<E> {
+    PreparedVerifyingKey {
+        alpha_g1_beta_g2: E::pairing(&vk.alpha_g1, &vk.beta_g2),
+        neg_gamma_g2: (-vk.gamma_g2).into(),
+        neg_delta_g2: (-vk.delta_g2).into(),
+        ic: vk.ic.clone(),
+    }
+}
+
+/// Verify a single Proof.
+pub fn verify_proof<E: MultiMillerLoop>(
+    pvk: &PreparedVerifyingKey<E>,
+    proof: &Proof<E>,
+    public_inputs: &[E::Fr],
+) -> Result<bool, VerificationError> {
+    if (public_inputs.len() + 1) != pvk.ic.len() {
+        return Err(VerificationError::MalformedVerifyingKey);
+    }
+
+    let mut acc = pvk.ic[0].to_curve();
+    for (input, ic) in public_inputs.iter().zip(&pvk.ic[1..]) {
+        acc += *ic * input;
+    }
+
+    Ok(check_verification_equation(
+        &pvk.alpha_g1_beta_g2,
+        &pvk.neg_gamma_g2,
+        &pvk.neg_delta_g2,
+        proof,
+        &acc.to_affine(),
+    ))
+}
+
+/// Checks the verification equation of a proof, given its input term
+/// `ic[0] + sum_i public_inputs[i] * ic[i + 1]` and the prepared parts of the verifying key.
+///
+/// This is the check of [`verify_proof`], bellperson uses it as well after computing the input
+/// term in its own way, e.g. in parallel or from prepared inputs.
+pub fn check_verification_equation<E: MultiMillerLoop>(
+    alpha_g1_beta_g2: &E::Gt,
+    neg_gamma_g2: &E::G2Prepared,
+    neg_delta_g2: &E::G2Prepared,
+    proof: &Proof<E>,
+    inputs_term: &E::G1Affine,
+) -> bool {
+    // The original verification equation is:
+    // A * B = alpha * beta + inputs * gamma + C * delta
+    // ... however, we rearrange it so that it is:
+    // A * B - inputs * gamma - C * delta = alpha * beta
+    // or equivalently:
+    // A * B + inputs * (-gamma) + C * (-delta) = alpha * beta
+    // which allows us to do a single final exponentiation.
+    let b: E::G2Prepared = proof.b.into();
+    let actual = E::multi_miller_loop(&[
+        (&proof.a, &b),
+        (inputs_term, neg_gamma_g2),
+        (&proof.c, neg_delta_g2),
+    ])
+    .final_exponentiation();
+
+    actual == *alpha_g1_beta_g2
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], VerificationError> {
+        if self.0.len() < len {
+            return Err(VerificationError::InvalidData);
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn read_uncompressed<C: UncompressedEncoding>(&mut self) -> Result<C, VerificationError> {
+        let mut repr = C::Uncompressed::default();
+        let len = repr.as_ref().len();
+        repr.as_mut().copy_from_slice(self.take(len)?);
+        Option::from(C::from_uncompressed(&repr)).ok_or(VerificationError::InvalidData)
+    }
+
+    fn read_compressed<C: GroupEncoding>(&mut self) -> Result<C, VerificationError> {
+        let mut repr = C::Repr::default();
+        let len = repr.as_ref().len();
+        repr.as_mut().copy_from_slice(self.take(len)?);
+        Option::from(C::from_bytes(&repr)).ok_or(VerificationError::InvalidData)
+    }
+}